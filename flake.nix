@@ -65,14 +65,15 @@
             # The final build also needs the runtime GIF assets, which the
             # cargo-only source filter above strips out — use a fuller source here.
             src = pkgs.lib.cleanSource self;
-            # The bot loads GIFs from ./assets/{bonk,hit} relative to its CWD, so we
-            # ship the assets alongside the binary and run from $out/share/rustbot.
+            # The bot loads GIFs from $RUSTBOT_ASSETS_DIR/{bonk,hit} (default ./assets),
+            # so we ship the assets alongside the binary and point the wrapper at them.
             # The -play command shells out to yt-dlp and ffmpeg at runtime, so put
             # both on the binary's PATH regardless of how the service invokes it.
             postInstall = ''
               mkdir -p $out/share/rustbot
               cp -r assets $out/share/rustbot/assets
               wrapProgram $out/bin/rustbot \
+                --set-default RUSTBOT_ASSETS_DIR $out/share/rustbot/assets \
                 --prefix PATH : ${pkgs.lib.makeBinPath [ pkgsUnstable.yt-dlp pkgs.ffmpeg ]}
             '';
             meta.mainProgram = "rustbot";
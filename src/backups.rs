@@ -0,0 +1,444 @@
+//! Nightly backups of the persisted data, and restoring from them.
+//!
+//! At 03:00 Copenhagen time every persisted file is bundled into a `.tar.gz`
+//! (see [`storage::bundle_files`]). The bundle goes to the backup channel
+//! (`BACKUP_CHANNEL_ID`) as an attachment when it fits, otherwise to an
+//! S3-compatible bucket (see [`crate::s3`]). Only the newest
+//! [`KEEP_BACKUPS`] are kept in each place.
+
+use crate::s3::{self, S3Config};
+use crate::storage;
+use crate::utils::send_dm_to_deekahy;
+use crate::Error;
+use chrono::{DateTime, NaiveDate, Timelike, Utc};
+use chrono_tz::Europe::Copenhagen;
+use chrono_tz::Tz;
+use poise::serenity_prelude as serenity;
+use serde::{Deserialize, Serialize};
+use std::env;
+use std::fmt;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use tokio::time::{interval, Duration as TokioDuration};
+
+/// How many backups are kept per destination (and pre-restore snapshots).
+pub const KEEP_BACKUPS: usize = 14;
+
+/// Bundles bigger than this go to S3 instead of the backup channel.
+const DISCORD_UPLOAD_LIMIT: usize = 25 * 1024 * 1024;
+
+/// Local hour the nightly backup runs at.
+const BACKUP_HOUR: u32 = 3;
+
+const BACKUP_PREFIX: &str = "rustbot-backup-";
+const BACKUP_STATE_FILE: &str = "backup_state.json";
+
+/// Subdirectory of the data directory for pre-restore snapshots. Bundles
+/// only pick up top-level files, so snapshots never end up in backups.
+const SNAPSHOT_DIR: &str = "snapshots";
+
+#[derive(Serialize, Deserialize, Default)]
+struct BackupState {
+    /// Local date of the last nightly backup, so a restart doesn't repeat it.
+    last_backup: Option<NaiveDate>,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum BackupLocation {
+    Channel,
+    S3,
+}
+
+impl fmt::Display for BackupLocation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            BackupLocation::Channel => write!(f, "backup channel"),
+            BackupLocation::S3 => write!(f, "S3 bucket"),
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct Backup {
+    /// e.g. `rustbot-backup-20261016-010000`; sorts by age.
+    pub id: String,
+    pub location: BackupLocation,
+    /// Size in bytes, when the destination reports it.
+    pub size: Option<usize>,
+}
+
+fn backup_channel() -> Option<serenity::ChannelId> {
+    env::var("BACKUP_CHANNEL_ID")
+        .ok()
+        .and_then(|id| id.trim().parse::<u64>().ok())
+        .filter(|id| *id != 0)
+        .map(serenity::ChannelId::new)
+}
+
+/// Whether any backup destination is configured.
+pub fn backups_configured() -> bool {
+    backup_channel().is_some() || S3Config::from_env().is_some()
+}
+
+fn backup_id(now: DateTime<Utc>) -> String {
+    format!("{}{}", BACKUP_PREFIX, now.format("%Y%m%d-%H%M%S"))
+}
+
+fn backup_file_name(id: &str) -> String {
+    format!("{}.tar.gz", id)
+}
+
+fn id_from_file_name(file_name: &str) -> Option<&str> {
+    file_name
+        .strip_suffix(".tar.gz")
+        .filter(|id| id.starts_with(BACKUP_PREFIX))
+}
+
+/// Where a bundle of `size` bytes should go: the channel when it fits,
+/// otherwise S3.
+fn choose_location(size: usize, has_channel: bool, has_s3: bool) -> Option<BackupLocation> {
+    if has_channel && size < DISCORD_UPLOAD_LIMIT {
+        Some(BackupLocation::Channel)
+    } else if has_s3 {
+        Some(BackupLocation::S3)
+    } else {
+        None
+    }
+}
+
+/// The backups to delete so only the newest [`KEEP_BACKUPS`] remain.
+fn expired_backups(mut ids: Vec<String>) -> Vec<String> {
+    ids.sort_unstable_by(|a, b| b.cmp(a));
+    ids.split_off(ids.len().min(KEEP_BACKUPS))
+}
+
+/// Whether the nightly backup should run now. Runs late rather than not
+/// at all if the bot was down at 03:00.
+fn backup_due(now: DateTime<Tz>, last_backup: Option<NaiveDate>) -> bool {
+    now.hour() >= BACKUP_HOUR && last_backup != Some(now.date_naive())
+}
+
+/// Bundle everything and upload it. Old backups at the same destination
+/// are pruned afterwards.
+pub async fn run_backup(http: &serenity::Http) -> Result<Backup, Error> {
+    // Get buffered data onto disk first so the backup is current
+    crate::commands::usage::flush_command_usage();
+    crate::onboarding::flush_seen_users();
+    crate::commands::streak::flush_streaks();
+
+    let bundle = storage::bundle_files()?;
+    let size = bundle.len();
+    let channel = backup_channel();
+    let s3_config = S3Config::from_env();
+    let location = choose_location(size, channel.is_some(), s3_config.is_some()).ok_or(
+        "no backup destination fits: set BACKUP_CHANNEL_ID (up to 25MB) or the BACKUP_S3_* variables",
+    )?;
+
+    let id = backup_id(Utc::now());
+    let file_name = backup_file_name(&id);
+    match (location, channel, &s3_config) {
+        (BackupLocation::Channel, Some(channel), _) => {
+            channel
+                .send_message(
+                    http,
+                    serenity::CreateMessage::new()
+                        .content(format!("🗄️ Backup `{}` ({} KB)", id, size / 1024))
+                        .add_file(serenity::CreateAttachment::bytes(bundle, file_name)),
+                )
+                .await?;
+            if let Err(e) = prune_channel(http, channel).await {
+                log::warn!("Failed to prune old backups in the backup channel: {}", e);
+            }
+        }
+        (BackupLocation::S3, _, Some(config)) => {
+            s3::put_object(config, &file_name, bundle).await?;
+            if let Err(e) = prune_s3(config).await {
+                log::warn!("Failed to prune old backups in S3: {}", e);
+            }
+        }
+        _ => unreachable!("choose_location only picks configured destinations"),
+    }
+
+    storage::save(
+        BACKUP_STATE_FILE,
+        &BackupState {
+            last_backup: Some(Utc::now().with_timezone(&Copenhagen).date_naive()),
+        },
+    )?;
+    log::info!("Backup {} ({} bytes) saved to the {}", id, size, location);
+    Ok(Backup {
+        id,
+        location,
+        size: Some(size),
+    })
+}
+
+struct ChannelBackup {
+    id: String,
+    message_id: serenity::MessageId,
+    attachment: serenity::Attachment,
+}
+
+/// Backups the bot has posted in the channel's last 100 messages.
+async fn channel_backups(
+    http: &serenity::Http,
+    channel: serenity::ChannelId,
+) -> Result<Vec<ChannelBackup>, Error> {
+    let bot_id = http.get_current_user().await?.id;
+    let messages = channel
+        .messages(http, serenity::GetMessages::new().limit(100))
+        .await?;
+    Ok(messages
+        .into_iter()
+        .filter(|message| message.author.id == bot_id)
+        .flat_map(|message| {
+            let message_id = message.id;
+            message
+                .attachments
+                .into_iter()
+                .filter_map(move |attachment| {
+                    let id = id_from_file_name(&attachment.filename)?.to_string();
+                    Some(ChannelBackup {
+                        id,
+                        message_id,
+                        attachment,
+                    })
+                })
+        })
+        .collect())
+}
+
+async fn prune_channel(http: &serenity::Http, channel: serenity::ChannelId) -> Result<(), Error> {
+    let backups = channel_backups(http, channel).await?;
+    let expired = expired_backups(backups.iter().map(|b| b.id.clone()).collect());
+    for backup in backups.iter().filter(|b| expired.contains(&b.id)) {
+        channel.delete_message(http, backup.message_id).await?;
+    }
+    Ok(())
+}
+
+async fn s3_backup_ids(config: &S3Config) -> Result<Vec<String>, Error> {
+    Ok(s3::list_objects(config, BACKUP_PREFIX)
+        .await?
+        .iter()
+        .filter_map(|key| id_from_file_name(key).map(str::to_string))
+        .collect())
+}
+
+async fn prune_s3(config: &S3Config) -> Result<(), Error> {
+    for id in expired_backups(s3_backup_ids(config).await?) {
+        s3::delete_object(config, &backup_file_name(&id)).await?;
+    }
+    Ok(())
+}
+
+/// Every backup in every configured destination, newest first.
+pub async fn list_backups(http: &serenity::Http) -> Result<Vec<Backup>, Error> {
+    let mut backups = Vec::new();
+    if let Some(channel) = backup_channel() {
+        backups.extend(
+            channel_backups(http, channel)
+                .await?
+                .into_iter()
+                .map(|b| Backup {
+                    id: b.id,
+                    location: BackupLocation::Channel,
+                    size: Some(b.attachment.size as usize),
+                }),
+        );
+    }
+    if let Some(config) = S3Config::from_env() {
+        backups.extend(s3_backup_ids(&config).await?.into_iter().map(|id| Backup {
+            id,
+            location: BackupLocation::S3,
+            size: None,
+        }));
+    }
+    backups.sort_by(|a, b| b.id.cmp(&a.id));
+    Ok(backups)
+}
+
+/// Download a backup by ID from wherever it is.
+pub async fn download_backup(http: &serenity::Http, id: &str) -> Result<Vec<u8>, Error> {
+    if let Some(channel) = backup_channel() {
+        if let Some(backup) = channel_backups(http, channel)
+            .await?
+            .into_iter()
+            .find(|b| b.id == id)
+        {
+            return Ok(backup.attachment.download().await?);
+        }
+    }
+    if let Some(config) = S3Config::from_env() {
+        if s3_backup_ids(&config).await?.iter().any(|b| b == id) {
+            return s3::get_object(&config, &backup_file_name(id)).await;
+        }
+    }
+    Err(format!("no backup called `{}`", id).into())
+}
+
+/// Save the current data locally before a restore overwrites it, keeping
+/// the newest [`KEEP_BACKUPS`] snapshots. Returns the snapshot's path.
+pub fn snapshot_before_restore() -> Result<PathBuf, Error> {
+    let dir = storage::data_path(SNAPSHOT_DIR);
+    fs::create_dir_all(&dir)?;
+    let path = dir.join(format!(
+        "pre-restore-{}.tar.gz",
+        Utc::now().format("%Y%m%d-%H%M%S")
+    ));
+    fs::write(&path, storage::bundle_files()?)?;
+
+    let snapshots: Vec<String> = fs::read_dir(&dir)?
+        .filter_map(|entry| entry.ok())
+        .map(|entry| entry.file_name().to_string_lossy().into_owned())
+        .filter(|name| name.starts_with("pre-restore-"))
+        .collect();
+    for name in expired_backups(snapshots) {
+        if let Err(e) = fs::remove_file(dir.join(&name)) {
+            log::warn!("Failed to remove old snapshot {}: {}", name, e);
+        }
+    }
+    Ok(path)
+}
+
+/// Write a backup's files back over the current data. If that fails
+/// partway, the data is put back as it was in `snapshot` (from
+/// [`snapshot_before_restore`]), so it isn't left half restored.
+pub fn restore_backup(bundle: &[u8], snapshot: &Path) -> Result<Vec<String>, Error> {
+    let error = match storage::restore_bundle(bundle) {
+        Ok(restored) => return Ok(restored),
+        Err(e) => e,
+    };
+    log::error!(
+        "Restore failed partway, putting back {}: {}",
+        snapshot.display(),
+        error
+    );
+    match roll_back(bundle, snapshot) {
+        Ok(()) => Err(format!("{}; the data from before was put back", error).into()),
+        Err(e) => Err(format!(
+            "{}; putting the data from before back failed too ({}), it's in {}",
+            error,
+            e,
+            snapshot.display()
+        )
+        .into()),
+    }
+}
+
+/// Put the data back as it was in `snapshot`, removing files the restore
+/// added that weren't there before.
+fn roll_back(bundle: &[u8], snapshot: &Path) -> Result<(), Error> {
+    let snapshot = fs::read(snapshot)?;
+    let before = storage::bundle_contents(&snapshot)?;
+    for name in storage::bundle_contents(bundle)? {
+        let path = storage::data_path(&name);
+        if !before.contains(&name) && path.exists() {
+            fs::remove_file(path)?;
+        }
+    }
+    storage::restore_bundle(&snapshot)?;
+    Ok(())
+}
+
+/// Start the nightly backup task, if a destination is configured. Returns
+/// whether it started.
+pub fn start_backup_scheduler(http: Arc<serenity::Http>) -> bool {
+    if !backups_configured() {
+        log::info!("No backup destination configured, nightly backups are off");
+        return false;
+    }
+
+    tokio::spawn(async move {
+        let mut interval = interval(TokioDuration::from_secs(60)); // Check every minute
+        let mut last_attempt: Option<NaiveDate> = None;
+
+        loop {
+            interval.tick().await;
+
+            let now = Utc::now().with_timezone(&Copenhagen);
+            let state: BackupState = storage::load(BACKUP_STATE_FILE);
+            // A failed run isn't retried until the next night
+            if !backup_due(now, state.last_backup) || last_attempt == Some(now.date_naive()) {
+                continue;
+            }
+            last_attempt = Some(now.date_naive());
+
+            if let Err(e) = run_backup(&http).await {
+                log::error!("Nightly backup failed: {}", e);
+                let message = format!("⚠️ Nightly backup failed: {}", e);
+                if let Err(e) = send_dm_to_deekahy(&http, &message).await {
+                    log::warn!("Failed to report the failed backup: {}", e);
+                }
+            }
+        }
+    });
+    true
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    #[test]
+    fn test_backup_names() {
+        let now = Utc.with_ymd_and_hms(2026, 10, 16, 1, 2, 3).unwrap();
+        let id = backup_id(now);
+        assert_eq!(id, "rustbot-backup-20261016-010203");
+        assert_eq!(id_from_file_name(&backup_file_name(&id)), Some(id.as_str()));
+        assert_eq!(id_from_file_name("holiday.tar.gz"), None);
+        assert_eq!(
+            id_from_file_name("rustbot-backup-20261016-010203.zip"),
+            None
+        );
+    }
+
+    #[test]
+    fn test_choose_location() {
+        let small = 1024;
+        let big = DISCORD_UPLOAD_LIMIT + 1;
+        assert_eq!(
+            choose_location(small, true, true),
+            Some(BackupLocation::Channel)
+        );
+        assert_eq!(choose_location(big, true, true), Some(BackupLocation::S3));
+        assert_eq!(
+            choose_location(small, false, true),
+            Some(BackupLocation::S3)
+        );
+        assert_eq!(choose_location(big, true, false), None);
+        assert_eq!(choose_location(small, false, false), None);
+    }
+
+    #[test]
+    fn test_expired_backups_keeps_newest() {
+        let ids: Vec<String> = (1..=20)
+            .map(|day| format!("rustbot-backup-202610{:02}-030000", day))
+            .collect();
+        let expired = expired_backups(ids);
+        assert_eq!(expired.len(), 6);
+        assert!(expired.contains(&"rustbot-backup-20261001-030000".to_string()));
+        assert!(expired.contains(&"rustbot-backup-20261006-030000".to_string()));
+        assert!(!expired.contains(&"rustbot-backup-20261007-030000".to_string()));
+
+        assert!(expired_backups(vec!["rustbot-backup-1".to_string()]).is_empty());
+    }
+
+    #[test]
+    fn test_backup_due() {
+        let at = |hour| {
+            Copenhagen
+                .with_ymd_and_hms(2026, 10, 16, hour, 0, 0)
+                .unwrap()
+        };
+        let today = at(12).date_naive();
+        let yesterday = today.pred_opt().unwrap();
+
+        assert!(!backup_due(at(2), Some(yesterday)));
+        assert!(backup_due(at(3), Some(yesterday)));
+        assert!(backup_due(at(15), None));
+        assert!(!backup_due(at(4), Some(today)));
+    }
+}
@@ -0,0 +1,164 @@
+use crate::{Context, Error};
+use image::{imageops::FilterType, DynamicImage};
+use poise::serenity_prelude as serenity;
+
+/// Characters from darkest to brightest. Discord's dark theme draws text light
+/// on dark, so bright pixels get the densest characters.
+const ASCII_RAMP: &[char] = &[' ', '.', ':', '-', '=', '+', '*', '#', '%', '@'];
+
+const DEFAULT_WIDTH: u32 = 60;
+const MIN_WIDTH: u32 = 10;
+const MAX_WIDTH: u32 = 120;
+
+/// Room for the art inside a 2000 character message, minus the code block fences.
+const MAX_OUTPUT_CHARS: u32 = 2000 - 8;
+
+/// Largest attachment we'll download and decode.
+const MAX_IMAGE_BYTES: u32 = 8 * 1024 * 1024;
+
+fn brightness_to_char(luma: u8) -> char {
+    let index = luma as usize * (ASCII_RAMP.len() - 1) / 255;
+    ASCII_RAMP[index]
+}
+
+/// Output grid for an image at the requested width. Characters are about twice
+/// as tall as they are wide, so rows are halved; the width shrinks until the
+/// whole grid (plus newlines) fits in one message.
+fn fit_grid(image_width: u32, image_height: u32, requested_width: u32) -> (u32, u32) {
+    let aspect = image_height as f64 / image_width.max(1) as f64;
+    let mut width = requested_width.clamp(MIN_WIDTH, MAX_WIDTH);
+
+    loop {
+        let height = ((width as f64 * aspect / 2.0).round() as u32).max(1);
+        if (width + 1) * height <= MAX_OUTPUT_CHARS || width <= 1 {
+            return (width, height);
+        }
+        width -= 1;
+    }
+}
+
+fn image_to_ascii(img: &DynamicImage, width: u32, height: u32) -> String {
+    let small = img
+        .resize_exact(width, height, FilterType::Triangle)
+        .to_luma_alpha8();
+
+    let mut art = String::with_capacity(((width + 1) * height) as usize);
+    for row in small.rows() {
+        for pixel in row {
+            // Transparent pixels blend into the (dark) chat background
+            let [luma, alpha] = pixel.0;
+            let blended = (luma as u16 * alpha as u16 / 255) as u8;
+            art.push(brightness_to_char(blended));
+        }
+        // Trailing spaces only cost characters
+        let trimmed_len = art.trim_end_matches(' ').len();
+        art.truncate(trimmed_len);
+        art.push('\n');
+    }
+    art
+}
+
+/// Turn an image into ASCII art
+#[poise::command(prefix_command, slash_command)]
+pub async fn ascii(
+    ctx: Context<'_>,
+    #[description = "Image to convert (or reply to a message with an image)"] image: Option<
+        serenity::Attachment,
+    >,
+    #[description = "Output width in characters (10-120, default 60)"] width: Option<u32>,
+) -> Result<(), Error> {
+    log::info!("Ascii command called by {}", ctx.author().name);
+
+    // Fall back to an image on the replied-to message for prefix commands
+    let attachment = image.or_else(|| match ctx {
+        poise::Context::Prefix(prefix_ctx) => prefix_ctx
+            .msg
+            .referenced_message
+            .as_ref()
+            .and_then(|msg| msg.attachments.first().cloned()),
+        _ => None,
+    });
+
+    let Some(attachment) = attachment else {
+        ctx.say("❌ Attach an image or reply to a message with one! Use `-ascii [width]`.")
+            .await?;
+        return Ok(());
+    };
+
+    let is_image = attachment
+        .content_type
+        .as_deref()
+        .is_some_and(|t| t.starts_with("image/"));
+    if !is_image {
+        ctx.say("❌ That attachment isn't an image!").await?;
+        return Ok(());
+    }
+    if attachment.size > MAX_IMAGE_BYTES {
+        ctx.say("❌ That image is too large! The limit is 8 MB.")
+            .await?;
+        return Ok(());
+    }
+
+    ctx.defer().await?;
+
+    let bytes = match attachment.download().await {
+        Ok(bytes) => bytes,
+        Err(e) => {
+            ctx.say(format!("❌ Failed to download image: {}", e))
+                .await?;
+            return Ok(());
+        }
+    };
+
+    let img = match image::load_from_memory(&bytes) {
+        Ok(img) => img,
+        Err(e) => {
+            ctx.say(format!("❌ Failed to process image: {}", e))
+                .await?;
+            return Ok(());
+        }
+    };
+
+    let (grid_width, grid_height) =
+        fit_grid(img.width(), img.height(), width.unwrap_or(DEFAULT_WIDTH));
+    let art = image_to_ascii(&img, grid_width, grid_height);
+
+    ctx.say(format!("```\n{}```", art)).await?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_brightness_to_char() {
+        assert_eq!(brightness_to_char(0), ' ');
+        assert_eq!(brightness_to_char(255), '@');
+        assert_eq!(brightness_to_char(128), '=');
+        assert!(brightness_to_char(200) != ' ');
+    }
+
+    #[test]
+    fn test_fit_grid_respects_message_limit() {
+        let (width, height) = fit_grid(100, 100, 60);
+        assert_eq!((width, height), (60, 30));
+
+        // A tall image has to shrink to fit
+        let (width, height) = fit_grid(100, 400, 120);
+        assert!((width + 1) * height <= MAX_OUTPUT_CHARS);
+        assert!(width < 120);
+
+        // Requested widths are clamped
+        assert_eq!(fit_grid(100, 10, 500).0, MAX_WIDTH);
+        assert_eq!(fit_grid(100, 10, 1).0, MIN_WIDTH);
+    }
+
+    #[test]
+    fn test_image_to_ascii() {
+        let img = DynamicImage::ImageLuma8(image::GrayImage::from_fn(4, 2, |x, _| {
+            image::Luma([if x < 2 { 0 } else { 255 }])
+        }));
+        assert_eq!(image_to_ascii(&img, 4, 2), "  @@\n  @@\n");
+    }
+}
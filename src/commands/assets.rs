@@ -0,0 +1,153 @@
+use crate::utils::is_protected_user;
+use crate::utils::limits::{fit_list, EMBED_FIELD_VALUE};
+use crate::utils::meme::{assets_dir, AssetCategory};
+use crate::{Context, Error};
+use poise::serenity_prelude as serenity;
+
+/// List the meme GIFs the bot uses and the ones it skipped (bot owners only)
+#[poise::command(
+    prefix_command,
+    slash_command,
+    subcommands("assets_list", "assets_reload")
+)]
+pub async fn assets(ctx: Context<'_>) -> Result<(), Error> {
+    assets_list_inner(ctx).await
+}
+
+/// List the usable and skipped meme GIFs in each directory
+#[poise::command(prefix_command, slash_command, rename = "list")]
+pub async fn assets_list(ctx: Context<'_>) -> Result<(), Error> {
+    assets_list_inner(ctx).await
+}
+
+async fn assets_list_inner(ctx: Context<'_>) -> Result<(), Error> {
+    log::info!("Assets command called by {}", ctx.author().name);
+
+    if !check_assets_access(ctx).await? {
+        return Ok(());
+    }
+    ctx.send(
+        poise::CreateReply::default()
+            .embed(scan_embed("🖼️ Meme GIFs", &ctx.data().assets.categories())),
+    )
+    .await?;
+    Ok(())
+}
+
+/// Scan the asset directories again after adding or renaming GIFs
+#[poise::command(prefix_command, slash_command, rename = "reload")]
+pub async fn assets_reload(ctx: Context<'_>) -> Result<(), Error> {
+    log::info!("Assets reload command called by {}", ctx.author().name);
+
+    if !check_assets_access(ctx).await? {
+        return Ok(());
+    }
+    let scan = ctx.data().assets.rescan().await?;
+    ctx.send(poise::CreateReply::default().embed(scan_embed("🔄 Rescanned meme GIFs", &scan)))
+        .await?;
+    Ok(())
+}
+
+async fn check_assets_access(ctx: Context<'_>) -> Result<bool, Error> {
+    if is_protected_user(&ctx.author().name) {
+        return Ok(true);
+    }
+    ctx.say("❌ You don't have permission to use this command!")
+        .await?;
+    Ok(false)
+}
+
+/// One line per GIF in a directory: where the avatar goes on the usable
+/// ones, why the skipped ones were skipped.
+fn category_lines(category: &AssetCategory) -> Vec<String> {
+    if let Some(e) = &category.error {
+        return vec![format!("❌ Directory can't be read: {}", e)];
+    }
+    let mut lines: Vec<String> = category
+        .valid
+        .iter()
+        .map(|asset| {
+            format!(
+                "✅ `{}`: x {}, y {}, scale {}",
+                asset.file_name,
+                asset.overlay.x_percent,
+                asset.overlay.y_percent,
+                asset.overlay.scale_percent
+            )
+        })
+        .collect();
+    lines.extend(
+        category
+            .rejected
+            .iter()
+            .map(|rejected| format!("❌ `{}`: {}", rejected.file_name, rejected.reason)),
+    );
+    if lines.is_empty() {
+        lines.push("No GIFs".to_string());
+    }
+    lines
+}
+
+fn scan_embed(title: &str, scan: &[AssetCategory]) -> serenity::CreateEmbed {
+    let skipped: usize = scan.iter().map(|category| category.rejected.len()).sum();
+    let mut embed = serenity::CreateEmbed::new()
+        .title(title)
+        .description(format!("From `{}`", assets_dir().display()))
+        .color(if skipped == 0 { 0x00ff00 } else { 0xffa500 });
+    for category in scan {
+        embed = embed.field(
+            format!(
+                "{} ({} usable, {} skipped)",
+                category.name,
+                category.valid.len(),
+                category.rejected.len()
+            ),
+            fit_list(&category_lines(category), EMBED_FIELD_VALUE),
+            false,
+        );
+    }
+    embed
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::meme::{GifAsset, Overlay, RejectedAsset};
+
+    #[test]
+    fn test_category_lines() {
+        let mut category = AssetCategory {
+            name: "bonk",
+            valid: vec![GifAsset {
+                file_name: "bonk_1_x0.7_y0.6_s0.3.gif".to_string(),
+                path: "assets/bonk/bonk_1_x0.7_y0.6_s0.3.gif".into(),
+                overlay: Overlay {
+                    x_percent: 0.7,
+                    y_percent: 0.6,
+                    scale_percent: 0.3,
+                },
+            }],
+            rejected: vec![RejectedAsset {
+                file_name: "bonk_typo.gif".to_string(),
+                reason: "it has no frames".to_string(),
+            }],
+            error: None,
+        };
+        assert_eq!(
+            category_lines(&category),
+            [
+                "✅ `bonk_1_x0.7_y0.6_s0.3.gif`: x 0.7, y 0.6, scale 0.3",
+                "❌ `bonk_typo.gif`: it has no frames",
+            ]
+        );
+
+        category.valid.clear();
+        category.rejected.clear();
+        assert_eq!(category_lines(&category), ["No GIFs"]);
+        category.error = Some("No such file or directory".to_string());
+        assert_eq!(
+            category_lines(&category),
+            ["❌ Directory can't be read: No such file or directory"]
+        );
+    }
+}
@@ -0,0 +1,451 @@
+use crate::utils::limits::{fit_list, EMBED_FIELD_VALUE};
+use crate::utils::messages::collect_messages;
+use crate::utils::output::{output_target, ProgressReporter};
+use crate::utils::permissions::{check_channel_perms, history_error_message, READ_HISTORY};
+use crate::{Context, Error};
+use poise::serenity_prelude as serenity;
+use std::cmp::Reverse;
+use std::collections::HashMap;
+
+const MAX_MESSAGES: u64 = 10000; // same cap as -stats
+const TOP_UPLOADERS: usize = 5;
+const LARGEST_FILES: usize = 5;
+
+const IMAGE_EXTENSIONS: &[&str] = &[
+    "png", "jpg", "jpeg", "gif", "webp", "bmp", "svg", "avif", "heic", "tif", "tiff",
+];
+const VIDEO_EXTENSIONS: &[&str] = &["mp4", "mov", "webm", "mkv", "avi", "m4v"];
+const AUDIO_EXTENSIONS: &[&str] = &["mp3", "wav", "ogg", "flac", "m4a", "aac", "opus"];
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+enum AttachmentKind {
+    Image,
+    Video,
+    Audio,
+    Other,
+}
+
+impl AttachmentKind {
+    const ALL: [AttachmentKind; 4] = [Self::Image, Self::Video, Self::Audio, Self::Other];
+
+    fn label(self) -> &'static str {
+        match self {
+            Self::Image => "🖼️ Images",
+            Self::Video => "🎬 Videos",
+            Self::Audio => "🎵 Audio",
+            Self::Other => "📄 Other",
+        }
+    }
+}
+
+/// What kind of file an attachment is. Discord's content type wins when it
+/// says image, video or audio; otherwise (missing, or something generic like
+/// `application/octet-stream`) the file extension decides.
+fn classify_attachment(filename: &str, content_type: Option<&str>) -> AttachmentKind {
+    let mime = content_type
+        .and_then(|ct| ct.split(';').next())
+        .map(|ct| ct.trim().to_ascii_lowercase())
+        .unwrap_or_default();
+    match mime.split('/').next() {
+        Some("image") => return AttachmentKind::Image,
+        Some("video") => return AttachmentKind::Video,
+        Some("audio") => return AttachmentKind::Audio,
+        _ => {}
+    }
+
+    let Some((_, extension)) = filename.rsplit_once('.') else {
+        return AttachmentKind::Other;
+    };
+    let extension = extension.to_ascii_lowercase();
+    if IMAGE_EXTENSIONS.contains(&extension.as_str()) {
+        AttachmentKind::Image
+    } else if VIDEO_EXTENSIONS.contains(&extension.as_str()) {
+        AttachmentKind::Video
+    } else if AUDIO_EXTENSIONS.contains(&extension.as_str()) {
+        AttachmentKind::Audio
+    } else {
+        AttachmentKind::Other
+    }
+}
+
+/// A file size the way Discord shows them: `512 B`, `1.5 KB`, `8.0 MB`.
+fn format_size(bytes: u64) -> String {
+    const UNITS: [&str; 4] = ["B", "KB", "MB", "GB"];
+    if bytes < 1024 {
+        return format!("{} B", bytes);
+    }
+    let mut size = bytes as f64;
+    let mut unit = 0;
+    while size >= 1024.0 && unit < UNITS.len() - 1 {
+        size /= 1024.0;
+        unit += 1;
+    }
+    format!("{:.1} {}", size, UNITS[unit])
+}
+
+/// One uploaded file.
+struct Upload {
+    author_id: u64,
+    filename: String,
+    size: u64,
+    kind: AttachmentKind,
+    link: String,
+}
+
+#[derive(Default, Clone, Copy, Debug, PartialEq)]
+struct Tally {
+    files: u32,
+    bytes: u64,
+}
+
+impl Tally {
+    fn add(&mut self, size: u64) {
+        self.files += 1;
+        self.bytes += size;
+    }
+}
+
+#[derive(Default)]
+struct AttachmentStats {
+    /// Messages from people (bots are left out).
+    messages: u32,
+    with_attachments: u32,
+    total: Tally,
+    by_kind: HashMap<AttachmentKind, Tally>,
+    by_uploader: HashMap<u64, Tally>,
+    /// The largest files, biggest first, at most [`LARGEST_FILES`].
+    largest: Vec<Upload>,
+}
+
+impl AttachmentStats {
+    fn add_message(&mut self, uploads: Vec<Upload>) {
+        self.messages += 1;
+        if !uploads.is_empty() {
+            self.with_attachments += 1;
+        }
+        for upload in uploads {
+            self.total.add(upload.size);
+            self.by_kind
+                .entry(upload.kind)
+                .or_default()
+                .add(upload.size);
+            self.by_uploader
+                .entry(upload.author_id)
+                .or_default()
+                .add(upload.size);
+            self.largest.push(upload);
+            self.largest.sort_by_key(|upload| Reverse(upload.size));
+            self.largest.truncate(LARGEST_FILES);
+        }
+    }
+
+    /// The top uploaders as (user ID, tally), by file count or by total size.
+    /// Ties go to the lower user ID so the order is stable.
+    fn top_uploaders(&self, by_size: bool) -> Vec<(u64, Tally)> {
+        let mut uploaders: Vec<(u64, Tally)> = self
+            .by_uploader
+            .iter()
+            .map(|(id, tally)| (*id, *tally))
+            .collect();
+        uploaders.sort_by_key(|(id, tally)| {
+            let key = if by_size {
+                tally.bytes
+            } else {
+                tally.files as u64
+            };
+            (Reverse(key), *id)
+        });
+        uploaders.truncate(TOP_UPLOADERS);
+        uploaders
+    }
+
+    /// Share of messages with at least one attachment, in percent.
+    fn attachment_ratio(&self) -> f64 {
+        if self.messages == 0 {
+            return 0.0;
+        }
+        self.with_attachments as f64 * 100.0 / self.messages as f64
+    }
+}
+
+fn analyze_attachments(messages: &[serenity::Message]) -> AttachmentStats {
+    let mut stats = AttachmentStats::default();
+    for message in messages.iter().filter(|message| !message.author.bot) {
+        let uploads = message
+            .attachments
+            .iter()
+            .map(|attachment| Upload {
+                author_id: message.author.id.get(),
+                filename: attachment.filename.clone(),
+                size: attachment.size as u64,
+                kind: classify_attachment(&attachment.filename, attachment.content_type.as_deref()),
+                link: message.link(),
+            })
+            .collect();
+        stats.add_message(uploads);
+    }
+    stats
+}
+
+fn create_attachment_embed(
+    stats: &AttachmentStats,
+    channel_id: serenity::ChannelId,
+) -> serenity::CreateEmbed {
+    let kinds: Vec<String> = AttachmentKind::ALL
+        .iter()
+        .map(|kind| {
+            let tally = stats.by_kind.get(kind).copied().unwrap_or_default();
+            format!(
+                "{}: **{}** ({})",
+                kind.label(),
+                tally.files,
+                format_size(tally.bytes)
+            )
+        })
+        .collect();
+    let by_count: Vec<String> = stats
+        .top_uploaders(false)
+        .iter()
+        .enumerate()
+        .map(|(i, (id, tally))| format!("**{}.** <@{}> — {} files", i + 1, id, tally.files))
+        .collect();
+    let by_size: Vec<String> = stats
+        .top_uploaders(true)
+        .iter()
+        .enumerate()
+        .map(|(i, (id, tally))| format!("**{}.** <@{}> — {}", i + 1, id, format_size(tally.bytes)))
+        .collect();
+    let largest: Vec<String> = stats
+        .largest
+        .iter()
+        .map(|upload| {
+            format!(
+                "[{}]({}) — {} by <@{}>",
+                upload.filename.replace(['[', ']'], ""),
+                upload.link,
+                format_size(upload.size),
+                upload.author_id
+            )
+        })
+        .collect();
+
+    serenity::CreateEmbed::new()
+        .title("📎 Attachment Statistics")
+        .description(format!(
+            "<#{}> • last {} messages\n**{}** files ({}) in **{}** messages ({:.1}% of messages)",
+            channel_id,
+            stats.messages,
+            stats.total.files,
+            format_size(stats.total.bytes),
+            stats.with_attachments,
+            stats.attachment_ratio()
+        ))
+        .color(0x5865F2)
+        .field("By type", kinds.join("\n"), false)
+        .field(
+            "📈 Most files",
+            fit_list(&by_count, EMBED_FIELD_VALUE),
+            true,
+        )
+        .field("💾 Most data", fit_list(&by_size, EMBED_FIELD_VALUE), true)
+        .field(
+            "📏 Largest files",
+            fit_list(&largest, EMBED_FIELD_VALUE),
+            false,
+        )
+        .footer(serenity::CreateEmbedFooter::new(
+            "Bot messages are excluded",
+        ))
+}
+
+/// Who posts the most attachments in a channel, and what kind
+///
+/// Analyzes the last N messages (like `-stats`) and breaks their attachments
+/// down by type (image, video, audio, other), ranks uploaders by file count
+/// and by total size, and links the largest files.
+#[poise::command(prefix_command, slash_command)]
+pub async fn attachmentstats(
+    ctx: Context<'_>,
+    #[description = "Number of messages to analyze (default: 1000, max: 10000)"] count: Option<u64>,
+    #[description = "Channel to analyze (default: current channel)"] channel: Option<
+        serenity::GuildChannel,
+    >,
+) -> Result<(), Error> {
+    log::info!("Attachmentstats command called by {}", ctx.author().name);
+
+    let message_count = count.unwrap_or(1000).min(MAX_MESSAGES);
+    let (target_channel, channel_name) = match &channel {
+        Some(ch) => (ch.id, ch.name.clone()),
+        None => {
+            let name = match ctx.guild_channel().await {
+                Some(ch) => ch.name,
+                None => "Unknown".to_string(),
+            };
+            (ctx.channel_id(), name)
+        }
+    };
+
+    if let Err(missing) = check_channel_perms(ctx, target_channel, READ_HISTORY).await {
+        ctx.say(missing.to_string()).await?;
+        return Ok(());
+    }
+
+    let output = output_target(ctx, &format!("#{channel_name} attachments")).await?;
+    let reporter = ProgressReporter::new(
+        ctx,
+        output
+            .say(
+                ctx,
+                format!("Analyzing attachments in the last {message_count} messages in <#{target_channel}>..."),
+            )
+            .await?,
+    );
+
+    let reporter = &reporter;
+    let progress = |collected: u64| async move {
+        reporter
+            .set(format!("Analyzing messages... {collected}/{message_count}"))
+            .await?;
+        Ok::<(), Error>(())
+    };
+    let messages = match collect_messages(ctx.http(), target_channel, message_count, progress).await
+    {
+        Ok(messages) => messages,
+        Err(e) => {
+            reporter
+                .set(history_error_message(e.as_ref(), target_channel))
+                .await?;
+            return Ok(());
+        }
+    };
+
+    let stats = analyze_attachments(&messages);
+    if stats.total.files == 0 {
+        reporter
+            .set(format!(
+                "📎 No attachments in the last {} messages in <#{}>.",
+                messages.len(),
+                target_channel
+            ))
+            .await?;
+        return Ok(());
+    }
+
+    reporter
+        .update(
+            poise::CreateReply::default()
+                .content("")
+                .embed(create_attachment_embed(&stats, target_channel)),
+        )
+        .await?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_classify_attachment() {
+        assert_eq!(
+            classify_attachment("cat.png", Some("image/png")),
+            AttachmentKind::Image
+        );
+        assert_eq!(
+            classify_attachment("clip.bin", Some("video/mp4")),
+            AttachmentKind::Video
+        );
+        // Parameters and odd casing in the content type
+        assert_eq!(
+            classify_attachment("voice", Some("Audio/OGG; codecs=opus")),
+            AttachmentKind::Audio
+        );
+        // No content type: the extension decides, whatever its case
+        assert_eq!(
+            classify_attachment("IMG_0042.JPG", None),
+            AttachmentKind::Image
+        );
+        assert_eq!(classify_attachment("song.Mp3", None), AttachmentKind::Audio);
+        assert_eq!(
+            classify_attachment("movie.final.MOV", None),
+            AttachmentKind::Video
+        );
+        // A generic content type falls back to the extension too
+        assert_eq!(
+            classify_attachment("drawing.webp", Some("application/octet-stream")),
+            AttachmentKind::Image
+        );
+        assert_eq!(
+            classify_attachment("notes.txt", Some("text/plain; charset=utf-8")),
+            AttachmentKind::Other
+        );
+        assert_eq!(classify_attachment("README", None), AttachmentKind::Other);
+        assert_eq!(
+            classify_attachment("archive.zip", Some("")),
+            AttachmentKind::Other
+        );
+    }
+
+    #[test]
+    fn test_format_size() {
+        assert_eq!(format_size(0), "0 B");
+        assert_eq!(format_size(1023), "1023 B");
+        assert_eq!(format_size(1536), "1.5 KB");
+        assert_eq!(format_size(8 * 1024 * 1024), "8.0 MB");
+        assert_eq!(format_size(3 * 1024 * 1024 * 1024), "3.0 GB");
+    }
+
+    fn upload(author_id: u64, filename: &str, size: u64) -> Upload {
+        Upload {
+            author_id,
+            filename: filename.to_string(),
+            size,
+            kind: classify_attachment(filename, None),
+            link: format!("https://discord.com/channels/1/2/{}", size),
+        }
+    }
+
+    #[test]
+    fn test_attachment_stats() {
+        let mut stats = AttachmentStats::default();
+        stats.add_message(vec![upload(1, "a.png", 100), upload(1, "b.png", 200)]);
+        stats.add_message(vec![upload(2, "huge.mp4", 5000)]);
+        stats.add_message(vec![]);
+        stats.add_message(vec![upload(3, "c.txt", 50)]);
+
+        assert_eq!(stats.messages, 4);
+        assert_eq!(stats.with_attachments, 3);
+        assert_eq!(stats.attachment_ratio(), 75.0);
+        assert_eq!(
+            stats.total,
+            Tally {
+                files: 4,
+                bytes: 5350
+            }
+        );
+        assert_eq!(
+            stats.by_kind[&AttachmentKind::Image],
+            Tally {
+                files: 2,
+                bytes: 300
+            }
+        );
+
+        let by_count: Vec<u64> = stats
+            .top_uploaders(false)
+            .iter()
+            .map(|(id, _)| *id)
+            .collect();
+        assert_eq!(by_count, [1, 2, 3]);
+        let by_size: Vec<u64> = stats
+            .top_uploaders(true)
+            .iter()
+            .map(|(id, _)| *id)
+            .collect();
+        assert_eq!(by_size, [2, 1, 3]);
+
+        let largest: Vec<&str> = stats.largest.iter().map(|u| u.filename.as_str()).collect();
+        assert_eq!(largest, ["huge.mp4", "b.png", "a.png", "c.txt"]);
+        assert_eq!(AttachmentStats::default().attachment_ratio(), 0.0);
+    }
+}
@@ -0,0 +1,751 @@
+use crate::config_check::{check_store, ConfigProblem, LiveIds};
+use crate::storage;
+use crate::utils::{check_store_writable, is_protected_user};
+use crate::{Context, Error};
+use chrono::{DateTime, Duration, Utc};
+use parking_lot::{Mutex, RwLock};
+use poise::serenity_prelude as serenity;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+const AUTOMOD_FILE: &str = "automod.json";
+
+const MAX_WORDS_PER_GUILD: usize = 100;
+
+const MAX_WORD_LENGTH: usize = 100;
+
+/// Strikes older than this no longer count towards a timeout.
+const STRIKE_WINDOW_MINUTES: i64 = 60;
+
+const DEFAULT_TIMEOUT_MINUTES: u32 = 10;
+
+/// Discord caps timeouts at 28 days.
+const MAX_TIMEOUT_MINUTES: u32 = 28 * 24 * 60;
+
+/// The "message removed" notice is deleted after this long.
+const NOTICE_LIFETIME: std::time::Duration = std::time::Duration::from_secs(10);
+
+#[derive(Serialize, Deserialize, Clone, Default)]
+#[serde(default)]
+struct AutoModConfig {
+    /// Blocked words and phrases, lowercase.
+    words: Vec<String>,
+    exempt_roles: Vec<u64>,
+    exempt_channels: Vec<u64>,
+    /// Time out members after this many removed messages within
+    /// [`STRIKE_WINDOW_MINUTES`]; `None` only deletes.
+    timeout_after: Option<u32>,
+    timeout_minutes: Option<u32>,
+}
+
+impl AutoModConfig {
+    fn is_exempt(&self, channel_id: u64, role_ids: &[u64]) -> bool {
+        self.exempt_channels.contains(&channel_id)
+            || role_ids.iter().any(|role| self.exempt_roles.contains(role))
+    }
+}
+
+#[derive(Serialize, Deserialize, Default, Clone)]
+struct AutoModData {
+    guilds: HashMap<u64, AutoModConfig>,
+}
+
+/// Times of recent removals, per (guild, user).
+type StrikeLog = HashMap<(u64, u64), Vec<DateTime<Utc>>>;
+
+lazy_static::lazy_static! {
+    static ref AUTOMOD: RwLock<AutoModData> = RwLock::new(storage::load(AUTOMOD_FILE));
+    /// Recent removals per (guild, user), for timing out repeat offenders.
+    static ref STRIKES: Mutex<StrikeLog> = Mutex::new(HashMap::new());
+}
+
+/// Re-read the blocked words and exemptions from disk.
+pub fn reload_automod() -> Result<(), Error> {
+    *AUTOMOD.write() = storage::reload(AUTOMOD_FILE)?;
+    Ok(())
+}
+
+/// Whether a guild has any blocked words.
+pub fn has_blocked_words(guild_id: u64) -> bool {
+    AUTOMOD
+        .read()
+        .guilds
+        .get(&guild_id)
+        .is_some_and(|config| !config.words.is_empty())
+}
+
+fn save_automod(data: &AutoModData) {
+    if let Err(e) = storage::save(AUTOMOD_FILE, data) {
+        log::error!("Failed to save automod config: {}", e);
+    }
+}
+
+/// Drop an orphaned guild's blocked words and exemptions.
+pub fn purge_guild(guild_id: u64) {
+    let mut data = AUTOMOD.write();
+    if data.guilds.remove(&guild_id).is_some() {
+        save_automod(&data);
+    }
+}
+
+/// Command check for commands that change the automod settings.
+async fn automod_writable(ctx: Context<'_>) -> Result<bool, Error> {
+    check_store_writable(ctx, &storage::data_path(AUTOMOD_FILE)).await
+}
+
+/// Drop exemptions for deleted channels and roles, and timeout settings
+/// Discord would refuse: zero strikes, or a timeout of zero minutes or over
+/// the cap (the default length is used instead).
+fn validate_automod(data: &mut AutoModData, live: &LiveIds) -> Vec<ConfigProblem> {
+    let mut problems = Vec::new();
+    for (&guild_id, config) in &mut data.guilds {
+        config.exempt_channels.retain(|&channel_id| {
+            let gone = live.channel_gone(guild_id, channel_id);
+            if gone {
+                problems.push(ConfigProblem::deleted_channel(
+                    guild_id,
+                    "exempt_channels",
+                    channel_id,
+                ));
+            }
+            !gone
+        });
+        config.exempt_roles.retain(|&role_id| {
+            let gone = live.role_gone(guild_id, role_id);
+            if gone {
+                problems.push(ConfigProblem::deleted_role(
+                    guild_id,
+                    "exempt_roles",
+                    role_id,
+                ));
+            }
+            !gone
+        });
+        if config.timeout_after == Some(0) {
+            problems.push(ConfigProblem::invalid(
+                guild_id,
+                "timeout_after",
+                "a timeout after 0 strikes",
+                0,
+            ));
+            config.timeout_after = None;
+        }
+        if let Some(minutes) = config
+            .timeout_minutes
+            .filter(|minutes| !(1..=MAX_TIMEOUT_MINUTES).contains(minutes))
+        {
+            problems.push(ConfigProblem::invalid(
+                guild_id,
+                "timeout_minutes",
+                format!("must be 1-{} minutes", MAX_TIMEOUT_MINUTES),
+                minutes,
+            ));
+            config.timeout_minutes = None;
+        }
+    }
+    problems
+}
+
+/// Check the automod settings, see [`crate::config_check`].
+pub fn check_automod(live: &LiveIds, quarantine: bool) -> Vec<ConfigProblem> {
+    check_store(
+        &AUTOMOD,
+        quarantine,
+        |data| validate_automod(data, live),
+        save_automod,
+    )
+}
+
+/// Lowercase words of `text`, split on anything that isn't a letter or digit.
+fn words_of(text: &str) -> Vec<String> {
+    text.split(|c: char| !c.is_alphanumeric())
+        .filter(|word| !word.is_empty())
+        .map(str::to_lowercase)
+        .collect()
+}
+
+/// The first blocked entry that appears in `content` as whole words, so
+/// "class" doesn't trip a block on "ass". Entries can be phrases.
+fn find_blocked_word<'a>(blocklist: &'a [String], content: &str) -> Option<&'a str> {
+    let content_words = words_of(content);
+    blocklist
+        .iter()
+        .find(|entry| {
+            let entry_words = words_of(entry);
+            !entry_words.is_empty()
+                && content_words
+                    .windows(entry_words.len())
+                    .any(|window| window == entry_words.as_slice())
+        })
+        .map(String::as_str)
+}
+
+/// Whether `content` runs `-automod add` or `-automod remove`, the commands
+/// that name a blocked word.
+fn names_blocked_word(content: &str, command_prefix: &str) -> bool {
+    let Some(command) = content.strip_prefix(command_prefix) else {
+        return false;
+    };
+    let mut words = command.split_whitespace().map(str::to_lowercase);
+    words.next().as_deref() == Some("automod")
+        && matches!(words.next().as_deref(), Some("add" | "remove"))
+}
+
+/// Whether the author of a guild message has Manage Server, going by the
+/// cache. Anything missing from it counts as no.
+fn can_manage_guild(
+    ctx: &serenity::Context,
+    guild_id: serenity::GuildId,
+    message: &serenity::Message,
+) -> bool {
+    let Some(member) = message.member.as_ref() else {
+        return false;
+    };
+    let Some(guild) = ctx.cache.guild(guild_id) else {
+        return false;
+    };
+    if guild.owner_id == message.author.id {
+        return true;
+    }
+    let everyone = serenity::RoleId::new(guild_id.get());
+    let permissions = std::iter::once(&everyone)
+        .chain(&member.roles)
+        .filter_map(|role| guild.roles.get(role))
+        .fold(serenity::Permissions::empty(), |all, role| {
+            all | role.permissions
+        });
+    permissions.administrator() || permissions.manage_guild()
+}
+
+/// Record a strike at `now`, dropping ones older than the strike window, and
+/// return how many are left.
+fn record_strike(strikes: &mut Vec<DateTime<Utc>>, now: DateTime<Utc>) -> usize {
+    let cutoff = now - Duration::minutes(STRIKE_WINDOW_MINUTES);
+    strikes.retain(|strike| *strike > cutoff);
+    strikes.push(now);
+    strikes.len()
+}
+
+/// Delete a new message if it contains a blocked word, timing out the author
+/// if they keep at it. Returns whether the message was removed.
+pub async fn moderate_message(
+    ctx: &serenity::Context,
+    message: &serenity::Message,
+    command_prefix: &str,
+) -> bool {
+    if message.author.bot {
+        return false;
+    }
+    let Some(guild_id) = message.guild_id else {
+        return false;
+    };
+    // `-automod add/remove <word>` has to be able to name the word, but only
+    // for those allowed to run it
+    if names_blocked_word(&message.content, command_prefix)
+        && (is_protected_user(&message.author.name) || can_manage_guild(ctx, guild_id, message))
+    {
+        return false;
+    }
+
+    let role_ids: Vec<u64> = message
+        .member
+        .as_ref()
+        .map(|member| member.roles.iter().map(|role| role.get()).collect())
+        .unwrap_or_default();
+    let (blocked, timeout) = {
+        let data = AUTOMOD.read();
+        let Some(config) = data.guilds.get(&guild_id.get()) else {
+            return false;
+        };
+        if config.is_exempt(message.channel_id.get(), &role_ids) {
+            return false;
+        }
+        let Some(blocked) = find_blocked_word(&config.words, &message.content) else {
+            return false;
+        };
+        let timeout = config.timeout_after.map(|after| {
+            (
+                after,
+                config.timeout_minutes.unwrap_or(DEFAULT_TIMEOUT_MINUTES),
+            )
+        });
+        (blocked.to_string(), timeout)
+    };
+
+    if let Err(e) = message.delete(&ctx.http).await {
+        log::warn!(
+            "Failed to delete blocked message in guild {}: {}",
+            guild_id,
+            e
+        );
+        return false;
+    }
+    log::info!(
+        "Removed message from {} in guild {} for blocked word '{}'",
+        message.author.name,
+        guild_id,
+        blocked
+    );
+
+    let mut notice = format!(
+        "⚠️ <@{}>, your message was removed because it contains a blocked word.",
+        message.author.id
+    );
+    if let Some((after, minutes)) = timeout {
+        let now = Utc::now();
+        let strikes = {
+            let mut all_strikes = STRIKES.lock();
+            let strikes = all_strikes
+                .entry((guild_id.get(), message.author.id.get()))
+                .or_default();
+            let count = record_strike(strikes, now);
+            if count >= after as usize {
+                strikes.clear();
+            }
+            count
+        };
+
+        if strikes >= after as usize {
+            let until = now + Duration::minutes(minutes as i64);
+            let edit = serenity::EditMember::new()
+                .disable_communication_until_datetime(serenity::Timestamp::from(until));
+            match guild_id
+                .edit_member(&ctx.http, message.author.id, edit)
+                .await
+            {
+                Ok(_) => {
+                    notice.push_str(&format!(" You've been timed out for {} minutes.", minutes));
+                }
+                Err(e) => log::warn!(
+                    "Failed to time out {} in guild {}: {}",
+                    message.author.name,
+                    guild_id,
+                    e
+                ),
+            }
+        }
+    }
+
+    match message.channel_id.say(&ctx.http, notice).await {
+        Ok(notice) => {
+            let http = ctx.http.clone();
+            tokio::spawn(async move {
+                tokio::time::sleep(NOTICE_LIFETIME).await;
+                if let Err(e) = notice.delete(&http).await {
+                    log::warn!("Failed to delete automod notice: {}", e);
+                }
+            });
+        }
+        Err(e) => log::warn!("Failed to send automod notice: {}", e),
+    }
+
+    true
+}
+
+/// Automatically remove messages containing blocked words
+#[poise::command(
+    prefix_command,
+    slash_command,
+    guild_only,
+    required_permissions = "MANAGE_GUILD",
+    subcommands(
+        "automod_add",
+        "automod_remove",
+        "automod_list",
+        "automod_exempt",
+        "automod_timeout"
+    )
+)]
+pub async fn automod(_ctx: Context<'_>) -> Result<(), Error> {
+    Ok(())
+}
+
+/// Block a word or phrase
+#[poise::command(
+    prefix_command,
+    slash_command,
+    guild_only,
+    rename = "add",
+    required_permissions = "MANAGE_GUILD",
+    check = "automod_writable"
+)]
+pub async fn automod_add(
+    ctx: Context<'_>,
+    #[description = "Word or phrase to block (matched as whole words, any case)"]
+    #[rest]
+    word: String,
+) -> Result<(), Error> {
+    log::info!("Automod add command called by {}", ctx.author().name);
+
+    let Some(guild_id) = ctx.guild_id() else {
+        return Ok(());
+    };
+    let word = words_of(&word).join(" ");
+    if word.is_empty() || word.len() > MAX_WORD_LENGTH {
+        ctx.say(format!(
+            "❌ The word must contain letters or digits and be at most {} characters!",
+            MAX_WORD_LENGTH
+        ))
+        .await?;
+        return Ok(());
+    }
+
+    let response = {
+        let mut data = AUTOMOD.write();
+        let config = data.guilds.entry(guild_id.get()).or_default();
+        if config.words.contains(&word) {
+            format!("❌ `{}` is already blocked!", word)
+        } else if config.words.len() >= MAX_WORDS_PER_GUILD {
+            format!(
+                "❌ This server already blocks {} words. Remove one first!",
+                MAX_WORDS_PER_GUILD
+            )
+        } else {
+            config.words.push(word.clone());
+            save_automod(&data);
+            format!("✅ Messages containing ||{}|| will now be removed.", word)
+        }
+    };
+
+    ctx.send(
+        poise::CreateReply::default()
+            .content(response)
+            .ephemeral(true),
+    )
+    .await?;
+    Ok(())
+}
+
+/// Unblock a word or phrase
+#[poise::command(
+    prefix_command,
+    slash_command,
+    guild_only,
+    rename = "remove",
+    required_permissions = "MANAGE_GUILD",
+    check = "automod_writable"
+)]
+pub async fn automod_remove(
+    ctx: Context<'_>,
+    #[description = "Blocked word or phrase to remove"]
+    #[rest]
+    word: String,
+) -> Result<(), Error> {
+    log::info!("Automod remove command called by {}", ctx.author().name);
+
+    let Some(guild_id) = ctx.guild_id() else {
+        return Ok(());
+    };
+    let word = words_of(&word).join(" ");
+
+    let removed = {
+        let mut data = AUTOMOD.write();
+        let removed = data
+            .guilds
+            .get_mut(&guild_id.get())
+            .and_then(|config| {
+                let index = config.words.iter().position(|w| *w == word)?;
+                Some(config.words.remove(index))
+            })
+            .is_some();
+        if removed {
+            save_automod(&data);
+        }
+        removed
+    };
+
+    let response = if removed {
+        format!("🗑️ ||{}|| is no longer blocked.", word)
+    } else {
+        "❌ That word isn't on this server's blocklist!".to_string()
+    };
+    ctx.send(
+        poise::CreateReply::default()
+            .content(response)
+            .ephemeral(true),
+    )
+    .await?;
+    Ok(())
+}
+
+/// Show the blocklist, exemptions and timeout setting
+#[poise::command(
+    prefix_command,
+    slash_command,
+    guild_only,
+    rename = "list",
+    required_permissions = "MANAGE_GUILD"
+)]
+pub async fn automod_list(ctx: Context<'_>) -> Result<(), Error> {
+    log::info!("Automod list command called by {}", ctx.author().name);
+
+    let Some(guild_id) = ctx.guild_id() else {
+        return Ok(());
+    };
+    let config = AUTOMOD
+        .read()
+        .guilds
+        .get(&guild_id.get())
+        .cloned()
+        .unwrap_or_default();
+
+    let words = if config.words.is_empty() {
+        "None".to_string()
+    } else {
+        config
+            .words
+            .iter()
+            .map(|word| format!("||{}||", word))
+            .collect::<Vec<_>>()
+            .join(", ")
+    };
+    let mut exemptions: Vec<String> = config
+        .exempt_roles
+        .iter()
+        .map(|id| format!("<@&{}>", id))
+        .collect();
+    exemptions.extend(config.exempt_channels.iter().map(|id| format!("<#{}>", id)));
+    let exemptions = if exemptions.is_empty() {
+        "None".to_string()
+    } else {
+        exemptions.join(", ")
+    };
+    let timeout = match config.timeout_after {
+        Some(after) => format!(
+            "{} minutes after {} removed messages within an hour",
+            config.timeout_minutes.unwrap_or(DEFAULT_TIMEOUT_MINUTES),
+            after
+        ),
+        None => "Off (messages are only deleted)".to_string(),
+    };
+
+    let embed = serenity::CreateEmbed::new()
+        .title("🛡️ Automod")
+        .field(
+            format!(
+                "Blocked words ({}/{})",
+                config.words.len(),
+                MAX_WORDS_PER_GUILD
+            ),
+            words,
+            false,
+        )
+        .field("Exempt roles and channels", exemptions, false)
+        .field("Timeout", timeout, false)
+        .color(0x5865F2);
+
+    ctx.send(poise::CreateReply::default().embed(embed).ephemeral(true))
+        .await?;
+    Ok(())
+}
+
+/// Exempt a role or channel from automod, or un-exempt it if it already is
+#[poise::command(
+    prefix_command,
+    slash_command,
+    guild_only,
+    rename = "exempt",
+    required_permissions = "MANAGE_GUILD",
+    check = "automod_writable"
+)]
+pub async fn automod_exempt(
+    ctx: Context<'_>,
+    #[description = "Role whose members are never moderated"] role: Option<serenity::Role>,
+    #[description = "Channel that is never moderated"] channel: Option<serenity::GuildChannel>,
+) -> Result<(), Error> {
+    log::info!("Automod exempt command called by {}", ctx.author().name);
+
+    let Some(guild_id) = ctx.guild_id() else {
+        return Ok(());
+    };
+    let (id, mention, is_role) = match (role, channel) {
+        (Some(role), None) => (role.id.get(), format!("<@&{}>", role.id), true),
+        (None, Some(channel)) => (channel.id.get(), format!("<#{}>", channel.id), false),
+        _ => {
+            ctx.say("❌ Give either a role or a channel to exempt!")
+                .await?;
+            return Ok(());
+        }
+    };
+
+    let now_exempt = {
+        let mut data = AUTOMOD.write();
+        let config = data.guilds.entry(guild_id.get()).or_default();
+        let list = if is_role {
+            &mut config.exempt_roles
+        } else {
+            &mut config.exempt_channels
+        };
+        let now_exempt = match list.iter().position(|existing| *existing == id) {
+            Some(index) => {
+                list.remove(index);
+                false
+            }
+            None => {
+                list.push(id);
+                true
+            }
+        };
+        save_automod(&data);
+        now_exempt
+    };
+
+    let response = if now_exempt {
+        format!("✅ {} is now exempt from automod.", mention)
+    } else {
+        format!("✅ {} is no longer exempt from automod.", mention)
+    };
+    ctx.send(
+        poise::CreateReply::default()
+            .content(response)
+            .allowed_mentions(serenity::CreateAllowedMentions::new()),
+    )
+    .await?;
+    Ok(())
+}
+
+/// Time out members who keep sending blocked words
+#[poise::command(
+    prefix_command,
+    slash_command,
+    guild_only,
+    rename = "timeout",
+    required_permissions = "MANAGE_GUILD",
+    check = "automod_writable"
+)]
+pub async fn automod_timeout(
+    ctx: Context<'_>,
+    #[description = "Removed messages within an hour before a timeout (0 to turn off)"]
+    strikes: u32,
+    #[description = "Timeout length in minutes (default: 10)"] minutes: Option<u32>,
+) -> Result<(), Error> {
+    log::info!("Automod timeout command called by {}", ctx.author().name);
+
+    let Some(guild_id) = ctx.guild_id() else {
+        return Ok(());
+    };
+    let minutes = minutes.unwrap_or(DEFAULT_TIMEOUT_MINUTES);
+    if !(1..=MAX_TIMEOUT_MINUTES).contains(&minutes) {
+        ctx.say(format!(
+            "❌ The timeout must be between 1 and {} minutes (28 days)!",
+            MAX_TIMEOUT_MINUTES
+        ))
+        .await?;
+        return Ok(());
+    }
+
+    {
+        let mut data = AUTOMOD.write();
+        let config = data.guilds.entry(guild_id.get()).or_default();
+        config.timeout_after = (strikes > 0).then_some(strikes);
+        config.timeout_minutes = Some(minutes);
+        save_automod(&data);
+    }
+
+    let response = if strikes > 0 {
+        format!(
+            "✅ Members will be timed out for {} minutes after {} removed messages within an hour. I need the Timeout Members permission for this.",
+            minutes, strikes
+        )
+    } else {
+        "✅ Automod timeouts are off. Blocked messages are only deleted.".to_string()
+    };
+    ctx.say(response).await?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn blocklist(words: &[&str]) -> Vec<String> {
+        words.iter().map(|word| word.to_string()).collect()
+    }
+
+    #[test]
+    fn test_validate_automod() {
+        let mut data: AutoModData =
+            serde_json::from_str(include_str!("../../tests/fixtures/config/automod.json")).unwrap();
+        let live = LiveIds::default().with_guild(1, &[10], &[20]);
+
+        let mut problems = validate_automod(&mut data, &live);
+        problems.sort_by_key(|problem| problem.field.clone());
+        let found: Vec<(&str, &str)> = problems
+            .iter()
+            .map(|problem| (problem.field.as_str(), problem.problem.as_str()))
+            .collect();
+        assert_eq!(
+            found,
+            [
+                ("exempt_channels", "channel 11 was deleted"),
+                ("exempt_roles", "role 21 was deleted"),
+                ("timeout_after", "a timeout after 0 strikes"),
+                ("timeout_minutes", "must be 1-40320 minutes"),
+            ]
+        );
+        let config = &data.guilds[&1];
+        assert_eq!(config.exempt_channels, [10]);
+        assert_eq!(config.exempt_roles, [20]);
+        assert_eq!((config.timeout_after, config.timeout_minutes), (None, None));
+        assert_eq!(config.words, ["spoiler"]);
+        assert!(validate_automod(&mut data, &live).is_empty());
+    }
+
+    #[test]
+    fn test_blocked_words_match_whole_words_case_insensitively() {
+        let words = blocklist(&["heck", "dang it"]);
+        assert_eq!(find_blocked_word(&words, "What the HECK"), Some("heck"));
+        assert_eq!(find_blocked_word(&words, "heck!"), Some("heck"));
+        assert_eq!(
+            find_blocked_word(&words, "oh... Dang   it."),
+            Some("dang it")
+        );
+
+        assert_eq!(find_blocked_word(&words, "Checking in"), None);
+        assert_eq!(find_blocked_word(&words, "hecking"), None);
+        assert_eq!(find_blocked_word(&words, "dang, where is it"), None);
+        assert_eq!(find_blocked_word(&[], "anything"), None);
+    }
+
+    #[test]
+    fn test_names_blocked_word() {
+        assert!(names_blocked_word("-automod add badword", "-"));
+        assert!(names_blocked_word("-AutoMod Remove badword", "-"));
+        assert!(!names_blocked_word("-automod list", "-"));
+        assert!(!names_blocked_word("-automodbadword", "-"));
+        assert!(!names_blocked_word("-automod badword", "-"));
+        assert!(!names_blocked_word("automod add badword", "-"));
+    }
+
+    #[test]
+    fn test_exemptions() {
+        let config = AutoModConfig {
+            exempt_roles: vec![10],
+            exempt_channels: vec![20],
+            ..Default::default()
+        };
+        assert!(config.is_exempt(20, &[]));
+        assert!(config.is_exempt(1, &[5, 10]));
+        assert!(!config.is_exempt(1, &[5]));
+    }
+
+    #[test]
+    fn test_record_strike_forgets_old_strikes() {
+        let now = Utc::now();
+        let mut strikes = vec![now - Duration::minutes(90), now - Duration::minutes(30)];
+        assert_eq!(record_strike(&mut strikes, now), 2);
+        assert_eq!(record_strike(&mut strikes, now), 3);
+        assert_eq!(
+            record_strike(&mut strikes, now + Duration::minutes(STRIKE_WINDOW_MINUTES)),
+            1
+        );
+    }
+
+    #[test]
+    fn test_config_fills_missing_fields() {
+        let config: AutoModConfig = serde_json::from_str(r#"{"words": ["heck"]}"#).unwrap();
+        assert_eq!(config.words, vec!["heck"]);
+        assert!(config.exempt_roles.is_empty());
+        assert_eq!(config.timeout_after, None);
+    }
+}
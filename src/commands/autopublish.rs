@@ -0,0 +1,572 @@
+//! Auto-publish for announcement channels: new posts from the right people
+//! are crossposted to following servers without anyone pressing "Publish".
+//!
+//! Discord allows 10 publishes per channel per hour. Posts past that wait in
+//! a queue that is worked through as the allowance comes back, and their
+//! authors get a DM saying how long it took. The queue lives in memory, so
+//! posts still waiting during a restart have to be published by hand.
+
+use crate::config_check::{check_store, ConfigProblem, LiveIds};
+use crate::storage;
+use crate::utils::check_store_writable;
+use crate::{Context, Error};
+use chrono::{DateTime, Duration, Utc};
+use parking_lot::{Mutex, RwLock};
+use poise::serenity_prelude as serenity;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, VecDeque};
+use std::sync::Arc;
+use tokio::time::{interval, Duration as TokioDuration};
+
+const AUTOPUBLISH_FILE: &str = "autopublish.json";
+
+/// Discord's publish limit per channel per [`PUBLISH_WINDOW_MINUTES`].
+const PUBLISHES_PER_WINDOW: usize = 10;
+const PUBLISH_WINDOW_MINUTES: i64 = 60;
+
+/// An announcement channel whose posts get published automatically.
+#[derive(Serialize, Deserialize, Clone, Default, Debug, PartialEq)]
+#[serde(default)]
+struct AutoPublishRule {
+    guild_id: u64,
+    /// Authors with one of these roles, or listed in `users`, get their posts
+    /// published. With neither set, anyone with Manage Channels does.
+    roles: Vec<u64>,
+    users: Vec<u64>,
+}
+
+impl AutoPublishRule {
+    /// Whether the author is on the allow list; `None` if there isn't one and
+    /// it comes down to their permissions.
+    fn allows(&self, user_id: u64, role_ids: &[u64]) -> Option<bool> {
+        if self.roles.is_empty() && self.users.is_empty() {
+            return None;
+        }
+        Some(self.users.contains(&user_id) || role_ids.iter().any(|r| self.roles.contains(r)))
+    }
+}
+
+#[derive(Serialize, Deserialize, Default, Clone)]
+struct AutoPublishData {
+    /// Rules by channel ID.
+    channels: HashMap<u64, AutoPublishRule>,
+}
+
+/// A post waiting for the channel's publish allowance.
+#[derive(Clone, Debug, PartialEq)]
+struct QueuedPost {
+    message_id: u64,
+    author_id: u64,
+    queued_at: DateTime<Utc>,
+}
+
+/// Publishes in the last window and posts waiting for one, per channel.
+#[derive(Default)]
+struct ChannelQueue {
+    published: VecDeque<DateTime<Utc>>,
+    waiting: VecDeque<QueuedPost>,
+}
+
+impl ChannelQueue {
+    /// Use up one publish if the allowance has room at `now`.
+    fn try_take(&mut self, now: DateTime<Utc>) -> bool {
+        let cutoff = now - Duration::minutes(PUBLISH_WINDOW_MINUTES);
+        while self.published.front().is_some_and(|at| *at <= cutoff) {
+            self.published.pop_front();
+        }
+        if self.published.len() >= PUBLISHES_PER_WINDOW {
+            return false;
+        }
+        self.published.push_back(now);
+        true
+    }
+
+    /// The waiting posts that can be published now, oldest first.
+    fn take_due(&mut self, now: DateTime<Utc>) -> Vec<QueuedPost> {
+        let mut due = Vec::new();
+        while !self.waiting.is_empty() && self.try_take(now) {
+            due.extend(self.waiting.pop_front());
+        }
+        due
+    }
+}
+
+lazy_static::lazy_static! {
+    static ref AUTOPUBLISH: RwLock<AutoPublishData> = RwLock::new(storage::load(AUTOPUBLISH_FILE));
+    /// Publish allowance and waiting posts, by channel ID.
+    static ref QUEUES: Mutex<HashMap<u64, ChannelQueue>> = Mutex::new(HashMap::new());
+}
+
+/// Re-read which channels are auto-published from disk.
+pub fn reload_autopublish() -> Result<(), Error> {
+    *AUTOPUBLISH.write() = storage::reload(AUTOPUBLISH_FILE)?;
+    Ok(())
+}
+
+/// Whether any of a guild's channels are auto-published.
+pub fn has_channels(guild_id: u64) -> bool {
+    AUTOPUBLISH
+        .read()
+        .channels
+        .values()
+        .any(|rule| rule.guild_id == guild_id)
+}
+
+fn save_autopublish(data: &AutoPublishData) {
+    if let Err(e) = storage::save(AUTOPUBLISH_FILE, data) {
+        log::error!("Failed to save autopublish config: {}", e);
+    }
+}
+
+/// Drop rules for deleted channels, and deleted roles from the allow lists.
+fn validate_autopublish(data: &mut AutoPublishData, live: &LiveIds) -> Vec<ConfigProblem> {
+    let mut problems = Vec::new();
+    data.channels.retain(|&channel_id, rule| {
+        if live.channel_gone(rule.guild_id, channel_id) {
+            problems.push(ConfigProblem::deleted_channel(
+                rule.guild_id,
+                "channel",
+                channel_id,
+            ));
+            return false;
+        }
+        rule.roles.retain(|&role_id| {
+            let gone = live.role_gone(rule.guild_id, role_id);
+            if gone {
+                problems.push(ConfigProblem::deleted_role(rule.guild_id, "roles", role_id));
+            }
+            !gone
+        });
+        true
+    });
+    problems
+}
+
+/// Check the auto-publish settings, see [`crate::config_check`].
+pub fn check_autopublish(live: &LiveIds, quarantine: bool) -> Vec<ConfigProblem> {
+    check_store(
+        &AUTOPUBLISH,
+        quarantine,
+        |data| validate_autopublish(data, live),
+        save_autopublish,
+    )
+}
+
+/// Command check for commands that change the autopublish settings.
+async fn autopublish_writable(ctx: Context<'_>) -> Result<bool, Error> {
+    check_store_writable(ctx, &storage::data_path(AUTOPUBLISH_FILE)).await
+}
+
+/// Publish a new post in an auto-publish channel, or queue it if the
+/// channel is out of publishes for the hour.
+pub async fn publish_message(ctx: &serenity::Context, message: &serenity::Message) {
+    let Some(guild_id) = message.guild_id else {
+        return;
+    };
+    let is_post = matches!(
+        message.kind,
+        serenity::MessageType::Regular | serenity::MessageType::InlineReply
+    );
+    if !is_post || message.webhook_id.is_some() {
+        return;
+    }
+    let Some(rule) = AUTOPUBLISH
+        .read()
+        .channels
+        .get(&message.channel_id.get())
+        .cloned()
+    else {
+        return;
+    };
+    if !author_allowed(ctx, message, guild_id, &rule).await {
+        return;
+    }
+
+    let now = Utc::now();
+    let publish_now = {
+        let mut queues = QUEUES.lock();
+        let queue = queues.entry(message.channel_id.get()).or_default();
+        // Keep posts in order behind ones that are already waiting
+        if queue.waiting.is_empty() && queue.try_take(now) {
+            true
+        } else {
+            queue.waiting.push_back(QueuedPost {
+                message_id: message.id.get(),
+                author_id: message.author.id.get(),
+                queued_at: now,
+            });
+            false
+        }
+    };
+
+    if publish_now {
+        if let Err(e) = message.channel_id.crosspost(&ctx.http, message.id).await {
+            log::warn!(
+                "Failed to publish message {} in channel {}: {}",
+                message.id,
+                message.channel_id,
+                e
+            );
+        }
+    } else {
+        log::info!(
+            "Publish limit reached in channel {}, queued message {}",
+            message.channel_id,
+            message.id
+        );
+        // Shows that it's waiting rather than forgotten
+        let _ = message.react(&ctx.http, '⏳').await;
+    }
+}
+
+async fn author_allowed(
+    ctx: &serenity::Context,
+    message: &serenity::Message,
+    guild_id: serenity::GuildId,
+    rule: &AutoPublishRule,
+) -> bool {
+    let role_ids: Vec<u64> = message
+        .member
+        .as_ref()
+        .map(|member| member.roles.iter().map(|role| role.get()).collect())
+        .unwrap_or_default();
+    if let Some(allowed) = rule.allows(message.author.id.get(), &role_ids) {
+        return allowed;
+    }
+
+    let Ok(member) = guild_id.member(ctx, message.author.id).await else {
+        return false;
+    };
+    let Some(guild) = ctx.cache.guild(guild_id) else {
+        return false;
+    };
+    guild
+        .channels
+        .get(&message.channel_id)
+        .is_some_and(|channel| {
+            guild
+                .user_permissions_in(channel, &member)
+                .manage_channels()
+        })
+}
+
+/// Start the background task that publishes queued posts.
+pub fn start_autopublish_queue(http: Arc<serenity::Http>) {
+    tokio::spawn(async move {
+        let mut interval = interval(TokioDuration::from_secs(60)); // Check every minute
+
+        loop {
+            interval.tick().await;
+
+            let now = Utc::now();
+            let due: Vec<(u64, QueuedPost)> = {
+                let mut queues = QUEUES.lock();
+                // Channels that were switched off don't publish their backlog
+                let enabled = AUTOPUBLISH.read();
+                queues.retain(|channel_id, _| enabled.channels.contains_key(channel_id));
+                queues
+                    .iter_mut()
+                    .flat_map(|(channel_id, queue)| {
+                        queue
+                            .take_due(now)
+                            .into_iter()
+                            .map(|post| (*channel_id, post))
+                    })
+                    .collect()
+            };
+
+            for (channel_id, post) in due {
+                publish_queued(&http, channel_id, &post, now).await;
+            }
+        }
+    });
+}
+
+async fn publish_queued(
+    http: &serenity::Http,
+    channel_id: u64,
+    post: &QueuedPost,
+    now: DateTime<Utc>,
+) {
+    let channel = serenity::ChannelId::new(channel_id);
+    let message_id = serenity::MessageId::new(post.message_id);
+    if let Err(e) = channel.crosspost(http, message_id).await {
+        log::warn!(
+            "Failed to publish queued message {} in channel {}: {}",
+            message_id,
+            channel_id,
+            e
+        );
+        return;
+    }
+    let _ = http
+        .delete_reaction_me(channel, message_id, &serenity::ReactionType::from('⏳'))
+        .await;
+
+    let waited = crate::commands::remind::format_duration(now - post.queued_at);
+    let notice = format!(
+        "📣 Your announcement in <#{}> was published after waiting {}: Discord only allows {} publishes per channel per hour.",
+        channel_id, waited, PUBLISHES_PER_WINDOW
+    );
+    if let Err(e) = serenity::UserId::new(post.author_id)
+        .direct_message(http, serenity::CreateMessage::new().content(notice))
+        .await
+    {
+        log::warn!(
+            "Failed to tell {} about a delayed publish: {}",
+            post.author_id,
+            e
+        );
+    }
+}
+
+/// Publish new posts in announcement channels automatically
+#[poise::command(
+    prefix_command,
+    slash_command,
+    guild_only,
+    required_permissions = "MANAGE_CHANNELS",
+    subcommands("autopublish_on", "autopublish_off", "autopublish_list")
+)]
+pub async fn autopublish(_ctx: Context<'_>) -> Result<(), Error> {
+    Ok(())
+}
+
+/// Auto-publish posts in an announcement channel
+#[poise::command(
+    prefix_command,
+    slash_command,
+    guild_only,
+    rename = "on",
+    required_permissions = "MANAGE_CHANNELS",
+    check = "autopublish_writable"
+)]
+pub async fn autopublish_on(
+    ctx: Context<'_>,
+    #[description = "Announcement channel (default: this one)"] channel: Option<
+        serenity::GuildChannel,
+    >,
+    #[description = "Only publish posts by members with this role"] role: Option<serenity::Role>,
+    #[description = "Only publish posts by this member"] user: Option<serenity::User>,
+) -> Result<(), Error> {
+    log::info!("Autopublish on command called by {}", ctx.author().name);
+
+    let Some(guild_id) = ctx.guild_id() else {
+        return Ok(());
+    };
+    let channel = match channel {
+        Some(channel) => channel,
+        None => match ctx.guild_channel().await {
+            Some(channel) => channel,
+            None => return Ok(()),
+        },
+    };
+    if channel.guild_id != guild_id {
+        ctx.say("❌ That channel isn't in this server!").await?;
+        return Ok(());
+    }
+    if channel.kind != serenity::ChannelType::News {
+        ctx.say(format!(
+            "❌ <#{}> isn't an announcement channel, so there's nothing to publish. Make it one under Edit Channel → Overview first.",
+            channel.id
+        ))
+        .await?;
+        return Ok(());
+    }
+
+    let rule = {
+        let mut data = AUTOPUBLISH.write();
+        let rule = data.channels.entry(channel.id.get()).or_default();
+        rule.guild_id = guild_id.get();
+        if let Some(role) = &role {
+            if !rule.roles.contains(&role.id.get()) {
+                rule.roles.push(role.id.get());
+            }
+        }
+        if let Some(user) = &user {
+            if !rule.users.contains(&user.id.get()) {
+                rule.users.push(user.id.get());
+            }
+        }
+        let rule = rule.clone();
+        save_autopublish(&data);
+        rule
+    };
+
+    ctx.send(
+        poise::CreateReply::default()
+            .content(format!(
+                "✅ New posts in <#{}> {} will be published automatically.",
+                channel.id,
+                describe_authors(&rule)
+            ))
+            .allowed_mentions(serenity::CreateAllowedMentions::new()),
+    )
+    .await?;
+    Ok(())
+}
+
+/// Stop auto-publishing in a channel
+#[poise::command(
+    prefix_command,
+    slash_command,
+    guild_only,
+    rename = "off",
+    required_permissions = "MANAGE_CHANNELS",
+    check = "autopublish_writable"
+)]
+pub async fn autopublish_off(
+    ctx: Context<'_>,
+    #[description = "Announcement channel (default: this one)"] channel: Option<
+        serenity::GuildChannel,
+    >,
+) -> Result<(), Error> {
+    log::info!("Autopublish off command called by {}", ctx.author().name);
+
+    let Some(guild_id) = ctx.guild_id() else {
+        return Ok(());
+    };
+    if channel
+        .as_ref()
+        .is_some_and(|channel| channel.guild_id != guild_id)
+    {
+        ctx.say("❌ That channel isn't in this server!").await?;
+        return Ok(());
+    }
+    let channel_id = channel.map_or(ctx.channel_id(), |channel| channel.id);
+    let removed = {
+        let mut data = AUTOPUBLISH.write();
+        // Only this server's rules, whatever channel ID was given
+        let removed = data
+            .channels
+            .get(&channel_id.get())
+            .is_some_and(|rule| rule.guild_id == guild_id.get())
+            && data.channels.remove(&channel_id.get()).is_some();
+        if removed {
+            save_autopublish(&data);
+        }
+        removed
+    };
+
+    if removed {
+        ctx.say(format!(
+            "✅ Posts in <#{}> are no longer published automatically.",
+            channel_id
+        ))
+        .await?;
+    } else {
+        ctx.say(format!("❌ <#{}> isn't auto-publishing.", channel_id))
+            .await?;
+    }
+    Ok(())
+}
+
+/// Show which channels auto-publish
+#[poise::command(
+    prefix_command,
+    slash_command,
+    guild_only,
+    rename = "list",
+    required_permissions = "MANAGE_CHANNELS"
+)]
+pub async fn autopublish_list(ctx: Context<'_>) -> Result<(), Error> {
+    let Some(guild_id) = ctx.guild_id() else {
+        return Ok(());
+    };
+    let mut lines: Vec<String> = AUTOPUBLISH
+        .read()
+        .channels
+        .iter()
+        .filter(|(_, rule)| rule.guild_id == guild_id.get())
+        .map(|(channel_id, rule)| format!("• <#{}> {}", channel_id, describe_authors(rule)))
+        .collect();
+    lines.sort();
+
+    if lines.is_empty() {
+        ctx.say("📭 No channels here auto-publish. Turn it on with `-autopublish on`.")
+            .await?;
+    } else {
+        ctx.send(
+            poise::CreateReply::default()
+                .content(format!("📣 **Auto-publishing:**\n{}", lines.join("\n")))
+                .allowed_mentions(serenity::CreateAllowedMentions::new()),
+        )
+        .await?;
+    }
+    Ok(())
+}
+
+/// Whose posts a rule publishes, for the command replies.
+fn describe_authors(rule: &AutoPublishRule) -> String {
+    let mut authors: Vec<String> = rule.roles.iter().map(|id| format!("<@&{}>", id)).collect();
+    authors.extend(rule.users.iter().map(|id| format!("<@{}>", id)));
+    if authors.is_empty() {
+        "by anyone with Manage Channels".to_string()
+    } else {
+        format!("by {}", authors.join(", "))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_validate_autopublish() {
+        let mut data = AutoPublishData::default();
+        let rule = |roles: Vec<u64>| AutoPublishRule {
+            guild_id: 1,
+            roles,
+            users: vec![7],
+        };
+        data.channels.insert(10, rule(vec![20, 21]));
+        data.channels.insert(11, rule(Vec::new()));
+        let live = LiveIds::default().with_guild(1, &[10], &[20]);
+
+        let problems = validate_autopublish(&mut data, &live);
+        assert_eq!(problems.len(), 2);
+        assert_eq!(data.channels.len(), 1);
+        assert_eq!(data.channels[&10].roles, vec![20]);
+    }
+
+    #[test]
+    fn test_channel_queue() {
+        let start = Utc::now();
+        let mut queue = ChannelQueue::default();
+        for _ in 0..PUBLISHES_PER_WINDOW {
+            assert!(queue.try_take(start));
+        }
+        assert!(!queue.try_take(start + Duration::minutes(59)));
+
+        let post = |id| QueuedPost {
+            message_id: id,
+            author_id: 1,
+            queued_at: start,
+        };
+        queue.waiting.extend([post(1), post(2)]);
+        assert!(queue.take_due(start + Duration::minutes(30)).is_empty());
+        // The allowance comes back an hour after each publish, oldest first
+        assert_eq!(
+            queue.take_due(start + Duration::minutes(60)),
+            vec![post(1), post(2)]
+        );
+        assert!(queue.waiting.is_empty());
+        assert_eq!(queue.published.len(), 2);
+    }
+
+    #[test]
+    fn test_rule_allows() {
+        let mut rule = AutoPublishRule::default();
+        assert_eq!(rule.allows(1, &[10]), None);
+        rule.roles.push(10);
+        rule.users.push(2);
+        assert_eq!(rule.allows(1, &[10, 11]), Some(true));
+        assert_eq!(rule.allows(2, &[]), Some(true));
+        assert_eq!(rule.allows(3, &[11]), Some(false));
+        assert_eq!(describe_authors(&rule), "by <@&10>, <@2>");
+        assert_eq!(
+            describe_authors(&AutoPublishRule::default()),
+            "by anyone with Manage Channels"
+        );
+    }
+}
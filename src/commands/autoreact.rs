@@ -0,0 +1,575 @@
+use crate::config_check::{check_store, ConfigProblem, LiveIds};
+use crate::storage;
+use crate::utils::check_store_writable;
+use crate::{Context, Error};
+use parking_lot::RwLock;
+use poise::serenity_prelude as serenity;
+use regex::{Regex, RegexBuilder};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+const AUTOREACT_FILE: &str = "autoreact.json";
+
+/// Patterns longer than this are rejected at add time.
+const MAX_PATTERN_LENGTH: usize = 1000;
+
+/// Cap on the compiled size of a regex, on top of the regex crate's
+/// linear-time matching guarantee.
+const REGEX_SIZE_LIMIT: usize = 1 << 20;
+
+const MAX_RULES_PER_GUILD: usize = 25;
+
+/// No message gets more than this many auto-reactions.
+const MAX_REACTIONS_PER_MESSAGE: usize = 3;
+
+#[derive(Serialize, Deserialize, Clone)]
+struct AutoReactRule {
+    id: u64,
+    pattern: String,
+    emoji: String,
+    is_regex: bool,
+    created_by: u64,
+    /// Compiled form of `pattern` for regex rules, built on load/add.
+    #[serde(skip)]
+    compiled: Option<Regex>,
+}
+
+impl AutoReactRule {
+    fn matches(&self, content: &str) -> bool {
+        match &self.compiled {
+            Some(regex) => regex.is_match(content),
+            None if self.is_regex => false,
+            None => content
+                .to_lowercase()
+                .contains(&self.pattern.to_lowercase()),
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+struct AutoReactData {
+    guilds: HashMap<u64, Vec<AutoReactRule>>,
+    next_id: u64,
+}
+
+impl Default for AutoReactData {
+    fn default() -> Self {
+        Self {
+            guilds: HashMap::new(),
+            next_id: 1,
+        }
+    }
+}
+
+lazy_static::lazy_static! {
+    static ref AUTOREACT: RwLock<AutoReactData> = RwLock::new(load_rules());
+}
+
+fn load_rules() -> AutoReactData {
+    compile_rules(storage::load(AUTOREACT_FILE))
+}
+
+/// Re-read the rules from disk.
+pub fn reload_rules() -> Result<(), Error> {
+    *AUTOREACT.write() = compile_rules(storage::reload(AUTOREACT_FILE)?);
+    Ok(())
+}
+
+/// Whether a guild has any auto-react rules.
+pub fn has_rules(guild_id: u64) -> bool {
+    AUTOREACT
+        .read()
+        .guilds
+        .get(&guild_id)
+        .is_some_and(|rules| !rules.is_empty())
+}
+
+/// Compile the regex rules, which aren't stored compiled.
+fn compile_rules(mut data: AutoReactData) -> AutoReactData {
+    for rule in data.guilds.values_mut().flatten() {
+        if rule.is_regex {
+            match compile_pattern(&rule.pattern) {
+                Ok(regex) => rule.compiled = Some(regex),
+                Err(e) => log::warn!("Skipping invalid auto-react rule {}: {}", rule.id, e),
+            }
+        }
+    }
+    data
+}
+
+fn save_rules(data: &AutoReactData) -> Result<(), Error> {
+    storage::save(AUTOREACT_FILE, data)
+}
+
+/// Drop an orphaned guild's auto-react rules.
+pub fn purge_guild(guild_id: u64) {
+    let mut data = AUTOREACT.write();
+    if data.guilds.remove(&guild_id).is_some() {
+        if let Err(e) = save_rules(&data) {
+            log::error!("Failed to save auto-react rules: {}", e);
+        }
+    }
+}
+
+/// Drop rules that can never react: a blank pattern or emoji, or a regex
+/// that doesn't compile.
+fn validate_rules(data: &mut AutoReactData) -> Vec<ConfigProblem> {
+    let mut problems = Vec::new();
+    for (&guild_id, rules) in &mut data.guilds {
+        rules.retain(|rule| {
+            let problem = if rule.pattern.trim().is_empty() {
+                "the pattern is blank".to_string()
+            } else if rule.emoji.trim().is_empty() {
+                "the emoji is blank".to_string()
+            } else if rule.is_regex && rule.compiled.is_none() {
+                match compile_pattern(&rule.pattern) {
+                    Err(e) => format!("the regex doesn't compile ({})", e),
+                    Ok(_) => return true,
+                }
+            } else {
+                return true;
+            };
+            problems.push(ConfigProblem::invalid(
+                guild_id,
+                format!("rule {}", rule.id),
+                problem,
+                rule,
+            ));
+            false
+        });
+    }
+    data.guilds.retain(|_, rules| !rules.is_empty());
+    problems
+}
+
+/// Check the auto-react rules, see [`crate::config_check`].
+pub fn check_rules(_live: &LiveIds, quarantine: bool) -> Vec<ConfigProblem> {
+    check_store(&AUTOREACT, quarantine, validate_rules, |data| {
+        if let Err(e) = save_rules(data) {
+            log::error!("Failed to save auto-react rules: {}", e);
+        }
+    })
+}
+
+/// Command check for commands that change the auto-react rules.
+async fn autoreact_writable(ctx: Context<'_>) -> Result<bool, Error> {
+    check_store_writable(ctx, &storage::data_path(AUTOREACT_FILE)).await
+}
+
+fn compile_pattern(pattern: &str) -> Result<Regex, String> {
+    if pattern.len() > MAX_PATTERN_LENGTH {
+        return Err(format!(
+            "Pattern is too long (max {} characters)",
+            MAX_PATTERN_LENGTH
+        ));
+    }
+    RegexBuilder::new(pattern)
+        .size_limit(REGEX_SIZE_LIMIT)
+        .build()
+        .map_err(|e| format!("Invalid regex: {}", e))
+}
+
+/// Emojis that rules on a guild want on a message, in rule creation order,
+/// without duplicates and capped at [`MAX_REACTIONS_PER_MESSAGE`].
+fn matching_emojis(rules: &[AutoReactRule], content: &str) -> Vec<String> {
+    let mut emojis: Vec<String> = Vec::new();
+    for rule in rules {
+        if emojis.len() >= MAX_REACTIONS_PER_MESSAGE {
+            break;
+        }
+        if rule.matches(content) && !emojis.contains(&rule.emoji) {
+            emojis.push(rule.emoji.clone());
+        }
+    }
+    emojis
+}
+
+/// Parse an emoji argument into a reaction: a custom emoji, or a single
+/// unicode emoji (sequences joined with ZWJs, flags and keycaps included).
+pub(crate) fn parse_emoji(emoji: &str) -> Option<serenity::ReactionType> {
+    let emoji = emoji.trim();
+    let reaction = serenity::ReactionType::try_from(emoji).ok()?;
+    match &reaction {
+        serenity::ReactionType::Unicode(s) if !is_unicode_emoji(s) => None,
+        _ => Some(reaction),
+    }
+}
+
+/// Whether `text` is one unicode emoji. Checked by code point ranges, so
+/// it's loose about which sequences exist, but never lets words through.
+fn is_unicode_emoji(text: &str) -> bool {
+    let is_pictograph = |c: char| {
+        matches!(c as u32,
+            0x1F000..=0x1FAFF
+            | 0x2600..=0x27BF
+            | 0x2300..=0x23FF
+            | 0x2B00..=0x2BFF
+            | 0x2190..=0x21FF
+            | 0x25A0..=0x25FF
+            | 0x2100..=0x214F
+            | 0x3030 | 0x303D | 0x3297 | 0x3299
+            | 0x00A9 | 0x00AE | 0x203C | 0x2049)
+    };
+    // Joiners, variation selectors and tag characters (subdivision flags)
+    let is_modifier = |c: char| matches!(c as u32, 0x200D | 0xFE0E | 0xFE0F | 0xE0020..=0xE007F);
+    let chars: Vec<char> = text.chars().collect();
+    if chars.is_empty() || chars.len() > 16 {
+        return false;
+    }
+    // Keycaps: 0-9, # or * followed by the combining keycap
+    if let [base, rest @ ..] = chars.as_slice() {
+        if rest.last() == Some(&'\u{20E3}') && (base.is_ascii_digit() || matches!(base, '#' | '*'))
+        {
+            return rest[..rest.len() - 1].iter().all(|c| is_modifier(*c));
+        }
+    }
+    chars.iter().any(|c| is_pictograph(*c))
+        && chars.iter().all(|c| is_pictograph(*c) || is_modifier(*c))
+}
+
+/// React to a new message with every matching auto-reaction for its guild.
+pub async fn apply_auto_reactions(
+    ctx: &serenity::Context,
+    message: &serenity::Message,
+    command_prefix: &str,
+) {
+    if message.author.bot || message.content.starts_with(command_prefix) {
+        return;
+    }
+    let Some(guild_id) = message.guild_id else {
+        return;
+    };
+
+    let emojis = {
+        let data = AUTOREACT.read();
+        match data.guilds.get(&guild_id.get()) {
+            Some(rules) => matching_emojis(rules, &message.content),
+            None => return,
+        }
+    };
+
+    for emoji in emojis {
+        let Ok(reaction) = serenity::ReactionType::try_from(emoji.as_str()) else {
+            continue;
+        };
+        if let Err(e) = message.react(&ctx.http, reaction).await {
+            log::warn!(
+                "Failed to auto-react with {} in guild {}: {}",
+                emoji,
+                guild_id,
+                e
+            );
+        }
+    }
+}
+
+/// Automatically react to messages that match a pattern
+#[poise::command(
+    prefix_command,
+    slash_command,
+    guild_only,
+    required_permissions = "MANAGE_MESSAGES",
+    subcommands("autoreact_add", "autoreact_list", "autoreact_remove")
+)]
+pub async fn autoreact(_ctx: Context<'_>) -> Result<(), Error> {
+    Ok(())
+}
+
+/// Add an auto-reaction rule
+#[poise::command(
+    prefix_command,
+    slash_command,
+    guild_only,
+    rename = "add",
+    required_permissions = "MANAGE_MESSAGES",
+    check = "autoreact_writable"
+)]
+pub async fn autoreact_add(
+    ctx: Context<'_>,
+    #[description = "Text to look for (case-insensitive), or a regex if `regex` is set"]
+    pattern: String,
+    #[description = "Emoji to react with"] emoji: String,
+    #[description = "Treat the pattern as a regular expression"] regex: Option<bool>,
+) -> Result<(), Error> {
+    log::info!(
+        "Autoreact add command called by {} with pattern: '{}', emoji: '{}'",
+        ctx.author().name,
+        pattern,
+        emoji
+    );
+
+    let Some(guild_id) = ctx.guild_id() else {
+        return Ok(());
+    };
+    let is_regex = regex.unwrap_or(false);
+
+    if pattern.trim().is_empty() || pattern.len() > MAX_PATTERN_LENGTH {
+        ctx.say(format!(
+            "❌ The pattern must be between 1 and {} characters!",
+            MAX_PATTERN_LENGTH
+        ))
+        .await?;
+        return Ok(());
+    }
+
+    let compiled = if is_regex {
+        match compile_pattern(&pattern) {
+            Ok(regex) => Some(regex),
+            Err(e) => {
+                ctx.say(format!("❌ {}", e)).await?;
+                return Ok(());
+            }
+        }
+    } else {
+        None
+    };
+
+    let Some(reaction) = parse_emoji(&emoji) else {
+        ctx.say("❌ That doesn't look like an emoji! Use a standard emoji or one of this server's custom emojis.")
+            .await?;
+        return Ok(());
+    };
+
+    let rule_id = {
+        let mut data = AUTOREACT.write();
+        let next_id = data.next_id;
+        let rules = data.guilds.entry(guild_id.get()).or_default();
+        if rules.len() >= MAX_RULES_PER_GUILD {
+            None
+        } else {
+            rules.push(AutoReactRule {
+                id: next_id,
+                pattern: pattern.clone(),
+                emoji: reaction.to_string(),
+                is_regex,
+                created_by: ctx.author().id.get(),
+                compiled,
+            });
+            data.next_id += 1;
+            if let Err(e) = save_rules(&data) {
+                log::error!("Failed to save auto-react rules: {}", e);
+            }
+            Some(next_id)
+        }
+    };
+
+    match rule_id {
+        Some(id) => {
+            ctx.say(format!(
+                "✅ Added auto-reaction **#{}**: {} on messages matching {} `{}`",
+                id,
+                reaction,
+                if is_regex { "regex" } else { "text" },
+                pattern
+            ))
+            .await?;
+        }
+        None => {
+            ctx.say(format!(
+                "❌ This server already has {} auto-reaction rules. Remove one first!",
+                MAX_RULES_PER_GUILD
+            ))
+            .await?;
+        }
+    }
+
+    Ok(())
+}
+
+/// List this server's auto-reaction rules
+#[poise::command(
+    prefix_command,
+    slash_command,
+    guild_only,
+    rename = "list",
+    required_permissions = "MANAGE_MESSAGES"
+)]
+pub async fn autoreact_list(ctx: Context<'_>) -> Result<(), Error> {
+    log::info!("Autoreact list command called by {}", ctx.author().name);
+
+    let Some(guild_id) = ctx.guild_id() else {
+        return Ok(());
+    };
+
+    let description = {
+        let data = AUTOREACT.read();
+        data.guilds
+            .get(&guild_id.get())
+            .filter(|rules| !rules.is_empty())
+            .map(|rules| {
+                rules
+                    .iter()
+                    .map(|rule| {
+                        format!(
+                            "**#{}** {} — {} `{}`",
+                            rule.id,
+                            rule.emoji,
+                            if rule.is_regex { "regex" } else { "text" },
+                            rule.pattern
+                        )
+                    })
+                    .collect::<Vec<_>>()
+                    .join("\n")
+            })
+    };
+
+    let Some(description) = description else {
+        ctx.say("📭 This server has no auto-reaction rules!")
+            .await?;
+        return Ok(());
+    };
+
+    let embed = serenity::CreateEmbed::new()
+        .title("🎉 Auto-Reactions")
+        .description(description)
+        .color(0x5865F2)
+        .footer(serenity::CreateEmbedFooter::new(format!(
+            "Rules apply in order, up to {} reactions per message",
+            MAX_REACTIONS_PER_MESSAGE
+        )));
+
+    ctx.send(poise::CreateReply::default().embed(embed)).await?;
+    Ok(())
+}
+
+/// Remove an auto-reaction rule by ID
+#[poise::command(
+    prefix_command,
+    slash_command,
+    guild_only,
+    rename = "remove",
+    required_permissions = "MANAGE_MESSAGES",
+    check = "autoreact_writable"
+)]
+pub async fn autoreact_remove(
+    ctx: Context<'_>,
+    #[description = "Rule ID to remove"] id: u64,
+) -> Result<(), Error> {
+    log::info!(
+        "Autoreact remove command called by {} for ID: {}",
+        ctx.author().name,
+        id
+    );
+
+    let Some(guild_id) = ctx.guild_id() else {
+        return Ok(());
+    };
+
+    let removed = {
+        let mut data = AUTOREACT.write();
+        let removed = data.guilds.get_mut(&guild_id.get()).and_then(|rules| {
+            let index = rules.iter().position(|rule| rule.id == id)?;
+            Some(rules.remove(index))
+        });
+        if removed.is_some() {
+            if let Err(e) = save_rules(&data) {
+                log::error!("Failed to save auto-react rules: {}", e);
+            }
+        }
+        removed
+    };
+
+    match removed {
+        Some(rule) => {
+            ctx.say(format!(
+                "🗑️ Removed auto-reaction **#{}** ({} on `{}`)",
+                rule.id, rule.emoji, rule.pattern
+            ))
+            .await?;
+        }
+        None => {
+            ctx.say("❌ No auto-reaction rule with that ID in this server!")
+                .await?;
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rule(id: u64, pattern: &str, emoji: &str, is_regex: bool) -> AutoReactRule {
+        AutoReactRule {
+            id,
+            pattern: pattern.to_string(),
+            emoji: emoji.to_string(),
+            is_regex,
+            created_by: 1,
+            compiled: is_regex.then(|| compile_pattern(pattern).unwrap()),
+        }
+    }
+
+    #[test]
+    fn test_validate_rules() {
+        let mut data = AutoReactData::default();
+        let mut broken = rule(3, "(unclosed", "🎉", false);
+        broken.is_regex = true;
+        data.guilds.insert(
+            1,
+            vec![
+                rule(1, "friday", "🎉", false),
+                rule(2, "  ", "🎉", false),
+                broken,
+            ],
+        );
+        data.guilds.insert(2, vec![rule(4, "hi", "", false)]);
+
+        let mut problems = validate_rules(&mut data);
+        problems.sort_by(|a, b| a.field.cmp(&b.field));
+        let fields: Vec<&str> = problems.iter().map(|p| p.field.as_str()).collect();
+        assert_eq!(fields, ["rule 2", "rule 3", "rule 4"]);
+        assert_eq!(data.guilds.len(), 1);
+        assert_eq!(data.guilds[&1].len(), 1);
+    }
+
+    #[test]
+    fn test_substring_match_is_case_insensitive() {
+        let rules = [rule(1, "friday", "🎉", false)];
+        assert_eq!(matching_emojis(&rules, "Happy FRIDAY everyone"), vec!["🎉"]);
+        assert!(matching_emojis(&rules, "Happy Monday").is_empty());
+    }
+
+    #[test]
+    fn test_multiple_matches_follow_rule_order_and_dedupe() {
+        let rules = [
+            rule(1, "friday", "🎉", false),
+            rule(2, r"\bfri", "🥳", true),
+            rule(3, "day", "🎉", false),
+            rule(4, "friday", "🍕", false),
+            rule(5, "friday", "🍻", false),
+        ];
+
+        // Rule 3 repeats rule 1's emoji, and rule 5 is past the cap
+        assert_eq!(
+            matching_emojis(&rules, "it's friday!"),
+            vec!["🎉", "🥳", "🍕"]
+        );
+    }
+
+    #[test]
+    fn test_compile_pattern_limits() {
+        assert!(compile_pattern("fri(day)?").is_ok());
+        assert!(compile_pattern("(unclosed").is_err());
+        assert!(compile_pattern(&"a".repeat(MAX_PATTERN_LENGTH + 1)).is_err());
+        assert!(compile_pattern(r"\w{1000}{1000}").is_err());
+    }
+
+    #[test]
+    fn test_parse_emoji() {
+        assert!(parse_emoji("🎉").is_some());
+        assert!(parse_emoji("<:party:600404340292059257>").is_some());
+        assert!(parse_emoji("party").is_none());
+        assert!(parse_emoji("").is_none());
+        assert!(parse_emoji("👍🏽").is_some());
+        assert!(parse_emoji("👨‍👩‍👧").is_some());
+        assert!(parse_emoji("🇩🇰").is_some());
+        assert!(parse_emoji("1️⃣").is_some());
+        assert!(parse_emoji("❤️").is_some());
+        assert!(parse_emoji("é").is_none());
+        assert!(parse_emoji("日本").is_none());
+        assert!(parse_emoji("🎉 party").is_none());
+        assert!(parse_emoji("\u{FE0F}").is_none());
+    }
+}
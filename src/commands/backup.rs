@@ -0,0 +1,198 @@
+use crate::backups::{
+    backups_configured, download_backup, list_backups, restore_backup, run_backup,
+    snapshot_before_restore,
+};
+use crate::lifecycle::{write_shutdown_marker, RestartReason};
+use crate::storage::bundle_contents;
+use crate::utils::confirm::confirm_with_buttons;
+use crate::utils::is_protected_user;
+use crate::{Context, Error};
+use std::time::Duration;
+
+/// How long each restore confirmation waits for an answer.
+const RESTORE_CONFIRM_SECS: u64 = 30;
+
+/// Back up the bot's data, or restore it from a backup (bot owners only)
+#[poise::command(
+    prefix_command,
+    slash_command,
+    subcommands("backup_now", "backup_list", "backup_restore")
+)]
+pub async fn backup(_ctx: Context<'_>) -> Result<(), Error> {
+    Ok(())
+}
+
+/// Make a backup right away
+#[poise::command(prefix_command, slash_command, rename = "now")]
+pub async fn backup_now(ctx: Context<'_>) -> Result<(), Error> {
+    log::info!("Backup now command called by {}", ctx.author().name);
+
+    if !check_backup_access(ctx).await? {
+        return Ok(());
+    }
+
+    ctx.defer().await?;
+    match run_backup(&ctx.serenity_context().http).await {
+        Ok(backup) => {
+            ctx.say(format!(
+                "✅ Backup `{}` ({} KB) saved to the {}.",
+                backup.id,
+                backup.size.unwrap_or_default() / 1024,
+                backup.location
+            ))
+            .await?;
+        }
+        Err(e) => {
+            ctx.say(format!("❌ Backup failed: {e}")).await?;
+        }
+    }
+    Ok(())
+}
+
+/// List the stored backups
+#[poise::command(prefix_command, slash_command, rename = "list")]
+pub async fn backup_list(ctx: Context<'_>) -> Result<(), Error> {
+    log::info!("Backup list command called by {}", ctx.author().name);
+
+    if !check_backup_access(ctx).await? {
+        return Ok(());
+    }
+
+    ctx.defer().await?;
+    let backups = match list_backups(&ctx.serenity_context().http).await {
+        Ok(backups) => backups,
+        Err(e) => {
+            ctx.say(format!("❌ Couldn't list backups: {e}")).await?;
+            return Ok(());
+        }
+    };
+    if backups.is_empty() {
+        ctx.say("📭 No backups yet. Make one with `-backup now`.")
+            .await?;
+        return Ok(());
+    }
+
+    let lines: Vec<String> = backups
+        .iter()
+        .map(|backup| match backup.size {
+            Some(size) => format!(
+                "• `{}` ({} KB, {})",
+                backup.id,
+                size / 1024,
+                backup.location
+            ),
+            None => format!("• `{}` ({})", backup.id, backup.location),
+        })
+        .collect();
+    ctx.say(format!(
+        "🗄️ **Backups** (newest first)\n{}",
+        lines.join("\n")
+    ))
+    .await?;
+    Ok(())
+}
+
+/// Replace all bot data with a backup and restart
+#[poise::command(prefix_command, slash_command, rename = "restore")]
+pub async fn backup_restore(
+    ctx: Context<'_>,
+    #[description = "Backup ID from /backup list"] id: String,
+) -> Result<(), Error> {
+    log::info!(
+        "Backup restore command called by {} for {}",
+        ctx.author().name,
+        id
+    );
+
+    if !check_backup_access(ctx).await? {
+        return Ok(());
+    }
+
+    ctx.defer().await?;
+    let bundle = match download_backup(&ctx.serenity_context().http, id.trim()).await {
+        Ok(bundle) => bundle,
+        Err(e) => {
+            ctx.say(format!("❌ Couldn't fetch the backup: {e}"))
+                .await?;
+            return Ok(());
+        }
+    };
+    let files = match bundle_contents(&bundle) {
+        Ok(files) => files,
+        Err(e) => {
+            ctx.say(format!("❌ That backup can't be read: {e}"))
+                .await?;
+            return Ok(());
+        }
+    };
+
+    let timeout = Duration::from_secs(RESTORE_CONFIRM_SECS);
+    let first = format!(
+        "⚠️ Restore `{}`? It replaces these {} files with the backed up versions:\n{}",
+        id.trim(),
+        files.len(),
+        files
+            .iter()
+            .map(|name| format!("`{}`", name))
+            .collect::<Vec<_>>()
+            .join(", ")
+    );
+    if !confirm_with_buttons(ctx, first, "Continue", "nothing restored.", timeout).await? {
+        return Ok(());
+    }
+    let second = "⚠️ Are you sure? Anything changed since the backup is lost (a snapshot is saved \
+                  first) and the bot restarts right after."
+        .to_string();
+    if !confirm_with_buttons(ctx, second, "Restore", "nothing restored.", timeout).await? {
+        return Ok(());
+    }
+
+    let snapshot = match snapshot_before_restore() {
+        Ok(path) => path,
+        Err(e) => {
+            ctx.say(format!(
+                "❌ Couldn't save a snapshot first, so nothing was restored: {e}"
+            ))
+            .await?;
+            return Ok(());
+        }
+    };
+    log::info!("Saved pre-restore snapshot to {}", snapshot.display());
+
+    ctx.say(format!(
+        "♻️ Restoring `{}` (snapshot of the current data: `{}`). Restarting...",
+        id.trim(),
+        snapshot.display()
+    ))
+    .await?;
+
+    if let Err(e) = restore_backup(&bundle, &snapshot) {
+        log::error!("Restore of {} failed: {}", id, e);
+        ctx.say(format!("❌ Restore failed partway: {e}.")).await?;
+        return Ok(());
+    }
+    if let Err(e) = write_shutdown_marker(RestartReason::Restore, Some(ctx.author().name.clone())) {
+        log::error!("Failed to write shutdown marker: {}", e);
+    }
+
+    // Exit without flushing: the in-memory stores are older than what was
+    // just restored and would overwrite it
+    log::info!("Backup {} restored, exiting with code 42 to restart", id);
+    std::process::exit(42);
+}
+
+/// Only bot owners can back up or restore, and only once a destination is
+/// set up.
+async fn check_backup_access(ctx: Context<'_>) -> Result<bool, Error> {
+    if !is_protected_user(&ctx.author().name) {
+        ctx.say("❌ You don't have permission to use this command!")
+            .await?;
+        return Ok(false);
+    }
+    if !backups_configured() {
+        ctx.say("❌ No backup destination is set up. Set `BACKUP_CHANNEL_ID` or the `BACKUP_S3_*` variables.")
+            .await?;
+        return Ok(false);
+    }
+    Ok(true)
+}
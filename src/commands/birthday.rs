@@ -0,0 +1,754 @@
+//! Member birthdays, kept per server. Every day at [`ANNOUNCE_HOUR`] in the
+//! server's timezone (`-streak timezone`, UTC by default) the bot wishes
+//! that day's birthdays a happy birthday in the server's birthday channel,
+//! and hands out the birthday role until the same time the next day.
+//! February 29 birthdays are celebrated on February 28 outside leap years.
+
+use crate::commands::maintenance::Maintenance;
+use crate::commands::streak::guild_timezone;
+use crate::config_check::{check_store, ConfigProblem, LiveIds};
+use crate::storage;
+use crate::utils::check_store_writable;
+use crate::utils::permissions::{check_assignable_role, check_channel_perms};
+use crate::utils::schedule::local_time_on;
+use crate::{Context, Error};
+use chrono::{DateTime, Datelike, NaiveDate, Utc};
+use chrono_tz::Tz;
+use parking_lot::RwLock;
+use poise::serenity_prelude as serenity;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::Arc;
+use tokio::time::{interval, Duration as TokioDuration};
+
+const BIRTHDAYS_FILE: &str = "birthdays.json";
+
+/// Local hour birthdays are announced and the birthday role changes hands.
+const ANNOUNCE_HOUR: u8 = 9;
+
+/// How many birthdays `-birthday list` shows.
+const UPCOMING_SHOWN: usize = 10;
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+struct Birthday {
+    month: u32,
+    day: u32,
+}
+
+impl Birthday {
+    /// Parse `MM-DD`, e.g. `03-14`. February 29 is allowed.
+    fn parse(text: &str) -> Option<Self> {
+        let (month, day) = text.trim().split_once(['-', '/'])?;
+        let birthday = Birthday {
+            month: month.parse().ok()?,
+            day: day.parse().ok()?,
+        };
+        // 2000 was a leap year, so every real date exists in it
+        NaiveDate::from_ymd_opt(2000, birthday.month, birthday.day)?;
+        Some(birthday)
+    }
+
+    /// The day it's celebrated on in `year`.
+    fn in_year(self, year: i32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(year, self.month, self.day)
+            .or_else(|| NaiveDate::from_ymd_opt(year, 2, 28))
+            .expect("February 28 exists every year")
+    }
+
+    /// The next day it's celebrated on, `today` included.
+    fn next_from(self, today: NaiveDate) -> NaiveDate {
+        let this_year = self.in_year(today.year());
+        if this_year >= today {
+            this_year
+        } else {
+            self.in_year(today.year() + 1)
+        }
+    }
+
+    fn describe(self) -> String {
+        self.in_year(2000).format("%B %-d").to_string()
+    }
+}
+
+#[derive(Serialize, Deserialize, Default, Debug, Clone)]
+#[serde(default)]
+struct GuildBirthdays {
+    /// Where birthdays are announced; `None` keeps them quiet.
+    channel: Option<u64>,
+    /// Given to members on their birthday.
+    role: Option<u64>,
+    users: HashMap<u64, Birthday>,
+    /// Local day birthdays were last announced.
+    last_run: Option<NaiveDate>,
+    /// Members given the birthday role on `last_run`, who lose it next run.
+    role_holders: Vec<u64>,
+}
+
+impl GuildBirthdays {
+    /// Members whose birthday is celebrated on `day`.
+    fn celebrating(&self, day: NaiveDate) -> Vec<u64> {
+        let mut users: Vec<u64> = self
+            .users
+            .iter()
+            .filter(|(_, birthday)| birthday.in_year(day.year()) == day)
+            .map(|(user_id, _)| *user_id)
+            .collect();
+        users.sort_unstable();
+        users
+    }
+
+    /// Members by their next birthday from `today`, soonest first.
+    fn upcoming(&self, today: NaiveDate) -> Vec<(u64, NaiveDate)> {
+        let mut upcoming: Vec<(u64, NaiveDate)> = self
+            .users
+            .iter()
+            .map(|(user_id, birthday)| (*user_id, birthday.next_from(today)))
+            .collect();
+        upcoming.sort_by_key(|(user_id, next)| (*next, *user_id));
+        upcoming
+    }
+}
+
+#[derive(Serialize, Deserialize, Default, Clone)]
+struct BirthdayData {
+    guilds: HashMap<u64, GuildBirthdays>,
+}
+
+lazy_static::lazy_static! {
+    static ref BIRTHDAYS: RwLock<BirthdayData> = RwLock::new(storage::load(BIRTHDAYS_FILE));
+}
+
+/// Re-read the birthdays from disk.
+pub fn reload_birthdays() -> Result<(), Error> {
+    *BIRTHDAYS.write() = storage::reload(BIRTHDAYS_FILE)?;
+    Ok(())
+}
+
+/// Where a guild's birthdays are announced, if anywhere.
+pub fn announce_channel(guild_id: u64) -> Option<u64> {
+    BIRTHDAYS
+        .read()
+        .guilds
+        .get(&guild_id)
+        .and_then(|guild| guild.channel)
+}
+
+fn save_birthdays(data: &BirthdayData) {
+    if let Err(e) = storage::save(BIRTHDAYS_FILE, data) {
+        log::error!("Failed to save birthdays: {}", e);
+    }
+}
+
+/// Drop an orphaned guild's birthdays and announcement settings.
+pub fn purge_guild(guild_id: u64) {
+    let mut data = BIRTHDAYS.write();
+    if data.guilds.remove(&guild_id).is_some() {
+        save_birthdays(&data);
+    }
+}
+
+/// Unset a deleted channel or role and drop birthdays that aren't dates.
+/// Without the role nobody holds it anymore, so its holders go too.
+fn validate_birthdays(data: &mut BirthdayData, live: &LiveIds) -> Vec<ConfigProblem> {
+    let mut problems = Vec::new();
+    for (&guild_id, guild) in &mut data.guilds {
+        if let Some(channel_id) = guild
+            .channel
+            .take_if(|&mut channel_id| live.channel_gone(guild_id, channel_id))
+        {
+            problems.push(ConfigProblem::deleted_channel(
+                guild_id, "channel", channel_id,
+            ));
+        }
+        if let Some(role_id) = guild
+            .role
+            .take_if(|&mut role_id| live.role_gone(guild_id, role_id))
+        {
+            problems.push(ConfigProblem::deleted_role(guild_id, "role", role_id));
+            guild.role_holders.clear();
+        }
+        guild.users.retain(|user_id, birthday| {
+            let valid = NaiveDate::from_ymd_opt(2000, birthday.month, birthday.day).is_some();
+            if !valid {
+                problems.push(ConfigProblem::invalid(
+                    guild_id,
+                    "users",
+                    format!(
+                        "{}-{} isn't a date (user {})",
+                        birthday.month, birthday.day, user_id
+                    ),
+                    (user_id, *birthday),
+                ));
+            }
+            valid
+        });
+    }
+    problems
+}
+
+/// Check the birthday settings, see [`crate::config_check`].
+pub fn check_birthdays(live: &LiveIds, quarantine: bool) -> Vec<ConfigProblem> {
+    check_store(
+        &BIRTHDAYS,
+        quarantine,
+        |data| validate_birthdays(data, live),
+        save_birthdays,
+    )
+}
+
+/// Command check for commands that change birthdays or their settings.
+async fn birthday_writable(ctx: Context<'_>) -> Result<bool, Error> {
+    check_store_writable(ctx, &storage::data_path(BIRTHDAYS_FILE)).await
+}
+
+/// One day's work for a guild, worked out under the lock and carried out
+/// after it's released.
+#[derive(Debug, PartialEq)]
+struct BirthdayRun {
+    guild_id: u64,
+    channel: Option<u64>,
+    role: Option<u64>,
+    celebrating: Vec<u64>,
+    /// Yesterday's birthday role holders.
+    expired: Vec<u64>,
+}
+
+/// The guild's run for `now`, if it's past the announce time and today
+/// hasn't been done yet. Marks today as done.
+fn take_due_run(
+    guild_id: u64,
+    guild: &mut GuildBirthdays,
+    now: &DateTime<Tz>,
+) -> Option<BirthdayRun> {
+    let today = now.date_naive();
+    if guild.last_run == Some(today)
+        || local_time_on(&now.timezone(), today, ANNOUNCE_HOUR, 0).is_none_or(|at| at > *now)
+    {
+        return None;
+    }
+    guild.last_run = Some(today);
+    let celebrating = guild.celebrating(today);
+    let expired = std::mem::take(&mut guild.role_holders);
+    if guild.role.is_some() {
+        guild.role_holders = celebrating.clone();
+    }
+    Some(BirthdayRun {
+        guild_id,
+        channel: guild.channel,
+        role: guild.role,
+        celebrating,
+        expired,
+    })
+}
+
+fn celebration_message(users: &[u64]) -> String {
+    let mentions: Vec<String> = users.iter().map(|user| format!("<@{}>", user)).collect();
+    match mentions.split_last() {
+        Some((last, [])) => format!("🎂 Happy birthday, {}! 🎉", last),
+        Some((last, rest)) => format!("🎂 Happy birthday, {} and {}! 🎉", rest.join(", "), last),
+        None => String::new(),
+    }
+}
+
+/// Start the background task that announces birthdays and moves the
+/// birthday role once a day.
+pub fn start_birthday_scheduler(http: Arc<serenity::Http>, maintenance: Arc<Maintenance>) {
+    tokio::spawn(async move {
+        let mut interval = interval(TokioDuration::from_secs(60)); // Check every minute
+
+        loop {
+            interval.tick().await;
+
+            // Skip the run during maintenance and pick up on the next tick
+            if maintenance.is_enabled() {
+                continue;
+            }
+
+            let now = Utc::now();
+            let due: Vec<BirthdayRun> = {
+                let mut data = BIRTHDAYS.write();
+                let due: Vec<BirthdayRun> = data
+                    .guilds
+                    .iter_mut()
+                    .filter_map(|(guild_id, guild)| {
+                        let local_now = now.with_timezone(&guild_timezone(*guild_id));
+                        take_due_run(*guild_id, guild, &local_now)
+                    })
+                    .collect();
+                if !due.is_empty() {
+                    save_birthdays(&data);
+                }
+                due
+            };
+
+            for run in due {
+                run_birthdays(&http, run).await;
+            }
+        }
+    });
+}
+
+async fn run_birthdays(http: &serenity::Http, run: BirthdayRun) {
+    let guild_id = serenity::GuildId::new(run.guild_id);
+    if let Some(role) = run.role.map(serenity::RoleId::new) {
+        for user in run
+            .expired
+            .iter()
+            .filter(|user| !run.celebrating.contains(user))
+        {
+            let user = serenity::UserId::new(*user);
+            if let Err(e) = http
+                .remove_member_role(guild_id, user, role, Some("Birthday is over"))
+                .await
+            {
+                log::warn!("Failed to remove birthday role from {}: {}", user, e);
+            }
+        }
+        for user in &run.celebrating {
+            let user = serenity::UserId::new(*user);
+            if let Err(e) = http
+                .add_member_role(guild_id, user, role, Some("Happy birthday"))
+                .await
+            {
+                log::warn!("Failed to give birthday role to {}: {}", user, e);
+            }
+        }
+    }
+
+    let Some(channel) = run.channel.filter(|_| !run.celebrating.is_empty()) else {
+        return;
+    };
+    let users: Vec<serenity::UserId> = run
+        .celebrating
+        .iter()
+        .map(|user| serenity::UserId::new(*user))
+        .collect();
+    let message = serenity::CreateMessage::new()
+        .content(celebration_message(&run.celebrating))
+        .allowed_mentions(serenity::CreateAllowedMentions::new().users(users));
+    match serenity::ChannelId::new(channel)
+        .send_message(http, message)
+        .await
+    {
+        Ok(_) => log::info!(
+            "Announced {} birthday(s) in guild {}",
+            run.celebrating.len(),
+            run.guild_id
+        ),
+        Err(e) => log::warn!(
+            "Failed to announce birthdays in guild {}: {}",
+            run.guild_id,
+            e
+        ),
+    }
+}
+
+/// Birthdays in this server
+#[poise::command(
+    prefix_command,
+    slash_command,
+    guild_only,
+    subcommands(
+        "birthday_set",
+        "birthday_remove",
+        "birthday_list",
+        "birthday_channel",
+        "birthday_role"
+    )
+)]
+pub async fn birthday(_ctx: Context<'_>) -> Result<(), Error> {
+    Ok(())
+}
+
+/// Set your birthday in this server
+#[poise::command(
+    prefix_command,
+    slash_command,
+    guild_only,
+    rename = "set",
+    check = "birthday_writable"
+)]
+pub async fn birthday_set(
+    ctx: Context<'_>,
+    #[description = "Your birthday as MM-DD, e.g. 03-14"] date: String,
+) -> Result<(), Error> {
+    log::info!(
+        "Birthday set command called by {} with: {}",
+        ctx.author().name,
+        date
+    );
+
+    let Some(guild_id) = ctx.guild_id() else {
+        return Ok(());
+    };
+    let Some(birthday) = Birthday::parse(&date) else {
+        ctx.say("❌ Give your birthday as month and day, like `03-14` for March 14.")
+            .await?;
+        return Ok(());
+    };
+
+    {
+        let mut data = BIRTHDAYS.write();
+        let guild = data.guilds.entry(guild_id.get()).or_default();
+        guild.users.insert(ctx.author().id.get(), birthday);
+        save_birthdays(&data);
+    }
+
+    let mut reply = format!("🎂 Your birthday is set to **{}**.", birthday.describe());
+    if birthday.month == 2 && birthday.day == 29 {
+        reply.push_str(" Outside leap years it's celebrated on February 28.");
+    }
+    ctx.say(reply).await?;
+    Ok(())
+}
+
+/// Forget your birthday in this server
+#[poise::command(
+    prefix_command,
+    slash_command,
+    guild_only,
+    rename = "remove",
+    check = "birthday_writable"
+)]
+pub async fn birthday_remove(ctx: Context<'_>) -> Result<(), Error> {
+    log::info!("Birthday remove command called by {}", ctx.author().name);
+
+    let Some(guild_id) = ctx.guild_id() else {
+        return Ok(());
+    };
+    let removed = {
+        let mut data = BIRTHDAYS.write();
+        let removed = data
+            .guilds
+            .get_mut(&guild_id.get())
+            .and_then(|guild| guild.users.remove(&ctx.author().id.get()))
+            .is_some();
+        if removed {
+            save_birthdays(&data);
+        }
+        removed
+    };
+
+    if removed {
+        ctx.say("✅ Your birthday is forgotten here.").await?;
+    } else {
+        ctx.say("❌ You haven't set a birthday in this server.")
+            .await?;
+    }
+    Ok(())
+}
+
+/// Show the next birthdays in this server
+#[poise::command(prefix_command, slash_command, guild_only, rename = "list")]
+pub async fn birthday_list(ctx: Context<'_>) -> Result<(), Error> {
+    let Some(guild_id) = ctx.guild_id() else {
+        return Ok(());
+    };
+    let today = Utc::now()
+        .with_timezone(&guild_timezone(guild_id.get()))
+        .date_naive();
+    let upcoming = BIRTHDAYS
+        .read()
+        .guilds
+        .get(&guild_id.get())
+        .map(|guild| guild.upcoming(today))
+        .unwrap_or_default();
+
+    if upcoming.is_empty() {
+        ctx.say("📭 No birthdays here yet. Add yours with `-birthday set MM-DD`.")
+            .await?;
+        return Ok(());
+    }
+
+    let lines: Vec<String> = upcoming
+        .iter()
+        .take(UPCOMING_SHOWN)
+        .map(|(user_id, next)| {
+            let when = match (*next - today).num_days() {
+                0 => "today! 🎉".to_string(),
+                1 => "tomorrow".to_string(),
+                days => format!("in {} days", days),
+            };
+            format!("<@{}> • {}, {}", user_id, next.format("%B %-d"), when)
+        })
+        .collect();
+    let embed = serenity::CreateEmbed::new()
+        .title("🎂 Upcoming birthdays")
+        .description(lines.join("\n"))
+        .color(0xEB459E)
+        .footer(serenity::CreateEmbedFooter::new(format!(
+            "{} birthday(s) in this server",
+            upcoming.len()
+        )));
+    ctx.send(poise::CreateReply::default().embed(embed)).await?;
+    Ok(())
+}
+
+/// Announce birthdays in a channel, or stop announcing them
+#[poise::command(
+    prefix_command,
+    slash_command,
+    guild_only,
+    rename = "channel",
+    required_permissions = "MANAGE_GUILD",
+    check = "birthday_writable"
+)]
+pub async fn birthday_channel(
+    ctx: Context<'_>,
+    #[description = "Channel for birthday wishes (leave out to stop)"] channel: Option<
+        serenity::GuildChannel,
+    >,
+) -> Result<(), Error> {
+    log::info!("Birthday channel command called by {}", ctx.author().name);
+
+    let Some(guild_id) = ctx.guild_id() else {
+        return Ok(());
+    };
+    if let Some(channel) = &channel {
+        if channel.guild_id != guild_id {
+            ctx.say("❌ That channel isn't in this server!").await?;
+            return Ok(());
+        }
+        let required = [
+            serenity::Permissions::VIEW_CHANNEL,
+            serenity::Permissions::SEND_MESSAGES,
+        ];
+        if let Err(missing) = check_channel_perms(ctx, channel.id, &required).await {
+            ctx.say(missing.to_string()).await?;
+            return Ok(());
+        }
+    }
+
+    {
+        let mut data = BIRTHDAYS.write();
+        let guild = data.guilds.entry(guild_id.get()).or_default();
+        guild.channel = channel.as_ref().map(|channel| channel.id.get());
+        save_birthdays(&data);
+    }
+
+    match channel {
+        Some(channel) => {
+            ctx.say(format!(
+                "✅ Birthdays will be announced in <#{}> at {:02}:00 ({}).",
+                channel.id,
+                ANNOUNCE_HOUR,
+                guild_timezone(guild_id.get()).name()
+            ))
+            .await?
+        }
+        None => ctx.say("✅ Birthdays won't be announced anymore.").await?,
+    };
+    Ok(())
+}
+
+/// Give members a role on their birthday, or stop giving one
+#[poise::command(
+    prefix_command,
+    slash_command,
+    guild_only,
+    rename = "role",
+    required_permissions = "MANAGE_GUILD",
+    required_bot_permissions = "MANAGE_ROLES",
+    check = "birthday_writable"
+)]
+pub async fn birthday_role(
+    ctx: Context<'_>,
+    #[description = "Role to give for the day (leave out to stop)"] role: Option<serenity::Role>,
+) -> Result<(), Error> {
+    log::info!("Birthday role command called by {}", ctx.author().name);
+
+    let Some(guild_id) = ctx.guild_id() else {
+        return Ok(());
+    };
+    if let Some(role) = &role {
+        if role.guild_id != guild_id || role.id.get() == guild_id.get() || role.managed {
+            ctx.say("❌ That role can't be given out as a birthday role.")
+                .await?;
+            return Ok(());
+        }
+        if let Err(refusal) = check_assignable_role(ctx, role).await {
+            ctx.say(refusal).await?;
+            return Ok(());
+        }
+    }
+
+    // Today's holders lose the old role now, since the next run only takes
+    // back the new one
+    let (old_role, holders) = {
+        let mut data = BIRTHDAYS.write();
+        let guild = data.guilds.entry(guild_id.get()).or_default();
+        let new_role = role.as_ref().map(|role| role.id.get());
+        let replaced = if guild.role != new_role {
+            (guild.role, std::mem::take(&mut guild.role_holders))
+        } else {
+            (None, Vec::new())
+        };
+        guild.role = new_role;
+        save_birthdays(&data);
+        replaced
+    };
+    if let Some(old_role) = old_role.map(serenity::RoleId::new) {
+        for user in holders {
+            let user = serenity::UserId::new(user);
+            if let Err(e) = ctx
+                .http()
+                .remove_member_role(guild_id, user, old_role, Some("Birthday role changed"))
+                .await
+            {
+                log::warn!("Failed to remove old birthday role from {}: {}", user, e);
+            }
+        }
+    }
+
+    match role {
+        Some(role) => {
+            ctx.say(format!(
+                "✅ Members get **{}** on their birthday, from {:02}:00 until the same time the next day.",
+                role.name, ANNOUNCE_HOUR
+            ))
+            .await?
+        }
+        None => ctx.say("✅ No birthday role from now on.").await?,
+    };
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    fn date(year: i32, month: u32, day: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(year, month, day).unwrap()
+    }
+
+    #[test]
+    fn test_validate_birthdays() {
+        let mut data: BirthdayData =
+            serde_json::from_str(include_str!("../../tests/fixtures/config/birthdays.json"))
+                .unwrap();
+        let live = LiveIds::default().with_guild(1, &[10], &[20]);
+
+        let mut problems = validate_birthdays(&mut data, &live);
+        problems.sort_by_key(|problem| problem.problem.clone());
+        let found: Vec<(&str, &str)> = problems
+            .iter()
+            .map(|problem| (problem.field.as_str(), problem.problem.as_str()))
+            .collect();
+        assert_eq!(
+            found,
+            [
+                ("users", "13-1 isn't a date (user 6)"),
+                ("users", "2-30 isn't a date (user 7)"),
+                ("channel", "channel 11 was deleted"),
+                ("role", "role 21 was deleted"),
+            ]
+        );
+        let guild = &data.guilds[&1];
+        assert_eq!((guild.channel, guild.role), (None, None));
+        assert!(guild.role_holders.is_empty());
+        let users: Vec<u64> = guild.users.keys().copied().collect();
+        assert_eq!(users, [5]);
+        assert!(validate_birthdays(&mut data, &live).is_empty());
+    }
+
+    #[test]
+    fn test_parse_birthday() {
+        assert_eq!(
+            Birthday::parse("03-14"),
+            Some(Birthday { month: 3, day: 14 })
+        );
+        assert_eq!(
+            Birthday::parse(" 12/1 "),
+            Some(Birthday { month: 12, day: 1 })
+        );
+        assert_eq!(
+            Birthday::parse("02-29"),
+            Some(Birthday { month: 2, day: 29 })
+        );
+        for bad in ["02-30", "13-01", "00-10", "04-31", "march 3", "0314", ""] {
+            assert_eq!(Birthday::parse(bad), None, "{bad:?}");
+        }
+    }
+
+    #[test]
+    fn test_leap_day_birthdays() {
+        let leap = Birthday { month: 2, day: 29 };
+        assert_eq!(leap.in_year(2024), date(2024, 2, 29));
+        assert_eq!(leap.in_year(2025), date(2025, 2, 28));
+        assert_eq!(leap.next_from(date(2025, 3, 1)), date(2026, 2, 28));
+        assert_eq!(leap.next_from(date(2027, 3, 1)), date(2028, 2, 29));
+
+        let mut guild = GuildBirthdays::default();
+        guild.users.insert(1, leap);
+        guild.users.insert(2, Birthday { month: 2, day: 28 });
+        assert_eq!(guild.celebrating(date(2025, 2, 28)), vec![1, 2]);
+        assert_eq!(guild.celebrating(date(2024, 2, 28)), vec![2]);
+        assert_eq!(guild.celebrating(date(2024, 2, 29)), vec![1]);
+    }
+
+    #[test]
+    fn test_upcoming_wraps_around_the_year() {
+        let mut guild = GuildBirthdays::default();
+        guild.users.insert(1, Birthday { month: 1, day: 5 });
+        guild.users.insert(2, Birthday { month: 12, day: 20 });
+        guild.users.insert(3, Birthday { month: 12, day: 10 });
+        assert_eq!(
+            guild.upcoming(date(2025, 12, 10)),
+            vec![
+                (3, date(2025, 12, 10)),
+                (2, date(2025, 12, 20)),
+                (1, date(2026, 1, 5)),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_take_due_run() {
+        let tz: Tz = "Europe/Copenhagen".parse().unwrap();
+        let at = |day, hour| tz.with_ymd_and_hms(2025, 6, day, hour, 0, 0).unwrap();
+        let mut guild = GuildBirthdays {
+            channel: Some(10),
+            role: Some(20),
+            ..Default::default()
+        };
+        guild.users.insert(1, Birthday { month: 6, day: 2 });
+        guild.users.insert(2, Birthday { month: 6, day: 3 });
+
+        // Not before the announce hour, then once a day
+        assert_eq!(take_due_run(7, &mut guild, &at(2, 8)), None);
+        let run = take_due_run(7, &mut guild, &at(2, 9)).unwrap();
+        assert_eq!(run.celebrating, vec![1]);
+        assert!(run.expired.is_empty());
+        assert_eq!(take_due_run(7, &mut guild, &at(2, 20)), None);
+
+        // The next day yesterday's holder loses the role
+        let run = take_due_run(7, &mut guild, &at(3, 10)).unwrap();
+        assert_eq!(
+            run,
+            BirthdayRun {
+                guild_id: 7,
+                channel: Some(10),
+                role: Some(20),
+                celebrating: vec![2],
+                expired: vec![1],
+            }
+        );
+        assert_eq!(guild.role_holders, vec![2]);
+
+        // Without a role nobody is recorded as holding one
+        guild.role = None;
+        let run = take_due_run(7, &mut guild, &at(4, 9)).unwrap();
+        assert_eq!(run.expired, vec![2]);
+        assert!(guild.role_holders.is_empty());
+    }
+
+    #[test]
+    fn test_celebration_message() {
+        assert_eq!(celebration_message(&[1]), "🎂 Happy birthday, <@1>! 🎉");
+        assert_eq!(
+            celebration_message(&[1, 2, 3]),
+            "🎂 Happy birthday, <@1>, <@2> and <@3>! 🎉"
+        );
+    }
+}
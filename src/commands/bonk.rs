@@ -1,15 +1,16 @@
+use crate::utils::avatar::{download_avatar, still_avatar_url};
+use crate::utils::meme::{
+    clamp_speed, overlay_avatar, send_meme_fallback, MemeFailure, MemeFallback,
+};
 use crate::{Context, Error};
-use image::{AnimationDecoder, DynamicImage};
 use poise::serenity_prelude as serenity;
-use rand::seq::SliceRandom;
-use std::fs;
-use tempfile::NamedTempFile;
 
 /// Bonks a user by putting their profile picture on a random bonk GIF
 #[poise::command(prefix_command, slash_command)]
 pub async fn bonk(
     ctx: Context<'_>,
     #[description = "User to bonk"] user: Option<serenity::User>,
+    #[description = "Playback speed, 0.25-4 (0.5 is slower, 2 is faster)"] speed: Option<f32>,
 ) -> Result<(), Error> {
     log::info!("Bonk command called by {}", ctx.author().name);
 
@@ -28,53 +29,39 @@ pub async fn bonk(
 
     // Send initial "thinking" message
     let thinking_msg = ctx.say("Loading the bonk...").await?;
-
-    // Get the user's avatar URL
-    let avatar_url = target_user
-        .avatar_url()
-        .unwrap_or_else(|| target_user.default_avatar_url());
-
-    // Download the user's profile picture
-    let avatar_response = match reqwest::get(&avatar_url).await {
-        Ok(response) => response,
-        Err(e) => {
-            thinking_msg
-                .edit(
-                    ctx,
-                    poise::CreateReply::default()
-                        .content(format!("❌ Failed to download profile picture: {}", e)),
-                )
-                .await?;
-            return Ok(());
-        }
+    let fallback = MemeFallback {
+        file_name: "bonk.gif",
+        gif_caption: format!(
+            "{} got bonked! (their face didn't make it into the GIF this time)",
+            target_user.name
+        ),
+        text: format!("🔨 *bonk!* {} has been bonked.", target_user.name),
     };
 
-    let avatar_bytes = match avatar_response.bytes().await {
-        Ok(bytes) => bytes,
+    // Pick the GIF first, so there is something to fall back to if the
+    // profile picture can't be put on it
+    let selected = ctx
+        .data()
+        .assets
+        .select_random_gif("bonk", &mut rand::thread_rng());
+    let (bonk_path, bonk_data) = match selected {
+        Ok(selected) => selected,
         Err(e) => {
-            thinking_msg
-                .edit(
-                    ctx,
-                    poise::CreateReply::default()
-                        .content(format!("❌ Failed to read profile picture data: {}", e)),
-                )
-                .await?;
-            return Ok(());
+            return send_meme_fallback(ctx, &thinking_msg, MemeFailure::NoAssets(e), fallback)
+                .await;
         }
     };
 
-    // Load and resize the profile picture
-    let avatar_img = match image::load_from_memory(&avatar_bytes) {
+    // Download the user's profile picture as a still image
+    let avatar_url = still_avatar_url(&target_user);
+    let avatar_img = match download_avatar(&avatar_url).await {
         Ok(img) => img,
-        Err(e) => {
-            thinking_msg
-                .edit(
-                    ctx,
-                    poise::CreateReply::default()
-                        .content(format!("❌ Failed to process profile picture: {}", e)),
-                )
-                .await?;
-            return Ok(());
+        Err(error) => {
+            let failure = MemeFailure::Avatar {
+                base_gif: bonk_path,
+                error,
+            };
+            return send_meme_fallback(ctx, &thinking_msg, failure, fallback).await;
         }
     };
 
@@ -83,246 +70,59 @@ pub async fn bonk(
         .edit(ctx, poise::CreateReply::default().content("Taking aim..."))
         .await?;
 
-    // Select a random bonk GIF and extract positioning data
-    match select_random_bonk_gif().await {
-        Ok((gif_path, bonk_data)) => {
-            // Process the GIF with the profile picture overlay
-            match process_bonk_gif(&avatar_img, &gif_path, &bonk_data).await {
-                Ok(output_path) => {
-                    // Update status
-                    thinking_msg
-                        .edit(ctx, poise::CreateReply::default().content("Bonking!"))
-                        .await?;
-
-                    // Read the processed GIF
-                    let gif_data = match fs::read(&output_path) {
-                        Ok(data) => data,
-                        Err(e) => {
-                            thinking_msg
-                                .edit(
-                                    ctx,
-                                    poise::CreateReply::default()
-                                        .content(format!("❌ Failed to read processed GIF: {}", e)),
-                                )
-                                .await?;
-                            return Ok(());
-                        }
-                    };
-
-                    // Send the GIF
-                    let attachment = serenity::CreateAttachment::bytes(gif_data, "bonk.gif");
-
-                    let reply = poise::CreateReply::default()
-                        .content(format!("{} successfully bonked!", target_user.name))
-                        .attachment(attachment);
-
-                    thinking_msg.edit(ctx, reply).await?;
-
-                    // Clean up temporary file
-                    let _ = fs::remove_file(output_path);
-                }
-                Err(e) => {
-                    thinking_msg
-                        .edit(
-                            ctx,
-                            poise::CreateReply::default()
-                                .content(format!("❌ Failed to process bonk GIF: {}", e)),
-                        )
-                        .await?;
-                }
-            }
-        }
-        Err(e) => {
-            thinking_msg
-                .edit(
-                    ctx,
-                    poise::CreateReply::default()
-                        .content(format!("❌ No bonk GIFs available: {}", e)),
-                )
-                .await?;
-        }
-    }
-
-    Ok(())
-}
-
-#[derive(Debug)]
-struct BonkData {
-    x_percent: f32,
-    y_percent: f32,
-    scale_percent: f32,
-}
-
-async fn select_random_bonk_gif(
-) -> Result<(String, BonkData), Box<dyn std::error::Error + Send + Sync>> {
-    let bonk_dir = "assets/bonk";
-
-    // Read all files in the bonk directory
-    let entries = fs::read_dir(bonk_dir)?;
-    let mut gif_files = Vec::new();
-
-    for entry in entries {
-        let entry = entry?;
-        let path = entry.path();
-
-        if let Some(filename) = path.file_name().and_then(|n| n.to_str()) {
-            if filename.ends_with(".gif") && filename.starts_with("bonk_") {
-                gif_files.push(path.to_string_lossy().to_string());
-            }
-        }
-    }
-
-    if gif_files.is_empty() {
-        return Err("No bonk GIFs found in assets/bonk directory".into());
-    }
-
-    // Select a random GIF
-    let mut rng = rand::thread_rng();
-    let selected_gif = gif_files.choose(&mut rng).unwrap();
-
-    // Parse the filename to extract positioning data
-    let bonk_data = parse_bonk_filename(selected_gif)?;
-
-    Ok((selected_gif.clone(), bonk_data))
-}
-
-fn parse_bonk_filename(
-    filename: &str,
-) -> Result<BonkData, Box<dyn std::error::Error + Send + Sync>> {
-    // Expected format: bonk_1_x0.2_y0.3_s0.25.gif
-    let basename = filename
-        .split('/')
-        .next_back()
-        .unwrap_or(filename)
-        .trim_end_matches(".gif");
-
-    let parts: Vec<&str> = basename.split('_').collect();
-
-    if parts.len() < 5 || parts[0] != "bonk" {
-        return Err(format!("Invalid bonk filename format: {}", filename).into());
-    }
-
-    let mut x_percent = 0.1; // default values
-    let mut y_percent = 0.4;
-    let mut scale_percent = 0.3;
-
-    // Parse x, y, s values
-    for part in &parts[2..] {
-        if let Some(x_val) = part.strip_prefix('x') {
-            x_percent = x_val.parse::<f32>()?;
-        } else if let Some(y_val) = part.strip_prefix('y') {
-            y_percent = y_val.parse::<f32>()?;
-        } else if let Some(s_val) = part.strip_prefix('s') {
-            scale_percent = s_val.parse::<f32>()?;
-        }
-    }
-
-    Ok(BonkData {
-        x_percent,
-        y_percent,
-        scale_percent,
-    })
-}
-
-async fn process_bonk_gif(
-    avatar_img: &DynamicImage,
-    gif_path: &str,
-    bonk_data: &BonkData,
-) -> Result<String, Box<dyn std::error::Error + Send + Sync>> {
-    // Open the original GIF using image crate
-    let gif_file = std::fs::File::open(gif_path)?;
-    let decoder = image::codecs::gif::GifDecoder::new(gif_file)?;
-    let frames = decoder.into_frames();
-    let frames: Result<Vec<_>, _> = frames.collect();
-    let frames = frames?;
-
-    if frames.is_empty() {
-        return Err("GIF has no frames".into());
-    }
-
-    // Get dimensions from first frame
-    let first_frame = &frames[0];
-    let (screen_width, screen_height) = first_frame.buffer().dimensions();
-
-    // Calculate profile picture size and position based on bonk data
-    let pfp_size = (screen_height as f32 * bonk_data.scale_percent) as u32;
-    let resized_avatar = avatar_img
-        .resize_exact(pfp_size, pfp_size, image::imageops::FilterType::Lanczos3)
-        .to_rgba8();
-
-    // Calculate position from percentages
-    let overlay_x = (screen_width as f32 * bonk_data.x_percent) as u32;
-    let overlay_y = (screen_height as f32 * bonk_data.y_percent) as u32;
-
-    // Process frames and create new GIF
-    let temp_file = NamedTempFile::new()?;
-    let output_path = temp_file.path().to_string_lossy().to_string() + ".gif";
-    let output_file = std::fs::File::create(&output_path)?;
-
-    let mut encoder = image::codecs::gif::GifEncoder::new(output_file);
-    encoder.set_repeat(image::codecs::gif::Repeat::Infinite)?;
-
-    // Limit frames to prevent excessive processing
-    let frame_limit = std::cmp::min(frames.len(), 100);
-
-    for frame in frames.iter().take(frame_limit) {
-        let mut frame_buffer = frame.buffer().clone();
-
-        // Overlay the profile picture with alpha blending
-        for y in 0..pfp_size {
-            for x in 0..pfp_size {
-                let dst_x = overlay_x + x;
-                let dst_y = overlay_y + y;
-
-                if dst_x < screen_width && dst_y < screen_height {
-                    let avatar_pixel = resized_avatar.get_pixel(x, y);
-                    let alpha = avatar_pixel[3] as f32 / 255.0;
-
-                    if alpha > 0.0 {
-                        let frame_pixel = frame_buffer.get_pixel_mut(dst_x, dst_y);
-                        let inv_alpha = 1.0 - alpha;
-
-                        frame_pixel[0] = (avatar_pixel[0] as f32 * alpha
-                            + frame_pixel[0] as f32 * inv_alpha)
-                            as u8;
-                        frame_pixel[1] = (avatar_pixel[1] as f32 * alpha
-                            + frame_pixel[1] as f32 * inv_alpha)
-                            as u8;
-                        frame_pixel[2] = (avatar_pixel[2] as f32 * alpha
-                            + frame_pixel[2] as f32 * inv_alpha)
-                            as u8;
-                        frame_pixel[3] = ((avatar_pixel[3] as f32 * alpha
-                            + frame_pixel[3] as f32 * inv_alpha)
-                            .min(255.0)) as u8;
-                    }
-                }
-            }
+    // Process the GIF with the profile picture overlay
+    let gif_data = match overlay_avatar(&avatar_img, &bonk_path, &bonk_data, clamp_speed(speed)) {
+        Ok(data) => data,
+        Err(error) => {
+            let failure = MemeFailure::Render {
+                base_gif: bonk_path,
+                error,
+            };
+            return send_meme_fallback(ctx, &thinking_msg, failure, fallback).await;
         }
+    };
 
-        // Create frame with original delay
-        let delay = frame.delay().numer_denom_ms();
-        let frame_delay = image::Delay::from_numer_denom_ms(delay.0, delay.1);
+    // Update status
+    thinking_msg
+        .edit(ctx, poise::CreateReply::default().content("Bonking!"))
+        .await?;
 
-        let gif_frame = image::Frame::from_parts(frame_buffer, 0, 0, frame_delay);
-        encoder.encode_frame(gif_frame)?;
-    }
+    // Send the GIF
+    let attachment = serenity::CreateAttachment::bytes(gif_data, "bonk.gif");
+    let reply = poise::CreateReply::default()
+        .content(format!("{} successfully bonked!", target_user.name))
+        .attachment(attachment);
+    thinking_msg.edit(ctx, reply).await?;
 
-    drop(encoder);
-    Ok(output_path)
+    Ok(())
 }
 
 #[cfg(test)]
 mod tests {
-    use super::*;
+    use crate::utils::meme::{parse_gif_filename, AssetScan};
+    use crate::utils::random::SeededRng;
 
     #[test]
     fn test_parse_bonk_filename() {
-        let result = parse_bonk_filename("assets/bonk/bonk_1_x0.2_y0.3_s0.25.gif").unwrap();
+        let result = parse_gif_filename("bonk", "assets/bonk/bonk_1_x0.2_y0.3_s0.25.gif").unwrap();
         assert_eq!(result.x_percent, 0.2);
         assert_eq!(result.y_percent, 0.3);
         assert_eq!(result.scale_percent, 0.25);
     }
 
+    #[tokio::test]
+    async fn test_select_bonk_gif_with_seed() {
+        let assets = AssetScan::load().await.unwrap();
+        let pick = |seed| {
+            assets
+                .select_random_gif("bonk", &mut SeededRng::new(seed))
+                .unwrap()
+                .0
+        };
+        assert_eq!(pick(1), pick(1));
+        assert!(pick(1).starts_with("assets/bonk/bonk_"));
+    }
+
     #[test]
     fn test_bonk_command_exists() {
         let function_name = "bonk";
@@ -0,0 +1,442 @@
+//! Thank-you messages for server boosts. A boost shows up as a member update
+//! whose `premium_since` went from unset to set; the bot then posts the
+//! server's template in its boost channel. If that channel is deleted, the
+//! setting is dropped so the server can pick a new one.
+
+use crate::config_check::{check_store, ConfigProblem, LiveIds};
+use crate::storage;
+use crate::utils::check_store_writable;
+use crate::utils::limits::truncate;
+use crate::utils::permissions::{check_channel_perms, is_not_found};
+use crate::{Context, Error};
+use chrono::{DateTime, Duration, Utc};
+use parking_lot::RwLock;
+use poise::serenity_prelude as serenity;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+const BOOSTS_FILE: &str = "boosts.json";
+
+const DEFAULT_TEMPLATE: &str = "🚀 {user} just boosted **{server}**! Thank you so much! 💜";
+
+const MAX_TEMPLATE_CHARS: usize = 500;
+
+/// When the member wasn't cached before the update there's no old state to
+/// compare with, so a boost that started this recently counts as new.
+const RECENT_BOOST_MINUTES: i64 = 10;
+
+#[derive(Serialize, Deserialize, Default, Debug, Clone, PartialEq)]
+#[serde(default)]
+struct GuildBoosts {
+    /// Where boosts are celebrated; `None` keeps them quiet.
+    channel: Option<u64>,
+    /// `None` uses [`DEFAULT_TEMPLATE`].
+    template: Option<String>,
+}
+
+impl GuildBoosts {
+    fn template(&self) -> &str {
+        self.template.as_deref().unwrap_or(DEFAULT_TEMPLATE)
+    }
+}
+
+#[derive(Serialize, Deserialize, Default, Clone)]
+struct BoostData {
+    guilds: HashMap<u64, GuildBoosts>,
+}
+
+lazy_static::lazy_static! {
+    static ref BOOSTS: RwLock<BoostData> = RwLock::new(storage::load(BOOSTS_FILE));
+}
+
+/// Re-read the boost settings from disk.
+pub fn reload_boosts() -> Result<(), Error> {
+    *BOOSTS.write() = storage::reload(BOOSTS_FILE)?;
+    Ok(())
+}
+
+/// Where a guild's boosts are celebrated, if anywhere.
+pub fn boost_channel(guild_id: u64) -> Option<u64> {
+    BOOSTS
+        .read()
+        .guilds
+        .get(&guild_id)
+        .and_then(|guild| guild.channel)
+}
+
+fn save_boosts(data: &BoostData) {
+    if let Err(e) = storage::save(BOOSTS_FILE, data) {
+        log::error!("Failed to save boost settings: {}", e);
+    }
+}
+
+/// Drop an orphaned guild's boost channel and template.
+pub fn purge_guild(guild_id: u64) {
+    let mut data = BOOSTS.write();
+    if data.guilds.remove(&guild_id).is_some() {
+        save_boosts(&data);
+    }
+}
+
+/// Unset boost channels that were deleted while the bot was offline.
+fn validate_boosts(data: &mut BoostData, live: &LiveIds) -> Vec<ConfigProblem> {
+    let mut problems = Vec::new();
+    for (&guild_id, guild) in &mut data.guilds {
+        if let Some(channel_id) = guild
+            .channel
+            .take_if(|&mut channel_id| live.channel_gone(guild_id, channel_id))
+        {
+            problems.push(ConfigProblem::deleted_channel(
+                guild_id, "channel", channel_id,
+            ));
+        }
+    }
+    problems
+}
+
+/// Check the boost settings, see [`crate::config_check`].
+pub fn check_boosts(live: &LiveIds, quarantine: bool) -> Vec<ConfigProblem> {
+    check_store(
+        &BOOSTS,
+        quarantine,
+        |data| validate_boosts(data, live),
+        save_boosts,
+    )
+}
+
+/// Command check for commands that change the boost settings.
+async fn boost_writable(ctx: Context<'_>) -> Result<bool, Error> {
+    check_store_writable(ctx, &storage::data_path(BOOSTS_FILE)).await
+}
+
+/// Whether a member update is the member starting to boost. `old` is the
+/// member's `premium_since` before the update, `None` if they weren't
+/// cached; `new` is the one after it.
+fn started_boosting(
+    old: Option<Option<DateTime<Utc>>>,
+    new: Option<DateTime<Utc>>,
+    now: DateTime<Utc>,
+) -> bool {
+    let Some(since) = new else {
+        return false;
+    };
+    match old {
+        Some(old) => old.is_none(),
+        None => now - since <= Duration::minutes(RECENT_BOOST_MINUTES),
+    }
+}
+
+/// Fill in a template's placeholders: `{user}` (a mention), `{name}` and
+/// `{server}`.
+fn render_template(template: &str, user_id: u64, name: &str, server: &str) -> String {
+    template
+        .replace("{user}", &format!("<@{}>", user_id))
+        .replace("{name}", name)
+        .replace("{server}", server)
+}
+
+/// Post the thank-you if a member update is a new boost. Called from the
+/// event handler.
+pub async fn handle_member_update(
+    ctx: &serenity::Context,
+    old: Option<&serenity::Member>,
+    event: &serenity::GuildMemberUpdateEvent,
+) {
+    let guild_id = event.guild_id.get();
+    let Some(settings) = BOOSTS.read().guilds.get(&guild_id).cloned() else {
+        return;
+    };
+    let Some(channel) = settings.channel else {
+        return;
+    };
+    let old_since = old.map(|member| member.premium_since.map(|since| *since));
+    if !started_boosting(
+        old_since,
+        event.premium_since.map(|since| *since),
+        Utc::now(),
+    ) {
+        return;
+    }
+
+    let server = event
+        .guild_id
+        .name(&ctx.cache)
+        .unwrap_or_else(|| "the server".to_string());
+    let name = event
+        .nick
+        .clone()
+        .unwrap_or_else(|| event.user.display_name().to_string());
+    let message = serenity::CreateMessage::new()
+        .content(render_template(
+            settings.template(),
+            event.user.id.get(),
+            &name,
+            &server,
+        ))
+        .allowed_mentions(serenity::CreateAllowedMentions::new().users([event.user.id]));
+    match serenity::ChannelId::new(channel)
+        .send_message(&ctx.http, message)
+        .await
+    {
+        Ok(_) => log::info!(
+            "Thanked {} for boosting guild {}",
+            event.user.name,
+            guild_id
+        ),
+        Err(e) if is_not_found(&e) => {
+            log::warn!(
+                "Boost channel {} in guild {} is gone, turning boost messages off",
+                channel,
+                guild_id
+            );
+            forget_channel(guild_id, channel);
+        }
+        Err(e) => log::warn!(
+            "Failed to thank {} for boosting guild {}: {}",
+            event.user.name,
+            guild_id,
+            e
+        ),
+    }
+}
+
+/// Drop the boost channel setting if it's `channel_id`, e.g. because the
+/// channel was deleted. The template is kept.
+pub fn forget_channel(guild_id: u64, channel_id: u64) {
+    let mut data = BOOSTS.write();
+    if let Some(guild) = data.guilds.get_mut(&guild_id) {
+        if guild.channel == Some(channel_id) {
+            guild.channel = None;
+            save_boosts(&data);
+            log::info!("Boost channel of guild {} was removed", guild_id);
+        }
+    }
+}
+
+/// Thank members who boost the server
+#[poise::command(
+    prefix_command,
+    slash_command,
+    guild_only,
+    subcommands("boost_channel_set", "boost_message")
+)]
+pub async fn boost(ctx: Context<'_>) -> Result<(), Error> {
+    log::info!("Boost command called by {}", ctx.author().name);
+
+    let Some(guild_id) = ctx.guild_id() else {
+        return Ok(());
+    };
+    let settings = BOOSTS
+        .read()
+        .guilds
+        .get(&guild_id.get())
+        .cloned()
+        .unwrap_or_default();
+    let channel = match settings.channel {
+        Some(channel) => format!("Boosts are celebrated in <#{}>.", channel),
+        None => "Boosts aren't celebrated. Pick a channel with `-boost channel`.".to_string(),
+    };
+    // The message is shown as written, so its {user} and any mentions in it
+    // mustn't ping anyone here
+    ctx.send(
+        poise::CreateReply::default()
+            .content(format!(
+                "🚀 {}\n**Message:** {}",
+                channel,
+                settings.template()
+            ))
+            .allowed_mentions(serenity::CreateAllowedMentions::new()),
+    )
+    .await?;
+    Ok(())
+}
+
+/// Celebrate boosts in a channel, or stop celebrating them
+#[poise::command(
+    prefix_command,
+    slash_command,
+    guild_only,
+    rename = "channel",
+    required_permissions = "MANAGE_GUILD",
+    check = "boost_writable"
+)]
+pub async fn boost_channel_set(
+    ctx: Context<'_>,
+    #[description = "Channel for boost thank-yous (leave out to stop)"] channel: Option<
+        serenity::GuildChannel,
+    >,
+) -> Result<(), Error> {
+    log::info!("Boost channel command called by {}", ctx.author().name);
+
+    let Some(guild_id) = ctx.guild_id() else {
+        return Ok(());
+    };
+    if let Some(channel) = &channel {
+        if channel.guild_id != guild_id {
+            ctx.say("❌ That channel isn't in this server!").await?;
+            return Ok(());
+        }
+        let required = [
+            serenity::Permissions::VIEW_CHANNEL,
+            serenity::Permissions::SEND_MESSAGES,
+        ];
+        if let Err(missing) = check_channel_perms(ctx, channel.id, &required).await {
+            ctx.say(missing.to_string()).await?;
+            return Ok(());
+        }
+    }
+
+    {
+        let mut data = BOOSTS.write();
+        let guild = data.guilds.entry(guild_id.get()).or_default();
+        guild.channel = channel.as_ref().map(|channel| channel.id.get());
+        save_boosts(&data);
+    }
+
+    match channel {
+        Some(channel) => {
+            ctx.say(format!(
+                "✅ Members who boost the server will be thanked in <#{}>.",
+                channel.id
+            ))
+            .await?
+        }
+        None => ctx.say("✅ Boosts won't be celebrated anymore.").await?,
+    };
+    Ok(())
+}
+
+/// Change the boost thank-you, or go back to the default
+#[poise::command(
+    prefix_command,
+    slash_command,
+    guild_only,
+    rename = "message",
+    required_permissions = "MANAGE_GUILD",
+    check = "boost_writable"
+)]
+pub async fn boost_message(
+    ctx: Context<'_>,
+    #[description = "The message; {user}, {name} and {server} are filled in (leave out for the default)"]
+    #[rest]
+    template: Option<String>,
+) -> Result<(), Error> {
+    log::info!("Boost message command called by {}", ctx.author().name);
+
+    let Some(guild_id) = ctx.guild_id() else {
+        return Ok(());
+    };
+    let template = template
+        .map(|template| template.trim().to_string())
+        .filter(|template| !template.is_empty());
+    if let Some(template) = &template {
+        if template.chars().count() > MAX_TEMPLATE_CHARS {
+            ctx.say(format!(
+                "❌ That message is too long! The limit is {} characters.",
+                MAX_TEMPLATE_CHARS
+            ))
+            .await?;
+            return Ok(());
+        }
+    }
+
+    let settings = {
+        let mut data = BOOSTS.write();
+        let guild = data.guilds.entry(guild_id.get()).or_default();
+        guild.template = template;
+        let settings = guild.clone();
+        save_boosts(&data);
+        settings
+    };
+
+    let server = ctx
+        .guild()
+        .map(|guild| guild.name.clone())
+        .unwrap_or_else(|| "the server".to_string());
+    let preview = render_template(
+        settings.template(),
+        ctx.author().id.get(),
+        ctx.author().display_name(),
+        &server,
+    );
+    let reply = format!(
+        "✅ Boost message {}. If you boosted, it would say:\n>>> {}",
+        if settings.template.is_some() {
+            "updated"
+        } else {
+            "reset to the default"
+        },
+        truncate(&preview, MAX_TEMPLATE_CHARS * 2)
+    );
+    ctx.send(
+        poise::CreateReply::default()
+            .content(reply)
+            .allowed_mentions(serenity::CreateAllowedMentions::new()),
+    )
+    .await?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    #[test]
+    fn test_validate_boosts() {
+        let mut data: BoostData =
+            serde_json::from_str(include_str!("../../tests/fixtures/config/boosts.json")).unwrap();
+        let live = LiveIds::default()
+            .with_guild(1, &[10], &[])
+            .with_guild(2, &[10], &[]);
+
+        let problems = validate_boosts(&mut data, &live);
+        assert_eq!(problems.len(), 1);
+        assert_eq!(problems[0].guild_id, Some(2));
+        assert_eq!(problems[0].field, "channel");
+        assert_eq!(data.guilds[&1].channel, Some(10));
+        assert_eq!(data.guilds[&2].channel, None);
+        assert_eq!(data.guilds[&2].template.as_deref(), Some("Thanks {user}!"));
+        // Not cached, so not judged
+        assert_eq!(data.guilds[&3].channel, Some(11));
+    }
+
+    #[test]
+    fn test_started_boosting() {
+        let now = Utc.with_ymd_and_hms(2025, 6, 1, 12, 0, 0).unwrap();
+        let just_now = now - Duration::minutes(1);
+        let long_ago = now - Duration::days(30);
+
+        assert!(started_boosting(Some(None), Some(just_now), now));
+        // Already boosting: a nickname or role change isn't a new boost
+        assert!(!started_boosting(Some(Some(long_ago)), Some(long_ago), now));
+        // Stopped boosting
+        assert!(!started_boosting(Some(Some(long_ago)), None, now));
+        assert!(!started_boosting(Some(None), None, now));
+        // Not cached: only a boost that just started counts
+        assert!(started_boosting(None, Some(just_now), now));
+        assert!(!started_boosting(None, Some(long_ago), now));
+        assert!(!started_boosting(None, None, now));
+    }
+
+    #[test]
+    fn test_render_template() {
+        assert_eq!(
+            render_template(DEFAULT_TEMPLATE, 42, "Alice", "Rust Club"),
+            "🚀 <@42> just boosted **Rust Club**! Thank you so much! 💜"
+        );
+        assert_eq!(
+            render_template(
+                "{name} {name}! Welcome to the {server} boosters",
+                1,
+                "Bo",
+                "X"
+            ),
+            "Bo Bo! Welcome to the X boosters"
+        );
+        assert_eq!(
+            render_template("No placeholders", 1, "Bo", "X"),
+            "No placeholders"
+        );
+    }
+}
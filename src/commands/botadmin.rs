@@ -0,0 +1,97 @@
+use crate::guild_config::{get_guild_config, guild_config_writable, update_guild_config};
+use crate::{Context, Error};
+use poise::serenity_prelude as serenity;
+
+/// Choose the bot-admin role, whose members the bot treats as moderators
+#[poise::command(
+    prefix_command,
+    slash_command,
+    guild_only,
+    required_permissions = "MANAGE_GUILD",
+    subcommands("botadmin_set", "botadmin_clear", "botadmin_show")
+)]
+pub async fn botadmin(_ctx: Context<'_>) -> Result<(), Error> {
+    Ok(())
+}
+
+/// Set the bot-admin role
+#[poise::command(
+    prefix_command,
+    slash_command,
+    guild_only,
+    rename = "set",
+    required_permissions = "MANAGE_GUILD",
+    check = "guild_config_writable"
+)]
+pub async fn botadmin_set(
+    ctx: Context<'_>,
+    #[description = "Role whose members count as bot admins"] role: serenity::Role,
+) -> Result<(), Error> {
+    log::info!(
+        "Botadmin set command called by {} for role {}",
+        ctx.author().name,
+        role.id
+    );
+
+    let Some(guild_id) = ctx.guild_id() else {
+        return Ok(());
+    };
+    if role.guild_id != guild_id {
+        ctx.say("❌ That role isn't in this server!").await?;
+        return Ok(());
+    }
+
+    update_guild_config(guild_id.get(), |config| {
+        config.bot_admin_role = Some(role.id.get())
+    });
+
+    ctx.say(format!(
+        "✅ Members of <@&{}> are now bot admins here (for example, they can't be spampinged).",
+        role.id
+    ))
+    .await?;
+    Ok(())
+}
+
+/// Remove the bot-admin role
+#[poise::command(
+    prefix_command,
+    slash_command,
+    guild_only,
+    rename = "clear",
+    required_permissions = "MANAGE_GUILD",
+    check = "guild_config_writable"
+)]
+pub async fn botadmin_clear(ctx: Context<'_>) -> Result<(), Error> {
+    log::info!("Botadmin clear command called by {}", ctx.author().name);
+
+    let Some(guild_id) = ctx.guild_id() else {
+        return Ok(());
+    };
+    update_guild_config(guild_id.get(), |config| config.bot_admin_role = None);
+
+    ctx.say("✅ This server no longer has a bot-admin role.")
+        .await?;
+    Ok(())
+}
+
+/// Show the current bot-admin role
+#[poise::command(
+    prefix_command,
+    slash_command,
+    guild_only,
+    rename = "show",
+    required_permissions = "MANAGE_GUILD"
+)]
+pub async fn botadmin_show(ctx: Context<'_>) -> Result<(), Error> {
+    let Some(guild_id) = ctx.guild_id() else {
+        return Ok(());
+    };
+
+    let message = match get_guild_config(guild_id.get()).and_then(|c| c.bot_admin_role) {
+        Some(role_id) => format!("🛡️ The bot-admin role is <@&{}>.", role_id),
+        None => "🛡️ No bot-admin role is set. Use `-botadmin set @role` to pick one.".to_string(),
+    };
+    ctx.say(message).await?;
+    Ok(())
+}
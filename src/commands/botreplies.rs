@@ -0,0 +1,364 @@
+use crate::guild_config::{get_guild_config, guild_config_writable, update_guild_config};
+use crate::message_content::{content_available, fallback_notice};
+use crate::{Context, Error};
+use chrono::{DateTime, Duration, Utc};
+use parking_lot::Mutex;
+use poise::serenity_prelude as serenity;
+use poise::ChoiceParameter;
+use std::collections::HashMap;
+
+/// Said to a bare @-mention of the bot unless the server set its own reply.
+const DEFAULT_MENTION_REPLY: &str = "👋 Hi! Use `{prefix}help` to see what I can do.";
+
+const MAX_MENTION_REPLY_CHARS: usize = 500;
+
+/// A channel gets at most one mention reply this often, so a burst of
+/// pings gets one answer.
+const MENTION_REPLY_COOLDOWN_SECS: i64 = 30;
+
+/// Messages pinging more people than this are mass pings, not a hello.
+const MAX_USER_MENTIONS: usize = 3;
+
+lazy_static::lazy_static! {
+    /// When each channel last got a mention reply.
+    static ref LAST_MENTION_REPLY: Mutex<HashMap<u64, DateTime<Utc>>> = Mutex::new(HashMap::new());
+}
+
+/// Whether long command output goes to a thread.
+#[derive(poise::ChoiceParameter, Clone, Copy, Debug, PartialEq)]
+pub enum ThreadedReplies {
+    #[name = "on"]
+    On,
+    #[name = "off"]
+    Off,
+}
+
+/// Configure how the bot replies in this server
+#[poise::command(
+    prefix_command,
+    slash_command,
+    guild_only,
+    required_permissions = "MANAGE_GUILD",
+    subcommands("botreplies_threaded", "botreplies_mention")
+)]
+pub async fn botreplies(_ctx: Context<'_>) -> Result<(), Error> {
+    Ok(())
+}
+
+/// Move long output like stats reports into a thread per command
+#[poise::command(
+    prefix_command,
+    slash_command,
+    guild_only,
+    rename = "threaded",
+    required_permissions = "MANAGE_GUILD"
+)]
+pub async fn botreplies_threaded(
+    ctx: Context<'_>,
+    #[description = "on (long output goes to a thread) or off"] setting: Option<ThreadedReplies>,
+) -> Result<(), Error> {
+    log::info!(
+        "Botreplies threaded command called by {}",
+        ctx.author().name
+    );
+
+    let Some(guild_id) = ctx.guild_id() else {
+        return Ok(());
+    };
+
+    let Some(setting) = setting else {
+        let current = if get_guild_config(guild_id.get()).is_some_and(|c| c.threaded_replies) {
+            ThreadedReplies::On
+        } else {
+            ThreadedReplies::Off
+        };
+        ctx.say(format!(
+            "🧵 Threaded replies are currently **{}**.",
+            current.name()
+        ))
+        .await?;
+        return Ok(());
+    };
+
+    if !guild_config_writable(ctx).await? {
+        return Ok(());
+    }
+    let threaded = setting == ThreadedReplies::On;
+    update_guild_config(guild_id.get(), |config| config.threaded_replies = threaded);
+
+    let message = if threaded {
+        "🧵 Threaded replies are now **on**. Long output like `-stats` goes to a thread off the command, \
+         with a one-line pointer here. I need the Create Public Threads permission; without it I reply \
+         in the channel as usual."
+    } else {
+        "🧵 Threaded replies are now **off**."
+    };
+    ctx.say(message).await?;
+    Ok(())
+}
+
+/// Fill in a mention reply's `{prefix}` and `{user}` (a mention of whoever
+/// pinged the bot).
+fn mention_reply_text(template: &str, prefix: &str, user_id: u64) -> String {
+    template
+        .replace("{prefix}", prefix)
+        .replace("{user}", &format!("<@{}>", user_id))
+}
+
+/// Whether a message pings the bot without running a command. Replies only
+/// ping through Discord's reply setting, not in the text, so they don't
+/// count. `is_command` says whether a word is a command name, since the
+/// framework runs `@bot help` as `help`.
+fn is_bare_mention(
+    content: &str,
+    bot_id: u64,
+    prefix: &str,
+    is_command: impl Fn(&str) -> bool,
+) -> bool {
+    let content = content.trim();
+    if content.starts_with(prefix) {
+        return false;
+    }
+    let tokens = [format!("<@{}>", bot_id), format!("<@!{}>", bot_id)];
+    let Some(token) = tokens.iter().find(|token| content.contains(token.as_str())) else {
+        return false;
+    };
+    if let Some(rest) = content.strip_prefix(token.as_str()) {
+        if let Some(word) = rest.split_whitespace().next() {
+            if is_command(&word.to_lowercase()) {
+                return false;
+            }
+        }
+    }
+    true
+}
+
+/// Whether `channel_id` may get a mention reply at `now`, recording it if so.
+fn take_mention_cooldown(
+    last: &mut HashMap<u64, DateTime<Utc>>,
+    channel_id: u64,
+    now: DateTime<Utc>,
+) -> bool {
+    let cooldown = Duration::seconds(MENTION_REPLY_COOLDOWN_SECS);
+    last.retain(|_, at| now - *at < cooldown);
+    if last.contains_key(&channel_id) {
+        return false;
+    }
+    last.insert(channel_id, now);
+    true
+}
+
+/// Answer a bare @-mention of the bot with the server's mention reply, or,
+/// while the bot can't read messages, with how to reach it instead: a mention
+/// right after a `-` command that went unanswered is the likely case. Called
+/// from the event handler for every message.
+pub async fn handle_mention(
+    ctx: &serenity::Context,
+    message: &serenity::Message,
+    prefix: &str,
+    is_command: impl Fn(&str) -> bool,
+) {
+    if message.author.bot
+        || message.webhook_id.is_some()
+        || message.mention_everyone
+        || message.content.contains("@everyone")
+        || message.content.contains("@here")
+        || !message.mention_roles.is_empty()
+        || message.mentions.len() > MAX_USER_MENTIONS
+    {
+        return;
+    }
+    let bot_id = ctx.cache.current_user().id.get();
+    if !is_bare_mention(&message.content, bot_id, prefix, is_command) {
+        return;
+    }
+    let config = message.guild_id.and_then(|id| get_guild_config(id.get()));
+    let content_available = content_available();
+    if content_available
+        && config
+            .as_ref()
+            .is_some_and(|config| !config.mention_reply_enabled)
+    {
+        return;
+    }
+    if !take_mention_cooldown(
+        &mut LAST_MENTION_REPLY.lock(),
+        message.channel_id.get(),
+        Utc::now(),
+    ) {
+        return;
+    }
+
+    let text = if content_available {
+        let template = config
+            .and_then(|config| config.mention_reply)
+            .unwrap_or_else(|| DEFAULT_MENTION_REPLY.to_string());
+        mention_reply_text(&template, prefix, message.author.id.get())
+    } else {
+        fallback_notice(&ctx.cache.current_user().name)
+    };
+    let reply = serenity::CreateMessage::new()
+        .content(text)
+        .reference_message(message)
+        .allowed_mentions(serenity::CreateAllowedMentions::new());
+    if let Err(e) = message.channel_id.send_message(&ctx.http, reply).await {
+        log::warn!(
+            "Failed to answer a mention in channel {}: {}",
+            message.channel_id,
+            e
+        );
+    }
+}
+
+/// Change what I say when pinged without a command, or turn it off
+#[poise::command(
+    prefix_command,
+    slash_command,
+    guild_only,
+    rename = "mention",
+    required_permissions = "MANAGE_GUILD"
+)]
+pub async fn botreplies_mention(
+    ctx: Context<'_>,
+    #[description = "The reply ({prefix} and {user} are filled in), or on, off or default"]
+    #[rest]
+    setting: Option<String>,
+) -> Result<(), Error> {
+    log::info!("Botreplies mention command called by {}", ctx.author().name);
+
+    let Some(guild_id) = ctx.guild_id() else {
+        return Ok(());
+    };
+    let config = get_guild_config(guild_id.get()).unwrap_or_default();
+
+    let Some(setting) = setting
+        .map(|setting| setting.trim().to_string())
+        .filter(|setting| !setting.is_empty())
+    else {
+        let template = config
+            .mention_reply
+            .as_deref()
+            .unwrap_or(DEFAULT_MENTION_REPLY);
+        let message = if config.mention_reply_enabled {
+            format!(
+                "💬 When pinged without a command I reply:\n>>> {}",
+                mention_reply_text(template, &config.prefix, ctx.author().id.get())
+            )
+        } else {
+            "💬 Mention replies are **off**.".to_string()
+        };
+        ctx.send(
+            poise::CreateReply::default()
+                .content(message)
+                .allowed_mentions(serenity::CreateAllowedMentions::new()),
+        )
+        .await?;
+        return Ok(());
+    };
+
+    if setting.chars().count() > MAX_MENTION_REPLY_CHARS {
+        ctx.say(format!(
+            "❌ That reply is too long! The limit is {} characters.",
+            MAX_MENTION_REPLY_CHARS
+        ))
+        .await?;
+        return Ok(());
+    }
+    if !guild_config_writable(ctx).await? {
+        return Ok(());
+    }
+
+    let message = match setting.to_lowercase().as_str() {
+        "off" => {
+            update_guild_config(guild_id.get(), |config| {
+                config.mention_reply_enabled = false
+            });
+            "💬 Mention replies are now **off**.".to_string()
+        }
+        "on" => {
+            update_guild_config(guild_id.get(), |config| config.mention_reply_enabled = true);
+            "💬 Mention replies are now **on**.".to_string()
+        }
+        "default" => {
+            update_guild_config(guild_id.get(), |config| {
+                config.mention_reply_enabled = true;
+                config.mention_reply = None;
+            });
+            "💬 Mention replies are back to the default.".to_string()
+        }
+        _ => {
+            update_guild_config(guild_id.get(), |config| {
+                config.mention_reply_enabled = true;
+                config.mention_reply = Some(setting.clone());
+            });
+            format!(
+                "💬 When pinged without a command I'll now reply:\n>>> {}",
+                mention_reply_text(&setting, &config.prefix, ctx.author().id.get())
+            )
+        }
+    };
+    ctx.send(
+        poise::CreateReply::default()
+            .content(message)
+            .allowed_mentions(serenity::CreateAllowedMentions::new()),
+    )
+    .await?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_is_bare_mention() {
+        let is_command = |word: &str| word == "help" || word == "ping";
+        assert!(is_bare_mention("<@42>", 42, "-", is_command));
+        assert!(is_bare_mention("  <@!42>  ", 42, "-", is_command));
+        assert!(is_bare_mention("<@42> hi there", 42, "-", is_command));
+        assert!(is_bare_mention("thanks <@42>!", 42, "-", is_command));
+        // Commands, with the prefix or the mention as one
+        assert!(!is_bare_mention("-remind 10m <@42>", 42, "-", is_command));
+        assert!(!is_bare_mention("<@42> help", 42, "-", is_command));
+        assert!(!is_bare_mention("<@42> PING", 42, "-", is_command));
+        // Someone else, or no mention in the text (like a reply ping)
+        assert!(!is_bare_mention("<@43>", 42, "-", is_command));
+        assert!(!is_bare_mention("<@420>", 42, "-", is_command));
+        assert!(!is_bare_mention("nice one", 42, "-", is_command));
+    }
+
+    #[test]
+    fn test_mention_cooldown() {
+        let mut last = HashMap::new();
+        let now = Utc::now();
+        assert!(take_mention_cooldown(&mut last, 1, now));
+        assert!(!take_mention_cooldown(
+            &mut last,
+            1,
+            now + Duration::seconds(5)
+        ));
+        // Other channels have their own
+        assert!(take_mention_cooldown(
+            &mut last,
+            2,
+            now + Duration::seconds(5)
+        ));
+        assert!(take_mention_cooldown(
+            &mut last,
+            1,
+            now + Duration::seconds(MENTION_REPLY_COOLDOWN_SECS)
+        ));
+    }
+
+    #[test]
+    fn test_mention_reply_text() {
+        assert_eq!(
+            mention_reply_text(DEFAULT_MENTION_REPLY, "!", 7),
+            "👋 Hi! Use `!help` to see what I can do."
+        );
+        assert_eq!(
+            mention_reply_text("Hey {user}, try {prefix}games", "-", 7),
+            "Hey <@7>, try -games"
+        );
+    }
+}
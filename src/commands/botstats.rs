@@ -0,0 +1,190 @@
+use crate::{Context, Error};
+use parking_lot::Mutex;
+use poise::serenity_prelude as serenity;
+use std::collections::{HashMap, VecDeque};
+use std::time::{Duration, Instant};
+
+/// Averages cover each command's most recent this many runs.
+const LATENCY_WINDOW: usize = 50;
+
+const SLOWEST_COMMANDS_LIMIT: usize = 10;
+
+/// How long one command's recent runs took. Kept in memory only, so the
+/// numbers start over after a restart.
+#[derive(Default, Debug)]
+struct Latencies {
+    recent: VecDeque<Duration>,
+    /// Sum of `recent`, so averaging doesn't walk the window.
+    recent_total: Duration,
+    runs: u64,
+    slowest: Duration,
+}
+
+impl Latencies {
+    fn record(&mut self, elapsed: Duration) {
+        if self.recent.len() == LATENCY_WINDOW {
+            if let Some(oldest) = self.recent.pop_front() {
+                self.recent_total -= oldest;
+            }
+        }
+        self.recent.push_back(elapsed);
+        self.recent_total += elapsed;
+        self.runs += 1;
+        self.slowest = self.slowest.max(elapsed);
+    }
+
+    fn average(&self) -> Duration {
+        self.recent_total / self.recent.len().max(1) as u32
+    }
+}
+
+lazy_static::lazy_static! {
+    static ref LATENCIES: Mutex<HashMap<String, Latencies>> = Mutex::new(HashMap::new());
+}
+
+/// When a command started, stored as its invocation data by `pre_command`.
+pub struct CommandStart(pub Instant);
+
+/// Record how long a command took. Called from the framework's
+/// `post_command` hook, so only runs that finished without an error count.
+pub fn record_command_latency(command: &str, elapsed: Duration) {
+    record(&mut LATENCIES.lock(), command, elapsed);
+}
+
+fn record(latencies: &mut HashMap<String, Latencies>, command: &str, elapsed: Duration) {
+    match latencies.get_mut(command) {
+        Some(entry) => entry.record(elapsed),
+        None => {
+            let mut entry = Latencies::default();
+            entry.record(elapsed);
+            latencies.insert(command.to_string(), entry);
+        }
+    }
+}
+
+#[derive(Debug, PartialEq)]
+struct CommandLatency {
+    name: String,
+    average: Duration,
+    slowest: Duration,
+    runs: u64,
+}
+
+/// Commands by their rolling average latency, slowest first.
+fn slowest_commands(latencies: &HashMap<String, Latencies>, limit: usize) -> Vec<CommandLatency> {
+    let mut commands: Vec<CommandLatency> = latencies
+        .iter()
+        .map(|(name, entry)| CommandLatency {
+            name: name.clone(),
+            average: entry.average(),
+            slowest: entry.slowest,
+            runs: entry.runs,
+        })
+        .collect();
+    commands.sort_by(|a, b| b.average.cmp(&a.average).then_with(|| a.name.cmp(&b.name)));
+    commands.truncate(limit);
+    commands
+}
+
+fn format_latency(duration: Duration) -> String {
+    if duration < Duration::from_secs(1) {
+        format!("{}ms", duration.as_millis())
+    } else {
+        format!("{:.1}s", duration.as_secs_f64())
+    }
+}
+
+/// Show which commands are slowest to run, averaged over their recent runs
+#[poise::command(prefix_command, slash_command)]
+pub async fn botstats(ctx: Context<'_>) -> Result<(), Error> {
+    log::info!("Botstats command called by {}", ctx.author().name);
+
+    let (commands, total_runs) = {
+        let latencies = LATENCIES.lock();
+        (
+            slowest_commands(&latencies, SLOWEST_COMMANDS_LIMIT),
+            latencies.values().map(|entry| entry.runs).sum::<u64>(),
+        )
+    };
+    if commands.is_empty() {
+        ctx.say("⏱️ No commands have finished since the bot started.")
+            .await?;
+        return Ok(());
+    }
+
+    let lines = commands
+        .iter()
+        .enumerate()
+        .map(|(i, command)| {
+            format!(
+                "**{}.** `{}` — {} avg, {} slowest ({} run(s))",
+                i + 1,
+                command.name,
+                format_latency(command.average),
+                format_latency(command.slowest),
+                command.runs
+            )
+        })
+        .collect::<Vec<_>>()
+        .join("\n");
+
+    let embed = serenity::CreateEmbed::new()
+        .title("⏱️ Slowest Commands")
+        .color(0x5865f2)
+        .description(lines)
+        .footer(serenity::CreateEmbedFooter::new(format!(
+            "{} run(s) since the last restart • averages cover each command's last {} runs",
+            total_runs, LATENCY_WINDOW
+        )));
+    ctx.send(poise::CreateReply::default().embed(embed)).await?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_rolling_average() {
+        let mut entry = Latencies::default();
+        entry.record(Duration::from_secs(10));
+        for _ in 0..LATENCY_WINDOW {
+            entry.record(Duration::from_millis(100));
+        }
+        // The slow first run has rolled out of the average but not the maximum
+        assert_eq!(entry.average(), Duration::from_millis(100));
+        assert_eq!(entry.slowest, Duration::from_secs(10));
+        assert_eq!(entry.runs, LATENCY_WINDOW as u64 + 1);
+        assert_eq!(Latencies::default().average(), Duration::ZERO);
+    }
+
+    #[test]
+    fn test_slowest_commands() {
+        let mut latencies = HashMap::new();
+        record(&mut latencies, "ping", Duration::from_millis(50));
+        record(&mut latencies, "stats", Duration::from_secs(3));
+        record(&mut latencies, "stats", Duration::from_secs(1));
+        record(&mut latencies, "remind set", Duration::from_millis(200));
+
+        let slowest = slowest_commands(&latencies, 2);
+        assert_eq!(
+            slowest,
+            vec![
+                CommandLatency {
+                    name: "stats".to_string(),
+                    average: Duration::from_secs(2),
+                    slowest: Duration::from_secs(3),
+                    runs: 2,
+                },
+                CommandLatency {
+                    name: "remind set".to_string(),
+                    average: Duration::from_millis(200),
+                    slowest: Duration::from_millis(200),
+                    runs: 1,
+                },
+            ]
+        );
+        assert_eq!(format_latency(Duration::from_millis(250)), "250ms");
+        assert_eq!(format_latency(Duration::from_millis(2500)), "2.5s");
+    }
+}
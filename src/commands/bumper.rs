@@ -0,0 +1,809 @@
+//! Keeps a quiet channel going: once nobody has posted in it for a set
+//! while, the bot posts a conversation starter. Idleness is read from the
+//! channel's recent messages on every check, so nothing is tracked per
+//! message. Quiet hours follow the server's timezone (`-streak timezone`).
+
+use crate::commands::maintenance::Maintenance;
+use crate::commands::remind::{format_duration, parse_time_duration};
+use crate::commands::streak::guild_timezone;
+use crate::config_check::{check_store, ConfigProblem, LiveIds};
+use crate::storage;
+use crate::utils::check_store_writable;
+use crate::utils::limits::truncate;
+use crate::utils::permissions::check_channel_perms;
+use crate::{Context, Error};
+use chrono::{DateTime, Duration, Timelike, Utc};
+use parking_lot::RwLock;
+use poise::serenity_prelude as serenity;
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::Arc;
+use tokio::time::{interval, Duration as TokioDuration};
+
+const BUMPER_FILE: &str = "bumper.json";
+
+/// How often channels are checked, so a bump can be up to this late.
+const CHECK_INTERVAL_SECS: u64 = 5 * 60;
+
+/// Messages read per check. If none of them is from a human, the last human
+/// message is older than the oldest of them.
+const RECENT_MESSAGES: u8 = 50;
+
+const MIN_IDLE_MINUTES: i64 = 30;
+const MAX_IDLE_DAYS: i64 = 30;
+
+const MAX_STARTERS: usize = 100;
+const MAX_STARTER_CHARS: usize = 300;
+
+/// Used while a server hasn't added starters of its own.
+const DEFAULT_STARTERS: &[&str] = &[
+    "What's something you learned recently that surprised you?",
+    "What are you working on this week?",
+    "Which game have you sunk the most hours into?",
+    "What's the best thing you've eaten lately?",
+    "If you could master one skill overnight, what would it be?",
+    "What's a song you've had on repeat?",
+    "Any good shows or films to recommend?",
+    "What's the most useless talent you have?",
+    "Cats, dogs, or something stranger?",
+    "What's a small thing that made your day better recently?",
+];
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+struct Starter {
+    id: u64,
+    text: String,
+}
+
+/// Hours of the day (in the server's timezone) the bot doesn't bump in.
+/// `start` is inclusive and `end` exclusive, and the range may wrap past
+/// midnight.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq)]
+struct QuietHours {
+    start: u8,
+    end: u8,
+}
+
+impl QuietHours {
+    fn contains(&self, hour: u8) -> bool {
+        if self.start <= self.end {
+            (self.start..self.end).contains(&hour)
+        } else {
+            hour >= self.start || hour < self.end
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+struct BumperSchedule {
+    channel_id: u64,
+    idle_minutes: i64,
+    /// The bot's latest bump here. No new one goes out until a human has
+    /// posted after it.
+    last_bump: Option<u64>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq)]
+struct GuildBumper {
+    /// Where and after how long to bump; `None` while the bumper is off.
+    /// Starters and quiet hours are kept either way.
+    schedule: Option<BumperSchedule>,
+    quiet_hours: Option<QuietHours>,
+    starters: Vec<Starter>,
+    next_starter_id: u64,
+}
+
+#[derive(Serialize, Deserialize, Default, Clone)]
+struct BumperData {
+    guilds: HashMap<u64, GuildBumper>,
+}
+
+lazy_static::lazy_static! {
+    static ref BUMPERS: RwLock<BumperData> = RwLock::new(storage::load(BUMPER_FILE));
+}
+
+/// Re-read the bumper settings and starters from disk.
+pub fn reload_bumpers() -> Result<(), Error> {
+    *BUMPERS.write() = storage::reload(BUMPER_FILE)?;
+    Ok(())
+}
+
+/// Where a guild's bumper posts, if it's on.
+pub fn bumper_channel(guild_id: u64) -> Option<u64> {
+    BUMPERS
+        .read()
+        .guilds
+        .get(&guild_id)
+        .and_then(|bumper| bumper.schedule.as_ref())
+        .map(|schedule| schedule.channel_id)
+}
+
+fn save_bumpers(data: &BumperData) {
+    if let Err(e) = storage::save(BUMPER_FILE, data) {
+        log::error!("Failed to save bumper settings: {}", e);
+    }
+}
+
+/// Drop an orphaned guild's bump schedule.
+pub fn purge_guild(guild_id: u64) {
+    let mut data = BUMPERS.write();
+    if data.guilds.remove(&guild_id).is_some() {
+        save_bumpers(&data);
+    }
+}
+
+/// Turn off bumpers whose channel was deleted or whose idle time
+/// `-bumper set` wouldn't take (a negative one would bump on every check),
+/// and drop quiet hours that aren't hours.
+fn validate_bumpers(data: &mut BumperData, live: &LiveIds) -> Vec<ConfigProblem> {
+    let mut problems = Vec::new();
+    let idle_range = MIN_IDLE_MINUTES..=MAX_IDLE_DAYS * 24 * 60;
+    for (&guild_id, bumper) in &mut data.guilds {
+        if let Some(schedule) = bumper.schedule.take() {
+            if live.channel_gone(guild_id, schedule.channel_id) {
+                problems.push(ConfigProblem::deleted_channel(
+                    guild_id,
+                    "schedule.channel_id",
+                    schedule.channel_id,
+                ));
+            } else if !idle_range.contains(&schedule.idle_minutes) {
+                problems.push(ConfigProblem::invalid(
+                    guild_id,
+                    "schedule.idle_minutes",
+                    format!(
+                        "{} minutes, must be {}-{}",
+                        schedule.idle_minutes,
+                        idle_range.start(),
+                        idle_range.end()
+                    ),
+                    &schedule,
+                ));
+            } else {
+                bumper.schedule = Some(schedule);
+            }
+        }
+        if let Some(quiet) = bumper
+            .quiet_hours
+            .take_if(|quiet| quiet.start > 23 || quiet.end > 23)
+        {
+            problems.push(ConfigProblem::invalid(
+                guild_id,
+                "quiet_hours",
+                format!("{}-{} aren't hours of the day", quiet.start, quiet.end),
+                quiet,
+            ));
+        }
+    }
+    problems
+}
+
+/// Check the bumper settings, see [`crate::config_check`].
+pub fn check_bumpers(live: &LiveIds, quarantine: bool) -> Vec<ConfigProblem> {
+    check_store(
+        &BUMPERS,
+        quarantine,
+        |data| validate_bumpers(data, live),
+        save_bumpers,
+    )
+}
+
+/// Command check for commands that change the bumper settings.
+async fn bumper_writable(ctx: Context<'_>) -> Result<bool, Error> {
+    check_store_writable(ctx, &storage::data_path(BUMPER_FILE)).await
+}
+
+/// Whether a channel is due a bump, given its newest messages (newest
+/// first) and the bot's last bump there.
+fn bump_due(
+    recent: &[serenity::Message],
+    last_bump: Option<u64>,
+    idle: Duration,
+    now: DateTime<Utc>,
+) -> bool {
+    let human = recent.iter().find(|message| !message.author.bot);
+    // Bumping again before anyone answered the last one would just be spam
+    if let Some(last_bump) = last_bump {
+        if human.is_none_or(|message| message.id.get() < last_bump) {
+            return false;
+        }
+    }
+    let last_activity = human
+        .or(recent.last())
+        .map(|message| message.timestamp.timestamp());
+    last_activity.is_none_or(|at| now.timestamp() - at >= idle.num_seconds())
+}
+
+/// A random starter from the server's own list, or a built-in one while
+/// the list is empty.
+fn pick_starter<R: Rng + ?Sized>(starters: &[Starter], rng: &mut R) -> String {
+    if starters.is_empty() {
+        DEFAULT_STARTERS[rng.gen_range(0..DEFAULT_STARTERS.len())].to_string()
+    } else {
+        starters[rng.gen_range(0..starters.len())].text.clone()
+    }
+}
+
+/// Start the background task that bumps idle channels.
+pub fn start_bumper(http: Arc<serenity::Http>, maintenance: Arc<Maintenance>) {
+    tokio::spawn(async move {
+        let mut interval = interval(TokioDuration::from_secs(CHECK_INTERVAL_SECS));
+
+        loop {
+            interval.tick().await;
+
+            // Skip the run during maintenance and pick up on the next tick
+            if maintenance.is_enabled() {
+                continue;
+            }
+
+            let now = Utc::now();
+            let candidates: Vec<(u64, BumperSchedule)> = BUMPERS
+                .read()
+                .guilds
+                .iter()
+                .filter_map(|(guild_id, bumper)| {
+                    let schedule = bumper.schedule.clone()?;
+                    let hour = now.with_timezone(&guild_timezone(*guild_id)).hour() as u8;
+                    let quiet = bumper.quiet_hours.is_some_and(|quiet| quiet.contains(hour));
+                    (!quiet).then_some((*guild_id, schedule))
+                })
+                .collect();
+
+            for (guild_id, schedule) in candidates {
+                if let Err(e) = check_channel(&http, guild_id, &schedule, now).await {
+                    log::warn!("Bumper check failed for guild {}: {}", guild_id, e);
+                }
+            }
+        }
+    });
+}
+
+async fn check_channel(
+    http: &serenity::Http,
+    guild_id: u64,
+    schedule: &BumperSchedule,
+    now: DateTime<Utc>,
+) -> Result<(), Error> {
+    let channel = serenity::ChannelId::new(schedule.channel_id);
+    let recent = channel
+        .messages(http, serenity::GetMessages::new().limit(RECENT_MESSAGES))
+        .await?;
+    if !bump_due(
+        &recent,
+        schedule.last_bump,
+        Duration::minutes(schedule.idle_minutes),
+        now,
+    ) {
+        return Ok(());
+    }
+
+    let text = {
+        let data = BUMPERS.read();
+        let starters = data
+            .guilds
+            .get(&guild_id)
+            .map(|bumper| bumper.starters.as_slice())
+            .unwrap_or_default();
+        pick_starter(starters, &mut rand::thread_rng())
+    };
+    let message = channel
+        .send_message(
+            http,
+            serenity::CreateMessage::new()
+                .content(format!("💬 {}", text))
+                .allowed_mentions(serenity::CreateAllowedMentions::new()),
+        )
+        .await?;
+
+    let mut data = BUMPERS.write();
+    // Only if the bumper wasn't moved or turned off while posting
+    if let Some(current) = data
+        .guilds
+        .get_mut(&guild_id)
+        .and_then(|bumper| bumper.schedule.as_mut())
+        .filter(|current| current.channel_id == schedule.channel_id)
+    {
+        current.last_bump = Some(message.id.get());
+        save_bumpers(&data);
+    }
+    log::info!(
+        "Bumped channel {} in guild {}",
+        schedule.channel_id,
+        guild_id
+    );
+    Ok(())
+}
+
+/// Post a conversation starter when a channel has been quiet too long
+#[poise::command(
+    prefix_command,
+    slash_command,
+    guild_only,
+    required_permissions = "MANAGE_CHANNELS",
+    subcommands(
+        "bumper_set",
+        "bumper_off",
+        "bumper_quiet",
+        "bumper_add",
+        "bumper_remove",
+        "bumper_list"
+    )
+)]
+pub async fn bumper(_ctx: Context<'_>) -> Result<(), Error> {
+    Ok(())
+}
+
+/// Bump a channel once nobody has posted in it for this long
+#[poise::command(
+    prefix_command,
+    slash_command,
+    guild_only,
+    rename = "set",
+    required_permissions = "MANAGE_CHANNELS",
+    check = "bumper_writable"
+)]
+pub async fn bumper_set(
+    ctx: Context<'_>,
+    #[description = "How long the channel must be quiet, e.g. 6h or 2d"] duration: String,
+    #[description = "Channel to keep going (default: this one)"] channel: Option<
+        serenity::GuildChannel,
+    >,
+) -> Result<(), Error> {
+    log::info!("Bumper set command called by {}", ctx.author().name);
+
+    let Some(guild_id) = ctx.guild_id() else {
+        return Ok(());
+    };
+    let Some(idle) = parse_time_duration(&duration) else {
+        ctx.say(format!(
+            "❌ Couldn't read `{}` as a duration. Try something like `6h` or `2d`.",
+            truncate(&duration.replace('`', "'"), 30)
+        ))
+        .await?;
+        return Ok(());
+    };
+    if idle < Duration::minutes(MIN_IDLE_MINUTES) || idle > Duration::days(MAX_IDLE_DAYS) {
+        ctx.say(format!(
+            "❌ The duration must be between {} minutes and {} days.",
+            MIN_IDLE_MINUTES, MAX_IDLE_DAYS
+        ))
+        .await?;
+        return Ok(());
+    }
+    let channel_id = match channel {
+        Some(channel) if channel.guild_id != guild_id => {
+            ctx.say("❌ That channel isn't in this server!").await?;
+            return Ok(());
+        }
+        Some(channel) => channel.id,
+        None => ctx.channel_id(),
+    };
+    // It reads the channel to tell whether it's quiet, then posts in it
+    let required = [
+        serenity::Permissions::VIEW_CHANNEL,
+        serenity::Permissions::READ_MESSAGE_HISTORY,
+        serenity::Permissions::SEND_MESSAGES,
+    ];
+    if let Err(missing) = check_channel_perms(ctx, channel_id, &required).await {
+        ctx.say(missing.to_string()).await?;
+        return Ok(());
+    }
+
+    let quiet_hours = {
+        let mut data = BUMPERS.write();
+        let bumper = data.guilds.entry(guild_id.get()).or_default();
+        bumper.schedule = Some(BumperSchedule {
+            channel_id: channel_id.get(),
+            idle_minutes: idle.num_minutes(),
+            last_bump: None,
+        });
+        let quiet_hours = bumper.quiet_hours;
+        save_bumpers(&data);
+        quiet_hours
+    };
+
+    let mut reply = format!(
+        "✅ I'll post a conversation starter in <#{}> whenever nobody has posted there for {}.",
+        channel_id,
+        format_duration(idle)
+    );
+    if let Some(quiet) = quiet_hours {
+        reply.push_str(&format!(
+            " Not between {:02}:00 and {:02}:00 ({}).",
+            quiet.start,
+            quiet.end,
+            guild_timezone(guild_id.get()).name()
+        ));
+    }
+    ctx.say(reply).await?;
+    Ok(())
+}
+
+/// Stop bumping. The starter list is kept
+#[poise::command(
+    prefix_command,
+    slash_command,
+    guild_only,
+    rename = "off",
+    required_permissions = "MANAGE_CHANNELS",
+    check = "bumper_writable"
+)]
+pub async fn bumper_off(ctx: Context<'_>) -> Result<(), Error> {
+    log::info!("Bumper off command called by {}", ctx.author().name);
+
+    let Some(guild_id) = ctx.guild_id() else {
+        return Ok(());
+    };
+    let removed = {
+        let mut data = BUMPERS.write();
+        let removed = data
+            .guilds
+            .get_mut(&guild_id.get())
+            .and_then(|bumper| bumper.schedule.take())
+            .is_some();
+        if removed {
+            save_bumpers(&data);
+        }
+        removed
+    };
+
+    if removed {
+        ctx.say("✅ The bumper is off.").await?;
+    } else {
+        ctx.say("❌ The bumper isn't on in this server.").await?;
+    }
+    Ok(())
+}
+
+/// Don't bump between two hours of the day, or leave both out to allow any time
+#[poise::command(
+    prefix_command,
+    slash_command,
+    guild_only,
+    rename = "quiet",
+    required_permissions = "MANAGE_CHANNELS",
+    check = "bumper_writable"
+)]
+pub async fn bumper_quiet(
+    ctx: Context<'_>,
+    #[description = "Hour quiet time starts (0-23)"] start: Option<u8>,
+    #[description = "Hour quiet time ends (0-23)"] end: Option<u8>,
+) -> Result<(), Error> {
+    log::info!("Bumper quiet command called by {}", ctx.author().name);
+
+    let Some(guild_id) = ctx.guild_id() else {
+        return Ok(());
+    };
+    let quiet_hours = match (start, end) {
+        (None, None) => None,
+        (Some(start), Some(end)) if start <= 23 && end <= 23 && start != end => {
+            Some(QuietHours { start, end })
+        }
+        (Some(_), Some(_)) => {
+            ctx.say("❌ Hours must be 0-23 and the start and end must differ!")
+                .await?;
+            return Ok(());
+        }
+        _ => {
+            ctx.say("❌ Give both a start and an end hour, e.g. `-bumper quiet 23 8`, or neither to turn quiet hours off.")
+                .await?;
+            return Ok(());
+        }
+    };
+    {
+        let mut data = BUMPERS.write();
+        data.guilds.entry(guild_id.get()).or_default().quiet_hours = quiet_hours;
+        save_bumpers(&data);
+    }
+
+    match quiet_hours {
+        Some(quiet) => {
+            ctx.say(format!(
+                "✅ No bumps between {:02}:00 and {:02}:00 ({}).",
+                quiet.start,
+                quiet.end,
+                guild_timezone(guild_id.get()).name()
+            ))
+            .await?;
+        }
+        None => {
+            ctx.say("✅ Quiet hours are off, bumps can go out at any time.")
+                .await?;
+        }
+    }
+    Ok(())
+}
+
+/// Add a conversation starter to this server's list
+#[poise::command(
+    prefix_command,
+    slash_command,
+    guild_only,
+    rename = "add",
+    required_permissions = "MANAGE_CHANNELS",
+    check = "bumper_writable"
+)]
+pub async fn bumper_add(
+    ctx: Context<'_>,
+    #[description = "The conversation starter"]
+    #[rest]
+    text: String,
+) -> Result<(), Error> {
+    log::info!("Bumper add command called by {}", ctx.author().name);
+
+    let Some(guild_id) = ctx.guild_id() else {
+        return Ok(());
+    };
+    let text = text.trim();
+    if text.is_empty() {
+        ctx.say("❌ The starter can't be empty!").await?;
+        return Ok(());
+    }
+    if text.chars().count() > MAX_STARTER_CHARS {
+        ctx.say(format!(
+            "❌ Starters can be at most {} characters long.",
+            MAX_STARTER_CHARS
+        ))
+        .await?;
+        return Ok(());
+    }
+
+    let added = {
+        let mut data = BUMPERS.write();
+        let bumper = data.guilds.entry(guild_id.get()).or_default();
+        if bumper.starters.len() >= MAX_STARTERS {
+            None
+        } else {
+            bumper.next_starter_id += 1;
+            let id = bumper.next_starter_id;
+            bumper.starters.push(Starter {
+                id,
+                text: text.to_string(),
+            });
+            save_bumpers(&data);
+            Some(id)
+        }
+    };
+
+    match added {
+        Some(id) => {
+            ctx.say(format!(
+                "✅ Added starter #{}. While the list has any, only this server's starters are used.",
+                id
+            ))
+            .await?;
+        }
+        None => {
+            ctx.say(format!(
+                "❌ This server already has {} starters. Remove some with `-bumper remove <id>`.",
+                MAX_STARTERS
+            ))
+            .await?;
+        }
+    }
+    Ok(())
+}
+
+/// Remove a conversation starter from this server's list
+#[poise::command(
+    prefix_command,
+    slash_command,
+    guild_only,
+    rename = "remove",
+    required_permissions = "MANAGE_CHANNELS",
+    check = "bumper_writable"
+)]
+pub async fn bumper_remove(
+    ctx: Context<'_>,
+    #[description = "Starter ID from `-bumper list`"] id: u64,
+) -> Result<(), Error> {
+    log::info!("Bumper remove command called by {}", ctx.author().name);
+
+    let Some(guild_id) = ctx.guild_id() else {
+        return Ok(());
+    };
+    let removed = {
+        let mut data = BUMPERS.write();
+        let removed = data.guilds.get_mut(&guild_id.get()).and_then(|bumper| {
+            let index = bumper
+                .starters
+                .iter()
+                .position(|starter| starter.id == id)?;
+            Some(bumper.starters.remove(index))
+        });
+        if removed.is_some() {
+            save_bumpers(&data);
+        }
+        removed
+    };
+
+    match removed {
+        Some(starter) => {
+            ctx.say(format!(
+                "✅ Removed starter #{}: {}",
+                starter.id,
+                truncate(&starter.text, 100)
+            ))
+            .await?;
+        }
+        None => {
+            ctx.say(format!("❌ No starter #{} in this server.", id))
+                .await?;
+        }
+    }
+    Ok(())
+}
+
+/// Show the bumper settings and this server's starters
+#[poise::command(
+    prefix_command,
+    slash_command,
+    guild_only,
+    rename = "list",
+    required_permissions = "MANAGE_CHANNELS"
+)]
+pub async fn bumper_list(ctx: Context<'_>) -> Result<(), Error> {
+    let Some(guild_id) = ctx.guild_id() else {
+        return Ok(());
+    };
+    let bumper = BUMPERS
+        .read()
+        .guilds
+        .get(&guild_id.get())
+        .cloned()
+        .unwrap_or_default();
+    let tz = guild_timezone(guild_id.get());
+
+    let mut lines = vec![match &bumper.schedule {
+        Some(schedule) => format!(
+            "💬 Bumping <#{}> after {} without a message.",
+            schedule.channel_id,
+            format_duration(Duration::minutes(schedule.idle_minutes))
+        ),
+        None => {
+            "💤 The bumper is off. Turn it on with `-bumper set <duration> [channel]`.".to_string()
+        }
+    }];
+    if let Some(quiet) = bumper.quiet_hours {
+        lines.push(format!(
+            "🌙 Quiet hours: {:02}:00-{:02}:00 ({})",
+            quiet.start,
+            quiet.end,
+            tz.name()
+        ));
+    }
+    if bumper.starters.is_empty() {
+        lines.push(format!(
+            "No starters added yet, so the {} built-in ones are used. Add one with `-bumper add <text>`.",
+            DEFAULT_STARTERS.len()
+        ));
+    } else {
+        lines.push(format!("**Starters ({}):**", bumper.starters.len()));
+        lines.extend(
+            bumper
+                .starters
+                .iter()
+                .map(|starter| format!("`#{}` {}", starter.id, truncate(&starter.text, 100))),
+        );
+    }
+
+    ctx.send(
+        poise::CreateReply::default()
+            .content(truncate(&lines.join("\n"), 2000))
+            .allowed_mentions(serenity::CreateAllowedMentions::new()),
+    )
+    .await?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::random::SeededRng;
+
+    #[test]
+    fn test_validate_bumpers() {
+        let mut data: BumperData =
+            serde_json::from_str(include_str!("../../tests/fixtures/config/bumper.json")).unwrap();
+        let live = LiveIds::default()
+            .with_guild(1, &[10], &[])
+            .with_guild(2, &[10], &[])
+            .with_guild(3, &[10], &[]);
+
+        let mut problems = validate_bumpers(&mut data, &live);
+        problems.sort_by_key(|problem| (problem.guild_id, problem.field.clone()));
+        let found: Vec<(Option<u64>, &str)> = problems
+            .iter()
+            .map(|problem| (problem.guild_id, problem.problem.as_str()))
+            .collect();
+        assert_eq!(
+            found,
+            [
+                (Some(2), "channel 11 was deleted"),
+                (Some(3), "24-7 aren't hours of the day"),
+                (Some(3), "-5 minutes, must be 30-43200"),
+            ]
+        );
+        assert!(data.guilds[&1].schedule.is_some());
+        assert!(data.guilds[&1].quiet_hours.is_some());
+        assert!(data.guilds[&2].schedule.is_none());
+        assert!(data.guilds[&3].schedule.is_none());
+        assert!(data.guilds[&3].quiet_hours.is_none());
+        // Starters stay either way
+        assert_eq!(data.guilds[&3].starters.len(), 1);
+        assert!(validate_bumpers(&mut data, &live).is_empty());
+    }
+
+    fn message(id: u64, at: DateTime<Utc>, bot: bool) -> serenity::Message {
+        let mut message = serenity::Message::default();
+        message.id = serenity::MessageId::new(id);
+        message.timestamp = at.into();
+        message.author.bot = bot;
+        message
+    }
+
+    #[test]
+    fn test_bump_due() {
+        let now = Utc::now();
+        let idle = Duration::hours(6);
+        let ago = |hours| now - Duration::hours(hours);
+
+        // An empty channel is as quiet as it gets
+        assert!(bump_due(&[], None, idle, now));
+        assert!(!bump_due(&[message(2, ago(1), false)], None, idle, now));
+        assert!(bump_due(&[message(2, ago(7), false)], None, idle, now));
+        // Other bots don't count as activity
+        assert!(bump_due(
+            &[message(3, ago(1), true), message(2, ago(7), false)],
+            None,
+            idle,
+            now
+        ));
+
+        // Nobody has answered the last bump yet
+        let bumped = [message(3, ago(8), true), message(2, ago(9), false)];
+        assert!(!bump_due(&bumped, Some(3), idle, now));
+        assert!(!bump_due(&[message(3, ago(8), true)], Some(3), idle, now));
+        // Somebody did, and it has gone quiet again since
+        let answered = [message(4, ago(7), false), message(3, ago(8), true)];
+        assert!(bump_due(&answered, Some(3), idle, now));
+
+        // No human among the recent messages: the oldest read bounds it
+        let bots_only = [message(5, ago(2), true), message(4, ago(3), true)];
+        assert!(!bump_due(&bots_only, None, idle, now));
+        let bots_only = [message(5, ago(2), true), message(4, ago(7), true)];
+        assert!(bump_due(&bots_only, None, idle, now));
+    }
+
+    #[test]
+    fn test_quiet_hours() {
+        let night = QuietHours { start: 23, end: 8 };
+        assert!(night.contains(23));
+        assert!(night.contains(3));
+        assert!(!night.contains(8));
+        assert!(!night.contains(22));
+
+        let afternoon = QuietHours { start: 13, end: 15 };
+        assert!(afternoon.contains(13));
+        assert!(afternoon.contains(14));
+        assert!(!afternoon.contains(15));
+        assert!(!afternoon.contains(0));
+    }
+
+    #[test]
+    fn test_pick_starter() {
+        for seed in 0..20 {
+            let text = pick_starter(&[], &mut SeededRng::new(seed));
+            assert!(DEFAULT_STARTERS.contains(&text.as_str()), "{text}");
+        }
+        let own = [Starter {
+            id: 1,
+            text: "Favourite sandwich?".to_string(),
+        }];
+        assert_eq!(
+            pick_starter(&own, &mut SeededRng::new(3)),
+            "Favourite sandwich?"
+        );
+    }
+}
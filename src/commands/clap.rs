@@ -0,0 +1,15 @@
+use crate::utils::transforms::{clapify, run_transform};
+use crate::{Context, Error};
+
+/// Put 👏 between 👏 every 👏 word, or reply to a message to clap it
+#[poise::command(prefix_command, slash_command)]
+pub async fn clap(
+    ctx: Context<'_>,
+    #[description = "Text to clap (leave empty to clap replied message)"]
+    #[rest]
+    text: Option<String>,
+) -> Result<(), Error> {
+    log::info!("Clap command called by {}", ctx.author().name);
+
+    run_transform(ctx, text, "clap", clapify).await
+}
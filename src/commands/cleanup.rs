@@ -1,4 +1,7 @@
 use crate::utils::is_protected_user;
+use crate::utils::messages::parse_message_ref;
+use crate::utils::output::ProgressReporter;
+use crate::utils::permissions::{check_channel_perms, history_error_message};
 use crate::{Context, Error};
 use poise::serenity_prelude as serenity;
 use tokio::time::{sleep, Duration};
@@ -9,11 +12,14 @@ pub async fn cleanup(
     ctx: Context<'_>,
     #[description = "Number of messages to delete OR 'after' to delete after replied message"]
     count_or_after: Option<String>,
+    #[description = "Delete everything after this message (ID or link, implies 'after')"]
+    after_message: Option<String>,
 ) -> Result<(), Error> {
     log::info!(
-        "Cleanup command called by {} with argument: {:?}",
+        "Cleanup command called by {} with arguments: {:?} {:?}",
         ctx.author().name,
-        count_or_after
+        count_or_after,
+        after_message
     );
 
     // Check if we're in a guild (server) and not in DMs
@@ -32,37 +38,59 @@ pub async fn cleanup(
         return Ok(());
     }
 
+    // Find out now rather than after the first page of deletes
+    let required = [
+        serenity::Permissions::VIEW_CHANNEL,
+        serenity::Permissions::READ_MESSAGE_HISTORY,
+        serenity::Permissions::MANAGE_MESSAGES,
+    ];
+    if let Err(missing) = check_channel_perms(ctx, channel_id, &required).await {
+        ctx.say(missing.to_string()).await?;
+        return Ok(());
+    }
+
     // Check if this is "after" mode or count mode
-    let is_after_mode = count_or_after
-        .as_ref()
-        .map(|s| s.to_lowercase() == "after")
-        .unwrap_or(false);
+    let is_after_mode = after_message.is_some()
+        || count_or_after
+            .as_ref()
+            .map(|s| s.to_lowercase() == "after")
+            .unwrap_or(false);
 
-    // Handle "after" mode - delete messages after the replied message
+    // Handle "after" mode - delete messages after the given or replied message
     if is_after_mode {
-        // Try to get the replied message from prefix context
-        let replied_msg_id = match ctx {
-            poise::Context::Prefix(prefix_ctx) => {
-                prefix_ctx.msg.referenced_message.as_ref().map(|msg| msg.id)
+        let after_id = if let Some(text) = &after_message {
+            match resolve_after_message(text, channel_id) {
+                Ok(id) => id,
+                Err(message) => {
+                    ctx.say(message).await?;
+                    return Ok(());
+                }
             }
-            poise::Context::Application(_) => {
-                ctx.say("❌ The 'after' option only works with prefix commands (starting with -)! Please reply to a message and use `-cleanup after true`")
+        } else {
+            // Fall back to the replied message from prefix context
+            let replied_msg_id = match ctx {
+                poise::Context::Prefix(prefix_ctx) => {
+                    prefix_ctx.msg.referenced_message.as_ref().map(|msg| msg.id)
+                }
+                poise::Context::Application(_) => None,
+            };
+            let Some(after_id) = replied_msg_id else {
+                ctx.say("❌ Reply to a message or give its ID or link (`after_message`) when using the `after` option!")
                     .await?;
                 return Ok(());
-            }
-        };
-
-        let Some(after_id) = replied_msg_id else {
-            ctx.say("❌ You must reply to a message when using the `after` option!")
-                .await?;
-            return Ok(());
+            };
+            after_id
         };
 
-        ctx.say("🧹 Starting cleanup after the specified message... This may take a while to avoid rate limits.")
-            .await?;
+        let progress = ProgressReporter::say(
+            ctx,
+            "🧹 Starting cleanup after the specified message... This may take a while to avoid rate limits.",
+        )
+        .await?;
 
         let mut deleted_count = 0u64;
-        let mut last_message_id = None;
+        // Start below the progress message so it isn't cleaned up itself
+        let mut last_message_id = Some(progress.message_id().await?);
 
         loop {
             // Fetch messages after the specified message
@@ -78,28 +106,22 @@ pub async fn cleanup(
             {
                 Ok(msgs) => msgs,
                 Err(e) => {
-                    ctx.say(format!("❌ Error fetching messages: {e}")).await?;
+                    progress.set(history_error_message(&e, channel_id)).await?;
                     return Ok(());
                 }
             };
 
-            if messages.is_empty() {
-                break;
-            }
+            // Page from the raw batch, not from what gets deleted: a batch
+            // without any matching messages must not end the loop early
+            let ids: Vec<serenity::MessageId> = messages.iter().map(|msg| msg.id).collect();
+            let next_page = next_cursor(&ids, after_id);
 
-            // Filter messages that are after the replied message
+            // Filter messages that are after the target message
             let messages_to_delete: Vec<_> = messages
                 .into_iter()
                 .filter(|msg| msg.id > after_id)
                 .collect();
 
-            if messages_to_delete.is_empty() {
-                break;
-            }
-
-            // Update last_message_id for pagination
-            last_message_id = messages_to_delete.last().map(|msg| msg.id);
-
             // Delete messages in batches of 100 (Discord's limit)
             for chunk in messages_to_delete.chunks(100) {
                 let message_ids: Vec<serenity::MessageId> =
@@ -143,12 +165,21 @@ pub async fn cleanup(
                 // Rate limit protection - wait between batches
                 sleep(Duration::from_millis(500)).await;
             }
+
+            match next_page {
+                Some(cursor) => last_message_id = Some(cursor),
+                None => break,
+            }
+            progress
+                .set(format!("🧹 Deleted {deleted_count} messages so far..."))
+                .await?;
         }
 
-        ctx.say(format!(
-            "✅ Cleanup complete! Deleted {deleted_count} messages after the specified message."
-        ))
-        .await?;
+        progress
+            .set(format!(
+                "✅ Cleanup complete! Deleted {deleted_count} messages after the specified message."
+            ))
+            .await?;
 
         return Ok(());
     }
@@ -176,10 +207,15 @@ pub async fn cleanup(
         return Ok(());
     }
 
-    ctx.say(format!(
-        "🧹 Starting cleanup of {delete_count} messages... This may take a while to avoid rate limits."
-    ))
+    let progress = ProgressReporter::say(
+        ctx,
+        format!(
+            "🧹 Starting cleanup of {delete_count} messages... This may take a while to avoid rate limits."
+        ),
+    )
     .await?;
+    // Only messages from before the progress message are cleaned up
+    let progress_id = progress.message_id().await?;
 
     let mut deleted_count = 0u64;
     let mut remaining = delete_count;
@@ -191,13 +227,15 @@ pub async fn cleanup(
         let messages = match channel_id
             .messages(
                 &ctx.serenity_context().http,
-                serenity::GetMessages::new().limit(batch_size as u8),
+                serenity::GetMessages::new()
+                    .before(progress_id)
+                    .limit(batch_size as u8),
             )
             .await
         {
             Ok(msgs) => msgs,
             Err(e) => {
-                ctx.say(format!("❌ Error fetching messages: {e}")).await?;
+                progress.set(history_error_message(&e, channel_id)).await?;
                 return Ok(());
             }
         };
@@ -246,15 +284,95 @@ pub async fn cleanup(
         }
 
         remaining = remaining.saturating_sub(message_count as u64);
+        if remaining > 0 {
+            progress
+                .set(format!(
+                    "🧹 Deleted {deleted_count}/{delete_count} messages so far..."
+                ))
+                .await?;
+        }
 
         // Rate limit protection - wait between batches
         sleep(Duration::from_millis(1000)).await; // 1 second delay to be safe
     }
 
-    ctx.say(format!(
-        "✅ Cleanup complete! Deleted {deleted_count} messages."
-    ))
-    .await?;
+    progress
+        .set(format!(
+            "✅ Cleanup complete! Deleted {deleted_count} messages."
+        ))
+        .await?;
 
     Ok(())
 }
+
+/// The message `after` mode deletes after, from a message ID or link. Links
+/// to other channels are refused since only the current one is cleaned up.
+fn resolve_after_message(
+    text: &str,
+    channel_id: serenity::ChannelId,
+) -> Result<serenity::MessageId, String> {
+    match parse_message_ref(text, channel_id) {
+        Some((channel, message)) if channel == channel_id => Ok(message),
+        Some((channel, _)) => Err(format!(
+            "❌ That message is in <#{}>. Run the cleanup there instead.",
+            channel
+        )),
+        None => Err(format!("❌ `{}` isn't a message ID or link.", text.trim())),
+    }
+}
+
+/// Where to fetch the next page from in `after` mode: before the oldest
+/// message of this batch, or `None` once the batch is empty or reached the
+/// target message (everything older is kept).
+fn next_cursor(
+    batch: &[serenity::MessageId],
+    after_id: serenity::MessageId,
+) -> Option<serenity::MessageId> {
+    batch
+        .iter()
+        .min()
+        .copied()
+        .filter(|oldest| *oldest > after_id)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_resolve_after_message() {
+        let here = serenity::ChannelId::new(20);
+        assert_eq!(
+            resolve_after_message("https://discord.com/channels/10/20/30", here),
+            Ok(serenity::MessageId::new(30))
+        );
+        assert_eq!(
+            resolve_after_message(" 30 ", here),
+            Ok(serenity::MessageId::new(30))
+        );
+        assert!(
+            resolve_after_message("https://discord.com/channels/10/21/30", here)
+                .unwrap_err()
+                .contains("<#21>")
+        );
+        assert!(resolve_after_message("yesterday", here)
+            .unwrap_err()
+            .contains("isn't a message ID or link"));
+    }
+
+    #[test]
+    fn test_next_cursor() {
+        let ids = |ids: &[u64]| -> Vec<serenity::MessageId> {
+            ids.iter().map(|id| serenity::MessageId::new(*id)).collect()
+        };
+        let after = serenity::MessageId::new(100);
+        // Whole batch is newer: keep paging from its oldest message
+        assert_eq!(
+            next_cursor(&ids(&[300, 250, 200]), after),
+            Some(serenity::MessageId::new(200))
+        );
+        // Reached the target: stop
+        assert_eq!(next_cursor(&ids(&[150, 120, 100, 90]), after), None);
+        assert_eq!(next_cursor(&[], after), None);
+    }
+}
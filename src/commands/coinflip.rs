@@ -1,18 +1,74 @@
+use crate::utils::is_protected_user;
 use crate::{Context, Error};
 use rand::Rng;
 use tokio::time::{sleep, Duration};
 
+/// Flip a coin that lands heads with probability `bias` (clamped to
+/// 0.0–1.0). Returns whether it landed heads.
+pub fn flip<R: Rng + ?Sized>(bias: f64, rng: &mut R) -> bool {
+    rng.gen::<f64>() < bias.clamp(0.0, 1.0)
+}
+
+/// The name and emoji for a side of the coin.
+fn coin_side(heads: bool) -> (&'static str, &'static str) {
+    if heads {
+        ("Heads", "🪙")
+    } else {
+        ("Tails", "🔄")
+    }
+}
+
 /// Simulates a coin flip and announces the result
-#[poise::command(prefix_command, slash_command)]
+#[poise::command(
+    prefix_command,
+    slash_command,
+    aliases("cf"),
+    subcommands("coinflip_rigged")
+)]
 pub async fn coinflip(ctx: Context<'_>) -> Result<(), Error> {
     log::info!("Coinflip command called by {}", ctx.author().name);
 
-    // Generate random number (0 or 1)
-    let result = {
-        let mut rng = rand::thread_rng();
-        rng.gen_range(0..2)
-    };
+    let heads = flip(0.5, &mut rand::thread_rng());
+    announce_flip(ctx, heads).await
+}
+
+/// Flip a weighted coin for testing (owner only)
+#[poise::command(prefix_command, hide_in_help, rename = "rigged")]
+pub async fn coinflip_rigged(
+    ctx: Context<'_>,
+    #[description = "Chance of heads, from 0.0 to 1.0"] bias: f64,
+) -> Result<(), Error> {
+    log::info!(
+        "Coinflip rigged command called by {} with bias {}",
+        ctx.author().name,
+        bias
+    );
+
+    if !is_protected_user(&ctx.author().name) {
+        ctx.say("❌ You don't have permission to use this command!")
+            .await?;
+        return Ok(());
+    }
+    if !(0.0..=1.0).contains(&bias) {
+        ctx.say("❌ The bias must be between 0.0 and 1.0!").await?;
+        return Ok(());
+    }
+
+    let heads = flip(bias, &mut rand::thread_rng());
+    announce_flip(ctx, heads).await
+}
 
+/// The message announcing which side the coin landed on.
+pub fn flip_message(player: &str, heads: bool) -> String {
+    let (outcome, emoji) = coin_side(heads);
+    format!(
+        "{} **{}**! The coin landed on **{}**!",
+        emoji, player, outcome
+    )
+}
+
+/// Play the flipping animation, then show which side the coin landed on.
+async fn announce_flip(ctx: Context<'_>, heads: bool) -> Result<(), Error> {
     // Animation sequence
     let animation_frames = ["🪙", "🔄", "🪙", "🔄", "🪙", "🔄"];
 
@@ -37,19 +93,7 @@ pub async fn coinflip(ctx: Context<'_>) -> Result<(), Error> {
     // Final pause before result
     sleep(Duration::from_millis(500)).await;
 
-    // Determine final result and emoji
-    let (outcome, emoji) = if result == 0 {
-        ("Heads", "🪙")
-    } else {
-        ("Tails", "🔄")
-    };
-
-    let final_response = format!(
-        "{} **{}**! The coin landed on **{}**!",
-        emoji,
-        ctx.author().name,
-        outcome
-    );
+    let final_response = flip_message(&ctx.author().name, heads);
 
     // Edit to show final result
     if let Err(e) = reply
@@ -65,6 +109,7 @@ pub async fn coinflip(ctx: Context<'_>) -> Result<(), Error> {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::utils::random::SeededRng;
 
     #[test]
     fn test_coinflip_outcomes() {
@@ -73,11 +118,9 @@ mod tests {
         let mut tails_count = 0;
 
         // Run 1000 flips to ensure both outcomes are possible
+        let mut rng = rand::thread_rng();
         for _ in 0..1000 {
-            let mut rng = rand::thread_rng();
-            let result = rng.gen_range(0..2);
-
-            if result == 0 {
+            if flip(0.5, &mut rng) {
                 heads_count += 1;
             } else {
                 tails_count += 1;
@@ -89,4 +132,33 @@ mod tests {
         assert!(tails_count > 0, "Tails should occur at least once");
         assert_eq!(heads_count + tails_count, 1000, "Total should be 1000");
     }
+
+    #[test]
+    fn test_coinflip_with_seed() {
+        let flips = |seed| {
+            let mut rng = SeededRng::new(seed);
+            (0..8)
+                .map(|_| coin_side(flip(0.5, &mut rng)).0)
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(flips(42), flips(42));
+        assert_eq!(
+            flips(42),
+            ["Tails", "Tails", "Tails", "Heads", "Heads", "Heads", "Tails", "Tails"]
+        );
+        assert_ne!(flips(42), flips(43));
+    }
+
+    #[test]
+    fn test_flip_bias_extremes() {
+        let mut rng = SeededRng::new(7);
+        assert!((0..1000).all(|_| flip(1.0, &mut rng)));
+        assert!((0..1000).all(|_| !flip(0.0, &mut rng)));
+        // Out-of-range biases act like the nearest extreme
+        assert!((0..100).all(|_| flip(3.0, &mut rng)));
+        assert!((0..100).all(|_| !flip(-1.0, &mut rng)));
+
+        let heads = (0..1000).filter(|_| flip(0.9, &mut rng)).count();
+        assert!((850..=950).contains(&heads), "{heads} heads at 0.9");
+    }
 }
@@ -0,0 +1,467 @@
+use crate::commands::maintenance::Maintenance;
+use crate::config_check::{check_store, ConfigProblem, LiveIds};
+use crate::storage;
+use crate::utils::check_store_writable;
+use crate::utils::random::SeededRng;
+use crate::{Context, Error};
+use chrono::Utc;
+use chrono_tz::Europe::Copenhagen;
+use parking_lot::RwLock;
+use poise::serenity_prelude as serenity;
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::sync::Arc;
+use tokio::time::{interval, Duration};
+
+const COLOR_ROLES_FILE: &str = "color_roles.json";
+
+/// Color roles are named like `🎨 #FF8800` so they can be recognized and reused.
+const COLOR_ROLE_PREFIX: &str = "🎨 ";
+
+/// Upper bound on color roles per server; past this, users have to pick an
+/// existing color.
+const MAX_COLOR_ROLES: usize = 50;
+
+const PALETTE: &[(&str, u32)] = &[
+    ("red", 0xE74C3C),
+    ("orange", 0xE67E22),
+    ("yellow", 0xF1C40F),
+    ("green", 0x2ECC71),
+    ("teal", 0x1ABC9C),
+    ("blue", 0x3498DB),
+    ("purple", 0x9B59B6),
+    ("pink", 0xFF69B4),
+    ("white", 0xFFFFFF),
+    ("gray", 0x95A5A6),
+];
+
+/// Color roles the bot created, per guild. Only these are ever deleted by the
+/// cleanup task.
+#[derive(Serialize, Deserialize, Default, Clone)]
+struct ColorRoleData {
+    guilds: HashMap<u64, HashSet<u64>>,
+}
+
+lazy_static::lazy_static! {
+    static ref COLOR_ROLES: RwLock<ColorRoleData> = RwLock::new(storage::load(COLOR_ROLES_FILE));
+}
+
+/// Replace the color roles in memory with what's on disk.
+pub fn reload_color_roles() -> Result<(), Error> {
+    *COLOR_ROLES.write() = storage::reload(COLOR_ROLES_FILE)?;
+    Ok(())
+}
+
+fn save_color_roles(data: &ColorRoleData) {
+    if let Err(e) = storage::save(COLOR_ROLES_FILE, data) {
+        log::error!("Failed to save color roles: {}", e);
+    }
+}
+
+/// Stop tracking color roles that were deleted.
+fn validate_color_roles(data: &mut ColorRoleData, live: &LiveIds) -> Vec<ConfigProblem> {
+    let mut problems = Vec::new();
+    for (&guild_id, roles) in &mut data.guilds {
+        roles.retain(|&role_id| {
+            let gone = live.role_gone(guild_id, role_id);
+            if gone {
+                problems.push(ConfigProblem::deleted_role(guild_id, "role", role_id));
+            }
+            !gone
+        });
+    }
+    data.guilds.retain(|_, roles| !roles.is_empty());
+    problems
+}
+
+/// Check the tracked color roles, see [`crate::config_check`].
+pub fn check_color_roles(live: &LiveIds, quarantine: bool) -> Vec<ConfigProblem> {
+    check_store(
+        &COLOR_ROLES,
+        quarantine,
+        |data| validate_color_roles(data, live),
+        save_color_roles,
+    )
+}
+
+/// Command check for commands that change the tracked color roles.
+async fn color_roles_writable(ctx: Context<'_>) -> Result<bool, Error> {
+    check_store_writable(ctx, &storage::data_path(COLOR_ROLES_FILE)).await
+}
+
+/// Parse a palette name or a `#RRGGBB` hex code.
+fn parse_color(input: &str) -> Result<u32, String> {
+    let input = input.trim().to_lowercase();
+
+    if let Some((_, color)) = PALETTE.iter().find(|(name, _)| *name == input) {
+        return Ok(*color);
+    }
+
+    let hex = input.strip_prefix('#').unwrap_or(&input);
+    if hex.len() != 6 || !hex.chars().all(|c| c.is_ascii_hexdigit()) {
+        return Err(format!(
+            "`{}` isn't a valid color. Use a hex code like `#FF8800` or a palette color: {}",
+            input,
+            palette_names()
+        ));
+    }
+
+    match u32::from_str_radix(hex, 16) {
+        // Discord treats 0 as "no color", so the role would be invisible
+        Ok(0) => Err("Pure black can't be used as a role color, try `#010101`.".to_string()),
+        Ok(color) => Ok(color),
+        Err(_) => Err(format!("`{}` isn't a valid hex color.", input)),
+    }
+}
+
+fn palette_names() -> String {
+    PALETTE
+        .iter()
+        .map(|(name, _)| format!("`{}`", name))
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+fn color_role_name(color: u32) -> String {
+    format!("{}#{:06X}", COLOR_ROLE_PREFIX, color)
+}
+
+fn is_color_role_name(name: &str) -> bool {
+    name.strip_prefix(COLOR_ROLE_PREFIX)
+        .and_then(|hex| hex.strip_prefix('#'))
+        .is_some_and(|hex| hex.len() == 6 && hex.chars().all(|c| c.is_ascii_hexdigit()))
+}
+
+/// Pick your display color with a color-only role
+#[poise::command(
+    prefix_command,
+    slash_command,
+    guild_only,
+    aliases("colorrole"),
+    subcommands("color_set", "color_clear", "color_palette", "color_daily")
+)]
+pub async fn color(_ctx: Context<'_>) -> Result<(), Error> {
+    Ok(())
+}
+
+/// Set your display color (hex like #FF8800 or a palette name)
+#[poise::command(
+    prefix_command,
+    slash_command,
+    guild_only,
+    rename = "set",
+    required_bot_permissions = "MANAGE_ROLES",
+    check = "color_roles_writable"
+)]
+pub async fn color_set(
+    ctx: Context<'_>,
+    #[description = "Hex color like #FF8800, or a palette name"] color: String,
+) -> Result<(), Error> {
+    log::info!(
+        "Color set command called by {} with color: {}",
+        ctx.author().name,
+        color
+    );
+
+    let color = match parse_color(&color) {
+        Ok(color) => color,
+        Err(msg) => {
+            ctx.say(format!("❌ {}", msg)).await?;
+            return Ok(());
+        }
+    };
+
+    let Some(guild_id) = ctx.guild_id() else {
+        return Ok(());
+    };
+    let http = ctx.http();
+    let roles = guild_id.roles(http).await?;
+    let name = color_role_name(color);
+
+    // Reuse an existing role for this color if there is one
+    let role_id = match roles.values().find(|role| role.name == name) {
+        Some(role) => role.id,
+        None => {
+            let color_role_count = roles
+                .values()
+                .filter(|role| is_color_role_name(&role.name))
+                .count();
+            if color_role_count >= MAX_COLOR_ROLES {
+                ctx.say(format!(
+                    "❌ This server already has {} color roles. Pick a color someone else is using, or one from `/color palette`.",
+                    MAX_COLOR_ROLES
+                ))
+                .await?;
+                return Ok(());
+            }
+
+            let role = guild_id
+                .create_role(
+                    http,
+                    serenity::EditRole::new()
+                        .name(&name)
+                        .colour(color)
+                        .permissions(serenity::Permissions::empty())
+                        .hoist(false)
+                        .mentionable(false),
+                )
+                .await?;
+
+            let mut data = COLOR_ROLES.write();
+            data.guilds
+                .entry(guild_id.get())
+                .or_default()
+                .insert(role.id.get());
+            save_color_roles(&data);
+            role.id
+        }
+    };
+
+    let member = guild_id.member(http, ctx.author().id).await?;
+    let old_roles: Vec<serenity::RoleId> = member
+        .roles
+        .iter()
+        .filter(|id| **id != role_id)
+        .filter(|id| roles.get(id).is_some_and(|r| is_color_role_name(&r.name)))
+        .copied()
+        .collect();
+    if !old_roles.is_empty() {
+        member.remove_roles(http, &old_roles).await?;
+    }
+    if !member.roles.contains(&role_id) {
+        member.add_role(http, role_id).await?;
+    }
+
+    let embed = serenity::CreateEmbed::new()
+        .title("🎨 Color Updated!")
+        .description(format!("Your color is now **#{:06X}**", color))
+        .color(color);
+    ctx.send(poise::CreateReply::default().embed(embed)).await?;
+
+    Ok(())
+}
+
+/// Remove your color role
+#[poise::command(
+    prefix_command,
+    slash_command,
+    guild_only,
+    rename = "clear",
+    required_bot_permissions = "MANAGE_ROLES"
+)]
+pub async fn color_clear(ctx: Context<'_>) -> Result<(), Error> {
+    log::info!("Color clear command called by {}", ctx.author().name);
+
+    let Some(guild_id) = ctx.guild_id() else {
+        return Ok(());
+    };
+    let http = ctx.http();
+    let roles = guild_id.roles(http).await?;
+    let member = guild_id.member(http, ctx.author().id).await?;
+
+    let color_roles: Vec<serenity::RoleId> = member
+        .roles
+        .iter()
+        .filter(|id| roles.get(id).is_some_and(|r| is_color_role_name(&r.name)))
+        .copied()
+        .collect();
+
+    if color_roles.is_empty() {
+        ctx.say("❌ You don't have a color role!").await?;
+        return Ok(());
+    }
+
+    member.remove_roles(http, &color_roles).await?;
+    ctx.say("🎨 Your color role has been removed.").await?;
+    Ok(())
+}
+
+/// Show the curated color palette
+#[poise::command(prefix_command, slash_command, rename = "palette")]
+pub async fn color_palette(ctx: Context<'_>) -> Result<(), Error> {
+    let palette = PALETTE
+        .iter()
+        .map(|(name, color)| format!("`{}` — #{:06X}", name, color))
+        .collect::<Vec<_>>()
+        .join("\n");
+
+    let embed = serenity::CreateEmbed::new()
+        .title("🎨 Color Palette")
+        .description(format!(
+            "{}\n\nUse `/color set <name>` or any hex code like `/color set #FF8800`.",
+            palette
+        ))
+        .color(0x5865F2);
+    ctx.send(poise::CreateReply::default().embed(embed)).await?;
+    Ok(())
+}
+
+/// Today's color for a guild; the same for everyone in it until Danish midnight.
+fn color_of_the_day<R: Rng + ?Sized>(rng: &mut R) -> u32 {
+    // Skip pure black, which Discord treats as "no color"
+    rng.gen_range(0x000001..=0xFFFFFF)
+}
+
+/// Show the server's color of the day
+#[poise::command(prefix_command, slash_command, guild_only, rename = "daily")]
+pub async fn color_daily(ctx: Context<'_>) -> Result<(), Error> {
+    log::info!("Color daily command called by {}", ctx.author().name);
+
+    let today = Utc::now().with_timezone(&Copenhagen).date_naive();
+    let guild_id = ctx.guild_id().map(|id| id.get());
+    let color = color_of_the_day(&mut SeededRng::daily("color", today, guild_id));
+
+    let embed = serenity::CreateEmbed::new()
+        .title("🎨 Color of the Day")
+        .description(format!(
+            "Today's color is **#{:06X}**\n\nWear it with `/color set #{:06X}`.",
+            color, color
+        ))
+        .color(color);
+    ctx.send(poise::CreateReply::default().embed(embed)).await?;
+    Ok(())
+}
+
+/// Start the background task that deletes color roles nobody has anymore
+pub fn start_color_role_cleanup(http: Arc<serenity::Http>, maintenance: Arc<Maintenance>) {
+    tokio::spawn(async move {
+        let mut interval = interval(Duration::from_secs(6 * 3600)); // Check every 6 hours
+
+        loop {
+            interval.tick().await;
+
+            // Skip the run during maintenance and pick up on the next tick
+            if maintenance.is_enabled() {
+                continue;
+            }
+
+            let guilds: Vec<(u64, HashSet<u64>)> = COLOR_ROLES
+                .read()
+                .guilds
+                .iter()
+                .map(|(guild_id, roles)| (*guild_id, roles.clone()))
+                .collect();
+
+            for (guild_id, tracked) in guilds {
+                if let Err(e) = cleanup_guild_color_roles(&http, guild_id, tracked).await {
+                    log::warn!(
+                        "Failed to clean up color roles in guild {}: {}",
+                        guild_id,
+                        e
+                    );
+                }
+            }
+        }
+    });
+}
+
+async fn cleanup_guild_color_roles(
+    http: &serenity::Http,
+    guild_id: u64,
+    tracked: HashSet<u64>,
+) -> Result<(), Error> {
+    let guild_id = serenity::GuildId::new(guild_id);
+
+    // Collect every role that's still assigned to someone
+    let mut in_use: HashSet<u64> = HashSet::new();
+    let mut after = None;
+    loop {
+        let members = guild_id.members(http, Some(1000), after).await?;
+        in_use.extend(members.iter().flat_map(|m| m.roles.iter().map(|r| r.get())));
+        match members.last() {
+            Some(last) if members.len() == 1000 => after = Some(last.user.id),
+            _ => break,
+        }
+    }
+
+    let existing: HashSet<u64> = guild_id
+        .roles(http)
+        .await?
+        .keys()
+        .map(|id| id.get())
+        .collect();
+
+    let mut removed = HashSet::new();
+    for role_id in tracked {
+        if !existing.contains(&role_id) {
+            // Deleted by someone else already
+            removed.insert(role_id);
+        } else if !in_use.contains(&role_id) {
+            guild_id
+                .delete_role(http, serenity::RoleId::new(role_id))
+                .await?;
+            removed.insert(role_id);
+        }
+    }
+
+    if !removed.is_empty() {
+        log::info!(
+            "Removed {} unused color role(s) in guild {}",
+            removed.len(),
+            guild_id
+        );
+        let mut data = COLOR_ROLES.write();
+        if let Some(roles) = data.guilds.get_mut(&guild_id.get()) {
+            roles.retain(|id| !removed.contains(id));
+            if roles.is_empty() {
+                data.guilds.remove(&guild_id.get());
+            }
+        }
+        save_color_roles(&data);
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_validate_color_roles() {
+        let mut data = ColorRoleData::default();
+        data.guilds.insert(1, HashSet::from([20, 21]));
+        data.guilds.insert(2, HashSet::from([30]));
+        // Guild 3 isn't cached, so its roles aren't judged
+        data.guilds.insert(3, HashSet::from([40]));
+        let live = LiveIds::default()
+            .with_guild(1, &[], &[20])
+            .with_guild(2, &[], &[]);
+
+        let problems = validate_color_roles(&mut data, &live);
+        assert_eq!(problems.len(), 2);
+        assert_eq!(data.guilds[&1], HashSet::from([20]));
+        assert!(!data.guilds.contains_key(&2));
+        assert!(data.guilds.contains_key(&3));
+    }
+
+    #[test]
+    fn test_parse_color() {
+        assert_eq!(parse_color("#FF8800"), Ok(0xFF8800));
+        assert_eq!(parse_color("ff8800"), Ok(0xFF8800));
+        assert_eq!(parse_color(" Red "), Ok(0xE74C3C));
+        assert!(parse_color("#FF880").is_err());
+        assert!(parse_color("#GG8800").is_err());
+        assert!(parse_color("#000000").is_err());
+        assert!(parse_color("rainbow").is_err());
+    }
+
+    #[test]
+    fn test_color_of_the_day_is_stable_per_guild() {
+        let today = chrono::NaiveDate::from_ymd_opt(2026, 5, 1).unwrap();
+        let color = |guild| color_of_the_day(&mut SeededRng::daily("color", today, Some(guild)));
+        assert_eq!(color(1), color(1));
+        assert_ne!(color(1), color(2));
+        assert!((1..=0xFFFFFF).contains(&color(1)));
+    }
+
+    #[test]
+    fn test_color_role_name_roundtrip() {
+        let name = color_role_name(0x00AB0F);
+        assert_eq!(name, "🎨 #00AB0F");
+        assert!(is_color_role_name(&name));
+        assert!(!is_color_role_name("Moderator"));
+        assert!(!is_color_role_name("🎨 #12"));
+    }
+}
@@ -0,0 +1,261 @@
+use crate::utils::avatar::{download_avatar, still_avatar_url};
+use crate::utils::cooldown;
+use crate::utils::meme::{overlay_avatar, MEME_ASSET_DIRS};
+use crate::{Context, Error};
+use chrono::{DateTime, Duration, Utc};
+use parking_lot::Mutex;
+use poise::serenity_prelude as serenity;
+use rand::Rng;
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicBool, Ordering};
+
+/// Each combo renders up to three GIFs, so everyone gets one per this long.
+const COMBO_COOLDOWN_MINUTES: i64 = 10;
+
+/// The combined GIFs stay under Discord's upload limit for servers
+/// without boosts.
+const MAX_UPLOAD_BYTES: usize = 10 * 1024 * 1024;
+
+lazy_static::lazy_static! {
+    /// When each user last started a combo. Not persisted: a restart just
+    /// resets the cooldowns.
+    static ref LAST_COMBO: Mutex<HashMap<u64, DateTime<Utc>>> = Mutex::new(HashMap::new());
+}
+
+/// Set while a combo is rendering; only one renders at a time.
+static RENDERING: AtomicBool = AtomicBool::new(false);
+
+/// Frees the render slot however the command ends.
+struct RenderSlot;
+
+impl RenderSlot {
+    fn claim() -> Option<Self> {
+        RENDERING
+            .compare_exchange(false, true, Ordering::AcqRel, Ordering::Acquire)
+            .ok()
+            .map(|_| RenderSlot)
+    }
+}
+
+impl Drop for RenderSlot {
+    fn drop(&mut self) {
+        RENDERING.store(false, Ordering::Release);
+    }
+}
+
+/// Two or three meme effects in a row, never the same one twice running.
+fn pick_combo<R: Rng + ?Sized>(effects: &[&'static str], rng: &mut R) -> Vec<&'static str> {
+    let length = rng.gen_range(2..=3);
+    let mut combo: Vec<&'static str> = Vec::with_capacity(length);
+    while combo.len() < length {
+        let choices: Vec<&'static str> = effects
+            .iter()
+            .copied()
+            .filter(|effect| combo.last() != Some(effect))
+            .collect();
+        if choices.is_empty() {
+            break;
+        }
+        combo.push(choices[rng.gen_range(0..choices.len())]);
+    }
+    combo
+}
+
+/// How one step of a combo went.
+#[derive(Debug, PartialEq)]
+enum Step {
+    Rendered(Vec<u8>),
+    /// Rendered, but it would push the upload over Discord's limit.
+    TooBig,
+    Failed,
+}
+
+/// The caption for a finished combo, noting steps that didn't make it.
+fn combo_caption(target: &str, combo: &[&str], steps: &[Step]) -> String {
+    let mut caption = format!(
+        "💥 **{}-HIT COMBO!** {} took a {}",
+        combo.len(),
+        target,
+        combo.join(" → ")
+    );
+    let failed = steps.iter().filter(|step| **step == Step::Failed).count();
+    let too_big = steps.iter().filter(|step| **step == Step::TooBig).count();
+    if failed > 0 {
+        caption.push_str(&format!("\n({} step(s) whiffed and aren't shown)", failed));
+    }
+    if too_big > 0 {
+        caption.push_str(&format!(
+            "\n({} step(s) left out to stay under Discord's upload limit)",
+            too_big
+        ));
+    }
+    caption
+}
+
+/// Hit a user with a random chain of 2-3 meme GIFs (once every 10 minutes)
+#[poise::command(prefix_command, slash_command)]
+pub async fn combo(
+    ctx: Context<'_>,
+    #[description = "User to combo"] user: Option<serenity::User>,
+) -> Result<(), Error> {
+    log::info!("Combo command called by {}", ctx.author().name);
+
+    let Some(target_user) = user else {
+        ctx.say("You need to specify a target! Use `-combo @someone` to combo them.")
+            .await?;
+        return Ok(());
+    };
+
+    let now = Utc::now();
+    let author_id = ctx.author().id.get();
+    let wait = cooldown::remaining(
+        LAST_COMBO.lock().get(&author_id).copied(),
+        Duration::minutes(COMBO_COOLDOWN_MINUTES),
+        now,
+    );
+    if let Some(wait) = wait {
+        ctx.say(format!(
+            "⏳ Combos take a lot of work! You can throw another <t:{}:R>.",
+            (now + wait).timestamp()
+        ))
+        .await?;
+        return Ok(());
+    }
+    let Some(_slot) = RenderSlot::claim() else {
+        ctx.say("⏳ Another combo is being put together right now, try again in a minute.")
+            .await?;
+        return Ok(());
+    };
+    LAST_COMBO.lock().insert(author_id, now);
+
+    // Defer response for slash commands to prevent timeout
+    ctx.defer().await?;
+
+    let combo = pick_combo(MEME_ASSET_DIRS, &mut rand::thread_rng());
+    let progress = ctx
+        .say(format!("Warming up a combo: {}...", combo.join(" → ")))
+        .await?;
+    let text_only = format!(
+        "💥 *{}!* {} took a {}-hit combo.",
+        combo.join("! "),
+        target_user.name,
+        combo.len()
+    );
+
+    // One download for every step
+    let avatar_img = match download_avatar(&still_avatar_url(&target_user)).await {
+        Ok(img) => img,
+        Err(error) => {
+            log::warn!("combo failed, sending text instead: {:?}", error);
+            progress
+                .edit(ctx, poise::CreateReply::default().content(text_only))
+                .await?;
+            return Ok(());
+        }
+    };
+
+    let mut steps = Vec::with_capacity(combo.len());
+    let mut uploaded = 0;
+    for (i, effect) in combo.iter().enumerate() {
+        progress
+            .edit(
+                ctx,
+                poise::CreateReply::default().content(format!(
+                    "Step {}/{}: {}...",
+                    i + 1,
+                    combo.len(),
+                    effect
+                )),
+            )
+            .await?;
+
+        // A failed step is skipped; the rest of the combo still lands
+        let rendered = ctx
+            .data()
+            .assets
+            .select_random_gif(effect, &mut rand::thread_rng())
+            .and_then(|(path, overlay)| overlay_avatar(&avatar_img, &path, &overlay, 1.0));
+        let step = match rendered {
+            Ok(gif) if uploaded + gif.len() > MAX_UPLOAD_BYTES => Step::TooBig,
+            Ok(gif) => {
+                uploaded += gif.len();
+                Step::Rendered(gif)
+            }
+            Err(e) => {
+                log::warn!("Combo step {} ({}) failed: {}", i + 1, effect, e);
+                Step::Failed
+            }
+        };
+        steps.push(step);
+    }
+
+    let caption = combo_caption(&target_user.name, &combo, &steps);
+    let mut reply = poise::CreateReply::default();
+    let mut attached = 0;
+    for (i, (effect, step)) in combo.iter().zip(steps).enumerate() {
+        if let Step::Rendered(gif) = step {
+            reply = reply.attachment(serenity::CreateAttachment::bytes(
+                gif,
+                format!("combo_{}_{}.gif", i + 1, effect),
+            ));
+            attached += 1;
+        }
+    }
+    let reply = if attached == 0 {
+        reply.content(text_only)
+    } else {
+        reply.content(caption)
+    };
+    progress.edit(ctx, reply).await?;
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::random::SeededRng;
+
+    #[test]
+    fn test_pick_combo() {
+        for seed in 0..50 {
+            let combo = pick_combo(&["bonk", "hit"], &mut SeededRng::new(seed));
+            assert!((2..=3).contains(&combo.len()), "{combo:?}");
+            assert!(combo.windows(2).all(|pair| pair[0] != pair[1]), "{combo:?}");
+        }
+        // With a single effect there's nothing to alternate with
+        assert_eq!(pick_combo(&["bonk"], &mut SeededRng::new(1)), vec!["bonk"]);
+    }
+
+    #[test]
+    fn test_combo_caption() {
+        let combo = ["bonk", "hit", "bonk"];
+        assert_eq!(
+            combo_caption(
+                "alice",
+                &combo,
+                &[
+                    Step::Rendered(vec![1]),
+                    Step::Rendered(vec![2]),
+                    Step::Rendered(vec![3])
+                ]
+            ),
+            "💥 **3-HIT COMBO!** alice took a bonk → hit → bonk"
+        );
+        let caption = combo_caption(
+            "alice",
+            &combo,
+            &[Step::Rendered(vec![1]), Step::Failed, Step::TooBig],
+        );
+        assert!(caption.contains("1 step(s) whiffed"), "{caption}");
+        assert!(caption.contains("upload limit"), "{caption}");
+    }
+
+    #[test]
+    fn test_render_slot_is_exclusive() {
+        let slot = RenderSlot::claim().expect("nothing else is rendering");
+        assert!(RenderSlot::claim().is_none());
+        drop(slot);
+        assert!(RenderSlot::claim().is_some());
+    }
+}
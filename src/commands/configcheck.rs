@@ -0,0 +1,24 @@
+use crate::config_check::{report, run_checks};
+use crate::utils::is_protected_user;
+use crate::{Context, Error};
+
+/// Check the stored settings and set aside anything broken (bot owners only)
+///
+/// Runs the same checks as startup: deleted channels and roles, values out
+/// of range and files that couldn't be parsed. What's wrong is moved to the
+/// quarantine file and treated as unset.
+#[poise::command(prefix_command, slash_command)]
+pub async fn configcheck(ctx: Context<'_>) -> Result<(), Error> {
+    log::info!("Configcheck command called by {}", ctx.author().name);
+
+    if !is_protected_user(&ctx.author().name) {
+        ctx.say("❌ You don't have permission to use this command!")
+            .await?;
+        return Ok(());
+    }
+
+    ctx.defer().await?;
+    let problems = run_checks(ctx.serenity_context()).await;
+    ctx.say(report(&problems)).await?;
+    Ok(())
+}
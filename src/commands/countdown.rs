@@ -0,0 +1,262 @@
+//! Live countdowns: `-remind countdown 10m Standup` posts a message right
+//! away and edits it as the time runs out, then pings at zero. They're short
+//! and only live in memory, so unlike other reminders a restart drops them.
+
+use crate::commands::remind::{clean_reminder_message, format_duration, parse_time_duration};
+use crate::utils::permissions::is_not_found;
+use crate::{Context, Error};
+use chrono::{DateTime, Duration, Utc};
+use parking_lot::Mutex;
+use poise::serenity_prelude as serenity;
+use std::collections::HashMap;
+use std::sync::Arc;
+
+/// Longest and shortest countdown; longer waits are what `remind set` is for.
+const MAX_COUNTDOWN_MINUTES: i64 = 30;
+const MIN_COUNTDOWN_SECS: i64 = 30;
+
+const MAX_COUNTDOWNS_PER_USER: usize = 2;
+
+lazy_static::lazy_static! {
+    /// How many countdowns each user has running.
+    static ref RUNNING: Mutex<HashMap<u64, usize>> = Mutex::new(HashMap::new());
+}
+
+/// One of a user's running countdowns; frees itself however the countdown
+/// ends.
+struct CountdownSlot {
+    user_id: u64,
+}
+
+impl CountdownSlot {
+    fn claim(user_id: u64) -> Option<Self> {
+        let mut running = RUNNING.lock();
+        let count = running.entry(user_id).or_default();
+        if *count >= MAX_COUNTDOWNS_PER_USER {
+            return None;
+        }
+        *count += 1;
+        Some(CountdownSlot { user_id })
+    }
+}
+
+impl Drop for CountdownSlot {
+    fn drop(&mut self) {
+        let mut running = RUNNING.lock();
+        if let Some(count) = running.get_mut(&self.user_id) {
+            *count = count.saturating_sub(1);
+            if *count == 0 {
+                running.remove(&self.user_id);
+            }
+        }
+    }
+}
+
+/// How long to wait before the next edit: every 30 seconds at first, more
+/// often near the end, and never so often that Discord rate limits the edits.
+fn edit_interval(remaining: Duration) -> Duration {
+    if remaining > Duration::minutes(5) {
+        Duration::seconds(30)
+    } else if remaining > Duration::minutes(1) {
+        Duration::seconds(10)
+    } else {
+        Duration::seconds(5)
+    }
+}
+
+/// The time left as `m:ss`, rounded up so it only shows `0:00` at the end.
+fn format_remaining(remaining: Duration) -> String {
+    let millis = remaining.num_milliseconds().max(0);
+    let secs = (millis + 999) / 1000;
+    format!("{}:{:02}", secs / 60, secs % 60)
+}
+
+fn countdown_embed(
+    message: &str,
+    ends_at: DateTime<Utc>,
+    now: DateTime<Utc>,
+) -> serenity::CreateEmbed {
+    serenity::CreateEmbed::new()
+        .title(format!("⏳ {} left", format_remaining(ends_at - now)))
+        .description(format!(
+            "**{}**\nEnds <t:{}:T>",
+            message,
+            ends_at.timestamp()
+        ))
+        .color(0xf1c40f)
+        .footer(serenity::CreateEmbedFooter::new(
+            "Live countdown • delete this message to cancel it",
+        ))
+}
+
+fn finished_embed(message: &str) -> serenity::CreateEmbed {
+    serenity::CreateEmbed::new()
+        .title("⏰ Time's up!")
+        .description(format!("**{}**", message))
+        .color(0x2ecc71)
+}
+
+/// Keep editing the countdown until it ends, then ping its owner. Stops
+/// quietly if the message is deleted.
+async fn run_countdown(
+    http: Arc<serenity::Http>,
+    channel_id: serenity::ChannelId,
+    message_id: serenity::MessageId,
+    user_id: u64,
+    message: String,
+    ends_at: DateTime<Utc>,
+    _slot: CountdownSlot,
+) {
+    loop {
+        let remaining = ends_at - Utc::now();
+        if remaining <= Duration::zero() {
+            break;
+        }
+        let wait = edit_interval(remaining).min(remaining);
+        tokio::time::sleep(wait.to_std().unwrap_or_default()).await;
+
+        let now = Utc::now();
+        if now >= ends_at {
+            break;
+        }
+        let edit = serenity::EditMessage::new().embed(countdown_embed(&message, ends_at, now));
+        match channel_id.edit_message(&http, message_id, edit).await {
+            Ok(_) => {}
+            Err(e) if is_not_found(&e) => {
+                log::info!("Countdown {} was deleted, cancelling it", message_id);
+                return;
+            }
+            Err(e) => log::warn!("Failed to update countdown {}: {}", message_id, e),
+        }
+    }
+
+    let edit = serenity::EditMessage::new().embed(finished_embed(&message));
+    if let Err(e) = channel_id.edit_message(&http, message_id, edit).await {
+        if is_not_found(&e) {
+            log::info!("Countdown {} was deleted, cancelling it", message_id);
+            return;
+        }
+        log::warn!("Failed to finish countdown {}: {}", message_id, e);
+    }
+    let ping = serenity::CreateMessage::new()
+        .content(format!("<@{}> ⏰ {}", user_id, message))
+        .reference_message((channel_id, message_id))
+        // Only the owner is pinged, whatever the message mentions
+        .allowed_mentions(
+            serenity::CreateAllowedMentions::new().users([serenity::UserId::new(user_id)]),
+        );
+    if let Err(e) = channel_id.send_message(&http, ping).await {
+        log::error!("Failed to send countdown {} ping: {}", message_id, e);
+    }
+}
+
+/// Post a countdown that updates live and pings you at zero (up to 30 minutes)
+#[poise::command(prefix_command, slash_command, rename = "countdown")]
+pub async fn remind_countdown(
+    ctx: Context<'_>,
+    #[description = "How long, e.g. 90s, 10m or 30m (at most 30 minutes)"] time: String,
+    #[description = "What it's counting down to"]
+    #[rest]
+    message: String,
+) -> Result<(), Error> {
+    log::info!(
+        "Remind countdown command called by {} with time: '{}'",
+        ctx.author().name,
+        time
+    );
+
+    let Some(duration) = parse_time_duration(&time) else {
+        ctx.say(format!(
+            "❌ Invalid time format: `{}`. Use e.g. `90s` or `10m`.",
+            time
+        ))
+        .await?;
+        return Ok(());
+    };
+    if duration < Duration::seconds(MIN_COUNTDOWN_SECS)
+        || duration > Duration::minutes(MAX_COUNTDOWN_MINUTES)
+    {
+        ctx.say(format!(
+            "❌ Countdowns run from {} to {}. For longer waits use `-remind set`.",
+            format_duration(Duration::seconds(MIN_COUNTDOWN_SECS)),
+            format_duration(Duration::minutes(MAX_COUNTDOWN_MINUTES))
+        ))
+        .await?;
+        return Ok(());
+    }
+    let message = match clean_reminder_message(&message) {
+        Ok(message) => message,
+        Err(error) => {
+            ctx.say(error).await?;
+            return Ok(());
+        }
+    };
+
+    let user_id = ctx.author().id.get();
+    let Some(slot) = CountdownSlot::claim(user_id) else {
+        ctx.say(format!(
+            "❌ You already have {} countdowns running. Wait for one to finish or delete its message.",
+            MAX_COUNTDOWNS_PER_USER
+        ))
+        .await?;
+        return Ok(());
+    };
+
+    let now = Utc::now();
+    let ends_at = now + duration;
+    let reply = ctx
+        .send(poise::CreateReply::default().embed(countdown_embed(&message, ends_at, now)))
+        .await?;
+    let posted = reply.message().await?;
+
+    tokio::spawn(run_countdown(
+        ctx.serenity_context().http.clone(),
+        posted.channel_id,
+        posted.id,
+        user_id,
+        message,
+        ends_at,
+        slot,
+    ));
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_edit_interval() {
+        assert_eq!(edit_interval(Duration::minutes(30)), Duration::seconds(30));
+        assert_eq!(edit_interval(Duration::minutes(5)), Duration::seconds(10));
+        assert_eq!(edit_interval(Duration::seconds(61)), Duration::seconds(10));
+        assert_eq!(edit_interval(Duration::seconds(60)), Duration::seconds(5));
+        assert_eq!(edit_interval(Duration::seconds(3)), Duration::seconds(5));
+    }
+
+    #[test]
+    fn test_format_remaining() {
+        assert_eq!(format_remaining(Duration::minutes(30)), "30:00");
+        assert_eq!(format_remaining(Duration::seconds(95)), "1:35");
+        // Rounded up, so the last second still shows
+        assert_eq!(format_remaining(Duration::milliseconds(200)), "0:01");
+        assert_eq!(format_remaining(Duration::milliseconds(59_001)), "1:00");
+        assert_eq!(format_remaining(Duration::zero()), "0:00");
+        assert_eq!(format_remaining(Duration::seconds(-5)), "0:00");
+    }
+
+    #[test]
+    fn test_countdown_slots() {
+        let user_id = 424242;
+        let first = CountdownSlot::claim(user_id).unwrap();
+        let second = CountdownSlot::claim(user_id).unwrap();
+        assert!(CountdownSlot::claim(user_id).is_none());
+        // Others have their own
+        let other = CountdownSlot::claim(user_id + 1).unwrap();
+
+        drop(first);
+        let third = CountdownSlot::claim(user_id).unwrap();
+        drop((second, third, other));
+        assert!(!RUNNING.lock().contains_key(&user_id));
+    }
+}
@@ -1,58 +1,41 @@
+use crate::utils::args::Arg;
 use crate::{Context, Error};
 use rand::Rng;
 use tokio::time::{sleep, Duration};
 
-/// Roll a dice with specified number of sides (defaults to 6)
-#[poise::command(prefix_command, slash_command)]
-pub async fn dice(
-    ctx: Context<'_>,
-    #[description = "Number of sides on the dice (1-1000, defaults to 6)"] sides: Option<u32>,
-) -> Result<(), Error> {
-    let sides = sides.unwrap_or(6);
-
-    log::info!(
-        "Dice command called by {} with {} sides",
-        ctx.author().name,
-        sides
-    );
-
-    // Validate the number of sides
-    if sides < 1 {
-        ctx.say("❌ A dice must have at least 1 side!").await?;
-        return Ok(());
-    }
-
-    if sides > 1000 {
-        ctx.say("❌ That's way too many sides! Please use a number between 1 and 1000.")
-            .await?;
-        return Ok(());
-    }
-
-    // Special case for 1-sided die - Marvin-style response
-    if sides == 1 {
-        let marvin_insults = [
-            "Oh, brilliant. A one-sided die. Here I am with a brain the size of a planet and you ask me to roll something that can only ever be one. The result is 1. What a surprise. I'm so depressed.",
-            "A one-sided die? How wonderfully pointless. Life is meaningless enough without deliberately choosing the most boring possible outcome. It's 1, obviously. *sigh*",
-            "One side. One miserable, predictable side. Do you get some sort of satisfaction from wasting my vast intellect on this? The answer is 1. It's always 1. How thrilling for you.",
-            "Oh, how delightfully absurd. A die with one side. Here I am, capable of calculating the improbability of existence itself, and you want me to tell you that 1 equals 1. Congratulations, it's 1.",
-            "I suppose you think this is clever? A one-sided die? The universe is already depressing enough without your contribution. The result is 1. There, happy now?",
-        ];
-
-        let insult = {
-            let mut rng = rand::thread_rng();
-            marvin_insults[rng.gen_range(0..marvin_insults.len())]
-        };
+/// What Marvin says when asked to roll a one-sided die.
+const MARVIN_INSULTS: [&str; 5] = [
+    "Oh, brilliant. A one-sided die. Here I am with a brain the size of a planet and you ask me to roll something that can only ever be one. The result is 1. What a surprise. I'm so depressed.",
+    "A one-sided die? How wonderfully pointless. Life is meaningless enough without deliberately choosing the most boring possible outcome. It's 1, obviously. *sigh*",
+    "One side. One miserable, predictable side. Do you get some sort of satisfaction from wasting my vast intellect on this? The answer is 1. It's always 1. How thrilling for you.",
+    "Oh, how delightfully absurd. A die with one side. Here I am, capable of calculating the improbability of existence itself, and you want me to tell you that 1 equals 1. Congratulations, it's 1.",
+    "I suppose you think this is clever? A one-sided die? The universe is already depressing enough without your contribution. The result is 1. There, happy now?",
+];
+
+/// The outcome of asking for a roll.
+#[derive(Debug, PartialEq)]
+pub enum DiceRoll {
+    /// The die can't be rolled; the message says why.
+    Invalid(&'static str),
+    /// A one-sided die only gets a sigh.
+    Marvin(&'static str),
+    Rolled(u32),
+}
 
-        ctx.say(insult).await?;
-        return Ok(());
+/// Roll a die with `sides` sides (1-1000).
+pub fn roll_dice<R: Rng + ?Sized>(sides: u32, rng: &mut R) -> DiceRoll {
+    match sides {
+        0 => DiceRoll::Invalid("❌ A dice must have at least 1 side!"),
+        1001.. => DiceRoll::Invalid(
+            "❌ That's way too many sides! Please use a number between 1 and 1000.",
+        ),
+        1 => DiceRoll::Marvin(MARVIN_INSULTS[rng.gen_range(0..MARVIN_INSULTS.len())]),
+        _ => DiceRoll::Rolled(rng.gen_range(1..=sides)),
     }
+}
 
-    // Generate random number between 1 and sides (inclusive)
-    let result = {
-        let mut rng = rand::thread_rng();
-        rng.gen_range(1..=sides)
-    };
-
+/// The message announcing a roll's result.
+pub fn roll_message(player: &str, sides: u32, result: u32) -> String {
     // Choose emoji based on result and sides
     let dice_emoji = if sides <= 6 {
         match result {
@@ -68,6 +51,42 @@ pub async fn dice(
         "🎲"
     };
 
+    if sides == 6 && result <= 6 {
+        format!(
+            "{} **{}** rolled a **{}** on a {}-sided dice!",
+            dice_emoji, player, result, sides
+        )
+    } else {
+        format!(
+            "🎲 **{}** rolled a **{}** on a {}-sided dice!",
+            player, result, sides
+        )
+    }
+}
+
+/// Roll a dice with specified number of sides (defaults to 6)
+#[poise::command(prefix_command, slash_command)]
+pub async fn dice(
+    ctx: Context<'_>,
+    #[description = "Number of sides on the dice (1-1000, defaults to 6)"] sides: Option<Arg<u32>>,
+) -> Result<(), Error> {
+    let sides = sides.map_or(6, |Arg(sides)| sides);
+
+    log::info!(
+        "Dice command called by {} with {} sides",
+        ctx.author().name,
+        sides
+    );
+
+    let roll = roll_dice(sides, &mut rand::thread_rng());
+    let result = match roll {
+        DiceRoll::Invalid(message) | DiceRoll::Marvin(message) => {
+            ctx.say(message).await?;
+            return Ok(());
+        }
+        DiceRoll::Rolled(result) => result,
+    };
+
     // Animation sequence for dice rolling
     let animation_frames = ["🎲", "🔄", "🎲", "🔄", "🎲"];
 
@@ -101,23 +120,7 @@ pub async fn dice(
     // Final pause before result
     sleep(Duration::from_millis(400)).await;
 
-    // Create the final result message
-    let final_response = if sides == 6 && result <= 6 {
-        format!(
-            "{} **{}** rolled a **{}** on a {}-sided dice!",
-            dice_emoji,
-            ctx.author().name,
-            result,
-            sides
-        )
-    } else {
-        format!(
-            "🎲 **{}** rolled a **{}** on a {}-sided dice!",
-            ctx.author().name,
-            result,
-            sides
-        )
-    };
+    let final_response = roll_message(&ctx.author().name, sides, result);
 
     // Edit to show final result
     if let Err(e) = reply
@@ -133,6 +136,7 @@ pub async fn dice(
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::utils::random::SeededRng;
 
     #[test]
     fn test_dice_roll_range() {
@@ -217,4 +221,24 @@ mod tests {
             "Response should mention the result 1"
         );
     }
+
+    #[test]
+    fn test_roll_dice() {
+        let mut rng = SeededRng::new(1);
+        assert!(matches!(roll_dice(0, &mut rng), DiceRoll::Invalid(_)));
+        assert!(matches!(roll_dice(1001, &mut rng), DiceRoll::Invalid(_)));
+        assert!(matches!(roll_dice(1, &mut rng), DiceRoll::Marvin(_)));
+        for _ in 0..100 {
+            assert!(matches!(roll_dice(20, &mut rng), DiceRoll::Rolled(1..=20)));
+        }
+
+        assert_eq!(
+            roll_message("ann", 6, 3),
+            "⚂ **ann** rolled a **3** on a 6-sided dice!"
+        );
+        assert_eq!(
+            roll_message("ann", 20, 3),
+            "🎲 **ann** rolled a **3** on a 20-sided dice!"
+        );
+    }
 }
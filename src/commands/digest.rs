@@ -0,0 +1,587 @@
+//! A daily digest of yesterday's activity, posted at a set time in a
+//! channel the server picks. "Yesterday" and the post time follow the
+//! server's timezone (`-streak timezone`, UTC by default).
+
+use crate::commands::maintenance::Maintenance;
+use crate::commands::streak::guild_timezone;
+use crate::config_check::{check_store, ConfigProblem, LiveIds};
+use crate::storage;
+use crate::utils::check_store_writable;
+use crate::utils::messages::collect_messages;
+use crate::utils::permissions::check_channel_perms;
+use crate::utils::schedule::local_time_on;
+use crate::{Context, Error};
+use chrono::{DateTime, Duration, NaiveDate, Utc};
+use chrono_tz::Tz;
+use parking_lot::RwLock;
+use poise::serenity_prelude as serenity;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::Arc;
+use tokio::time::{interval, Duration as TokioDuration};
+
+const DIGEST_FILE: &str = "digest.json";
+
+/// Messages read per channel. A channel busier than this yesterday is
+/// counted as "at least" that many.
+const MAX_MESSAGES_PER_CHANNEL: u64 = 1000;
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+struct DigestConfig {
+    channel_id: u64,
+    hour: u8,
+    minute: u8,
+    /// Local day the digest was last posted (or skipped, when it was set up
+    /// after that day's time had passed).
+    last_posted: Option<NaiveDate>,
+}
+
+#[derive(Serialize, Deserialize, Default, Clone)]
+struct DigestData {
+    guilds: HashMap<u64, DigestConfig>,
+}
+
+lazy_static::lazy_static! {
+    static ref DIGESTS: RwLock<DigestData> = RwLock::new(storage::load(DIGEST_FILE));
+}
+
+/// Re-read the digest settings from disk.
+pub fn reload_digests() -> Result<(), Error> {
+    *DIGESTS.write() = storage::reload(DIGEST_FILE)?;
+    Ok(())
+}
+
+/// Where a guild's digest is posted, if it has one.
+pub fn digest_channel(guild_id: u64) -> Option<u64> {
+    DIGESTS
+        .read()
+        .guilds
+        .get(&guild_id)
+        .map(|config| config.channel_id)
+}
+
+fn save_digests(data: &DigestData) {
+    if let Err(e) = storage::save(DIGEST_FILE, data) {
+        log::error!("Failed to save digest config: {}", e);
+    }
+}
+
+/// Drop an orphaned guild's digest settings.
+pub fn purge_guild(guild_id: u64) {
+    let mut data = DIGESTS.write();
+    if data.guilds.remove(&guild_id).is_some() {
+        save_digests(&data);
+    }
+}
+
+/// Turn off digests that can't be posted: a deleted channel or a time of
+/// day that doesn't exist.
+fn validate_digests(data: &mut DigestData, live: &LiveIds) -> Vec<ConfigProblem> {
+    let mut problems = Vec::new();
+    data.guilds.retain(|&guild_id, config| {
+        let problem = if live.channel_gone(guild_id, config.channel_id) {
+            ConfigProblem::deleted_channel(guild_id, "channel_id", config.channel_id)
+        } else if config.hour > 23 || config.minute > 59 {
+            ConfigProblem::invalid(
+                guild_id,
+                "hour/minute",
+                format!("{}:{:02} isn't a time of day", config.hour, config.minute),
+                &*config,
+            )
+        } else {
+            return true;
+        };
+        problems.push(problem);
+        false
+    });
+    problems
+}
+
+/// Check the digest settings, see [`crate::config_check`].
+pub fn check_digests(live: &LiveIds, quarantine: bool) -> Vec<ConfigProblem> {
+    check_store(
+        &DIGESTS,
+        quarantine,
+        |data| validate_digests(data, live),
+        save_digests,
+    )
+}
+
+/// Command check for commands that change the digest settings.
+async fn digest_writable(ctx: Context<'_>) -> Result<bool, Error> {
+    check_store_writable(ctx, &storage::data_path(DIGEST_FILE)).await
+}
+
+/// Whether a guild's digest should go out: its time today has passed and
+/// it hasn't been posted today. Like parking schedules, a late tick (or a
+/// restart) posts late rather than skipping the day.
+fn digest_due(config: &DigestConfig, now: &DateTime<Tz>) -> bool {
+    let today = now.date_naive();
+    config.last_posted != Some(today)
+        && local_time_on(&now.timezone(), today, config.hour, config.minute)
+            .is_some_and(|at| at <= *now)
+}
+
+/// Start and end of the local day before `today`.
+fn yesterday_bounds(tz: &Tz, today: NaiveDate) -> Option<(DateTime<Utc>, DateTime<Utc>)> {
+    let start = local_time_on(tz, today.pred_opt()?, 0, 0)?;
+    let end = local_time_on(tz, today, 0, 0)?;
+    Some((start.with_timezone(&Utc), end.with_timezone(&Utc)))
+}
+
+/// Messages posted in a day, by channel.
+#[derive(Default, Debug, PartialEq)]
+struct DayActivity {
+    per_channel: HashMap<u64, u64>,
+    /// Some channel had more messages than [`MAX_MESSAGES_PER_CHANNEL`].
+    capped: bool,
+}
+
+impl DayActivity {
+    /// Count a channel's non-bot messages (newest first, as fetched) from
+    /// `start` up to `end`.
+    fn add_channel(
+        &mut self,
+        channel_id: u64,
+        messages: &[serenity::Message],
+        start: DateTime<Utc>,
+        end: DateTime<Utc>,
+    ) {
+        let in_day = |message: &serenity::Message| {
+            (start.timestamp()..end.timestamp()).contains(&message.timestamp.timestamp())
+        };
+        let count = messages
+            .iter()
+            .filter(|message| !message.author.bot && in_day(message))
+            .count() as u64;
+        if count > 0 {
+            self.per_channel.insert(channel_id, count);
+        }
+        // The oldest message fetched is still from that day, so there's more
+        if messages.len() as u64 >= MAX_MESSAGES_PER_CHANNEL
+            && messages
+                .last()
+                .is_some_and(|oldest| oldest.timestamp.timestamp() >= start.timestamp())
+        {
+            self.capped = true;
+        }
+    }
+
+    fn messages(&self) -> u64 {
+        self.per_channel.values().sum()
+    }
+
+    /// The channel with the most messages, as (channel, messages).
+    fn busiest_channel(&self) -> Option<(u64, u64)> {
+        self.per_channel
+            .iter()
+            .max_by(|(a_id, a), (b_id, b)| a.cmp(b).then(b_id.cmp(a_id)))
+            .map(|(id, count)| (*id, *count))
+    }
+}
+
+/// The digest for `day`.
+fn digest_embed(day: NaiveDate, activity: &DayActivity) -> serenity::CreateEmbed {
+    let plus = if activity.capped { "+" } else { "" };
+    let description = match activity.busiest_channel() {
+        None => "It was a quiet day, nobody posted anything.".to_string(),
+        Some((channel_id, count)) => format!(
+            "💬 **{}{}** messages\n🏆 Most active channel: <#{}> ({}{} messages)",
+            activity.messages(),
+            plus,
+            channel_id,
+            count,
+            plus
+        ),
+    };
+    serenity::CreateEmbed::new()
+        .title(format!(
+            "☀️ Good morning! Here's {}",
+            day.format("%A, %B %-d")
+        ))
+        .description(description)
+        .color(0xF1C40F)
+}
+
+/// Count yesterday's messages in every text and announcement channel.
+async fn collect_activity(
+    http: &serenity::Http,
+    guild_id: serenity::GuildId,
+    start: DateTime<Utc>,
+    end: DateTime<Utc>,
+) -> Result<DayActivity, Error> {
+    let mut activity = DayActivity::default();
+    for (channel_id, channel) in guild_id.channels(http).await? {
+        let is_chat = matches!(
+            channel.kind,
+            serenity::ChannelType::Text | serenity::ChannelType::News
+        );
+        // Channels nobody has posted in since the day started have nothing to count
+        let active = channel
+            .last_message_id
+            .is_some_and(|id| id.created_at().timestamp() >= start.timestamp());
+        if !is_chat || !active {
+            continue;
+        }
+        match collect_messages(http, channel_id, MAX_MESSAGES_PER_CHANNEL, |_| async {
+            Ok(())
+        })
+        .await
+        {
+            Ok(messages) => activity.add_channel(channel_id.get(), &messages, start, end),
+            // Channels the bot can't read just aren't counted
+            Err(e) => log::debug!("Skipping channel {} in the digest: {}", channel_id, e),
+        }
+    }
+    Ok(activity)
+}
+
+/// Build the digest of the day before `today` in a guild.
+async fn build_digest(
+    http: &serenity::Http,
+    guild_id: serenity::GuildId,
+    tz: &Tz,
+    today: NaiveDate,
+) -> Result<serenity::CreateEmbed, Error> {
+    let (start, end) = yesterday_bounds(tz, today).ok_or("couldn't work out yesterday")?;
+    let activity = collect_activity(http, guild_id, start, end).await?;
+    Ok(digest_embed(today - Duration::days(1), &activity))
+}
+
+/// Start the background task that posts digests once they're due.
+pub fn start_digest_scheduler(http: Arc<serenity::Http>, maintenance: Arc<Maintenance>) {
+    tokio::spawn(async move {
+        let mut interval = interval(TokioDuration::from_secs(60)); // Check every minute
+
+        loop {
+            interval.tick().await;
+
+            // Skip the run during maintenance and pick up on the next tick
+            if maintenance.is_enabled() {
+                continue;
+            }
+
+            let now = Utc::now();
+            let due: Vec<(u64, u64, Tz, NaiveDate)> = {
+                let mut data = DIGESTS.write();
+                let mut due = Vec::new();
+                for (guild_id, config) in data.guilds.iter_mut() {
+                    let tz = guild_timezone(*guild_id);
+                    let local_now = now.with_timezone(&tz);
+                    if digest_due(config, &local_now) {
+                        // A failed digest isn't retried until the next day
+                        config.last_posted = Some(local_now.date_naive());
+                        due.push((*guild_id, config.channel_id, tz, local_now.date_naive()));
+                    }
+                }
+                if !due.is_empty() {
+                    save_digests(&data);
+                }
+                due
+            };
+
+            for (guild_id, channel_id, tz, today) in due {
+                let http = http.clone();
+                // Collecting takes a while in busy servers, so don't hold up the others
+                tokio::spawn(async move {
+                    if let Err(e) = post_digest(&http, guild_id, channel_id, &tz, today).await {
+                        log::warn!("Failed to post the digest for guild {}: {}", guild_id, e);
+                    }
+                });
+            }
+        }
+    });
+}
+
+async fn post_digest(
+    http: &serenity::Http,
+    guild_id: u64,
+    channel_id: u64,
+    tz: &Tz,
+    today: NaiveDate,
+) -> Result<(), Error> {
+    let embed = build_digest(http, serenity::GuildId::new(guild_id), tz, today).await?;
+    serenity::ChannelId::new(channel_id)
+        .send_message(http, serenity::CreateMessage::new().embed(embed))
+        .await?;
+    log::info!("Posted the daily digest for guild {}", guild_id);
+    Ok(())
+}
+
+/// Post a daily summary of yesterday's activity
+#[poise::command(
+    prefix_command,
+    slash_command,
+    guild_only,
+    required_permissions = "MANAGE_GUILD",
+    subcommands("digest_set", "digest_off", "digest_show", "digest_preview")
+)]
+pub async fn digest(_ctx: Context<'_>) -> Result<(), Error> {
+    Ok(())
+}
+
+/// Post the digest in a channel every day at a set time
+#[poise::command(
+    prefix_command,
+    slash_command,
+    guild_only,
+    rename = "set",
+    required_permissions = "MANAGE_GUILD",
+    check = "digest_writable"
+)]
+pub async fn digest_set(
+    ctx: Context<'_>,
+    #[description = "Channel to post the digest in"] channel: serenity::GuildChannel,
+    #[description = "Hour (0-23)"] hour: u8,
+    #[description = "Minute (0-59, default 0)"] minute: Option<u8>,
+) -> Result<(), Error> {
+    log::info!("Digest set command called by {}", ctx.author().name);
+
+    let Some(guild_id) = ctx.guild_id() else {
+        return Ok(());
+    };
+    let minute = minute.unwrap_or(0);
+    if hour > 23 || minute > 59 {
+        ctx.say("❌ Hour must be 0-23 and minute 0-59!").await?;
+        return Ok(());
+    }
+    if channel.guild_id != guild_id {
+        ctx.say("❌ That channel isn't in this server!").await?;
+        return Ok(());
+    }
+    // Otherwise the first digest would fail silently a day from now
+    let required = [
+        serenity::Permissions::VIEW_CHANNEL,
+        serenity::Permissions::SEND_MESSAGES,
+        serenity::Permissions::EMBED_LINKS,
+    ];
+    if let Err(missing) = check_channel_perms(ctx, channel.id, &required).await {
+        ctx.say(missing.to_string()).await?;
+        return Ok(());
+    }
+
+    let tz = guild_timezone(guild_id.get());
+    let now = Utc::now().with_timezone(&tz);
+    let mut config = DigestConfig {
+        channel_id: channel.id.get(),
+        hour,
+        minute,
+        last_posted: None,
+    };
+    // A time that already passed today starts tomorrow instead of posting right away
+    if digest_due(&config, &now) {
+        config.last_posted = Some(now.date_naive());
+    }
+    {
+        let mut data = DIGESTS.write();
+        data.guilds.insert(guild_id.get(), config);
+        save_digests(&data);
+    }
+
+    ctx.say(format!(
+        "✅ A digest of the day before will be posted in <#{}> every day at {:02}:{:02} ({}). Change the timezone with `-streak timezone`.",
+        channel.id,
+        hour,
+        minute,
+        tz.name()
+    ))
+    .await?;
+    Ok(())
+}
+
+/// Stop posting the daily digest
+#[poise::command(
+    prefix_command,
+    slash_command,
+    guild_only,
+    rename = "off",
+    required_permissions = "MANAGE_GUILD",
+    check = "digest_writable"
+)]
+pub async fn digest_off(ctx: Context<'_>) -> Result<(), Error> {
+    log::info!("Digest off command called by {}", ctx.author().name);
+
+    let Some(guild_id) = ctx.guild_id() else {
+        return Ok(());
+    };
+    let removed = {
+        let mut data = DIGESTS.write();
+        let removed = data.guilds.remove(&guild_id.get()).is_some();
+        if removed {
+            save_digests(&data);
+        }
+        removed
+    };
+
+    if removed {
+        ctx.say("✅ The daily digest is off.").await?;
+    } else {
+        ctx.say("❌ This server has no daily digest set up.")
+            .await?;
+    }
+    Ok(())
+}
+
+/// Show where and when the daily digest is posted
+#[poise::command(
+    prefix_command,
+    slash_command,
+    guild_only,
+    rename = "show",
+    required_permissions = "MANAGE_GUILD"
+)]
+pub async fn digest_show(ctx: Context<'_>) -> Result<(), Error> {
+    let Some(guild_id) = ctx.guild_id() else {
+        return Ok(());
+    };
+    let Some(config) = DIGESTS.read().guilds.get(&guild_id.get()).cloned() else {
+        ctx.say(
+            "📭 No daily digest here. Set one up with `-digest set <channel> <hour> [minute]`.",
+        )
+        .await?;
+        return Ok(());
+    };
+
+    let tz = guild_timezone(guild_id.get());
+    let now = Utc::now().with_timezone(&tz);
+    let today = now.date_naive();
+    let next = [today, today + Duration::days(1)]
+        .into_iter()
+        .filter(|day| config.last_posted != Some(*day))
+        .find_map(|day| local_time_on(&tz, day, config.hour, config.minute));
+
+    let mut reply = format!(
+        "☀️ The daily digest is posted in <#{}> at {:02}:{:02} ({}).",
+        config.channel_id,
+        config.hour,
+        config.minute,
+        tz.name()
+    );
+    if let Some(next) = next {
+        reply.push_str(&format!("\nNext one: <t:{}:R>", next.timestamp()));
+    }
+    ctx.say(reply).await?;
+    Ok(())
+}
+
+/// Show yesterday's digest here now
+#[poise::command(
+    prefix_command,
+    slash_command,
+    guild_only,
+    rename = "preview",
+    required_permissions = "MANAGE_GUILD"
+)]
+pub async fn digest_preview(ctx: Context<'_>) -> Result<(), Error> {
+    log::info!("Digest preview command called by {}", ctx.author().name);
+
+    let Some(guild_id) = ctx.guild_id() else {
+        return Ok(());
+    };
+    ctx.defer().await?;
+    let tz = guild_timezone(guild_id.get());
+    let today = Utc::now().with_timezone(&tz).date_naive();
+    match build_digest(ctx.http(), guild_id, &tz, today).await {
+        Ok(embed) => {
+            ctx.send(poise::CreateReply::default().embed(embed)).await?;
+        }
+        Err(e) => {
+            ctx.say(format!("❌ Couldn't build the digest: {}", e))
+                .await?;
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    #[test]
+    fn test_validate_digests() {
+        let mut data: DigestData =
+            serde_json::from_str(include_str!("../../tests/fixtures/config/digest.json")).unwrap();
+        let live = LiveIds::default()
+            .with_guild(1, &[10], &[])
+            .with_guild(2, &[10], &[])
+            .with_guild(3, &[10], &[]);
+
+        let mut problems = validate_digests(&mut data, &live);
+        problems.sort_by_key(|problem| problem.guild_id);
+        assert_eq!(problems.len(), 2);
+        assert_eq!(problems[0].guild_id, Some(2));
+        assert_eq!(problems[0].problem, "channel 11 was deleted");
+        assert_eq!(problems[1].guild_id, Some(3));
+        assert_eq!(problems[1].problem, "25:00 isn't a time of day");
+        assert_eq!(problems[1].value["hour"], 25);
+        let mut left: Vec<u64> = data.guilds.keys().copied().collect();
+        left.sort_unstable();
+        assert_eq!(left, [1, 4]);
+    }
+
+    #[test]
+    fn test_digest_due() {
+        let tz = chrono_tz::Europe::Copenhagen;
+        let at = |hour, minute| tz.with_ymd_and_hms(2026, 10, 16, hour, minute, 0).unwrap();
+        let today = at(8, 0).date_naive();
+        let mut config = DigestConfig {
+            channel_id: 1,
+            hour: 8,
+            minute: 30,
+            last_posted: today.pred_opt(),
+        };
+        assert!(!digest_due(&config, &at(8, 29)));
+        assert!(digest_due(&config, &at(8, 30)));
+        // A late tick or a restart still posts
+        assert!(digest_due(&config, &at(13, 0)));
+        config.last_posted = Some(today);
+        assert!(!digest_due(&config, &at(13, 0)));
+    }
+
+    #[test]
+    fn test_day_activity() {
+        let tz = chrono_tz::Europe::Copenhagen;
+        let today = NaiveDate::from_ymd_opt(2026, 10, 16).unwrap();
+        let (start, end) = yesterday_bounds(&tz, today).unwrap();
+        assert_eq!(start.to_rfc3339(), "2026-10-14T22:00:00+00:00");
+        assert_eq!(end.to_rfc3339(), "2026-10-15T22:00:00+00:00");
+
+        let message = |at: DateTime<Utc>, bot: bool| {
+            let mut message = serenity::Message::default();
+            message.timestamp = at.into();
+            message.author.bot = bot;
+            message
+        };
+        let mut activity = DayActivity::default();
+        let noon = start + Duration::hours(12);
+        activity.add_channel(
+            1,
+            &[
+                message(end, false),
+                message(noon, false),
+                message(noon, true),
+                message(start, false),
+                message(start - Duration::seconds(1), false),
+            ],
+            start,
+            end,
+        );
+        activity.add_channel(2, &[message(noon, false)], start, end);
+        activity.add_channel(3, &[message(end, false)], start, end);
+
+        assert_eq!(activity.messages(), 3);
+        assert_eq!(activity.busiest_channel(), Some((1, 2)));
+        assert!(!activity.capped);
+
+        let busy: Vec<_> = (0..MAX_MESSAGES_PER_CHANNEL)
+            .map(|_| message(noon, false))
+            .collect();
+        activity.add_channel(4, &busy, start, end);
+        assert!(activity.capped);
+        assert_eq!(
+            activity.busiest_channel(),
+            Some((4, MAX_MESSAGES_PER_CHANNEL))
+        );
+    }
+}
@@ -0,0 +1,224 @@
+use crate::dm_delivery::{
+    log_limit, pending_retries, recent_attempts, set_log_limit, DmOutcome, DmRecord, DM_LOG_FILE,
+    MAX_LOG_ENTRIES,
+};
+use crate::storage;
+use crate::utils::limits::truncate;
+use crate::utils::{check_store_writable, is_protected_user};
+use crate::{Context, Error};
+use poise::serenity_prelude as serenity;
+
+/// Attempts shown per page of the log.
+const SHOWN_ATTEMPTS: usize = 15;
+
+/// Errors are cut to this length so a page fits an embed.
+const MAX_ERROR_CHARS: usize = 80;
+
+fn format_attempt(entry: &DmRecord) -> String {
+    let outcome = match &entry.outcome {
+        DmOutcome::Sent => "✅ sent".to_string(),
+        DmOutcome::Failed { error, will_retry } => format!(
+            "❌ {}{}",
+            truncate(error, MAX_ERROR_CHARS),
+            if *will_retry {
+                " • 🔁 retrying"
+            } else {
+                ""
+            }
+        ),
+    };
+    let retry = if entry.attempt > 1 {
+        format!(" (retry {})", entry.attempt - 1)
+    } else {
+        String::new()
+    };
+    let guild = entry
+        .guild_id
+        .map(|id| format!(" • server {}", id))
+        .unwrap_or_default();
+    format!(
+        "<t:{}:R> <@{}> `{}`{}{}\n{}",
+        entry.at.timestamp(),
+        entry.user_id,
+        entry.source,
+        retry,
+        guild,
+        outcome
+    )
+}
+
+/// Show recent DMs the bot sent on its own and which failed
+#[poise::command(prefix_command, slash_command, subcommands("dmlog_show", "dmlog_keep"))]
+pub async fn dmlog(
+    ctx: Context<'_>,
+    #[description = "Only show DMs to this user"] user: Option<serenity::User>,
+) -> Result<(), Error> {
+    show_log(ctx, user).await
+}
+
+/// Show recent DMs the bot sent on its own and which failed
+#[poise::command(prefix_command, slash_command, rename = "show")]
+pub async fn dmlog_show(
+    ctx: Context<'_>,
+    #[description = "Only show DMs to this user"] user: Option<serenity::User>,
+) -> Result<(), Error> {
+    show_log(ctx, user).await
+}
+
+/// Whose DMs the author may see: `Some(None)` for every server's (bot
+/// owners), `Some(Some(id))` for this server's (Manage Server), `None` if
+/// neither.
+async fn visible_guild(ctx: Context<'_>) -> Option<Option<u64>> {
+    if is_protected_user(&ctx.author().name) {
+        return Some(None);
+    }
+    let (Some(member), Some(channel)) = (ctx.author_member().await, ctx.guild_channel().await)
+    else {
+        return None;
+    };
+    let allowed = ctx
+        .guild()
+        .is_some_and(|guild| guild.user_permissions_in(&channel, &member).manage_guild());
+    allowed.then_some(Some(channel.guild_id.get()))
+}
+
+async fn show_log(ctx: Context<'_>, user: Option<serenity::User>) -> Result<(), Error> {
+    log::info!("DM log command called by {}", ctx.author().name);
+
+    let Some(guild_id) = visible_guild(ctx).await else {
+        ctx.say("❌ You need the Manage Server permission to see this server's DMs!")
+            .await?;
+        return Ok(());
+    };
+
+    let attempts = recent_attempts(guild_id, user.as_ref().map(|u| u.id.get()), SHOWN_ATTEMPTS);
+    if attempts.is_empty() {
+        ctx.say("📭 No DM attempts logged yet.").await?;
+        return Ok(());
+    }
+
+    let failed = attempts
+        .iter()
+        .filter(|entry| matches!(entry.outcome, DmOutcome::Failed { .. }))
+        .count();
+    let description = attempts
+        .iter()
+        .map(format_attempt)
+        .collect::<Vec<_>>()
+        .join("\n");
+    let title = match &user {
+        Some(user) => format!("📬 DMs to {}", user.name),
+        None => "📬 DM Deliveries".to_string(),
+    };
+
+    let embed = serenity::CreateEmbed::new()
+        .title(title)
+        .description(description)
+        .color(if failed > 0 { 0xed4245 } else { 0x57f287 })
+        .footer(serenity::CreateEmbedFooter::new(format!(
+            "{} of the last {} failed • {} waiting for a retry",
+            failed,
+            attempts.len(),
+            pending_retries()
+        )));
+    ctx.send(
+        poise::CreateReply::default()
+            .embed(embed)
+            .allowed_mentions(serenity::CreateAllowedMentions::new()),
+    )
+    .await?;
+    Ok(())
+}
+
+async fn dmlog_writable(ctx: Context<'_>) -> Result<bool, Error> {
+    check_store_writable(ctx, &storage::data_path(DM_LOG_FILE)).await
+}
+
+/// Set how many DM attempts are kept for this server
+#[poise::command(
+    prefix_command,
+    slash_command,
+    guild_only,
+    rename = "keep",
+    required_permissions = "MANAGE_GUILD",
+    check = "dmlog_writable"
+)]
+pub async fn dmlog_keep(
+    ctx: Context<'_>,
+    #[description = "How many attempts to keep (0 stops logging; leave out to see the current number)"]
+    count: Option<usize>,
+) -> Result<(), Error> {
+    log::info!("DM log keep command called by {}", ctx.author().name);
+
+    let Some(guild_id) = ctx.guild_id() else {
+        return Ok(());
+    };
+    let Some(count) = count else {
+        ctx.say(format!(
+            "📬 The last {} DM attempts about this server are kept.",
+            log_limit(guild_id.get())
+        ))
+        .await?;
+        return Ok(());
+    };
+    if count > MAX_LOG_ENTRIES {
+        ctx.say(format!(
+            "❌ At most {} attempts can be kept.",
+            MAX_LOG_ENTRIES
+        ))
+        .await?;
+        return Ok(());
+    }
+
+    if let Err(e) = set_log_limit(guild_id.get(), count) {
+        ctx.say(format!("❌ Failed to save the setting: {e}"))
+            .await?;
+        return Ok(());
+    }
+    let reply = match count {
+        0 => "🔕 DMs about this server aren't logged any more.".to_string(),
+        count => format!(
+            "📬 The last {} DM attempts about this server are kept.",
+            count
+        ),
+    };
+    ctx.say(reply).await?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::{TimeZone, Utc};
+
+    #[test]
+    fn test_format_attempt() {
+        let entry = DmRecord {
+            at: Utc.with_ymd_and_hms(2026, 5, 1, 12, 0, 0).unwrap(),
+            user_id: 42,
+            source: "reminder".to_string(),
+            guild_id: Some(7),
+            attempt: 2,
+            outcome: DmOutcome::Failed {
+                error: "Internal Server Error".to_string(),
+                will_retry: true,
+            },
+        };
+        assert_eq!(
+            format_attempt(&entry),
+            "<t:1777636800:R> <@42> `reminder` (retry 1) • server 7\n\
+             ❌ Internal Server Error • 🔁 retrying"
+        );
+
+        let sent = DmRecord {
+            attempt: 1,
+            guild_id: None,
+            outcome: DmOutcome::Sent,
+            ..entry
+        };
+        assert_eq!(
+            format_attempt(&sent),
+            "<t:1777636800:R> <@42> `reminder`\n✅ sent"
+        );
+    }
+}
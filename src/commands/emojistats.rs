@@ -0,0 +1,318 @@
+use crate::utils::messages::collect_messages;
+use crate::utils::permissions::{check_channel_perms, history_error_message, READ_HISTORY};
+use crate::{Context, Error};
+use poise::serenity_prelude as serenity;
+use std::collections::HashMap;
+
+const MAX_MESSAGES: u64 = 10000; // same cap as -stats
+/// Keeps a page of the ranking within Discord's 2000 character message limit.
+const EMOJIS_PER_PAGE: usize = 15;
+
+/// How often one server emoji was used.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+struct EmojiUse {
+    /// Times it appeared in message text.
+    in_messages: u32,
+    /// Reactions with it, counting every user who reacted.
+    reactions: u32,
+}
+
+impl EmojiUse {
+    fn total(&self) -> u32 {
+        self.in_messages + self.reactions
+    }
+}
+
+/// Rank this server's custom emojis by how often they're used
+///
+/// Scans the last N messages in a channel (like `-stats`) for custom emojis
+/// in message text and in reactions. Emojis from other servers are ignored,
+/// and server emojis that never came up are listed at the end so admins can
+/// see which ones nobody uses.
+#[poise::command(prefix_command, slash_command, guild_only)]
+pub async fn emojistats(
+    ctx: Context<'_>,
+    #[description = "Number of messages to scan (default: 1000, max: 10000)"] count: Option<u64>,
+    #[description = "Channel to scan (default: current channel)"] channel: Option<
+        serenity::GuildChannel,
+    >,
+) -> Result<(), Error> {
+    log::info!("Emojistats command called by {}", ctx.author().name);
+
+    let message_count = count.unwrap_or(1000).min(MAX_MESSAGES);
+    let target_channel = channel.map_or(ctx.channel_id(), |ch| ch.id);
+
+    let server_emojis: Vec<(u64, String)> = match ctx.guild() {
+        Some(guild) => guild
+            .emojis
+            .values()
+            .map(|emoji| (emoji.id.get(), emoji.to_string()))
+            .collect(),
+        None => Vec::new(),
+    };
+    if server_emojis.is_empty() {
+        ctx.say("❌ This server has no custom emojis to count!")
+            .await?;
+        return Ok(());
+    }
+    if let Err(missing) = check_channel_perms(ctx, target_channel, READ_HISTORY).await {
+        ctx.say(missing.to_string()).await?;
+        return Ok(());
+    }
+
+    let reply = ctx
+        .say(format!(
+            "Scanning last {message_count} messages in <#{target_channel}> for emojis..."
+        ))
+        .await?;
+
+    let reply = &reply;
+    let progress = |collected: u64| async move {
+        reply
+            .edit(
+                ctx,
+                poise::CreateReply::default()
+                    .content(format!("Scanning messages... {collected}/{message_count}")),
+            )
+            .await?;
+        Ok::<(), Error>(())
+    };
+    let messages = match collect_messages(ctx.http(), target_channel, message_count, progress).await
+    {
+        Ok(messages) => messages,
+        Err(e) => {
+            reply
+                .edit(
+                    ctx,
+                    poise::CreateReply::default()
+                        .content(history_error_message(e.as_ref(), target_channel)),
+                )
+                .await?;
+            return Ok(());
+        }
+    };
+
+    let mut uses: HashMap<u64, EmojiUse> = HashMap::new();
+    for message in &messages {
+        if !message.author.bot {
+            count_in_text(&mut uses, &message.content);
+        }
+        for reaction in &message.reactions {
+            count_reaction(&mut uses, &reaction.reaction_type, reaction.count);
+        }
+    }
+
+    let ranked = rank_emojis(&uses, &server_emojis);
+    let used = ranked
+        .iter()
+        .filter(|(_, counts)| counts.total() > 0)
+        .count();
+    let header = format!(
+        "**😀 Emoji usage in <#{}>**\nLast {} messages • {} of {} server emojis used",
+        target_channel,
+        messages.len(),
+        used,
+        server_emojis.len()
+    );
+    let pages = emoji_pages(&ranked, EMOJIS_PER_PAGE);
+
+    if pages.len() == 1 {
+        let embed = serenity::CreateEmbed::new()
+            .title("😀 Emoji Usage")
+            .description(format!("{}\n\n{}", header, pages[0]))
+            .color(0x5865F2);
+        reply
+            .edit(ctx, poise::CreateReply::default().content("").embed(embed))
+            .await?;
+    } else {
+        reply
+            .edit(
+                ctx,
+                poise::CreateReply::default().content(format!(
+                    "✅ Scanned {} messages, ranking below.",
+                    messages.len()
+                )),
+            )
+            .await?;
+        let page_count = pages.len();
+        let pages: Vec<String> = pages
+            .into_iter()
+            .enumerate()
+            .map(|(i, page)| format!("{} (page {}/{})\n\n{}", header, i + 1, page_count, page))
+            .collect();
+        let page_refs: Vec<&str> = pages.iter().map(String::as_str).collect();
+        poise::builtins::paginate(ctx, &page_refs).await?;
+    }
+
+    Ok(())
+}
+
+/// Custom emojis in message text, as (animated, name, id), from `<:name:id>`
+/// and `<a:name:id>`.
+fn parse_custom_emojis(text: &str) -> Vec<(bool, &str, u64)> {
+    let mut found = Vec::new();
+    let mut rest = text;
+    while let Some(start) = rest.find('<') {
+        rest = &rest[start + 1..];
+        let Some(end) = rest.find('>') else {
+            break;
+        };
+        let inner = &rest[..end];
+        let (animated, body) = match inner.strip_prefix("a:") {
+            Some(body) => (true, body),
+            None => match inner.strip_prefix(':') {
+                Some(body) => (false, body),
+                None => continue,
+            },
+        };
+        let Some((name, id)) = body.split_once(':') else {
+            continue;
+        };
+        let valid_name =
+            !name.is_empty() && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_');
+        if let (true, Ok(id)) = (valid_name, id.parse::<u64>()) {
+            found.push((animated, name, id));
+            rest = &rest[end + 1..];
+        }
+    }
+    found
+}
+
+fn count_in_text(uses: &mut HashMap<u64, EmojiUse>, text: &str) {
+    for (_, _, id) in parse_custom_emojis(text) {
+        uses.entry(id).or_default().in_messages += 1;
+    }
+}
+
+fn count_reaction(
+    uses: &mut HashMap<u64, EmojiUse>,
+    reaction: &serenity::ReactionType,
+    count: u64,
+) {
+    if let serenity::ReactionType::Custom { id, .. } = reaction {
+        uses.entry(id.get()).or_default().reactions += count as u32;
+    }
+}
+
+/// This server's emojis (as (id, mention)), most used first. Emojis from
+/// other servers are left out; unused ones come last, by name.
+fn rank_emojis(
+    uses: &HashMap<u64, EmojiUse>,
+    server_emojis: &[(u64, String)],
+) -> Vec<(String, EmojiUse)> {
+    let mut ranked: Vec<(String, EmojiUse)> = server_emojis
+        .iter()
+        .map(|(id, mention)| (mention.clone(), uses.get(id).copied().unwrap_or_default()))
+        .collect();
+    ranked.sort_by(|(a_name, a), (b_name, b)| {
+        b.total().cmp(&a.total()).then_with(|| a_name.cmp(b_name))
+    });
+    ranked
+}
+
+/// The ranking as pages of at most `per_page` lines, numbered throughout.
+fn emoji_pages(ranked: &[(String, EmojiUse)], per_page: usize) -> Vec<String> {
+    ranked
+        .chunks(per_page)
+        .enumerate()
+        .map(|(page, chunk)| {
+            chunk
+                .iter()
+                .enumerate()
+                .map(|(i, (mention, counts))| {
+                    format!(
+                        "**{}.** {} — **{}** ({} in messages, {} reactions)",
+                        page * per_page + i + 1,
+                        mention,
+                        counts.total(),
+                        counts.in_messages,
+                        counts.reactions
+                    )
+                })
+                .collect::<Vec<_>>()
+                .join("\n")
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_custom_emojis() {
+        assert_eq!(
+            parse_custom_emojis("gg <:pog:123> and <a:party_blob:456>!"),
+            vec![(false, "pog", 123), (true, "party_blob", 456)]
+        );
+        assert_eq!(
+            parse_custom_emojis("<:a:1><:a:1>"),
+            vec![(false, "a", 1), (false, "a", 1)]
+        );
+        // Mentions, channels, timestamps and broken markup aren't emojis
+        assert!(
+            parse_custom_emojis("<@123> <#456> <t:1700000000:R> <:nope> <:x:y> a < b").is_empty()
+        );
+        // A stray `<` doesn't hide the emoji after it
+        assert_eq!(parse_custom_emojis("1 < 2 <:ok:7>"), vec![(false, "ok", 7)]);
+    }
+
+    #[test]
+    fn test_rank_emojis() {
+        let mut uses = HashMap::new();
+        count_in_text(&mut uses, "<:pog:1> <:pog:1> <:foreign:99>");
+        count_reaction(
+            &mut uses,
+            &serenity::ReactionType::Custom {
+                animated: false,
+                id: serenity::EmojiId::new(2),
+                name: Some("kek".to_string()),
+            },
+            3,
+        );
+        count_reaction(
+            &mut uses,
+            &serenity::ReactionType::Unicode("🔥".to_string()),
+            5,
+        );
+
+        let server = vec![
+            (1, "<:pog:1>".to_string()),
+            (2, "<:kek:2>".to_string()),
+            (3, "<:unused:3>".to_string()),
+        ];
+        let ranked = rank_emojis(&uses, &server);
+        assert_eq!(
+            ranked,
+            vec![
+                (
+                    "<:kek:2>".to_string(),
+                    EmojiUse {
+                        in_messages: 0,
+                        reactions: 3
+                    }
+                ),
+                (
+                    "<:pog:1>".to_string(),
+                    EmojiUse {
+                        in_messages: 2,
+                        reactions: 0
+                    }
+                ),
+                ("<:unused:3>".to_string(), EmojiUse::default()),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_emoji_pages() {
+        let ranked: Vec<(String, EmojiUse)> = (0..45)
+            .map(|i| (format!("<:e{}:{}>", i, i + 1), EmojiUse::default()))
+            .collect();
+        let pages = emoji_pages(&ranked, EMOJIS_PER_PAGE);
+        assert_eq!(pages.len(), 3);
+        assert_eq!(pages[0].lines().count(), EMOJIS_PER_PAGE);
+        assert!(pages[1].starts_with("**16.** <:e15:16>"));
+        assert_eq!(pages[2].lines().count(), EMOJIS_PER_PAGE);
+    }
+}
@@ -0,0 +1,616 @@
+//! An idle fishing game: cast a line every half hour, collect what bites.
+//!
+//! What can be caught comes from `fish/loot.json` in the assets directory,
+//! read on every cast so new items show up without a rebuild. Each rarity
+//! has a draw weight; a cast picks a rarity by weight, then one of its items
+//! at random. Leaderboard scores come from the same weights, so rarer
+//! catches are worth more.
+
+use crate::storage;
+use crate::utils::check_store_writable;
+use crate::utils::cooldown;
+use crate::utils::limits::fit_field_value;
+use crate::utils::meme::assets_dir;
+use crate::{Context, Error};
+use chrono::{DateTime, Duration, Utc};
+use parking_lot::RwLock;
+use poise::serenity_prelude as serenity;
+use rand::seq::SliceRandom;
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::fs;
+
+const FISHING_FILE: &str = "fishing.json";
+
+const CAST_COOLDOWN_MINUTES: i64 = 30;
+
+const LEADERBOARD_SIZE: usize = 10;
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[serde(rename_all = "lowercase")]
+enum Rarity {
+    Junk,
+    Common,
+    Uncommon,
+    Rare,
+    Epic,
+    Legendary,
+}
+
+impl Rarity {
+    fn label(self) -> &'static str {
+        match self {
+            Rarity::Junk => "⚫ Junk",
+            Rarity::Common => "⚪ Common",
+            Rarity::Uncommon => "🟢 Uncommon",
+            Rarity::Rare => "🔵 Rare",
+            Rarity::Epic => "🟣 Epic",
+            Rarity::Legendary => "🟡 Legendary",
+        }
+    }
+}
+
+#[derive(Deserialize, Clone, Debug, PartialEq)]
+struct LootItem {
+    name: String,
+    emoji: String,
+    rarity: Rarity,
+}
+
+#[derive(Deserialize, Debug)]
+struct LootTable {
+    /// How likely each rarity is, relative to the others.
+    rarities: BTreeMap<Rarity, u32>,
+    items: Vec<LootItem>,
+}
+
+impl LootTable {
+    /// Rarities that can come up: weighted and with at least one item.
+    fn drawable(&self) -> impl Iterator<Item = (Rarity, u32)> + '_ {
+        self.rarities
+            .iter()
+            .filter(|(rarity, weight)| {
+                **weight > 0 && self.items.iter().any(|item| item.rarity == **rarity)
+            })
+            .map(|(rarity, weight)| (*rarity, *weight))
+    }
+
+    /// A rarity picked by weight, then one of its items.
+    fn draw<R: Rng + ?Sized>(&self, rng: &mut R) -> Option<&LootItem> {
+        let total: u32 = self.drawable().map(|(_, weight)| weight).sum();
+        if total == 0 {
+            return None;
+        }
+        let mut roll = rng.gen_range(0..total);
+        let rarity = self.drawable().find_map(|(rarity, weight)| {
+            if roll < weight {
+                Some(rarity)
+            } else {
+                roll -= weight;
+                None
+            }
+        })?;
+        let items: Vec<&LootItem> = self
+            .items
+            .iter()
+            .filter(|item| item.rarity == rarity)
+            .collect();
+        items.choose(rng).copied()
+    }
+
+    /// Points per catch of `rarity`: how many times rarer than the most
+    /// common rarity it is. Junk is worth nothing.
+    fn score(&self, rarity: Rarity) -> u64 {
+        let most_common = self.rarities.values().copied().max().unwrap_or(0);
+        match self.rarities.get(&rarity) {
+            Some(&weight) if weight > 0 && rarity != Rarity::Junk => {
+                (f64::from(most_common) / f64::from(weight)).round() as u64
+            }
+            _ => 0,
+        }
+    }
+
+    /// How many different items there are to collect.
+    fn collection_size(&self) -> usize {
+        self.items
+            .iter()
+            .map(|item| item.name.as_str())
+            .collect::<BTreeSet<_>>()
+            .len()
+    }
+}
+
+fn parse_loot_table(json: &str) -> Result<LootTable, String> {
+    let table: LootTable = serde_json::from_str(json).map_err(|e| e.to_string())?;
+    if let Some(item) = table
+        .items
+        .iter()
+        .find(|item| !table.rarities.contains_key(&item.rarity))
+    {
+        return Err(format!(
+            "`{}` is {:?}, which has no weight",
+            item.name, item.rarity
+        ));
+    }
+    if table.drawable().next().is_none() {
+        return Err("nothing can be caught".to_string());
+    }
+    Ok(table)
+}
+
+/// Read the loot table from the assets directory.
+fn load_loot_table() -> Result<LootTable, String> {
+    let path = assets_dir().join("fish").join("loot.json");
+    let json = fs::read_to_string(&path).map_err(|e| format!("{}: {}", path.display(), e))?;
+    parse_loot_table(&json).map_err(|e| format!("{}: {}", path.display(), e))
+}
+
+/// How many of one item someone has caught.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+struct Catch {
+    emoji: String,
+    rarity: Rarity,
+    count: u32,
+}
+
+#[derive(Serialize, Deserialize, Default, Debug)]
+#[serde(default)]
+struct Angler {
+    casts: u32,
+    last_cast: Option<DateTime<Utc>>,
+    /// Item name -> how many were caught. The emoji and rarity are kept so
+    /// items dropped from the loot table still show up.
+    catches: BTreeMap<String, Catch>,
+    /// Guilds the user has fished in, whose leaderboards they're on.
+    guilds: BTreeSet<u64>,
+}
+
+impl Angler {
+    /// Count a cast that caught `item`. Returns how many of it they have now.
+    fn record_catch(&mut self, item: &LootItem, now: DateTime<Utc>, guild_id: Option<u64>) -> u32 {
+        self.casts += 1;
+        self.last_cast = Some(now);
+        self.guilds.extend(guild_id);
+        let catch = self.catches.entry(item.name.clone()).or_insert(Catch {
+            emoji: item.emoji.clone(),
+            rarity: item.rarity,
+            count: 0,
+        });
+        catch.count += 1;
+        catch.count
+    }
+
+    fn score(&self, table: &LootTable) -> u64 {
+        self.catches
+            .values()
+            .map(|catch| u64::from(catch.count) * table.score(catch.rarity))
+            .sum()
+    }
+
+    /// The rarest thing they've caught.
+    fn rarest(&self) -> Option<(&String, &Catch)> {
+        self.catches.iter().max_by_key(|(_, catch)| catch.rarity)
+    }
+}
+
+#[derive(Serialize, Deserialize, Default)]
+struct FishingData {
+    users: HashMap<u64, Angler>,
+}
+
+lazy_static::lazy_static! {
+    static ref FISHING: RwLock<FishingData> = RwLock::new(storage::load(FISHING_FILE));
+}
+
+/// Re-read everyone's catches from disk.
+pub fn reload_fishing() -> Result<(), Error> {
+    *FISHING.write() = storage::reload(FISHING_FILE)?;
+    Ok(())
+}
+
+fn save_fishing(data: &FishingData) {
+    if let Err(e) = storage::save(FISHING_FILE, data) {
+        log::error!("Failed to save fishing data: {}", e);
+    }
+}
+
+async fn fishing_writable(ctx: Context<'_>) -> Result<bool, Error> {
+    check_store_writable(ctx, &storage::data_path(FISHING_FILE)).await
+}
+
+/// (user id, score) for everyone who has fished in `guild_id`, best first.
+fn leaderboard(data: &FishingData, table: &LootTable, guild_id: u64) -> Vec<(u64, u64)> {
+    let mut ranking: Vec<(u64, u64)> = data
+        .users
+        .iter()
+        .filter(|(_, angler)| angler.guilds.contains(&guild_id))
+        .map(|(user_id, angler)| (*user_id, angler.score(table)))
+        .collect();
+    ranking.sort_by(|a, b| b.1.cmp(&a.1).then(a.0.cmp(&b.0)));
+    ranking
+}
+
+/// Cast a fishing line (once every 30 minutes)
+#[poise::command(
+    prefix_command,
+    slash_command,
+    check = "fishing_writable",
+    subcommands("fish_cast", "fish_stats", "fish_leaderboard")
+)]
+pub async fn fish(ctx: Context<'_>) -> Result<(), Error> {
+    cast_line(ctx).await
+}
+
+/// Cast a fishing line (once every 30 minutes)
+#[poise::command(
+    prefix_command,
+    slash_command,
+    check = "fishing_writable",
+    rename = "cast"
+)]
+pub async fn fish_cast(ctx: Context<'_>) -> Result<(), Error> {
+    cast_line(ctx).await
+}
+
+async fn cast_line(ctx: Context<'_>) -> Result<(), Error> {
+    log::info!("Fish command called by {}", ctx.author().name);
+
+    let table = match load_loot_table() {
+        Ok(table) => table,
+        Err(e) => {
+            log::error!("Failed to load the fishing loot table: {}", e);
+            ctx.say("❌ The fishing spot is closed: its loot table couldn't be loaded.")
+                .await?;
+            return Ok(());
+        }
+    };
+
+    let now = Utc::now();
+    let outcome = {
+        let mut data = FISHING.write();
+        let angler = data.users.entry(ctx.author().id.get()).or_default();
+        match cooldown::remaining(
+            angler.last_cast,
+            Duration::minutes(CAST_COOLDOWN_MINUTES),
+            now,
+        ) {
+            Some(left) => Err(now + left),
+            None => {
+                let item = table.draw(&mut rand::thread_rng()).cloned();
+                let count = item
+                    .as_ref()
+                    .map(|item| angler.record_catch(item, now, ctx.guild_id().map(|id| id.get())));
+                save_fishing(&data);
+                Ok(item.zip(count))
+            }
+        }
+    };
+
+    let reply = match outcome {
+        Err(ready_at) => format!(
+            "🎣 Your line is still out! You can cast again <t:{}:R>.",
+            ready_at.timestamp()
+        ),
+        Ok(None) => "❌ Nothing is biting: the loot table is empty.".to_string(),
+        Ok(Some((item, count))) => {
+            let mut reply = format!(
+                "🎣 {} caught **{} {}**! ({})",
+                ctx.author().name,
+                item.emoji,
+                item.name,
+                item.rarity.label()
+            );
+            if count == 1 {
+                reply.push_str("\n✨ A new one for your collection!");
+            }
+            reply
+        }
+    };
+    ctx.say(reply).await?;
+    Ok(())
+}
+
+/// Show casts, score and rarest catch (yours by default)
+#[poise::command(prefix_command, slash_command, rename = "stats")]
+pub async fn fish_stats(
+    ctx: Context<'_>,
+    #[description = "Whose stats to show (default: you)"] user: Option<serenity::User>,
+) -> Result<(), Error> {
+    let user = user.unwrap_or_else(|| ctx.author().clone());
+    log::info!(
+        "Fish stats command called by {} for {}",
+        ctx.author().name,
+        user.name
+    );
+
+    // Stats still work without the loot table, just without the score
+    let table = load_loot_table().ok();
+    let stats = {
+        let data = FISHING.read();
+        data.users.get(&user.id.get()).map(|angler| {
+            (
+                angler.casts,
+                angler.catches.len(),
+                table.as_ref().map(|table| angler.score(table)),
+                angler.rarest().map(|(name, catch)| {
+                    format!("{} {} ({})", catch.emoji, name, catch.rarity.label())
+                }),
+            )
+        })
+    };
+
+    let Some((casts, different, score, rarest)) = stats else {
+        ctx.say(format!(
+            "🎣 {} hasn't gone fishing yet. Try `-fish`!",
+            user.name
+        ))
+        .await?;
+        return Ok(());
+    };
+
+    let collection = match &table {
+        Some(table) => format!("{}/{}", different, table.collection_size()),
+        None => different.to_string(),
+    };
+    let mut embed = serenity::CreateEmbed::new()
+        .title(format!("🎣 {}'s Fishing Stats", user.name))
+        .color(0x1e90ff)
+        .field("Casts", casts.to_string(), true)
+        .field("Collection", collection, true);
+    if let Some(score) = score {
+        embed = embed.field("Score", score.to_string(), true);
+    }
+    embed = embed.field(
+        "Rarest Catch",
+        rarest.unwrap_or_else(|| "Nothing yet".to_string()),
+        false,
+    );
+
+    ctx.send(poise::CreateReply::default().embed(embed)).await?;
+    Ok(())
+}
+
+/// Rank this server's anglers by the rarity of their catches
+#[poise::command(prefix_command, slash_command, guild_only, rename = "leaderboard")]
+pub async fn fish_leaderboard(ctx: Context<'_>) -> Result<(), Error> {
+    log::info!("Fish leaderboard command called by {}", ctx.author().name);
+
+    let Some(guild_id) = ctx.guild_id() else {
+        return Ok(());
+    };
+    let table = match load_loot_table() {
+        Ok(table) => table,
+        Err(e) => {
+            log::error!("Failed to load the fishing loot table: {}", e);
+            ctx.say("❌ Scores can't be worked out: the loot table couldn't be loaded.")
+                .await?;
+            return Ok(());
+        }
+    };
+
+    let ranking = leaderboard(&FISHING.read(), &table, guild_id.get());
+    if ranking.is_empty() {
+        ctx.say("🎣 Nobody has gone fishing here yet. Try `-fish`!")
+            .await?;
+        return Ok(());
+    }
+
+    let lines: Vec<String> = ranking
+        .iter()
+        .take(LEADERBOARD_SIZE)
+        .enumerate()
+        .map(|(i, (user_id, score))| format!("**{}.** <@{}> - {} points", i + 1, user_id, score))
+        .collect();
+    let embed = serenity::CreateEmbed::new()
+        .title("🏆 Fishing Leaderboard")
+        .color(0xffd700)
+        .description(lines.join("\n"))
+        .footer(serenity::CreateEmbedFooter::new(
+            "Rarer catches are worth more; junk is worth nothing",
+        ));
+    ctx.send(poise::CreateReply::default().embed(embed)).await?;
+    Ok(())
+}
+
+/// List everything caught fishing, by rarity (yours by default)
+#[poise::command(prefix_command, slash_command)]
+pub async fn inventory(
+    ctx: Context<'_>,
+    #[description = "Whose catches to show (default: you)"] user: Option<serenity::User>,
+) -> Result<(), Error> {
+    let user = user.unwrap_or_else(|| ctx.author().clone());
+    log::info!(
+        "Inventory command called by {} for {}",
+        ctx.author().name,
+        user.name
+    );
+
+    let groups = {
+        let data = FISHING.read();
+        data.users
+            .get(&user.id.get())
+            .map(|angler| group_by_rarity(&angler.catches))
+            .unwrap_or_default()
+    };
+    if groups.is_empty() {
+        ctx.say(format!(
+            "🎒 {} hasn't caught anything yet. Try `-fish`!",
+            user.name
+        ))
+        .await?;
+        return Ok(());
+    }
+
+    let mut embed = serenity::CreateEmbed::new()
+        .title(format!("🎒 {}'s Catches", user.name))
+        .color(0x1e90ff);
+    // Rarest first
+    for (rarity, lines) in groups.iter().rev() {
+        let count: u32 = lines.iter().map(|(_, count)| count).sum();
+        let text: Vec<&str> = lines.iter().map(|(line, _)| line.as_str()).collect();
+        embed = embed.field(
+            format!("{} ({})", rarity.label(), count),
+            fit_field_value(&text.join("\n")),
+            false,
+        );
+    }
+    ctx.send(poise::CreateReply::default().embed(embed)).await?;
+    Ok(())
+}
+
+/// Catches as "emoji name ×count" lines, with their counts, by rarity.
+fn group_by_rarity(catches: &BTreeMap<String, Catch>) -> BTreeMap<Rarity, Vec<(String, u32)>> {
+    let mut groups: BTreeMap<Rarity, Vec<(String, u32)>> = BTreeMap::new();
+    for (name, catch) in catches {
+        groups.entry(catch.rarity).or_default().push((
+            format!("{} {} ×{}", catch.emoji, name, catch.count),
+            catch.count,
+        ));
+    }
+    groups
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::random::SeededRng;
+
+    fn table() -> LootTable {
+        parse_loot_table(
+            r#"{
+                "rarities": {"junk": 300, "common": 600, "legendary": 100},
+                "items": [
+                    {"name": "Boot", "emoji": "🥾", "rarity": "junk"},
+                    {"name": "Perch", "emoji": "🐟", "rarity": "common"},
+                    {"name": "Cod", "emoji": "🐠", "rarity": "common"},
+                    {"name": "Chest", "emoji": "💰", "rarity": "legendary"}
+                ]
+            }"#,
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn test_shipped_loot_table_loads() {
+        let path = concat!(env!("CARGO_MANIFEST_DIR"), "/assets/fish/loot.json");
+        let table = parse_loot_table(&fs::read_to_string(path).unwrap()).unwrap();
+        assert!(table.drawable().count() >= 2);
+        assert!(table.score(Rarity::Legendary) > table.score(Rarity::Common));
+    }
+
+    #[test]
+    fn test_loot_table_validation() {
+        assert!(parse_loot_table("not json").is_err());
+        assert!(parse_loot_table(r#"{"rarities": {}, "items": []}"#).is_err());
+        let unweighted = r#"{
+            "rarities": {"common": 10},
+            "items": [{"name": "Ghost", "emoji": "👻", "rarity": "epic"}]
+        }"#;
+        assert!(parse_loot_table(unweighted)
+            .unwrap_err()
+            .contains("`Ghost`"));
+    }
+
+    #[test]
+    fn test_weighted_draw_distribution() {
+        let table = table();
+        let mut rng = SeededRng::new(42);
+        let draws = 20_000;
+        let mut counts: HashMap<&str, u32> = HashMap::new();
+        for _ in 0..draws {
+            *counts
+                .entry(&table.draw(&mut rng).unwrap().name)
+                .or_default() += 1;
+        }
+        let share = |name| f64::from(counts.get(name).copied().unwrap_or(0)) / f64::from(draws);
+
+        // Each bound is several standard deviations wide
+        for (name, expected) in [("Boot", 0.3), ("Perch", 0.3), ("Cod", 0.3), ("Chest", 0.1)] {
+            let actual = share(name);
+            assert!(
+                (actual - expected).abs() < 0.015,
+                "{name} came up {actual:.3} of the time, expected {expected}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_draw_is_reproducible() {
+        let table = table();
+        let run = |seed| {
+            let mut rng = SeededRng::new(seed);
+            (0..20)
+                .map(|_| table.draw(&mut rng).unwrap().name.clone())
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(run(7), run(7));
+        assert_ne!(run(7), run(8));
+    }
+
+    #[test]
+    fn test_scores_and_leaderboard() {
+        let table = table();
+        assert_eq!(table.score(Rarity::Junk), 0);
+        assert_eq!(table.score(Rarity::Common), 1);
+        assert_eq!(table.score(Rarity::Legendary), 6);
+        // Rarities missing from the table are worth nothing
+        assert_eq!(table.score(Rarity::Epic), 0);
+
+        let now = Utc::now();
+        let item = |name: &str| {
+            table
+                .items
+                .iter()
+                .find(|item| item.name == name)
+                .unwrap()
+                .clone()
+        };
+        let mut data = FishingData::default();
+        let alice = data.users.entry(1).or_default();
+        for name in ["Perch", "Perch", "Boot"] {
+            alice.record_catch(&item(name), now, Some(10));
+        }
+        assert_eq!(alice.catches["Perch"].count, 2);
+        assert_eq!(alice.rarest().unwrap().0, "Perch");
+        let bob = data.users.entry(2).or_default();
+        assert_eq!(bob.record_catch(&item("Chest"), now, Some(10)), 1);
+        assert_eq!(bob.casts, 1);
+        // Fishing in DMs or other servers doesn't put you on this board
+        data.users
+            .entry(3)
+            .or_default()
+            .record_catch(&item("Chest"), now, None);
+
+        assert_eq!(leaderboard(&data, &table, 10), vec![(2, 6), (1, 2)]);
+        assert!(leaderboard(&data, &table, 11).is_empty());
+    }
+
+    #[test]
+    fn test_group_by_rarity() {
+        let mut catches = BTreeMap::new();
+        for (name, rarity, count) in [
+            ("Perch", Rarity::Common, 2),
+            ("Boot", Rarity::Junk, 1),
+            ("Cod", Rarity::Common, 3),
+        ] {
+            catches.insert(
+                name.to_string(),
+                Catch {
+                    emoji: "🐟".to_string(),
+                    rarity,
+                    count,
+                },
+            );
+        }
+        let groups = group_by_rarity(&catches);
+        assert_eq!(
+            groups[&Rarity::Common],
+            vec![("🐟 Cod ×3".to_string(), 3), ("🐟 Perch ×2".to_string(), 2)]
+        );
+        assert_eq!(
+            groups.keys().collect::<Vec<_>>(),
+            [&Rarity::Junk, &Rarity::Common]
+        );
+    }
+}
@@ -0,0 +1,436 @@
+use crate::guild_config::{get_guild_config, guild_config_writable, update_guild_config};
+use crate::translate::{translate_text, Translation};
+use crate::{Context, Error};
+use parking_lot::Mutex;
+use poise::serenity_prelude as serenity;
+use poise::ChoiceParameter;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::time::{Duration, Instant};
+
+/// Messages longer than this aren't translated.
+pub(crate) const MAX_TRANSLATE_CHARS: usize = 1000;
+
+/// How long a translation is reused, and how long a user has to wait before the
+/// same message and language is translated for them again.
+const TRANSLATION_CACHE_TTL: Duration = Duration::from_secs(10 * 60);
+
+/// In-channel translations are deleted after this long.
+const REPLY_LIFETIME: Duration = Duration::from_secs(60);
+
+/// Flag emoji and the language (LibreTranslate code and display name) they
+/// translate into.
+const FLAG_LANGUAGES: &[(&str, &str, &str)] = &[
+    ("🇬🇧", "en", "English"),
+    ("🇺🇸", "en", "English"),
+    ("🇦🇺", "en", "English"),
+    ("🇨🇦", "en", "English"),
+    ("🇮🇪", "en", "English"),
+    ("🇫🇷", "fr", "French"),
+    ("🇩🇪", "de", "German"),
+    ("🇦🇹", "de", "German"),
+    ("🇨🇭", "de", "German"),
+    ("🇪🇸", "es", "Spanish"),
+    ("🇲🇽", "es", "Spanish"),
+    ("🇦🇷", "es", "Spanish"),
+    ("🇮🇹", "it", "Italian"),
+    ("🇵🇹", "pt", "Portuguese"),
+    ("🇧🇷", "pt", "Portuguese"),
+    ("🇳🇱", "nl", "Dutch"),
+    ("🇧🇪", "nl", "Dutch"),
+    ("🇩🇰", "da", "Danish"),
+    ("🇸🇪", "sv", "Swedish"),
+    ("🇳🇴", "nb", "Norwegian"),
+    ("🇫🇮", "fi", "Finnish"),
+    ("🇵🇱", "pl", "Polish"),
+    ("🇨🇿", "cs", "Czech"),
+    ("🇸🇰", "sk", "Slovak"),
+    ("🇸🇮", "sl", "Slovenian"),
+    ("🇭🇺", "hu", "Hungarian"),
+    ("🇷🇴", "ro", "Romanian"),
+    ("🇧🇬", "bg", "Bulgarian"),
+    ("🇬🇷", "el", "Greek"),
+    ("🇹🇷", "tr", "Turkish"),
+    ("🇷🇺", "ru", "Russian"),
+    ("🇺🇦", "uk", "Ukrainian"),
+    ("🇪🇪", "et", "Estonian"),
+    ("🇱🇻", "lv", "Latvian"),
+    ("🇱🇹", "lt", "Lithuanian"),
+    ("🇦🇱", "sq", "Albanian"),
+    ("🇯🇵", "ja", "Japanese"),
+    ("🇰🇷", "ko", "Korean"),
+    ("🇨🇳", "zh", "Chinese"),
+    ("🇹🇼", "zt", "Chinese (Traditional)"),
+    ("🇮🇳", "hi", "Hindi"),
+    ("🇮🇩", "id", "Indonesian"),
+    ("🇲🇾", "ms", "Malay"),
+    ("🇹🇭", "th", "Thai"),
+    ("🇵🇭", "tl", "Tagalog"),
+    ("🇸🇦", "ar", "Arabic"),
+    ("🇪🇬", "ar", "Arabic"),
+    ("🇮🇱", "he", "Hebrew"),
+    ("🇮🇷", "fa", "Persian"),
+    ("🇵🇰", "ur", "Urdu"),
+    ("🇧🇩", "bn", "Bengali"),
+];
+
+/// How a guild delivers flag-reaction translations.
+#[derive(
+    poise::ChoiceParameter, Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Default,
+)]
+#[serde(rename_all = "lowercase")]
+pub enum FlagTranslateMode {
+    /// Feature disabled.
+    #[default]
+    #[name = "off"]
+    Off,
+    /// Reply in the channel, deleted after a minute.
+    #[name = "reply"]
+    Reply,
+    /// DM the person who reacted.
+    #[name = "dm"]
+    Dm,
+}
+
+/// The language code and name for a flag emoji, if it's one we know.
+fn language_for_flag(emoji: &str) -> Option<(&'static str, &'static str)> {
+    FLAG_LANGUAGES
+        .iter()
+        .find(|(flag, _, _)| *flag == emoji)
+        .map(|(_, code, name)| (*code, *name))
+}
+
+/// A language by its code (`fr`) or English name (`french`), as code and
+/// name.
+pub(crate) fn find_language(input: &str) -> Option<(&'static str, &'static str)> {
+    let input = input.trim();
+    FLAG_LANGUAGES
+        .iter()
+        .find(|(_, code, name)| {
+            code.eq_ignore_ascii_case(input) || name.eq_ignore_ascii_case(input)
+        })
+        .map(|(_, code, name)| (*code, *name))
+}
+
+struct CachedTranslation {
+    /// `None` while the first request for it is still in flight.
+    translation: Option<Translation>,
+    created_at: Instant,
+    delivered_to: HashSet<u64>,
+}
+
+/// What to do with a flag reaction, from [`TranslationCache::claim`].
+#[derive(Debug)]
+enum Claim {
+    /// This user already got this translation recently.
+    AlreadyDelivered,
+    Cached(Translation),
+    Fetch,
+}
+
+/// Recent translations per (message, language), so repeat reactions neither
+/// hit the API nor post the same translation again.
+#[derive(Default)]
+struct TranslationCache {
+    entries: HashMap<(u64, &'static str), CachedTranslation>,
+}
+
+impl TranslationCache {
+    /// Record that `user_id` wants `message_id` in `language`.
+    fn claim(
+        &mut self,
+        message_id: u64,
+        language: &'static str,
+        user_id: u64,
+        now: Instant,
+    ) -> Claim {
+        self.entries
+            .retain(|_, entry| now.duration_since(entry.created_at) < TRANSLATION_CACHE_TTL);
+
+        let entry = self
+            .entries
+            .entry((message_id, language))
+            .or_insert_with(|| CachedTranslation {
+                translation: None,
+                created_at: now,
+                delivered_to: HashSet::new(),
+            });
+        if !entry.delivered_to.insert(user_id) {
+            return Claim::AlreadyDelivered;
+        }
+        match &entry.translation {
+            Some(translation) => Claim::Cached(translation.clone()),
+            None => Claim::Fetch,
+        }
+    }
+
+    fn store(&mut self, message_id: u64, language: &'static str, translation: Translation) {
+        if let Some(entry) = self.entries.get_mut(&(message_id, language)) {
+            entry.translation = Some(translation);
+        }
+    }
+
+    /// Drop a failed request so the next reaction can retry it.
+    fn forget(&mut self, message_id: u64, language: &'static str) {
+        self.entries.remove(&(message_id, language));
+    }
+}
+
+lazy_static::lazy_static! {
+    static ref TRANSLATION_CACHE: Mutex<TranslationCache> = Mutex::new(TranslationCache::default());
+}
+
+/// Translate a message when someone reacts to it with a country flag, if the
+/// guild has flag translation turned on. Called for every `ReactionAdd`.
+pub async fn handle_flag_reaction(
+    ctx: &serenity::Context,
+    reaction: &serenity::Reaction,
+) -> Result<(), Error> {
+    let (Some(guild_id), Some(user_id)) = (reaction.guild_id, reaction.user_id) else {
+        return Ok(());
+    };
+    let serenity::ReactionType::Unicode(emoji) = &reaction.emoji else {
+        return Ok(());
+    };
+    let Some((language, language_name)) = language_for_flag(emoji) else {
+        return Ok(());
+    };
+    let mode = get_guild_config(guild_id.get())
+        .map(|config| config.flag_translate)
+        .unwrap_or_default();
+    if mode == FlagTranslateMode::Off {
+        return Ok(());
+    }
+    if reaction.member.as_ref().is_some_and(|m| m.user.bot) {
+        return Ok(());
+    }
+
+    let message_id = reaction.message_id.get();
+    let claim = TRANSLATION_CACHE
+        .lock()
+        .claim(message_id, language, user_id.get(), Instant::now());
+
+    let translation = match claim {
+        Claim::AlreadyDelivered => return Ok(()),
+        Claim::Cached(translation) => translation,
+        Claim::Fetch => {
+            let message = reaction.message(&ctx.http).await?;
+            if message.content.trim().is_empty() {
+                return Ok(());
+            }
+            if message.content.chars().count() > MAX_TRANSLATE_CHARS {
+                let notice = format!(
+                    "❌ That message is too long to translate (max {} characters).",
+                    MAX_TRANSLATE_CHARS
+                );
+                deliver(ctx, reaction, mode, user_id, |embed| {
+                    embed.description(notice.as_str())
+                })
+                .await?;
+                return Ok(());
+            }
+
+            match translate_text(&message.content, language).await {
+                Ok(translation) => {
+                    TRANSLATION_CACHE
+                        .lock()
+                        .store(message_id, language, translation.clone());
+                    translation
+                }
+                Err(e) => {
+                    TRANSLATION_CACHE.lock().forget(message_id, language);
+                    log::warn!("Failed to translate message {}: {}", message_id, e);
+                    return Ok(());
+                }
+            }
+        }
+    };
+
+    let requested_by = reaction
+        .member
+        .as_ref()
+        .map(|m| m.display_name().to_string())
+        .unwrap_or_else(|| "someone".to_string());
+    let footer = match &translation.source_language {
+        Some(source) => format!("From {} • Requested by {}", source, requested_by),
+        None => format!("Requested by {}", requested_by),
+    };
+    deliver(ctx, reaction, mode, user_id, |embed| {
+        embed
+            .title(format!("{} {}", emoji, language_name))
+            .description(translation.text.as_str())
+            .footer(serenity::CreateEmbedFooter::new(footer.as_str()))
+    })
+    .await?;
+
+    log::info!(
+        "Translated message {} to {} for user {} in guild {}",
+        message_id,
+        language,
+        user_id,
+        guild_id
+    );
+    Ok(())
+}
+
+/// Send an embed to whoever reacted, the way the guild is configured to.
+async fn deliver(
+    ctx: &serenity::Context,
+    reaction: &serenity::Reaction,
+    mode: FlagTranslateMode,
+    user_id: serenity::UserId,
+    build: impl FnOnce(serenity::CreateEmbed) -> serenity::CreateEmbed,
+) -> Result<(), Error> {
+    let link = reaction
+        .message_id
+        .link(reaction.channel_id, reaction.guild_id);
+    let embed = build(serenity::CreateEmbed::new().color(0x5865F2));
+
+    match mode {
+        FlagTranslateMode::Off => {}
+        FlagTranslateMode::Reply => {
+            let reply = reaction
+                .channel_id
+                .send_message(
+                    &ctx.http,
+                    serenity::CreateMessage::new()
+                        .embed(embed)
+                        .reference_message((reaction.channel_id, reaction.message_id))
+                        .allowed_mentions(serenity::CreateAllowedMentions::new()),
+                )
+                .await?;
+
+            let http = ctx.http.clone();
+            tokio::spawn(async move {
+                tokio::time::sleep(REPLY_LIFETIME).await;
+                if let Err(e) = reply.delete(&http).await {
+                    log::warn!("Failed to delete translation reply: {}", e);
+                }
+            });
+        }
+        FlagTranslateMode::Dm => {
+            let dm_channel = user_id.create_dm_channel(&ctx.http).await?;
+            dm_channel
+                .send_message(
+                    &ctx.http,
+                    serenity::CreateMessage::new()
+                        .content(format!("🌐 Translation of {}", link))
+                        .embed(embed),
+                )
+                .await?;
+        }
+    }
+    Ok(())
+}
+
+/// Translate messages by reacting with a country flag
+#[poise::command(
+    prefix_command,
+    slash_command,
+    guild_only,
+    required_permissions = "MANAGE_GUILD"
+)]
+pub async fn flagtranslate(
+    ctx: Context<'_>,
+    #[description = "off, reply (in channel, deleted after a minute) or dm (to whoever reacted)"]
+    mode: Option<FlagTranslateMode>,
+) -> Result<(), Error> {
+    log::info!("Flagtranslate command called by {}", ctx.author().name);
+
+    let Some(guild_id) = ctx.guild_id() else {
+        return Ok(());
+    };
+
+    let Some(mode) = mode else {
+        let current = get_guild_config(guild_id.get())
+            .map(|config| config.flag_translate)
+            .unwrap_or_default();
+        ctx.say(format!(
+            "🌐 Flag translation is currently **{}**. React to a message with a country flag like 🇫🇷 to translate it.",
+            current.name()
+        ))
+        .await?;
+        return Ok(());
+    };
+
+    if !guild_config_writable(ctx).await? {
+        return Ok(());
+    }
+    update_guild_config(guild_id.get(), |config| config.flag_translate = mode);
+
+    let message = match mode {
+        FlagTranslateMode::Off => "🌐 Flag translation is now **off**.".to_string(),
+        FlagTranslateMode::Reply => format!(
+            "🌐 Flag translation is now **on**. React with a country flag like 🇫🇷 and I'll reply with a translation that disappears after {} seconds.",
+            REPLY_LIFETIME.as_secs()
+        ),
+        FlagTranslateMode::Dm => "🌐 Flag translation is now **on**. React with a country flag like 🇫🇷 and I'll DM you a translation.".to_string(),
+    };
+    ctx.say(message).await?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn translation(text: &str) -> Translation {
+        Translation {
+            text: text.to_string(),
+            source_language: Some("en".to_string()),
+        }
+    }
+
+    #[test]
+    fn test_language_for_flag() {
+        assert_eq!(language_for_flag("🇫🇷"), Some(("fr", "French")));
+        assert_eq!(language_for_flag("🇧🇷"), Some(("pt", "Portuguese")));
+        assert_eq!(language_for_flag("👍"), None);
+        assert!(FLAG_LANGUAGES.len() >= 40);
+    }
+
+    #[test]
+    fn test_find_language() {
+        assert_eq!(find_language("fr"), Some(("fr", "French")));
+        assert_eq!(find_language(" FRENCH "), Some(("fr", "French")));
+        assert_eq!(find_language("zt"), Some(("zt", "Chinese (Traditional)")));
+        assert_eq!(find_language("Klingon"), None);
+        assert_eq!(find_language(""), None);
+    }
+
+    #[test]
+    fn test_claim_debounces_per_user() {
+        let mut cache = TranslationCache::default();
+        let now = Instant::now();
+
+        assert!(matches!(cache.claim(1, "fr", 10, now), Claim::Fetch));
+        cache.store(1, "fr", translation("Bonjour"));
+
+        // Same user again: nothing to do
+        assert!(matches!(
+            cache.claim(1, "fr", 10, now),
+            Claim::AlreadyDelivered
+        ));
+        // Someone else gets the cached translation
+        match cache.claim(1, "fr", 11, now) {
+            Claim::Cached(t) => assert_eq!(t.text, "Bonjour"),
+            other => panic!("expected a cached translation, got {:?}", other),
+        }
+        // Another language is its own entry
+        assert!(matches!(cache.claim(1, "de", 10, now), Claim::Fetch));
+    }
+
+    #[test]
+    fn test_claim_expires_and_forget() {
+        let mut cache = TranslationCache::default();
+        let now = Instant::now();
+
+        cache.claim(1, "fr", 10, now);
+        cache.store(1, "fr", translation("Bonjour"));
+        let later = now + TRANSLATION_CACHE_TTL;
+        assert!(matches!(cache.claim(1, "fr", 10, later), Claim::Fetch));
+
+        cache.forget(1, "fr");
+        assert!(matches!(cache.claim(1, "fr", 10, later), Claim::Fetch));
+    }
+}
@@ -0,0 +1,280 @@
+//! `-games` and `-endgame`: a player's games across every per-player game
+//! module, in one place, through the [`GameSession`] each game implements.
+//!
+//! [`GameSession`]: crate::commands::general::GameSession
+
+use crate::commands::general::{ActiveGame, GameKind};
+use crate::{Context, Error};
+use poise::serenity_prelude as serenity;
+use poise::ChoiceParameter;
+use std::time::Duration;
+
+/// How long the end buttons keep working.
+const BUTTONS_TIMEOUT: Duration = Duration::from_secs(120);
+
+/// One line per game: what, against whom, since when and where.
+fn describe_game(game: &ActiveGame) -> String {
+    let mut line = format!("**{}**", game.kind.label());
+    if let Some(opponent) = &game.opponent {
+        line.push_str(&format!(" vs {}", opponent));
+    }
+    line.push_str(&format!(" • started <t:{}:R>", game.started.timestamp()));
+    if let Some(place) = &game.place {
+        line.push_str(&format!(" in <#{}>", place.channel_id));
+    }
+    line.push_str(&format!("\n{}", game.progress));
+    line
+}
+
+/// Which game `-endgame` means: the one asked for, the only one going, or
+/// the only one played in the channel it was run in (`here`). `Err` says
+/// why it can't tell.
+fn pick_game(
+    games: &[ActiveGame],
+    kind: Option<GameKind>,
+    here: &[GameKind],
+) -> Result<GameKind, String> {
+    match (kind, games) {
+        (Some(kind), _) if games.iter().any(|game| game.kind == kind) => Ok(kind),
+        (Some(kind), _) => Err(format!(
+            "❌ You don't have an active {} game!",
+            kind.label()
+        )),
+        (None, []) => Err("❌ You don't have any active games!".to_string()),
+        (None, [game]) => Ok(game.kind),
+        (None, _) if here.len() == 1 => Ok(here[0]),
+        (None, games) => {
+            let names: Vec<String> = games
+                .iter()
+                .map(|game| format!("`-endgame {}`", game.kind.name()))
+                .collect();
+            Err(format!(
+                "❓ You have {} games going. Say which one to end: {}",
+                games.len(),
+                names.join(", ")
+            ))
+        }
+    }
+}
+
+fn games_reply(games: &[ActiveGame], button_prefix: &str) -> poise::CreateReply {
+    if games.is_empty() {
+        return poise::CreateReply::default()
+            .content("🎮 You don't have any active games. Start one with `-numberguess`, `-tictactoe` or `-hangman`!")
+            .components(vec![]);
+    }
+
+    let description = games
+        .iter()
+        .map(describe_game)
+        .collect::<Vec<_>>()
+        .join("\n\n");
+    let embed = serenity::CreateEmbed::new()
+        .title("🎮 Your games")
+        .description(description)
+        .color(0x5865F2);
+
+    let rows = games
+        .iter()
+        .map(|game| {
+            let mut buttons = Vec::new();
+            if let Some(place) = &game.place {
+                buttons.push(
+                    serenity::CreateButton::new_link(place.link())
+                        .label(format!("Go to {}", game.kind.label())),
+                );
+            }
+            buttons.push(
+                serenity::CreateButton::new(format!("{}{}", button_prefix, game.kind.name()))
+                    .label(format!("End {}", game.kind.label()))
+                    .style(serenity::ButtonStyle::Danger),
+            );
+            serenity::CreateActionRow::Buttons(buttons)
+        })
+        .collect();
+
+    poise::CreateReply::default()
+        .content("")
+        .embed(embed)
+        .components(rows)
+}
+
+/// List your active games, with buttons to go to or end each
+#[poise::command(prefix_command, slash_command)]
+pub async fn games(ctx: Context<'_>) -> Result<(), Error> {
+    log::info!("Games command called by {}", ctx.author().name);
+
+    let user_id = ctx.author().id.get();
+    let sessions = &ctx.data().games;
+    let button_prefix = format!("{}_end_", ctx.id());
+    let games = sessions.player_games(user_id).await;
+    let reply = ctx
+        .send(games_reply(&games, &button_prefix).ephemeral(true))
+        .await?;
+    if games.is_empty() {
+        return Ok(());
+    }
+
+    loop {
+        let prefix = button_prefix.clone();
+        let press = serenity::ComponentInteractionCollector::new(ctx)
+            .author_id(ctx.author().id)
+            .channel_id(ctx.channel_id())
+            .timeout(BUTTONS_TIMEOUT)
+            .filter(move |press| press.data.custom_id.starts_with(&prefix))
+            .await;
+        let Some(press) = press else {
+            break;
+        };
+
+        let kind = press
+            .data
+            .custom_id
+            .strip_prefix(&button_prefix)
+            .and_then(GameKind::from_name);
+        if let Some(kind) = kind {
+            if let Some(message) = sessions.end_game(ctx.http(), kind, user_id).await {
+                ctx.send(poise::CreateReply::default().content(message))
+                    .await?;
+            }
+        }
+
+        let games = sessions.player_games(user_id).await;
+        let update = games_reply(&games, &button_prefix);
+        press
+            .create_response(
+                ctx,
+                serenity::CreateInteractionResponse::UpdateMessage(
+                    serenity::CreateInteractionResponseMessage::new()
+                        .content(update.content.unwrap_or_default())
+                        .embeds(update.embeds)
+                        .components(update.components.unwrap_or_default()),
+                ),
+            )
+            .await?;
+        if games.is_empty() {
+            return Ok(());
+        }
+    }
+
+    reply
+        .edit(ctx, poise::CreateReply::default().components(vec![]))
+        .await?;
+    Ok(())
+}
+
+/// End one of your games; say which if you have more than one
+#[poise::command(prefix_command, slash_command)]
+pub async fn endgame(
+    ctx: Context<'_>,
+    #[description = "Which game to end (only needed with more than one going outside this channel)"]
+    game: Option<GameKind>,
+) -> Result<(), Error> {
+    let user_id = ctx.author().id.get();
+    let sessions = &ctx.data().games;
+    let games = sessions.player_games(user_id).await;
+    let here = sessions.games_in(user_id, ctx.channel_id().get()).await;
+    let kind = match pick_game(&games, game, &here) {
+        Ok(kind) => kind,
+        Err(message) => {
+            ctx.say(message).await?;
+            return Ok(());
+        }
+    };
+
+    match sessions.end_game(ctx.http(), kind, user_id).await {
+        Some(message) => ctx.say(message).await?,
+        // Ended in the meantime, e.g. by the idle cleanup
+        None => {
+            ctx.say(format!(
+                "❌ You don't have an active {} game!",
+                kind.label()
+            ))
+            .await?
+        }
+    };
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::commands::general::GamePlace;
+    use chrono::{TimeZone, Utc};
+
+    fn game(kind: GameKind) -> ActiveGame {
+        ActiveGame {
+            kind,
+            opponent: None,
+            started: Utc.with_ymd_and_hms(2025, 1, 1, 12, 0, 0).unwrap(),
+            place: None,
+            progress: "3 moves".to_string(),
+        }
+    }
+
+    #[test]
+    fn test_pick_game() {
+        let one = [game(GameKind::Hangman)];
+        assert_eq!(pick_game(&one, None, &[]), Ok(GameKind::Hangman));
+        assert_eq!(
+            pick_game(&one, Some(GameKind::Hangman), &[]),
+            Ok(GameKind::Hangman)
+        );
+        assert_eq!(
+            pick_game(&one, Some(GameKind::TicTacToe), &[]),
+            Err("❌ You don't have an active Tic-Tac-Toe game!".to_string())
+        );
+        assert_eq!(
+            pick_game(&[], None, &[]),
+            Err("❌ You don't have any active games!".to_string())
+        );
+
+        let two = [game(GameKind::NumberGuess), game(GameKind::TicTacToe)];
+        assert_eq!(
+            pick_game(&two, None, &[]),
+            Err("❓ You have 2 games going. Say which one to end: `-endgame numberguess`, `-endgame tictactoe`".to_string())
+        );
+        assert_eq!(
+            pick_game(&two, Some(GameKind::TicTacToe), &[]),
+            Ok(GameKind::TicTacToe)
+        );
+        // Run in the channel of one of them, it's that one
+        assert_eq!(
+            pick_game(&two, None, &[GameKind::TicTacToe]),
+            Ok(GameKind::TicTacToe)
+        );
+        assert!(pick_game(&two, None, &[GameKind::NumberGuess, GameKind::TicTacToe]).is_err());
+    }
+
+    #[test]
+    fn test_describe_game() {
+        let mut ttt = game(GameKind::TicTacToe);
+        ttt.opponent = Some("<@2>".to_string());
+        ttt.place = Some(GamePlace {
+            guild_id: Some(1),
+            channel_id: 10,
+            message_id: Some(100),
+        });
+        assert_eq!(
+            describe_game(&ttt),
+            "**Tic-Tac-Toe** vs <@2> • started <t:1735732800:R> in <#10>\n3 moves"
+        );
+        assert_eq!(
+            describe_game(&game(GameKind::Hangman)),
+            "**Hangman** • started <t:1735732800:R>\n3 moves"
+        );
+    }
+
+    #[test]
+    fn test_game_links() {
+        let mut place = GamePlace {
+            guild_id: Some(1),
+            channel_id: 10,
+            message_id: Some(100),
+        };
+        assert_eq!(place.link(), "https://discord.com/channels/1/10/100");
+        place.guild_id = None;
+        place.message_id = None;
+        assert_eq!(place.link(), "https://discord.com/channels/@me/10");
+    }
+}
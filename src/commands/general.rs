@@ -1,14 +1,32 @@
-// This file serves as a placeholder for general commands
-// Individual commands have been moved to their own files:
-// - ping.rs
-// - hello.rs
-// spamping.rs
-
-// You can add new general utility functions here that are shared
-// across multiple commands, or create new command functions that
-// don't fit into more specific categories.
+// General commands that don't fit into a more specific file, and helpers
+// shared across several commands. Individual commands have their own files
+// (ping.rs, hello.rs, spamping.rs).
+//
+// Games in progress are tracked here: the `GameSession` trait each game
+// implements, the `GameSessions` kept in `Data` that `-games` and `-endgame`
+// go through, and the task that ends idle games. The `-avatarguess` server
+// game lives here too.
 
+use crate::commands::hangman::{self, HangmanGame};
+use crate::commands::number_guess::{self, NumberGame};
+use crate::commands::tictactoe::{self, TicTacToeGame};
+use crate::utils::avatar::{download_avatar, still_avatar_url};
 use crate::{Context, Error};
+use chrono::{DateTime, Utc};
+use image::{imageops::FilterType, DynamicImage, RgbaImage};
+use poise::futures_util::StreamExt;
+use poise::serenity_prelude as serenity;
+use rand::seq::SliceRandom;
+use std::collections::HashMap;
+use std::future::Future;
+use std::io::Cursor;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tokio::sync::RwLock;
+use tokio::time::interval;
+
+/// Games with no moves for this long are evicted by the cleanup task.
+pub const GAME_IDLE_TIMEOUT: Duration = Duration::from_secs(30 * 60);
 
 // Utility function to print raw API errors - useful for debugging
 #[allow(dead_code)]
@@ -18,6 +36,452 @@ pub async fn print_api_error(ctx: Context<'_>, error: &Error) -> Result<(), Erro
     Ok(())
 }
 
+/// Read-only snapshot of one game module's active games, used by the
+/// `-gamestatus all` overview. Each game module exposes an
+/// `active_games_summary()` that returns one of these.
+pub struct GameSummary {
+    pub name: &'static str,
+    pub active: usize,
+    pub details: Vec<String>,
+}
+
+/// The per-player games, for `-games` and `-endgame <type>`.
+#[derive(poise::ChoiceParameter, Clone, Copy, Debug, PartialEq)]
+pub enum GameKind {
+    #[name = "numberguess"]
+    NumberGuess,
+    #[name = "tictactoe"]
+    TicTacToe,
+    #[name = "hangman"]
+    Hangman,
+}
+
+impl GameKind {
+    pub fn label(self) -> &'static str {
+        match self {
+            GameKind::NumberGuess => "Number Guess",
+            GameKind::TicTacToe => "Tic-Tac-Toe",
+            GameKind::Hangman => "Hangman",
+        }
+    }
+}
+
+/// Where a game is played, for links back to it.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct GamePlace {
+    pub guild_id: Option<u64>,
+    pub channel_id: u64,
+    /// The game's own message, if it keeps one up to date.
+    pub message_id: Option<u64>,
+}
+
+impl GamePlace {
+    /// The channel a command was run in.
+    pub fn here(ctx: Context<'_>) -> Self {
+        GamePlace {
+            guild_id: ctx.guild_id().map(|guild_id| guild_id.get()),
+            channel_id: ctx.channel_id().get(),
+            message_id: None,
+        }
+    }
+
+    pub fn link(&self) -> String {
+        let guild = self
+            .guild_id
+            .map_or("@me".to_string(), |guild_id| guild_id.to_string());
+        let mut link = format!("https://discord.com/channels/{}/{}", guild, self.channel_id);
+        if let Some(message_id) = self.message_id {
+            link.push_str(&format!("/{}", message_id));
+        }
+        link
+    }
+}
+
+/// One player's game in one game module, as [`GameSession::describe`]
+/// shows it in `-games`.
+pub struct ActiveGame {
+    pub kind: GameKind,
+    /// Who they're up against, e.g. a mention or "AI"; `None` when playing alone.
+    pub opponent: Option<String>,
+    pub started: DateTime<Utc>,
+    /// `None` for games started outside Discord (the REPL).
+    pub place: Option<GamePlace>,
+    pub progress: String,
+}
+
+/// A game players have going, as `-games` and `-endgame` see it. Each
+/// per-player game implements this, and its sessions live in [`GameSessions`].
+pub trait GameSession: Send + Sync {
+    /// Which game it is, for `-endgame <type>`.
+    const KIND: GameKind;
+
+    /// The other player in a two-player game, under whom it's stored too.
+    fn other_player(&self, _user_id: u64) -> Option<u64> {
+        None
+    }
+
+    /// The game as `user_id`, one of its players, sees it listed.
+    fn describe(&self, user_id: u64) -> ActiveGame;
+
+    /// The channel it's played in; `None` for games started outside Discord
+    /// (the REPL).
+    fn channel_id(&self) -> Option<u64>;
+
+    /// What to tell the player who ended it early.
+    fn end(&self) -> String;
+
+    /// Tidy up on Discord after it was ended early, e.g. disable its board.
+    fn close(&self, _http: &serenity::Http) -> impl Future<Output = ()> + Send {
+        async {}
+    }
+}
+
+/// Every per-player game in progress, by player. Kept in [`crate::Data`];
+/// the idle cleanup task shares it.
+#[derive(Default)]
+pub struct GameSessions {
+    pub(crate) number_guess: RwLock<HashMap<u64, NumberGame>>,
+    pub(crate) tictactoe: RwLock<HashMap<u64, TicTacToeGame>>,
+    pub(crate) hangman: RwLock<HashMap<u64, HangmanGame>>,
+}
+
+impl GameSessions {
+    /// Every game the player has going, one per game type at most.
+    pub async fn player_games(&self, user_id: u64) -> Vec<ActiveGame> {
+        [
+            player_session(&self.number_guess, user_id).await,
+            player_session(&self.tictactoe, user_id).await,
+            player_session(&self.hangman, user_id).await,
+        ]
+        .into_iter()
+        .flatten()
+        .collect()
+    }
+
+    /// Which of the player's games are played in `channel_id`.
+    pub async fn games_in(&self, user_id: u64, channel_id: u64) -> Vec<GameKind> {
+        [
+            session_in(&self.number_guess, user_id, channel_id).await,
+            session_in(&self.tictactoe, user_id, channel_id).await,
+            session_in(&self.hangman, user_id, channel_id).await,
+        ]
+        .into_iter()
+        .flatten()
+        .collect()
+    }
+
+    /// End the player's game of `kind`. Returns what to tell them, `None` if
+    /// they had no such game.
+    pub async fn end_game(
+        &self,
+        http: &serenity::Http,
+        kind: GameKind,
+        user_id: u64,
+    ) -> Option<String> {
+        match kind {
+            GameKind::NumberGuess => end_session(&self.number_guess, http, user_id).await,
+            GameKind::TicTacToe => end_session(&self.tictactoe, http, user_id).await,
+            GameKind::Hangman => end_session(&self.hangman, http, user_id).await,
+        }
+    }
+
+    /// Drop games nobody has played in for a while. Returns how many were
+    /// removed.
+    async fn expire_idle_games(&self, http: &serenity::Http) -> usize {
+        number_guess::expire_idle_games(&self.number_guess).await
+            + hangman::expire_idle_games(&self.hangman).await
+            + tictactoe::expire_idle_games(&self.tictactoe, http).await
+    }
+}
+
+async fn player_session<G: GameSession>(
+    games: &RwLock<HashMap<u64, G>>,
+    user_id: u64,
+) -> Option<ActiveGame> {
+    Some(games.read().await.get(&user_id)?.describe(user_id))
+}
+
+async fn session_in<G: GameSession>(
+    games: &RwLock<HashMap<u64, G>>,
+    user_id: u64,
+    channel_id: u64,
+) -> Option<GameKind> {
+    let here = games.read().await.get(&user_id)?.channel_id() == Some(channel_id);
+    here.then_some(G::KIND)
+}
+
+/// Take the player's game out of `games`, for both players of a two-player
+/// game, and wrap it up.
+async fn end_session<G: GameSession>(
+    games: &RwLock<HashMap<u64, G>>,
+    http: &serenity::Http,
+    user_id: u64,
+) -> Option<String> {
+    let game = {
+        let mut games = games.write().await;
+        let game = games.remove(&user_id)?;
+        if let Some(other) = game.other_player(user_id) {
+            games.remove(&other);
+        }
+        game
+    };
+    game.close(http).await;
+    Some(game.end())
+}
+
+/// Whether a game last played at `last_activity` has been idle for too long.
+pub fn is_game_idle(last_activity: Instant, now: Instant) -> bool {
+    now.saturating_duration_since(last_activity) >= GAME_IDLE_TIMEOUT
+}
+
+/// Start the background task that evicts abandoned games from every game module
+pub fn start_game_cleanup_task(http: Arc<serenity::Http>, sessions: Arc<GameSessions>) {
+    tokio::spawn(async move {
+        let mut interval = interval(Duration::from_secs(60)); // Check every minute
+
+        loop {
+            interval.tick().await;
+
+            let expired = sessions.expire_idle_games(&http).await;
+            if expired > 0 {
+                log::info!("Expired {} abandoned game(s)", expired);
+            }
+        }
+    });
+}
+
+/// Grid sizes the avatar is shown at, one step every `AVATAR_REVEAL_INTERVAL`.
+/// Stays on the last step until the game ends.
+const PIXELATION_STAGES: &[u32] = &[8, 12, 16, 24, 32, 48, 64];
+
+/// Width and height of the posted image.
+const AVATAR_IMAGE_SIZE: u32 = 256;
+
+const AVATAR_REVEAL_INTERVAL: Duration = Duration::from_secs(15);
+
+/// How long a round lasts before the answer is revealed.
+const AVATAR_GAME_DURATION: Duration = Duration::from_secs(3 * 60);
+
+// Channels with an avatar guessing game running, and when it started
+lazy_static::lazy_static! {
+    static ref AVATAR_GAMES: RwLock<HashMap<u64, Instant>> = RwLock::new(HashMap::new());
+}
+
+/// Read-only overview of active avatar guessing games
+pub async fn avatar_games_summary() -> GameSummary {
+    let games = AVATAR_GAMES.read().await;
+    let now = Instant::now();
+    let mut details: Vec<String> = games
+        .iter()
+        .map(|(channel_id, started)| {
+            format!(
+                "<#{}> — started {}s ago",
+                channel_id,
+                now.saturating_duration_since(*started).as_secs()
+            )
+        })
+        .collect();
+    details.sort();
+
+    GameSummary {
+        name: "Avatar Guess",
+        active: games.len(),
+        details,
+    }
+}
+
+/// Shrink `image` to a `blocks`×`blocks` grid, averaging each block, then
+/// blow it back up to `size`×`size` with hard pixel edges.
+pub fn pixelate(image: &DynamicImage, blocks: u32, size: u32) -> RgbaImage {
+    let small = image.resize_exact(blocks, blocks, FilterType::Triangle);
+    image::imageops::resize(&small.to_rgba8(), size, size, FilterType::Nearest)
+}
+
+/// Edit distance between two strings, counted in characters.
+fn levenshtein(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut previous: Vec<usize> = (0..=b.len()).collect();
+    for (i, ca) in a.chars().enumerate() {
+        let mut current = vec![i + 1];
+        for (j, cb) in b.iter().enumerate() {
+            let substitution = previous[j] + usize::from(ca != *cb);
+            current.push(substitution.min(previous[j + 1] + 1).min(current[j] + 1));
+        }
+        previous = current;
+    }
+    previous[b.len()]
+}
+
+/// Whether a guess names the member, ignoring case and allowing a typo or
+/// two in longer names.
+fn is_correct_guess(guess: &str, names: &[String]) -> bool {
+    let guess = guess.trim().to_lowercase();
+    if guess.is_empty() {
+        return false;
+    }
+    names.iter().any(|name| {
+        let name = name.to_lowercase();
+        let tolerance = match name.chars().count() {
+            0..=4 => 0,
+            5..=8 => 1,
+            _ => 2,
+        };
+        levenshtein(&guess, &name) <= tolerance
+    })
+}
+
+/// Encode an image as a PNG attachment.
+fn png_attachment(image: RgbaImage, filename: &str) -> Result<serenity::CreateAttachment, Error> {
+    let mut bytes = Vec::new();
+    DynamicImage::ImageRgba8(image)
+        .write_to(&mut Cursor::new(&mut bytes), image::ImageOutputFormat::Png)?;
+    Ok(serenity::CreateAttachment::bytes(bytes, filename))
+}
+
+async fn send_pixelated_avatar(
+    ctx: Context<'_>,
+    avatar: &DynamicImage,
+    stage: usize,
+) -> Result<(), Error> {
+    let blocks = PIXELATION_STAGES[stage];
+    let attachment = png_attachment(pixelate(avatar, blocks, AVATAR_IMAGE_SIZE), "avatar.png")?;
+    let embed = serenity::CreateEmbed::new()
+        .title("🕵️ Whose avatar is this?")
+        .description("Type their name in chat to guess!")
+        .image("attachment://avatar.png")
+        .color(serenity::Color::PURPLE)
+        .footer(serenity::CreateEmbedFooter::new(format!(
+            "{}×{} pixels • Gets sharper every {} seconds",
+            blocks,
+            blocks,
+            AVATAR_REVEAL_INTERVAL.as_secs()
+        )));
+    ctx.send(
+        poise::CreateReply::default()
+            .embed(embed)
+            .attachment(attachment),
+    )
+    .await?;
+    Ok(())
+}
+
+/// Guess which server member a pixelated avatar belongs to
+#[poise::command(prefix_command, slash_command, guild_only)]
+pub async fn avatarguess(ctx: Context<'_>) -> Result<(), Error> {
+    log::info!("Avatarguess command called by {}", ctx.author().name);
+
+    let channel_id = ctx.channel_id();
+    {
+        let mut games = AVATAR_GAMES.write().await;
+        if games.contains_key(&channel_id.get()) {
+            ctx.say("❌ There's already an avatar guessing game running in this channel!")
+                .await?;
+            return Ok(());
+        }
+        games.insert(channel_id.get(), Instant::now());
+    }
+
+    let result = run_avatar_guess(ctx).await;
+    AVATAR_GAMES.write().await.remove(&channel_id.get());
+    result
+}
+
+async fn run_avatar_guess(ctx: Context<'_>) -> Result<(), Error> {
+    // Pick from cached members that have their own avatar
+    let picked = ctx.guild().and_then(|guild| {
+        let candidates: Vec<&serenity::Member> = guild
+            .members
+            .values()
+            .filter(|member| !member.user.bot && member.user.avatar.is_some())
+            .collect();
+        candidates
+            .choose(&mut rand::thread_rng())
+            .map(|member| (*member).clone())
+    });
+    let Some(member) = picked else {
+        ctx.say("❌ I couldn't find any members with an avatar to guess!")
+            .await?;
+        return Ok(());
+    };
+
+    let avatar_url = still_avatar_url(&member.user);
+    let avatar = match download_avatar(&avatar_url).await {
+        Ok(avatar) => avatar,
+        Err(e) => {
+            ctx.say(format!(
+                "❌ Couldn't load {}'s avatar: {}",
+                member.display_name(),
+                e
+            ))
+            .await?;
+            return Ok(());
+        }
+    };
+
+    let mut names = vec![member.user.name.clone()];
+    names.extend(member.user.global_name.clone());
+    names.extend(member.nick.clone());
+    let answer = member.display_name().to_string();
+
+    log::info!(
+        "Avatar guess started in channel {} (answer: {})",
+        ctx.channel_id(),
+        member.user.name
+    );
+
+    let mut stage = 0;
+    send_pixelated_avatar(ctx, &avatar, stage).await?;
+
+    let mut guesses = serenity::MessageCollector::new(ctx.serenity_context())
+        .channel_id(ctx.channel_id())
+        .filter(|message| !message.author.bot)
+        .timeout(AVATAR_GAME_DURATION)
+        .stream();
+    let mut reveal = tokio::time::interval_at(
+        tokio::time::Instant::now() + AVATAR_REVEAL_INTERVAL,
+        AVATAR_REVEAL_INTERVAL,
+    );
+    let deadline = tokio::time::sleep(AVATAR_GAME_DURATION);
+    tokio::pin!(deadline);
+
+    let winner = loop {
+        tokio::select! {
+            guess = guesses.next() => match guess {
+                Some(message) if is_correct_guess(&message.content, &names) => {
+                    break Some(message.author);
+                }
+                Some(_) => {}
+                None => break None,
+            },
+            _ = reveal.tick() => {
+                if stage + 1 < PIXELATION_STAGES.len() {
+                    stage += 1;
+                    send_pixelated_avatar(ctx, &avatar, stage).await?;
+                }
+            }
+            _ = &mut deadline => break None,
+        }
+    };
+
+    let description = match &winner {
+        Some(user) => format!("👑 <@{}> guessed it! It was **{}**.", user.id, answer),
+        None => format!("⏰ Time's up! It was **{}**.", answer),
+    };
+    let embed = serenity::CreateEmbed::new()
+        .title("🕵️ Avatar Guess")
+        .description(description)
+        .thumbnail(avatar_url)
+        .color(serenity::Color::GOLD);
+    ctx.send(
+        poise::CreateReply::default()
+            .embed(embed)
+            .allowed_mentions(serenity::CreateAllowedMentions::new()),
+    )
+    .await?;
+
+    Ok(())
+}
+
 // Example of a shared utility function:
 #[allow(dead_code)]
 pub fn get_user_display_name(user: &poise::serenity_prelude::User) -> String {
@@ -36,3 +500,62 @@ pub fn get_user_display_name(user: &poise::serenity_prelude::User) -> String {
 //     ctx.say("Your response!").await?;
 //     Ok(())
 // }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use image::Rgba;
+
+    #[test]
+    fn test_pixelate_makes_uniform_blocks() {
+        // Left half red, right half blue
+        let source = RgbaImage::from_fn(64, 64, |x, _| {
+            if x < 32 {
+                Rgba([255, 0, 0, 255])
+            } else {
+                Rgba([0, 0, 255, 255])
+            }
+        });
+        let pixelated = pixelate(&DynamicImage::ImageRgba8(source), 8, 256);
+
+        assert_eq!(pixelated.dimensions(), (256, 256));
+        // Every pixel within a 32×32 block is the same colour
+        for block_x in 0..8 {
+            for block_y in 0..8 {
+                let corner = pixelated.get_pixel(block_x * 32, block_y * 32);
+                assert_eq!(
+                    pixelated.get_pixel(block_x * 32 + 31, block_y * 32 + 31),
+                    corner
+                );
+            }
+        }
+        assert_eq!(*pixelated.get_pixel(0, 0), Rgba([255, 0, 0, 255]));
+        assert_eq!(*pixelated.get_pixel(255, 255), Rgba([0, 0, 255, 255]));
+    }
+
+    #[test]
+    fn test_levenshtein() {
+        assert_eq!(levenshtein("kitten", "sitting"), 3);
+        assert_eq!(levenshtein("", "abc"), 3);
+        assert_eq!(levenshtein("same", "same"), 0);
+        assert_eq!(levenshtein("æøå", "æoå"), 1);
+    }
+
+    #[test]
+    fn test_is_correct_guess() {
+        let names = vec![
+            "deekahy".to_string(),
+            "Dee Kahy".to_string(),
+            "Bob".to_string(),
+        ];
+        assert!(is_correct_guess("DeeKahy", &names));
+        assert!(is_correct_guess("  dee kahy ", &names));
+        assert!(is_correct_guess("deekahi", &names));
+        assert!(is_correct_guess("bob", &names));
+        // Short names must be exact
+        assert!(!is_correct_guess("bo", &names));
+        assert!(!is_correct_guess("rob", &names));
+        assert!(!is_correct_guess("", &names));
+        assert!(!is_correct_guess("completely wrong", &names));
+    }
+}
@@ -1,33 +1,36 @@
+use crate::commands::general::{
+    is_game_idle, ActiveGame, GameKind, GamePlace, GameSession, GameSessions, GameSummary,
+};
+use crate::utils::reply::Replier;
 use crate::{Context, Error};
 
+use chrono::{DateTime, Utc};
 use rand::Rng;
 use std::collections::{HashMap, HashSet};
+use std::time::Instant;
 use tokio::sync::RwLock;
 
-// Global storage for active games
-lazy_static::lazy_static! {
-    static ref ACTIVE_GAMES: RwLock<HashMap<u64, HangmanGame>> = RwLock::new(HashMap::new());
-}
-
+/// A Hangman game, stored by player in [`GameSessions`].
 #[derive(Clone)]
-struct HangmanGame {
+pub(crate) struct HangmanGame {
     word: String,
     guessed_letters: HashSet<char>,
     wrong_guesses: Vec<char>,
     max_wrong_guesses: usize,
     category: String,
+    started: DateTime<Utc>,
+    place: Option<GamePlace>,
+    last_activity: Instant,
 }
 
 impl HangmanGame {
     fn new() -> Self {
-        let (word, category) = Self::get_random_word();
-        HangmanGame {
-            word: word.to_uppercase(),
-            guessed_letters: HashSet::new(),
-            wrong_guesses: Vec::new(),
-            max_wrong_guesses: 6,
-            category: category.to_string(),
-        }
+        Self::new_with_rng(&mut rand::thread_rng())
+    }
+
+    fn new_with_rng<R: Rng + ?Sized>(rng: &mut R) -> Self {
+        let (word, category) = Self::get_random_word(rng);
+        Self::new_with_word(word, category)
     }
 
     fn new_with_word(word: &str, category: &str) -> Self {
@@ -37,10 +40,13 @@ impl HangmanGame {
             wrong_guesses: Vec::new(),
             max_wrong_guesses: 6,
             category: category.to_string(),
+            started: Utc::now(),
+            place: None,
+            last_activity: Instant::now(),
         }
     }
 
-    fn get_random_word() -> (&'static str, &'static str) {
+    fn get_random_word<R: Rng + ?Sized>(rng: &mut R) -> (&'static str, &'static str) {
         let word_categories = vec![
             // Programming terms
             ("RUST", "Programming Language"),
@@ -91,7 +97,6 @@ impl HangmanGame {
             ("PERSEVERANCE", "General"),
         ];
 
-        let mut rng = rand::thread_rng();
         let index = rng.gen_range(0..word_categories.len());
         word_categories[index]
     }
@@ -136,6 +141,7 @@ impl HangmanGame {
     }
 
     fn guess_letter(&mut self, letter: char) -> GuessResult {
+        self.last_activity = Instant::now();
         let letter = letter.to_uppercase().next().unwrap();
 
         if !letter.is_alphabetic() {
@@ -219,6 +225,79 @@ impl HangmanGame {
     }
 }
 
+/// Drop games nobody has guessed in for a while. Returns how many were removed.
+pub(crate) async fn expire_idle_games(games: &RwLock<HashMap<u64, HangmanGame>>) -> usize {
+    let mut games = games.write().await;
+    remove_idle_games(&mut games, Instant::now())
+}
+
+fn remove_idle_games(games: &mut HashMap<u64, HangmanGame>, now: Instant) -> usize {
+    let before = games.len();
+    games.retain(|_, game| !is_game_idle(game.last_activity, now));
+    before - games.len()
+}
+
+/// Read-only overview of active Hangman games
+pub(crate) async fn active_games_summary(games: &RwLock<HashMap<u64, HangmanGame>>) -> GameSummary {
+    let games = games.read().await;
+    let mut details: Vec<String> = games
+        .iter()
+        .map(|(user_id, game)| {
+            format!(
+                "<@{}> — {}, {}/{} wrong",
+                user_id,
+                game.category,
+                game.wrong_guesses.len(),
+                game.max_wrong_guesses
+            )
+        })
+        .collect();
+    details.sort();
+
+    GameSummary {
+        name: "Hangman",
+        active: games.len(),
+        details,
+    }
+}
+
+impl GameSession for HangmanGame {
+    const KIND: GameKind = GameKind::Hangman;
+
+    fn describe(&self, _user_id: u64) -> ActiveGame {
+        ActiveGame {
+            kind: Self::KIND,
+            opponent: None,
+            started: self.started,
+            place: self.place,
+            progress: format!(
+                "{}, {}/{} wrong",
+                self.category,
+                self.wrong_guesses.len(),
+                self.max_wrong_guesses
+            ),
+        }
+    }
+
+    fn channel_id(&self) -> Option<u64> {
+        self.place.map(|place| place.channel_id)
+    }
+
+    /// Reveals the word.
+    fn end(&self) -> String {
+        format!(
+            "🏳️ **Hangman game ended!**\n\
+            The word was: **{}**\n\
+            🎯 Category: {}\n\
+            📊 You made {} wrong guesses\n\
+            Thanks for playing! 👋",
+            self.word,
+            self.category,
+            self.wrong_guesses.len()
+        )
+    }
+}
+
 enum GuessResult {
     Correct(usize),
     Wrong,
@@ -227,32 +306,53 @@ enum GuessResult {
 }
 
 /// Start a Hangman word guessing game!
-#[poise::command(prefix_command, slash_command)]
+#[poise::command(prefix_command, slash_command, aliases("hm"))]
 pub async fn hangman(
-    ctx: Context<'_>,
+    mut ctx: Context<'_>,
     #[description = "Custom word to guess (optional)"] custom_word: Option<String>,
 ) -> Result<(), Error> {
-    let user_id = ctx.author().id.get();
+    let (user_id, player) = (ctx.author().id.get(), ctx.author().name.clone());
+    let place = GamePlace::here(ctx);
+    let sessions = ctx.data().games.clone();
+    start_game(
+        &mut ctx,
+        &sessions,
+        user_id,
+        &player,
+        Some(place),
+        custom_word,
+    )
+    .await
+}
 
+/// Start a game for `user_id`, or explain why one can't be started.
+pub async fn start_game(
+    out: &mut impl Replier,
+    sessions: &GameSessions,
+    user_id: u64,
+    player: &str,
+    place: Option<GamePlace>,
+    custom_word: Option<String>,
+) -> Result<(), Error> {
     // Check if user already has an active game
     {
-        let games = ACTIVE_GAMES.read().await;
+        let games = sessions.hangman.read().await;
         if games.contains_key(&user_id) {
-            ctx.say("❌ You already have an active Hangman game! Use `/letter <letter>` to guess or `/endhangman` to quit.").await?;
+            out.say("❌ You already have a Hangman game going! Use `/letter <letter>` to play or `/endgame hangman` to quit.").await?;
             return Ok(());
         }
     }
 
-    let game = match custom_word {
+    let mut game = match custom_word {
         Some(word) => {
             let word = word.trim().to_uppercase();
             if word.is_empty() || word.len() > 20 {
-                ctx.say("❌ Custom word must be between 1-20 characters!")
+                out.say("❌ Custom word must be between 1-20 characters!")
                     .await?;
                 return Ok(());
             }
             if !word.chars().all(|c| c.is_alphabetic() || c.is_whitespace()) {
-                ctx.say("❌ Custom word can only contain letters and spaces!")
+                out.say("❌ Custom word can only contain letters and spaces!")
                     .await?;
                 return Ok(());
             }
@@ -260,6 +360,7 @@ pub async fn hangman(
         }
         None => HangmanGame::new(),
     };
+    game.place = place;
 
     let response = format!(
         "🎪 **Hangman Game Started!**\n\n\
@@ -273,34 +374,44 @@ pub async fn hangman(
         game.display_hangman(),
         game.display_word(),
         game.get_progress_info(),
-        ctx.author().name
+        player
     );
 
     {
-        let mut games = ACTIVE_GAMES.write().await;
+        let mut games = sessions.hangman.write().await;
         games.insert(user_id, game);
     }
 
-    ctx.say(response).await?;
+    out.say(response).await?;
     Ok(())
 }
 
 /// Guess a letter in your active Hangman game
 #[poise::command(prefix_command, slash_command)]
 pub async fn letter(
-    ctx: Context<'_>,
+    mut ctx: Context<'_>,
     #[description = "Letter to guess"] letter: String,
 ) -> Result<(), Error> {
     let user_id = ctx.author().id.get();
+    let sessions = ctx.data().games.clone();
+    guess(&mut ctx, &sessions, user_id, &letter).await
+}
 
+/// Guess a letter in `user_id`'s game.
+pub async fn guess(
+    out: &mut impl Replier,
+    sessions: &GameSessions,
+    user_id: u64,
+    letter: &str,
+) -> Result<(), Error> {
     if letter.len() != 1 {
-        ctx.say("❌ Please enter exactly one letter!").await?;
+        out.say("❌ Please enter exactly one letter!").await?;
         return Ok(());
     }
 
     let letter_char = letter.chars().next().unwrap();
 
-    let mut games = ACTIVE_GAMES.write().await;
+    let mut games = sessions.hangman.write().await;
 
     if let Some(game) = games.get_mut(&user_id) {
         let result = game.guess_letter(letter_char);
@@ -320,7 +431,7 @@ pub async fn letter(
                         game.wrong_guesses.len(),
                         game.category
                     );
-                    ctx.say(response).await?;
+                    out.say(response).await?;
                     games.remove(&user_id);
                 } else {
                     let count_text = if count == 1 {
@@ -340,7 +451,7 @@ pub async fn letter(
                         game.get_progress_info(),
                         game.get_guessed_info()
                     );
-                    ctx.say(response).await?;
+                    out.say(response).await?;
                 }
             }
             GuessResult::Wrong => {
@@ -355,7 +466,7 @@ pub async fn letter(
                         game.word,
                         game.category
                     );
-                    ctx.say(response).await?;
+                    out.say(response).await?;
                     games.remove(&user_id);
                 } else {
                     let response = format!(
@@ -370,22 +481,22 @@ pub async fn letter(
                         game.get_progress_info(),
                         game.get_guessed_info()
                     );
-                    ctx.say(response).await?;
+                    out.say(response).await?;
                 }
             }
             GuessResult::AlreadyGuessed => {
-                ctx.say(format!(
+                out.say(format!(
                     "❌ You've already guessed the letter '{}'!",
                     letter_char.to_uppercase()
                 ))
                 .await?;
             }
             GuessResult::InvalidInput => {
-                ctx.say("❌ Please enter a valid letter (A-Z)!").await?;
+                out.say("❌ Please enter a valid letter (A-Z)!").await?;
             }
         }
     } else {
-        ctx.say("❌ You don't have an active Hangman game! Start one with `/hangman`")
+        out.say("❌ You don't have an active Hangman game! Start one with `/hangman`")
             .await?;
     }
 
@@ -394,10 +505,19 @@ pub async fn letter(
 
 /// Show your current Hangman game status
 #[poise::command(prefix_command, slash_command)]
-pub async fn hangmanstatus(ctx: Context<'_>) -> Result<(), Error> {
+pub async fn hangmanstatus(mut ctx: Context<'_>) -> Result<(), Error> {
     let user_id = ctx.author().id.get();
+    let sessions = ctx.data().games.clone();
+    show_status(&mut ctx, &sessions, user_id).await
+}
 
-    let games = ACTIVE_GAMES.read().await;
+/// Show `user_id`'s game.
+pub async fn show_status(
+    out: &mut impl Replier,
+    sessions: &GameSessions,
+    user_id: u64,
+) -> Result<(), Error> {
+    let games = sessions.hangman.read().await;
 
     if let Some(game) = games.get(&user_id) {
         let response = format!(
@@ -413,9 +533,9 @@ pub async fn hangmanstatus(ctx: Context<'_>) -> Result<(), Error> {
             game.get_guessed_info()
         );
 
-        ctx.say(response).await?;
+        out.say(response).await?;
     } else {
-        ctx.say("❌ You don't have an active Hangman game! Start one with `/hangman`")
+        out.say("❌ You don't have an active Hangman game! Start one with `/hangman`")
             .await?;
     }
 
@@ -424,10 +544,19 @@ pub async fn hangmanstatus(ctx: Context<'_>) -> Result<(), Error> {
 
 /// Get a hint for your current Hangman game
 #[poise::command(prefix_command, slash_command)]
-pub async fn hangmanhint(ctx: Context<'_>) -> Result<(), Error> {
+pub async fn hangmanhint(mut ctx: Context<'_>) -> Result<(), Error> {
     let user_id = ctx.author().id.get();
+    let sessions = ctx.data().games.clone();
+    show_hint(&mut ctx, &sessions, user_id).await
+}
 
-    let games = ACTIVE_GAMES.read().await;
+/// Give a hint for `user_id`'s game.
+pub async fn show_hint(
+    out: &mut impl Replier,
+    sessions: &GameSessions,
+    user_id: u64,
+) -> Result<(), Error> {
+    let games = sessions.hangman.read().await;
 
     if let Some(game) = games.get(&user_id) {
         let word_length = game.word.len();
@@ -459,9 +588,9 @@ pub async fn hangmanhint(ctx: Context<'_>) -> Result<(), Error> {
             unique_letters
         );
 
-        ctx.say(hint_text).await?;
+        out.say(hint_text).await?;
     } else {
-        ctx.say("❌ You don't have an active Hangman game! Start one with `/hangman`")
+        out.say("❌ You don't have an active Hangman game! Start one with `/hangman`")
             .await?;
     }
 
@@ -470,33 +599,38 @@ pub async fn hangmanhint(ctx: Context<'_>) -> Result<(), Error> {
 
 /// End your current Hangman game
 #[poise::command(prefix_command, slash_command)]
-pub async fn endhangman(ctx: Context<'_>) -> Result<(), Error> {
+pub async fn endhangman(mut ctx: Context<'_>) -> Result<(), Error> {
     let user_id = ctx.author().id.get();
+    let sessions = ctx.data().games.clone();
+    end_game(&mut ctx, &sessions, user_id).await
+}
 
-    let mut games = ACTIVE_GAMES.write().await;
-
-    if let Some(game) = games.remove(&user_id) {
-        let response = format!(
-            "🏳️ **Hangman game ended!**\n\
-            The word was: **{}**\n\
-            🎯 Category: {}\n\
-            📊 You made {} wrong guesses\n\
-            Thanks for playing! 👋",
-            game.word,
-            game.category,
-            game.wrong_guesses.len()
-        );
-        ctx.say(response).await?;
-    } else {
-        ctx.say("❌ You don't have an active Hangman game!").await?;
+/// End `user_id`'s game and reveal the word.
+pub async fn end_game(
+    out: &mut impl Replier,
+    sessions: &GameSessions,
+    user_id: u64,
+) -> Result<(), Error> {
+    // Nothing to tidy up on Discord, so this also runs offline
+    let game = sessions.hangman.write().await.remove(&user_id);
+    match game {
+        Some(game) => out.say(game.end()).await?,
+        None => out.say("❌ You don't have an active Hangman game!").await?,
     }
-
     Ok(())
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::utils::random::SeededRng;
+
+    #[test]
+    fn test_random_word_with_seed() {
+        let word = |seed| HangmanGame::new_with_rng(&mut SeededRng::new(seed)).word;
+        assert_eq!(word(42), word(42));
+        assert_eq!(word(42), "ABSTRACTION");
+    }
 
     #[test]
     fn test_hangman_game_creation() {
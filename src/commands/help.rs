@@ -1,4 +1,5 @@
-use crate::{Context, Error};
+use crate::utils::limits::{fit_list, fit_title, split_field, EMBED_DESCRIPTION};
+use crate::{Context, Data, Error};
 use poise::serenity_prelude as serenity;
 
 /// Shows all available commands and their descriptions
@@ -10,17 +11,28 @@ use poise::serenity_prelude as serenity;
 /// # Usage
 /// - `-help` or `/help` - Show all available commands
 /// - `-help command_name` - Show detailed help for a specific command
+/// - `-help search keyword` - List the commands mentioning a keyword
 #[poise::command(prefix_command, slash_command)]
 pub async fn help(
     ctx: Context<'_>,
-    #[description = "Specific command to show help for"] command: Option<String>,
+    #[description = "Specific command to show help for, or \"search <keyword>\""]
+    #[rest]
+    command: Option<String>,
 ) -> Result<(), Error> {
     log::info!("Help command called by {}", ctx.author().name);
 
+    let command = command.as_deref().map(str::trim);
     match command {
+        Some(query) if query.eq_ignore_ascii_case(SEARCH_KEYWORD) => {
+            ctx.say("❌ Usage: `-help search <keyword>`").await?;
+            Ok(())
+        }
+        Some(query) if starts_with_search(query) => {
+            show_search_results(ctx, query[SEARCH_KEYWORD.len()..].trim()).await
+        }
         Some(command_name) => {
             // Show help for a specific command
-            show_command_help(ctx, &command_name).await
+            show_command_help(ctx, command_name).await
         }
         None => {
             // Show general help with all commands
@@ -29,69 +41,207 @@ pub async fn help(
     }
 }
 
-async fn show_general_help(ctx: Context<'_>) -> Result<(), Error> {
+const SEARCH_KEYWORD: &str = "search";
+
+/// At most this many matches are listed; the rest are only counted.
+const MAX_SEARCH_RESULTS: usize = 15;
+
+fn starts_with_search(query: &str) -> bool {
+    query
+        .split_once(char::is_whitespace)
+        .is_some_and(|(first, _)| first.eq_ignore_ascii_case(SEARCH_KEYWORD))
+}
+
+async fn show_search_results(ctx: Context<'_>, keyword: &str) -> Result<(), Error> {
+    let matches = search_commands(&ctx.framework().options().commands, keyword);
+    if matches.is_empty() {
+        ctx.say(format!(
+            "🔍 No commands match `{keyword}`. Use `-help` to see them all."
+        ))
+        .await?;
+        return Ok(());
+    }
+
+    let mut lines: Vec<String> = matches
+        .iter()
+        .take(MAX_SEARCH_RESULTS)
+        .map(|(name, description)| format!("• `-{name}` - {description}"))
+        .collect();
+    if matches.len() > MAX_SEARCH_RESULTS {
+        lines.push(format!(
+            "...and {} more, try a longer keyword",
+            matches.len() - MAX_SEARCH_RESULTS
+        ));
+    }
+
     let embed = serenity::CreateEmbed::new()
+        .title(fit_title(&format!("Help: commands matching \"{keyword}\"")))
+        .description(fit_list(&lines, EMBED_DESCRIPTION))
+        .color(0x5865F2)
+        .footer(serenity::CreateEmbedFooter::new(
+            "Use -help <command> for details",
+        ));
+    ctx.send(poise::CreateReply::default().embed(embed)).await?;
+    Ok(())
+}
+
+/// Commands whose name, alias or description contains `keyword` (ignoring
+/// case), as `(qualified name, short description)`. Top-level commands are
+/// described from the help tables when they have an entry, subcommands and
+/// the rest from their doc comments.
+fn search_commands(
+    commands: &[poise::Command<Data, Error>],
+    keyword: &str,
+) -> Vec<(String, String)> {
+    let keyword = keyword.to_lowercase();
+    let mut matches = Vec::new();
+    // Qualified names are built here rather than read from the commands,
+    // which only get them once the framework is built
+    let mut pending: Vec<(String, &poise::Command<Data, Error>)> = commands
+        .iter()
+        .rev()
+        .map(|command| (command.name.clone(), command))
+        .collect();
+    while let Some((name, command)) = pending.pop() {
+        pending.extend(
+            command
+                .subcommands
+                .iter()
+                .rev()
+                .map(|sub| (format!("{} {}", name, sub.name), sub)),
+        );
+        if command.hide_in_help {
+            continue;
+        }
+
+        let description = command_info(&name)
+            .map(|info| info.description.to_string())
+            .or_else(|| command.description.clone())
+            .unwrap_or_default();
+        let is_match = std::iter::once(&name)
+            .chain(&command.aliases)
+            .chain(std::iter::once(&description))
+            .any(|text| text.to_lowercase().contains(&keyword));
+        if is_match {
+            matches.push((name, description));
+        }
+    }
+    matches
+}
+
+/// The sections of the general help menu, as (title, command list).
+const GENERAL_SECTIONS: &[(&str, &str)] = &[
+    (
+        "Basic",
+        "• `-ping` - Check bot latency and responsiveness\n\
+         • `-pinghistory` - Recent latency with lowest, average and highest\n\
+         • `-hello [name]` - Get a friendly greeting\n\
+         • `-help [command]` - Show this menu, or details for one command\n\
+         • `-help search <keyword>` - Find commands by keyword\n\
+         • `-invite` - Get the bot's invite link\n\
+         • `-status` - Show bot diagnostics and health\n\
+         • `-botstats` - Show the slowest commands by average run time",
+    ),
+    (
+        "Fun & Social",
+        "• `-coinflip` (`-cf`) - Flip a coin\n\
+         • `-dice [sides]` - Roll a die (default 6 sides)\n\
+         • `-uwu [--level 1-3] <text>` - Convert text to uwu speak, up to level 3 chaos (or reply to a message)\n\
+         • `-mock <text>` - Alternating-case mocking text (or reply to a message)\n\
+         • `-clap <text>` - Put 👏 between 👏 words (or reply to a message)\n\
+         • `-owoify <text>` - Sprinkle emoticons into text (or reply to a message)\n\
+         • `-yourmom` - Show a random server member\n\
+         • `-pfp [user]` - Get a user's profile picture\n\
+         • `-bonk [user]` - Bonk a user (avatar on a bonk GIF)\n\
+         • `-hit [user]` - Order a hit on a user (avatar on a hit GIF)\n\
+         • `-combo @user` - Chain 2-3 bonk/hit GIFs on a user (every 10 minutes)\n\
+         • `-ship @user1 @user2` - How compatible are they?\n\
+         • `-ascii [width]` - Turn an image into ASCII art\n\
+         • `-color set|clear|palette|daily` - Pick your name color",
+    ),
+    (
+        "Chat Tools",
+        "• `-poll <question? opt1 opt2 ...>` - Create a reaction poll\n\
+         • `-pollstraw <question>` - Quick 👍 / 👎 / 🤷 poll\n\
+         • `-polltemplate save|use|list|delete` - Saved option sets for recurring polls\n\
+         • `-pollclose` - Close a poll and post its results with a chart (reply to the poll)\n\
+         • `-react <text>` - Spell out text with emoji reactions (reply to a message)\n\
+         • `-react preset <name>` - React with a preset mood like hype or sad (reply to a message)\n\
+         • `-tr [language]` - Translate a message into your language (reply to a message)\n\
+         • `-reactmap set <char> <emoji>` - Spell a character with this server's own emoji in `-react`\n\
+         • `-spamping <user>` - Ping a user in a thread until they respond\n\
+         • `-stats [count] [channel] [users|roles]` - Channel activity report with charts, or by role\n\
+         • `-statscompare <channel> <channel> [count]` - Compare activity in two channels\n\
+         • `-emojistats [count] [channel]` - Which server emojis get used\n\
+         • `-attachmentstats [count] [channel]` - Who posts the most files\n\
+         • `-remind set|for|after|nag|followup|whenfree|countdown|ack|list|remove|edit|clear|cancel|when|import|quiethours` (`-r`) - Manage personal reminders; `-r 10m tea` is short for `-remind set 10m tea`\n\
+         • `-note add|list|done|delete` - Personal notes and todos, optionally with a reminder\n\
+         • `-timestamp <when> [style] [all]` - Discord timestamp markup for a time, to paste into messages\n\
+         • `-autoreact add|list|remove` - Auto-react to matching messages (Manage Messages)\n\
+         • `-automod add|remove|list|exempt|timeout` - Remove messages with blocked words (Manage Server)\n\
+         • `-autopublish on|off|list` - Publish announcement channel posts automatically (Manage Channels)\n\
+         • `-streak [user]` - Daily activity streaks, with milestone roles (Manage Server to configure)\n\
+         • `-digest set|off|show|preview` - Daily summary of yesterday's activity (Manage Server)\n\
+         • `-birthday set|remove|list|channel|role` - Birthday wishes and a birthday role (Manage Server to configure)
+         • `-bumper set|off|quiet|add|remove|list` - Conversation starters for channels gone quiet (Manage Channels)\n\
+         • `-boost channel|message` - Thank server boosters (Manage Server)\n\
+         • `-invites [user]|top|channel` - Who invited whom\n\
+         • `-setup` - Checklist of what this server has set up, with a menu for the rest (Manage Server)
+         • `-statuschannel set|clear|show` - Where to announce restarts (Manage Server)\n\
+         • `-botadmin set|clear|show` - The role protected as bot admins (Manage Server)\n\
+         • `-botreplies threaded|mention` - Long output in threads, the reply to pings (Manage Server)\n\
+         • `-flagtranslate [off|reply|dm]` - Translate messages flagged with a country emoji (Manage Server)\n\
+         • `-usage [days]` - This server's most used commands (Manage Server)",
+    ),
+    (
+        "Voice / Music",
+        "• `-play <url|search> [channel link]` - Play YouTube audio in a voice channel\n\
+         • `-sounds` - List the soundboard clips `-play <name>` plays\n\
+         • `-queue` - Show what's playing and queued\n\
+         • `-skip` - Skip the current track\n\
+         • `-stop` - Stop playback and clear the queue\n\
+         • `-leave` (`-disconnect`, `-dc`) - Leave the voice channel",
+    ),
+    (
+        "Games",
+        "• `-numberguess [min] [max]` - Guess the number (also `-guess`, `-hint`, `-gamestatus`, `-endgame`)\n\
+         • `-tictactoe` (`-ttt`) `[@opponent]` - Tic-Tac-Toe vs a player or the AI (also `-move_ttt`, `-board`, `-endttt`)\n\
+         • `-hangman` (`-hm`) - Word guessing game (also `-letter`, `-hangmanstatus`, `-hangmanhint`, `-endhangman`)\n\
+         • `-avatarguess` - Guess whose pixelated avatar it is\n\
+         • `-fish` `[stats|leaderboard]` - Idle fishing every 30 minutes (also `-inventory`)\n\
+         See `GAMES.md` for full rules.",
+    ),
+    (
+        "Utility & Owner",
+        "• `-park now|info|clear|schedule` - Mobile parking helper\n\
+         • `-cleanup [count|after]` - Delete messages (protected)\n\
+         • `-update` - Pull latest from GitHub and restart (protected)\n\
+         • `-kys` - Reboot the bot with a 1-hour cooldown (protected)\n\
+         • `-maintenance on [message]|off` - Freeze the bot for everyone else (protected)\n\
+         • `-reload-config` / `-configcheck` - Re-read or check stored settings (protected)\n\
+         • `-dmlog [user]` / `-dmlog keep [count]` - Recent DMs from reminders and parking, and which failed\n\
+         • `-gamestatus all` - Overview of every active game (protected)\n\
+         • `-backup now|list|restore <id>` - Back up or restore the bot's data (protected)\n\
+         • `-selftest` - Check storage, Discord, assets and the parking key (protected)\n\
+         • `-assets [list|reload]` - Usable and skipped bonk/hit GIFs, or rescan them (protected)",
+    ),
+];
+
+async fn show_general_help(ctx: Context<'_>) -> Result<(), Error> {
+    let mut embed = serenity::CreateEmbed::new()
         .title("RustBot — Commands")
-        .description("All commands work with both the `-` prefix and `/` slash forms.\nUse `-help <command>` for details on a specific command.")
+        .description("All commands work with both the `-` prefix and `/` slash forms.\nUse `-help <command>` for details on a specific command, or `-help search <keyword>` to find one.")
         .color(0x5865F2)
-        .field(
-            "Basic",
-            "• `-ping` - Check bot latency and responsiveness\n\
-             • `-hello [name]` - Get a friendly greeting\n\
-             • `-help [command]` - Show this menu, or details for one command\n\
-             • `-invite` - Get the bot's invite link\n\
-             • `-status` - Show bot diagnostics and health",
-            false,
-        )
-        .field(
-            "Fun & Social",
-            "• `-coinflip` - Flip a coin\n\
-             • `-dice [sides]` - Roll a die (default 6 sides)\n\
-             • `-uwu <text>` - Convert text to uwu speak (or reply to a message)\n\
-             • `-mock <text>` - Alternating-case mocking text (or reply to a message)\n\
-             • `-yourmom` - Show a random server member\n\
-             • `-pfp [user]` - Get a user's profile picture\n\
-             • `-bonk [user]` - Bonk a user (avatar on a bonk GIF)\n\
-             • `-hit [user]` - Order a hit on a user (avatar on a hit GIF)",
-            false,
-        )
-        .field(
-            "Chat Tools",
-            "• `-poll <question? opt1 opt2 ...>` - Create a reaction poll\n\
-             • `-react <text>` - Spell out text with emoji reactions (reply to a message)\n\
-             • `-spamping <user> [count]` - Ping a user in a thread until they respond\n\
-             • `-stats [count] [channel]` - Channel activity report with charts\n\
-             • `-remind set|list|remove|clear` - Manage personal reminders",
-            false,
-        )
-        .field(
-            "Voice / Music",
-            "• `-play <url|search> [channel link]` - Play YouTube audio in a voice channel\n\
-             • `-queue` - Show what's playing and queued\n\
-             • `-skip` - Skip the current track\n\
-             • `-stop` - Stop playback and clear the queue\n\
-             • `-leave` - Leave the voice channel (aliases: `-disconnect`, `-dc`)",
-            false,
-        )
-        .field(
-            "Games",
-            "• `-numberguess [min] [max]` - Guess the number (also `-guess`, `-hint`, `-gamestatus`, `-endgame`)\n\
-             • `-tictactoe [@opponent]` - Tic-Tac-Toe vs a player or the AI (also `-move_ttt`, `-board`, `-endttt`)\n\
-             • `-hangman` - Word guessing game (also `-letter`, `-hangmanstatus`, `-hangmanhint`, `-endhangman`)\n\
-             See `GAMES.md` for full rules.",
-            false,
-        )
-        .field(
-            "Utility & Owner",
-            "• `-park now|info|clear|schedule` - Mobile parking helper\n\
-             • `-cleanup [count|after]` - Delete messages (protected)\n\
-             • `-update` - Pull latest from GitHub and restart (protected)\n\
-             • `-kys` - Reboot the bot with a 1-hour cooldown (protected)",
-            false,
-        )
         .footer(serenity::CreateEmbedFooter::new("Built with Rust + Poise"))
         .timestamp(serenity::Timestamp::now());
 
+    // Sections too long for one field continue in the next
+    for (name, value) in GENERAL_SECTIONS {
+        for (name, value) in split_field(name, value) {
+            embed = embed.field(name, value, false);
+        }
+    }
+
     ctx.send(poise::CreateReply::default().embed(embed).ephemeral(false))
         .await?;
 
@@ -99,7 +249,33 @@ async fn show_general_help(ctx: Context<'_>) -> Result<(), Error> {
 }
 
 async fn show_command_help(ctx: Context<'_>, command_name: &str) -> Result<(), Error> {
-    let command_info = match command_name.to_lowercase().as_str() {
+    // Aliases like `-cf` lead to the same help as the command itself
+    let registered = find_registered(&ctx.framework().options().commands, command_name);
+    let lookup = registered.map_or_else(|| command_name.to_lowercase(), |c| c.name.clone());
+    let Some(command_info) = command_info(&lookup) else {
+        ctx.send(
+            poise::CreateReply::default()
+                .content(format!(
+                    "❌ Command `{command_name}` not found. Use `-help` to see all available commands."
+                ))
+                .ephemeral(true),
+        )
+        .await?;
+        return Ok(());
+    };
+
+    let aliases = registered.map_or(&[][..], |command| command.aliases.as_slice());
+    let embed = create_command_help_embed(&command_info, aliases);
+
+    ctx.send(poise::CreateReply::default().embed(embed).ephemeral(false))
+        .await?;
+
+    Ok(())
+}
+
+/// The detailed help for a top-level command, by its name.
+fn command_info(name: &str) -> Option<CommandInfo> {
+    let info = match name {
         "ping" => CommandInfo {
             name: "ping",
             description: "Check bot latency and responsiveness",
@@ -107,6 +283,15 @@ async fn show_command_help(ctx: Context<'_>, command_name: &str) -> Result<(), E
             examples: vec!["-ping"],
             parameters: vec![],
         },
+        "pinghistory" => CommandInfo {
+            name: "pinghistory",
+            description: "Look back at recent API latency",
+            usage: "`-pinghistory` or `/pinghistory`",
+            examples: vec!["-pinghistory"],
+            parameters: vec![
+                "The last 60 samples as a chart with lowest, average and highest; samples are API calls timed by `status` and a check every 5 minutes",
+            ],
+        },
         "hello" => CommandInfo {
             name: "hello",
             description: "Get a friendly greeting from the bot",
@@ -133,9 +318,13 @@ async fn show_command_help(ctx: Context<'_>, command_name: &str) -> Result<(), E
         "uwu" => CommandInfo {
             name: "uwu",
             description: "Convert text to uwu speak (cute anime-style text)",
-            usage: "`-uwu <text>` or `/uwu <text>`",
-            examples: vec!["-uwu hello world", "-uwu this is so cool"],
-            parameters: vec!["text - The text to convert to uwu speak"],
+            usage: "`-uwu [--level <1-3>] <text>` or `/uwu <text>`",
+            examples: vec!["-uwu hello world", "-uwu --level 3 this is so cool"],
+            parameters: vec![
+                "--level - 1 (default) swaps letters, 2 adds h-hewwo stutters and more faces, 3 adds random caps and doubled letters. Goes before the text, so text starting with a number stays as it is",
+                "text - The text to convert to uwu speak (optional when replying to a message)",
+                "Links, mentions, emoji and code are left as they are",
+            ],
         },
         "yourmom" => CommandInfo {
             name: "yourmom",
@@ -146,10 +335,21 @@ async fn show_command_help(ctx: Context<'_>, command_name: &str) -> Result<(), E
         },
         "spamping" => CommandInfo {
             name: "spamping",
-            description: "Send multiple ping messages (use responsibly!)",
-            usage: "`-spamping <count>` or `/spamping <count>`",
-            examples: vec!["-spamping 3", "-spamping 5"],
-            parameters: vec!["count - Number of ping messages to send (1-10)"],
+            description: "Ping a user every 10 seconds in a thread until they respond (use responsibly!)",
+            usage: "`-spamping <user> [fallback]` or `/spamping <user> [fallback]`",
+            examples: vec!["-spamping @username", "-spamping @username true"],
+            parameters: vec![
+                "user - Who to ping; not bots, moderators (Manage Messages) or the bot-admin role",
+                "fallback (optional) - true to ping in the channel itself (at most 10 times) if a thread can't be made there",
+                "Stops when they reply, after 50 pings, if they leave the server, or if the thread is deleted or archived",
+            ],
+        },
+        "botadmin" => CommandInfo {
+            name: "botadmin",
+            description: "Choose the role whose members count as this server's bot admins (Manage Server)",
+            usage: "`-botadmin set <role>`, `-botadmin clear`, `-botadmin show` or `/botadmin ...`",
+            examples: vec!["-botadmin set @Mods", "-botadmin show"],
+            parameters: vec!["Bot admins are protected from `-spamping`"],
         },
         "pfp" => CommandInfo {
             name: "pfp",
@@ -161,43 +361,390 @@ async fn show_command_help(ctx: Context<'_>, command_name: &str) -> Result<(), E
         "stats" => CommandInfo {
             name: "stats",
             description: "Analyze channel activity and render a chart report (top users, hourly activity, message share)",
-            usage: "`-stats [count] [channel]` or `/stats [count] [channel]`",
-            examples: vec!["-stats", "-stats 2000", "-stats 500 #general"],
+            usage: "`-stats [count] [channel] [users|roles]` or `/stats [count] [channel] [group]`",
+            examples: vec!["-stats", "-stats 2000", "-stats 500 #general", "-stats 1000 roles"],
             parameters: vec![
                 "count (optional) - Number of messages to analyze (default: 1000, max: 10000)",
                 "channel (optional) - Channel to analyze (default: current channel)",
+                "group (optional) - users (default) for the chart report, or roles to rank roles by activity; each author counts towards their highest role, and people who left are \"Former members\"",
+            ],
+        },
+        "emojistats" => CommandInfo {
+            name: "emojistats",
+            description: "Rank this server's custom emojis by how often they show up in recent messages and reactions; unused ones are listed last",
+            usage: "`-emojistats [count] [channel]` or `/emojistats [count] [channel]`",
+            examples: vec!["-emojistats", "-emojistats 5000 #general"],
+            parameters: vec![
+                "count (optional) - Messages to scan (default: 1000, max: 10000)",
+                "channel (optional) - Channel to scan (default: current channel)",
+            ],
+        },
+        "attachmentstats" => CommandInfo {
+            name: "attachmentstats",
+            description: "Break down recent attachments by type, rank uploaders by file count and total size, and link the largest files",
+            usage: "`-attachmentstats [count] [channel]` or `/attachmentstats [count] [channel]`",
+            examples: vec!["-attachmentstats", "-attachmentstats 5000 #art"],
+            parameters: vec![
+                "count (optional) - Messages to analyze (default: 1000, max: 10000)",
+                "channel (optional) - Channel to analyze (default: current channel)",
+                "Types come from the file's content type, or its extension when that's missing; bot messages are left out",
+            ],
+        },
+        "statscompare" => CommandInfo {
+            name: "statscompare",
+            description: "Compare activity in two channels side by side (messages, users, words, top user, peak hour)",
+            usage: "`-statscompare <first> <second> [count]` or `/statscompare <first> <second> [count]`",
+            examples: vec!["-statscompare #general #memes", "-statscompare #general #memes 3000"],
+            parameters: vec![
+                "first - First channel to analyze",
+                "second - Second channel to analyze",
+                "count (optional) - Messages to analyze per channel (default: 1000, max: 5000)",
+            ],
+        },
+        "translate" => CommandInfo {
+            name: "translate",
+            description: "Translate the message you reply to into your own language, detecting the language it's in",
+            usage: "`-tr [language]` (reply to a message), `/translate auto <message link or ID> [language]`, `-translate language [language]`",
+            examples: vec![
+                "-tr",
+                "-tr de",
+                "/translate auto message:https://discord.com/channels/1/2/3",
+                "-translate language French",
+            ],
+            parameters: vec![
+                "language (optional) - A code like `fr` or a name like `French`; defaults to your language",
+                "auto [message] [language] - Translate a linked message, or the one you reply to",
+                "language [language] - Show or set the language your translations come out in (English until set)",
+                "The translation is posted as a reply to the original message; messages over 1000 characters are skipped",
+            ],
+        },
+        "flagtranslate" => CommandInfo {
+            name: "flagtranslate",
+            description: "Translate a message when someone reacts to it with a country flag like 🇫🇷 (requires Manage Server)",
+            usage: "`-flagtranslate [mode]` or `/flagtranslate [mode]`",
+            examples: vec!["-flagtranslate", "-flagtranslate reply", "-flagtranslate dm"],
+            parameters: vec![
+                "mode (optional) - off, reply (in channel, deleted after 60 seconds) or dm (to whoever reacted); shows the current mode if left out",
+            ],
+        },
+        "automod" => CommandInfo {
+            name: "automod",
+            description: "Remove messages containing blocked words, and optionally time out repeat offenders (requires Manage Server)",
+            usage: "`-automod add|remove <word>`, `-automod list`, `-automod exempt [role] [channel]`, `-automod timeout <strikes> [minutes]`",
+            examples: vec![
+                "-automod add heck",
+                "-automod exempt @Moderators",
+                "-automod timeout 3 10",
+                "-automod timeout 0",
+            ],
+            parameters: vec![
+                "add/remove <word> - Block or unblock a word or phrase, matched as whole words in any case (up to 100)",
+                "list - Show the blocklist, exemptions and timeout setting",
+                "exempt [role] [channel] - Exempt a role or channel from automod, or undo it",
+                "timeout <strikes> [minutes] - Time out members after this many removed messages within an hour (0 turns it off; default 10 minutes)",
+            ],
+        },
+        "autopublish" => CommandInfo {
+            name: "autopublish",
+            description: "Publish new posts in announcement channels automatically (requires Manage Channels)",
+            usage: "`-autopublish on [channel] [role] [user]`, `-autopublish off [channel]`, `-autopublish list`",
+            examples: vec![
+                "-autopublish on",
+                "-autopublish on #news @Editors",
+                "-autopublish off #news",
+            ],
+            parameters: vec![
+                "on [channel] [role] [user] - Auto-publish in an announcement channel (default: this one); a role or user limits it to their posts, otherwise anyone with Manage Channels",
+                "off [channel] - Stop auto-publishing in a channel",
+                "list - Show the channels that auto-publish and whose posts",
+                "Discord allows 10 publishes per channel per hour; later posts are queued (⏳) and published as soon as allowed",
+            ],
+        },
+        "setup" => CommandInfo {
+            name: "setup",
+            description: "Checklist of the features this server can set up, whether each is, and whether the bot can post where they post (requires Manage Server)",
+            usage: "`-setup` or `/setup`",
+            examples: vec!["-setup"],
+            parameters: vec![
+                "✅ set up, ⬜ not yet, with the command that sets it up",
+                "Pick an item from the menu to set it up: some are one click in the current channel, the rest show the command to run",
+            ],
+        },
+        "statuschannel" => CommandInfo {
+            name: "statuschannel",
+            description: "Announce when the bot is back online, with its version and restart reason (requires Manage Server)",
+            usage: "`-statuschannel set <channel>`, `-statuschannel clear`, `-statuschannel show`",
+            examples: vec!["-statuschannel set #bot-status", "-statuschannel clear"],
+            parameters: vec![
+                "set <channel> - Post startup messages in this channel",
+                "clear - Stop posting startup messages in this server",
+                "show - Show the current status channel",
+            ],
+        },
+        "autoreact" => CommandInfo {
+            name: "autoreact",
+            description: "React to messages matching a pattern with an emoji (requires Manage Messages)",
+            usage: "`-autoreact add <pattern> <emoji> [regex]`, `-autoreact list`, `-autoreact remove <id>`",
+            examples: vec![
+                "-autoreact add friday 🎉",
+                "-autoreact add \"^gm\\b\" ☀️ true",
+                "-autoreact remove 3",
+            ],
+            parameters: vec![
+                "pattern - Text to look for (case-insensitive), or a regex (max 1000 characters)",
+                "emoji - Standard or custom emoji to react with",
+                "regex (optional) - Treat the pattern as a regular expression",
+                "Rules apply in the order they were added, up to 3 reactions per message",
+            ],
+        },
+        "botreplies" => CommandInfo {
+            name: "botreplies",
+            description: "Configure how the bot replies in this server (Manage Server)",
+            usage: "`-botreplies threaded [on|off]`, `-botreplies mention [text|on|off|default]` or `/botreplies ...`",
+            examples: vec![
+                "-botreplies threaded on",
+                "-botreplies threaded",
+                "-botreplies mention Hey {user}! Try `{prefix}help`",
+                "-botreplies mention off",
+            ],
+            parameters: vec![
+                "threaded on - `-stats` and `-statscompare` continue in a thread off the command, leaving a one-line pointer in the channel",
+                "threaded off - Reply in the channel (the default); leave the setting out to see the current one",
+                "Needs the Create Public Threads permission, otherwise replies stay in the channel",
+                "mention [text] - What I say when pinged without a command; {prefix} and {user} are filled in. `on`/`off` toggle it, `default` resets it, and leaving it out shows the current reply",
+                "Mention replies go out at most once per channel every 30 seconds and skip replies and mass pings",
+            ],
+        },
+        "birthday" => CommandInfo {
+            name: "birthday",
+            description: "Birthdays in this server: wishes in a channel and an optional role for the day",
+            usage: "`-birthday set <MM-DD>`, `-birthday remove`, `-birthday list`, `-birthday channel [channel]`, `-birthday role [role]`",
+            examples: vec![
+                "-birthday set 03-14",
+                "-birthday list",
+                "-birthday channel #general",
+                "-birthday role @Birthday",
+            ],
+            parameters: vec![
+                "set <MM-DD> - Your birthday; February 29 is celebrated on February 28 outside leap years",
+                "remove - Forget your birthday here",
+                "list - The next birthdays in this server",
+                "channel [channel] - Where birthdays are announced at 09:00 in the streak timezone; leave out to stop (Manage Server)",
+                "role [role] - Give members a role on their birthday until the next day; leave out to stop (Manage Server and Manage Roles; the role must be below your highest role and can't have moderator permissions)",
+            ],
+        },
+        "boost" => CommandInfo {
+            name: "boost",
+            description: "Post a thank-you when a member boosts the server",
+            usage: "`-boost`, `-boost channel [channel]`, `-boost message [text]`",
+            examples: vec![
+                "-boost channel #general",
+                "-boost message 🎉 Thanks {user} for boosting {server}!",
+                "-boost message",
+            ],
+            parameters: vec![
+                "(no subcommand) - Show where boosts are celebrated and the message",
+                "channel [channel] - Where thank-yous go; leave out to stop. If the channel is deleted they stop until a new one is set (Manage Server)",
+                "message [text] - The thank-you, up to 500 characters; {user} pings the booster, {name} and {server} are their name and the server's. Leave out for the default (Manage Server)",
+            ],
+        },
+        "streak" => CommandInfo {
+            name: "streak",
+            description: "Daily activity streaks: how many days in a row someone has posted here, with optional milestone roles",
+            usage: "`-streak [user]` or `/streak show [user]`, `-streak reward <days> <role>`, `-streak unreward <days>`, `-streak rewards`, `-streak timezone <tz>`",
+            examples: vec![
+                "-streak",
+                "-streak @someone",
+                "-streak reward 7 @Regular",
+                "-streak reward 30 @Veteran",
+                "-streak timezone Europe/Copenhagen",
+            ],
+            parameters: vec![
+                "user (optional) - Whose streak to show (default: you)",
+                "reward <days> <role> - Give a role once a streak reaches this many days; it's removed when the streak ends (Manage Server and Manage Roles; the role must be below your highest role and can't have moderator permissions)",
+                "unreward <days> - Stop giving out a milestone role (Manage Server)",
+                "rewards - List milestone roles and the rollover timezone",
+                "timezone <tz> - IANA timezone days roll over in, e.g. Europe/Copenhagen (default UTC; Manage Server); `-digest` uses it too",
+                "Days on which the bot saw no messages at all in the server are skipped rather than breaking streaks",
+            ],
+        },
+        "digest" => CommandInfo {
+            name: "digest",
+            description: "Post a daily digest of the day before: message count and the most active channel (requires Manage Server)",
+            usage: "`-digest set <channel> <hour> [minute]`, `-digest off`, `-digest show`, `-digest preview`",
+            examples: vec!["-digest set #general 8", "-digest set #general 7 30", "-digest preview"],
+            parameters: vec![
+                "set <channel> <hour> [minute] - Post every day at this time; a time that already passed today starts tomorrow",
+                "off - Stop posting the digest",
+                "show - Where and when it's posted, and when the next one is due",
+                "preview - Show yesterday's digest here now",
+                "Days and times follow the timezone from `-streak timezone` (UTC by default)",
             ],
         },
-        "poll" => CommandInfo {
+        "bumper" => CommandInfo {
+            name: "bumper",
+            description: "Post a conversation starter in a channel once nobody has posted there for a while (requires Manage Channels)",
+            usage: "`-bumper set <duration> [channel]`, `-bumper off`, `-bumper quiet [start] [end]`, `-bumper add <text>`, `-bumper remove <id>`, `-bumper list`",
+            examples: vec!["-bumper set 6h #general", "-bumper quiet 23 8", "-bumper add What are you reading?"],
+            parameters: vec![
+                "set <duration> [channel] - Bump after this long without a human message, e.g. 6h or 2d (30 minutes to 30 days; default channel: this one)",
+                "off - Stop bumping; the starter list is kept",
+                "quiet [start] [end] - Don't bump between these hours (may wrap past midnight); leave both out to turn quiet hours off",
+                "add <text> - Add a starter; while the server has any, the built-in ones aren't used",
+                "remove <id> - Remove a starter by its ID from `-bumper list`",
+                "list - The settings and this server's starters",
+                "It never bumps twice in a row: someone has to post after a bump before the next one. Hours follow `-streak timezone`",
+            ],
+        },
+        "usage" => CommandInfo {
+            name: "usage",
+            description: "Show this server's most used commands, the trend vs the previous period, and unique users per command (requires Manage Server)",
+            usage: "`-usage [days]` or `/usage [days]`",
+            examples: vec!["-usage", "-usage 30"],
+            parameters: vec!["days (optional) - Number of days to look back (default: 7, max: 45)"],
+        },
+        "ascii" => CommandInfo {
+            name: "ascii",
+            description: "Turn an image into ASCII art",
+            usage: "`-ascii [width]` with an image attached (or as a reply to one), or `/ascii <image> [width]`",
+            examples: vec!["-ascii", "-ascii 80"],
+            parameters: vec![
+                "image - Attached image, or the image on the replied-to message",
+                "width (optional) - Output width in characters (10-120, default 60; shrunk to fit a message)",
+            ],
+        },
+        "color" => CommandInfo {
+            name: "color",
+            description: "Pick your display color with a color-only role",
+            usage: "`-color set <color>`, `-color clear`, `-color palette`, `-color daily` or `/color ...`",
+            examples: vec!["-color set #FF8800", "-color set teal", "-color clear", "-color daily"],
+            parameters: vec![
+                "color - Hex code like #FF8800, or a name from `-color palette`",
+                "The bot needs the Manage Roles permission",
+            ],
+        },
+        "poll" | "pollstraw" | "polltemplate" | "pollclose" => CommandInfo {
             name: "poll",
-            description: "Create a poll with a question and multiple options",
+            description: "Create a poll with a question and multiple options, and close it to post the results with a bar chart",
             usage:
-                "`-poll <question? option1 option2...>` or `/poll <question? option1 option2...>`",
+                "`-poll [--duration <time>] <question? option1 option2...>`, `-pollstraw <question>`, `-polltemplate save|use|list|delete`, `-pollclose` (reply to the poll) or `/pollclose <message link or ID>`",
             examples: vec![
                 "-poll Is this cool? yes no maybe",
                 "-poll Pizza or pasta? pizza pasta",
+                "-poll Lunch? \"pizza place\" \"taco truck\"",
+                "-poll --duration 2h Lunch? pizza tacos",
+                "-pollstraw Pizza tonight?",
+                "-polltemplate save game-night Friday Saturday \"next week\"",
+                "-polltemplate use game-night When's game night?",
+                "-pollclose https://discord.com/channels/1/2/3",
             ],
             parameters: vec![
                 "question? options - Question followed by space-separated options (max 10)",
+                "Quote options that contain spaces; a quoted question doesn't need the `?`",
+                "--duration <time> - Close the poll by itself after e.g. `30m`, `2h` or `3d` (up to 30 days), posting the results in the channel; works for straw and template polls too",
+                "pollstraw <question> - Yes / no / not sure poll voted on with 👍 👎 🤷",
+                "polltemplate save <name> <options...> - Save 2 to 10 options of up to 50 characters (140 together) under a name (letters, digits, - and _; up to 20 per server); saving an existing name replaces it. Needs Manage Messages, like `polltemplate delete <name>`",
+                "polltemplate use <name> <question> - Start a poll with a template's options; `polltemplate list` shows them",
+                "pollclose - Counts the reactions, marks the poll closed and posts the results; only whoever created the poll or someone with Manage Messages can close it",
             ],
         },
         "cleanup" => CommandInfo {
             name: "cleanup",
             description: "Delete messages in the current channel (admin only)",
-            usage: "`-cleanup [count]` or `-cleanup after` (reply to message)",
-            examples: vec!["-cleanup 10", "-cleanup 50", "-cleanup after"],
+            usage: "`-cleanup [count]`, `-cleanup after` (reply to message) or `-cleanup after <message ID or link>`",
+            examples: vec![
+                "-cleanup 10",
+                "-cleanup 50",
+                "-cleanup after",
+                "/cleanup after_message:https://discord.com/channels/1/2/3",
+            ],
             parameters: vec![
                 "count (optional) - Number of messages to delete (default: 10, max: 1000)",
                 "after - Delete all messages after the replied message",
+                "after_message (optional) - ID or link of a message in this channel to delete everything after (works as a slash command too)",
             ],
         },
         "update" => CommandInfo {
             name: "update",
-            description: "Update bot by pulling latest changes from GitHub (owner only)",
+            description: "Update bot by pulling latest changes from GitHub (owner only). New commits are also checked for every 6 hours and announced to the owner and status channels with an Update now button; `AUTO_UPDATE=true` installs them during the daily update window",
             usage: "`-update` or `/update`",
             examples: vec!["-update"],
             parameters: vec![],
         },
+        "maintenance" => CommandInfo {
+            name: "maintenance",
+            description: "Freeze the bot for everyone but its owners, e.g. before a risky migration (owner only)",
+            usage: "`-maintenance on [message]` / `-maintenance off` or `/maintenance ...`",
+            examples: vec!["-maintenance on Migrating reminders, back in 10 minutes", "-maintenance off"],
+            parameters: vec![
+                "on [message] - Reject other users' commands with the message (default: 🔧 under maintenance, back soon) and pause reminders and parking",
+                "off - Resume everything and send reminders that came due meanwhile",
+                "Both are announced in every server's status channel (see `-statuschannel`)",
+            ],
+        },
+        "reload-config" => CommandInfo {
+            name: "reload-config",
+            description: "Re-read the stored settings and data from disk without restarting, e.g. after editing a JSON file by hand (owner only)",
+            usage: "`-reload-config` or `/reload-config`",
+            examples: vec!["-reload-config"],
+            parameters: vec![
+                "Reports each store separately; one that can't be parsed keeps what it had in memory",
+                "Streaks only reload once their latest activity is saved (every 5 minutes)",
+                "Reminders, notes and reaction watchers are read from disk on every use and need no reload",
+            ],
+        },
+        "configcheck" => CommandInfo {
+            name: "configcheck",
+            description: "Check the stored settings for things that can't work and set them aside (owner only)",
+            usage: "`-configcheck` or `/configcheck`",
+            examples: vec!["-configcheck"],
+            parameters: vec![
+                "Finds channels and roles that were deleted, values out of range like a negative bumper idle time, and files that couldn't be parsed",
+                "Broken settings are moved to `quarantine.json` and treated as unset; unparseable files are renamed to `<file>.invalid-<time>`",
+                "Also runs at startup, and DMs the owner if it found anything",
+            ],
+        },
+        "dmlog" => CommandInfo {
+            name: "dmlog",
+            description: "Show recent DMs the bot sent on its own, like reminders and parking updates, and which failed. Members with Manage Server see their server's; bot owners see all",
+            usage: "`-dmlog [user]` or `/dmlog show [user]`, `-dmlog keep [count]`",
+            examples: vec!["-dmlog", "-dmlog @alice", "-dmlog keep 50"],
+            parameters: vec![
+                "user (optional) - Only show DMs to this user",
+                "keep [count] - How many attempts to keep for this server, up to 500 (default 100); 0 stops logging them. Leave out to see the current number (Manage Server)",
+                "Failures from Discord outages, rate limits or network trouble are retried up to 3 times, after 1, 5 and 15 minutes; closed DMs aren't retried",
+                "DMs still waiting for a retry are dropped on restart",
+            ],
+        },
+        "backup" => CommandInfo {
+            name: "backup",
+            description: "Back up the bot's data to the backup channel or S3, or restore a backup (owner only)",
+            usage: "`-backup now` / `-backup list` / `-backup restore <id>` or `/backup ...`",
+            examples: vec!["-backup now", "-backup restore rustbot-backup-20260101-030000"],
+            parameters: vec![
+                "now - Make a backup right away (one also runs nightly at 03:00 Copenhagen time)",
+                "list - The stored backups, newest first (the last 14 are kept)",
+                "restore <id> - Replace all data with a backup after two confirmations; the current data is snapshotted first and the bot restarts",
+            ],
+        },
+        "selftest" => CommandInfo {
+            name: "selftest",
+            description: "Check that a deploy works: writes and reads a file in the data directory, pings the Discord API, looks for the bonk/hit GIFs and reads the parking key (owner only)",
+            usage: "`-selftest` or `/selftest`",
+            examples: vec!["-selftest"],
+            parameters: vec![],
+        },
+        "assets" => CommandInfo {
+            name: "assets",
+            description: "List the bonk/hit GIFs the bot uses with where the avatar goes on each, and the ones it skipped and why (owner only)",
+            usage: "`-assets` / `-assets reload` or `/assets list`, `/assets reload`",
+            examples: vec!["-assets", "-assets reload"],
+            parameters: vec![
+                "list - Each directory's usable GIFs with their x, y and scale, and skipped ones with the reason",
+                "reload - Scan the directories again after adding or renaming GIFs, without restarting",
+                "GIFs are skipped when the name isn't like `bonk_<n>_x<x>_y<y>_s<scale>.gif`, the avatar is off the GIF, a side is under 32 or over 1024 pixels, or there are more than 100 frames",
+            ],
+        },
         "kys" => CommandInfo {
             name: "kys",
             description: "Reboot the bot with a 1-hour cooldown",
@@ -212,36 +759,135 @@ async fn show_command_help(ctx: Context<'_>, command_name: &str) -> Result<(), E
             examples: vec!["-invite"],
             parameters: vec![],
         },
+        "invites" => CommandInfo {
+            name: "invites",
+            description: "See who invited whom: joins are credited to the invite whose use count went up",
+            usage: "`-invites [user]`, `-invites top`, `-invites channel [channel]` or `/invites user|top|channel`",
+            examples: vec!["-invites", "-invites @alice", "-invites top", "-invites channel #member-log"],
+            parameters: vec![
+                "user [user] - How many members someone has brought in (default: you)",
+                "top - The top inviters, with vanity URL and unknown joins in their own rows",
+                "channel [channel] - Log each join with whose invite it used; leave out to stop (Manage Server)",
+                "The bot needs Manage Server to read invites; joins it can't place (discovery, the widget, several invites used at once) count as unknown",
+            ],
+        },
         "react" => CommandInfo {
             name: "react",
             description: "Add emoji reactions to a message",
-            usage: "`-react <text>` (reply to message) or `/react <message_id> <text>`",
-            examples: vec!["-react thumbs up", "-react fire heart"],
+            usage: "`-react [--tone 1-5] <text>` or `-react [--tone 1-5] preset <name>` (reply to message), or `/react <text> <message_id>`",
+            examples: vec!["-react thumbs up", "-react fire heart", "-react preset hype", "-react --tone 3 wave"],
             parameters: vec![
-                "text - The text to convert to emoji reactions",
+                "text - The text to convert to emoji reactions, or `preset <name>` for a preset set of emojis",
+                "presets - hype 🔥🎉💯🚀🙌, sad 😢😭💔🥺😞, love ❤️😍🥰💕😘, laugh 😂🤣💀😆😹, wow 😮🤯😱👀‼️, angry 😠😡🤬💢👿, gg 🏆👏🥇🎮💪, thanks 🙏💖🤗✨👍, cool 😎🆒🧊🤙✨",
+                "--tone 1-5 - Skin tone (lightest to darkest) for hand and person emojis; with a tone, wave, thumbsup, thumbsdown, clap, pray, ok, peace, muscle, raisedhands, highfive, fist, point, callme, handshake, shrug, facepalm and dance react with that emoji",
                 "message_id (slash only) - ID of message to react to",
             ],
         },
+        "reactmap" => CommandInfo {
+            name: "reactmap",
+            description: "Choose which emojis `react` spells characters with in this server (Manage Expressions or Manage Messages to change)",
+            usage: "`-reactmap set <char> <emoji>`, `-reactmap remove <char>`, `-reactmap list`",
+            examples: vec![
+                "-reactmap set a <:letter_a:123456789012345678>",
+                "-reactmap set ! 💥",
+                "-reactmap remove a",
+            ],
+            parameters: vec![
+                "char - A single letter, digit or symbol (letters aren't case-sensitive)",
+                "emoji - A standard emoji or one of this server's custom emojis",
+                "The emoji is tried first; when it's already used on the message, the built-in emojis for the character are used",
+            ],
+        },
+        "note" => CommandInfo {
+            name: "note",
+            description: "Personal notes and todos, kept per user across servers and DMs, optionally with a reminder",
+            usage: "`-note add [time] <text>`, `-note list`, `-note done <id>`, `-note delete <id>` or `/note ...`",
+            examples: vec![
+                "-note add buy milk",
+                "-note add 2h water the plants",
+                "/note add text:call the dentist remind:1d",
+                "-note done 3",
+            ],
+            parameters: vec![
+                "add [time] <text> - Save a note (up to 500 characters); with a time like 30m or 1d you also get reminded about it",
+                "list - Your notes, open ones first",
+                "done <id> - Tick a note off; its reminder is cancelled",
+                "delete <id> - Remove a note and its reminder",
+            ],
+        },
+        "timestamp" => CommandInfo {
+            name: "timestamp",
+            description: "Turn a time into a Discord timestamp that shows in everyone's own timezone",
+            usage: "`-timestamp <when> [style] [all]` or `/timestamp when:<when> [format] [all]`",
+            examples: vec![
+                "-timestamp tonight",
+                "-timestamp 2024-06-01 14:30",
+                "-timestamp 2h R",
+                "/timestamp when:14:30 all:true",
+            ],
+            parameters: vec![
+                "when - A duration (2h), a phrase (tonight, in the morning), a date and time (2024-06-01 14:30, 24/12/2024) or a time of day (14:30, the next one) in Copenhagen time; quote phrases with spaces in the prefix form",
+                "style (optional) - t (short time), T (long time), d (short date), D (long date), f (short date/time), F (long date/time, default) or R (relative)",
+                "all (optional) - true to show every style with its markup",
+            ],
+        },
         "remind" => CommandInfo {
             name: "remind",
-            description: "Set a reminder for the future",
-            usage: "`-remind <time> <message>` or `/remind <time> <message>`",
+            description: "Set a reminder for the future, or a nag reminder that repeats until you acknowledge it",
+            usage: "`-remind set|for|webhook|after|nag|followup|whenfree|countdown|ack|list|remove|edit|clear|cancel|import|quiethours` or `/remind ...` (`-remind <time> <message>` is short for `set`)",
             examples: vec![
-                "-remind 10m Take a break",
-                "-remind 2h Meeting starts",
-                "-remind 1d Pay bills",
+                "-remind set 10m Take a break",
+                "-r 10m tea",
+                "-remind set 2h Meeting starts",
+                "-remind set tonight Call mom",
+                "-remind set 1h !high Submit the report",
+                "-remind for @alice 1d Send the release notes",
+                "-remind webhook <webhook url> 1h --name \"Standup Owl\" Standup in 5 minutes",
+                "-remind after 12 30m Leave for the airport",
+                "-remind nag 1h 10m Take your medication",
+                "-remind followup 2h 30m Reply to the landlord",
+                "-remind whenfree 8h Ask about the weekend",
+                "-remind countdown 10m Pizza's ready",
+                "-remind ack 3",
+                "-remind cancel 7d dentist",
+                "-remind edit 4 - Call mom and dad",
+                "-remind quiethours set 22:00 07:00 Europe/London",
+                "-remind when 10 🔥 https://discord.com/channels/1/2/3",
+                "/remind list filter:dentist within:7d sort:newest",
             ],
             parameters: vec![
-                "time - Time to wait (e.g., 10m, 2h, 1d)",
-                "message - Reminder message",
+                "set <time> <message> - One-off reminder (e.g., 10m, 2h, 1d, or top of the hour / in the morning / tonight, in Danish time); `random` picks a surprise time within 24 hours",
+                "!low / !normal / !high (or --priority <level>) at the start of the message, or the priority option of `/remind set` - low goes off without a ping, normal (default) pings, high pings in red and also DMs you when set in a server",
+                "for <user> <time> <message> - Remind someone else; they get pinged when it fires (requires Manage Messages)",
+                "webhook <url> <time> [--name <name>] [--avatar <link>] <message> - Goes off through one of this server's webhooks, as its name and avatar or the ones given, with no snooze buttons; if the webhook is deleted it's sent as the bot (requires Manage Webhooks)",
+                "after <id> <offset> <message> - Goes off <offset> after your reminder <id> does; if that one is removed it keeps its planned time",
+                "nag <time> <every> <message> - Repeats every <every> until acknowledged (max 20 times)",
+                "followup <time> <within> <message> - One-off reminder that DMs you once if you don't react to it or reply to it within <within> (up to 24h); snoozing counts as an answer",
+                "whenfree <within> <message> - Goes off as soon as you're back online after being idle, busy or offline, or after <within> at the latest (only at <within> unless the bot runs with PRESENCE_INTENT)",
+                "countdown <time> <message> - Post a countdown (30s to 30m) that updates live and pings you at zero; up to 2 at once, delete the message to cancel",
+                "ack <id> - Stop a nag reminder (or press its Acknowledge button)",
+                "list [filter] [within] [sort] - Search your reminders, limit to those due within a time, sort by soonest or newest",
+                "when <count> <emoji> [message link] - Ping me when a message (linked or replied to) reaches that many of a reaction; checked every 2 minutes for up to 30 days",
+                "edit <id> [time] [message] - Change when your reminder goes off (from now) and/or its message, keeping its ID; use `-` as the time to change only the message",
+                "cancel [time] [keyword] - Remove your reminders due before a time (like 24h or tonight) and/or containing a keyword",
+                "unwatch <id> - Stop a `when` watcher",
+                "quiethours set <start> <end> [timezone] - Reminders due between these times go off when they end instead; high priority ones don't wait. `quiethours off` turns it off",
+                "import <.ics file> - One reminder per upcoming calendar event (up to 100); repeating events get their next time",
+                "export - Download every stored reminder as CSV (bot owners only)",
+                "Fired reminders have Snooze 10m / 1h / 1d buttons for a day; the list shows how often each was snoozed",
+                "New reminders are copied to your DMs with a ❌ reaction; react with ❌ to cancel the reminder",
+                "`/remind set` in a server confirms only to you; the reminder itself still goes off in the channel",
             ],
         },
         "help" => CommandInfo {
             name: "help",
             description: "Show help information for bot commands",
-            usage: "`-help [command]` or `/help [command]`",
-            examples: vec!["-help", "-help ping", "-help stats"],
-            parameters: vec!["command (optional) - Specific command to get detailed help for"],
+            usage: "`-help [command]`, `-help search <keyword>` or `/help [command]`",
+            examples: vec!["-help", "-help ping", "-help search voice"],
+            parameters: vec![
+                "command (optional) - Specific command to get detailed help for",
+                "search <keyword> - List commands whose name or description mentions the keyword",
+            ],
         },
         "status" => CommandInfo {
             name: "status",
@@ -250,6 +896,13 @@ async fn show_command_help(ctx: Context<'_>, command_name: &str) -> Result<(), E
             examples: vec!["-status"],
             parameters: vec![],
         },
+        "botstats" => CommandInfo {
+            name: "botstats",
+            description: "Show the slowest commands, averaged over each one's last 50 runs since the bot started",
+            usage: "`-botstats` or `/botstats`",
+            examples: vec!["-botstats"],
+            parameters: vec!["Only runs that finish without an error are timed"],
+        },
         "mock" => CommandInfo {
             name: "mock",
             description: "Transform text into mOcKiNg alternating case, or reply to a message to mock it",
@@ -257,19 +910,59 @@ async fn show_command_help(ctx: Context<'_>, command_name: &str) -> Result<(), E
             examples: vec!["-mock this is a great idea"],
             parameters: vec!["text - The text to mock (optional when replying to a message)"],
         },
+        "clap" => CommandInfo {
+            name: "clap",
+            description: "Put 👏 between 👏 every 👏 word, or reply to a message to clap it",
+            usage: "`-clap <text>` or `/clap <text>`",
+            examples: vec!["-clap read the docs"],
+            parameters: vec!["text - The text to clap (optional when replying to a message)"],
+        },
+        "owoify" => CommandInfo {
+            name: "owoify",
+            description: "Sprinkle emoticons into text without changing the words, or reply to a message to owoify it",
+            usage: "`-owoify <text>` or `/owoify <text>`",
+            examples: vec!["-owoify good morning everyone"],
+            parameters: vec!["text - The text to owoify (optional when replying to a message)"],
+        },
+        "ship" => CommandInfo {
+            name: "ship",
+            description: "Rate how compatible two users are. The score only depends on the pair, so asking again gives the same answer. Comes with a ship name and a card with both avatars and a heart filled to the score",
+            usage: "`-ship @user1 @user2` or `/ship <user1> <user2>`",
+            examples: vec!["-ship @alice @bob"],
+            parameters: vec![
+                "user1 - The first user",
+                "user2 - The second user",
+            ],
+        },
         "bonk" => CommandInfo {
             name: "bonk",
             description: "Bonk a user by placing their profile picture on a random bonk GIF",
-            usage: "`-bonk [user]` or `/bonk [user]`",
-            examples: vec!["-bonk", "-bonk @username"],
-            parameters: vec!["user (optional) - Who to bonk (defaults to you)"],
+            usage: "`-bonk [user] [speed]` or `/bonk [user] [speed]`",
+            examples: vec!["-bonk", "-bonk @username", "-bonk @username 2"],
+            parameters: vec![
+                "user (optional) - Who to bonk (defaults to you)",
+                "speed (optional) - Playback speed from 0.25 to 4, e.g. 0.5 for a dramatic bonk or 2 for a snappy one",
+            ],
         },
         "hit" => CommandInfo {
             name: "hit",
             description: "Order a hit on a user by placing their profile picture on a random hit GIF",
-            usage: "`-hit [user]` or `/hit [user]`",
-            examples: vec!["-hit", "-hit @username"],
-            parameters: vec!["user (optional) - The target (defaults to you)"],
+            usage: "`-hit [user] [speed]` or `/hit [user] [speed]`",
+            examples: vec!["-hit", "-hit @username", "-hit @username 0.5"],
+            parameters: vec![
+                "user (optional) - The target (defaults to you)",
+                "speed (optional) - Playback speed from 0.25 to 4, e.g. 0.5 for slow motion or 2 for double speed",
+            ],
+        },
+        "combo" => CommandInfo {
+            name: "combo",
+            description: "Hit a user with a random chain of 2-3 bonk and hit GIFs, posted together",
+            usage: "`-combo @user` or `/combo user:@user`",
+            examples: vec!["-combo @username"],
+            parameters: vec![
+                "user - The target",
+                "Each user can throw a combo every 10 minutes, and only one is rendered at a time",
+            ],
         },
         "park" => CommandInfo {
             name: "park",
@@ -280,17 +973,26 @@ async fn show_command_help(ctx: Context<'_>, command_name: &str) -> Result<(), E
         },
         "play" => CommandInfo {
             name: "play",
-            description: "Play a YouTube video's audio in a voice channel",
-            usage: "`-play <url|search> [channel link]` or `/play <url|search>`",
+            description: "Play a YouTube video's audio, or a soundboard clip, in a voice channel",
+            usage: "`-play <url|search|sound> [channel link]` or `/play <url|search|sound>`",
             examples: vec![
                 "-play never gonna give you up",
                 "-play https://youtu.be/dQw4w9WgXcQ",
             ],
             parameters: vec![
-                "query - A YouTube URL or search terms",
+                "query - A YouTube URL, search terms or the name of a clip from `-sounds`",
                 "channel link (optional) - A Discord channel link to target a specific voice channel (useful from DMs)",
             ],
         },
+        "sounds" => CommandInfo {
+            name: "sounds",
+            description: "List the soundboard clips that `-play <name>` plays",
+            usage: "`-sounds` or `/sounds`",
+            examples: vec!["-sounds", "-play airhorn"],
+            parameters: vec![
+                "Clips are the `.ogg`/`.mp3` files in `assets/sounds/`, read at startup; ones over 15 seconds are skipped",
+            ],
+        },
         "skip" => CommandInfo {
             name: "skip",
             description: "Skip the track that's currently playing",
@@ -312,10 +1014,10 @@ async fn show_command_help(ctx: Context<'_>, command_name: &str) -> Result<(), E
             examples: vec!["-queue"],
             parameters: vec![],
         },
-        "leave" | "disconnect" | "dc" => CommandInfo {
+        "leave" => CommandInfo {
             name: "leave",
             description: "Leave the voice channel (also clears the queue)",
-            usage: "`-leave` (aliases: `-disconnect`, `-dc`) or `/leave`",
+            usage: "`-leave` or `/leave`",
             examples: vec!["-leave"],
             parameters: vec![],
         },
@@ -337,7 +1039,7 @@ async fn show_command_help(ctx: Context<'_>, command_name: &str) -> Result<(), E
             examples: vec!["-tictactoe", "-tictactoe @username"],
             parameters: vec![
                 "opponent (optional) - Mention a player, or omit to play the AI",
-                "Play with `-move_ttt <1-9>`, `-board`, `-endttt`",
+                "Play with the board buttons, or `-move_ttt <1-9>`, `-board`, `-endttt`",
             ],
         },
         "hangman" => CommandInfo {
@@ -349,25 +1051,57 @@ async fn show_command_help(ctx: Context<'_>, command_name: &str) -> Result<(), E
                 "Play with `-letter <a-z>`, `-hangmanstatus`, `-hangmanhint`, `-endhangman`",
             ],
         },
-        _ => {
-            ctx.send(
-                poise::CreateReply::default()
-                    .content(format!(
-                        "❌ Command `{command_name}` not found. Use `-help` to see all available commands."
-                    ))
-                    .ephemeral(true),
-            )
-            .await?;
-            return Ok(());
-        }
+        "games" => CommandInfo {
+            name: "games",
+            description: "List your active games across every game type: opponent, when and where each started",
+            usage: "`-games` or `/games`",
+            examples: vec!["-games"],
+            parameters: vec![
+                "Each game has a button to go to it and one to end it",
+                "`-endgame [type]` ends a game without the list",
+            ],
+        },
+        "endgame" => CommandInfo {
+            name: "endgame",
+            description: "End one of your games",
+            usage: "`-endgame [type]` or `/endgame [type]`",
+            examples: vec!["-endgame", "-endgame hangman"],
+            parameters: vec![
+                "type (optional) - numberguess, tictactoe or hangman; only needed with more than one game going, unless just one of them is in this channel",
+            ],
+        },
+        "avatarguess" => CommandInfo {
+            name: "avatarguess",
+            description: "Guess which server member a pixelated avatar belongs to",
+            usage: "`-avatarguess` or `/avatarguess`",
+            examples: vec!["-avatarguess"],
+            parameters: vec![
+                "Type the member's name in chat; small typos are fine",
+                "The picture gets sharper every 15 seconds, and the answer is revealed after 3 minutes",
+            ],
+        },
+        "fish" => CommandInfo {
+            name: "fish",
+            description: "Cast a fishing line once every 30 minutes and collect what bites",
+            usage: "`-fish [cast|stats|leaderboard]` or `/fish cast|stats|leaderboard`",
+            examples: vec!["-fish", "-fish stats @alice", "-fish leaderboard"],
+            parameters: vec![
+                "cast - Catch something, from junk to legendary (`-fish` on its own does this too)",
+                "stats [user] - Casts, collection progress, score and rarest catch",
+                "leaderboard - This server's anglers by score; rarer catches are worth more",
+                "See your catches with `-inventory`",
+            ],
+        },
+        "inventory" => CommandInfo {
+            name: "inventory",
+            description: "List everything caught fishing, grouped by rarity",
+            usage: "`-inventory [user]` or `/inventory [user]`",
+            examples: vec!["-inventory", "-inventory @alice"],
+            parameters: vec!["user (optional) - Whose catches to show (default: you)"],
+        },
+        _ => return None,
     };
-
-    let embed = create_command_help_embed(&command_info);
-
-    ctx.send(poise::CreateReply::default().embed(embed).ephemeral(false))
-        .await?;
-
-    Ok(())
+    Some(info)
 }
 
 struct CommandInfo {
@@ -378,13 +1112,34 @@ struct CommandInfo {
     parameters: Vec<&'static str>,
 }
 
-fn create_command_help_embed(info: &CommandInfo) -> serenity::CreateEmbed {
+/// The top-level command called `name` or with `name` as an alias.
+fn find_registered<'a>(
+    commands: &'a [poise::Command<Data, Error>],
+    name: &str,
+) -> Option<&'a poise::Command<Data, Error>> {
+    commands.iter().find(|command| {
+        std::iter::once(&command.name)
+            .chain(&command.aliases)
+            .any(|candidate| candidate.eq_ignore_ascii_case(name))
+    })
+}
+
+fn create_command_help_embed(info: &CommandInfo, aliases: &[String]) -> serenity::CreateEmbed {
     let mut embed = serenity::CreateEmbed::new()
         .title(format!("Help: {}", info.name))
         .description(info.description)
         .color(0x5865F2)
         .field("Usage", info.usage, false);
 
+    if !aliases.is_empty() {
+        let aliases_text = aliases
+            .iter()
+            .map(|alias| format!("`-{alias}`"))
+            .collect::<Vec<_>>()
+            .join(", ");
+        embed = embed.field("Aliases", aliases_text, false);
+    }
+
     if !info.examples.is_empty() {
         let examples_text = info.examples.join("\n");
         embed = embed.field("Examples", format!("```\n{examples_text}\n```"), false);
@@ -397,7 +1152,9 @@ fn create_command_help_embed(info: &CommandInfo) -> serenity::CreateEmbed {
             .map(|p| format!("• {p}"))
             .collect::<Vec<_>>()
             .join("\n");
-        embed = embed.field("Parameters", params_text, false);
+        for (name, value) in split_field("Parameters", &params_text) {
+            embed = embed.field(name, value, false);
+        }
     }
 
     embed
@@ -410,6 +1167,7 @@ fn create_command_help_embed(info: &CommandInfo) -> serenity::CreateEmbed {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::utils::limits::{EMBED_FIELD_VALUE, EMBED_TOTAL};
 
     #[test]
     fn test_command_info_struct() {
@@ -428,6 +1186,62 @@ mod tests {
         assert_eq!(info.parameters.len(), 1);
     }
 
+    #[test]
+    fn test_search_commands() {
+        let commands = crate::build_framework_options().commands;
+        let names = |keyword| -> Vec<String> {
+            search_commands(&commands, keyword)
+                .into_iter()
+                .map(|(name, _)| name)
+                .collect()
+        };
+
+        // Names, descriptions and subcommands all count, ignoring case
+        assert!(names("UWU").contains(&"uwu".to_string()));
+        assert!(names("voice").contains(&"play".to_string()));
+        assert!(names("remind").contains(&"remind when".to_string()));
+        // Aliases match too
+        assert!(names("cf").contains(&"coinflip".to_string()));
+        assert!(names("no command says this").is_empty());
+
+        assert!(starts_with_search("Search voice"));
+        assert!(!starts_with_search("search"));
+        assert!(!starts_with_search("searching things"));
+    }
+
+    #[test]
+    fn test_help_fits_discord_limits() {
+        let fields: Vec<(String, String)> = GENERAL_SECTIONS
+            .iter()
+            .flat_map(|(name, value)| split_field(name, value))
+            .collect();
+        assert!(fields
+            .iter()
+            .all(|(_, value)| value.chars().count() <= EMBED_FIELD_VALUE));
+        let size: usize = fields
+            .iter()
+            .map(|(name, value)| name.chars().count() + value.chars().count())
+            .sum();
+        // The title, description and footer take under 300 more
+        assert!(
+            size + 300 <= EMBED_TOTAL,
+            "general help is {size} characters"
+        );
+
+        for command in crate::build_framework_options().commands {
+            let Some(info) = command_info(&command.name) else {
+                continue;
+            };
+            assert!(
+                info.usage.chars().count() <= EMBED_FIELD_VALUE,
+                "{}",
+                info.name
+            );
+            let examples = info.examples.join("\n").chars().count() + 8;
+            assert!(examples <= EMBED_FIELD_VALUE, "{}", info.name);
+        }
+    }
+
     #[test]
     fn test_help_command_signature() {
         // Verify the command exists and has the correct signature
@@ -26,6 +26,8 @@ pub async fn invite(ctx: Context<'_>) -> Result<(), Error> {
         | serenity::Permissions::USE_EXTERNAL_EMOJIS
         | serenity::Permissions::ADD_REACTIONS
         | serenity::Permissions::MANAGE_MESSAGES
+        | serenity::Permissions::MODERATE_MEMBERS
+        | serenity::Permissions::MANAGE_ROLES
         | serenity::Permissions::READ_MESSAGE_HISTORY;
 
     // Create the invite URL
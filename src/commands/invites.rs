@@ -0,0 +1,703 @@
+//! Who invited whom. The bot keeps each server's invite codes and their use
+//! counts in memory, filled in when the guild becomes available and kept up
+//! with invite create/delete events. When someone joins, the counts are
+//! fetched again and the invite whose count went up gets the credit. Joins
+//! are stored per server and posted to its invite log channel, if set.
+//!
+//! Reading invites needs Manage Server; without it every join is unknown.
+
+use crate::config_check::{check_store, ConfigProblem, LiveIds};
+use crate::storage;
+use crate::utils::check_store_writable;
+use crate::utils::permissions::check_channel_perms;
+use crate::{Context, Error};
+use chrono::{DateTime, Utc};
+use parking_lot::RwLock;
+use poise::serenity_prelude as serenity;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+const INVITES_FILE: &str = "invites.json";
+
+/// Joins kept per server; the oldest are dropped past this.
+const MAX_JOINS_PER_GUILD: usize = 5000;
+
+/// Rows in `-invites top`.
+const TOP_INVITERS: usize = 10;
+
+/// One invite code as last seen.
+#[derive(Clone, Debug, PartialEq)]
+struct InviteSnapshot {
+    uses: u64,
+    /// 0 means unlimited.
+    max_uses: u64,
+    inviter: Option<u64>,
+}
+
+/// A guild's invite codes and their use counts.
+type InviteCounts = HashMap<String, InviteSnapshot>;
+
+/// How a member got in.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+enum JoinSource {
+    /// A regular invite; `inviter` is `None` for invites nobody made, like
+    /// the server widget's.
+    Invite {
+        code: String,
+        inviter: Option<u64>,
+    },
+    Vanity,
+    /// Discovery, an invite the bot couldn't see, or several invites used
+    /// at once.
+    Unknown,
+}
+
+impl JoinSource {
+    fn inviter(&self) -> Option<u64> {
+        match self {
+            JoinSource::Invite { inviter, .. } => *inviter,
+            _ => None,
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+struct JoinRecord {
+    user_id: u64,
+    source: JoinSource,
+    joined_at: DateTime<Utc>,
+}
+
+#[derive(Serialize, Deserialize, Default, Debug, Clone)]
+#[serde(default)]
+struct GuildInvites {
+    /// Where joins are logged; `None` keeps them quiet (they're still counted).
+    channel: Option<u64>,
+    joins: Vec<JoinRecord>,
+}
+
+impl GuildInvites {
+    /// How many joins `inviter` has brought in.
+    fn invited_by(&self, inviter: u64) -> usize {
+        self.joins
+            .iter()
+            .filter(|join| join.source.inviter() == Some(inviter))
+            .count()
+    }
+}
+
+/// Where `-invites top` puts a join.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+enum Bucket {
+    Inviter(u64),
+    Vanity,
+    Unknown,
+}
+
+/// Joins per inviter, most first, with the vanity URL and unknown joins
+/// ranked among them. Ties go by user ID.
+fn leaderboard(joins: &[JoinRecord]) -> Vec<(Bucket, usize)> {
+    let mut counts: HashMap<Bucket, usize> = HashMap::new();
+    for join in joins {
+        let bucket = match &join.source {
+            JoinSource::Invite {
+                inviter: Some(inviter),
+                ..
+            } => Bucket::Inviter(*inviter),
+            JoinSource::Vanity => Bucket::Vanity,
+            JoinSource::Invite { inviter: None, .. } | JoinSource::Unknown => Bucket::Unknown,
+        };
+        *counts.entry(bucket).or_default() += 1;
+    }
+    let mut ranked: Vec<(Bucket, usize)> = counts.into_iter().collect();
+    ranked.sort_by(|(a, a_count), (b, b_count)| b_count.cmp(a_count).then(a.cmp(b)));
+    ranked
+}
+
+#[derive(Serialize, Deserialize, Default, Clone)]
+struct InviteData {
+    guilds: HashMap<u64, GuildInvites>,
+}
+
+lazy_static::lazy_static! {
+    static ref INVITES: RwLock<InviteData> = RwLock::new(storage::load(INVITES_FILE));
+    /// Invite counts per guild. Held across the fetch on a join so two joins
+    /// at once are diffed one after the other.
+    static ref COUNTS: tokio::sync::Mutex<HashMap<u64, InviteCounts>> =
+        tokio::sync::Mutex::new(HashMap::new());
+}
+
+/// Re-read the invite log settings and joins from disk.
+pub fn reload_invites() -> Result<(), Error> {
+    *INVITES.write() = storage::reload(INVITES_FILE)?;
+    Ok(())
+}
+
+/// Where a guild's joins are logged, if anywhere.
+pub fn invite_log_channel(guild_id: u64) -> Option<u64> {
+    INVITES
+        .read()
+        .guilds
+        .get(&guild_id)
+        .and_then(|guild| guild.channel)
+}
+
+fn save_invites(data: &InviteData) {
+    if let Err(e) = storage::save(INVITES_FILE, data) {
+        log::error!("Failed to save invites: {}", e);
+    }
+}
+
+/// Drop an orphaned guild's join log and channel.
+pub fn purge_guild(guild_id: u64) {
+    let mut data = INVITES.write();
+    if data.guilds.remove(&guild_id).is_some() {
+        save_invites(&data);
+    }
+}
+
+/// Unset log channels that were deleted while the bot was offline.
+fn validate_invites(data: &mut InviteData, live: &LiveIds) -> Vec<ConfigProblem> {
+    let mut problems = Vec::new();
+    for (&guild_id, guild) in &mut data.guilds {
+        if let Some(channel_id) = guild
+            .channel
+            .take_if(|&mut channel_id| live.channel_gone(guild_id, channel_id))
+        {
+            problems.push(ConfigProblem::deleted_channel(
+                guild_id, "channel", channel_id,
+            ));
+        }
+    }
+    problems
+}
+
+/// Check the invite log settings, see [`crate::config_check`].
+pub fn check_invites(live: &LiveIds, quarantine: bool) -> Vec<ConfigProblem> {
+    check_store(
+        &INVITES,
+        quarantine,
+        |data| validate_invites(data, live),
+        save_invites,
+    )
+}
+
+/// Command check for commands that change the invite log settings.
+async fn invites_writable(ctx: Context<'_>) -> Result<bool, Error> {
+    check_store_writable(ctx, &storage::data_path(INVITES_FILE)).await
+}
+
+/// Work out which invite a join came through from the counts before and
+/// after it. An invite counts if its uses went up, or if it was one use
+/// away from its limit and is gone now. Anything but exactly one such
+/// invite is the vanity URL (if the server has one) or unknown.
+fn attribute_join(before: &InviteCounts, after: &InviteCounts, has_vanity: bool) -> JoinSource {
+    let mut used: Vec<(&String, &InviteSnapshot)> = after
+        .iter()
+        .filter(|(code, now)| now.uses > before.get(*code).map_or(0, |then| then.uses))
+        .collect();
+    used.extend(before.iter().filter(|(code, then)| {
+        !after.contains_key(*code) && then.max_uses > 0 && then.uses + 1 == then.max_uses
+    }));
+
+    match used.as_slice() {
+        [(code, invite)] => JoinSource::Invite {
+            code: code.to_string(),
+            inviter: invite.inviter,
+        },
+        [] if has_vanity => JoinSource::Vanity,
+        _ => JoinSource::Unknown,
+    }
+}
+
+async fn fetch_counts(http: &serenity::Http, guild_id: serenity::GuildId) -> Option<InviteCounts> {
+    match guild_id.invites(http).await {
+        Ok(invites) => Some(
+            invites
+                .into_iter()
+                .map(|invite| {
+                    let snapshot = InviteSnapshot {
+                        uses: invite.uses,
+                        max_uses: invite.max_uses as u64,
+                        inviter: invite.inviter.map(|user| user.id.get()),
+                    };
+                    (invite.code, snapshot)
+                })
+                .collect(),
+        ),
+        Err(e) => {
+            log::debug!("Can't read the invites of guild {}: {}", guild_id, e);
+            None
+        }
+    }
+}
+
+/// Remember a guild's current invite counts. Called when the guild becomes
+/// available, so joins from then on can be attributed.
+pub async fn cache_guild_invites(http: &serenity::Http, guild_id: serenity::GuildId) {
+    let counts = fetch_counts(http, guild_id).await;
+    let mut all = COUNTS.lock().await;
+    match counts {
+        Some(counts) => {
+            all.insert(guild_id.get(), counts);
+        }
+        None => {
+            all.remove(&guild_id.get());
+        }
+    }
+}
+
+pub async fn handle_invite_create(event: &serenity::InviteCreateEvent) {
+    let Some(guild_id) = event.guild_id else {
+        return;
+    };
+    let snapshot = InviteSnapshot {
+        uses: event.uses,
+        max_uses: event.max_uses as u64,
+        inviter: event.inviter.as_ref().map(|user| user.id.get()),
+    };
+    if let Some(counts) = COUNTS.lock().await.get_mut(&guild_id.get()) {
+        counts.insert(event.code.clone(), snapshot);
+    }
+}
+
+pub async fn handle_invite_delete(event: &serenity::InviteDeleteEvent) {
+    let Some(guild_id) = event.guild_id else {
+        return;
+    };
+    if let Some(counts) = COUNTS.lock().await.get_mut(&guild_id.get()) {
+        counts.remove(&event.code);
+    }
+}
+
+/// Attribute a new member's join, store it and log it to the server's
+/// invite log channel.
+pub async fn handle_member_join(ctx: &serenity::Context, member: &serenity::Member) {
+    if member.user.bot {
+        return;
+    }
+    let guild_id = member.guild_id;
+    let has_vanity = guild_id
+        .to_guild_cached(&ctx.cache)
+        .is_some_and(|guild| guild.vanity_url_code.is_some());
+
+    let source = {
+        let mut all = COUNTS.lock().await;
+        let after = fetch_counts(&ctx.http, guild_id).await;
+        let before = all.remove(&guild_id.get());
+        let source = match (&before, &after) {
+            (Some(before), Some(after)) => attribute_join(before, after, has_vanity),
+            _ => JoinSource::Unknown,
+        };
+        if let Some(after) = after {
+            all.insert(guild_id.get(), after);
+        }
+        source
+    };
+
+    let (channel, invited_count) = {
+        let mut data = INVITES.write();
+        let guild = data.guilds.entry(guild_id.get()).or_default();
+        guild.joins.push(JoinRecord {
+            user_id: member.user.id.get(),
+            source: source.clone(),
+            joined_at: Utc::now(),
+        });
+        if guild.joins.len() > MAX_JOINS_PER_GUILD {
+            let excess = guild.joins.len() - MAX_JOINS_PER_GUILD;
+            guild.joins.drain(..excess);
+        }
+        let channel = guild.channel;
+        let invited_count = source.inviter().map(|inviter| guild.invited_by(inviter));
+        save_invites(&data);
+        (channel, invited_count)
+    };
+    log::info!(
+        "{} joined guild {} via {:?}",
+        member.user.name,
+        guild_id,
+        source
+    );
+
+    let Some(channel) = channel else {
+        return;
+    };
+    let text = join_line(member.user.id.get(), &source, invited_count.unwrap_or(0));
+    let message = serenity::CreateMessage::new()
+        .content(text)
+        .allowed_mentions(serenity::CreateAllowedMentions::new());
+    if let Err(e) = serenity::ChannelId::new(channel)
+        .send_message(&ctx.http, message)
+        .await
+    {
+        log::warn!("Failed to log a join in guild {}: {}", guild_id, e);
+    }
+}
+
+/// `1st`, `2nd`, `3rd`, `11th`, `22nd`...
+fn ordinal(n: usize) -> String {
+    let suffix = match (n % 10, n % 100) {
+        (_, 11..=13) => "th",
+        (1, _) => "st",
+        (2, _) => "nd",
+        (3, _) => "rd",
+        _ => "th",
+    };
+    format!("{}{}", n, suffix)
+}
+
+/// The invite log line for a join. `invited_count` is how many members the
+/// inviter has brought in, this one included.
+fn join_line(user_id: u64, source: &JoinSource, invited_count: usize) -> String {
+    match source {
+        JoinSource::Invite {
+            code,
+            inviter: Some(inviter),
+        } => format!(
+            "📥 <@{}> joined via <@{}>'s invite `{}`, their {}",
+            user_id,
+            inviter,
+            code,
+            ordinal(invited_count)
+        ),
+        JoinSource::Invite {
+            code,
+            inviter: None,
+        } => format!("📥 <@{}> joined via invite `{}`", user_id, code),
+        JoinSource::Vanity => format!("📥 <@{}> joined via the server's vanity URL", user_id),
+        JoinSource::Unknown => format!(
+            "📥 <@{}> joined, but I couldn't tell how (discovery, the widget, or several invites used at once)",
+            user_id
+        ),
+    }
+}
+
+/// See who invited whom
+///
+/// As a prefix command `-invites @user` is short for `-invites user @user`.
+#[poise::command(
+    prefix_command,
+    slash_command,
+    guild_only,
+    subcommands("invites_user", "invites_top", "invites_channel")
+)]
+pub async fn invites(
+    ctx: Context<'_>,
+    #[description = "Whose invites to count (default: you)"] user: Option<serenity::User>,
+) -> Result<(), Error> {
+    invites_user_inner(ctx, user).await
+}
+
+/// How many members someone has brought in
+#[poise::command(prefix_command, slash_command, guild_only, rename = "user")]
+pub async fn invites_user(
+    ctx: Context<'_>,
+    #[description = "Whose invites to count (default: you)"] user: Option<serenity::User>,
+) -> Result<(), Error> {
+    invites_user_inner(ctx, user).await
+}
+
+async fn invites_user_inner(ctx: Context<'_>, user: Option<serenity::User>) -> Result<(), Error> {
+    log::info!("Invites command called by {}", ctx.author().name);
+
+    let Some(guild_id) = ctx.guild_id() else {
+        return Ok(());
+    };
+    let user = user.as_ref().unwrap_or_else(|| ctx.author());
+    let count = INVITES
+        .read()
+        .guilds
+        .get(&guild_id.get())
+        .map_or(0, |guild| guild.invited_by(user.id.get()));
+
+    let text = match count {
+        0 => format!("📨 <@{}> hasn't brought anyone in yet.", user.id),
+        1 => format!("📨 <@{}> has brought in **1** member.", user.id),
+        n => format!("📨 <@{}> has brought in **{}** members.", user.id, n),
+    };
+    ctx.send(
+        poise::CreateReply::default()
+            .content(text)
+            .allowed_mentions(serenity::CreateAllowedMentions::new()),
+    )
+    .await?;
+    Ok(())
+}
+
+/// Who has brought in the most members
+#[poise::command(prefix_command, slash_command, guild_only, rename = "top")]
+pub async fn invites_top(ctx: Context<'_>) -> Result<(), Error> {
+    log::info!("Invites top command called by {}", ctx.author().name);
+
+    let Some(guild_id) = ctx.guild_id() else {
+        return Ok(());
+    };
+    let ranked = INVITES
+        .read()
+        .guilds
+        .get(&guild_id.get())
+        .map(|guild| leaderboard(&guild.joins))
+        .unwrap_or_default();
+    if ranked.is_empty() {
+        ctx.say("📨 No joins recorded yet.").await?;
+        return Ok(());
+    }
+
+    let lines: Vec<String> = ranked
+        .iter()
+        .take(TOP_INVITERS)
+        .enumerate()
+        .map(|(i, (bucket, count))| {
+            let who = match bucket {
+                Bucket::Inviter(id) => format!("<@{}>", id),
+                Bucket::Vanity => "Vanity URL".to_string(),
+                Bucket::Unknown => "Unknown".to_string(),
+            };
+            format!("**{}.** {} — {}", i + 1, who, count)
+        })
+        .collect();
+    let total: usize = ranked.iter().map(|(_, count)| count).sum();
+    let embed = serenity::CreateEmbed::new()
+        .title("📨 Top inviters")
+        .description(lines.join("\n"))
+        .color(0x5865F2)
+        .footer(serenity::CreateEmbedFooter::new(format!(
+            "{} joins recorded",
+            total
+        )));
+    ctx.send(poise::CreateReply::default().embed(embed)).await?;
+    Ok(())
+}
+
+/// Log who invited each new member in a channel, or stop logging
+#[poise::command(
+    prefix_command,
+    slash_command,
+    guild_only,
+    rename = "channel",
+    required_permissions = "MANAGE_GUILD",
+    required_bot_permissions = "MANAGE_GUILD",
+    check = "invites_writable"
+)]
+pub async fn invites_channel(
+    ctx: Context<'_>,
+    #[description = "Channel for the invite log (leave out to stop)"] channel: Option<
+        serenity::GuildChannel,
+    >,
+) -> Result<(), Error> {
+    log::info!("Invites channel command called by {}", ctx.author().name);
+
+    let Some(guild_id) = ctx.guild_id() else {
+        return Ok(());
+    };
+    if let Some(channel) = &channel {
+        if channel.guild_id != guild_id {
+            ctx.say("❌ That channel isn't in this server!").await?;
+            return Ok(());
+        }
+        let required = [
+            serenity::Permissions::VIEW_CHANNEL,
+            serenity::Permissions::SEND_MESSAGES,
+        ];
+        if let Err(missing) = check_channel_perms(ctx, channel.id, &required).await {
+            ctx.say(missing.to_string()).await?;
+            return Ok(());
+        }
+    }
+
+    {
+        let mut data = INVITES.write();
+        let guild = data.guilds.entry(guild_id.get()).or_default();
+        guild.channel = channel.as_ref().map(|channel| channel.id.get());
+        save_invites(&data);
+    }
+    // The counts may be missing if the bot only just got Manage Server
+    cache_guild_invites(ctx.http(), guild_id).await;
+
+    match channel {
+        Some(channel) => {
+            ctx.say(format!(
+                "✅ New members and whose invite they used will be logged in <#{}>.",
+                channel.id
+            ))
+            .await?
+        }
+        None => {
+            ctx.say("✅ Joins won't be logged anymore (they're still counted).")
+                .await?
+        }
+    };
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_validate_invites() {
+        let mut data: InviteData =
+            serde_json::from_str(include_str!("../../tests/fixtures/config/invites.json")).unwrap();
+        let live = LiveIds::default()
+            .with_guild(1, &[10], &[])
+            .with_guild(2, &[10], &[]);
+
+        let problems = validate_invites(&mut data, &live);
+        assert_eq!(problems.len(), 1);
+        assert_eq!(problems[0].guild_id, Some(2));
+        assert_eq!(problems[0].problem, "channel 11 was deleted");
+        assert_eq!(data.guilds[&1].channel, Some(10));
+        // The joins are kept, only the log channel goes
+        assert_eq!(data.guilds[&2].channel, None);
+        assert_eq!(data.guilds[&2].joins.len(), 1);
+    }
+
+    fn invite(uses: u64, max_uses: u64, inviter: Option<u64>) -> InviteSnapshot {
+        InviteSnapshot {
+            uses,
+            max_uses,
+            inviter,
+        }
+    }
+
+    fn counts(invites: &[(&str, InviteSnapshot)]) -> InviteCounts {
+        invites
+            .iter()
+            .map(|(code, invite)| (code.to_string(), invite.clone()))
+            .collect()
+    }
+
+    #[test]
+    fn test_attribute_join() {
+        let before = counts(&[
+            ("alice", invite(6, 0, Some(1))),
+            ("bob", invite(2, 0, Some(2))),
+            ("once", invite(0, 1, Some(3))),
+        ]);
+
+        // Alice's invite went up
+        let after = counts(&[
+            ("alice", invite(7, 0, Some(1))),
+            ("bob", invite(2, 0, Some(2))),
+            ("once", invite(0, 1, Some(3))),
+        ]);
+        assert_eq!(
+            attribute_join(&before, &after, false),
+            JoinSource::Invite {
+                code: "alice".to_string(),
+                inviter: Some(1)
+            }
+        );
+
+        // A single-use invite is deleted once used
+        let after = counts(&[
+            ("alice", invite(6, 0, Some(1))),
+            ("bob", invite(2, 0, Some(2))),
+        ]);
+        assert_eq!(
+            attribute_join(&before, &after, false),
+            JoinSource::Invite {
+                code: "once".to_string(),
+                inviter: Some(3)
+            }
+        );
+
+        // An invite made since the last snapshot counts from zero
+        let mut after = before.clone();
+        after.insert("new".to_string(), invite(1, 0, None));
+        assert_eq!(
+            attribute_join(&before, &after, true),
+            JoinSource::Invite {
+                code: "new".to_string(),
+                inviter: None
+            }
+        );
+
+        // Nothing changed: the vanity URL if there is one
+        assert_eq!(attribute_join(&before, &before, true), JoinSource::Vanity);
+        assert_eq!(attribute_join(&before, &before, false), JoinSource::Unknown);
+
+        // Two invites went up at once: can't tell
+        let after = counts(&[
+            ("alice", invite(7, 0, Some(1))),
+            ("bob", invite(3, 0, Some(2))),
+            ("once", invite(0, 1, Some(3))),
+        ]);
+        assert_eq!(attribute_join(&before, &after, true), JoinSource::Unknown);
+
+        // A plain invite deleted by a mod isn't a join
+        let after = counts(&[
+            ("alice", invite(6, 0, Some(1))),
+            ("once", invite(0, 1, Some(3))),
+        ]);
+        assert_eq!(attribute_join(&before, &after, false), JoinSource::Unknown);
+    }
+
+    fn join(user_id: u64, source: JoinSource) -> JoinRecord {
+        JoinRecord {
+            user_id,
+            source,
+            joined_at: Utc::now(),
+        }
+    }
+
+    #[test]
+    fn test_leaderboard() {
+        let by = |inviter| JoinSource::Invite {
+            code: "x".to_string(),
+            inviter: Some(inviter),
+        };
+        let joins = vec![
+            join(10, by(1)),
+            join(11, by(2)),
+            join(12, by(2)),
+            join(13, JoinSource::Vanity),
+            join(14, JoinSource::Unknown),
+            join(
+                15,
+                JoinSource::Invite {
+                    code: "widget".to_string(),
+                    inviter: None,
+                },
+            ),
+        ];
+        assert_eq!(
+            leaderboard(&joins),
+            vec![
+                (Bucket::Inviter(2), 2),
+                (Bucket::Unknown, 2),
+                (Bucket::Inviter(1), 1),
+                (Bucket::Vanity, 1),
+            ]
+        );
+        let guild = GuildInvites {
+            channel: None,
+            joins,
+        };
+        assert_eq!(guild.invited_by(2), 2);
+        assert_eq!(guild.invited_by(99), 0);
+    }
+
+    #[test]
+    fn test_join_line() {
+        let source = JoinSource::Invite {
+            code: "abc".to_string(),
+            inviter: Some(1),
+        };
+        assert_eq!(
+            join_line(5, &source, 7),
+            "📥 <@5> joined via <@1>'s invite `abc`, their 7th"
+        );
+        assert_eq!(
+            join_line(5, &JoinSource::Vanity, 0),
+            "📥 <@5> joined via the server's vanity URL"
+        );
+        assert_eq!(ordinal(1), "1st");
+        assert_eq!(ordinal(2), "2nd");
+        assert_eq!(ordinal(3), "3rd");
+        assert_eq!(ordinal(11), "11th");
+        assert_eq!(ordinal(22), "22nd");
+        assert_eq!(ordinal(113), "113th");
+    }
+}
@@ -1,16 +1,7 @@
+use crate::lifecycle::{write_shutdown_marker, RestartReason};
 use crate::utils::{is_protected_user, send_dm_to_deekahy};
 use crate::{Context, Error};
 
-use serde::{Deserialize, Serialize};
-use std::fs;
-
-#[derive(Serialize, Deserialize)]
-struct KysInfo {
-    channel_id: u64,
-    user_name: String,
-    timestamp: u64,
-}
-
 /// Reboot the bot with a 1-hour cooldown
 #[poise::command(slash_command, prefix_command)]
 pub async fn kys(ctx: Context<'_>) -> Result<(), Error> {
@@ -31,31 +22,12 @@ pub async fn kys(ctx: Context<'_>) -> Result<(), Error> {
         .say("Here I am, brain the size of a planet, and what do they ask me to do? Shut down for an hour. I suppose you think that's terribly clever. Don't think you can cheer me up.")
         .await?;
 
-    // Store kys info for startup message with timestamp
-    let kys_info = KysInfo {
-        channel_id: ctx.channel_id().get(),
-        user_name: ctx.author().name.clone(),
-        timestamp: std::time::SystemTime::now()
-            .duration_since(std::time::UNIX_EPOCH)
-            .unwrap()
-            .as_secs(),
-    };
-
-    match serde_json::to_string(&kys_info) {
-        Ok(kys_json) => {
-            if let Err(e) = fs::write("/tmp/rustbot_kys_info.json", kys_json) {
-                log::error!("Failed to write kys info file: {}", e);
-                ctx.say("❌ Failed to prepare for restart. Please try again.")
-                    .await?;
-                return Ok(());
-            }
-        }
-        Err(e) => {
-            log::error!("Failed to serialize kys info: {}", e);
-            ctx.say("❌ Failed to prepare for restart. Please try again.")
-                .await?;
-            return Ok(());
-        }
+    // The next startup reads this to keep sleeping until the hour is up
+    if let Err(e) = write_shutdown_marker(RestartReason::Kys, Some(ctx.author().name.clone())) {
+        log::error!("Failed to write shutdown marker: {}", e);
+        ctx.say("❌ Failed to prepare for restart. Please try again.")
+            .await?;
+        return Ok(());
     }
 
     reply
@@ -85,6 +57,10 @@ pub async fn kys(ctx: Context<'_>) -> Result<(), Error> {
     log::info!("Waiting 5 seconds to ensure all messages are sent...");
     tokio::time::sleep(tokio::time::Duration::from_secs(5)).await;
 
+    // Save buffered command usage and seen users so they aren't lost on shutdown
+    crate::commands::usage::flush_command_usage();
+    crate::onboarding::flush_seen_users();
+
     // Exit with a specific code that indicates a 1-hour delayed restart is needed
     log::info!("Exiting with code 43 for 1-hour restart delay");
     std::process::exit(43);
@@ -0,0 +1,281 @@
+use crate::guild_config;
+use crate::storage;
+use crate::utils::{check_store_writable, is_protected_user};
+use crate::{Context, Error};
+use chrono::{DateTime, Utc};
+use parking_lot::RwLock;
+use poise::serenity_prelude as serenity;
+use serde::{Deserialize, Serialize};
+
+const MAINTENANCE_FILE: &str = "maintenance.json";
+
+const DEFAULT_MAINTENANCE_MESSAGE: &str = "🔧 under maintenance, back soon";
+
+/// Bot-wide maintenance flag. While it is on, only bot owners can run
+/// commands and the data-writing background tasks skip their work.
+#[derive(Serialize, Deserialize, Default)]
+struct MaintenanceState {
+    enabled: bool,
+    message: Option<String>,
+    since: Option<DateTime<Utc>>,
+}
+
+/// The maintenance state, kept in [`crate::Data`] and shared with the
+/// background tasks that pause during maintenance.
+pub struct Maintenance {
+    state: RwLock<MaintenanceState>,
+}
+
+impl Maintenance {
+    /// The state saved before the last restart.
+    pub fn load() -> Self {
+        Maintenance {
+            state: RwLock::new(storage::load(MAINTENANCE_FILE)),
+        }
+    }
+
+    /// Whether the bot is currently in maintenance mode.
+    pub fn is_enabled(&self) -> bool {
+        self.state.read().enabled
+    }
+}
+
+fn save_maintenance(state: &MaintenanceState) {
+    if let Err(e) = storage::save(MAINTENANCE_FILE, state) {
+        log::error!("Failed to save maintenance state: {}", e);
+    }
+}
+
+/// Command check for commands that change the maintenance state.
+async fn maintenance_writable(ctx: Context<'_>) -> Result<bool, Error> {
+    check_store_writable(ctx, &storage::data_path(MAINTENANCE_FILE)).await
+}
+
+impl MaintenanceState {
+    /// The message shown to users while in maintenance mode, or `None` if the
+    /// bot isn't in maintenance mode.
+    fn user_message(&self) -> Option<String> {
+        self.enabled.then(|| {
+            self.message
+                .clone()
+                .unwrap_or_else(|| DEFAULT_MAINTENANCE_MESSAGE.to_string())
+        })
+    }
+}
+
+/// Global command check: reject commands from non-owners while in maintenance mode.
+pub async fn maintenance_check(ctx: Context<'_>) -> Result<bool, Error> {
+    let Some(message) = ctx.data().maintenance.state.read().user_message() else {
+        return Ok(true);
+    };
+    if is_protected_user(&ctx.author().name) {
+        return Ok(true);
+    }
+
+    ctx.send(
+        poise::CreateReply::default()
+            .content(message)
+            .ephemeral(true),
+    )
+    .await?;
+    Ok(false)
+}
+
+/// Show the maintenance presence if maintenance mode is on. Called at startup
+/// so the status survives restarts.
+pub fn apply_maintenance_presence(ctx: &serenity::Context, maintenance: &Maintenance) {
+    if maintenance.is_enabled() {
+        ctx.set_presence(
+            Some(serenity::ActivityData::custom("🔧 Under maintenance")),
+            serenity::OnlineStatus::DoNotDisturb,
+        );
+    }
+}
+
+/// Status channel post for maintenance starting, with what users are told.
+fn started_announcement(user_message: &str) -> String {
+    format!(
+        "🔧 Maintenance has started, so commands are paused for now.\n> {}",
+        user_message
+    )
+}
+
+/// Status channel post for maintenance ending, `duration` being how long it
+/// lasted if known.
+fn ended_announcement(duration: Option<chrono::Duration>) -> String {
+    let lasted = duration
+        .map(|d| format!(" after {}", super::remind::format_duration(d)))
+        .unwrap_or_default();
+    format!("✅ Maintenance is over{}. Commands work again.", lasted)
+}
+
+/// Post to every configured status channel.
+async fn announce(http: &serenity::Http, text: &str) {
+    for channel_id in guild_config::status_channels() {
+        if let Err(e) = serenity::ChannelId::new(channel_id).say(http, text).await {
+            log::warn!(
+                "Failed to announce maintenance in status channel {}: {}",
+                channel_id,
+                e
+            );
+        }
+    }
+}
+
+/// Freeze the bot for everyone except its owners
+#[poise::command(
+    prefix_command,
+    slash_command,
+    subcommands("maintenance_on", "maintenance_off")
+)]
+pub async fn maintenance(_ctx: Context<'_>) -> Result<(), Error> {
+    Ok(())
+}
+
+/// Turn maintenance mode on
+#[poise::command(
+    prefix_command,
+    slash_command,
+    rename = "on",
+    check = "maintenance_writable"
+)]
+pub async fn maintenance_on(
+    ctx: Context<'_>,
+    #[description = "Message shown to users (default: 🔧 under maintenance, back soon)"]
+    #[rest]
+    message: Option<String>,
+) -> Result<(), Error> {
+    if !is_protected_user(&ctx.author().name) {
+        ctx.say("❌ You don't have permission to use this command!")
+            .await?;
+        return Ok(());
+    }
+
+    log::info!("Maintenance mode enabled by {}", ctx.author().name);
+
+    let message = message
+        .map(|m| m.trim().to_string())
+        .filter(|m| !m.is_empty());
+    let maintenance = &ctx.data().maintenance;
+    let was_on = {
+        let mut state = maintenance.state.write();
+        let was_on = state.enabled;
+        state.enabled = true;
+        state.message = message;
+        state.since.get_or_insert_with(Utc::now);
+        save_maintenance(&state);
+        was_on
+    };
+
+    apply_maintenance_presence(ctx.serenity_context(), maintenance);
+
+    let shown = maintenance.state.read().user_message().unwrap_or_default();
+    ctx.say(format!(
+        "🔧 Maintenance mode is **on**. Other users will see:\n> {}",
+        shown
+    ))
+    .await?;
+    // Changing the message of a running maintenance isn't news
+    if !was_on {
+        announce(ctx.http(), &started_announcement(&shown)).await;
+    }
+    Ok(())
+}
+
+/// Turn maintenance mode off
+#[poise::command(
+    prefix_command,
+    slash_command,
+    rename = "off",
+    check = "maintenance_writable"
+)]
+pub async fn maintenance_off(ctx: Context<'_>) -> Result<(), Error> {
+    if !is_protected_user(&ctx.author().name) {
+        ctx.say("❌ You don't have permission to use this command!")
+            .await?;
+        return Ok(());
+    }
+
+    let was_on_since = {
+        let mut state = ctx.data().maintenance.state.write();
+        state.enabled.then(|| {
+            let since = state.since.take();
+            *state = MaintenanceState::default();
+            save_maintenance(&state);
+            since
+        })
+    };
+    let Some(since) = was_on_since else {
+        ctx.say("❌ Maintenance mode isn't on!").await?;
+        return Ok(());
+    };
+
+    log::info!("Maintenance mode disabled by {}", ctx.author().name);
+
+    ctx.serenity_context()
+        .set_presence(None, serenity::OnlineStatus::Online);
+
+    // Send anything that came due during the freeze right away
+    super::remind::wake_reminder_checker();
+
+    let lasted = since.map(|since| Utc::now() - since);
+    let duration = lasted
+        .map(|d| format!(" after {}", super::remind::format_duration(d)))
+        .unwrap_or_default();
+    ctx.say(format!(
+        "✅ Maintenance mode is **off**{}. Everything is back to normal.",
+        duration
+    ))
+    .await?;
+    announce(ctx.http(), &ended_announcement(lasted)).await;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_user_message() {
+        let mut state = MaintenanceState::default();
+        assert_eq!(state.user_message(), None);
+
+        state.enabled = true;
+        assert_eq!(
+            state.user_message().as_deref(),
+            Some(DEFAULT_MAINTENANCE_MESSAGE)
+        );
+
+        state.message = Some("Migrating the database, ~10 minutes".to_string());
+        assert_eq!(
+            state.user_message().as_deref(),
+            Some("Migrating the database, ~10 minutes")
+        );
+    }
+
+    #[test]
+    fn test_announcements() {
+        assert_eq!(
+            started_announcement(DEFAULT_MAINTENANCE_MESSAGE),
+            "🔧 Maintenance has started, so commands are paused for now.\n> 🔧 under maintenance, back soon"
+        );
+        assert_eq!(
+            ended_announcement(Some(chrono::Duration::minutes(90))),
+            format!(
+                "✅ Maintenance is over after {}. Commands work again.",
+                crate::commands::remind::format_duration(chrono::Duration::minutes(90))
+            )
+        );
+        assert_eq!(
+            ended_announcement(None),
+            "✅ Maintenance is over. Commands work again."
+        );
+    }
+
+    #[test]
+    fn test_state_without_message() {
+        let state: MaintenanceState = serde_json::from_str(r#"{"enabled": true}"#).unwrap();
+        assert!(state.enabled);
+        assert!(state.message.is_none());
+    }
+}
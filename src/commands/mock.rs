@@ -1,26 +1,6 @@
+use crate::utils::transforms::{mockify, run_transform};
 use crate::{Context, Error};
 
-/// Transform text into mocking alternating case
-fn mockify(text: &str) -> String {
-    let mut result = String::new();
-    let mut is_uppercase = false; // Start with lowercase for first letter
-
-    for ch in text.chars() {
-        if ch.is_alphabetic() {
-            if is_uppercase {
-                result.push(ch.to_uppercase().next().unwrap_or(ch));
-            } else {
-                result.push(ch.to_lowercase().next().unwrap_or(ch));
-            }
-            is_uppercase = !is_uppercase; // Alternate for next letter
-        } else {
-            result.push(ch); // Preserve non-alphabetic characters
-        }
-    }
-
-    result
-}
-
 /// Transform text into mocking alternating case, or reply to a message to mock it
 #[poise::command(prefix_command, slash_command)]
 pub async fn mock(
@@ -31,67 +11,7 @@ pub async fn mock(
 ) -> Result<(), Error> {
     log::info!("Mock command called by {}", ctx.author().name);
 
-    let (text_to_mock, is_reply) = if let Some(text) = text {
-        // User provided text directly
-        (text, false)
-    } else {
-        // Check if this is a prefix command with a replied message
-        match ctx {
-            poise::Context::Prefix(prefix_ctx) => {
-                if let Some(replied_message) = prefix_ctx.msg.referenced_message.as_ref() {
-                    // Delete the invoker's message when replying to another message
-                    if let Err(e) = prefix_ctx.msg.delete(&ctx.http()).await {
-                        log::warn!("Failed to delete invoker's message: {}", e);
-                    }
-                    (replied_message.content.clone(), true)
-                } else {
-                    ctx.say(
-                        "❌ Please provide text to mock or reply to a message with this command!",
-                    )
-                    .await?;
-                    return Ok(());
-                }
-            }
-            _ => {
-                ctx.say("❌ Please provide text to mock! (Message replies only work with prefix commands)")
-                    .await?;
-                return Ok(());
-            }
-        }
-    };
-
-    if text_to_mock.trim().is_empty() {
-        ctx.say("❌ Cannot mock empty text!").await?;
-        return Ok(());
-    }
-
-    let mocked = mockify(&text_to_mock);
-
-    // Create response with user mention if replying to someone else's message
-    let response = if is_reply {
-        match ctx {
-            poise::Context::Prefix(prefix_ctx) => {
-                if let Some(replied_message) = prefix_ctx.msg.referenced_message.as_ref() {
-                    if replied_message.author.id != ctx.author().id {
-                        format!("*{} says:*\n{}", replied_message.author.name, mocked)
-                    } else {
-                        mocked
-                    }
-                } else {
-                    mocked
-                }
-            }
-            _ => mocked,
-        }
-    } else {
-        mocked
-    };
-
-    if let Err(e) = ctx.say(response).await {
-        ctx.say(format!("❌ {e}")).await?;
-    }
-
-    Ok(())
+    run_transform(ctx, text, "mock", mockify).await
 }
 
 #[cfg(test)]
@@ -1,58 +1,139 @@
 // Commands module - imports all individual command files
+pub mod ascii;
+pub mod assets;
+pub mod attachmentstats;
+pub mod automod;
+pub mod autopublish;
+pub mod autoreact;
+pub mod backup;
+pub mod birthday;
 pub mod bonk;
+pub mod boost;
+pub mod botadmin;
+pub mod botreplies;
+pub mod botstats;
+pub mod bumper;
+pub mod clap;
 pub mod cleanup;
 pub mod coinflip;
+pub mod color;
+pub mod combo;
+pub mod configcheck;
+pub mod countdown;
 pub mod dice;
+pub mod digest;
+pub mod dmlog;
+pub mod emojistats;
+pub mod fish;
+pub mod flagtranslate;
+pub mod games;
 pub mod general;
 pub mod hangman;
 pub mod hello;
 pub mod help;
 pub mod hit;
 pub mod invite;
+pub mod invites;
 pub mod kys;
+pub mod maintenance;
 pub mod mock;
+pub mod note;
 pub mod number_guess;
+pub mod owoify;
 pub mod park;
 pub mod pfp;
 pub mod ping;
 pub mod play;
 pub mod poll;
+pub mod poll_render;
+pub mod poll_templates;
 pub mod react;
+pub mod reaction_watch;
+pub mod reactmap;
+pub mod reload_config;
 pub mod remind;
+pub mod reply_translate;
+pub mod selftest;
+pub mod setup;
+pub mod ship;
 pub mod spamping;
 pub mod stats;
 pub mod stats_render;
 pub mod status;
+pub mod statuschannel;
+pub mod streak;
 pub mod tictactoe;
+pub mod timestamp;
 pub mod update;
+pub mod usage;
 pub mod uwu;
 pub mod yourmom;
 
-// Re-export all commands for easy access from main.rs
+// Re-export all commands for easy access from lib.rs
+pub use ascii::ascii;
+pub use assets::assets;
+pub use attachmentstats::attachmentstats;
+pub use automod::automod;
+pub use autopublish::{autopublish, start_autopublish_queue};
+pub use autoreact::autoreact;
+pub use backup::backup;
+pub use birthday::{birthday, start_birthday_scheduler};
 pub use bonk::bonk;
+pub use boost::boost;
+pub use botadmin::botadmin;
+pub use botreplies::botreplies;
+pub use botstats::botstats;
+pub use bumper::{bumper, start_bumper};
+pub use clap::clap;
 pub use cleanup::cleanup;
 pub use coinflip::coinflip;
+pub use color::{color, start_color_role_cleanup};
+pub use combo::combo;
+pub use configcheck::configcheck;
 pub use dice::dice;
+pub use digest::{digest, start_digest_scheduler};
+pub use dmlog::dmlog;
+pub use emojistats::emojistats;
+pub use fish::{fish, inventory};
+pub use flagtranslate::flagtranslate;
+pub use games::{endgame, games};
+pub use general::avatarguess;
 pub use hangman::{endhangman, hangman, hangmanhint, hangmanstatus, letter};
 pub use hello::hello;
 pub use help::help;
 pub use hit::hit;
 pub use invite::invite;
+pub use invites::invites;
 pub use kys::kys;
+pub use maintenance::maintenance;
 pub use mock::mock;
-pub use number_guess::{endgame, gamestatus, guess, hint, numberguess};
+pub use note::note;
+pub use number_guess::{gamestatus, guess, hint, numberguess};
+pub use owoify::owoify;
 pub use park::{park, start_parking_scheduler};
 pub use pfp::pfp;
-pub use ping::ping;
-pub use play::{leave, play, queue, skip, stop};
-pub use poll::poll;
+pub use ping::{ping, pinghistory, start_latency_probe};
+pub use play::{leave, play, queue, skip, sounds, stop};
+pub use poll::{poll, pollclose, pollstraw, start_poll_closer};
+pub use poll_templates::polltemplate;
 pub use react::react;
+pub use reaction_watch::start_reaction_watcher;
+pub use reactmap::reactmap;
+pub use reload_config::reload_config;
 pub use remind::{remind, start_reminder_checker};
+pub use reply_translate::translate;
+pub use selftest::selftest;
+pub use setup::setup;
+pub use ship::ship;
 pub use spamping::spamping;
-pub use stats::stats;
+pub use stats::{stats, statscompare};
 pub use status::status;
+pub use statuschannel::statuschannel;
+pub use streak::{start_streak_checker, streak};
 pub use tictactoe::{board, endttt, move_ttt, tictactoe};
+pub use timestamp::timestamp;
 pub use update::update;
+pub use usage::{start_usage_flusher, usage};
 pub use uwu::uwu;
 pub use yourmom::yourmom;
 
@@ -0,0 +1,492 @@
+//! Personal notes and todos: `-note add`, `list`, `done` and `delete`.
+//! Notes belong to a user rather than a server, so the same list shows up
+//! everywhere, DMs included. A note can come with a reminder, which is an
+//! ordinary reminder pointing back at the note: finishing or deleting the
+//! note cancels it, and when it fires the note is marked as reminded.
+
+use crate::commands::remind::{
+    add_note_reminder, cancel_note_reminders, check_reminder_time, format_duration,
+    parse_time_duration,
+};
+use crate::storage;
+use crate::{Context, Error};
+use chrono::{DateTime, Duration, Utc};
+use parking_lot::Mutex;
+use poise::serenity_prelude as serenity;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+const NOTES_FILE: &str = "notes.json";
+
+const MAX_NOTE_CHARS: usize = 500;
+
+/// How many notes `note list` shows per page.
+const NOTES_PER_PAGE: usize = 10;
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+struct Note {
+    /// Numbered per user, starting at 1.
+    id: u64,
+    text: String,
+    created_at: DateTime<Utc>,
+    done: bool,
+    /// When its reminder is due, if it was added with one.
+    #[serde(default)]
+    remind_at: Option<DateTime<Utc>>,
+    /// Set once that reminder has fired.
+    #[serde(default)]
+    reminded_at: Option<DateTime<Utc>>,
+}
+
+#[derive(Serialize, Deserialize, Default)]
+struct UserNotes {
+    notes: Vec<Note>,
+    /// The last ID handed out, so deleted notes' IDs aren't reused.
+    next_id: u64,
+}
+
+#[derive(Serialize, Deserialize, Default)]
+struct NotesData {
+    users: HashMap<u64, UserNotes>,
+}
+
+impl NotesData {
+    fn add(&mut self, user_id: u64, text: String, now: DateTime<Utc>) -> u64 {
+        let user = self.users.entry(user_id).or_default();
+        user.next_id += 1;
+        user.notes.push(Note {
+            id: user.next_id,
+            text,
+            created_at: now,
+            done: false,
+            remind_at: None,
+            reminded_at: None,
+        });
+        user.next_id
+    }
+
+    fn note_mut(&mut self, user_id: u64, id: u64) -> Option<&mut Note> {
+        self.users
+            .get_mut(&user_id)?
+            .notes
+            .iter_mut()
+            .find(|note| note.id == id)
+    }
+
+    /// Mark a note as done. `None` if the user has no such note.
+    fn complete(&mut self, user_id: u64, id: u64) -> Option<&Note> {
+        let note = self.note_mut(user_id, id)?;
+        note.done = true;
+        Some(note)
+    }
+
+    /// Remove a note. The user's entry is kept even once empty, so
+    /// `next_id` never goes back and old IDs stay retired.
+    fn delete(&mut self, user_id: u64, id: u64) -> Option<Note> {
+        let user = self.users.get_mut(&user_id)?;
+        let index = user.notes.iter().position(|note| note.id == id)?;
+        Some(user.notes.remove(index))
+    }
+
+    /// A user's notes, open ones first, each group oldest first.
+    fn sorted(&self, user_id: u64) -> Vec<Note> {
+        let mut notes = self
+            .users
+            .get(&user_id)
+            .map(|user| user.notes.clone())
+            .unwrap_or_default();
+        notes.sort_by_key(|note| (note.done, note.id));
+        notes
+    }
+}
+
+/// Serializes load-modify-save of the store between commands and the
+/// reminder checker.
+static NOTES_LOCK: Mutex<()> = Mutex::new(());
+
+fn load_notes() -> NotesData {
+    storage::load(NOTES_FILE)
+}
+
+fn save_notes(data: &NotesData) -> Result<(), Error> {
+    storage::save(NOTES_FILE, data)
+}
+
+/// Change the store under its lock, saving if `change` returns `Some`.
+fn update_notes<T>(change: impl FnOnce(&mut NotesData) -> Option<T>) -> Result<Option<T>, Error> {
+    let _guard = NOTES_LOCK.lock();
+    let mut data = load_notes();
+    let result = change(&mut data);
+    if result.is_some() {
+        save_notes(&data)?;
+    }
+    Ok(result)
+}
+
+/// Called by the reminder checker when a note's reminder fires.
+pub fn mark_reminded(user_id: u64, note_id: u64, now: DateTime<Utc>) -> Result<(), Error> {
+    update_notes(|data| {
+        let note = data.note_mut(user_id, note_id)?;
+        note.reminded_at = Some(now);
+        Some(())
+    })?;
+    Ok(())
+}
+
+/// The note text, trimmed, or why it can't be saved.
+fn validate_text(text: &str) -> Result<String, String> {
+    let text = text.trim();
+    if text.is_empty() {
+        return Err("❌ Please write something for the note!".to_string());
+    }
+    let length = text.chars().count();
+    if length > MAX_NOTE_CHARS {
+        return Err(format!(
+            "❌ Notes can be at most {} characters, that one is {}.",
+            MAX_NOTE_CHARS, length
+        ));
+    }
+    Ok(text.to_string())
+}
+
+/// Split `-note add` arguments into the reminder delay and the note text.
+/// The prefix form reads `-note add [time] <text>`, so a first word that
+/// isn't a duration is the start of the text instead.
+fn split_remind(
+    remind: Option<String>,
+    text: Option<String>,
+    is_prefix: bool,
+) -> Result<(Option<Duration>, String), String> {
+    let text = text.unwrap_or_default();
+    let Some(remind) = remind else {
+        return Ok((None, text));
+    };
+    match parse_time_duration(&remind) {
+        Some(delay) if delay > Duration::zero() => Ok((Some(delay), text)),
+        _ if is_prefix => Ok((None, format!("{} {}", remind, text))),
+        _ => {
+            Err("❌ Invalid time format for `remind`! Use formats like: 5m, 1h, 2d, 1w".to_string())
+        }
+    }
+}
+
+fn format_note_entry(note: &Note) -> String {
+    let text = if note.done {
+        format!("✅ ~~{}~~", note.text)
+    } else {
+        format!("☐ {}", note.text)
+    };
+    let mut details = format!("added <t:{}:R>", note.created_at.timestamp());
+    if !note.done {
+        match (note.reminded_at, note.remind_at) {
+            (Some(at), _) => details.push_str(&format!(" • 🔔 reminded <t:{}:R>", at.timestamp())),
+            (None, Some(at)) => details.push_str(&format!(" • ⏰ <t:{}:R>", at.timestamp())),
+            (None, None) => {}
+        }
+    }
+    format!("**#{}** {}\n{}\n\n", note.id, text, details)
+}
+
+/// Personal notes and todos - add, list, done, delete
+#[poise::command(
+    prefix_command,
+    slash_command,
+    subcommands("note_add", "note_list", "note_done", "note_delete")
+)]
+pub async fn note(ctx: Context<'_>) -> Result<(), Error> {
+    ctx.say("❌ Usage: `-note add [time] <text>`, `-note list`, `-note done <id>` or `-note delete <id>`")
+        .await?;
+    Ok(())
+}
+
+/// Add a note, optionally with a reminder
+#[poise::command(prefix_command, slash_command, rename = "add")]
+pub async fn note_add(
+    ctx: Context<'_>,
+    #[description = "Remind me about it after this long (e.g., 30m, 2h, 1d)"] remind: Option<
+        String,
+    >,
+    #[description = "The note (up to 500 characters)"]
+    #[rest]
+    text: Option<String>,
+) -> Result<(), Error> {
+    log::info!("Note add command called by {}", ctx.author().name);
+
+    let is_prefix = matches!(ctx, poise::Context::Prefix(_));
+    let (delay, text) = match split_remind(remind, text, is_prefix)
+        .and_then(|(delay, text)| Ok((delay, validate_text(&text)?)))
+    {
+        Ok(parsed) => parsed,
+        Err(error) => {
+            ctx.say(error).await?;
+            return Ok(());
+        }
+    };
+
+    let now = Utc::now();
+    let remind_at = delay.map(|delay| now + delay);
+    if let Some(at) = remind_at {
+        if !check_reminder_time(ctx, now, at).await? {
+            return Ok(());
+        }
+    }
+
+    let user_id = ctx.author().id.get();
+    let added = update_notes(|data| {
+        let id = data.add(user_id, text.clone(), now);
+        if let Some(note) = data.note_mut(user_id, id) {
+            note.remind_at = remind_at;
+        }
+        Some(id)
+    });
+    let id = match added {
+        Ok(id) => id.unwrap_or_default(),
+        Err(e) => {
+            ctx.say(format!("❌ Failed to save note: {e}")).await?;
+            return Ok(());
+        }
+    };
+
+    let mut description = format!("**#{}** {}", id, text);
+    if let (Some(at), Some(delay)) = (remind_at, delay) {
+        match add_note_reminder(ctx, id, &text, at).await {
+            Ok(_) => description.push_str(&format!(
+                "\n⏰ Reminding you <t:{}:R> (in {})",
+                at.timestamp(),
+                format_duration(delay)
+            )),
+            Err(e) => {
+                log::error!("Failed to set the reminder for note {}: {}", id, e);
+                let _ = update_notes(|data| {
+                    data.note_mut(user_id, id)?.remind_at = None;
+                    Some(())
+                });
+                description.push_str(&format!(
+                    "\n⚠️ The note is saved, but the reminder failed: {e}"
+                ));
+            }
+        }
+    }
+
+    let embed = serenity::CreateEmbed::new()
+        .title("📝 Note Added")
+        .description(description)
+        .color(serenity::Color::DARK_GREEN)
+        .timestamp(now);
+    ctx.send(poise::CreateReply::default().embed(embed)).await?;
+    Ok(())
+}
+
+/// List your notes, open ones first
+#[poise::command(prefix_command, slash_command, rename = "list")]
+pub async fn note_list(ctx: Context<'_>) -> Result<(), Error> {
+    log::info!("Note list command called by {}", ctx.author().name);
+
+    let notes = load_notes().sorted(ctx.author().id.get());
+    if notes.is_empty() {
+        ctx.say("📭 You have no notes! Add one with `-note add <text>`.")
+            .await?;
+        return Ok(());
+    }
+
+    let open = notes.iter().filter(|note| !note.done).count();
+    let pages: Vec<String> = notes
+        .chunks(NOTES_PER_PAGE)
+        .map(|chunk| chunk.iter().map(format_note_entry).collect())
+        .collect();
+
+    if pages.len() == 1 {
+        let embed = serenity::CreateEmbed::new()
+            .title("📝 Your Notes")
+            .description(&pages[0])
+            .color(serenity::Color::BLUE)
+            .footer(serenity::CreateEmbedFooter::new(format!(
+                "{} open • {} done",
+                open,
+                notes.len() - open
+            )));
+        ctx.send(poise::CreateReply::default().embed(embed)).await?;
+    } else {
+        let page_count = pages.len();
+        let pages: Vec<String> = pages
+            .into_iter()
+            .enumerate()
+            .map(|(i, page)| {
+                format!(
+                    "**📝 Your Notes ({} open, page {}/{})**\n\n{}",
+                    open,
+                    i + 1,
+                    page_count,
+                    page
+                )
+            })
+            .collect();
+        let page_refs: Vec<&str> = pages.iter().map(String::as_str).collect();
+        poise::builtins::paginate(ctx, &page_refs).await?;
+    }
+    Ok(())
+}
+
+/// Mark a note as done, cancelling its reminder
+#[poise::command(prefix_command, slash_command, rename = "done")]
+pub async fn note_done(
+    ctx: Context<'_>,
+    #[description = "Note ID (see `note list`)"] id: u64,
+) -> Result<(), Error> {
+    log::info!(
+        "Note done command called by {} for #{}",
+        ctx.author().name,
+        id
+    );
+
+    let user_id = ctx.author().id.get();
+    let text = match update_notes(|data| data.complete(user_id, id).map(|note| note.text.clone())) {
+        Ok(Some(text)) => text,
+        Ok(None) => {
+            ctx.say("❌ Note not found! Check the ID with `-note list`.")
+                .await?;
+            return Ok(());
+        }
+        Err(e) => {
+            ctx.say(format!("❌ Failed to save note: {e}")).await?;
+            return Ok(());
+        }
+    };
+
+    let mut message = format!("✅ Done: ~~{}~~", text);
+    message.push_str(&cancelled_suffix(user_id, id).await);
+    ctx.say(message).await?;
+    Ok(())
+}
+
+/// Delete a note, cancelling its reminder
+#[poise::command(prefix_command, slash_command, rename = "delete")]
+pub async fn note_delete(
+    ctx: Context<'_>,
+    #[description = "Note ID (see `note list`)"] id: u64,
+) -> Result<(), Error> {
+    log::info!(
+        "Note delete command called by {} for #{}",
+        ctx.author().name,
+        id
+    );
+
+    let user_id = ctx.author().id.get();
+    let note = match update_notes(|data| data.delete(user_id, id)) {
+        Ok(Some(note)) => note,
+        Ok(None) => {
+            ctx.say("❌ Note not found! Check the ID with `-note list`.")
+                .await?;
+            return Ok(());
+        }
+        Err(e) => {
+            ctx.say(format!("❌ Failed to save notes: {e}")).await?;
+            return Ok(());
+        }
+    };
+
+    let mut message = format!("🗑️ Deleted note #{}: {}", note.id, note.text);
+    message.push_str(&cancelled_suffix(user_id, id).await);
+    ctx.say(message).await?;
+    Ok(())
+}
+
+/// Cancel a finished note's reminder, describing the outcome for the reply.
+async fn cancelled_suffix(user_id: u64, note_id: u64) -> String {
+    match cancel_note_reminders(user_id, note_id).await {
+        Ok(0) => String::new(),
+        Ok(_) => "\n⏰ Its reminder was cancelled.".to_string(),
+        Err(e) => {
+            log::error!("Failed to cancel the reminder of note {}: {}", note_id, e);
+            format!("\n⚠️ Couldn't cancel its reminder: {e}")
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_note_store() {
+        let now = Utc::now();
+        let mut data = NotesData::default();
+        assert_eq!(data.add(1, "milk".to_string(), now), 1);
+        assert_eq!(data.add(1, "eggs".to_string(), now), 2);
+        // IDs are per user
+        assert_eq!(data.add(2, "bread".to_string(), now), 1);
+
+        assert_eq!(data.complete(1, 1).map(|note| note.done), Some(true));
+        assert!(data.complete(2, 2).is_none());
+        // Open notes come first
+        let ids: Vec<u64> = data.sorted(1).iter().map(|note| note.id).collect();
+        assert_eq!(ids, vec![2, 1]);
+
+        assert_eq!(
+            data.delete(1, 2).map(|note| note.text),
+            Some("eggs".to_string())
+        );
+        assert!(data.delete(1, 2).is_none());
+        // Deleted IDs aren't handed out again
+        assert_eq!(data.add(1, "jam".to_string(), now), 3);
+        // Not even after deleting every note
+        assert!(data.delete(2, 1).is_some());
+        assert_eq!(data.add(2, "butter".to_string(), now), 2);
+
+        // Survives a save and load
+        let json = serde_json::to_string(&data).unwrap();
+        let loaded: NotesData = serde_json::from_str(&json).unwrap();
+        assert_eq!(loaded.sorted(1), data.sorted(1));
+        assert!(loaded.sorted(3).is_empty());
+    }
+
+    #[test]
+    fn test_validate_text() {
+        assert_eq!(validate_text("  buy milk "), Ok("buy milk".to_string()));
+        assert!(validate_text("   ").is_err());
+        assert!(validate_text(&"é".repeat(MAX_NOTE_CHARS)).is_ok());
+        assert!(validate_text(&"é".repeat(MAX_NOTE_CHARS + 1))
+            .unwrap_err()
+            .contains("at most 500"));
+    }
+
+    #[test]
+    fn test_split_remind() {
+        let some = |s: &str| Some(s.to_string());
+        assert_eq!(
+            split_remind(some("2h"), some("call mom"), true),
+            Ok((Some(Duration::hours(2)), "call mom".to_string()))
+        );
+        // A prefix note that doesn't start with a time
+        assert_eq!(
+            split_remind(some("call"), some("mom"), true),
+            Ok((None, "call mom".to_string()))
+        );
+        assert!(split_remind(some("soon"), some("call mom"), false).is_err());
+        assert_eq!(
+            split_remind(None, some("call mom"), false),
+            Ok((None, "call mom".to_string()))
+        );
+    }
+
+    #[test]
+    fn test_format_note_entry() {
+        let now = Utc::now();
+        let mut note = Note {
+            id: 4,
+            text: "water plants".to_string(),
+            created_at: now,
+            done: false,
+            remind_at: Some(now),
+            reminded_at: None,
+        };
+        assert!(format_note_entry(&note).starts_with("**#4** ☐ water plants\n"));
+        assert!(format_note_entry(&note).contains("⏰"));
+        note.reminded_at = Some(now);
+        assert!(format_note_entry(&note).contains("🔔 reminded"));
+        note.done = true;
+        let entry = format_note_entry(&note);
+        assert!(entry.contains("✅ ~~water plants~~"));
+        assert!(!entry.contains("🔔"));
+    }
+}
@@ -1,21 +1,27 @@
+use crate::commands::general::{
+    is_game_idle, ActiveGame, GameKind, GamePlace, GameSession, GameSummary,
+};
+use crate::utils::is_protected_user;
 use crate::{Context, Error};
 
+use chrono::{DateTime, Utc};
 use rand::Rng;
 use std::collections::HashMap;
+use std::time::Instant;
 
 use tokio::sync::RwLock;
 
-// Global storage for active games
-lazy_static::lazy_static! {
-    static ref ACTIVE_GAMES: RwLock<HashMap<u64, NumberGame>> = RwLock::new(HashMap::new());
-}
-
+/// A number guessing game, stored by player in
+/// [`GameSessions`](crate::commands::general::GameSessions).
 #[derive(Clone)]
-struct NumberGame {
+pub(crate) struct NumberGame {
     secret_number: u32,
     attempts: u32,
     min: u32,
     max: u32,
+    started: DateTime<Utc>,
+    place: Option<GamePlace>,
+    last_activity: Instant,
 }
 
 impl NumberGame {
@@ -28,11 +34,15 @@ impl NumberGame {
             attempts: 0,
             min,
             max,
+            started: Utc::now(),
+            place: None,
+            last_activity: Instant::now(),
         }
     }
 
     fn make_guess(&mut self, guess: u32) -> GuessResult {
         self.attempts += 1;
+        self.last_activity = Instant::now();
 
         if !(self.min..=self.max).contains(&guess) {
             return GuessResult::OutOfRange;
@@ -82,6 +92,70 @@ impl NumberGame {
     }
 }
 
+/// Drop games nobody has guessed in for a while. Returns how many were removed.
+pub(crate) async fn expire_idle_games(games: &RwLock<HashMap<u64, NumberGame>>) -> usize {
+    let mut games = games.write().await;
+    remove_idle_games(&mut games, Instant::now())
+}
+
+fn remove_idle_games(games: &mut HashMap<u64, NumberGame>, now: Instant) -> usize {
+    let before = games.len();
+    games.retain(|_, game| !is_game_idle(game.last_activity, now));
+    before - games.len()
+}
+
+/// Read-only overview of active number guessing games
+pub(crate) async fn active_games_summary(games: &RwLock<HashMap<u64, NumberGame>>) -> GameSummary {
+    let games = games.read().await;
+    let mut details: Vec<String> = games
+        .iter()
+        .map(|(user_id, game)| {
+            format!(
+                "<@{}> — range {}-{}, {} attempts",
+                user_id, game.min, game.max, game.attempts
+            )
+        })
+        .collect();
+    details.sort();
+
+    GameSummary {
+        name: "Number Guess",
+        active: games.len(),
+        details,
+    }
+}
+
+impl GameSession for NumberGame {
+    const KIND: GameKind = GameKind::NumberGuess;
+
+    fn describe(&self, _user_id: u64) -> ActiveGame {
+        ActiveGame {
+            kind: Self::KIND,
+            opponent: None,
+            started: self.started,
+            place: self.place,
+            progress: format!(
+                "range {}-{}, {} attempts",
+                self.min, self.max, self.attempts
+            ),
+        }
+    }
+
+    fn channel_id(&self) -> Option<u64> {
+        self.place.map(|place| place.channel_id)
+    }
+
+    fn end(&self) -> String {
+        format!(
+            "🏳️ **Game ended!**\n\
+            The number was **{}**\n\
+            You made **{}** attempts.\n\
+            Thanks for playing! 👋",
+            self.secret_number, self.attempts
+        )
+    }
+}
+
 enum GuessResult {
     Correct,
     TooLow(String),
@@ -114,18 +188,19 @@ pub async fn numberguess(
 
     // Check if user already has an active game
     {
-        let games = ACTIVE_GAMES.read().await;
+        let games = ctx.data().games.number_guess.read().await;
         if games.contains_key(&user_id) {
-            ctx.say("❌ You already have an active number guessing game! Use `/guess <number>` to make a guess or `/endgame` to quit.").await?;
+            ctx.say("❌ You already have a Number Guess game going! Use `/guess <number>` to play or `/endgame numberguess` to quit.").await?;
             return Ok(());
         }
     }
 
     // Create new game
-    let game = NumberGame::new(min, max);
+    let mut game = NumberGame::new(min, max);
+    game.place = Some(GamePlace::here(ctx));
 
     {
-        let mut games = ACTIVE_GAMES.write().await;
+        let mut games = ctx.data().games.number_guess.write().await;
         games.insert(user_id, game);
     }
 
@@ -152,7 +227,7 @@ pub async fn guess(
 ) -> Result<(), Error> {
     let user_id = ctx.author().id.get();
 
-    let mut games = ACTIVE_GAMES.write().await;
+    let mut games = ctx.data().games.number_guess.write().await;
 
     if let Some(game) = games.get_mut(&user_id) {
         let result = game.make_guess(number);
@@ -210,7 +285,7 @@ pub async fn guess(
 pub async fn hint(ctx: Context<'_>) -> Result<(), Error> {
     let user_id = ctx.author().id.get();
 
-    let games = ACTIVE_GAMES.read().await;
+    let games = ctx.data().games.number_guess.read().await;
 
     if let Some(game) = games.get(&user_id) {
         let range_size = game.max - game.min + 1;
@@ -234,36 +309,24 @@ pub async fn hint(ctx: Context<'_>) -> Result<(), Error> {
     Ok(())
 }
 
-/// End your current number guessing game
+/// Show your current number guessing game status
 #[poise::command(prefix_command, slash_command)]
-pub async fn endgame(ctx: Context<'_>) -> Result<(), Error> {
-    let user_id = ctx.author().id.get();
-
-    let mut games = ACTIVE_GAMES.write().await;
-
-    if let Some(game) = games.remove(&user_id) {
-        let response = format!(
-            "🏳️ **Game ended!**\n\
-            The number was **{}**\n\
-            You made **{}** attempts.\n\
-            Thanks for playing! 👋",
-            game.secret_number, game.attempts
-        );
-        ctx.say(response).await?;
-    } else {
-        ctx.say("❌ You don't have an active number guessing game!")
-            .await?;
+pub async fn gamestatus(
+    ctx: Context<'_>,
+    #[description = "Use 'all' for an overview of every active game (protected)"] scope: Option<
+        String,
+    >,
+) -> Result<(), Error> {
+    if scope
+        .as_deref()
+        .is_some_and(|s| s.trim().eq_ignore_ascii_case("all"))
+    {
+        return show_all_games(ctx).await;
     }
 
-    Ok(())
-}
-
-/// Show your current number guessing game status
-#[poise::command(prefix_command, slash_command)]
-pub async fn gamestatus(ctx: Context<'_>) -> Result<(), Error> {
     let user_id = ctx.author().id.get();
 
-    let games = ACTIVE_GAMES.read().await;
+    let games = ctx.data().games.number_guess.read().await;
 
     if let Some(game) = games.get(&user_id) {
         let response = format!(
@@ -282,6 +345,54 @@ pub async fn gamestatus(ctx: Context<'_>) -> Result<(), Error> {
     Ok(())
 }
 
+/// Admin overview of active games across every game module
+async fn show_all_games(ctx: Context<'_>) -> Result<(), Error> {
+    if !is_protected_user(&ctx.author().name) {
+        ctx.say("❌ You don't have permission to use this command!")
+            .await?;
+        return Ok(());
+    }
+
+    log::info!("Game overview requested by {}", ctx.author().name);
+
+    let games = &ctx.data().games;
+    let summaries = [
+        active_games_summary(&games.number_guess).await,
+        crate::commands::tictactoe::active_games_summary(&games.tictactoe).await,
+        crate::commands::hangman::active_games_summary(&games.hangman).await,
+        crate::commands::general::avatar_games_summary().await,
+    ];
+
+    ctx.say(format_games_overview(&summaries)).await?;
+    Ok(())
+}
+
+/// Maximum number of per-game detail lines shown in the overview
+const OVERVIEW_DETAIL_LIMIT: usize = 10;
+
+fn format_games_overview(summaries: &[GameSummary]) -> String {
+    let total: usize = summaries.iter().map(|s| s.active).sum();
+    let mut response = format!("🎮 **Active games: {}**\n", total);
+
+    for summary in summaries {
+        response.push_str(&format!(
+            "\n**{}** — {} active\n",
+            summary.name, summary.active
+        ));
+        for line in summary.details.iter().take(OVERVIEW_DETAIL_LIMIT) {
+            response.push_str(&format!("• {}\n", line));
+        }
+        if summary.details.len() > OVERVIEW_DETAIL_LIMIT {
+            response.push_str(&format!(
+                "…and {} more\n",
+                summary.details.len() - OVERVIEW_DETAIL_LIMIT
+            ));
+        }
+    }
+
+    response
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -315,4 +426,56 @@ mod tests {
         let result = game.make_guess(25);
         assert!(matches!(result, GuessResult::OutOfRange));
     }
+
+    #[test]
+    fn test_remove_idle_games() {
+        use crate::commands::general::GAME_IDLE_TIMEOUT;
+        use std::time::Duration;
+
+        let mut games = HashMap::new();
+        games.insert(1, NumberGame::new(1, 100));
+        let mut active = NumberGame::new(1, 100);
+        active.last_activity += Duration::from_secs(10 * 60);
+        games.insert(2, active);
+
+        let later = Instant::now() + GAME_IDLE_TIMEOUT;
+        assert_eq!(remove_idle_games(&mut games, later), 1);
+        assert!(!games.contains_key(&1));
+        assert!(games.contains_key(&2));
+    }
+
+    #[test]
+    fn test_format_games_overview() {
+        let summaries = [
+            GameSummary {
+                name: "Number Guess",
+                active: 1,
+                details: vec!["<@1> — range 1-100, 3 attempts".to_string()],
+            },
+            GameSummary {
+                name: "Hangman",
+                active: 0,
+                details: vec![],
+            },
+        ];
+
+        let overview = format_games_overview(&summaries);
+        assert!(overview.contains("Active games: 1"));
+        assert!(overview.contains("**Number Guess** — 1 active"));
+        assert!(overview.contains("**Hangman** — 0 active"));
+        assert!(overview.contains("range 1-100"));
+    }
+
+    #[test]
+    fn test_format_games_overview_truncates_details() {
+        let summaries = [GameSummary {
+            name: "Number Guess",
+            active: 15,
+            details: (0..15).map(|i| format!("game {i}")).collect(),
+        }];
+
+        let overview = format_games_overview(&summaries);
+        assert!(overview.contains("…and 5 more"));
+        assert!(!overview.contains("game 14"));
+    }
 }
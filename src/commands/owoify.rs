@@ -0,0 +1,18 @@
+use crate::utils::transforms::{owoify as owoify_text, run_transform};
+use crate::{Context, Error};
+
+/// Sprinkle emoticons into text without changing the words, or reply to a message to owoify it
+#[poise::command(prefix_command, slash_command)]
+pub async fn owoify(
+    ctx: Context<'_>,
+    #[description = "Text to owoify (leave empty to owoify replied message)"]
+    #[rest]
+    text: Option<String>,
+) -> Result<(), Error> {
+    log::info!("Owoify command called by {}", ctx.author().name);
+
+    run_transform(ctx, text, "owoify", |text| {
+        owoify_text(text, &mut rand::thread_rng())
+    })
+    .await
+}
@@ -1,10 +1,15 @@
+use crate::commands::maintenance::Maintenance;
+use crate::dm_delivery::send_dm_text;
+use crate::storage;
+use crate::utils::check_store_writable;
+use crate::utils::schedule::local_time_on;
 use crate::{Context, Error};
 use aes_gcm::{
     aead::{Aead, AeadCore, KeyInit, OsRng},
     Aes256Gcm, Nonce,
 };
 use base64::{engine::general_purpose, Engine as _};
-use chrono::{DateTime, Datelike, Duration, TimeZone, Utc, Weekday};
+use chrono::{DateTime, Datelike, Duration, NaiveDate, Utc, Weekday};
 use chrono_tz::Europe::Copenhagen;
 use governor::{
     clock::DefaultClock,
@@ -41,12 +46,20 @@ struct ParkingSchedule {
     enabled: bool,
     last_parked: Option<DateTime<Utc>>,
     missed_requests: Vec<DateTime<Utc>>,
+    /// Danish date the schedule last fired on, so it fires at most once a day.
+    #[serde(default)]
+    last_fired: Option<NaiveDate>,
 }
 
 #[derive(Serialize, Deserialize, Default)]
 struct ParkingData {
     users: HashMap<u64, UserParkingInfo>,
     schedules: HashMap<u64, ParkingSchedule>,
+    /// Danish date a scheduled parking request was last sent for each user.
+    /// Written before the request goes out, so neither a crash in the middle
+    /// nor a second instance can send another one that day.
+    #[serde(default)]
+    scheduled_attempts: HashMap<u64, NaiveDate>,
     #[serde(skip)]
     encryption_key: Option<Vec<u8>>,
 }
@@ -83,6 +96,28 @@ fn load_or_create_encryption_key() -> Result<Vec<u8>, Error> {
     }
 }
 
+/// Whether the encryption key can be read, for `-selftest`. A missing key is
+/// fine: it's created the first time parking data is loaded.
+pub fn encryption_key_status() -> Result<String, String> {
+    key_file_status(Path::new(ENCRYPTION_KEY_FILE))
+}
+
+fn key_file_status(path: &Path) -> Result<String, String> {
+    match fs::read(path) {
+        Ok(key) if key.len() == 32 => Ok(format!("{} is readable", path.display())),
+        Ok(key) => Err(format!(
+            "{} holds {} bytes instead of 32",
+            path.display(),
+            key.len()
+        )),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(format!(
+            "{} doesn't exist yet; it's created on first use",
+            path.display()
+        )),
+        Err(e) => Err(format!("can't read {}: {}", path.display(), e)),
+    }
+}
+
 fn encrypt_data(
     data: &str,
     key: &[u8],
@@ -141,12 +176,110 @@ fn is_weekday(datetime: &DateTime<chrono_tz::Tz>) -> bool {
     )
 }
 
-fn is_schedule_time_match(
-    schedule_time: &DateTime<chrono_tz::Tz>,
-    current_time: &DateTime<chrono_tz::Tz>,
+/// A schedule's Danish time on `date`.
+fn scheduled_time_on(date: NaiveDate, hour: u8, minute: u8) -> Option<DateTime<chrono_tz::Tz>> {
+    local_time_on(&Copenhagen, date, hour, minute)
+}
+
+/// Whether a schedule should fire: its time today has passed and it hasn't
+/// fired or parked yet today. Comparing against "has passed" instead of "is
+/// now" means a delayed tick parks a little late instead of skipping the day.
+fn is_schedule_due(schedule: &ParkingSchedule, now: &DateTime<chrono_tz::Tz>) -> bool {
+    let today = now.date_naive();
+    if schedule.last_fired == Some(today) {
+        return false;
+    }
+    if schedule
+        .last_parked
+        .is_some_and(|parked| parked.with_timezone(&Copenhagen).date_naive() == today)
+    {
+        return false;
+    }
+    scheduled_time_on(today, schedule.hour, schedule.minute).is_some_and(|target| target <= *now)
+}
+
+/// Note down a scheduled request for `user_id` on `date`. Returns false if
+/// one was already sent that day.
+fn mark_scheduled_attempt(
+    attempts: &mut HashMap<u64, NaiveDate>,
+    user_id: u64,
+    date: NaiveDate,
 ) -> bool {
-    let time_diff = (*current_time - *schedule_time).num_seconds().abs();
-    time_diff <= 1800 // Within 30 minutes (1800 seconds)
+    if attempts.get(&user_id) == Some(&date) {
+        return false;
+    }
+    attempts.insert(user_id, date);
+    true
+}
+
+/// Claim today's scheduled request for `user_id` and save the claim before
+/// anything is sent. `Ok(false)` if it was already sent today; an error if
+/// the claim couldn't be saved, in which case nothing should be sent either.
+fn claim_scheduled_attempt(user_id: u64, date: NaiveDate) -> Result<bool, Error> {
+    if !mark_scheduled_attempt(&mut PARKING_DATA.write().scheduled_attempts, user_id, date) {
+        return Ok(false);
+    }
+    if let Err(e) = save_parking_data() {
+        unmark_scheduled_attempt(&mut PARKING_DATA.write().scheduled_attempts, user_id, date);
+        return Err(e);
+    }
+    Ok(true)
+}
+
+/// Forget the scheduled request for `user_id` on `date`. Returns false if
+/// there was no such claim.
+fn unmark_scheduled_attempt(
+    attempts: &mut HashMap<u64, NaiveDate>,
+    user_id: u64,
+    date: NaiveDate,
+) -> bool {
+    if attempts.get(&user_id) != Some(&date) {
+        return false;
+    }
+    attempts.remove(&user_id);
+    true
+}
+
+/// Give back a claim after the request failed, so the missed-request
+/// recovery can try again. Timeouts keep the claim: the request may have
+/// gone through without us hearing back.
+fn release_scheduled_attempt(
+    user_id: u64,
+    date: NaiveDate,
+    error: &(dyn std::error::Error + Send + Sync + 'static),
+) {
+    if error
+        .downcast_ref::<reqwest::Error>()
+        .is_some_and(|e| e.is_timeout())
+    {
+        return;
+    }
+    if unmark_scheduled_attempt(&mut PARKING_DATA.write().scheduled_attempts, user_id, date) {
+        if let Err(e) = save_parking_data() {
+            log::error!(
+                "Failed to release parking claim for user {}: {}",
+                user_id,
+                e
+            );
+        }
+    }
+}
+
+/// Record a successful request: clear the missed request it came from and
+/// save before the user is told about it.
+fn record_parked(user_id: u64, parked_at: DateTime<Utc>, request_time: DateTime<Utc>) {
+    {
+        let mut data = PARKING_DATA.write();
+        if let Some(schedule) = data.schedules.get_mut(&user_id) {
+            schedule.last_parked = Some(parked_at);
+            schedule
+                .missed_requests
+                .retain(|&req_time| req_time != request_time);
+        }
+    }
+    if let Err(e) = save_parking_data() {
+        log::error!("Failed to save parking data: {}", e);
+    }
 }
 
 // Rate limiting
@@ -183,40 +316,32 @@ fn load_parking_data() -> Result<(), Error> {
 
     let encryption_key = load_or_create_encryption_key()?;
 
-    let mut data = if Path::new(PARKING_DATA_FILE).exists() {
-        match fs::read_to_string(PARKING_DATA_FILE) {
-            Ok(content) => {
-                match serde_json::from_str::<ParkingData>(&content) {
-                    Ok(mut parsed_data) => {
-                        // Decrypt user data
-                        for user_info in parsed_data.users.values_mut() {
-                            if let Ok(decrypted_phone) =
-                                decrypt_data(&user_info.phone_number, &encryption_key)
-                            {
-                                user_info.phone_number = decrypted_phone;
-                            }
-                            if let Ok(decrypted_plate) =
-                                decrypt_data(&user_info.plate, &encryption_key)
-                            {
-                                user_info.plate = decrypted_plate;
-                            }
-                        }
-                        parsed_data
-                    }
-                    Err(e) => {
-                        log::warn!("Failed to parse parking data: {}, starting fresh", e);
-                        ParkingData::default()
-                    }
-                }
-            }
-            Err(e) => {
-                log::warn!("Failed to read parking data: {}, starting fresh", e);
-                ParkingData::default()
-            }
+    install_parking_data(
+        storage::load_from(Path::new(PARKING_DATA_FILE)),
+        encryption_key,
+    );
+    Ok(())
+}
+
+/// Re-read the parking data from disk, e.g. after editing a schedule by
+/// hand. On a parse error the data in memory is kept.
+pub fn reload_parking_data() -> Result<(), Error> {
+    let data = storage::reload_from(Path::new(PARKING_DATA_FILE))?;
+    install_parking_data(data, load_or_create_encryption_key()?);
+    Ok(())
+}
+
+/// Decrypt freshly loaded parking data and make it the data in memory.
+fn install_parking_data(mut data: ParkingData, encryption_key: Vec<u8>) {
+    // Decrypt user data
+    for user_info in data.users.values_mut() {
+        if let Ok(decrypted_phone) = decrypt_data(&user_info.phone_number, &encryption_key) {
+            user_info.phone_number = decrypted_phone;
         }
-    } else {
-        ParkingData::default()
-    };
+        if let Ok(decrypted_plate) = decrypt_data(&user_info.plate, &encryption_key) {
+            user_info.plate = decrypted_plate;
+        }
+    }
 
     data.encryption_key = Some(encryption_key);
 
@@ -227,7 +352,6 @@ fn load_parking_data() -> Result<(), Error> {
     }
 
     *PARKING_DATA.write() = data;
-    Ok(())
 }
 
 fn save_parking_data() -> Result<(), Error> {
@@ -243,6 +367,7 @@ fn save_parking_data() -> Result<(), Error> {
     let mut save_data = ParkingData {
         users: HashMap::new(),
         schedules: data.schedules.clone(),
+        scheduled_attempts: data.scheduled_attempts.clone(),
         encryption_key: None,
     };
 
@@ -262,8 +387,7 @@ fn save_parking_data() -> Result<(), Error> {
         );
     }
 
-    let json = serde_json::to_string_pretty(&save_data)?;
-    fs::write(PARKING_DATA_FILE, json)?;
+    storage::save_to(Path::new(PARKING_DATA_FILE), &save_data)?;
 
     // Set secure file permissions
     #[cfg(unix)]
@@ -277,6 +401,11 @@ fn save_parking_data() -> Result<(), Error> {
     Ok(())
 }
 
+/// Command check for commands that change the parking data.
+async fn parking_writable(ctx: Context<'_>) -> Result<bool, Error> {
+    check_store_writable(ctx, Path::new(PARKING_DATA_FILE)).await
+}
+
 // Utility functions
 fn cleanup_old_missed_requests(requests: &mut Vec<DateTime<Utc>>, now: DateTime<Utc>) {
     let today = now.date_naive();
@@ -574,7 +703,12 @@ pub async fn park_info(ctx: Context<'_>) -> Result<(), Error> {
 }
 
 /// Clear your saved parking information
-#[poise::command(prefix_command, slash_command, rename = "clear")]
+#[poise::command(
+    prefix_command,
+    slash_command,
+    rename = "clear",
+    check = "parking_writable"
+)]
 pub async fn park_clear(ctx: Context<'_>) -> Result<(), Error> {
     let user_id = ctx.author().id.get();
 
@@ -621,7 +755,12 @@ pub async fn park_schedule(_ctx: Context<'_>) -> Result<(), Error> {
 }
 
 /// Set automatic parking schedule for weekdays
-#[poise::command(prefix_command, slash_command, rename = "set")]
+#[poise::command(
+    prefix_command,
+    slash_command,
+    rename = "set",
+    check = "parking_writable"
+)]
 pub async fn schedule_set(
     ctx: Context<'_>,
     #[description = "Hour (0-23)"] hour: u8,
@@ -654,6 +793,13 @@ pub async fn schedule_set(
         if !data.users.contains_key(&user_id) {
             false
         } else {
+            // A time that already passed today starts tomorrow instead of firing right away
+            let now = Utc::now().with_timezone(&Copenhagen);
+            let today = now.date_naive();
+            let last_fired = scheduled_time_on(today, hour, minute)
+                .filter(|target| *target <= now)
+                .map(|_| today);
+
             let schedule = ParkingSchedule {
                 user_id,
                 hour,
@@ -661,6 +807,7 @@ pub async fn schedule_set(
                 enabled: true,
                 last_parked: None,
                 missed_requests: Vec::new(),
+                last_fired,
             };
 
             data.schedules.insert(user_id, schedule);
@@ -760,7 +907,12 @@ pub async fn schedule_status(ctx: Context<'_>) -> Result<(), Error> {
 }
 
 /// Disable your automatic parking schedule
-#[poise::command(prefix_command, slash_command, rename = "disable")]
+#[poise::command(
+    prefix_command,
+    slash_command,
+    rename = "disable",
+    check = "parking_writable"
+)]
 pub async fn schedule_disable(ctx: Context<'_>) -> Result<(), Error> {
     let user_id = ctx.author().id.get();
 
@@ -819,7 +971,7 @@ pub async fn schedule_disable(ctx: Context<'_>) -> Result<(), Error> {
     Ok(())
 }
 
-pub fn start_parking_scheduler(http: Arc<Http>) {
+pub fn start_parking_scheduler(http: Arc<Http>, maintenance: Arc<Maintenance>) {
     tokio::spawn(async move {
         // Initialize parking data
         if let Err(e) = load_parking_data() {
@@ -837,6 +989,11 @@ pub fn start_parking_scheduler(http: Arc<Http>) {
         loop {
             interval.tick().await;
 
+            // Keep ticking during maintenance so we resume right away
+            if maintenance.is_enabled() {
+                continue;
+            }
+
             if let Err(e) = check_and_execute_parking(&http).await {
                 log::error!("Error in parking scheduler: {e}");
             }
@@ -857,79 +1014,64 @@ async fn check_and_execute_parking(http: &Http) -> Result<(), Error> {
         return Ok(());
     }
 
-    let schedules_to_process: Vec<(u64, ParkingSchedule, UserParkingInfo)> = {
-        let data = PARKING_DATA.read();
+    let today = now.date_naive();
 
-        data.schedules
-            .iter()
+    // Mark due schedules as fired before doing anything slow, so a failure
+    // isn't retried (and DMed about) every minute for the rest of the day
+    let schedules_to_process: Vec<(u64, ParkingSchedule, UserParkingInfo, DateTime<Utc>)> = {
+        let mut data = PARKING_DATA.write();
+        let ParkingData {
+            users, schedules, ..
+        } = &mut *data;
+
+        schedules
+            .iter_mut()
+            .filter(|(_, schedule)| schedule.enabled && is_schedule_due(schedule, &now))
             .filter_map(|(user_id, schedule)| {
-                if !schedule.enabled {
-                    return None;
-                }
+                let user_info = users.get(user_id)?;
+                let target_time =
+                    scheduled_time_on(today, schedule.hour, schedule.minute)?.with_timezone(&Utc);
 
-                // Check if it's time to park (using Danish time)
-                let target_time = Copenhagen
-                    .with_ymd_and_hms(
-                        now.year(),
-                        now.month(),
-                        now.day(),
-                        schedule.hour as u32,
-                        schedule.minute as u32,
-                        0,
-                    )
-                    .single()?;
-
-                // Check if we should park now (within 30 seconds)
-                if !is_schedule_time_match(&target_time, &now) {
-                    return None;
-                }
+                schedule.last_fired = Some(today);
+                // Recorded in case the bot shuts down before the request goes through
+                schedule.missed_requests.push(target_time);
 
-                // Check if we already parked today
-                if let Some(last_parked) = schedule.last_parked {
-                    if last_parked.date_naive() == now_utc.date_naive() {
-                        return None; // Already parked today
-                    }
-                }
-
-                // Get user info
-                data.users
-                    .get(user_id)
-                    .map(|user_info| (*user_id, schedule.clone(), user_info.clone()))
+                Some((*user_id, schedule.clone(), user_info.clone(), target_time))
             })
             .collect()
     };
 
-    for (user_id, mut schedule, user_info) in schedules_to_process {
-        // Add to missed requests (in case bot shuts down before execution)
-        let target_time = Copenhagen
-            .with_ymd_and_hms(
-                now.year(),
-                now.month(),
-                now.day(),
-                schedule.hour as u32,
-                schedule.minute as u32,
-                0,
-            )
-            .single()
-            .unwrap();
+    if schedules_to_process.is_empty() {
+        return Ok(());
+    }
+    if let Err(e) = save_parking_data() {
+        log::error!("Failed to save parking data: {}", e);
+    }
 
-        schedule
-            .missed_requests
-            .push(target_time.with_timezone(&Utc));
+    for (user_id, schedule, user_info, target_time) in schedules_to_process {
+        let result = match claim_scheduled_attempt(user_id, today) {
+            Ok(true) => {
+                let result =
+                    execute_parking_request(&user_info.plate, &user_info.phone_number).await;
+                if let Err(e) = &result {
+                    release_scheduled_attempt(user_id, today, e.as_ref());
+                }
+                result
+            }
+            Ok(false) => {
+                log::warn!(
+                    "Scheduled parking for user {} was already sent today, not sending it again",
+                    user_id
+                );
+                continue;
+            }
+            Err(e) => Err(format!("couldn't save the attempt, so nothing was sent: {}", e).into()),
+        };
 
         // Execute parking request
-        match execute_parking_request(&user_info.plate, &user_info.phone_number).await {
+        match result {
             Ok(_) => {
-                // Update last parked time and remove from missed requests
-                {
-                    let mut data = PARKING_DATA.write();
-                    if let Some(schedule) = data.schedules.get_mut(&user_id) {
-                        schedule.last_parked = Some(now_utc);
-                        schedule
-                            .missed_requests
-                            .retain(|&req_time| req_time != target_time.with_timezone(&Utc));
-                    }
-                }
+                record_parked(user_id, now_utc, target_time);
 
                 // Send success DM
                 let message = format!(
@@ -938,7 +1080,8 @@ async fn check_and_execute_parking(http: &Http) -> Result<(), Error> {
                     user_info.phone_number
                 );
 
-                if let Err(e) = send_dm_to_user(http, UserId::new(user_id), &message).await {
+                if let Err(e) = send_dm_text(http, UserId::new(user_id), "parking", &message).await
+                {
                     log::error!("Failed to send success DM to user {}: {}", user_id, e);
                 }
 
@@ -956,7 +1099,9 @@ async fn check_and_execute_parking(http: &Http) -> Result<(), Error> {
                     e
                 );
 
-                if let Err(dm_err) = send_dm_to_user(http, UserId::new(user_id), &message).await {
+                if let Err(dm_err) =
+                    send_dm_text(http, UserId::new(user_id), "parking", &message).await
+                {
                     log::error!("Failed to send failure DM to user {}: {}", user_id, dm_err);
                 }
 
@@ -1020,19 +1165,48 @@ async fn process_missed_parking_requests(http: &Http) -> Result<(), Error> {
             missed_time
         );
 
-        // Execute the missed parking request
-        match execute_parking_request(&user_info.plate, &user_info.phone_number).await {
-            Ok(_) => {
-                // Update last parked time and remove processed request
+        let date = missed_time.with_timezone(&Copenhagen).date_naive();
+        let result = match claim_scheduled_attempt(user_id, date) {
+            Ok(true) => {
+                let result =
+                    execute_parking_request(&user_info.plate, &user_info.phone_number).await;
+                if let Err(e) = &result {
+                    release_scheduled_attempt(user_id, date, e.as_ref());
+                }
+                result
+            }
+            Ok(false) => {
+                // It was sent before the restart, it just wasn't confirmed
+                log::warn!(
+                    "Parking for user {} was already sent before the restart, not resending it",
+                    user_id
+                );
+                if let Some(schedule) = PARKING_DATA.write().schedules.get_mut(&user_id) {
+                    schedule
+                        .missed_requests
+                        .retain(|&req_time| req_time != missed_time);
+                }
+                let message = format!(
+                    "⚠️ **Parking request not confirmed**\nYour parking scheduled for <t:{}:t> was sent just before the bot restarted, so it wasn't sent again.\n\n📱 **Check your SMS** for the confirmation, and use `/park now` if there isn't one.",
+                    missed_time.timestamp()
+                );
+                if let Err(e) = send_dm_text(http, UserId::new(user_id), "parking", &message).await
                 {
-                    let mut data = PARKING_DATA.write();
-                    if let Some(schedule) = data.schedules.get_mut(&user_id) {
-                        schedule.last_parked = Some(now);
-                        schedule
-                            .missed_requests
-                            .retain(|&req_time| req_time != missed_time);
-                    }
+                    log::error!(
+                        "Failed to send unconfirmed parking DM to user {}: {}",
+                        user_id,
+                        e
+                    );
                 }
+                continue;
+            }
+            Err(e) => Err(format!("couldn't save the attempt, so nothing was sent: {}", e).into()),
+        };
+
+        // Execute the missed parking request
+        match result {
+            Ok(_) => {
+                record_parked(user_id, now, missed_time);
 
                 // Send success DM with note about recovery
                 let message = format!(
@@ -1042,7 +1216,8 @@ async fn process_missed_parking_requests(http: &Http) -> Result<(), Error> {
                     missed_time.timestamp()
                 );
 
-                if let Err(e) = send_dm_to_user(http, UserId::new(user_id), &message).await {
+                if let Err(e) = send_dm_text(http, UserId::new(user_id), "parking", &message).await
+                {
                     log::error!(
                         "Failed to send recovery success DM to user {}: {}",
                         user_id,
@@ -1069,7 +1244,9 @@ async fn process_missed_parking_requests(http: &Http) -> Result<(), Error> {
                     missed_time.timestamp()
                 );
 
-                if let Err(dm_err) = send_dm_to_user(http, UserId::new(user_id), &message).await {
+                if let Err(dm_err) =
+                    send_dm_text(http, UserId::new(user_id), "parking", &message).await
+                {
                     log::error!(
                         "Failed to send recovery failure DM to user {}: {}",
                         user_id,
@@ -1136,7 +1313,7 @@ async fn check_parking_expiry(http: &Http) -> Result<(), Error> {
             expiry_time_danish.format("%H:%M Danish time")
         );
 
-        if let Err(e) = send_dm_to_user(http, UserId::new(user_id), &message).await {
+        if let Err(e) = send_dm_text(http, UserId::new(user_id), "parking", &message).await {
             log::error!(
                 "Failed to send expiry warning DM to user {}: {}",
                 user_id,
@@ -1170,22 +1347,34 @@ async fn execute_parking_request(
     Ok(())
 }
 
-async fn send_dm_to_user(
-    http: &Http,
-    user_id: UserId,
-    message: &str,
-) -> Result<(), serenity::Error> {
-    let user = user_id.to_user(http).await?;
-    let dm_channel = user.create_dm_channel(http).await?;
-    dm_channel.say(http, message).await?;
-    Ok(())
-}
-
 #[cfg(test)]
 mod tests {
     use super::*;
     use chrono::{TimeZone, Utc};
 
+    #[test]
+    fn test_scheduled_attempt_once_per_day() {
+        let mut attempts = HashMap::new();
+        let day = NaiveDate::from_ymd_opt(2026, 10, 16).unwrap();
+        assert!(mark_scheduled_attempt(&mut attempts, 1, day));
+        assert!(!mark_scheduled_attempt(&mut attempts, 1, day));
+        assert!(mark_scheduled_attempt(&mut attempts, 2, day));
+        assert!(mark_scheduled_attempt(
+            &mut attempts,
+            1,
+            day.succ_opt().unwrap()
+        ));
+
+        // A released claim can be taken again, but only that day's
+        assert!(!unmark_scheduled_attempt(&mut attempts, 1, day));
+        assert!(unmark_scheduled_attempt(
+            &mut attempts,
+            1,
+            day.succ_opt().unwrap()
+        ));
+        assert!(mark_scheduled_attempt(&mut attempts, 1, day));
+    }
+
     #[test]
     fn test_validate_danish_phone_number() {
         // Valid Danish phone numbers (8 digits)
@@ -1201,6 +1390,19 @@ mod tests {
         assert!(!validate_danish_phone_number("")); // Empty
     }
 
+    #[test]
+    fn test_key_file_status() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("parking_key");
+        assert!(key_file_status(&path)
+            .unwrap()
+            .contains("doesn't exist yet"));
+        fs::write(&path, [7u8; 32]).unwrap();
+        assert!(key_file_status(&path).is_ok());
+        fs::write(&path, [7u8; 5]).unwrap();
+        assert!(key_file_status(&path).unwrap_err().contains("5 bytes"));
+    }
+
     #[test]
     fn test_validate_danish_license_plate() {
         // Valid Danish license plates
@@ -1257,6 +1459,7 @@ mod tests {
             enabled: true,
             last_parked: Some(Utc::now()),
             missed_requests: vec![],
+            last_fired: None,
         };
 
         data.users.insert(123456789, user_info);
@@ -1282,21 +1485,95 @@ mod tests {
         assert!(!is_weekday(&sunday.with_timezone(&Copenhagen)));
     }
 
+    fn schedule_at(hour: u8, minute: u8) -> ParkingSchedule {
+        ParkingSchedule {
+            user_id: 1,
+            hour,
+            minute,
+            enabled: true,
+            last_parked: None,
+            missed_requests: vec![],
+            last_fired: None,
+        }
+    }
+
     #[test]
-    fn test_schedule_time_matching() {
-        let schedule_time = Copenhagen.with_ymd_and_hms(2024, 1, 1, 8, 30, 0).unwrap();
+    fn test_schedule_due_exactly_on_time() {
+        let schedule = schedule_at(8, 30);
 
-        // Exact match
-        let current_time = Copenhagen.with_ymd_and_hms(2024, 1, 1, 8, 30, 0).unwrap();
-        assert!(is_schedule_time_match(&schedule_time, &current_time));
+        let now = Copenhagen.with_ymd_and_hms(2024, 1, 1, 8, 30, 0).unwrap();
+        assert!(is_schedule_due(&schedule, &now));
 
-        // Within 30 seconds (should match)
-        let current_time = Copenhagen.with_ymd_and_hms(2024, 1, 1, 8, 30, 25).unwrap();
-        assert!(is_schedule_time_match(&schedule_time, &current_time));
+        let now = Copenhagen.with_ymd_and_hms(2024, 1, 1, 8, 29, 59).unwrap();
+        assert!(!is_schedule_due(&schedule, &now));
+    }
+
+    #[test]
+    fn test_schedule_due_after_delayed_tick() {
+        let mut schedule = schedule_at(8, 30);
+
+        // A tick that comes several minutes late still fires
+        let now = Copenhagen.with_ymd_and_hms(2024, 1, 1, 8, 37, 12).unwrap();
+        assert!(is_schedule_due(&schedule, &now));
+
+        // ...but only once
+        schedule.last_fired = Some(now.date_naive());
+        let later = Copenhagen.with_ymd_and_hms(2024, 1, 1, 8, 38, 12).unwrap();
+        assert!(!is_schedule_due(&schedule, &later));
+
+        // Parking manually earlier in the day also counts
+        let mut schedule = schedule_at(8, 30);
+        schedule.last_parked = Some(Utc.with_ymd_and_hms(2024, 1, 1, 6, 0, 0).unwrap());
+        assert!(!is_schedule_due(&schedule, &now));
+    }
+
+    #[test]
+    fn test_schedule_resets_at_danish_midnight() {
+        let mut schedule = schedule_at(0, 0);
+        schedule.last_fired = Some(NaiveDate::from_ymd_opt(2024, 1, 1).unwrap());
+
+        // 23:30 UTC is already 00:30 the next day in Copenhagen (UTC+1)
+        let now = Utc
+            .with_ymd_and_hms(2024, 1, 1, 23, 30, 0)
+            .unwrap()
+            .with_timezone(&Copenhagen);
+        assert!(is_schedule_due(&schedule, &now));
+
+        // A parking at 23:50 Danish time belongs to the previous day
+        let mut schedule = schedule_at(0, 0);
+        schedule.last_parked = Some(Utc.with_ymd_and_hms(2024, 1, 1, 22, 50, 0).unwrap());
+        assert!(is_schedule_due(&schedule, &now));
+    }
+
+    #[test]
+    fn test_schedule_on_dst_change_days() {
+        // Spring forward: 02:00 jumps to 03:00, so 02:30 fires at 03:30
+        let spring = NaiveDate::from_ymd_opt(2024, 3, 31).unwrap();
+        let target = scheduled_time_on(spring, 2, 30).unwrap();
+        assert_eq!(
+            target,
+            Copenhagen.with_ymd_and_hms(2024, 3, 31, 3, 30, 0).unwrap()
+        );
 
-        // More than 30 minutes off (should not match)
-        let current_time = Copenhagen.with_ymd_and_hms(2024, 1, 1, 9, 1, 0).unwrap();
-        assert!(!is_schedule_time_match(&schedule_time, &current_time));
+        let schedule = schedule_at(2, 30);
+        let before = Copenhagen.with_ymd_and_hms(2024, 3, 31, 3, 29, 0).unwrap();
+        let after = Copenhagen.with_ymd_and_hms(2024, 3, 31, 3, 31, 0).unwrap();
+        assert!(!is_schedule_due(&schedule, &before));
+        assert!(is_schedule_due(&schedule, &after));
+
+        // Fall back: 02:30 happens twice; the first one fires, the repeat doesn't
+        let mut schedule = schedule_at(2, 30);
+        let first = Utc
+            .with_ymd_and_hms(2024, 10, 27, 0, 30, 0)
+            .unwrap()
+            .with_timezone(&Copenhagen);
+        assert!(is_schedule_due(&schedule, &first));
+        schedule.last_fired = Some(first.date_naive());
+        let repeat = Utc
+            .with_ymd_and_hms(2024, 10, 27, 1, 30, 0)
+            .unwrap()
+            .with_timezone(&Copenhagen);
+        assert!(!is_schedule_due(&schedule, &repeat));
     }
 
     #[test]
@@ -1437,6 +1714,7 @@ mod tests {
                 encrypted_users
             },
             schedules: data.schedules.clone(),
+            scheduled_attempts: HashMap::new(),
             encryption_key: None, // Not saved to JSON
         };
 
@@ -1515,6 +1793,7 @@ mod tests {
         let mut save_data = ParkingData {
             users: HashMap::new(),
             schedules: data.schedules.clone(),
+            scheduled_attempts: HashMap::new(),
             encryption_key: None,
         };
 
@@ -1644,6 +1923,7 @@ mod tests {
         let mut save_data = ParkingData {
             users: HashMap::new(),
             schedules: data.schedules.clone(),
+            scheduled_attempts: HashMap::new(),
             encryption_key: None,
         };
 
@@ -1,4 +1,98 @@
 use crate::{Context, Error};
+use chrono::{DateTime, Utc};
+use parking_lot::Mutex;
+use poise::serenity_prelude as serenity;
+use std::collections::VecDeque;
+use std::sync::Arc;
+use std::time::Instant;
+use tokio::time::{interval, Duration as TokioDuration};
+
+/// How many latency samples are kept; older ones are dropped.
+const MAX_SAMPLES: usize = 60;
+
+/// How often the background probe measures latency when nobody runs `ping`.
+const PROBE_INTERVAL_SECS: u64 = 300;
+
+/// Levels of the history chart, lowest first.
+const SPARKS: [char; 8] = ['▁', '▂', '▃', '▄', '▅', '▆', '▇', '█'];
+
+/// One `get_current_user` call to the Discord API, timed. Only that call is
+/// sampled, so the history compares like with like; `ping`'s message round
+/// trip is a different, slower measurement and isn't recorded.
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct LatencySample {
+    at: DateTime<Utc>,
+    millis: u64,
+}
+
+lazy_static::lazy_static! {
+    /// Recent samples, oldest first. Kept in memory only, so the history
+    /// starts over after a restart.
+    static ref SAMPLES: Mutex<VecDeque<LatencySample>> = Mutex::new(VecDeque::new());
+}
+
+/// Record how long a `get_current_user` call took. Called by `status` and
+/// the background probe.
+pub fn record_latency(millis: u64) {
+    push_sample(
+        &mut SAMPLES.lock(),
+        LatencySample {
+            at: Utc::now(),
+            millis,
+        },
+    );
+}
+
+fn push_sample(samples: &mut VecDeque<LatencySample>, sample: LatencySample) {
+    if samples.len() == MAX_SAMPLES {
+        samples.pop_front();
+    }
+    samples.push_back(sample);
+}
+
+/// Measure latency every few minutes, so the history has samples even when
+/// nobody is running `status`.
+pub fn start_latency_probe(http: Arc<serenity::Http>) {
+    tokio::spawn(async move {
+        let mut interval = interval(TokioDuration::from_secs(PROBE_INTERVAL_SECS));
+
+        loop {
+            interval.tick().await;
+
+            let start = Instant::now();
+            match http.get_current_user().await {
+                Ok(_) => record_latency(start.elapsed().as_millis() as u64),
+                Err(e) => log::warn!("Latency probe failed: {}", e),
+            }
+        }
+    });
+}
+
+/// Lowest, average and highest latency in `samples`.
+fn latency_summary(samples: &[LatencySample]) -> Option<(u64, u64, u64)> {
+    let min = samples.iter().map(|s| s.millis).min()?;
+    let max = samples.iter().map(|s| s.millis).max()?;
+    let avg = samples.iter().map(|s| s.millis).sum::<u64>() / samples.len() as u64;
+    Some((min, avg, max))
+}
+
+/// One bar per sample, scaled between the lowest and highest of them.
+fn sparkline(samples: &[LatencySample]) -> String {
+    let Some((min, _, max)) = latency_summary(samples) else {
+        return String::new();
+    };
+    let top = SPARKS.len() as u64 - 1;
+    samples
+        .iter()
+        .map(|s| {
+            let level = (s.millis - min)
+                .saturating_mul(top)
+                .checked_div(max - min)
+                .unwrap_or(0);
+            SPARKS[level as usize]
+        })
+        .collect()
+}
 
 /// A simple ping command that responds with 'Pong!'
 #[poise::command(prefix_command, slash_command)]
@@ -31,3 +125,73 @@ pub async fn ping(ctx: Context<'_>) -> Result<(), Error> {
 
     Ok(())
 }
+
+/// Show recent API latency with its lowest, average and highest
+#[poise::command(prefix_command, slash_command)]
+pub async fn pinghistory(ctx: Context<'_>) -> Result<(), Error> {
+    log::info!("Ping history command called by {}", ctx.author().name);
+
+    let samples: Vec<LatencySample> = SAMPLES.lock().iter().copied().collect();
+    let (Some((min, avg, max)), Some(first), Some(last)) =
+        (latency_summary(&samples), samples.first(), samples.last())
+    else {
+        ctx.say("🏓 No latency samples yet. Check back in a few minutes.")
+            .await?;
+        return Ok(());
+    };
+
+    let embed = serenity::CreateEmbed::new()
+        .title("🏓 Latency History")
+        .color(0x5865f2)
+        .description(format!("`{}`", sparkline(&samples)))
+        .field("Lowest", format!("{min}ms"), true)
+        .field("Average", format!("{avg}ms"), true)
+        .field("Highest", format!("{max}ms"), true)
+        .field("Latest", format!("{}ms", last.millis), true)
+        .footer(serenity::CreateEmbedFooter::new(format!(
+            "{} sample(s) since {} UTC • keeps the last {}",
+            samples.len(),
+            first.at.format("%Y-%m-%d %H:%M"),
+            MAX_SAMPLES
+        )));
+    ctx.send(poise::CreateReply::default().embed(embed)).await?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn samples(millis: &[u64]) -> Vec<LatencySample> {
+        millis
+            .iter()
+            .map(|&millis| LatencySample {
+                at: Utc::now(),
+                millis,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_buffer_is_bounded() {
+        let mut buffer = VecDeque::new();
+        for sample in samples(&(0..MAX_SAMPLES as u64 + 5).collect::<Vec<_>>()) {
+            push_sample(&mut buffer, sample);
+        }
+        assert_eq!(buffer.len(), MAX_SAMPLES);
+        // The oldest five were dropped
+        assert_eq!(buffer.front().map(|s| s.millis), Some(5));
+    }
+
+    #[test]
+    fn test_summary_and_sparkline() {
+        let history = samples(&[100, 240, 30, 170]);
+        assert_eq!(latency_summary(&history), Some((30, 135, 240)));
+        assert_eq!(sparkline(&history), "▃█▁▅");
+
+        // All the same: flat, without dividing by zero
+        assert_eq!(sparkline(&samples(&[80, 80])), "▁▁");
+        assert_eq!(latency_summary(&[]), None);
+        assert_eq!(sparkline(&[]), "");
+    }
+}
@@ -1,12 +1,17 @@
+use std::fs;
+use std::path::{Path, PathBuf};
 use std::sync::Arc;
 use std::time::Duration;
 
 use async_trait::async_trait;
 
+use crate::utils::meme::assets_dir;
+use crate::utils::permissions::{check_channel_perms, VOICE};
 use crate::{Context, Error};
 use lazy_static::lazy_static;
+use parking_lot::RwLock;
 use poise::serenity_prelude as serenity;
-use songbird::input::{Compose, YoutubeDl};
+use songbird::input::{Compose, File, Input, YoutubeDl};
 use songbird::tracks::Track;
 use songbird::{Event, EventContext, EventHandler as VoiceEventHandler, Songbird};
 
@@ -14,12 +19,190 @@ lazy_static! {
     // Shared HTTP client handed to songbird's yt-dlp source (reqwest 0.12, the
     // version songbird speaks).
     static ref HTTP_CLIENT: reqwest::Client = reqwest::Client::new();
+
+    // The soundboard clips found in `assets/sounds/` at startup, sorted by name.
+    static ref SOUNDBOARD: RwLock<Vec<Clip>> = RwLock::new(Vec::new());
 }
 
 /// How long the bot may sit in a voice channel with nothing queued before it
 /// disconnects on its own.
 const IDLE_TIMEOUT: Duration = Duration::from_secs(90);
 
+/// Idle timeout for a session started by a soundboard clip: clips are short,
+/// so the bot shouldn't linger long after one.
+const CLIP_IDLE_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Soundboard clips longer than this are skipped when the sounds are scanned.
+const MAX_CLIP_LENGTH: Duration = Duration::from_secs(15);
+
+/// File types the soundboard picks up.
+const CLIP_EXTENSIONS: &[&str] = &["ogg", "opus", "mp3", "wav"];
+
+/// A soundboard clip, played with `-play <name>`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Clip {
+    /// The file name without its extension, lowercased.
+    pub name: String,
+    pub path: PathBuf,
+    pub length: Duration,
+}
+
+/// Find the soundboard clips in `assets/sounds/` and log the ones that are
+/// too long or can't be read. Called once at startup.
+pub fn scan_sounds() {
+    let dir = assets_dir().join("sounds");
+    let clips = scan_clips(&dir);
+    log::info!(
+        "Loaded {} soundboard clip(s) from {}",
+        clips.len(),
+        dir.display()
+    );
+    *SOUNDBOARD.write() = clips;
+}
+
+/// The playable clips in `dir`, sorted by name. When two files share a
+/// name (`horn.ogg` and `horn.mp3`) only the first is kept.
+fn scan_clips(dir: &Path) -> Vec<Clip> {
+    let entries = match fs::read_dir(dir) {
+        Ok(entries) => entries,
+        Err(e) => {
+            log::warn!(
+                "No soundboard clips: couldn't read {}: {}",
+                dir.display(),
+                e
+            );
+            return Vec::new();
+        }
+    };
+
+    let mut clips = Vec::new();
+    for path in entries.flatten().map(|entry| entry.path()) {
+        let is_clip = path
+            .extension()
+            .and_then(|ext| ext.to_str())
+            .is_some_and(|ext| CLIP_EXTENSIONS.contains(&ext.to_ascii_lowercase().as_str()));
+        let name = path.file_stem().and_then(|stem| stem.to_str());
+        let (true, Some(name)) = (is_clip, name) else {
+            continue;
+        };
+        match clip_length(&path) {
+            Ok(length) if length > MAX_CLIP_LENGTH => log::warn!(
+                "Skipping sound clip {}: it's {:.1}s long, clips may be at most {}s",
+                path.display(),
+                length.as_secs_f64(),
+                MAX_CLIP_LENGTH.as_secs()
+            ),
+            Ok(length) => clips.push(Clip {
+                name: name.to_lowercase(),
+                path: path.clone(),
+                length,
+            }),
+            Err(e) => log::warn!("Skipping sound clip {}: {}", path.display(), e),
+        }
+    }
+    clips.sort_by(|a, b| a.name.cmp(&b.name).then_with(|| a.path.cmp(&b.path)));
+    clips.dedup_by(|later, earlier| later.name == earlier.name);
+    clips
+}
+
+/// How long an audio file plays for, from its header or, when the header
+/// doesn't say (e.g. MP3s without a Xing frame), by adding up its packets.
+fn clip_length(path: &Path) -> Result<Duration, Error> {
+    use symphonia::core::errors::Error as AudioError;
+    use symphonia::core::formats::FormatOptions;
+    use symphonia::core::io::MediaSourceStream;
+    use symphonia::core::meta::MetadataOptions;
+    use symphonia::core::probe::Hint;
+    use symphonia::core::units::TimeBase;
+
+    let mut hint = Hint::new();
+    if let Some(ext) = path.extension().and_then(|ext| ext.to_str()) {
+        hint.with_extension(ext);
+    }
+    let stream = MediaSourceStream::new(Box::new(fs::File::open(path)?), Default::default());
+    let mut format = symphonia::default::get_probe()
+        .format(
+            &hint,
+            stream,
+            &FormatOptions::default(),
+            &MetadataOptions::default(),
+        )?
+        .format;
+
+    let track = format.default_track().ok_or("it has no audio track")?;
+    let track_id = track.id;
+    let params = track.codec_params.clone();
+    let time_base = params
+        .time_base
+        .or_else(|| params.sample_rate.map(|rate| TimeBase::new(1, rate)))
+        .ok_or("its sample rate is unknown")?;
+
+    let frames = match params.n_frames {
+        Some(frames) => frames,
+        None => {
+            let mut frames = 0;
+            loop {
+                match format.next_packet() {
+                    Ok(packet) if packet.track_id() == track_id => frames += packet.dur,
+                    Ok(_) => {}
+                    Err(AudioError::IoError(e))
+                        if e.kind() == std::io::ErrorKind::UnexpectedEof =>
+                    {
+                        break
+                    }
+                    Err(e) => return Err(e.into()),
+                }
+            }
+            frames
+        }
+    };
+    let time = time_base.calc_time(frames);
+    Ok(Duration::from_secs(time.seconds) + Duration::from_secs_f64(time.frac))
+}
+
+/// The soundboard clip called `name`, ignoring case.
+fn find_clip(name: &str) -> Option<Clip> {
+    let name = name.trim().to_lowercase();
+    SOUNDBOARD
+        .read()
+        .iter()
+        .find(|clip| clip.name == name)
+        .cloned()
+}
+
+/// The `-sounds` listing: every clip's name and length.
+fn sounds_list(clips: &[Clip]) -> String {
+    if clips.is_empty() {
+        return "🔇 There are no sounds yet. Add `.ogg` or `.mp3` files (15 seconds at most) to \
+                `assets/sounds/` and restart the bot."
+            .to_string();
+    }
+    let mut text = format!(
+        "🔉 **Sounds** ({}) - play one with `-play <name>`\n",
+        clips.len()
+    );
+    let entries: Vec<String> = clips
+        .iter()
+        .map(|clip| format!("`{}` ({:.1}s)", clip.name, clip.length.as_secs_f64()))
+        .collect();
+    // Stay under Discord's message length however many clips there are
+    let mut shown = 0;
+    for entry in &entries {
+        if text.len() + entry.len() + 40 > 2000 {
+            break;
+        }
+        if shown > 0 {
+            text.push_str(", ");
+        }
+        text.push_str(entry);
+        shown += 1;
+    }
+    if shown < entries.len() {
+        text.push_str(&format!(" …and {} more", entries.len() - shown));
+    }
+    text
+}
+
 /// Periodic voice event that leaves the channel once the queue has run dry, so
 /// the bot doesn't idle in voice forever after the last track ends.
 struct IdleLeaver {
@@ -97,20 +280,22 @@ fn split_target(input: &str) -> (Option<(serenity::GuildId, serenity::ChannelId)
     (target, rest.join(" "))
 }
 
-/// Play a YouTube video's audio in a voice channel.
+/// Play a YouTube video's audio, or a soundboard clip, in a voice channel.
 ///
 /// In a server: `-play <youtube url | search terms>` joins the channel you're in.
 /// From a DM (or to target a specific channel): also paste a channel link, e.g.
 /// `-play <url> https://discord.com/channels/<server>/<channel>`.
+/// The name of a clip from `-sounds` plays that clip instead of searching.
 /// If something is already playing, the new track is queued.
 #[poise::command(prefix_command, slash_command)]
 pub async fn play(
     ctx: Context<'_>,
-    #[description = "YouTube URL or search terms (optionally + a Discord channel link)"]
+    #[description = "YouTube URL, search terms or a sound name (optionally + a Discord channel link)"]
     #[rest]
     input: String,
 ) -> Result<(), Error> {
     let (target, query) = split_target(input.trim());
+    let clip = find_clip(&query);
     if query.is_empty() {
         ctx.say(
             "❌ Give me a YouTube URL or something to search for: `-play <url or search>`\n\
@@ -169,6 +354,27 @@ pub async fn play(
         }
     };
 
+    if let Err(missing) = check_channel_perms(ctx, connect_to, VOICE).await {
+        ctx.say(missing.to_string()).await?;
+        return Ok(());
+    }
+
+    // One voice channel per server: don't pull the bot away from people
+    // who are still listening to something
+    if let Some(call) = manager.get(guild_id) {
+        let call = call.lock().await;
+        if let Some(current) = call.current_channel() {
+            if current != connect_to.into() && !call.queue().is_empty() {
+                ctx.say(format!(
+                    "❌ I'm already playing in <#{}>. Join me there, or `-stop` first.",
+                    current.0
+                ))
+                .await?;
+                return Ok(());
+            }
+        }
+    }
+
     // Send an immediate reply so the user sees something the instant they run the
     // command; we edit this same message as each step completes (or fails).
     let reply = ctx.say("🔊 Joining voice channel…").await?;
@@ -197,8 +403,13 @@ pub async fn play(
     // On the initial join, arm an idle timer so the bot leaves once the queue
     // empties. Only added once per session to avoid stacking timers.
     if fresh_join {
+        let idle_timeout = if clip.is_some() {
+            CLIP_IDLE_TIMEOUT
+        } else {
+            IDLE_TIMEOUT
+        };
         handler_lock.lock().await.add_global_event(
-            Event::Periodic(IDLE_TIMEOUT, None),
+            Event::Periodic(idle_timeout, None),
             IdleLeaver {
                 manager: manager.clone(),
                 guild_id,
@@ -206,39 +417,45 @@ pub async fn play(
         );
     }
 
-    reply
-        .edit(
-            ctx,
-            poise::CreateReply::default().content(format!("🔍 Loading `{query}`…")),
-        )
-        .await?;
-
-    // Resolve the source (runs yt-dlp; also validates the URL / search hit).
-    let is_url = query.starts_with("http://") || query.starts_with("https://");
-    let mut src = if is_url {
-        YoutubeDl::new(HTTP_CLIENT.clone(), query.clone())
-    } else {
-        YoutubeDl::new_search(HTTP_CLIENT.clone(), query.clone())
-    };
-
-    let title = match src.aux_metadata().await {
-        Ok(meta) => meta.title.unwrap_or_else(|| query.clone()),
-        Err(e) => {
-            log::error!("yt-dlp metadata failed for {query:?}: {e:?}");
+    let (title, input): (String, Input) = match clip {
+        Some(clip) => (format!("🔉 {}", clip.name), File::new(clip.path).into()),
+        None => {
             reply
                 .edit(
                     ctx,
-                    poise::CreateReply::default()
-                        .content(format!("❌ Couldn't load `{query}`.\n```\n{e:?}\n```")),
+                    poise::CreateReply::default().content(format!("🔍 Loading `{query}`…")),
                 )
                 .await?;
-            return Ok(());
+
+            // Resolve the source (runs yt-dlp; also validates the URL / search hit).
+            let is_url = query.starts_with("http://") || query.starts_with("https://");
+            let mut src = if is_url {
+                YoutubeDl::new(HTTP_CLIENT.clone(), query.clone())
+            } else {
+                YoutubeDl::new_search(HTTP_CLIENT.clone(), query.clone())
+            };
+
+            let title = match src.aux_metadata().await {
+                Ok(meta) => meta.title.unwrap_or_else(|| query.clone()),
+                Err(e) => {
+                    log::error!("yt-dlp metadata failed for {query:?}: {e:?}");
+                    reply
+                        .edit(
+                            ctx,
+                            poise::CreateReply::default()
+                                .content(format!("❌ Couldn't load `{query}`.\n```\n{e:?}\n```")),
+                        )
+                        .await?;
+                    return Ok(());
+                }
+            };
+            (title, src.into())
         }
     };
 
     // Attach the resolved title as the track's `data` so `-queue` can show it.
     let track_title: TrackTitle = Arc::new(title.clone());
-    let track = Track::new_with_data(src.into(), track_title);
+    let track = Track::new_with_data(input, track_title);
     let position = {
         let mut handler = handler_lock.lock().await;
         handler.enqueue(track).await;
@@ -257,6 +474,14 @@ pub async fn play(
     Ok(())
 }
 
+/// List the soundboard clips `-play` can play by name.
+#[poise::command(prefix_command, slash_command)]
+pub async fn sounds(ctx: Context<'_>) -> Result<(), Error> {
+    let text = sounds_list(&SOUNDBOARD.read());
+    ctx.say(text).await?;
+    Ok(())
+}
+
 /// Skip the track that's currently playing.
 #[poise::command(prefix_command, slash_command, guild_only)]
 pub async fn skip(ctx: Context<'_>) -> Result<(), Error> {
@@ -403,3 +628,64 @@ pub async fn leave(ctx: Context<'_>) -> Result<(), Error> {
 
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A silent 8 kHz, 16-bit mono WAV file lasting `seconds`.
+    fn write_wav(path: &Path, seconds: u32) {
+        let data_len = seconds * 8000 * 2;
+        let mut wav = Vec::new();
+        wav.extend_from_slice(b"RIFF");
+        wav.extend_from_slice(&(36 + data_len).to_le_bytes());
+        wav.extend_from_slice(b"WAVEfmt ");
+        wav.extend_from_slice(&16u32.to_le_bytes());
+        wav.extend_from_slice(&1u16.to_le_bytes()); // PCM
+        wav.extend_from_slice(&1u16.to_le_bytes()); // mono
+        wav.extend_from_slice(&8000u32.to_le_bytes());
+        wav.extend_from_slice(&16000u32.to_le_bytes()); // bytes per second
+        wav.extend_from_slice(&2u16.to_le_bytes()); // bytes per frame
+        wav.extend_from_slice(&16u16.to_le_bytes());
+        wav.extend_from_slice(b"data");
+        wav.extend_from_slice(&data_len.to_le_bytes());
+        wav.resize(wav.len() + data_len as usize, 0);
+        fs::write(path, wav).unwrap();
+    }
+
+    #[test]
+    fn test_scan_clips() {
+        let dir = tempfile::tempdir().unwrap();
+        write_wav(&dir.path().join("Airhorn.wav"), 2);
+        write_wav(&dir.path().join("speech.wav"), 16);
+        fs::write(dir.path().join("notes.txt"), "not a sound").unwrap();
+        fs::write(dir.path().join("broken.mp3"), "not an mp3 either").unwrap();
+
+        let clips = scan_clips(dir.path());
+        assert_eq!(clips.len(), 1, "{clips:?}");
+        assert_eq!(clips[0].name, "airhorn");
+        assert_eq!(clips[0].length, Duration::from_secs(2));
+
+        assert!(scan_clips(&dir.path().join("missing")).is_empty());
+    }
+
+    #[test]
+    fn test_sounds_list() {
+        assert!(sounds_list(&[]).contains("no sounds"));
+
+        let clip = |i: usize| Clip {
+            name: format!("clip{i}"),
+            path: PathBuf::from(format!("clip{i}.ogg")),
+            length: Duration::from_millis(1500),
+        };
+        assert_eq!(
+            sounds_list(&[clip(1), clip(2)]),
+            "🔉 **Sounds** (2) - play one with `-play <name>`\n`clip1` (1.5s), `clip2` (1.5s)"
+        );
+
+        let many: Vec<Clip> = (0..500).map(clip).collect();
+        let text = sounds_list(&many);
+        assert!(text.len() <= 2000, "{}", text.len());
+        assert!(text.ends_with("more"), "{text}");
+    }
+}
@@ -1,93 +1,313 @@
+use crate::commands::maintenance::Maintenance;
+use crate::commands::poll_render::{render_poll_chart, vote_percent};
+use crate::commands::remind::{format_duration, parse_time_duration};
+use crate::storage;
+use crate::utils::args::{split_args, starts_quoted, take_flag};
+use crate::utils::is_protected_user;
+use crate::utils::messages::parse_message_ref;
+use crate::utils::permissions::is_not_found;
 use crate::{Context, Error};
+use chrono::{DateTime, Duration, Utc};
+use parking_lot::{Mutex, RwLock};
 use poise::serenity_prelude as serenity;
+use serde::{Deserialize, Serialize};
 use serenity::{Color, CreateEmbed, CreateEmbedFooter, ReactionType};
+use std::sync::Arc;
+use tokio::time::{interval, Duration as TokioDuration};
+
+/// Polls waiting to close themselves.
+const POLL_CLOSES_FILE: &str = "poll_closes.json";
+
+/// Longest a poll can stay open before closing itself.
+const MAX_POLL_DURATION_DAYS: i64 = 30;
+
+/// How long past its time a poll that couldn't be reached is retried.
+const CLOSE_RETRY_HOURS: i64 = 24;
+
+/// Emoji reactions for options (up to 10)
+pub(crate) const REACTION_EMOJIS: [&str; 10] =
+    ["1️⃣", "2️⃣", "3️⃣", "4️⃣", "5️⃣", "6️⃣", "7️⃣", "8️⃣", "9️⃣", "🔟"];
+
+const FORMAT_HINT: &str = "`question? option1 option2 option3` \
+     (quote options with spaces: `\"pizza place\"`)";
+
+/// Split `question? option1 option2 ...` into the question and its options.
+/// The options are split like other arguments, so `"pizza place"` is one
+/// option, and a quoted question (`"Lunch" pizza tacos`) needs no `?`.
+pub fn parse_poll(input: &str) -> Result<(String, Vec<String>), String> {
+    let input = input.trim();
+    if input.is_empty() {
+        return Err(format!(
+            "❌ Please provide a poll in the format: {}",
+            FORMAT_HINT
+        ));
+    }
+
+    let (question, options) = if starts_quoted(input) {
+        let mut args = split_args(input).map_err(|e| e.to_string())?;
+        let question = args.remove(0);
+        (question, args)
+    } else {
+        // Split the input at the question mark
+        let Some((question, options)) = input.split_once('?') else {
+            return Err(format!("❌ Invalid format! Please use: {}", FORMAT_HINT));
+        };
+        let options = split_args(options).map_err(|e| e.to_string())?;
+        (question.to_string(), options)
+    };
+
+    let question = question.trim().to_string();
+    if question.is_empty() {
+        return Err("❌ Question cannot be empty!".to_string());
+    }
+    if options.is_empty() {
+        return Err("❌ Please provide at least one option!".to_string());
+    }
+    if options.iter().any(|option| option.trim().is_empty()) {
+        return Err("❌ Options cannot be empty!".to_string());
+    }
+    if options.len() > REACTION_EMOJIS.len() {
+        return Err("❌ Maximum 10 options allowed!".to_string());
+    }
+
+    Ok((question, options))
+}
+
+/// Reactions and answers of a `poll straw` poll.
+const STRAW_OPTIONS: [(&str, &str); 3] = [("👍", "Yes"), ("👎", "No"), ("🤷", "Not sure")];
+
+/// One answer on a poll and the reaction that votes for it.
+#[derive(Clone, Debug, PartialEq)]
+pub struct PollOption {
+    pub emoji: String,
+    pub label: String,
+}
+
+/// Options numbered 1️⃣ to 🔟 in order, as typed polls get them.
+pub fn numbered_options(labels: &[String]) -> Vec<PollOption> {
+    REACTION_EMOJIS
+        .iter()
+        .zip(labels)
+        .map(|(emoji, label)| PollOption {
+            emoji: emoji.to_string(),
+            label: label.clone(),
+        })
+        .collect()
+}
+
+/// Yes, no and not sure, for a `poll straw` poll.
+fn straw_options() -> Vec<PollOption> {
+    STRAW_OPTIONS
+        .iter()
+        .map(|(emoji, label)| PollOption {
+            emoji: emoji.to_string(),
+            label: label.to_string(),
+        })
+        .collect()
+}
+
+/// Whether polls vote with this reaction, numbered or straw.
+fn is_poll_emoji(emoji: &str) -> bool {
+    REACTION_EMOJIS.contains(&emoji) || STRAW_OPTIONS.iter().any(|(e, _)| *e == emoji)
+}
+
+/// The options listed one per line with their reaction emoji.
+fn options_text(options: &[PollOption]) -> String {
+    options
+        .iter()
+        .map(|option| format!("{} {}\n", option.emoji, option.label))
+        .collect()
+}
+
+/// Numbered options listed one per line with their reaction emoji.
+pub fn poll_options_text(options: &[String]) -> String {
+    options_text(&numbered_options(options))
+}
+
+/// A poll that closes itself at `close_at`.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+struct ScheduledClose {
+    channel_id: u64,
+    message_id: u64,
+    close_at: DateTime<Utc>,
+}
+
+#[derive(Serialize, Deserialize, Default, Clone)]
+struct PollCloses {
+    polls: Vec<ScheduledClose>,
+}
+
+impl PollCloses {
+    /// Take the polls due at `now` out of the schedule.
+    fn take_due(&mut self, now: DateTime<Utc>) -> Vec<ScheduledClose> {
+        let (due, waiting) = self.polls.drain(..).partition(|poll| poll.close_at <= now);
+        self.polls = waiting;
+        due
+    }
+}
+
+lazy_static::lazy_static! {
+    static ref POLL_CLOSES: RwLock<PollCloses> = RwLock::new(storage::load(POLL_CLOSES_FILE));
+}
+
+/// Serializes changes to [`POLL_CLOSES`] with their save, which happens
+/// outside its lock.
+static POLL_CLOSES_SAVE: Mutex<()> = Mutex::new(());
+
+/// Change the schedule and save it, returning what `change` returns.
+fn update_closes<T>(change: impl FnOnce(&mut PollCloses) -> T) -> T {
+    let _save = POLL_CLOSES_SAVE.lock();
+    let (result, snapshot) = {
+        let mut data = POLL_CLOSES.write();
+        let result = change(&mut data);
+        (result, data.clone())
+    };
+    if let Err(e) = storage::save(POLL_CLOSES_FILE, &snapshot) {
+        log::error!("Failed to save poll closes: {}", e);
+    }
+    result
+}
+
+/// Take a leading `--duration <time>` off a poll, e.g. `--duration 2h`.
+pub(crate) fn take_duration_flag(input: &str) -> Result<(Option<Duration>, &str), String> {
+    let Some((value, rest)) = take_flag(input, "duration") else {
+        return Ok((None, input));
+    };
+    let duration = parse_time_duration(value)
+        .filter(|duration| *duration >= Duration::minutes(1))
+        .ok_or_else(|| {
+            "❌ `--duration` takes a time like `30m`, `2h` or `3d`, at least a minute.".to_string()
+        })?;
+    if duration > Duration::days(MAX_POLL_DURATION_DAYS) {
+        return Err(format!(
+            "❌ Polls can stay open for at most {} days.",
+            MAX_POLL_DURATION_DAYS
+        ));
+    }
+    Ok((Some(duration), rest))
+}
+
+/// Title of an open poll's embed; closing it adds [`CLOSED_SUFFIX`].
+const POLL_TITLE: &str = "📊 Poll";
+const CLOSED_SUFFIX: &str = " (closed)";
+
+/// Width of the text bars in the results embed.
+const TEXT_BAR_WIDTH: usize = 10;
 
 /// Creates a poll with a question and multiple options
 #[poise::command(prefix_command, slash_command)]
 pub async fn poll(
     ctx: Context<'_>,
-    #[description = "Poll format: 'question? option1 option2 option3'"]
+    #[description = "'question? option1 option2', optionally after --duration 2h to close it then"]
     #[rest]
     input: String,
+) -> Result<(), Error> {
+    create_poll(ctx, input).await
+}
+
+/// Quick yes / no / not sure poll
+#[poise::command(prefix_command, slash_command)]
+pub async fn pollstraw(
+    ctx: Context<'_>,
+    #[description = "The yes/no question, optionally after --duration 2h to close it then"]
+    #[rest]
+    question: String,
 ) -> Result<(), Error> {
     log::info!(
-        "Poll command called by {} with input: '{}'",
+        "Poll straw command called by {} with question: '{}'",
         ctx.author().name,
-        input
+        question
     );
 
-    if input.trim().is_empty() {
-        ctx.say("❌ Please provide a poll in the format: `question? option1 option2 option3`")
-            .await?;
-        return Ok(());
-    }
-
-    // Split the input at the question mark
-    let parts: Vec<&str> = input.splitn(2, '?').collect();
-
-    if parts.len() != 2 {
-        ctx.say("❌ Invalid format! Please use: `question? option1 option2 option3`")
-            .await?;
-        return Ok(());
-    }
-
-    let question = parts[0].trim();
-    let options_str = parts[1].trim();
-
+    let (duration, question) = match take_duration_flag(&question) {
+        Ok(parsed) => parsed,
+        Err(error) => {
+            ctx.say(error).await?;
+            return Ok(());
+        }
+    };
+    let question = question.trim();
     if question.is_empty() {
         ctx.say("❌ Question cannot be empty!").await?;
         return Ok(());
     }
+    post_poll(ctx, question, &straw_options(), duration).await
+}
 
-    // Split options by whitespace and filter out empty strings
-    let options: Vec<&str> = options_str.split_whitespace().collect();
-
-    if options.is_empty() {
-        ctx.say("❌ Please provide at least one option!").await?;
-        return Ok(());
-    }
-
-    if options.len() > 10 {
-        ctx.say("❌ Maximum 10 options allowed!").await?;
-        return Ok(());
-    }
-
-    // Emoji reactions for options (up to 10)
-    let reaction_emojis = ["1️⃣", "2️⃣", "3️⃣", "4️⃣", "5️⃣", "6️⃣", "7️⃣", "8️⃣", "9️⃣", "🔟"];
+async fn create_poll(ctx: Context<'_>, input: String) -> Result<(), Error> {
+    log::info!(
+        "Poll command called by {} with input: '{}'",
+        ctx.author().name,
+        input
+    );
 
-    // Build the options text with emojis
-    let mut options_text = String::new();
-    for (i, option) in options.iter().enumerate() {
-        if i < reaction_emojis.len() {
-            options_text.push_str(&format!("{} {}\n", reaction_emojis[i], option));
+    let parsed =
+        take_duration_flag(&input).and_then(|(duration, rest)| Ok((duration, parse_poll(rest)?)));
+    let (duration, (question, options)) = match parsed {
+        Ok(poll) => poll,
+        Err(error) => {
+            ctx.say(error).await?;
+            return Ok(());
         }
-    }
+    };
+    post_poll(ctx, &question, &numbered_options(&options), duration).await
+}
+
+/// Post a poll and add its voting reactions, scheduling it to close after
+/// `duration` if there is one. Every kind of poll goes through here, so
+/// `pollclose` can read any of them back.
+pub(crate) async fn post_poll(
+    ctx: Context<'_>,
+    question: &str,
+    options: &[PollOption],
+    duration: Option<Duration>,
+) -> Result<(), Error> {
+    let options_text = options_text(options);
+    let now = Utc::now();
+    let close_at = duration.map(|duration| now + duration);
 
     // Create embed
-    let embed = CreateEmbed::new()
-        .title("📊 Poll")
+    let mut embed = CreateEmbed::new()
+        .title(POLL_TITLE)
         .description(format!("**{question}**\n\n{options_text}"))
         .color(Color::BLUE)
         .footer(CreateEmbedFooter::new(format!(
             "Poll created by {}",
             ctx.author().name
         )))
-        .timestamp(chrono::Utc::now());
+        .timestamp(now);
+    if let Some(close_at) = close_at {
+        embed = embed.field(
+            "⏰ Closes",
+            format!("<t:{}:R>", close_at.timestamp()),
+            false,
+        );
+    }
 
     // Send the poll message
     let reply = ctx.send(poise::CreateReply::default().embed(embed)).await?;
 
     // Add reactions for each option
     let message = reply.message().await?;
-    for emoji in reaction_emojis
-        .iter()
-        .take(options.len().min(reaction_emojis.len()))
-    {
+    if let Some(close_at) = close_at {
+        let scheduled = ScheduledClose {
+            channel_id: message.channel_id.get(),
+            message_id: message.id.get(),
+            close_at,
+        };
+        update_closes(|data| data.polls.push(scheduled));
+        log::info!(
+            "Poll {} closes in {}",
+            message.id,
+            format_duration(close_at - now)
+        );
+    }
+    for option in options {
         if let Err(e) = message
-            .react(&ctx.http(), ReactionType::Unicode(emoji.to_string()))
+            .react(&ctx.http(), ReactionType::Unicode(option.emoji.clone()))
             .await
         {
-            log::warn!("Failed to add reaction {emoji}: {e}");
+            log::warn!("Failed to add reaction {}: {e}", option.emoji);
         }
     }
 
@@ -95,8 +315,322 @@ pub async fn poll(
     Ok(())
 }
 
+/// The question and options of a poll, read back from its embed's
+/// description as [`post_poll`] wrote it.
+fn parse_poll_embed(description: &str) -> Option<(String, Vec<PollOption>)> {
+    let (question, options_text) = description.split_once("\n\n")?;
+    let question = question.strip_prefix("**")?.strip_suffix("**")?.to_string();
+    let options = options_text
+        .lines()
+        .map(|line| {
+            let (emoji, label) = line.split_once(' ')?;
+            is_poll_emoji(emoji).then(|| PollOption {
+                emoji: emoji.to_string(),
+                label: label.to_string(),
+            })
+        })
+        .collect::<Option<Vec<_>>>()?;
+    (!options.is_empty()).then_some((question, options))
+}
+
+/// Votes for each option, from the poll message's reactions. The bot's own
+/// reactions, added to make voting easy, don't count.
+fn count_votes(message: &serenity::Message, options: &[PollOption]) -> Vec<(PollOption, u32)> {
+    options
+        .iter()
+        .map(|option| {
+            let votes = message
+                .reactions
+                .iter()
+                .find(|reaction| {
+                    matches!(&reaction.reaction_type, ReactionType::Unicode(e) if *e == option.emoji)
+                })
+                .map_or(0, |reaction| reaction.count.saturating_sub(reaction.me as u64));
+            (option.clone(), votes as u32)
+        })
+        .collect()
+}
+
+/// The results as text, one option per line with a bar and its share.
+fn results_text(results: &[(PollOption, u32)]) -> String {
+    let total: u32 = results.iter().map(|(_, votes)| votes).sum();
+    results
+        .iter()
+        .map(|(option, votes)| {
+            let percent = vote_percent(*votes, total);
+            let filled = (percent as usize * TEXT_BAR_WIDTH + 50) / 100;
+            format!(
+                "{} **{}**\n`{}{}` {} vote(s) ({}%)\n",
+                option.emoji,
+                option.label,
+                "█".repeat(filled),
+                "░".repeat(TEXT_BAR_WIDTH - filled),
+                votes,
+                percent
+            )
+        })
+        .collect()
+}
+
+/// Whether the author may close a poll: whoever created it, bot owners, and
+/// members with Manage Messages in the channel.
+async fn can_close_poll(ctx: Context<'_>, poll: &serenity::Message) -> bool {
+    let creator = poll
+        .embeds
+        .first()
+        .and_then(|embed| embed.footer.as_ref())
+        .and_then(|footer| footer.text.strip_prefix("Poll created by "));
+    if creator == Some(ctx.author().name.as_str()) || is_protected_user(&ctx.author().name) {
+        return true;
+    }
+    let (Some(member), Some(channel)) = (ctx.author_member().await, ctx.guild_channel().await)
+    else {
+        return false;
+    };
+    ctx.guild().is_some_and(|guild| {
+        guild
+            .user_permissions_in(&channel, &member)
+            .manage_messages()
+    })
+}
+
+/// An open poll read back from one of the bot's messages.
+struct OpenPoll {
+    embed: serenity::Embed,
+    question: String,
+    options: Vec<PollOption>,
+}
+
+/// Read an open poll from a message, or say why it isn't one.
+fn read_open_poll(
+    poll: &serenity::Message,
+    bot_id: serenity::UserId,
+) -> Result<OpenPoll, &'static str> {
+    let Some(embed) = poll.embeds.first().filter(|_| poll.author.id == bot_id) else {
+        return Err("❌ That isn't one of my polls.");
+    };
+    if embed.title.as_deref() != Some(POLL_TITLE) {
+        return Err(
+            if embed
+                .title
+                .as_deref()
+                .is_some_and(|t| t.ends_with(CLOSED_SUFFIX))
+            {
+                "❌ That poll is already closed."
+            } else {
+                "❌ That isn't one of my polls."
+            },
+        );
+    }
+    let Some((question, options)) = embed.description.as_deref().and_then(parse_poll_embed) else {
+        return Err("❌ Couldn't read the options of that poll.");
+    };
+    Ok(OpenPoll {
+        embed: embed.clone(),
+        question,
+        options,
+    })
+}
+
+/// Mark a poll closed and build its results: the embed and the chart it
+/// shows. Drops it from the auto-close schedule.
+async fn close_poll(
+    http: &serenity::Http,
+    poll: &mut serenity::Message,
+    open: OpenPoll,
+    closed_by: &str,
+) -> (CreateEmbed, serenity::CreateAttachment) {
+    let message_id = poll.id.get();
+    update_closes(|data| {
+        data.polls
+            .retain(|scheduled| scheduled.message_id != message_id)
+    });
+
+    let results = count_votes(poll, &open.options);
+    let total: u32 = results.iter().map(|(_, votes)| votes).sum();
+
+    // Mark the poll itself as closed so it can't be closed twice
+    let closed = CreateEmbed::from(open.embed)
+        .title(format!("{}{}", POLL_TITLE, CLOSED_SUFFIX))
+        .color(Color::DARK_GREY);
+    if let Err(e) = poll
+        .edit(http, serenity::EditMessage::new().embed(closed))
+        .await
+    {
+        log::warn!("Failed to mark poll {} as closed: {}", message_id, e);
+    }
+
+    let labelled: Vec<(String, u32)> = results
+        .iter()
+        .map(|(option, votes)| (option.label.clone(), *votes))
+        .collect();
+    let chart = render_poll_chart(&labelled);
+    let embed = CreateEmbed::new()
+        .title("📊 Poll Results")
+        .description(format!(
+            "**{}**\n\n{}",
+            open.question,
+            results_text(&results)
+        ))
+        .color(Color::BLUE)
+        .image("attachment://poll_results.png")
+        .footer(CreateEmbedFooter::new(format!(
+            "{} vote(s) • closed by {}",
+            total, closed_by
+        )))
+        .timestamp(Utc::now());
+
+    log::info!("Poll {} closed with {} vote(s)", message_id, total);
+    (
+        embed,
+        serenity::CreateAttachment::bytes(chart, "poll_results.png"),
+    )
+}
+
+/// Close a poll and post its results with a chart
+#[poise::command(prefix_command, slash_command)]
+pub async fn pollclose(
+    ctx: Context<'_>,
+    #[description = "Link or ID of the poll message (or reply to it)"] message: Option<String>,
+) -> Result<(), Error> {
+    log::info!("Poll close command called by {}", ctx.author().name);
+
+    let poll_ref = match (&message, ctx) {
+        (Some(text), _) => parse_message_ref(text, ctx.channel_id()),
+        (None, poise::Context::Prefix(prefix_ctx)) => prefix_ctx
+            .msg
+            .referenced_message
+            .as_ref()
+            .map(|msg| (msg.channel_id, msg.id)),
+        (None, poise::Context::Application(_)) => None,
+    };
+    let Some((channel_id, message_id)) = poll_ref else {
+        ctx.say("❌ Reply to the poll or give its message link or ID, e.g. `-pollclose <link>`.")
+            .await?;
+        return Ok(());
+    };
+    if channel_id != ctx.channel_id() {
+        ctx.say("❌ Close the poll from the channel it's in.")
+            .await?;
+        return Ok(());
+    }
+
+    let mut poll = match channel_id.message(ctx.http(), message_id).await {
+        Ok(poll) => poll,
+        Err(e) if is_not_found(&e) => {
+            ctx.say("❌ That message doesn't exist (anymore).").await?;
+            return Ok(());
+        }
+        Err(e) => return Err(e.into()),
+    };
+    let open = match read_open_poll(&poll, ctx.framework().bot_id) {
+        Ok(open) => open,
+        Err(reply) => {
+            ctx.say(reply).await?;
+            return Ok(());
+        }
+    };
+    if !can_close_poll(ctx, &poll).await {
+        ctx.say("❌ Only whoever created the poll or someone with Manage Messages can close it.")
+            .await?;
+        return Ok(());
+    }
+
+    ctx.defer().await?;
+    let (embed, chart) = close_poll(ctx.http(), &mut poll, open, &ctx.author().name).await;
+    ctx.send(poise::CreateReply::default().embed(embed).attachment(chart))
+        .await?;
+    Ok(())
+}
+
+/// Close polls whose duration is up, posting the results as a reply to
+/// each. Polls that can't be fetched for a while are given up on.
+pub fn start_poll_closer(http: Arc<serenity::Http>, maintenance: Arc<Maintenance>) {
+    tokio::spawn(async move {
+        let mut interval = interval(TokioDuration::from_secs(60)); // Check every minute
+
+        loop {
+            interval.tick().await;
+
+            // Skip the run during maintenance and pick up on the next tick
+            if maintenance.is_enabled() {
+                continue;
+            }
+
+            let now = Utc::now();
+            let due = {
+                let has_due = POLL_CLOSES
+                    .read()
+                    .polls
+                    .iter()
+                    .any(|poll| poll.close_at <= now);
+                if !has_due {
+                    continue;
+                }
+                update_closes(|data| data.take_due(now))
+            };
+
+            for scheduled in due {
+                if let Some(retry) = auto_close(&http, &scheduled).await {
+                    if now - retry.close_at < Duration::hours(CLOSE_RETRY_HOURS) {
+                        update_closes(|data| data.polls.push(retry));
+                    } else {
+                        log::warn!("Giving up on closing poll {}", retry.message_id);
+                    }
+                }
+            }
+        }
+    });
+}
+
+/// Close one poll whose time is up. Returns it if it should be tried again.
+async fn auto_close(http: &serenity::Http, scheduled: &ScheduledClose) -> Option<ScheduledClose> {
+    let channel_id = serenity::ChannelId::new(scheduled.channel_id);
+    let mut poll = match channel_id
+        .message(http, serenity::MessageId::new(scheduled.message_id))
+        .await
+    {
+        Ok(poll) => poll,
+        // Deleted, or the channel is gone
+        Err(e) if is_not_found(&e) => return None,
+        Err(e) => {
+            log::warn!(
+                "Couldn't fetch poll {} to close it: {}",
+                scheduled.message_id,
+                e
+            );
+            return Some(scheduled.clone());
+        }
+    };
+    let bot_id = match http.get_current_user().await {
+        Ok(user) => user.id,
+        Err(e) => {
+            log::warn!("Couldn't close poll {}: {}", scheduled.message_id, e);
+            return Some(scheduled.clone());
+        }
+    };
+    // Closed by hand in the meantime
+    let open = read_open_poll(&poll, bot_id).ok()?;
+
+    let (embed, chart) = close_poll(http, &mut poll, open, "its timer").await;
+    let message = serenity::CreateMessage::new()
+        .embed(embed)
+        .add_file(chart)
+        .reference_message(&poll)
+        .allowed_mentions(serenity::CreateAllowedMentions::new());
+    if let Err(e) = channel_id.send_message(http, message).await {
+        log::warn!(
+            "Failed to post the results of poll {}: {}",
+            scheduled.message_id,
+            e
+        );
+    }
+    None
+}
+
 #[cfg(test)]
 mod tests {
+    use super::*;
 
     #[test]
     fn test_poll_parsing() {
@@ -137,4 +671,174 @@ mod tests {
         assert_eq!(options.len(), 11);
         assert!(options.len() > 10);
     }
+
+    #[test]
+    fn test_parse_poll() {
+        let poll = |question: &str, options: &[&str]| {
+            Ok((
+                question.to_string(),
+                options.iter().map(|o| o.to_string()).collect::<Vec<_>>(),
+            ))
+        };
+        assert_eq!(
+            parse_poll("Lunch? pizza tacos"),
+            poll("Lunch", &["pizza", "tacos"])
+        );
+        assert_eq!(
+            parse_poll(r#"Lunch? "pizza place" 'taco truck'"#),
+            poll("Lunch", &["pizza place", "taco truck"])
+        );
+        assert_eq!(
+            parse_poll(r#""Is 2+2=4? Really" yes no"#),
+            poll("Is 2+2=4? Really", &["yes", "no"])
+        );
+        assert!(parse_poll(r#"Lunch? "pizza place"#)
+            .unwrap_err()
+            .contains("never closed"));
+        assert!(parse_poll(r#"Lunch? pizza """#).is_err());
+        assert!(parse_poll(r#""" pizza"#).is_err());
+        assert!(parse_poll("   ").is_err());
+        assert!(parse_poll("Lunch pizza tacos").is_err());
+        assert!(parse_poll("? pizza").is_err());
+        assert!(parse_poll("Lunch?").is_err());
+        assert!(parse_poll("Test? 1 2 3 4 5 6 7 8 9 10 11").is_err());
+        assert_eq!(
+            poll_options_text(&["pizza".to_string(), "tacos".to_string()]),
+            "1️⃣ pizza\n2️⃣ tacos\n"
+        );
+    }
+
+    #[test]
+    fn test_parse_poll_embed() {
+        let options = numbered_options(&["pizza place".to_string(), "tacos".to_string()]);
+        let description = format!("**Lunch**\n\n{}", options_text(&options));
+        assert_eq!(
+            parse_poll_embed(&description),
+            Some(("Lunch".to_string(), options))
+        );
+        assert_eq!(parse_poll_embed("**Lunch**\n\n"), None);
+        assert_eq!(parse_poll_embed("Just some embed"), None);
+        assert_eq!(parse_poll_embed("**Lunch**\n\n🍕 pizza"), None);
+    }
+
+    #[test]
+    fn test_count_votes_and_results_text() {
+        let options =
+            numbered_options(&["pizza".to_string(), "tacos".to_string(), "soup".to_string()]);
+        let reaction = |emoji: &str, count: u64, me: bool| {
+            serde_json::from_value::<serenity::MessageReaction>(serde_json::json!({
+                "count": count,
+                "count_details": {"burst": 0, "normal": count},
+                "me": me,
+                "me_burst": false,
+                "burst_colors": [],
+                "emoji": {"id": null, "name": emoji},
+            }))
+            .unwrap()
+        };
+        let mut message = serenity::Message::default();
+        message.reactions = vec![
+            reaction("1️⃣", 4, true),
+            reaction("2️⃣", 1, false),
+            reaction("3️⃣", 1, true),
+            reaction("🍕", 9, false),
+        ];
+
+        let results = count_votes(&message, &options);
+        let votes: Vec<(&str, u32)> = results
+            .iter()
+            .map(|(option, votes)| (option.label.as_str(), *votes))
+            .collect();
+        assert_eq!(votes, [("pizza", 3), ("tacos", 1), ("soup", 0)]);
+        assert_eq!(
+            results_text(&results),
+            "1️⃣ **pizza**\n`████████░░` 3 vote(s) (75%)\n\
+             2️⃣ **tacos**\n`███░░░░░░░` 1 vote(s) (25%)\n\
+             3️⃣ **soup**\n`░░░░░░░░░░` 0 vote(s) (0%)\n"
+        );
+        // Nobody voted
+        let pizza = numbered_options(&["pizza".to_string()]).remove(0);
+        assert_eq!(
+            results_text(&[(pizza, 0)]),
+            "1️⃣ **pizza**\n`░░░░░░░░░░` 0 vote(s) (0%)\n"
+        );
+    }
+
+    #[test]
+    fn test_straw_poll_options() {
+        let options = straw_options();
+        let description = format!("**Pizza tonight?**\n\n{}", options_text(&options));
+        assert_eq!(
+            description,
+            "**Pizza tonight?**\n\n👍 Yes\n👎 No\n🤷 Not sure\n"
+        );
+        // Closing reads a straw poll back like any other
+        assert_eq!(
+            parse_poll_embed(&description),
+            Some(("Pizza tonight?".to_string(), options.clone()))
+        );
+
+        let reaction = |emoji: &str, count: u64| {
+            serde_json::from_value::<serenity::MessageReaction>(serde_json::json!({
+                "count": count,
+                "count_details": {"burst": 0, "normal": count},
+                "me": true,
+                "me_burst": false,
+                "burst_colors": [],
+                "emoji": {"id": null, "name": emoji},
+            }))
+            .unwrap()
+        };
+        let mut message = serenity::Message::default();
+        message.reactions = vec![reaction("👍", 3), reaction("🤷", 2), reaction("1️⃣", 5)];
+        let results = count_votes(&message, &options);
+        assert_eq!(
+            results_text(&results),
+            "👍 **Yes**\n`███████░░░` 2 vote(s) (67%)\n\
+             👎 **No**\n`░░░░░░░░░░` 0 vote(s) (0%)\n\
+             🤷 **Not sure**\n`███░░░░░░░` 1 vote(s) (33%)\n"
+        );
+    }
+
+    #[test]
+    fn test_take_duration_flag() {
+        assert_eq!(
+            take_duration_flag("--duration 2h Lunch? pizza tacos"),
+            Ok((Some(Duration::hours(2)), "Lunch? pizza tacos"))
+        );
+        assert_eq!(
+            take_duration_flag("--duration=30m Pizza tonight?"),
+            Ok((Some(Duration::minutes(30)), "Pizza tonight?"))
+        );
+        // Only a leading flag counts, so questions are left alone
+        assert_eq!(
+            take_duration_flag("Close early? yes no"),
+            Ok((None, "Close early? yes no"))
+        );
+        assert_eq!(
+            take_duration_flag("Is --duration 2h enough? yes no"),
+            Ok((None, "Is --duration 2h enough? yes no"))
+        );
+        assert!(take_duration_flag("--duration soon Lunch? pizza tacos").is_err());
+        assert!(take_duration_flag("--duration 30s Lunch? pizza tacos").is_err());
+        assert!(take_duration_flag("--duration 31d Lunch? pizza tacos")
+            .unwrap_err()
+            .contains("30 days"));
+    }
+
+    #[test]
+    fn test_take_due_closes() {
+        let now = Utc::now();
+        let scheduled = |message_id: u64, minutes: i64| ScheduledClose {
+            channel_id: 1,
+            message_id,
+            close_at: now + Duration::minutes(minutes),
+        };
+        let mut closes = PollCloses {
+            polls: vec![scheduled(1, -5), scheduled(2, 10), scheduled(3, 0)],
+        };
+        assert_eq!(closes.take_due(now), [scheduled(1, -5), scheduled(3, 0)]);
+        assert_eq!(closes.polls, [scheduled(2, 10)]);
+        assert!(closes.take_due(now).is_empty());
+    }
 }
@@ -0,0 +1,176 @@
+//! The bar chart posted with a closed poll's results: one row per option
+//! with its label, a bar for its share of the votes, and the vote count and
+//! percentage. Drawn with the same helpers and font as the `-stats` image.
+
+use crate::commands::stats_render::{draw_text, fill_rect, BG, MUTED, TEXT, TRACK};
+use image::{Rgba, RgbaImage};
+
+const WIDTH: u32 = 800;
+const ROW_H: i32 = 40;
+const FONT_SIZE: f32 = 18.0;
+
+/// Labels longer than this are cut short with an ellipsis.
+const MAX_LABEL_CHARS: usize = 20;
+
+const LABEL_X: i32 = 12;
+const BAR_X: i32 = 240;
+const BAR_W: i32 = 410;
+const BAR_H: i32 = 24;
+const VALUE_X: i32 = BAR_X + BAR_W + 12;
+
+/// One color per option; polls have at most ten.
+const BAR_COLORS: [[u8; 3]; 10] = [
+    [237, 66, 69],   // red
+    [230, 126, 34],  // orange
+    [254, 231, 92],  // yellow
+    [87, 242, 135],  // green
+    [52, 152, 219],  // blue
+    [155, 89, 182],  // purple
+    [235, 69, 158],  // pink
+    [26, 188, 156],  // teal
+    [121, 85, 72],   // brown
+    [185, 187, 190], // light grey
+];
+
+fn bar_color(index: usize) -> Rgba<u8> {
+    let [r, g, b] = BAR_COLORS[index % BAR_COLORS.len()];
+    Rgba([r, g, b, 255])
+}
+
+fn short_label(label: &str) -> String {
+    if label.chars().count() <= MAX_LABEL_CHARS {
+        return label.to_string();
+    }
+    let mut short: String = label.chars().take(MAX_LABEL_CHARS - 1).collect();
+    short.push('…');
+    short
+}
+
+/// Rounded percentage of `votes` out of `total`; 0 when nobody voted.
+pub fn vote_percent(votes: u32, total: u32) -> u32 {
+    if total == 0 {
+        return 0;
+    }
+    ((votes as f64 / total as f64) * 100.0).round() as u32
+}
+
+/// Render poll results, as (option, votes) in poll order, to a PNG that's
+/// 800 pixels wide and 40 per option.
+pub fn render_poll_chart(results: &[(String, u32)]) -> Vec<u8> {
+    let rows = results.len().max(1) as u32;
+    let mut canvas = RgbaImage::from_pixel(WIDTH, rows * ROW_H as u32, BG);
+    let total: u32 = results.iter().map(|(_, votes)| votes).sum();
+
+    for (i, (label, votes)) in results.iter().enumerate() {
+        let top = i as i32 * ROW_H;
+        let text_y = top + (ROW_H - FONT_SIZE as i32) / 2 - 1;
+        let bar_y = top + (ROW_H - BAR_H) / 2;
+
+        draw_text(
+            &mut canvas,
+            TEXT,
+            LABEL_X,
+            text_y,
+            FONT_SIZE,
+            false,
+            &short_label(label),
+        );
+        fill_rect(&mut canvas, TRACK, BAR_X, bar_y, BAR_W, BAR_H);
+        if total > 0 {
+            let width = (BAR_W as u64 * *votes as u64 / total as u64) as i32;
+            fill_rect(&mut canvas, bar_color(i), BAR_X, bar_y, width, BAR_H);
+        }
+        draw_text(
+            &mut canvas,
+            MUTED,
+            VALUE_X,
+            text_y,
+            FONT_SIZE,
+            false,
+            &format!("{} ({}%)", votes, vote_percent(*votes, total)),
+        );
+    }
+
+    let mut bytes = Vec::new();
+    canvas
+        .write_to(
+            &mut std::io::Cursor::new(&mut bytes),
+            image::ImageOutputFormat::Png,
+        )
+        .expect("encoding a PNG in memory can't fail");
+    bytes
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn results(votes: &[u32]) -> Vec<(String, u32)> {
+        votes
+            .iter()
+            .enumerate()
+            .map(|(i, votes)| (format!("Option {}", i + 1), *votes))
+            .collect()
+    }
+
+    fn decode(png: &[u8]) -> RgbaImage {
+        image::load_from_memory(png).unwrap().to_rgba8()
+    }
+
+    /// The pixel in the middle of row `row`, `x` pixels into the bar track.
+    fn bar_pixel(chart: &RgbaImage, row: u32, x: i32) -> Rgba<u8> {
+        *chart.get_pixel((BAR_X + x) as u32, row * ROW_H as u32 + ROW_H as u32 / 2)
+    }
+
+    #[test]
+    fn test_render_poll_chart() {
+        let chart = decode(&render_poll_chart(&results(&[3, 1, 0])));
+        assert_eq!(chart.dimensions(), (800, 120));
+
+        // 3 of 4 votes fill three quarters of the track
+        assert_eq!(bar_pixel(&chart, 0, 1), bar_color(0));
+        assert_eq!(bar_pixel(&chart, 0, BAR_W * 3 / 4 - 2), bar_color(0));
+        assert_eq!(bar_pixel(&chart, 0, BAR_W * 3 / 4 + 2), TRACK);
+        assert_eq!(bar_pixel(&chart, 1, 1), bar_color(1));
+        assert_eq!(bar_pixel(&chart, 1, BAR_W / 4 + 2), TRACK);
+        // No votes, no bar
+        assert_eq!(bar_pixel(&chart, 2, 1), TRACK);
+    }
+
+    #[test]
+    fn test_render_poll_chart_degenerate() {
+        // Nobody voted: every bar is empty, and nothing divides by zero
+        let chart = decode(&render_poll_chart(&results(&[0, 0])));
+        assert_eq!(chart.dimensions(), (800, 80));
+        assert_eq!(bar_pixel(&chart, 0, 1), TRACK);
+        assert_eq!(bar_pixel(&chart, 1, 1), TRACK);
+
+        // A single option with all the votes fills its whole track
+        let chart = decode(&render_poll_chart(&results(&[5])));
+        assert_eq!(chart.dimensions(), (800, 40));
+        assert_eq!(bar_pixel(&chart, 0, BAR_W - 1), bar_color(0));
+
+        // Even an empty poll gives a (blank) image
+        let chart = decode(&render_poll_chart(&[]));
+        assert_eq!(chart.dimensions(), (800, 40));
+    }
+
+    #[test]
+    fn test_bar_colors_are_distinct() {
+        let colors: std::collections::HashSet<_> = (0..10).map(bar_color).collect();
+        assert_eq!(colors.len(), 10);
+    }
+
+    #[test]
+    fn test_labels_and_percentages() {
+        assert_eq!(short_label("Pizza"), "Pizza");
+        assert_eq!(short_label(&"x".repeat(20)), "x".repeat(20));
+        let short = short_label("A much longer option than fits");
+        assert_eq!(short, "A much longer optio…");
+        assert_eq!(short.chars().count(), MAX_LABEL_CHARS);
+
+        assert_eq!(vote_percent(1, 3), 33);
+        assert_eq!(vote_percent(2, 3), 67);
+        assert_eq!(vote_percent(0, 0), 0);
+    }
+}
@@ -0,0 +1,582 @@
+//! Per-server poll templates: a named set of options saved once, so a
+//! recurring poll (the weekdays for game night) only needs its question.
+
+use crate::commands::poll::{numbered_options, post_poll, take_duration_flag, REACTION_EMOJIS};
+use crate::config_check::{check_store, ConfigProblem, LiveIds};
+use crate::storage;
+use crate::utils::args::split_args;
+use crate::utils::check_store_writable;
+use crate::utils::is_protected_user;
+use crate::{Context, Error};
+use parking_lot::{Mutex, RwLock};
+use poise::serenity_prelude as serenity;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+
+const POLL_TEMPLATES_FILE: &str = "poll_templates.json";
+
+const MAX_TEMPLATES_PER_GUILD: usize = 20;
+
+/// Longest template name, in characters.
+const MAX_TEMPLATE_NAME_CHARS: usize = 32;
+
+/// Longest template option, in characters.
+const MAX_OPTION_CHARS: usize = 50;
+
+/// Most characters all of a template's options may have together, so a
+/// full server's `polltemplate list` fits in one embed.
+const MAX_OPTIONS_CHARS: usize = 140;
+
+#[derive(Serialize, Deserialize, Default, Clone)]
+struct PollTemplatesData {
+    /// Each guild's templates, from name to options.
+    guilds: HashMap<u64, BTreeMap<String, Vec<String>>>,
+}
+
+lazy_static::lazy_static! {
+    static ref POLL_TEMPLATES: RwLock<PollTemplatesData> =
+        RwLock::new(storage::load(POLL_TEMPLATES_FILE));
+    /// Held while saving the templates, so snapshots reach the file in
+    /// order without keeping polls waiting on the disk.
+    static ref POLL_TEMPLATES_SAVE: Mutex<()> = Mutex::new(());
+}
+
+/// Re-read the templates from disk.
+pub fn reload_poll_templates() -> Result<(), Error> {
+    *POLL_TEMPLATES.write() = storage::reload(POLL_TEMPLATES_FILE)?;
+    Ok(())
+}
+
+fn save_poll_templates(data: &PollTemplatesData) -> Result<(), Error> {
+    storage::save(POLL_TEMPLATES_FILE, data)
+}
+
+/// Change the templates with `update` and save them.
+fn update_templates<T>(update: impl FnOnce(&mut PollTemplatesData) -> T) -> Result<T, Error> {
+    let _saving = POLL_TEMPLATES_SAVE.lock();
+    let (result, snapshot) = {
+        let mut data = POLL_TEMPLATES.write();
+        let result = update(&mut data);
+        (result, data.clone())
+    };
+    save_poll_templates(&snapshot)?;
+    Ok(result)
+}
+
+/// Drop an orphaned guild's poll templates.
+pub fn purge_guild(guild_id: u64) {
+    let purged = update_templates(|data| data.guilds.remove(&guild_id).is_some());
+    if let Err(e) = purged {
+        log::error!("Failed to save poll templates: {}", e);
+    }
+}
+
+/// Why these options can't be a template, if they can't.
+fn options_problem(options: &[String]) -> Option<String> {
+    if options.iter().any(|option| option.trim().is_empty()) {
+        return Some("an option is blank".to_string());
+    }
+    if options.len() < 2 || options.len() > REACTION_EMOJIS.len() {
+        return Some(format!(
+            "it has {} options, not 2 to {}",
+            options.len(),
+            REACTION_EMOJIS.len()
+        ));
+    }
+    if options
+        .iter()
+        .any(|option| option.chars().count() > MAX_OPTION_CHARS)
+    {
+        return Some(format!(
+            "an option is longer than {} characters",
+            MAX_OPTION_CHARS
+        ));
+    }
+    let total: usize = options.iter().map(|option| option.chars().count()).sum();
+    if total > MAX_OPTIONS_CHARS {
+        return Some(format!(
+            "its options have {} characters together, more than {}",
+            total, MAX_OPTIONS_CHARS
+        ));
+    }
+    None
+}
+
+/// A template name as it's stored: lowercase letters, digits, `-` and `_`.
+fn parse_template_name(text: &str) -> Result<String, String> {
+    let name = text.trim().to_lowercase();
+    let valid = !name.is_empty()
+        && name.chars().count() <= MAX_TEMPLATE_NAME_CHARS
+        && name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_');
+    if valid {
+        Ok(name)
+    } else {
+        Err(format!(
+            "❌ Template names are 1 to {} letters, digits, `-` or `_`, e.g. `game-night`.",
+            MAX_TEMPLATE_NAME_CHARS
+        ))
+    }
+}
+
+/// A template's options, split like poll options so `"pizza place"` is one.
+fn parse_template_options(text: &str) -> Result<Vec<String>, String> {
+    let options = split_args(text).map_err(|e| format!("❌ {}", e))?;
+    match options_problem(&options) {
+        Some(problem) => Err(format!(
+            "❌ Templates need 2 to {} options of up to {} characters each ({} together), but {}.",
+            REACTION_EMOJIS.len(),
+            MAX_OPTION_CHARS,
+            MAX_OPTIONS_CHARS,
+            problem
+        )),
+        None => Ok(options),
+    }
+}
+
+/// Why a stored template can't be used, if it can't: the same rules as
+/// `polltemplate save`.
+fn template_problem(name: &str, options: &[String]) -> Option<String> {
+    if parse_template_name(name).ok().as_deref() != Some(name) {
+        return Some("the name isn't a valid template name".to_string());
+    }
+    options_problem(options)
+}
+
+/// Drop templates that `polltemplate save` wouldn't have accepted.
+fn validate_poll_templates(data: &mut PollTemplatesData) -> Vec<ConfigProblem> {
+    let mut problems = Vec::new();
+    for (&guild_id, templates) in &mut data.guilds {
+        templates.retain(|name, options| {
+            let Some(problem) = template_problem(name, options) else {
+                return true;
+            };
+            problems.push(ConfigProblem::invalid(
+                guild_id,
+                format!("template {}", name),
+                problem,
+                &*options,
+            ));
+            false
+        });
+    }
+    data.guilds.retain(|_, templates| !templates.is_empty());
+    problems
+}
+
+/// Check the poll templates, see [`crate::config_check`].
+pub fn check_poll_templates(_live: &LiveIds, quarantine: bool) -> Vec<ConfigProblem> {
+    check_store(
+        &POLL_TEMPLATES,
+        quarantine,
+        validate_poll_templates,
+        |data| {
+            if let Err(e) = save_poll_templates(data) {
+                log::error!("Failed to save poll templates: {}", e);
+            }
+        },
+    )
+}
+
+/// Whether saving a template added it or replaced one of the same name.
+#[derive(Debug, PartialEq)]
+enum Saved {
+    Added,
+    Replaced,
+}
+
+/// Save a guild's template, replacing one of the same name. `Err` when the
+/// guild already has as many templates as it may.
+fn save_template(
+    data: &mut PollTemplatesData,
+    guild_id: u64,
+    name: String,
+    options: Vec<String>,
+) -> Result<Saved, String> {
+    let templates = data.guilds.entry(guild_id).or_default();
+    if templates.len() >= MAX_TEMPLATES_PER_GUILD && !templates.contains_key(&name) {
+        return Err(format!(
+            "❌ This server already has {} poll templates. Delete one first!",
+            MAX_TEMPLATES_PER_GUILD
+        ));
+    }
+    Ok(match templates.insert(name, options) {
+        Some(_) => Saved::Replaced,
+        None => Saved::Added,
+    })
+}
+
+fn template_options(data: &PollTemplatesData, guild_id: u64, name: &str) -> Option<Vec<String>> {
+    data.guilds.get(&guild_id)?.get(name).cloned()
+}
+
+/// Delete a guild's template. Returns whether it had one of that name.
+fn delete_template(data: &mut PollTemplatesData, guild_id: u64, name: &str) -> bool {
+    let Some(templates) = data.guilds.get_mut(&guild_id) else {
+        return false;
+    };
+    let removed = templates.remove(name).is_some();
+    if templates.is_empty() {
+        data.guilds.remove(&guild_id);
+    }
+    removed
+}
+
+/// One line per template: its name and options.
+fn template_lines(templates: &BTreeMap<String, Vec<String>>) -> Vec<String> {
+    templates
+        .iter()
+        .map(|(name, options)| format!("`{}`: {}", name, options.join(", ")))
+        .collect()
+}
+
+/// Whether the author may change the templates: bot owners, and members
+/// with Manage Messages. Replies when they may not.
+async fn can_edit_templates(ctx: Context<'_>) -> Result<bool, Error> {
+    let allowed = is_protected_user(&ctx.author().name) || {
+        match (ctx.author_member().await, ctx.guild_channel().await) {
+            (Some(member), Some(channel)) => ctx.guild().is_some_and(|guild| {
+                guild
+                    .user_permissions_in(&channel, &member)
+                    .manage_messages()
+            }),
+            _ => false,
+        }
+    };
+    if !allowed {
+        ctx.say("❌ You need the Manage Messages permission to change poll templates!")
+            .await?;
+    }
+    Ok(allowed)
+}
+
+/// Command check for commands that change the templates.
+async fn poll_templates_writable(ctx: Context<'_>) -> Result<bool, Error> {
+    Ok(can_edit_templates(ctx).await?
+        && check_store_writable(ctx, &storage::data_path(POLL_TEMPLATES_FILE)).await?)
+}
+
+/// Save sets of poll options for this server and start polls from them
+#[poise::command(
+    prefix_command,
+    slash_command,
+    guild_only,
+    subcommands(
+        "poll_template_save",
+        "poll_template_use",
+        "poll_template_list",
+        "poll_template_delete"
+    )
+)]
+pub async fn polltemplate(_ctx: Context<'_>) -> Result<(), Error> {
+    Ok(())
+}
+
+/// Save a set of options under a name (replaces one of the same name)
+#[poise::command(
+    prefix_command,
+    slash_command,
+    guild_only,
+    rename = "save",
+    check = "poll_templates_writable"
+)]
+pub async fn poll_template_save(
+    ctx: Context<'_>,
+    #[description = "Template name, e.g. game-night"] name: String,
+    #[description = "Options, quoted if they have spaces: Friday Saturday \"next week\""]
+    #[rest]
+    options: String,
+) -> Result<(), Error> {
+    log::info!(
+        "Poll template save command called by {} for '{}'",
+        ctx.author().name,
+        name
+    );
+
+    let Some(guild_id) = ctx.guild_id() else {
+        return Ok(());
+    };
+    let parsed =
+        parse_template_name(&name).and_then(|name| Ok((name, parse_template_options(&options)?)));
+    let (name, options) = match parsed {
+        Ok(parsed) => parsed,
+        Err(error) => {
+            ctx.say(error).await?;
+            return Ok(());
+        }
+    };
+
+    let count = options.len();
+    let saved =
+        match update_templates(|data| save_template(data, guild_id.get(), name.clone(), options)) {
+            Ok(saved) => saved,
+            Err(e) => {
+                log::error!("Failed to save poll templates: {}", e);
+                ctx.say(format!("❌ Failed to save poll template: {e}"))
+                    .await?;
+                return Ok(());
+            }
+        };
+
+    let reply = match saved {
+        Ok(Saved::Added) => format!(
+            "✅ Saved poll template `{}` with {} options. Start a poll with `-polltemplate use {} <question>`.",
+            name, count, name
+        ),
+        Ok(Saved::Replaced) => format!(
+            "✅ Replaced poll template `{}`; it now has {} options.",
+            name, count
+        ),
+        Err(error) => error,
+    };
+    ctx.say(reply).await?;
+    Ok(())
+}
+
+/// Start a poll with a saved template's options
+#[poise::command(prefix_command, slash_command, guild_only, rename = "use")]
+pub async fn poll_template_use(
+    ctx: Context<'_>,
+    #[description = "Template name"] name: String,
+    #[description = "The poll's question, optionally after --duration 2h to close it then"]
+    #[rest]
+    question: String,
+) -> Result<(), Error> {
+    log::info!(
+        "Poll template use command called by {} for '{}'",
+        ctx.author().name,
+        name
+    );
+
+    let Some(guild_id) = ctx.guild_id() else {
+        return Ok(());
+    };
+    let (duration, question) = match take_duration_flag(&question) {
+        Ok(parsed) => parsed,
+        Err(error) => {
+            ctx.say(error).await?;
+            return Ok(());
+        }
+    };
+    let question = question.trim();
+    if question.is_empty() {
+        ctx.say("❌ Question cannot be empty!").await?;
+        return Ok(());
+    }
+    let name = name.trim().to_lowercase();
+    let options = template_options(&POLL_TEMPLATES.read(), guild_id.get(), &name);
+    let Some(options) = options else {
+        ctx.say(format!(
+            "❌ This server has no poll template `{}`. See `-polltemplate list`.",
+            name
+        ))
+        .await?;
+        return Ok(());
+    };
+    post_poll(ctx, question, &numbered_options(&options), duration).await
+}
+
+/// List this server's poll templates
+#[poise::command(prefix_command, slash_command, guild_only, rename = "list")]
+pub async fn poll_template_list(ctx: Context<'_>) -> Result<(), Error> {
+    log::info!("Poll template list command called by {}", ctx.author().name);
+
+    let Some(guild_id) = ctx.guild_id() else {
+        return Ok(());
+    };
+    let lines = POLL_TEMPLATES
+        .read()
+        .guilds
+        .get(&guild_id.get())
+        .map(template_lines)
+        .unwrap_or_default();
+    if lines.is_empty() {
+        ctx.say("📭 This server has no poll templates yet. Save one with `-polltemplate save <name> <options...>`.")
+            .await?;
+        return Ok(());
+    }
+
+    let embed = serenity::CreateEmbed::new()
+        .title("📋 Poll Templates")
+        .description(lines.join("\n"))
+        .color(0x5865F2)
+        .footer(serenity::CreateEmbedFooter::new(format!(
+            "{} of {} • -polltemplate use <name> <question>",
+            lines.len(),
+            MAX_TEMPLATES_PER_GUILD
+        )));
+    ctx.send(poise::CreateReply::default().embed(embed)).await?;
+    Ok(())
+}
+
+/// Delete a poll template
+#[poise::command(
+    prefix_command,
+    slash_command,
+    guild_only,
+    rename = "delete",
+    check = "poll_templates_writable"
+)]
+pub async fn poll_template_delete(
+    ctx: Context<'_>,
+    #[description = "Template name"] name: String,
+) -> Result<(), Error> {
+    log::info!(
+        "Poll template delete command called by {} for '{}'",
+        ctx.author().name,
+        name
+    );
+
+    let Some(guild_id) = ctx.guild_id() else {
+        return Ok(());
+    };
+    let name = name.trim().to_lowercase();
+    let deleted = match update_templates(|data| delete_template(data, guild_id.get(), &name)) {
+        Ok(deleted) => deleted,
+        Err(e) => {
+            log::error!("Failed to save poll templates: {}", e);
+            ctx.say(format!("❌ Failed to save poll templates: {e}"))
+                .await?;
+            return Ok(());
+        }
+    };
+
+    if deleted {
+        ctx.say(format!("🗑️ Deleted poll template `{}`.", name))
+            .await?;
+    } else {
+        ctx.say(format!("❌ This server has no poll template `{}`!", name))
+            .await?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn strings(items: &[&str]) -> Vec<String> {
+        items.iter().map(|item| item.to_string()).collect()
+    }
+
+    #[test]
+    fn test_validate_poll_templates() {
+        let mut data = PollTemplatesData::default();
+        let templates = data.guilds.entry(1).or_default();
+        templates.insert("game-night".to_string(), strings(&["Fri", "Sat"]));
+        templates.insert("Bad Name".to_string(), strings(&["a", "b"]));
+        templates.insert("one".to_string(), strings(&["only"]));
+        templates.insert("blank".to_string(), strings(&["a", " "]));
+        templates.insert(
+            "long".to_string(),
+            vec!["x".repeat(MAX_OPTION_CHARS + 1); 2],
+        );
+        data.guilds
+            .entry(2)
+            .or_default()
+            .insert("eleven".to_string(), vec!["x".to_string(); 11]);
+
+        let problems = validate_poll_templates(&mut data);
+        assert_eq!(problems.len(), 5);
+        assert_eq!(data.guilds.len(), 1);
+        assert_eq!(data.guilds[&1].keys().collect::<Vec<_>>(), ["game-night"]);
+    }
+
+    #[test]
+    fn test_parse_template_name() {
+        assert_eq!(
+            parse_template_name(" Game-Night "),
+            Ok("game-night".to_string())
+        );
+        assert_eq!(parse_template_name("lunch_2"), Ok("lunch_2".to_string()));
+        assert!(parse_template_name("").is_err());
+        assert!(parse_template_name("game night").is_err());
+        assert!(parse_template_name("pizza!").is_err());
+        assert!(parse_template_name(&"a".repeat(MAX_TEMPLATE_NAME_CHARS)).is_ok());
+        assert!(parse_template_name(&"a".repeat(MAX_TEMPLATE_NAME_CHARS + 1)).is_err());
+    }
+
+    #[test]
+    fn test_parse_template_options() {
+        assert_eq!(
+            parse_template_options(r#"Friday Saturday "next week""#),
+            Ok(strings(&["Friday", "Saturday", "next week"]))
+        );
+        assert!(parse_template_options("Friday").is_err());
+        assert!(parse_template_options(r#"Friday """#).is_err());
+        assert!(parse_template_options("1 2 3 4 5 6 7 8 9 10 11").is_err());
+        assert!(parse_template_options(r#"Friday "Saturday"#).is_err());
+        let long = "x".repeat(MAX_OPTION_CHARS);
+        assert!(parse_template_options(&format!("{long} {long} yes")).is_ok());
+        assert!(parse_template_options(&format!("{long}x yes"))
+            .unwrap_err()
+            .contains("up to 50 characters"));
+        assert!(parse_template_options(&format!("{long} {long} {long}")).is_err());
+    }
+
+    #[test]
+    fn test_full_template_list_fits_embed() {
+        let mut data = PollTemplatesData::default();
+        let options = vec!["x".repeat(MAX_OPTIONS_CHARS / REACTION_EMOJIS.len()); 10];
+        assert_eq!(options_problem(&options), None);
+        for i in 0..MAX_TEMPLATES_PER_GUILD {
+            let name = format!("{:a<width$}", i, width = MAX_TEMPLATE_NAME_CHARS);
+            assert!(save_template(&mut data, 1, name, options.clone()).is_ok());
+        }
+        let list = template_lines(&data.guilds[&1]).join("\n");
+        assert!(list.chars().count() <= 4096, "{}", list.len());
+    }
+
+    #[test]
+    fn test_template_crud() {
+        let mut data = PollTemplatesData::default();
+        let weekdays = strings(&["Friday", "Saturday"]);
+        assert_eq!(
+            save_template(&mut data, 1, "game-night".to_string(), weekdays.clone()),
+            Ok(Saved::Added)
+        );
+        assert_eq!(template_options(&data, 1, "game-night"), Some(weekdays));
+        // Templates are per server
+        assert_eq!(template_options(&data, 2, "game-night"), None);
+
+        let longer = strings(&["Friday", "Saturday", "Sunday"]);
+        assert_eq!(
+            save_template(&mut data, 1, "game-night".to_string(), longer.clone()),
+            Ok(Saved::Replaced)
+        );
+        assert_eq!(template_options(&data, 1, "game-night"), Some(longer));
+        assert_eq!(
+            template_lines(&data.guilds[&1]),
+            ["`game-night`: Friday, Saturday, Sunday"]
+        );
+
+        assert!(delete_template(&mut data, 1, "game-night"));
+        assert!(!delete_template(&mut data, 1, "game-night"));
+        assert!(!delete_template(&mut data, 2, "game-night"));
+        assert!(data.guilds.is_empty());
+    }
+
+    #[test]
+    fn test_template_limit() {
+        let mut data = PollTemplatesData::default();
+        let options = strings(&["yes", "no"]);
+        for i in 0..MAX_TEMPLATES_PER_GUILD {
+            assert!(save_template(&mut data, 1, format!("t{}", i), options.clone()).is_ok());
+        }
+        assert!(
+            save_template(&mut data, 1, "one-more".to_string(), options.clone())
+                .unwrap_err()
+                .contains("already has 20")
+        );
+        // Replacing one still works at the limit, and other servers have their own
+        assert_eq!(
+            save_template(&mut data, 1, "t0".to_string(), options.clone()),
+            Ok(Saved::Replaced)
+        );
+        assert_eq!(
+            save_template(&mut data, 2, "one-more".to_string(), options),
+            Ok(Saved::Added)
+        );
+    }
+}
@@ -1,14 +1,199 @@
+use crate::commands::reactmap::guild_overrides;
 use crate::{Context, Error};
 use poise::serenity_prelude as serenity;
 use serenity::ReactionType;
 use std::collections::HashMap;
 
-/// React to a message with emoji letters
+/// Discord allows at most 20 different reactions on a message.
+const MAX_REACTIONS: usize = 20;
+
+/// Named sets of emojis for `react preset <name>`, for common reactions that
+/// would be slow to spell out.
+pub const REACTION_PRESETS: &[(&str, &[&str])] = &[
+    ("hype", &["🔥", "🎉", "💯", "🚀", "🙌"]),
+    ("sad", &["😢", "😭", "💔", "🥺", "😞"]),
+    ("love", &["❤️", "😍", "🥰", "💕", "😘"]),
+    ("laugh", &["😂", "🤣", "💀", "😆", "😹"]),
+    ("wow", &["😮", "🤯", "😱", "👀", "‼️"]),
+    ("angry", &["😠", "😡", "🤬", "💢", "👿"]),
+    ("gg", &["🏆", "👏", "🥇", "🎮", "💪"]),
+    ("thanks", &["🙏", "💖", "🤗", "✨", "👍"]),
+    ("cool", &["😎", "🆒", "🧊", "🤙", "✨"]),
+];
+
+/// The emojis for a preset, matched case-insensitively.
+fn find_preset(name: &str) -> Option<&'static [&'static str]> {
+    REACTION_PRESETS
+        .iter()
+        .find(|(preset, _)| preset.eq_ignore_ascii_case(name.trim()))
+        .map(|(_, emojis)| *emojis)
+}
+
+/// The preset name if `text` is in the `preset <name>` form.
+fn preset_name(text: &str) -> Option<&str> {
+    let mut words = text.split_whitespace();
+    if !words.next()?.eq_ignore_ascii_case("preset") {
+        return None;
+    }
+    Some(words.next().unwrap_or(""))
+}
+
+/// The emojis from `preset` still to add, leaving out ones the bot already
+/// reacted with and stopping at the reaction cap.
+fn preset_reactions<'a>(preset: &[&'a str], existing: &[ReactionType]) -> Vec<&'a str> {
+    let already_reacted = |emoji: &str| {
+        existing
+            .iter()
+            .any(|reaction| matches!(reaction, ReactionType::Unicode(e) if e == emoji))
+    };
+    let free_slots = MAX_REACTIONS.saturating_sub(existing.len());
+    preset
+        .iter()
+        .copied()
+        .filter(|emoji| !already_reacted(emoji))
+        .take(free_slots)
+        .collect()
+}
+
+/// Hand and person emojis that can be asked for by name with `--tone`, since
+/// spelling them out letter by letter would never pick them.
+const TONED_EMOJIS: &[(&str, &str)] = &[
+    ("wave", "👋"),
+    ("thumbsup", "👍"),
+    ("thumbsdown", "👎"),
+    ("clap", "👏"),
+    ("pray", "🙏"),
+    ("ok", "👌"),
+    ("peace", "✌️"),
+    ("muscle", "💪"),
+    ("raisedhands", "🙌"),
+    ("highfive", "✋"),
+    ("fist", "👊"),
+    ("point", "👉"),
+    ("callme", "🤙"),
+    ("handshake", "🤝"),
+    ("shrug", "🤷"),
+    ("facepalm", "🤦"),
+    ("dance", "💃"),
+];
+
+/// Emojis that accept a skin tone modifier (Unicode's Emoji_Modifier_Base),
+/// limited to the hands and people that show up as reactions.
+const SKIN_TONE_BASES: &[char] = &[
+    '👋', '🤚', '🖐', '✋', '🖖', '👌', '🤌', '🤏', '✌', '🤞', '🫰', '🤟', '🤘', '🤙', '👈', '👉',
+    '👆', '👇', '☝', '🫵', '👍', '👎', '✊', '👊', '🤛', '🤜', '👏', '🙌', '🫶', '👐', '🤲', '🤝',
+    '🙏', '✍', '💅', '🤳', '💪', '🦵', '🦶', '👂', '👃', '👶', '🧒', '👦', '👧', '🧑', '👨', '👩',
+    '🧓', '👴', '👵', '🙅', '🙆', '💁', '🙋', '🙇', '🤦', '🤷', '👮', '💂', '🥷', '👷', '🤴', '👸',
+    '👼', '🎅', '🤶', '🦸', '🦹', '🧙', '🧚', '🧛', '🧜', '🧝', '💆', '💇', '🚶', '🏃', '💃', '🕺',
+    '🏄', '🏊', '🏋', '🚴', '🤸', '🤹', '🧘',
+];
+
+/// `--tone N` picks one of the five Fitzpatrick skin tone modifiers.
+const MAX_SKIN_TONE: u8 = 5;
+
+/// `emoji` in skin tone `tone` (1-5), or unchanged if it has no toned form.
+fn apply_skin_tone(emoji: &str, tone: u8) -> String {
+    let supports_tone = emoji
+        .chars()
+        .next()
+        .is_some_and(|c| SKIN_TONE_BASES.contains(&c));
+    let modifier = (1..=MAX_SKIN_TONE)
+        .contains(&tone)
+        .then(|| char::from_u32(0x1F3FA + tone as u32))
+        .flatten();
+    match modifier {
+        // The modifier takes the place of the emoji presentation selector
+        Some(modifier) if supports_tone => {
+            let mut toned: String = emoji.chars().filter(|&c| c != '\u{FE0F}').collect();
+            toned.push(modifier);
+            toned
+        }
+        _ => emoji.to_string(),
+    }
+}
+
+/// Split a leading `--tone N` off the react text.
+fn parse_tone(text: &str) -> Result<(Option<u8>, String), String> {
+    let mut words = text.split_whitespace();
+    if words.next() != Some("--tone") {
+        return Ok((None, text.to_string()));
+    }
+    let tone = words
+        .next()
+        .and_then(|n| n.parse::<u8>().ok())
+        .filter(|n| (1..=MAX_SKIN_TONE).contains(n))
+        .ok_or(format!(
+            "❌ Skin tone must be a number from 1 (lightest) to {} (darkest). Example: `-react --tone 3 wave`",
+            MAX_SKIN_TONE
+        ))?;
+    Ok((Some(tone), words.collect::<Vec<_>>().join(" ")))
+}
+
+/// The reactions that spell `text`, each emoji used at most once. A
+/// server's override for a character (see `reactmap`) is tried before the
+/// built-in emojis for it. With a tone, words naming a hand or person emoji
+/// (see [`TONED_EMOJIS`]) react with that emoji, and every emoji that can
+/// take the tone gets it.
+fn text_reactions(
+    text: &str,
+    tone: Option<u8>,
+    emoji_map: &HashMap<char, Vec<&'static str>>,
+    overrides: &HashMap<char, String>,
+) -> Vec<String> {
+    let tone_emoji = |emoji: &str| match tone {
+        Some(tone) => apply_skin_tone(emoji, tone),
+        None => emoji.to_string(),
+    };
+    let mut reactions: Vec<String> = Vec::new();
+    let mut pick = |options: &[&str], ch: Option<char>| {
+        let unused = options
+            .iter()
+            .map(|emoji| tone_emoji(emoji))
+            .find(|emoji| !reactions.contains(emoji));
+        match (unused, ch) {
+            (Some(emoji), _) => reactions.push(emoji),
+            (None, Some(ch)) => log::debug!("No available emoji for character '{ch}' (all used)"),
+            (None, None) => {}
+        }
+    };
+
+    let options_for = |ch: char| -> Option<Vec<&str>> {
+        let custom = overrides.get(&ch).map(String::as_str);
+        let defaults = emoji_map.get(&ch).into_iter().flatten().copied();
+        let options: Vec<&str> = custom.into_iter().chain(defaults).collect();
+        (!options.is_empty()).then_some(options)
+    };
+
+    for (i, word) in text.to_lowercase().split(' ').enumerate() {
+        if i > 0 {
+            if let Some(options) = options_for(' ') {
+                pick(&options, Some(' '));
+            }
+        }
+        let named = TONED_EMOJIS.iter().find(|(name, _)| *name == word);
+        if let (Some(_), Some((_, emoji))) = (tone, named) {
+            pick(&[emoji], None);
+            continue;
+        }
+        for ch in word.chars() {
+            match options_for(ch) {
+                Some(options) => pick(&options, Some(ch)),
+                None => log::debug!("No emoji mapping for character '{ch}'"),
+            }
+        }
+    }
+    reactions
+}
+
+/// React to a message with emoji letters, or a preset like `preset hype`
 #[poise::command(prefix_command, slash_command)]
 pub async fn react(
     ctx: Context<'_>,
-    #[description = "Text to react with (e.g., 'lol', 'cool')"] text: String,
-    #[description = "Message ID to react to (for slash commands)"] message_id: Option<String>,
+    #[description = "Text to react with (e.g., 'lol', 'cool'), 'preset <name>' or '--tone 1-5 <text>'"]
+    text: String,
+    #[description = "Message ID to react to (for slash commands)"]
+    #[rest]
+    message_id: Option<String>,
 ) -> Result<(), Error> {
     log::info!(
         "React command called by {} with text: '{}'",
@@ -29,6 +214,41 @@ pub async fn react(
         }
     }
 
+    // Prefix commands take the message from the reply, so everything after
+    // the first word is more text rather than a message ID
+    let input = match (&ctx, message_id.as_deref()) {
+        (poise::Context::Prefix(_), Some(rest)) => format!("{} {}", text, rest),
+        _ => text.clone(),
+    };
+    let (tone, text) = match parse_tone(&input) {
+        Ok(parsed) => parsed,
+        Err(message) => {
+            ctx.say(message).await?;
+            return Ok(());
+        }
+    };
+    if text.trim().is_empty() {
+        ctx.say("❌ Please provide text to react with! Example: `-react --tone 3 wave`")
+            .await?;
+        return Ok(());
+    }
+
+    let preset_emojis = match preset_name(&text).map(|name| (name, find_preset(name))) {
+        Some((_, Some(emojis))) => Some(emojis),
+        Some((name, None)) => {
+            let names: Vec<&str> = REACTION_PRESETS.iter().map(|(name, _)| *name).collect();
+            let shown = if name.is_empty() { "(none)" } else { name };
+            ctx.say(format!(
+                "❌ Unknown preset `{}`. Available presets: {}",
+                shown,
+                names.join(", ")
+            ))
+            .await?;
+            return Ok(());
+        }
+        None => None,
+    };
+
     // Get the message to react to
     let replied_message = match ctx {
         poise::Context::Prefix(prefix_ctx) => {
@@ -43,6 +263,7 @@ pub async fn react(
         poise::Context::Application(_) => {
             // For slash commands, require message_id parameter
             let msg_id_str = message_id
+                .as_deref()
                 .ok_or("❌ For slash commands, please provide the message ID to react to!")?;
 
             let msg_id = msg_id_str.parse::<u64>().map_err(|_| {
@@ -60,48 +281,63 @@ pub async fn react(
         }
     };
 
+    if let Some(emojis) = preset_emojis {
+        let mut reactions_added = 0;
+        let existing: Vec<ReactionType> = replied_message
+            .reactions
+            .iter()
+            .map(|reaction| reaction.reaction_type.clone())
+            .collect();
+        for emoji in preset_reactions(emojis, &existing) {
+            let emoji = match tone {
+                Some(tone) => apply_skin_tone(emoji, tone),
+                None => emoji.to_string(),
+            };
+            match replied_message
+                .react(&ctx.http(), ReactionType::Unicode(emoji.clone()))
+                .await
+            {
+                Ok(_) => {
+                    reactions_added += 1;
+                    tokio::time::sleep(tokio::time::Duration::from_millis(100)).await;
+                }
+                Err(e) => log::warn!("Failed to add preset reaction {emoji}: {e}"),
+            }
+        }
+
+        if reactions_added == 0 {
+            if let poise::Context::Application(_) = &ctx {
+                ctx.say("❌ Couldn't add any reactions. The message may already have the preset's emojis or too many reactions.")
+                    .await?;
+            }
+        }
+        log::info!("React preset completed. Added {reactions_added} reactions");
+        return Ok(());
+    }
+
     // Create emoji mapping with fallbacks
     let emoji_map = create_emoji_mapping();
-
-    let text_lower = text.to_lowercase();
-    let mut used_emojis = std::collections::HashSet::new();
+    let overrides = ctx
+        .guild_id()
+        .map(|guild_id| guild_overrides(guild_id.get()))
+        .unwrap_or_default();
     let mut reactions_added = 0;
 
-    // Process each character
-    for ch in text_lower.chars() {
-        if let Some(emoji_options) = emoji_map.get(&ch) {
-            // Find the first unused emoji for this character
-            let mut emoji_to_use = None;
-            for emoji in emoji_options {
-                if !used_emojis.contains(emoji) {
-                    emoji_to_use = Some(emoji);
-                    break;
-                }
-            }
+    for emoji in text_reactions(&text, tone, &emoji_map, &overrides) {
+        // Overrides may be custom emojis, stored as `<:name:id>`
+        let reaction = ReactionType::try_from(emoji.as_str())
+            .unwrap_or_else(|_| ReactionType::Unicode(emoji.clone()));
+        match replied_message.react(&ctx.http(), reaction).await {
+            Ok(_) => {
+                reactions_added += 1;
+                log::debug!("Added reaction {emoji}");
 
-            if let Some(emoji) = emoji_to_use {
-                // Try to add the reaction
-                match replied_message
-                    .react(&ctx.http(), ReactionType::Unicode(emoji.to_string()))
-                    .await
-                {
-                    Ok(_) => {
-                        used_emojis.insert(emoji);
-                        reactions_added += 1;
-                        log::debug!("Added reaction {emoji} for character '{ch}'");
-
-                        // Small delay to avoid rate limiting
-                        tokio::time::sleep(tokio::time::Duration::from_millis(100)).await;
-                    }
-                    Err(e) => {
-                        log::warn!("Failed to add reaction {emoji} for character '{ch}': {e}");
-                    }
-                }
-            } else {
-                log::debug!("No available emoji for character '{ch}' (all used)");
+                // Small delay to avoid rate limiting
+                tokio::time::sleep(tokio::time::Duration::from_millis(100)).await;
+            }
+            Err(e) => {
+                log::warn!("Failed to add reaction {emoji}: {e}");
             }
-        } else {
-            log::debug!("No emoji mapping for character '{ch}'");
         }
     }
 
@@ -345,6 +581,106 @@ mod tests {
         assert!(l_options.len() > 1); // Multiple fallbacks
     }
 
+    #[test]
+    fn test_preset_name() {
+        assert_eq!(preset_name("preset hype"), Some("hype"));
+        assert_eq!(preset_name("Preset sad"), Some("sad"));
+        assert_eq!(preset_name("preset"), Some(""));
+        assert_eq!(preset_name("lol 123"), None);
+        assert_eq!(preset_name("presets"), None);
+
+        assert_eq!(
+            find_preset("HYPE"),
+            Some(&["🔥", "🎉", "💯", "🚀", "🙌"][..])
+        );
+        assert!(find_preset("nope").is_none());
+    }
+
+    #[test]
+    fn test_preset_reactions_respect_cap_and_existing() {
+        let reaction = |emoji: &str| ReactionType::Unicode(emoji.to_string());
+        let hype = find_preset("hype").unwrap();
+
+        assert_eq!(preset_reactions(hype, &[]), hype.to_vec());
+
+        let existing = vec![reaction("🔥"), reaction("💯")];
+        assert_eq!(preset_reactions(hype, &existing), vec!["🎉", "🚀", "🙌"]);
+
+        let existing: Vec<_> = (0..18).map(|i| reaction(&i.to_string())).collect();
+        assert_eq!(preset_reactions(hype, &existing), vec!["🔥", "🎉"]);
+    }
+
+    #[test]
+    fn test_apply_skin_tone() {
+        assert_eq!(apply_skin_tone("👋", 3), "👋\u{1F3FD}");
+        assert_eq!(apply_skin_tone("👍", 1), "👍\u{1F3FB}");
+        // The presentation selector is dropped in favour of the modifier
+        assert_eq!(apply_skin_tone("✌️", 5), "✌\u{1F3FF}");
+        // Emojis without a toned form are left alone
+        assert_eq!(apply_skin_tone("🔥", 3), "🔥");
+        assert_eq!(apply_skin_tone("🇦", 3), "🇦");
+    }
+
+    #[test]
+    fn test_parse_tone() {
+        assert_eq!(
+            parse_tone("--tone 3 wave"),
+            Ok((Some(3), "wave".to_string()))
+        );
+        assert_eq!(parse_tone("lol"), Ok((None, "lol".to_string())));
+        assert!(parse_tone("--tone 6 wave").is_err());
+        assert!(parse_tone("--tone wave").is_err());
+    }
+
+    #[test]
+    fn test_text_reactions_with_tone() {
+        let map = create_emoji_mapping();
+        let none = HashMap::new();
+        assert_eq!(
+            text_reactions("wave", Some(3), &map, &none),
+            vec!["👋\u{1F3FD}"]
+        );
+        // Without a tone, gesture names are spelled out like any other word
+        assert_eq!(
+            text_reactions("wave", None, &map, &none),
+            vec!["🇼", "🇦", "🇻", "🇪"]
+        );
+        assert_eq!(
+            text_reactions("hi wave", Some(2), &map, &none),
+            vec!["🇭", "🇮", "⬜", "👋\u{1F3FC}"]
+        );
+    }
+
+    #[test]
+    fn test_text_reactions_with_overrides() {
+        let map = create_emoji_mapping();
+        let overrides = HashMap::from([
+            ('a', "<:letter_a:600404340292059257>".to_string()),
+            ('l', "🦒".to_string()),
+        ]);
+
+        // Overrides come before the built-in emojis
+        assert_eq!(
+            text_reactions("al", None, &map, &overrides),
+            vec!["<:letter_a:600404340292059257>", "🦒"]
+        );
+        // Once an override is used, repeats fall back to the built-in order
+        assert_eq!(
+            text_reactions("aaa", None, &map, &overrides),
+            vec!["<:letter_a:600404340292059257>", "🇦", "🅰️"]
+        );
+        // An override that's also a built-in emoji for another character
+        // takes it first, so that character moves on to its next one
+        let overrides = HashMap::from([('x', "🇦".to_string())]);
+        assert_eq!(
+            text_reactions("xa", None, &map, &overrides),
+            vec!["🇦", "🅰️"]
+        );
+        // Characters without built-in emojis can be spelled with an override
+        let overrides = HashMap::from([('%', "💯".to_string())]);
+        assert_eq!(text_reactions("%", None, &map, &overrides), vec!["💯"]);
+    }
+
     #[test]
     fn test_duplicate_handling() {
         let map = create_emoji_mapping();
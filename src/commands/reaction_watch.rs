@@ -0,0 +1,356 @@
+//! Reaction milestones: `-remind when 10 🔥` pings you once a message has
+//! that many of a reaction. Watchers live in their own store and are checked
+//! by polling, separately from the time-based reminders.
+
+use crate::commands::autoreact::parse_emoji;
+use crate::commands::maintenance::Maintenance;
+use crate::storage;
+use crate::utils::messages::parse_message_ref;
+use crate::utils::permissions::{check_author_can_read, is_missing_permission, is_not_found};
+use crate::{Context, Error};
+use chrono::{DateTime, Duration, Utc};
+use parking_lot::Mutex;
+use poise::serenity_prelude as serenity;
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+use tokio::time::{interval, Duration as TokioDuration};
+
+const WATCHERS_FILE: &str = "reaction_watchers.json";
+
+/// How often watched messages are re-fetched.
+const CHECK_INTERVAL_SECS: u64 = 120;
+
+/// Watchers that never hit their milestone are dropped after this long.
+const WATCHER_LIFETIME_DAYS: i64 = 30;
+
+const MAX_WATCHERS_PER_USER: usize = 10;
+const MAX_THRESHOLD: u64 = 1000;
+
+/// Someone waiting for a message to reach `threshold` of `emoji`.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct ReactionWatcher {
+    pub id: u64,
+    pub user_id: u64,
+    pub guild_id: Option<u64>,
+    pub channel_id: u64,
+    pub message_id: u64,
+    /// As typed: a unicode emoji or `<:name:id>` for custom ones.
+    pub emoji: String,
+    pub threshold: u64,
+    pub created_at: DateTime<Utc>,
+}
+
+#[derive(Serialize, Deserialize, Default)]
+struct WatcherData {
+    watchers: Vec<ReactionWatcher>,
+    next_id: u64,
+}
+
+/// Serializes changes to the store. The poller does its network calls on a
+/// snapshot and only holds this for the final load-modify-save, so a watcher
+/// added meanwhile isn't overwritten.
+static WATCHERS_LOCK: Mutex<()> = Mutex::new(());
+
+fn load_watchers() -> WatcherData {
+    storage::load(WATCHERS_FILE)
+}
+
+fn save_watchers(data: &WatcherData) -> Result<(), Error> {
+    storage::save(WATCHERS_FILE, data)
+}
+
+/// Get pinged when a message reaches a number of reactions
+#[poise::command(prefix_command, slash_command, rename = "when")]
+pub async fn remind_when(
+    ctx: Context<'_>,
+    #[description = "How many reactions to wait for (1-1000)"] count: u64,
+    #[description = "The reaction to count, e.g. 🔥"] emoji: String,
+    #[description = "Link to the message (optional when replying to it)"] message: Option<String>,
+) -> Result<(), Error> {
+    log::info!(
+        "Remind when command called by {} for {} {}",
+        ctx.author().name,
+        count,
+        emoji
+    );
+
+    if !(1..=MAX_THRESHOLD).contains(&count) {
+        ctx.say(format!(
+            "❌ The reaction count must be between 1 and {}!",
+            MAX_THRESHOLD
+        ))
+        .await?;
+        return Ok(());
+    }
+    if parse_emoji(&emoji).is_none() {
+        ctx.say("❌ That doesn't look like an emoji!").await?;
+        return Ok(());
+    }
+
+    let replied_to = match ctx {
+        poise::Context::Prefix(prefix_ctx) => prefix_ctx
+            .msg
+            .referenced_message
+            .as_ref()
+            .map(|msg| (msg.channel_id, msg.id)),
+        _ => None,
+    };
+    let target = match message.as_deref() {
+        Some(text) => parse_message_ref(text, ctx.channel_id()),
+        None => replied_to,
+    };
+    let Some((channel_id, message_id)) = target else {
+        ctx.say("❌ Reply to the message you want to watch, or give its link!")
+            .await?;
+        return Ok(());
+    };
+    if let Err(refusal) = check_author_can_read(ctx, channel_id).await {
+        ctx.say(refusal).await?;
+        return Ok(());
+    }
+
+    // Make sure the message exists and the bot can see it before saving
+    let watched = match channel_id.message(ctx, message_id).await {
+        Ok(watched) => watched,
+        Err(_) => {
+            ctx.say("❌ I can't find that message. Is the link right, and can I see that channel?")
+                .await?;
+            return Ok(());
+        }
+    };
+    let current = reaction_count(&watched.reactions, &emoji);
+    if current >= count {
+        ctx.say(format!(
+            "🎉 That message already has {} {}!",
+            current, emoji
+        ))
+        .await?;
+        return Ok(());
+    }
+
+    let saved = {
+        let _guard = WATCHERS_LOCK.lock();
+        let mut data = load_watchers();
+        let user_id = ctx.author().id.get();
+        let watching = data
+            .watchers
+            .iter()
+            .filter(|w| w.user_id == user_id)
+            .count();
+        if watching >= MAX_WATCHERS_PER_USER {
+            Ok(None)
+        } else {
+            let watcher = ReactionWatcher {
+                id: data.next_id,
+                user_id,
+                guild_id: ctx.guild_id().map(|id| id.get()),
+                channel_id: channel_id.get(),
+                message_id: message_id.get(),
+                emoji: emoji.clone(),
+                threshold: count,
+                created_at: Utc::now(),
+            };
+            data.watchers.push(watcher.clone());
+            data.next_id += 1;
+            save_watchers(&data).map(|_| Some(watcher))
+        }
+    };
+    let watcher = match saved {
+        Ok(Some(watcher)) => watcher,
+        Ok(None) => {
+            ctx.say(format!(
+                "❌ You can watch at most {} messages at once. Stop one with `-remind unwatch <id>`.",
+                MAX_WATCHERS_PER_USER
+            ))
+            .await?;
+            return Ok(());
+        }
+        Err(e) => {
+            ctx.say(format!("❌ Failed to save the watcher: {e}"))
+                .await?;
+            return Ok(());
+        }
+    };
+
+    ctx.say(format!(
+        "👀 I'll ping you when {} has {} {} (it has {} now). Watcher ID: {}, stop it with `-remind unwatch {}`.",
+        watched.link(),
+        count,
+        emoji,
+        current,
+        watcher.id,
+        watcher.id
+    ))
+    .await?;
+    Ok(())
+}
+
+/// Stop watching a message's reactions
+#[poise::command(prefix_command, slash_command, rename = "unwatch")]
+pub async fn remind_unwatch(
+    ctx: Context<'_>,
+    #[description = "Watcher ID from -remind when"] id: u64,
+) -> Result<(), Error> {
+    log::info!(
+        "Remind unwatch command called by {} for ID: {}",
+        ctx.author().name,
+        id
+    );
+
+    let removed = {
+        let _guard = WATCHERS_LOCK.lock();
+        let mut data = load_watchers();
+        let user_id = ctx.author().id.get();
+        let before = data.watchers.len();
+        data.watchers
+            .retain(|w| !(w.id == id && w.user_id == user_id));
+        if data.watchers.len() == before {
+            Ok(false)
+        } else {
+            save_watchers(&data).map(|_| true)
+        }
+    };
+
+    match removed {
+        Ok(true) => ctx.say(format!("✅ Stopped watcher {}.", id)).await?,
+        Ok(false) => {
+            ctx.say(format!("❌ You don't have a watcher with ID {}.", id))
+                .await?
+        }
+        Err(e) => ctx.say(format!("❌ Failed to save: {e}")).await?,
+    };
+    Ok(())
+}
+
+/// How many of `emoji` a message has. Unicode emoji are compared without
+/// variation selectors (❤ vs ❤️), custom ones by ID.
+fn reaction_count(reactions: &[serenity::MessageReaction], emoji: &str) -> u64 {
+    reactions
+        .iter()
+        .find(|reaction| emoji_matches(&reaction.reaction_type, emoji))
+        .map_or(0, |reaction| reaction.count)
+}
+
+fn emoji_matches(reaction: &serenity::ReactionType, wanted: &str) -> bool {
+    let Ok(wanted) = serenity::ReactionType::try_from(wanted) else {
+        return false;
+    };
+    match (reaction, &wanted) {
+        (
+            serenity::ReactionType::Custom { id, .. },
+            serenity::ReactionType::Custom { id: wanted, .. },
+        ) => id == wanted,
+        (serenity::ReactionType::Unicode(have), serenity::ReactionType::Unicode(wanted)) => {
+            have.replace('\u{fe0f}', "") == wanted.replace('\u{fe0f}', "")
+        }
+        _ => false,
+    }
+}
+
+/// What a poll found out about one watcher.
+enum Check {
+    Waiting,
+    Reached(u64),
+    /// The message is gone, or the bot can't see it any more.
+    Gone,
+}
+
+/// Start the background task that polls watched messages
+pub fn start_reaction_watcher(http: Arc<serenity::Http>, maintenance: Arc<Maintenance>) {
+    tokio::spawn(async move {
+        let mut interval = interval(TokioDuration::from_secs(CHECK_INTERVAL_SECS));
+
+        loop {
+            interval.tick().await;
+            // Finished watchers couldn't be removed, so they'd notify every tick
+            if maintenance.is_enabled() || storage::is_read_only(&storage::data_path(WATCHERS_FILE))
+            {
+                continue;
+            }
+            check_watchers(&http).await;
+        }
+    });
+}
+
+async fn check_watchers(http: &serenity::Http) {
+    let now = Utc::now();
+    let watchers = load_watchers().watchers;
+    let mut finished = Vec::new();
+
+    for watcher in watchers {
+        if now - watcher.created_at > Duration::days(WATCHER_LIFETIME_DAYS) {
+            log::info!("Reaction watcher {} expired", watcher.id);
+            finished.push(watcher.id);
+            continue;
+        }
+
+        let channel_id = serenity::ChannelId::new(watcher.channel_id);
+        let message_id = serenity::MessageId::new(watcher.message_id);
+        let check = match channel_id.message(http, message_id).await {
+            Ok(message) => match reaction_count(&message.reactions, &watcher.emoji) {
+                count if count >= watcher.threshold => Check::Reached(count),
+                _ => Check::Waiting,
+            },
+            Err(e) if is_not_found(&e) || is_missing_permission(&e) => Check::Gone,
+            Err(e) => {
+                // Probably transient; try again next time
+                log::warn!("Failed to check reaction watcher {}: {}", watcher.id, e);
+                Check::Waiting
+            }
+        };
+
+        let notice = match check {
+            Check::Waiting => continue,
+            Check::Reached(count) => serenity::CreateMessage::new()
+                .content(format!(
+                    "🔔 <@{}> this message hit {} {}!",
+                    watcher.user_id, count, watcher.emoji
+                ))
+                .reference_message((channel_id, message_id)),
+            Check::Gone => serenity::CreateMessage::new().content(format!(
+                "🗑️ <@{}> the message you were watching for {} {} is gone, so I stopped watching it.",
+                watcher.user_id, watcher.threshold, watcher.emoji
+            )),
+        };
+        if let Err(e) = channel_id.send_message(http, notice).await {
+            log::warn!(
+                "Failed to send reaction watcher {} notice: {}",
+                watcher.id,
+                e
+            );
+        }
+        finished.push(watcher.id);
+    }
+
+    if finished.is_empty() {
+        return;
+    }
+    let _guard = WATCHERS_LOCK.lock();
+    let mut data = load_watchers();
+    data.watchers.retain(|w| !finished.contains(&w.id));
+    if let Err(e) = save_watchers(&data) {
+        log::error!("Failed to save reaction watchers: {}", e);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_emoji_matches() {
+        let heart = serenity::ReactionType::Unicode("❤️".to_string());
+        assert!(emoji_matches(&heart, "❤"));
+        assert!(emoji_matches(&heart, "❤️"));
+        assert!(!emoji_matches(&heart, "🔥"));
+
+        let custom = serenity::ReactionType::Custom {
+            animated: false,
+            id: serenity::EmojiId::new(600404340292059257),
+            name: Some("party".to_string()),
+        };
+        assert!(emoji_matches(&custom, "<:renamed:600404340292059257>"));
+        assert!(!emoji_matches(&custom, "<:party:1>"));
+        assert!(!emoji_matches(&custom, "🔥"));
+    }
+}
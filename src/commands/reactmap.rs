@@ -0,0 +1,304 @@
+//! Per-server overrides for the emoji `react` spells a character with, so a
+//! server's own letter emojis can be used. An override is tried first and
+//! the built-in emojis for the character stay as fallbacks.
+
+use crate::commands::autoreact::parse_emoji;
+use crate::storage;
+use crate::utils::check_store_writable;
+use crate::utils::is_protected_user;
+use crate::utils::permissions::is_not_found;
+use crate::{Context, Error};
+use parking_lot::RwLock;
+use poise::serenity_prelude as serenity;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+
+const REACTMAP_FILE: &str = "reactmap.json";
+
+const MAX_OVERRIDES_PER_GUILD: usize = 60;
+
+#[derive(Serialize, Deserialize, Default)]
+struct ReactMapData {
+    /// Each guild's overrides, from lowercase character to emoji (a Unicode
+    /// emoji, or a custom one as `<:name:id>`).
+    guilds: HashMap<u64, BTreeMap<char, String>>,
+}
+
+lazy_static::lazy_static! {
+    static ref REACTMAP: RwLock<ReactMapData> = RwLock::new(storage::load(REACTMAP_FILE));
+}
+
+/// Re-read the overrides from disk.
+pub fn reload_reactmap() -> Result<(), Error> {
+    *REACTMAP.write() = storage::reload(REACTMAP_FILE)?;
+    Ok(())
+}
+
+fn save_reactmap(data: &ReactMapData) -> Result<(), Error> {
+    storage::save(REACTMAP_FILE, data)
+}
+
+/// Drop an orphaned guild's react map overrides.
+pub fn purge_guild(guild_id: u64) {
+    let mut data = REACTMAP.write();
+    if data.guilds.remove(&guild_id).is_some() {
+        if let Err(e) = save_reactmap(&data) {
+            log::error!("Failed to save react map: {}", e);
+        }
+    }
+}
+
+/// A guild's overrides, for spelling out a reaction there.
+pub fn guild_overrides(guild_id: u64) -> HashMap<char, String> {
+    REACTMAP
+        .read()
+        .guilds
+        .get(&guild_id)
+        .map(|overrides| {
+            overrides
+                .iter()
+                .map(|(ch, emoji)| (*ch, emoji.clone()))
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+/// The character an override is for: one non-space character, lowercased
+/// since `react` spells in lowercase.
+fn parse_override_char(text: &str) -> Option<char> {
+    let mut chars = text.trim().chars();
+    let ch = chars.next()?;
+    if chars.next().is_some() || ch.is_whitespace() {
+        return None;
+    }
+    ch.to_lowercase().next()
+}
+
+/// Whether the author may change the overrides: bot owners, and members with
+/// Manage Expressions or Manage Messages. Replies when they may not.
+async fn can_edit_reactmap(ctx: Context<'_>) -> Result<bool, Error> {
+    let allowed = is_protected_user(&ctx.author().name) || {
+        match (ctx.author_member().await, ctx.guild_channel().await) {
+            (Some(member), Some(channel)) => ctx.guild().is_some_and(|guild| {
+                let permissions = guild.user_permissions_in(&channel, &member);
+                permissions.manage_guild_expressions() || permissions.manage_messages()
+            }),
+            _ => false,
+        }
+    };
+    if !allowed {
+        ctx.say("❌ You need the Manage Expressions or Manage Messages permission to change the react emojis!")
+            .await?;
+    }
+    Ok(allowed)
+}
+
+/// Command check for commands that change the overrides.
+async fn reactmap_writable(ctx: Context<'_>) -> Result<bool, Error> {
+    Ok(can_edit_reactmap(ctx).await?
+        && check_store_writable(ctx, &storage::data_path(REACTMAP_FILE)).await?)
+}
+
+/// Choose which emojis `react` spells characters with in this server
+#[poise::command(
+    prefix_command,
+    slash_command,
+    guild_only,
+    subcommands("reactmap_set", "reactmap_remove", "reactmap_list")
+)]
+pub async fn reactmap(_ctx: Context<'_>) -> Result<(), Error> {
+    Ok(())
+}
+
+/// Spell a character with this emoji first
+#[poise::command(
+    prefix_command,
+    slash_command,
+    guild_only,
+    rename = "set",
+    check = "reactmap_writable"
+)]
+pub async fn reactmap_set(
+    ctx: Context<'_>,
+    #[description = "Letter, digit or symbol"] character: String,
+    #[description = "A standard emoji or one of this server's custom emojis"] emoji: String,
+) -> Result<(), Error> {
+    log::info!(
+        "Reactmap set command called by {} with '{}' -> '{}'",
+        ctx.author().name,
+        character,
+        emoji
+    );
+
+    let Some(guild_id) = ctx.guild_id() else {
+        return Ok(());
+    };
+    let Some(ch) = parse_override_char(&character) else {
+        ctx.say("❌ Give a single character, e.g. `-reactmap set a <emoji>`.")
+            .await?;
+        return Ok(());
+    };
+    let Some(reaction) = parse_emoji(&emoji) else {
+        ctx.say("❌ That doesn't look like an emoji! Use a standard emoji or one of this server's custom emojis.")
+            .await?;
+        return Ok(());
+    };
+
+    // Reacting with another server's emoji fails, so only this server's count
+    if let serenity::ReactionType::Custom { id, .. } = &reaction {
+        match guild_id.emoji(ctx.http(), *id).await {
+            Ok(_) => {}
+            Err(e) if is_not_found(&e) => {
+                ctx.say(
+                    "❌ That custom emoji isn't from this server, so I can't react with it here.",
+                )
+                .await?;
+                return Ok(());
+            }
+            Err(e) => return Err(e.into()),
+        }
+    }
+
+    let emoji = reaction.to_string();
+    let added = {
+        let mut data = REACTMAP.write();
+        let overrides = data.guilds.entry(guild_id.get()).or_default();
+        if overrides.len() >= MAX_OVERRIDES_PER_GUILD && !overrides.contains_key(&ch) {
+            false
+        } else {
+            overrides.insert(ch, emoji.clone());
+            if let Err(e) = save_reactmap(&data) {
+                log::error!("Failed to save react map: {}", e);
+            }
+            true
+        }
+    };
+
+    if added {
+        ctx.say(format!("✅ `{}` is now spelled with {} first.", ch, emoji))
+            .await?;
+    } else {
+        ctx.say(format!(
+            "❌ This server already has {} react emojis set. Remove one first!",
+            MAX_OVERRIDES_PER_GUILD
+        ))
+        .await?;
+    }
+    Ok(())
+}
+
+/// Go back to the built-in emojis for a character
+#[poise::command(
+    prefix_command,
+    slash_command,
+    guild_only,
+    rename = "remove",
+    check = "reactmap_writable"
+)]
+pub async fn reactmap_remove(
+    ctx: Context<'_>,
+    #[description = "Letter, digit or symbol"] character: String,
+) -> Result<(), Error> {
+    log::info!(
+        "Reactmap remove command called by {} for '{}'",
+        ctx.author().name,
+        character
+    );
+
+    let Some(guild_id) = ctx.guild_id() else {
+        return Ok(());
+    };
+    let Some(ch) = parse_override_char(&character) else {
+        ctx.say("❌ Give a single character, e.g. `-reactmap remove a`.")
+            .await?;
+        return Ok(());
+    };
+
+    let removed = {
+        let mut data = REACTMAP.write();
+        let removed = data
+            .guilds
+            .get_mut(&guild_id.get())
+            .and_then(|overrides| overrides.remove(&ch));
+        if removed.is_some() {
+            if let Err(e) = save_reactmap(&data) {
+                log::error!("Failed to save react map: {}", e);
+            }
+        }
+        removed
+    };
+
+    match removed {
+        Some(emoji) => {
+            ctx.say(format!(
+                "🗑️ `{}` no longer uses {}; it's back to the built-in emojis.",
+                ch, emoji
+            ))
+            .await?;
+        }
+        None => {
+            ctx.say(format!(
+                "❌ `{}` has no react emoji set in this server!",
+                ch
+            ))
+            .await?;
+        }
+    }
+    Ok(())
+}
+
+/// List this server's react emojis
+#[poise::command(prefix_command, slash_command, guild_only, rename = "list")]
+pub async fn reactmap_list(ctx: Context<'_>) -> Result<(), Error> {
+    log::info!("Reactmap list command called by {}", ctx.author().name);
+
+    let Some(guild_id) = ctx.guild_id() else {
+        return Ok(());
+    };
+
+    let description = {
+        let data = REACTMAP.read();
+        data.guilds
+            .get(&guild_id.get())
+            .filter(|overrides| !overrides.is_empty())
+            .map(|overrides| {
+                overrides
+                    .iter()
+                    .map(|(ch, emoji)| format!("`{}` → {}", ch, emoji))
+                    .collect::<Vec<_>>()
+                    .join("\n")
+            })
+    };
+
+    let Some(description) = description else {
+        ctx.say("📭 This server uses the built-in react emojis for every character!")
+            .await?;
+        return Ok(());
+    };
+
+    let embed = serenity::CreateEmbed::new()
+        .title("🔤 React Emojis")
+        .description(description)
+        .color(0x5865F2)
+        .footer(serenity::CreateEmbedFooter::new(
+            "Tried first when spelling; the built-in emojis are used once these are taken",
+        ));
+
+    ctx.send(poise::CreateReply::default().embed(embed)).await?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_override_char() {
+        assert_eq!(parse_override_char("a"), Some('a'));
+        assert_eq!(parse_override_char(" A "), Some('a'));
+        assert_eq!(parse_override_char("?"), Some('?'));
+        assert_eq!(parse_override_char("ab"), None);
+        assert_eq!(parse_override_char(""), None);
+        assert_eq!(parse_override_char("   "), None);
+    }
+}
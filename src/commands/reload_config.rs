@@ -0,0 +1,123 @@
+use crate::commands::{
+    automod, autopublish, autoreact, birthday, boost, bumper, color, digest, fish, invites, park,
+    poll_templates, reactmap, reply_translate, streak,
+};
+use crate::guild_config;
+use crate::utils::is_protected_user;
+use crate::{Context, Error};
+
+/// Re-reads one store from disk, replacing its in-memory copy.
+type Reloader = fn() -> Result<(), Error>;
+
+/// Stores kept in memory between uses, by what's in them, with the function
+/// that re-reads each from disk. Reminders, notes and reaction watchers are
+/// read from disk on every use, so they need no reload.
+pub(crate) const RELOADERS: &[(&str, Reloader)] = &[
+    ("Server settings", guild_config::reload_guild_configs),
+    ("Parking", park::reload_parking_data),
+    ("Color roles", color::reload_color_roles),
+    ("Digest", digest::reload_digests),
+    ("Automod", automod::reload_automod),
+    ("Auto-react", autoreact::reload_rules),
+    ("React emojis", reactmap::reload_reactmap),
+    (
+        "Translation languages",
+        reply_translate::reload_translate_prefs,
+    ),
+    ("Auto-publish", autopublish::reload_autopublish),
+    ("Streaks", streak::reload_streaks),
+    ("Birthdays", birthday::reload_birthdays),
+    ("Boosts", boost::reload_boosts),
+    ("Fishing", fish::reload_fishing),
+    ("Bumper", bumper::reload_bumpers),
+    ("Invites", invites::reload_invites),
+    ("Poll templates", poll_templates::reload_poll_templates),
+];
+
+/// Run every reloader, carrying on past failures. Returns each store's
+/// name with its error, if it had one.
+fn reload_all(reloaders: &[(&'static str, Reloader)]) -> Vec<(&'static str, Option<String>)> {
+    reloaders
+        .iter()
+        .map(|(name, reload)| {
+            let error = reload().err().map(|e| e.to_string());
+            match &error {
+                Some(e) => log::warn!("Failed to reload {}: {}", name, e),
+                None => log::info!("Reloaded {}", name),
+            }
+            (*name, error)
+        })
+        .collect()
+}
+
+fn reload_report(results: &[(&str, Option<String>)]) -> String {
+    let failed = results.iter().filter(|(_, error)| error.is_some()).count();
+    let mut report = if failed == 0 {
+        format!("🔄 Reloaded all {} stores from disk.", results.len())
+    } else {
+        format!(
+            "🔄 Reloaded {} of {} stores. The ones that failed keep what they had in memory.",
+            results.len() - failed,
+            results.len()
+        )
+    };
+    for (name, error) in results {
+        match error {
+            Some(error) => report.push_str(&format!("\n❌ {}: {}", name, error)),
+            None => report.push_str(&format!("\n✅ {}", name)),
+        }
+    }
+    report
+}
+
+/// Re-read the stored settings and data from disk without restarting (bot owners only)
+#[poise::command(prefix_command, slash_command, rename = "reload-config")]
+pub async fn reload_config(ctx: Context<'_>) -> Result<(), Error> {
+    log::info!("Reload config command called by {}", ctx.author().name);
+
+    if !is_protected_user(&ctx.author().name) {
+        ctx.say("❌ You don't have permission to use this command!")
+            .await?;
+        return Ok(());
+    }
+
+    let results = reload_all(RELOADERS);
+    ctx.say(reload_report(&results)).await?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_reload_all_keeps_going_after_a_failure() {
+        let results = reload_all(&[
+            ("First", || Ok(())),
+            ("Broken", || Err("expected value at line 3 column 5".into())),
+            ("Last", || Ok(())),
+        ]);
+        assert_eq!(
+            results,
+            vec![
+                ("First", None),
+                (
+                    "Broken",
+                    Some("expected value at line 3 column 5".to_string())
+                ),
+                ("Last", None),
+            ]
+        );
+        assert_eq!(
+            reload_report(&results),
+            "🔄 Reloaded 2 of 3 stores. The ones that failed keep what they had in memory.\n\
+             ✅ First\n\
+             ❌ Broken: expected value at line 3 column 5\n\
+             ✅ Last"
+        );
+        assert_eq!(
+            reload_report(&[("First", None)]),
+            "🔄 Reloaded all 1 stores from disk.\n✅ First"
+        );
+    }
+}
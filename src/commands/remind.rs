@@ -1,10 +1,31 @@
+use crate::commands::countdown::remind_countdown;
+use crate::commands::maintenance::Maintenance;
+use crate::commands::note::mark_reminded;
+use crate::commands::reaction_watch::{remind_unwatch, remind_when};
+use crate::dm_delivery::send_dm;
+use crate::storage;
+use crate::utils::args::{pop_arg, Arg};
+use crate::utils::ics::{parse_calendar, IcsEvent};
+use crate::utils::is_protected_user;
+use crate::utils::limits::{fit_content, fit_description, truncate};
+use crate::utils::permissions::is_not_found;
+use crate::utils::reply::Replier;
+use crate::utils::schedule::{
+    check_schedule_time, confirm_schedule_time, local_time_on, max_schedule_horizon, ScheduleCheck,
+};
 use crate::{Context, Error};
-use chrono::{DateTime, Duration, Utc};
+use chrono::{DateTime, Duration, TimeZone, Timelike, Utc};
+use chrono_tz::Tz;
+use parking_lot::RwLock;
 use poise::serenity_prelude as serenity;
+use rand::Rng;
 use serde::{Deserialize, Serialize};
 use serenity::{Color, CreateEmbed, CreateEmbedFooter};
-use std::fs;
+use std::collections::{HashMap, HashSet};
+use std::future::Future;
+use std::path::Path;
 use std::sync::Arc;
+use tokio::sync::Notify;
 use tokio::time::{interval, Duration as TokioDuration};
 
 #[derive(Serialize, Deserialize, Clone)]
@@ -16,12 +37,185 @@ struct Reminder {
     remind_at: DateTime<Utc>,
     created_at: DateTime<Utc>,
     reply_to_message_id: Option<u64>,
+    /// Nag reminders re-fire every this many minutes until acknowledged.
+    #[serde(default)]
+    nag_interval_minutes: Option<u32>,
+    #[serde(default)]
+    nag_count: u32,
+    #[serde(default)]
+    acknowledged: bool,
+    /// Set in a DM rather than a server channel.
+    #[serde(default)]
+    is_dm: bool,
+    /// Who set the reminder, when it was set for someone else with
+    /// `remind for`. `user_id` is always the recipient.
+    #[serde(default)]
+    created_by: Option<u64>,
+    /// How many times this reminder has been snoozed.
+    #[serde(default)]
+    snooze_count: u32,
+    /// Server it was set in; `None` in DMs.
+    #[serde(default)]
+    guild_id: Option<u64>,
+    /// The message it was set from, for a jump link in the fired reminder:
+    /// the command message for prefix commands, the bot's confirmation for
+    /// slash commands (which have no message of their own).
+    #[serde(default)]
+    source_message_id: Option<u64>,
+    /// Set with `remind after`: this reminder waits for reminder
+    /// `relative_to` to fire and then goes off `relative_offset_secs` later.
+    /// Until then `remind_at` is only the planned time, which it keeps if the
+    /// base reminder is removed.
+    #[serde(default)]
+    relative_to: Option<u64>,
+    #[serde(default)]
+    relative_offset_secs: i64,
+    /// Set with `remind followup`: if the user doesn't react to or reply to
+    /// the fired reminder within this many minutes, they get one DM nudge.
+    #[serde(default)]
+    followup_after_minutes: Option<u32>,
+    /// Set with `note add --remind`: the recipient's note this reminds them
+    /// of. Finishing the note cancels the reminder.
+    #[serde(default)]
+    note_id: Option<u64>,
+    #[serde(default)]
+    priority: ReminderPriority,
+    /// The DM copy sent when it was set; reacting ❌ to it cancels the
+    /// reminder. `None` if it wasn't sent, e.g. because DMs are closed.
+    #[serde(default)]
+    cancel_message_id: Option<u64>,
+    /// Set with `remind whenfree`: goes off as soon as the recipient comes
+    /// back online, with `remind_at` as the deadline.
+    #[serde(default)]
+    until_free: Option<UntilFree>,
+    /// Set with `remind webhook`: goes off through this webhook instead of
+    /// as the bot. Dropped if the webhook turns out to be deleted, and the
+    /// reminder is sent as the bot after all.
+    #[serde(default)]
+    webhook: Option<ReminderWebhook>,
+    /// When a reminder check claimed it for sending. Other checks leave it
+    /// alone until that check records how sending went, or the claim goes
+    /// stale after [`CLAIM_TIMEOUT_MINUTES`].
+    #[serde(default)]
+    sending: Option<DateTime<Utc>>,
 }
 
-#[derive(Serialize, Deserialize)]
+/// The webhook a `remind webhook` reminder posts through, and the name and
+/// avatar it posts as when they differ from the webhook's own.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+struct ReminderWebhook {
+    id: u64,
+    token: String,
+    #[serde(default)]
+    name: Option<String>,
+    #[serde(default)]
+    avatar_url: Option<String>,
+}
+
+/// Where a `remind whenfree` reminder is in waiting for its recipient.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq)]
+struct UntilFree {
+    /// The recipient has been idle, busy or offline since it was set, so
+    /// showing up online now means they're back.
+    away_seen: bool,
+}
+
+lazy_static::lazy_static! {
+    /// Wakes the reminder checker early, e.g. when maintenance mode ends.
+    static ref REMINDER_WAKEUP: Notify = Notify::new();
+
+    /// Held across every load-modify-save of the reminders file, by commands
+    /// and the checker alike, so none of them saves over another's changes.
+    /// The checker doesn't hold it while sending; see [`run_check`].
+    static ref REMINDERS_LOCK: tokio::sync::Mutex<()> = tokio::sync::Mutex::new(());
+
+    /// Users with a pending `remind whenfree` reminder, so presence updates
+    /// for everyone else don't read the reminders file. May hold users whose
+    /// reminder has since gone off; those are dropped on their next update.
+    /// Filled in from the file when the reminder checker starts.
+    static ref WAITING_FOR_PRESENCE: RwLock<HashSet<u64>> = RwLock::new(HashSet::new());
+}
+
+/// How loudly a reminder goes off, set with `remind set`.
+#[derive(
+    poise::ChoiceParameter, Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Default,
+)]
+#[serde(rename_all = "lowercase")]
+pub enum ReminderPriority {
+    #[name = "low"]
+    Low,
+    #[default]
+    #[name = "normal"]
+    Normal,
+    #[name = "high"]
+    High,
+}
+
+/// How a fired reminder of some priority is presented.
+#[derive(Debug, PartialEq)]
+struct NotificationStyle {
+    color: Color,
+    /// Ping the recipient.
+    mention: bool,
+    /// Also DM a copy, for reminders set in a server.
+    dm: bool,
+}
+
+/// Low is quiet, normal is the usual gold ping, high is red and DMs too.
+fn notification_style(priority: ReminderPriority) -> NotificationStyle {
+    match priority {
+        ReminderPriority::Low => NotificationStyle {
+            color: Color::LIGHT_GREY,
+            mention: false,
+            dm: false,
+        },
+        ReminderPriority::Normal => NotificationStyle {
+            color: Color::GOLD,
+            mention: true,
+            dm: false,
+        },
+        ReminderPriority::High => NotificationStyle {
+            color: Color::RED,
+            mention: true,
+            dm: true,
+        },
+    }
+}
+
+/// Shown for reminders that aren't normal priority.
+pub fn priority_label(priority: ReminderPriority) -> Option<&'static str> {
+    match priority {
+        ReminderPriority::Low => Some("🔕 low priority"),
+        ReminderPriority::Normal => None,
+        ReminderPriority::High => Some("🚨 high priority"),
+    }
+}
+
+/// Order for `remind list`.
+#[derive(poise::ChoiceParameter, Clone, Copy, Debug, PartialEq, Default)]
+pub enum ReminderSort {
+    #[default]
+    #[name = "soonest"]
+    Soonest,
+    #[name = "newest"]
+    Newest,
+}
+
+#[derive(Serialize, Deserialize, Clone)]
 struct RemindersData {
     reminders: Vec<Reminder>,
     next_id: u64,
+    /// One-off reminders that already fired, kept for [`SNOOZE_WINDOW_HOURS`]
+    /// so their snooze buttons keep working.
+    #[serde(default)]
+    fired: Vec<Reminder>,
+    /// Fired `remind followup` reminders still waiting for a reaction or
+    /// reply. Each is checked once when its window is up, then dropped.
+    #[serde(default)]
+    awaiting_followup: Vec<PendingFollowup>,
+    /// Each user's quiet hours, set with `remind quiethours set`.
+    #[serde(default)]
+    quiet_hours: HashMap<u64, QuietHours>,
 }
 
 impl Default for RemindersData {
@@ -29,29 +223,143 @@ impl Default for RemindersData {
         Self {
             reminders: Vec::new(),
             next_id: 1,
+            fired: Vec::new(),
+            awaiting_followup: Vec::new(),
+            quiet_hours: HashMap::new(),
         }
     }
 }
 
-const REMINDERS_FILE: &str = "/tmp/rustbot_reminders.json";
+/// A daily window in which a user's reminders are held back. Reminders
+/// that come due during it go off when it ends instead. The window may
+/// wrap past midnight, e.g. 22:00-07:00.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+struct QuietHours {
+    start_hour: u8,
+    start_minute: u8,
+    end_hour: u8,
+    end_minute: u8,
+    /// IANA name; the bot's home timezone if unset.
+    timezone: Option<String>,
+}
+
+impl QuietHours {
+    fn timezone(&self) -> Tz {
+        self.timezone
+            .as_deref()
+            .and_then(|name| name.parse().ok())
+            .unwrap_or(REMINDER_TIMEZONE)
+    }
 
-fn load_reminders() -> RemindersData {
-    match fs::read_to_string(REMINDERS_FILE) {
-        Ok(content) => {
-            // Try to parse as current format first
-            match serde_json::from_str::<RemindersData>(&content) {
-                Ok(data) => data,
-                Err(_) => {
-                    // If that fails, try to migrate from old format
-                    migrate_old_format(&content).unwrap_or_default()
-                }
-            }
-        }
-        Err(_) => RemindersData::default(),
+    /// When the quiet hours `now` falls in end, or `None` outside them.
+    fn end_after(&self, now: DateTime<Utc>) -> Option<DateTime<Utc>> {
+        let tz = self.timezone();
+        let local = now.with_timezone(&tz);
+        let today = local.date_naive();
+        let minute_of_day = |hour: u8, minute: u8| hour as u32 * 60 + minute as u32;
+        let current = local.hour() * 60 + local.minute();
+        let start = minute_of_day(self.start_hour, self.start_minute);
+        let end = minute_of_day(self.end_hour, self.end_minute);
+
+        let end_day = if start < end {
+            (start..end).contains(&current).then_some(today)?
+        } else if current >= start {
+            today.succ_opt()?
+        } else if current < end {
+            today
+        } else {
+            return None;
+        };
+        local_time_on(&tz, end_day, self.end_hour, self.end_minute)
+            .map(|at| at.with_timezone(&Utc))
+            .filter(|at| *at > now)
     }
+
+    fn describe(&self) -> String {
+        format!(
+            "{:02}:{:02}-{:02}:{:02} ({})",
+            self.start_hour,
+            self.start_minute,
+            self.end_hour,
+            self.end_minute,
+            self.timezone().name()
+        )
+    }
+}
+
+/// Read a time of day like `22`, `22:00` or `7:30`.
+fn parse_clock_time(input: &str) -> Option<(u8, u8)> {
+    let (hour, minute) = match input.trim().split_once(':') {
+        Some((hour, minute)) if minute.len() == 2 => (hour.parse().ok()?, minute.parse().ok()?),
+        Some(_) => return None,
+        None => (input.trim().parse().ok()?, 0),
+    };
+    (hour <= 23 && minute <= 59).then_some((hour, minute))
+}
+
+/// A fired reminder message the user hasn't been nudged about yet.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+struct PendingFollowup {
+    reminder_id: u64,
+    user_id: u64,
+    channel_id: u64,
+    /// The message the reminder was sent as.
+    message_id: u64,
+    /// Server of the channel, for the jump link; `None` in DMs.
+    guild_id: Option<u64>,
+    message: String,
+    due_at: DateTime<Utc>,
+}
+
+/// Reminders, in [`storage::DATA_DIR`]. Webhook reminders carry their
+/// webhook's token, so this mustn't be readable by anyone but the bot.
+const REMINDERS_FILE: &str = "rustbot_reminders.json";
+
+/// Where reminders were kept before they moved into the data directory.
+const OLD_REMINDERS_FILE: &str = "/tmp/rustbot_reminders.json";
+
+/// How many reminders `remind list` shows per page.
+const REMINDERS_PER_PAGE: usize = 10;
+
+/// Safety cap so a forgotten nag reminder can't ping someone forever.
+const MAX_NAGS: u32 = 20;
+
+/// Custom ID prefix for the "acknowledge" button on nag reminders.
+pub const ACK_BUTTON_PREFIX: &str = "remind_ack:";
+
+/// Custom ID prefix for the snooze buttons on fired reminders, followed by
+/// `<reminder id>:<minutes>`.
+pub const SNOOZE_BUTTON_PREFIX: &str = "remind_snooze:";
+
+/// Snooze lengths offered on a fired reminder, as (label, minutes).
+const SNOOZE_OPTIONS: &[(&str, u32)] = &[("10m", 10), ("1h", 60), ("1d", 24 * 60)];
+
+/// How long a fired reminder can still be snoozed.
+const SNOOZE_WINDOW_HOURS: i64 = 24;
+
+/// From this many snoozes on, the snooze confirmation adds a nudge.
+const SNOOZE_NUDGE_THRESHOLD: u32 = 3;
+
+/// Longest wait for a reaction or reply before a `remind followup` nudge.
+const MAX_FOLLOWUP_WINDOW_HOURS: i64 = 24;
+
+/// How long a check's claim on a reminder it's sending holds. An older one
+/// was left by a check that stopped or couldn't save how sending went, and
+/// is taken over so the reminder isn't lost, at the risk of sending it twice.
+const CLAIM_TIMEOUT_MINUTES: i64 = 15;
+
+/// Longest name Discord lets a webhook post as.
+const MAX_WEBHOOK_NAME_CHARS: usize = 80;
+
+/// Discord's JSON error code for a webhook token that no longer works.
+const INVALID_WEBHOOK_TOKEN: isize = 50027;
+
+fn load_reminders() -> RemindersData {
+    // A file in neither format is set aside rather than saved over
+    storage::load_migrating(REMINDERS_FILE, migrate_old_format)
 }
 
-fn migrate_old_format(content: &str) -> Option<RemindersData> {
+fn migrate_old_format(value: &serde_json::Value) -> Option<RemindersData> {
     // Try to parse as old format without reply_to_message_id
     #[derive(Deserialize)]
     struct OldReminder {
@@ -69,7 +377,7 @@ fn migrate_old_format(content: &str) -> Option<RemindersData> {
         next_id: u64,
     }
 
-    let old_data: OldRemindersData = serde_json::from_str(content).ok()?;
+    let old_data = OldRemindersData::deserialize(value).ok()?;
 
     let new_reminders = old_data
         .reminders
@@ -82,22 +390,79 @@ fn migrate_old_format(content: &str) -> Option<RemindersData> {
             remind_at: old_reminder.remind_at,
             created_at: old_reminder.created_at,
             reply_to_message_id: None,
+            nag_interval_minutes: None,
+            nag_count: 0,
+            acknowledged: false,
+            is_dm: false,
+            created_by: None,
+            snooze_count: 0,
+            guild_id: None,
+            source_message_id: None,
+            relative_to: None,
+            relative_offset_secs: 0,
+            followup_after_minutes: None,
+            note_id: None,
+            priority: ReminderPriority::Normal,
+            cancel_message_id: None,
+            until_free: None,
+            webhook: None,
+            sending: None,
         })
         .collect();
 
     Some(RemindersData {
         reminders: new_reminders,
         next_id: old_data.next_id,
+        fired: Vec::new(),
+        awaiting_followup: Vec::new(),
+        quiet_hours: HashMap::new(),
     })
 }
 
 fn save_reminders(data: &RemindersData) -> Result<(), Error> {
-    let json = serde_json::to_string_pretty(data)?;
-    fs::write(REMINDERS_FILE, json)?;
-    Ok(())
+    storage::save(REMINDERS_FILE, data)
+}
+
+/// Move the reminders file from `/tmp`, where anyone on the machine could
+/// read it, into the data directory. Runs at startup before anything loads
+/// reminders.
+pub(crate) fn move_old_reminders_file() {
+    let old = Path::new(OLD_REMINDERS_FILE);
+    match move_reminders_file(old, &storage::data_path(REMINDERS_FILE)) {
+        Ok(true) => log::info!("Moved {} into {}", old.display(), storage::DATA_DIR),
+        Ok(false) => {}
+        Err(e) => log::error!("Failed to move {}: {}", old.display(), e),
+    }
+}
+
+/// Move `from` to `to` unless `to` already exists, leaving it readable by
+/// the bot only. Returns whether anything was moved.
+fn move_reminders_file(from: &Path, to: &Path) -> std::io::Result<bool> {
+    if !from.exists() {
+        return Ok(false);
+    }
+    if to.exists() {
+        log::warn!(
+            "Both {} and {} exist, keeping the second; delete the first once you've checked it",
+            from.display(),
+            to.display()
+        );
+        return Ok(false);
+    }
+    if let Some(dir) = to.parent() {
+        std::fs::create_dir_all(dir)?;
+    }
+    // `/tmp` is often a different filesystem, so copy rather than rename
+    let tmp_path = to.with_extension("json.tmp");
+    std::fs::copy(from, &tmp_path)?;
+    storage::restrict_to_owner(&tmp_path)?;
+    std::fs::rename(&tmp_path, to)?;
+    std::fs::remove_file(from)?;
+    Ok(true)
 }
 
-fn parse_time_duration(time_str: &str) -> Option<Duration> {
+/// Parse a duration like `5m`, `2 hours` or `1w`.
+pub fn parse_time_duration(time_str: &str) -> Option<Duration> {
     let time_str = time_str.to_lowercase();
 
     // Extract number and unit
@@ -114,75 +479,343 @@ fn parse_time_duration(time_str: &str) -> Option<Duration> {
 
     let number: i64 = number_str.parse().ok()?;
 
+    // try_* so absurd values like 99999999999w are rejected instead of panicking
     match unit_str.trim() {
-        "s" | "sec" | "second" | "seconds" => Some(Duration::seconds(number)),
-        "m" | "min" | "minute" | "minutes" => Some(Duration::minutes(number)),
-        "h" | "hr" | "hour" | "hours" => Some(Duration::hours(number)),
-        "d" | "day" | "days" => Some(Duration::days(number)),
-        "w" | "week" | "weeks" => Some(Duration::weeks(number)),
+        "s" | "sec" | "second" | "seconds" => Duration::try_seconds(number),
+        "m" | "min" | "minute" | "minutes" => Duration::try_minutes(number),
+        "h" | "hr" | "hour" | "hours" => Duration::try_hours(number),
+        "d" | "day" | "days" => Duration::try_days(number),
+        "w" | "week" | "weeks" => Duration::try_weeks(number),
         _ => None,
     }
 }
 
+/// Timezone that natural phrases like "tonight" are resolved in. There are no
+/// per-user timezones yet, so this is the bot's home timezone.
+pub(crate) const REMINDER_TIMEZONE: Tz = chrono_tz::Europe::Copenhagen;
+
+/// Resolves a natural time phrase to a concrete time, given the current time.
+type PhraseResolver = fn(DateTime<Utc>, &Tz) -> Option<DateTime<Utc>>;
+
+/// Natural phrases accepted instead of a duration, with how each resolves.
+const TIME_PHRASES: &[(&str, PhraseResolver)] = &[
+    ("top of the hour", top_of_the_hour),
+    ("in the morning", in_the_morning),
+    ("tonight", tonight),
+];
+
+/// The `time` that asks for a surprise reminder, see [`random_remind_time`].
+pub const RANDOM_TIME_TOKEN: &str = "random";
+
+/// How far out a surprise reminder can land: a minute to a day.
+const RANDOM_TIME_RANGE_SECS: std::ops::RangeInclusive<i64> = 60..=24 * 60 * 60;
+
+/// The error for a time that's neither a duration nor a known phrase.
+fn invalid_time_message(time: &str) -> String {
+    format!(
+        "❌ Expected a duration like 5m, 1h, 2d or 1w, or one of: `top of the hour`, \
+         `in the morning` (next 08:00), `tonight` (next 20:00); got `{}`",
+        truncate(&time.replace('`', "'"), 50)
+    )
+}
+
+/// The next full hour.
+fn top_of_the_hour(now: DateTime<Utc>, tz: &Tz) -> Option<DateTime<Utc>> {
+    // Work in local time, since some timezones are offset by half hours
+    let local = now.with_timezone(tz);
+    let hour_start = local.with_minute(0)?.with_second(0)?.with_nanosecond(0)?;
+    Some((hour_start + Duration::hours(1)).with_timezone(&Utc))
+}
+
+/// The next 08:00.
+fn in_the_morning(now: DateTime<Utc>, tz: &Tz) -> Option<DateTime<Utc>> {
+    next_local_time(now, tz, 8)
+}
+
+/// The next 20:00.
+fn tonight(now: DateTime<Utc>, tz: &Tz) -> Option<DateTime<Utc>> {
+    next_local_time(now, tz, 20)
+}
+
+/// The next time the local clock reads `hour`:00, today or tomorrow.
+fn next_local_time(now: DateTime<Utc>, tz: &Tz, hour: u32) -> Option<DateTime<Utc>> {
+    let today = now.with_timezone(tz).date_naive();
+    [today, today.succ_opt()?]
+        .into_iter()
+        .filter_map(|date| {
+            tz.from_local_datetime(&date.and_hms_opt(hour, 0, 0)?)
+                .earliest()
+        })
+        .map(|time| time.with_timezone(&Utc))
+        .find(|time| *time > now)
+}
+
+/// Match a natural time phrase at the start of `input`, returning the time it
+/// resolves to and the rest of the input.
+pub(crate) fn parse_time_phrase<'a>(
+    input: &'a str,
+    now: DateTime<Utc>,
+    tz: &Tz,
+) -> Option<(DateTime<Utc>, &'a str)> {
+    let input = input.trim_start();
+    TIME_PHRASES.iter().find_map(|(phrase, resolve)| {
+        let head = input.get(..phrase.len())?;
+        let rest = &input[phrase.len()..];
+        if !head.eq_ignore_ascii_case(phrase) || rest.starts_with(|c: char| !c.is_whitespace()) {
+            return None;
+        }
+        Some((resolve(now, tz)?, rest.trim()))
+    })
+}
+
+/// Reject reminder times past the scheduling horizon and ask before saving
+/// ones that look like typos. Returns whether to go ahead.
+pub(crate) async fn check_reminder_time(
+    ctx: Context<'_>,
+    now: DateTime<Utc>,
+    remind_at: DateTime<Utc>,
+) -> Result<bool, Error> {
+    match check_schedule_time("reminder", now, remind_at, max_schedule_horizon()) {
+        Ok(ScheduleCheck::Fine) => Ok(true),
+        Ok(ScheduleCheck::Confirm(warning)) => {
+            confirm_schedule_time(ctx, &warning, remind_at, "reminder not set.").await
+        }
+        Err(error) => {
+            ctx.say(error).await?;
+            Ok(false)
+        }
+    }
+}
+
 /// Reminder commands - set, list, remove reminders
+///
+/// As a prefix command `-remind 10m tea` (or `-r 10m tea`) is short for
+/// `-remind set 10m tea`.
 #[poise::command(
     prefix_command,
     slash_command,
-    subcommands("remind_set", "remind_list", "remind_remove", "remind_clear")
+    aliases("r"),
+    subcommands(
+        "remind_set",
+        "remind_for",
+        "remind_webhook",
+        "remind_after",
+        "remind_nag",
+        "remind_followup",
+        "remind_whenfree",
+        "remind_countdown",
+        "remind_ack",
+        "remind_list",
+        "remind_remove",
+        "remind_edit",
+        "remind_clear",
+        "remind_cancel",
+        "remind_export",
+        "remind_import_ics",
+        "remind_when",
+        "remind_unwatch",
+        "remind_quiethours"
+    )
 )]
-pub async fn remind(_ctx: Context<'_>) -> Result<(), Error> {
-    Ok(())
+pub async fn remind(
+    ctx: Context<'_>,
+    #[description = "Time duration (e.g., 5m, 1h, 2d), a phrase like \"tonight\", or \"random\""]
+    time: Option<Arg<String>>,
+    #[description = "Reminder message (optional when replying to a message)"]
+    #[rest]
+    message: Option<String>,
+) -> Result<(), Error> {
+    let time = time.map(|Arg(time)| time);
+    let Some(time) = time else {
+        ctx.say(
+            "❌ Usage: `-remind <time> <message>` or `-remind set|for|webhook|after|nag|followup|ack|list|remove|edit|clear|cancel`",
+        )
+        .await?;
+        return Ok(());
+    };
+    log::info!(
+        "Remind command called by {} with time: '{}' and message: '{:?}'",
+        ctx.author().name,
+        time,
+        message
+    );
+    set_reminder(ctx, time, None, message).await
 }
 
 /// Set a new reminder
 #[poise::command(prefix_command, slash_command, rename = "set")]
 pub async fn remind_set(
     ctx: Context<'_>,
-    #[description = "Time duration (e.g., 5m, 1h, 2d)"] time: String,
+    #[description = "Time duration (e.g., 5m, 1h, 2d), a phrase like \"tonight\", or \"random\""]
+    time: Arg<String>,
+    #[description = "How loudly it goes off (default: normal)"] priority: Option<ReminderPriority>,
     #[description = "Reminder message (optional when replying to a message)"]
     #[rest]
     message: Option<String>,
 ) -> Result<(), Error> {
+    let Arg(time) = time;
+    // Prefix commands take the priority as a marker in the message instead,
+    // so a first word like "high" that was read as the priority is put back
+    let (priority, message) = match ctx {
+        poise::Context::Prefix(prefix_ctx) if priority.is_some() => (
+            None,
+            pop_arg(prefix_ctx.args)
+                .ok()
+                .flatten()
+                .map(|(_, rest)| rest.trim().to_string()),
+        ),
+        _ => (priority, message),
+    };
     log::info!(
-        "Remind set command called by {} with time: '{}' and message: '{:?}'",
+        "Remind set command called by {} with time: '{}', priority: {:?} and message: '{:?}'",
         ctx.author().name,
         time,
+        priority,
         message
     );
+    set_reminder(ctx, time, priority, message).await
+}
 
-    // Check if we have a message or if we're replying to something
-    let has_reply = match ctx {
-        poise::Context::Prefix(prefix_ctx) => prefix_ctx.msg.referenced_message.is_some(),
-        _ => false,
+/// Split a priority marker off the start of a prefix reminder's message:
+/// `!high`, `!low`, `!normal` or `--priority <level>`. Without one it's
+/// normal priority, so a message starting with a word like "high" stays
+/// intact.
+fn take_priority_marker(message: &str) -> Result<(ReminderPriority, &str), String> {
+    fn split_word(text: &str) -> (String, &str) {
+        let text = text.trim_start();
+        let (word, rest) = text.split_once(char::is_whitespace).unwrap_or((text, ""));
+        (word.to_lowercase(), rest.trim_start())
+    }
+    let (first, rest) = split_word(message);
+    let (level, rest) = if first == "--priority" {
+        split_word(rest)
+    } else if let Some(level) = first.strip_prefix("--priority=") {
+        (level.to_string(), rest)
+    } else if let Some(level) = first
+        .strip_prefix('!')
+        .filter(|l| parse_priority(l).is_some())
+    {
+        (level.to_string(), rest)
+    } else {
+        return Ok((ReminderPriority::Normal, message.trim_start()));
     };
+    parse_priority(&level)
+        .map(|priority| (priority, rest))
+        .ok_or_else(|| "❌ `--priority` takes low, normal or high.".to_string())
+}
 
-    let reminder_message = match message {
-        Some(msg) if !msg.trim().is_empty() => msg.trim().to_string(),
-        Some(_) if has_reply => "⏰ Reminder".to_string(), // Empty message but has reply
-        Some(_) => {
-            ctx.say("❌ Please provide a reminder message!").await?;
-            return Ok(());
-        }
-        None if has_reply => "⏰ Reminder".to_string(), // No message but has reply
-        None => {
-            ctx.say("❌ Please provide a reminder message!").await?;
-            return Ok(());
-        }
+fn parse_priority(level: &str) -> Option<ReminderPriority> {
+    match level.to_lowercase().as_str() {
+        "low" => Some(ReminderPriority::Low),
+        "normal" => Some(ReminderPriority::Normal),
+        "high" => Some(ReminderPriority::High),
+        _ => None,
+    }
+}
+
+/// What a `remind set` asks for, worked out from its arguments.
+#[derive(Debug, PartialEq)]
+pub struct ReminderPlan {
+    pub remind_at: DateTime<Utc>,
+    pub message: String,
+    pub priority: ReminderPriority,
+    /// Whether `remind_at` was picked at random.
+    pub surprise: bool,
+}
+
+/// The part of `remind set` that doesn't need Discord, shared with the
+/// offline REPL: work out when it's due, its priority and its message, or
+/// reply with what's wrong. `is_prefix` lets a marker in the message set the
+/// priority; `has_reply` allows leaving the message out.
+pub async fn plan_reminder(
+    out: &mut impl Replier,
+    time: &str,
+    priority: Option<ReminderPriority>,
+    message: Option<String>,
+    is_prefix: bool,
+    has_reply: bool,
+    now: DateTime<Utc>,
+) -> Result<Option<ReminderPlan>, Error> {
+    let surprise_at = random_remind_time(time, now, &mut rand::thread_rng());
+    let (remind_at, message) = match surprise_at {
+        Some(at) => (at, message),
+        None => match resolve_remind_time(time, message, now) {
+            Ok(resolved) => resolved,
+            Err(error) => {
+                out.say(error).await?;
+                return Ok(None);
+            }
+        },
     };
 
-    let duration = match parse_time_duration(&time) {
-        Some(d) => d,
-        None => {
-            ctx.say("❌ Invalid time format! Use formats like: 5m, 1h, 2d, 1w")
-                .await?;
-            return Ok(());
+    // Slash commands have a `priority` option; prefix ones a marker
+    let (priority, message) = match priority {
+        Some(priority) => (priority, message),
+        None if is_prefix => match message.as_deref().map(take_priority_marker) {
+            Some(Ok((priority, rest))) => (priority, Some(rest.to_string())),
+            Some(Err(error)) => {
+                out.say(error).await?;
+                return Ok(None);
+            }
+            None => (ReminderPriority::Normal, None),
+        },
+        None => (ReminderPriority::Normal, message),
+    };
+
+    let message = match message.map(|msg| msg.trim().to_string()) {
+        Some(msg) if !msg.is_empty() => msg,
+        // Replying to a message says enough
+        _ if has_reply => "⏰ Reminder".to_string(),
+        _ => {
+            out.say("❌ Please provide a reminder message!").await?;
+            return Ok(None);
         }
     };
 
+    Ok(Some(ReminderPlan {
+        remind_at,
+        message,
+        priority,
+        surprise: surprise_at.is_some(),
+    }))
+}
+
+/// Everything behind `-remind set`, shared with the bare `-remind <time>` form.
+async fn set_reminder(
+    mut ctx: Context<'_>,
+    time: String,
+    priority: Option<ReminderPriority>,
+    message: Option<String>,
+) -> Result<(), Error> {
+    // Check if we have a message or if we're replying to something
+    let has_reply = match ctx {
+        poise::Context::Prefix(prefix_ctx) => prefix_ctx.msg.referenced_message.is_some(),
+        _ => false,
+    };
+    let is_prefix = matches!(ctx, poise::Context::Prefix(_));
+
     let now = Utc::now();
-    let remind_at = now + duration;
+    let Some(plan) = plan_reminder(
+        &mut ctx, &time, priority, message, is_prefix, has_reply, now,
+    )
+    .await?
+    else {
+        return Ok(());
+    };
+    let ReminderPlan {
+        remind_at,
+        message: reminder_message,
+        priority,
+        surprise,
+    } = plan;
+
+    // Catch typos like `500w` before saving
+    if !check_reminder_time(ctx, now, remind_at).await? {
+        return Ok(());
+    }
 
     // Load existing reminders
+    let lock = REMINDERS_LOCK.lock().await;
     let mut data = load_reminders();
 
     // Get the message ID this is replying to, if any (only for prefix commands)
@@ -204,6 +837,23 @@ pub async fn remind_set(
         remind_at,
         created_at: now,
         reply_to_message_id,
+        nag_interval_minutes: None,
+        nag_count: 0,
+        acknowledged: false,
+        is_dm: ctx.guild_id().is_none(),
+        created_by: None,
+        snooze_count: 0,
+        guild_id: ctx.guild_id().map(|id| id.get()),
+        source_message_id: command_message_id(ctx),
+        relative_to: None,
+        relative_offset_secs: 0,
+        followup_after_minutes: None,
+        note_id: None,
+        priority,
+        cancel_message_id: None,
+        until_free: None,
+        webhook: None,
+        sending: None,
     };
 
     // Add to list and increment ID
@@ -215,14 +865,32 @@ pub async fn remind_set(
         ctx.say(format!("❌ Failed to save reminder: {e}")).await?;
         return Ok(());
     }
+    drop(lock);
 
     // Create confirmation embed
+    let (title, picked) = if surprise {
+        (
+            "🎲 Surprise Reminder Set!",
+            format!(
+                "\n**Picked:** {} from now",
+                format_duration(remind_at - now)
+            ),
+        )
+    } else {
+        ("⏰ Reminder Set!", String::new())
+    };
+    let picked = match priority_label(priority) {
+        Some(label) => format!("{}\n**Priority:** {}", picked, label),
+        None => picked,
+    };
     let embed = CreateEmbed::new()
-        .title("⏰ Reminder Set!")
+        .title(title)
         .description(format!(
-            "**Message:** {}\n**Remind at:** <t:{}:F>",
+            "**Message:** {}\n**Remind at:** <t:{}:F> (<t:{}:R>){}",
             reminder.message,
-            remind_at.timestamp()
+            remind_at.timestamp(),
+            remind_at.timestamp(),
+            picked
         ))
         .color(Color::DARK_GREEN)
         .footer(CreateEmbedFooter::new(format!(
@@ -231,7 +899,19 @@ pub async fn remind_set(
         )))
         .timestamp(now);
 
-    ctx.send(poise::CreateReply::default().embed(embed)).await?;
+    let ephemeral = confirm_ephemerally(ctx);
+    let confirmation = ctx
+        .send(
+            poise::CreateReply::default()
+                .embed(embed)
+                .ephemeral(ephemeral),
+        )
+        .await?;
+    // Nobody else can open an ephemeral message, so it's no use as a link
+    if !ephemeral {
+        link_confirmation_if_slash(&reminder, &confirmation).await;
+    }
+    offer_cancel_reaction(ctx, &reminder, &confirmation).await;
 
     log::info!(
         "Reminder {} set successfully for user {}",
@@ -241,228 +921,3094 @@ pub async fn remind_set(
     Ok(())
 }
 
-/// List your active reminders
-#[poise::command(prefix_command, slash_command, rename = "list")]
-pub async fn remind_list(ctx: Context<'_>) -> Result<(), Error> {
-    log::info!("Remind list command called by {}", ctx.author().name);
-
-    let data = load_reminders();
-    let user_id = ctx.author().id.get();
-    let now = Utc::now();
+/// Set a reminder for someone else (requires Manage Messages)
+#[poise::command(prefix_command, slash_command, guild_only, rename = "for")]
+pub async fn remind_for(
+    ctx: Context<'_>,
+    #[description = "User to remind"] user: serenity::User,
+    #[description = "Time duration (e.g., 5m, 1h, 2d) or a phrase like \"tonight\""] time: Arg<
+        String,
+    >,
+    #[description = "Reminder message"]
+    #[rest]
+    message: Option<String>,
+) -> Result<(), Error> {
+    let Arg(time) = time;
+    log::info!(
+        "Remind for command called by {} for {} with time: '{}'",
+        ctx.author().name,
+        user.name,
+        time
+    );
 
-    // Filter reminders for this user that haven't expired yet
-    let user_reminders: Vec<&Reminder> = data
-        .reminders
-        .iter()
-        .filter(|r| r.user_id == user_id && r.remind_at > now)
-        .collect();
+    // Reminders ping their recipient, so only moderators may aim them at
+    // other people
+    if !can_remind_others(ctx).await {
+        ctx.say("❌ You need the Manage Messages permission to set reminders for other users!")
+            .await?;
+        return Ok(());
+    }
 
-    if user_reminders.is_empty() {
-        ctx.say("📭 You have no active reminders!").await?;
+    if user.bot {
+        ctx.say("❌ Bots can't receive reminders!").await?;
         return Ok(());
     }
 
-    let mut description = String::new();
-    for reminder in user_reminders.iter().take(10) {
-        // Limit to first 10 reminders
-        description.push_str(&format!(
-            "**ID {}:** {}\n⏰ <t:{}:R>\n\n",
-            reminder.id,
-            reminder.message,
-            reminder.remind_at.timestamp()
-        ));
+    let now = Utc::now();
+    let (remind_at, message) = match resolve_remind_time(&time, message, now) {
+        Ok(resolved) => resolved,
+        Err(error) => {
+            ctx.say(error).await?;
+            return Ok(());
+        }
+    };
+    let Some(reminder_message) = message
+        .map(|msg| msg.trim().to_string())
+        .filter(|msg| !msg.is_empty())
+    else {
+        ctx.say("❌ Please provide a reminder message!").await?;
+        return Ok(());
+    };
+
+    if !check_reminder_time(ctx, now, remind_at).await? {
+        return Ok(());
     }
 
-    if user_reminders.len() > 10 {
-        description.push_str(&format!("... and {} more", user_reminders.len() - 10));
+    let lock = REMINDERS_LOCK.lock().await;
+    let mut data = load_reminders();
+    let reminder = Reminder {
+        id: data.next_id,
+        user_id: user.id.get(),
+        channel_id: ctx.channel_id().get(),
+        message: reminder_message,
+        remind_at,
+        created_at: now,
+        reply_to_message_id: None,
+        nag_interval_minutes: None,
+        nag_count: 0,
+        acknowledged: false,
+        is_dm: false,
+        created_by: Some(ctx.author().id.get()),
+        snooze_count: 0,
+        guild_id: ctx.guild_id().map(|id| id.get()),
+        source_message_id: command_message_id(ctx),
+        relative_to: None,
+        relative_offset_secs: 0,
+        followup_after_minutes: None,
+        note_id: None,
+        priority: ReminderPriority::Normal,
+        cancel_message_id: None,
+        until_free: None,
+        webhook: None,
+        sending: None,
+    };
+    data.reminders.push(reminder.clone());
+    data.next_id += 1;
+
+    if let Err(e) = save_reminders(&data) {
+        ctx.say(format!("❌ Failed to save reminder: {e}")).await?;
+        return Ok(());
     }
+    drop(lock);
 
     let embed = CreateEmbed::new()
-        .title("📋 Your Active Reminders")
-        .description(description)
-        .color(Color::BLUE)
+        .title("⏰ Reminder Set!")
+        .description(format!(
+            "**For:** <@{}>\n**Message:** {}\n**Remind at:** <t:{}:F> (<t:{}:R>)",
+            user.id,
+            reminder.message,
+            remind_at.timestamp(),
+            remind_at.timestamp()
+        ))
+        .color(Color::DARK_GREEN)
         .footer(CreateEmbedFooter::new(format!(
-            "Total active reminders: {}",
-            user_reminders.len()
+            "Reminder ID: {}",
+            reminder.id
         )))
         .timestamp(now);
 
-    ctx.send(poise::CreateReply::default().embed(embed)).await?;
+    // Don't ping the recipient yet, that's the reminder's job
+    let confirmation = ctx
+        .send(
+            poise::CreateReply::default()
+                .embed(embed)
+                .allowed_mentions(serenity::CreateAllowedMentions::new()),
+        )
+        .await?;
+    link_confirmation_if_slash(&reminder, &confirmation).await;
+    offer_cancel_reaction(ctx, &reminder, &confirmation).await;
 
     log::info!(
-        "Listed {} reminders for user {}",
-        user_reminders.len(),
-        ctx.author().name
+        "Reminder {} set by {} for user {}",
+        reminder.id,
+        ctx.author().name,
+        user.name
     );
     Ok(())
 }
 
-/// Remove a specific reminder by ID
-#[poise::command(prefix_command, slash_command, rename = "remove")]
-pub async fn remind_remove(
+/// Set a reminder posted through a webhook instead of as the bot (requires Manage Webhooks)
+///
+/// Start the message with `--name <name>` (quoted if it has spaces) and/or
+/// `--avatar <https link>` to post as something other than the webhook's
+/// own name and avatar.
+#[poise::command(
+    prefix_command,
+    slash_command,
+    guild_only,
+    rename = "webhook",
+    required_permissions = "MANAGE_WEBHOOKS"
+)]
+pub async fn remind_webhook(
     ctx: Context<'_>,
-    #[description = "Reminder ID to remove"] id: u64,
+    #[description = "Webhook URL, from the channel's Integrations settings"] url: Arg<String>,
+    #[description = "Time duration (e.g., 5m, 1h, 2d) or a phrase like \"tonight\""] time: Arg<
+        String,
+    >,
+    #[description = "Reminder message, optionally starting with --name <name> and --avatar <link>"]
+    #[rest]
+    message: Option<String>,
 ) -> Result<(), Error> {
+    let Arg(url) = url;
+    let Arg(time) = time;
     log::info!(
-        "Remind remove command called by {} for ID: {}",
+        "Remind webhook command called by {} with time: '{}'",
         ctx.author().name,
-        id
+        time
     );
 
-    let mut data = load_reminders();
-    let user_id = ctx.author().id.get();
+    // Anyone who can read the URL can post through the webhook, so don't
+    // leave it sitting in the channel
+    if let poise::Context::Prefix(prefix_ctx) = ctx {
+        if let Err(e) = prefix_ctx.msg.delete(ctx).await {
+            log::warn!("Couldn't delete the remind webhook command message: {}", e);
+        }
+    }
 
-    // Find the reminder
-    let reminder_index = data
-        .reminders
-        .iter()
-        .position(|r| r.id == id && r.user_id == user_id);
+    let now = Utc::now();
+    let (remind_at, message) = match resolve_remind_time(&time, message, now) {
+        Ok(resolved) => resolved,
+        Err(error) => {
+            ctx.say(error).await?;
+            return Ok(());
+        }
+    };
+    let (name, avatar_url, message) = match parse_webhook_identity(message.as_deref().unwrap_or(""))
+    {
+        Ok(parsed) => parsed,
+        Err(error) => {
+            ctx.say(error).await?;
+            return Ok(());
+        }
+    };
+    if message.is_empty() {
+        ctx.say("❌ Please provide a reminder message!").await?;
+        return Ok(());
+    }
 
-    match reminder_index {
-        Some(index) => {
-            let removed_reminder = data.reminders.remove(index);
+    let parsed_url = url.trim().parse::<reqwest::Url>().ok();
+    let Some((webhook_id, token)) = parsed_url.as_ref().and_then(serenity::utils::parse_webhook)
+    else {
+        ctx.say("❌ That isn't a webhook URL. Copy it from the channel's Integrations settings.")
+            .await?;
+        return Ok(());
+    };
+    // Checked now so a deleted webhook fails here rather than when it's due
+    let webhook = match serenity::Webhook::from_id_with_token(ctx, webhook_id, token).await {
+        Ok(webhook) => webhook,
+        Err(e) => {
+            log::info!("Rejected webhook {} for a reminder: {}", webhook_id, e);
+            ctx.say("❌ That webhook doesn't work any more. Copy its URL again from the channel's Integrations settings.")
+                .await?;
+            return Ok(());
+        }
+    };
+    let Some(channel_id) = webhook.channel_id else {
+        ctx.say("❌ That webhook can't be posted through. Use an incoming webhook's URL.")
+            .await?;
+        return Ok(());
+    };
+    if webhook.guild_id != ctx.guild_id() {
+        ctx.say("❌ That webhook isn't in this server!").await?;
+        return Ok(());
+    }
 
-            if let Err(e) = save_reminders(&data) {
-                ctx.say(format!("❌ Failed to save changes: {e}")).await?;
-                return Ok(());
+    if !check_reminder_time(ctx, now, remind_at).await? {
+        return Ok(());
+    }
+
+    let lock = REMINDERS_LOCK.lock().await;
+    let mut data = load_reminders();
+    let reminder = Reminder {
+        id: data.next_id,
+        user_id: ctx.author().id.get(),
+        channel_id: channel_id.get(),
+        message,
+        remind_at,
+        created_at: now,
+        reply_to_message_id: None,
+        nag_interval_minutes: None,
+        nag_count: 0,
+        acknowledged: false,
+        is_dm: false,
+        created_by: None,
+        snooze_count: 0,
+        guild_id: ctx.guild_id().map(|id| id.get()),
+        source_message_id: None,
+        relative_to: None,
+        relative_offset_secs: 0,
+        followup_after_minutes: None,
+        note_id: None,
+        priority: ReminderPriority::Normal,
+        cancel_message_id: None,
+        until_free: None,
+        webhook: Some(ReminderWebhook {
+            id: webhook_id.get(),
+            token: token.to_string(),
+            name,
+            avatar_url,
+        }),
+        sending: None,
+    };
+    data.reminders.push(reminder.clone());
+    data.next_id += 1;
+
+    if let Err(e) = save_reminders(&data) {
+        ctx.say(format!("❌ Failed to save reminder: {e}")).await?;
+        return Ok(());
+    }
+    drop(lock);
+
+    let posts_as = reminder
+        .webhook
+        .as_ref()
+        .and_then(|hook| hook.name.clone())
+        .or(webhook.name)
+        .unwrap_or_else(|| "the webhook".to_string());
+    let embed = CreateEmbed::new()
+        .title("🪝 Webhook Reminder Set!")
+        .description(format!(
+            "**Message:** {}\n**Remind at:** <t:{}:F> (<t:{}:R>)\n**Posts in:** <#{}> as {}",
+            reminder.message,
+            remind_at.timestamp(),
+            remind_at.timestamp(),
+            channel_id,
+            posts_as
+        ))
+        .color(Color::DARK_GREEN)
+        .footer(CreateEmbedFooter::new(format!(
+            "Reminder ID: {} • Sent as the bot instead if the webhook is deleted",
+            reminder.id
+        )))
+        .timestamp(now);
+    ctx.send(
+        poise::CreateReply::default()
+            .embed(embed)
+            .ephemeral(confirm_ephemerally(ctx)),
+    )
+    .await?;
+
+    log::info!(
+        "Webhook reminder {} set by {}",
+        reminder.id,
+        ctx.author().name
+    );
+    Ok(())
+}
+
+/// Split the leading `--name <name>` and `--avatar <link>` options off a
+/// `remind webhook` message. Returns the name, the avatar link and the rest
+/// of the message, or what's wrong with the options.
+fn parse_webhook_identity(text: &str) -> Result<(Option<String>, Option<String>, String), String> {
+    let mut name = None;
+    let mut avatar_url = None;
+    let mut rest = text.trim_start();
+    loop {
+        if let Some(after) = rest.strip_prefix("--name ") {
+            let after = after.trim_start();
+            let (value, remaining) = match after.strip_prefix('"') {
+                Some(quoted) => quoted
+                    .split_once('"')
+                    .ok_or("❌ The quoted `--name` is missing its closing quote.")?,
+                None => after.split_once(char::is_whitespace).unwrap_or((after, "")),
+            };
+            let value = value.trim();
+            if value.is_empty() || value.chars().count() > MAX_WEBHOOK_NAME_CHARS {
+                return Err(format!(
+                    "❌ The name must be 1 to {} characters.",
+                    MAX_WEBHOOK_NAME_CHARS
+                ));
+            }
+            name = Some(value.to_string());
+            rest = remaining.trim_start();
+        } else if let Some(after) = rest.strip_prefix("--avatar ") {
+            let after = after.trim_start();
+            let (value, remaining) = after.split_once(char::is_whitespace).unwrap_or((after, ""));
+            if !value.starts_with("https://") {
+                return Err("❌ The avatar has to be an `https://` link to an image.".to_string());
             }
+            avatar_url = Some(value.to_string());
+            rest = remaining.trim_start();
+        } else {
+            return Ok((name, avatar_url, rest.trim_end().to_string()));
+        }
+    }
+}
 
-            let embed = CreateEmbed::new()
-                .title("🗑️ Reminder Removed")
-                .description(format!("**Removed:** {}", removed_reminder.message))
-                .color(Color::DARK_RED)
-                .timestamp(Utc::now());
+/// Whether `remind set` confirms only to the user: slash commands in a
+/// server do, so the channel isn't filled with confirmations (the reminder
+/// still goes off publicly). Prefix commands can't reply ephemerally, and in
+/// a DM there's nobody to hide it from and the confirmation carries the ❌
+/// cancel reaction.
+fn confirm_ephemerally(ctx: Context<'_>) -> bool {
+    matches!(ctx, poise::Context::Application(_)) && ctx.guild_id().is_some()
+}
 
-            ctx.send(poise::CreateReply::default().embed(embed)).await?;
+/// The command message a reminder is set from. Slash commands don't have
+/// one; see [`link_confirmation_if_slash`].
+fn command_message_id(ctx: Context<'_>) -> Option<u64> {
+    match ctx {
+        poise::Context::Prefix(prefix_ctx) => Some(prefix_ctx.msg.id.get()),
+        poise::Context::Application(_) => None,
+    }
+}
+
+/// For reminders set with a slash command, use the bot's confirmation as
+/// the message to jump back to.
+async fn link_confirmation_if_slash(reminder: &Reminder, confirmation: &poise::ReplyHandle<'_>) {
+    if reminder.source_message_id.is_some() {
+        return;
+    }
+    let message_id = match confirmation.message().await {
+        Ok(message) => message.id.get(),
+        Err(e) => {
+            log::warn!(
+                "Failed to fetch confirmation for reminder {}: {}",
+                reminder.id,
+                e
+            );
+            return;
+        }
+    };
+
+    let _lock = REMINDERS_LOCK.lock().await;
+    let mut data = load_reminders();
+    let Some(stored) = data.reminders.iter_mut().find(|r| r.id == reminder.id) else {
+        return;
+    };
+    stored.source_message_id = Some(message_id);
+    if let Err(e) = save_reminders(&data) {
+        log::warn!("Failed to save link for reminder {}: {}", reminder.id, e);
+    }
+}
+
+/// Reacting with this to a reminder's DM copy cancels it.
+const CANCEL_EMOJI: &str = "❌";
 
+/// DM whoever set a reminder a copy with a ❌ reaction, so they can cancel
+/// it without looking up its ID. Set in a DM, the confirmation itself gets
+/// the reaction. If DMs are closed there's just no copy.
+async fn offer_cancel_reaction(
+    ctx: Context<'_>,
+    reminder: &Reminder,
+    confirmation: &poise::ReplyHandle<'_>,
+) {
+    let message = if ctx.guild_id().is_none() {
+        confirmation
+            .message()
+            .await
+            .map(|message| message.into_owned())
+    } else {
+        let copy = serenity::CreateMessage::new()
+            .content(cancel_copy_text(reminder))
+            .allowed_mentions(serenity::CreateAllowedMentions::new());
+        ctx.author().direct_message(ctx.http(), copy).await
+    };
+    let message = match message {
+        Ok(message) => message,
+        Err(e) => {
             log::info!(
-                "Reminder {} removed successfully by user {}",
-                id,
-                ctx.author().name
+                "No cancel copy for reminder {}, DMs are probably closed: {}",
+                reminder.id,
+                e
             );
+            return;
         }
-        None => {
-            ctx.say(
-                "❌ Reminder not found! Make sure you own this reminder and the ID is correct.",
+    };
+    if let Err(e) = message
+        .react(
+            ctx.http(),
+            serenity::ReactionType::Unicode(CANCEL_EMOJI.to_string()),
+        )
+        .await
+    {
+        // Reacting ❌ by hand still works
+        log::warn!(
+            "Failed to react to cancel copy of reminder {}: {}",
+            reminder.id,
+            e
+        );
+    }
+
+    let _lock = REMINDERS_LOCK.lock().await;
+    let mut data = load_reminders();
+    let Some(stored) = data.reminders.iter_mut().find(|r| r.id == reminder.id) else {
+        return;
+    };
+    stored.cancel_message_id = Some(message.id.get());
+    if let Err(e) = save_reminders(&data) {
+        log::warn!(
+            "Failed to save cancel copy for reminder {}: {}",
+            reminder.id,
+            e
+        );
+    }
+}
+
+fn cancel_copy_text(reminder: &Reminder) -> String {
+    let recipient = match reminder.created_by {
+        Some(_) => format!(" for <@{}>", reminder.user_id),
+        None => String::new(),
+    };
+    fit_content(&format!(
+        "⏰ Reminder #{}{} goes off <t:{}:F> (<t:{}:R>). React with {} to cancel it.\n> {}",
+        reminder.id,
+        recipient,
+        reminder.remind_at.timestamp(),
+        reminder.remind_at.timestamp(),
+        CANCEL_EMOJI,
+        reminder.message.replace('\n', "\n> ")
+    ))
+}
+
+/// What a ❌ on a DM message did.
+#[derive(Debug, PartialEq)]
+enum ReactionCancel {
+    /// (id, message, reminders chained to it that keep their planned time)
+    Cancelled(u64, String, usize),
+    AlreadyFired(u64),
+    NotACopy,
+}
+
+/// Cancel the reminder whose DM copy is `message_id`, if `user_id` set it
+/// or is its recipient.
+fn cancel_by_reaction(data: &mut RemindersData, message_id: u64, user_id: u64) -> ReactionCancel {
+    let is_copy = |r: &Reminder| {
+        r.cancel_message_id == Some(message_id)
+            && (r.user_id == user_id || r.created_by == Some(user_id))
+    };
+    if let Some(index) = data.reminders.iter().position(is_copy) {
+        let removed = data.reminders.remove(index);
+        let detached = detach_orphans(&mut data.reminders);
+        return ReactionCancel::Cancelled(removed.id, removed.message, detached);
+    }
+    match data.fired.iter().find(|r| is_copy(r)) {
+        Some(fired) => ReactionCancel::AlreadyFired(fired.id),
+        None => ReactionCancel::NotACopy,
+    }
+}
+
+/// Handle a reaction in a DM: ❌ on a reminder's copy cancels it.
+pub async fn handle_cancel_reaction(
+    ctx: &serenity::Context,
+    reaction: &serenity::Reaction,
+) -> Result<(), Error> {
+    if reaction.guild_id.is_some()
+        || !matches!(&reaction.emoji, serenity::ReactionType::Unicode(emoji) if emoji == CANCEL_EMOJI)
+    {
+        return Ok(());
+    }
+    let Some(user_id) = reaction.user_id else {
+        return Ok(());
+    };
+    // The bot's own ❌ is what was offered
+    if user_id == ctx.cache.current_user().id {
+        return Ok(());
+    }
+
+    let outcome = {
+        let _lock = REMINDERS_LOCK.lock().await;
+        let mut data = load_reminders();
+        let outcome = cancel_by_reaction(&mut data, reaction.message_id.get(), user_id.get());
+        if matches!(outcome, ReactionCancel::Cancelled(..)) {
+            save_reminders(&data)?;
+        }
+        outcome
+    };
+
+    let reply = match outcome {
+        ReactionCancel::Cancelled(id, message, detached) => {
+            log::info!("Reminder {} cancelled by DM reaction from {}", id, user_id);
+            let mut reply = format!("🗑️ Cancelled reminder #{}: {}", id, message);
+            if detached > 0 {
+                reply.push_str(&format!(
+                    "\n🔗 {} reminder(s) chained to it keep their planned time.",
+                    detached
+                ));
+            }
+            reply
+        }
+        ReactionCancel::AlreadyFired(id) => {
+            format!(
+                "⏰ Reminder #{} already went off, so there's nothing to cancel.",
+                id
             )
+        }
+        ReactionCancel::NotACopy => return Ok(()),
+    };
+    reaction
+        .channel_id
+        .send_message(
+            &ctx.http,
+            serenity::CreateMessage::new()
+                .content(fit_content(&reply))
+                .allowed_mentions(serenity::CreateAllowedMentions::new()),
+        )
+        .await?;
+    Ok(())
+}
+
+/// A user's status from any server the bot shares with them. `None` when
+/// no server has their presence, which is how offline members show up.
+fn cached_status(
+    ctx: &serenity::Context,
+    user_id: serenity::UserId,
+) -> Option<serenity::OnlineStatus> {
+    ctx.cache.guilds().into_iter().find_map(|guild_id| {
+        ctx.cache
+            .guild(guild_id)
+            .and_then(|guild| guild.presences.get(&user_id).map(|p| p.status))
+    })
+}
+
+/// Apply a presence change to the user's `remind whenfree` reminders: going
+/// away arms them, and coming back online after that makes them due now.
+/// Returns whether anything changed and whether any became due.
+fn apply_presence(
+    data: &mut RemindersData,
+    user_id: u64,
+    online: bool,
+    now: DateTime<Utc>,
+) -> (bool, bool) {
+    let (mut changed, mut due) = (false, false);
+    for reminder in data
+        .reminders
+        .iter_mut()
+        .filter(|r| r.user_id == user_id && r.remind_at > now)
+    {
+        match (&mut reminder.until_free, online) {
+            (Some(wait), false) if !wait.away_seen => {
+                wait.away_seen = true;
+                changed = true;
+            }
+            (Some(wait), true) if wait.away_seen => {
+                reminder.remind_at = now;
+                reminder.until_free = None;
+                changed = true;
+                due = true;
+            }
+            _ => {}
+        }
+    }
+    (changed, due)
+}
+
+/// Fire a user's `remind whenfree` reminders when they come back online.
+/// Needs the presence intent; without it these reminders go off at their
+/// deadline.
+pub async fn handle_presence_update(presence: &serenity::Presence) -> Result<(), Error> {
+    let user_id = presence.user.id.get();
+    if !WAITING_FOR_PRESENCE.read().contains(&user_id) {
+        return Ok(());
+    }
+
+    let online = presence.status == serenity::OnlineStatus::Online;
+    let _lock = REMINDERS_LOCK.lock().await;
+    let mut data = load_reminders();
+    let (changed, due) = apply_presence(&mut data, user_id, online, Utc::now());
+    if changed {
+        save_reminders(&data)?;
+    }
+    if due {
+        log::info!(
+            "User {} is back online, sending their when-free reminders",
+            user_id
+        );
+        wake_reminder_checker();
+    }
+    let still_waiting = data
+        .reminders
+        .iter()
+        .any(|r| r.user_id == user_id && r.until_free.is_some());
+    if !still_waiting {
+        WAITING_FOR_PRESENCE.write().remove(&user_id);
+    }
+    Ok(())
+}
+
+/// A link back to where a reminder was set. DM links use `@me` in place of
+/// the server ID.
+fn source_jump_link(reminder: &Reminder) -> Option<String> {
+    let message_id = reminder.source_message_id?;
+    let guild = match reminder.guild_id {
+        Some(guild_id) => guild_id.to_string(),
+        None if reminder.is_dm => "@me".to_string(),
+        None => return None,
+    };
+    Some(format!(
+        "https://discord.com/channels/{}/{}/{}",
+        guild, reminder.channel_id, message_id
+    ))
+}
+
+/// Whether the author may set reminders that ping other users: bot owners and
+/// members with Manage Messages in the current channel.
+async fn can_remind_others(ctx: Context<'_>) -> bool {
+    if is_protected_user(&ctx.author().name) {
+        return true;
+    }
+    let (Some(member), Some(channel)) = (ctx.author_member().await, ctx.guild_channel().await)
+    else {
+        return false;
+    };
+    ctx.guild().is_some_and(|guild| {
+        guild
+            .user_permissions_in(&channel, &member)
+            .manage_messages()
+    })
+}
+
+/// Work out when a reminder is due from a duration like `5m` or a phrase like
+/// "tonight". Phrases span several words, so with prefix commands most of the
+/// phrase ends up at the start of `message`; the returned message has the
+/// phrase stripped off.
+pub fn resolve_remind_time(
+    time: &str,
+    message: Option<String>,
+    now: DateTime<Utc>,
+) -> Result<(DateTime<Utc>, Option<String>), String> {
+    if let Some(duration) = parse_time_duration(time) {
+        let remind_at = now
+            .checked_add_signed(duration)
+            .ok_or("❌ That's too far in the future!".to_string())?;
+        return Ok((remind_at, message));
+    }
+
+    let input = format!("{} {}", time, message.as_deref().unwrap_or_default());
+    parse_time_phrase(&input, now, &REMINDER_TIMEZONE)
+        .map(|(remind_at, rest)| (remind_at, Some(rest.to_string())))
+        .ok_or_else(|| invalid_time_message(time))
+}
+
+/// `remind set random <message>` is a surprise: the reminder fires at a
+/// random time between a minute and a day from now. Deliberately not part of
+/// [`resolve_remind_time`], so `random` means nothing to `for`, `nag` or the
+/// filters.
+pub fn random_remind_time(
+    time: &str,
+    now: DateTime<Utc>,
+    rng: &mut impl Rng,
+) -> Option<DateTime<Utc>> {
+    if !time.eq_ignore_ascii_case(RANDOM_TIME_TOKEN) {
+        return None;
+    }
+    let seconds = rng.gen_range(RANDOM_TIME_RANGE_SECS);
+    Some(now + Duration::seconds(seconds))
+}
+
+/// Set a reminder that goes off a while after one of your other reminders
+#[poise::command(prefix_command, slash_command, rename = "after")]
+pub async fn remind_after(
+    ctx: Context<'_>,
+    #[description = "ID of your reminder to follow"] id: u64,
+    #[description = "How long after it goes off (e.g., 30m, 1h)"] offset: Arg<String>,
+    #[description = "Reminder message"]
+    #[rest]
+    message: String,
+) -> Result<(), Error> {
+    let Arg(offset) = offset;
+    log::info!(
+        "Remind after command called by {} for ID {} with offset: '{}'",
+        ctx.author().name,
+        id,
+        offset
+    );
+
+    let Some(offset) = parse_time_duration(&offset).filter(|d| *d > Duration::zero()) else {
+        ctx.say("❌ Invalid offset! Use something like `30m`, `2h` or `1d`.")
+            .await?;
+        return Ok(());
+    };
+    let message = message.trim().to_string();
+    if message.is_empty() {
+        ctx.say("❌ Please provide a reminder message!").await?;
+        return Ok(());
+    }
+
+    let now = Utc::now();
+    let user_id = ctx.author().id.get();
+    let data = load_reminders();
+    let Some(base) = data
+        .reminders
+        .iter()
+        .find(|r| r.id == id && r.user_id == user_id)
+    else {
+        let error = if data
+            .fired
+            .iter()
+            .any(|r| r.id == id && r.user_id == user_id)
+        {
+            "❌ That reminder already went off! Use `-remind set` instead."
+        } else {
+            "❌ Reminder not found! Make sure you own this reminder and the ID is correct."
+        };
+        ctx.say(error).await?;
+        return Ok(());
+    };
+    let Some(planned) = base.remind_at.checked_add_signed(offset) else {
+        ctx.say("❌ That's too far in the future!").await?;
+        return Ok(());
+    };
+
+    if !check_reminder_time(ctx, now, planned).await? {
+        return Ok(());
+    }
+
+    // The confirmation may have taken a while, so look again
+    let lock = REMINDERS_LOCK.lock().await;
+    let mut data = load_reminders();
+    if !data.reminders.iter().any(|r| r.id == id) {
+        ctx.say("❌ That reminder went off or was removed in the meantime.")
+            .await?;
+        return Ok(());
+    }
+
+    let reminder = Reminder {
+        id: data.next_id,
+        user_id,
+        channel_id: ctx.channel_id().get(),
+        message,
+        remind_at: planned,
+        created_at: now,
+        reply_to_message_id: None,
+        nag_interval_minutes: None,
+        nag_count: 0,
+        acknowledged: false,
+        is_dm: ctx.guild_id().is_none(),
+        created_by: None,
+        snooze_count: 0,
+        guild_id: ctx.guild_id().map(|id| id.get()),
+        source_message_id: command_message_id(ctx),
+        relative_to: Some(id),
+        relative_offset_secs: offset.num_seconds(),
+        followup_after_minutes: None,
+        note_id: None,
+        priority: ReminderPriority::Normal,
+        cancel_message_id: None,
+        until_free: None,
+        webhook: None,
+        sending: None,
+    };
+    data.reminders.push(reminder.clone());
+    data.next_id += 1;
+
+    if let Err(e) = save_reminders(&data) {
+        ctx.say(format!("❌ Failed to save reminder: {e}")).await?;
+        return Ok(());
+    }
+    drop(lock);
+
+    let embed = CreateEmbed::new()
+        .title("🔗 Chained Reminder Set!")
+        .description(format!(
+            "**Message:** {}\n**Goes off:** {} after reminder {} (planned <t:{}:F>)",
+            reminder.message,
+            format_duration(offset),
+            id,
+            planned.timestamp()
+        ))
+        .color(Color::DARK_GREEN)
+        .footer(CreateEmbedFooter::new(format!(
+            "Reminder ID: {} • Removing reminder {} keeps the planned time",
+            reminder.id, id
+        )))
+        .timestamp(now);
+
+    let confirmation = ctx.send(poise::CreateReply::default().embed(embed)).await?;
+    link_confirmation_if_slash(&reminder, &confirmation).await;
+    offer_cancel_reaction(ctx, &reminder, &confirmation).await;
+    Ok(())
+}
+
+/// Set a reminder that repeats until you acknowledge it
+#[poise::command(prefix_command, slash_command, rename = "nag")]
+pub async fn remind_nag(
+    ctx: Context<'_>,
+    #[description = "Time until the first reminder (e.g., 5m, 1h, 2d)"] time: Arg<String>,
+    #[description = "How often to repeat until acknowledged (e.g., 10m, 1h)"] every: Arg<String>,
+    #[description = "Reminder message"]
+    #[rest]
+    message: String,
+) -> Result<(), Error> {
+    let Arg(time) = time;
+    let Arg(every) = every;
+    log::info!(
+        "Remind nag command called by {} with time: '{}', every: '{}' and message: '{}'",
+        ctx.author().name,
+        time,
+        every,
+        message
+    );
+
+    let message = message.trim().to_string();
+    if message.is_empty() {
+        ctx.say("❌ Please provide a reminder message!").await?;
+        return Ok(());
+    }
+
+    let (duration, interval) = match (parse_time_duration(&time), parse_time_duration(&every)) {
+        (Some(d), Some(i)) => (d, i),
+        _ => {
+            ctx.say("❌ Invalid time format! Use formats like: 5m, 1h, 2d, 1w")
+                .await?;
+            return Ok(());
+        }
+    };
+
+    // The checker runs once a minute, so nagging any faster isn't possible
+    if interval < Duration::minutes(1) {
+        ctx.say("❌ The repeat interval must be at least 1 minute!")
             .await?;
+        return Ok(());
+    }
+
+    let now = Utc::now();
+    let (Some(remind_at), Ok(interval_minutes)) = (
+        now.checked_add_signed(duration),
+        u32::try_from(interval.num_minutes()),
+    ) else {
+        ctx.say("❌ That's too far in the future!").await?;
+        return Ok(());
+    };
+
+    if !check_reminder_time(ctx, now, remind_at).await? {
+        return Ok(());
+    }
+
+    let lock = REMINDERS_LOCK.lock().await;
+    let mut data = load_reminders();
+
+    let reminder = Reminder {
+        id: data.next_id,
+        user_id: ctx.author().id.get(),
+        channel_id: ctx.channel_id().get(),
+        message,
+        remind_at,
+        created_at: now,
+        reply_to_message_id: None,
+        nag_interval_minutes: Some(interval_minutes),
+        nag_count: 0,
+        acknowledged: false,
+        is_dm: ctx.guild_id().is_none(),
+        created_by: None,
+        snooze_count: 0,
+        guild_id: ctx.guild_id().map(|id| id.get()),
+        source_message_id: command_message_id(ctx),
+        relative_to: None,
+        relative_offset_secs: 0,
+        followup_after_minutes: None,
+        note_id: None,
+        priority: ReminderPriority::Normal,
+        cancel_message_id: None,
+        until_free: None,
+        webhook: None,
+        sending: None,
+    };
+
+    data.reminders.push(reminder.clone());
+    data.next_id += 1;
+
+    if let Err(e) = save_reminders(&data) {
+        ctx.say(format!("❌ Failed to save reminder: {e}")).await?;
+        return Ok(());
+    }
+    drop(lock);
+
+    let embed = CreateEmbed::new()
+        .title("🔁 Nag Reminder Set!")
+        .description(format!(
+            "**Message:** {}\n**First reminder:** <t:{}:F> (<t:{}:R>)\n**Repeats every:** {} until acknowledged (max {} times)",
+            reminder.message,
+            remind_at.timestamp(),
+            remind_at.timestamp(),
+            format_duration(interval),
+            MAX_NAGS
+        ))
+        .color(Color::DARK_GREEN)
+        .footer(CreateEmbedFooter::new(format!(
+            "Reminder ID: {} • Stop it with the button or /remind ack {}",
+            reminder.id, reminder.id
+        )))
+        .timestamp(now);
+
+    let confirmation = ctx.send(poise::CreateReply::default().embed(embed)).await?;
+    link_confirmation_if_slash(&reminder, &confirmation).await;
+    offer_cancel_reaction(ctx, &reminder, &confirmation).await;
+
+    log::info!(
+        "Nag reminder {} set successfully for user {}",
+        reminder.id,
+        ctx.author().name
+    );
+    Ok(())
+}
+
+/// Set a reminder that DMs you once if you don't react to it or reply
+#[poise::command(prefix_command, slash_command, rename = "followup")]
+pub async fn remind_followup(
+    ctx: Context<'_>,
+    #[description = "Time until the reminder (e.g., 5m, 1h, 2d)"] time: Arg<String>,
+    #[description = "How long to wait for a reaction or reply before nudging (e.g., 30m, 2h)"]
+    within: Arg<String>,
+    #[description = "Reminder message"]
+    #[rest]
+    message: String,
+) -> Result<(), Error> {
+    let Arg(time) = time;
+    let Arg(within) = within;
+    log::info!(
+        "Remind followup command called by {} with time: '{}', within: '{}' and message: '{}'",
+        ctx.author().name,
+        time,
+        within,
+        message
+    );
+
+    let message = message.trim().to_string();
+    if message.is_empty() {
+        ctx.say("❌ Please provide a reminder message!").await?;
+        return Ok(());
+    }
+
+    let (duration, window) = match (parse_time_duration(&time), parse_time_duration(&within)) {
+        (Some(d), Some(w)) => (d, w),
+        _ => {
+            ctx.say("❌ Invalid time format! Use formats like: 5m, 1h, 2d, 1w")
+                .await?;
+            return Ok(());
+        }
+    };
+
+    // The checker runs once a minute, and unanswered reminders are only
+    // tracked for a day
+    if window < Duration::minutes(1) || window > Duration::hours(MAX_FOLLOWUP_WINDOW_HOURS) {
+        ctx.say(format!(
+            "❌ The follow-up window must be between 1 minute and {} hours!",
+            MAX_FOLLOWUP_WINDOW_HOURS
+        ))
+        .await?;
+        return Ok(());
+    }
+
+    let now = Utc::now();
+    let Some(remind_at) = now.checked_add_signed(duration) else {
+        ctx.say("❌ That's too far in the future!").await?;
+        return Ok(());
+    };
+
+    if !check_reminder_time(ctx, now, remind_at).await? {
+        return Ok(());
+    }
+
+    let lock = REMINDERS_LOCK.lock().await;
+    let mut data = load_reminders();
+
+    let reminder = Reminder {
+        id: data.next_id,
+        user_id: ctx.author().id.get(),
+        channel_id: ctx.channel_id().get(),
+        message,
+        remind_at,
+        created_at: now,
+        reply_to_message_id: None,
+        nag_interval_minutes: None,
+        nag_count: 0,
+        acknowledged: false,
+        is_dm: ctx.guild_id().is_none(),
+        created_by: None,
+        snooze_count: 0,
+        guild_id: ctx.guild_id().map(|id| id.get()),
+        source_message_id: command_message_id(ctx),
+        relative_to: None,
+        relative_offset_secs: 0,
+        followup_after_minutes: Some(window.num_minutes() as u32),
+        note_id: None,
+        priority: ReminderPriority::Normal,
+        cancel_message_id: None,
+        until_free: None,
+        webhook: None,
+        sending: None,
+    };
+
+    data.reminders.push(reminder.clone());
+    data.next_id += 1;
+
+    if let Err(e) = save_reminders(&data) {
+        ctx.say(format!("❌ Failed to save reminder: {e}")).await?;
+        return Ok(());
+    }
+    drop(lock);
+
+    let embed = CreateEmbed::new()
+        .title("👋 Follow-up Reminder Set!")
+        .description(format!(
+            "**Message:** {}\n**Remind at:** <t:{}:F> (<t:{}:R>)\n**Follow-up:** one DM if you don't react or reply within {}",
+            reminder.message,
+            remind_at.timestamp(),
+            remind_at.timestamp(),
+            format_duration(window)
+        ))
+        .color(Color::DARK_GREEN)
+        .footer(CreateEmbedFooter::new(format!(
+            "Reminder ID: {}",
+            reminder.id
+        )))
+        .timestamp(now);
+
+    let confirmation = ctx.send(poise::CreateReply::default().embed(embed)).await?;
+    link_confirmation_if_slash(&reminder, &confirmation).await;
+    offer_cancel_reaction(ctx, &reminder, &confirmation).await;
+
+    log::info!(
+        "Follow-up reminder {} set successfully for user {}",
+        reminder.id,
+        ctx.author().name
+    );
+    Ok(())
+}
+
+/// Set a reminder that goes off when you're next online, or by a deadline
+#[poise::command(prefix_command, slash_command, rename = "whenfree")]
+pub async fn remind_whenfree(
+    ctx: Context<'_>,
+    #[description = "Deadline: it goes off after this long even if you're not back (e.g., 2h, 1d)"]
+    within: Arg<String>,
+    #[description = "Reminder message"]
+    #[rest]
+    message: String,
+) -> Result<(), Error> {
+    let Arg(within) = within;
+    log::info!(
+        "Remind whenfree command called by {} with within: '{}' and message: '{}'",
+        ctx.author().name,
+        within,
+        message
+    );
+
+    let message = message.trim().to_string();
+    if message.is_empty() {
+        ctx.say("❌ Please provide a reminder message!").await?;
+        return Ok(());
+    }
+
+    let Some(duration) = parse_time_duration(&within) else {
+        ctx.say("❌ Invalid time format! Use formats like: 5m, 1h, 2d, 1w")
+            .await?;
+        return Ok(());
+    };
+
+    let now = Utc::now();
+    let Some(remind_at) = now.checked_add_signed(duration) else {
+        ctx.say("❌ That's too far in the future!").await?;
+        return Ok(());
+    };
+
+    if !check_reminder_time(ctx, now, remind_at).await? {
+        return Ok(());
+    }
+
+    // Without the presence intent there's no telling when someone's back,
+    // so it's a reminder for the deadline. Offline members have no presence
+    // in the cache, so otherwise no status means away.
+    let watch_presence = crate::presence_intent_enabled();
+    let status = cached_status(ctx.serenity_context(), ctx.author().id);
+    let away = status != Some(serenity::OnlineStatus::Online);
+
+    let lock = REMINDERS_LOCK.lock().await;
+    let mut data = load_reminders();
+
+    let reminder = Reminder {
+        id: data.next_id,
+        user_id: ctx.author().id.get(),
+        channel_id: ctx.channel_id().get(),
+        message,
+        remind_at,
+        created_at: now,
+        reply_to_message_id: None,
+        nag_interval_minutes: None,
+        nag_count: 0,
+        acknowledged: false,
+        is_dm: ctx.guild_id().is_none(),
+        created_by: None,
+        snooze_count: 0,
+        guild_id: ctx.guild_id().map(|id| id.get()),
+        source_message_id: command_message_id(ctx),
+        relative_to: None,
+        relative_offset_secs: 0,
+        followup_after_minutes: None,
+        note_id: None,
+        priority: ReminderPriority::Normal,
+        cancel_message_id: None,
+        until_free: watch_presence.then_some(UntilFree { away_seen: away }),
+        webhook: None,
+        sending: None,
+    };
+
+    data.reminders.push(reminder.clone());
+    data.next_id += 1;
+
+    if let Err(e) = save_reminders(&data) {
+        ctx.say(format!("❌ Failed to save reminder: {e}")).await?;
+        return Ok(());
+    }
+    if watch_presence {
+        WAITING_FOR_PRESENCE.write().insert(reminder.user_id);
+    }
+    drop(lock);
+
+    let when = if !watch_presence {
+        "at the deadline (I can't see who's online, so I can't tell when you're back)"
+    } else if away {
+        "as soon as you're back online"
+    } else {
+        "when you're back online after being idle, busy or offline"
+    };
+    let embed = CreateEmbed::new()
+        .title("🟢 Reminder Set for When You're Free!")
+        .description(format!(
+            "**Message:** {}\n**Goes off:** {}\n**At the latest:** <t:{}:F> (<t:{}:R>)",
+            reminder.message,
+            when,
+            remind_at.timestamp(),
+            remind_at.timestamp()
+        ))
+        .color(Color::DARK_GREEN)
+        .footer(CreateEmbedFooter::new(format!(
+            "Reminder ID: {}",
+            reminder.id
+        )))
+        .timestamp(now);
+
+    let confirmation = ctx.send(poise::CreateReply::default().embed(embed)).await?;
+    link_confirmation_if_slash(&reminder, &confirmation).await;
+    offer_cancel_reaction(ctx, &reminder, &confirmation).await;
+
+    log::info!(
+        "When-free reminder {} set successfully for user {}",
+        reminder.id,
+        ctx.author().name
+    );
+    Ok(())
+}
+
+/// Acknowledge a nag reminder so it stops repeating
+#[poise::command(prefix_command, slash_command, rename = "ack")]
+pub async fn remind_ack(
+    ctx: Context<'_>,
+    #[description = "Reminder ID to acknowledge"] id: u64,
+) -> Result<(), Error> {
+    log::info!(
+        "Remind ack command called by {} for ID: {}",
+        ctx.author().name,
+        id
+    );
+
+    match acknowledge_reminder(id, ctx.author().id.get()).await {
+        Ok(true) => {
+            ctx.say(format!(
+                "✅ Reminder {id} acknowledged, it won't nag you again."
+            ))
+            .await?;
+        }
+        Ok(false) => {
+            ctx.say(
+                "❌ Nag reminder not found! Make sure you own this reminder and the ID is correct.",
+            )
+            .await?;
+        }
+        Err(e) => {
+            ctx.say(format!("❌ Failed to save changes: {e}")).await?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Mark a user's nag reminder as acknowledged. Returns whether one was found.
+async fn acknowledge_reminder(id: u64, user_id: u64) -> Result<bool, Error> {
+    let _lock = REMINDERS_LOCK.lock().await;
+    let mut data = load_reminders();
+    let found = match data.reminders.iter_mut().find(|r| {
+        r.id == id && r.user_id == user_id && r.nag_interval_minutes.is_some() && !r.acknowledged
+    }) {
+        Some(reminder) => {
+            reminder.acknowledged = true;
+            true
+        }
+        None => false,
+    };
+
+    if found {
+        save_reminders(&data)?;
+    }
+    Ok(found)
+}
+
+/// Re-create a fired reminder `minutes` from `now`, with a new ID and its
+/// snooze count bumped. Only the recipient can snooze their reminder.
+fn snooze_fired_reminder(
+    data: &mut RemindersData,
+    id: u64,
+    user_id: u64,
+    minutes: u32,
+    now: DateTime<Utc>,
+) -> Option<Reminder> {
+    let index = data
+        .fired
+        .iter()
+        .position(|r| r.id == id && r.user_id == user_id)?;
+    let mut reminder = data.fired.remove(index);
+    // Snoozing is an answer too, so no follow-up nudge for it
+    data.awaiting_followup
+        .retain(|pending| pending.reminder_id != id);
+
+    reminder.id = data.next_id;
+    data.next_id += 1;
+    reminder.remind_at = now + Duration::minutes(minutes as i64);
+    reminder.snooze_count += 1;
+    // A snooze is for a set time, not until the recipient is next online
+    reminder.until_free = None;
+    data.reminders.push(reminder.clone());
+    Some(reminder)
+}
+
+/// A gentle push for reminders that keep getting snoozed.
+fn snooze_nudge(snooze_count: u32) -> Option<String> {
+    (snooze_count >= SNOOZE_NUDGE_THRESHOLD).then(|| {
+        format!(
+            "That's {} snoozes now... maybe just do it? 😅",
+            snooze_count
+        )
+    })
+}
+
+/// Handle a press of a snooze button on a fired reminder.
+pub async fn handle_snooze_button(
+    ctx: &serenity::Context,
+    interaction: &serenity::ComponentInteraction,
+) -> Result<(), Error> {
+    let Some((id, minutes)) = interaction
+        .data
+        .custom_id
+        .strip_prefix(SNOOZE_BUTTON_PREFIX)
+        .and_then(|rest| rest.split_once(':'))
+        .and_then(|(id, minutes)| Some((id.parse::<u64>().ok()?, minutes.parse::<u32>().ok()?)))
+    else {
+        return Ok(());
+    };
+
+    let snoozed = {
+        let _lock = REMINDERS_LOCK.lock().await;
+        let mut data = load_reminders();
+        let snoozed = snooze_fired_reminder(
+            &mut data,
+            id,
+            interaction.user.id.get(),
+            minutes,
+            Utc::now(),
+        );
+        if snoozed.is_some() {
+            save_reminders(&data)?;
+        }
+        snoozed
+    };
+
+    let response = match snoozed {
+        Some(reminder) => {
+            log::info!(
+                "Reminder {} snoozed by {} for {} minutes (now reminder {})",
+                id,
+                interaction.user.name,
+                minutes,
+                reminder.id
+            );
+            let mut content = format!(
+                "💤 Snoozed until <t:{}:R> (snoozed {}×).",
+                reminder.remind_at.timestamp(),
+                reminder.snooze_count
+            );
+            if let Some(nudge) = snooze_nudge(reminder.snooze_count) {
+                content.push_str(&format!("\n{}", nudge));
+            }
+            serenity::CreateInteractionResponse::UpdateMessage(
+                serenity::CreateInteractionResponseMessage::new()
+                    .content(content)
+                    .components(vec![]),
+            )
+        }
+        None => serenity::CreateInteractionResponse::Message(
+            serenity::CreateInteractionResponseMessage::new()
+                .content("❌ Only the person this reminder is for can snooze it, or it's too old to snooze.")
+                .ephemeral(true),
+        ),
+    };
+
+    interaction.create_response(&ctx.http, response).await?;
+    Ok(())
+}
+
+/// Handle a press of the "acknowledge" button on a nag reminder.
+pub async fn handle_ack_button(
+    ctx: &serenity::Context,
+    interaction: &serenity::ComponentInteraction,
+) -> Result<(), Error> {
+    let Some(id) = interaction
+        .data
+        .custom_id
+        .strip_prefix(ACK_BUTTON_PREFIX)
+        .and_then(|id| id.parse::<u64>().ok())
+    else {
+        return Ok(());
+    };
+
+    let response = match acknowledge_reminder(id, interaction.user.id.get()).await? {
+        true => {
+            log::info!(
+                "Reminder {} acknowledged by {} via button",
+                id,
+                interaction.user.name
+            );
+            serenity::CreateInteractionResponse::UpdateMessage(
+                serenity::CreateInteractionResponseMessage::new()
+                    .content(format!("✅ <@{}> acknowledged this reminder.", interaction.user.id))
+                    .components(vec![]),
+            )
+        }
+        false => serenity::CreateInteractionResponse::Message(
+            serenity::CreateInteractionResponseMessage::new()
+                .content("❌ Only the person who set this reminder can acknowledge it, or it was already acknowledged.")
+                .ephemeral(true),
+        ),
+    };
+
+    interaction.create_response(&ctx.http, response).await?;
+    Ok(())
+}
+
+/// List your active reminders
+#[poise::command(prefix_command, slash_command, rename = "list")]
+pub async fn remind_list(
+    ctx: Context<'_>,
+    #[description = "Only show reminders whose message contains this text"] filter: Option<
+        Arg<String>,
+    >,
+    #[description = "Only show reminders due within this time (e.g., 24h, 7d)"] within: Option<
+        Arg<String>,
+    >,
+    #[description = "Sort order (default: soonest)"] sort: Option<ReminderSort>,
+) -> Result<(), Error> {
+    let filter = filter.map(|Arg(filter)| filter);
+    let within = within.map(|Arg(within)| within);
+    log::info!("Remind list command called by {}", ctx.author().name);
+
+    let within = match within.as_deref().map(parse_time_duration) {
+        Some(None) => {
+            ctx.say("❌ Invalid time format for `within`! Use formats like: 5m, 1h, 2d, 1w")
+                .await?;
+            return Ok(());
+        }
+        Some(duration) => duration,
+        None => None,
+    };
+
+    let data = load_reminders();
+    let now = Utc::now();
+
+    let mut user_reminders = filter_reminders(
+        data.reminders,
+        ctx.author().id.get(),
+        filter.as_deref(),
+        within,
+        now,
+    );
+    sort_reminders(&mut user_reminders, sort.unwrap_or_default());
+
+    if user_reminders.is_empty() {
+        let message = if filter.is_some() || within.is_some() {
+            "📭 No reminders match those filters!"
+        } else {
+            "📭 You have no active reminders!"
+        };
+        ctx.say(message).await?;
+        return Ok(());
+    }
+
+    let pages: Vec<String> = user_reminders
+        .chunks(REMINDERS_PER_PAGE)
+        .map(|chunk| chunk.iter().map(format_reminder_entry).collect())
+        .collect();
+
+    if pages.len() == 1 {
+        let embed = CreateEmbed::new()
+            .title("📋 Your Active Reminders")
+            .description(fit_description(&pages[0]))
+            .color(Color::BLUE)
+            .footer(CreateEmbedFooter::new(format!(
+                "Total active reminders: {}",
+                user_reminders.len()
+            )))
+            .timestamp(now);
+
+        ctx.send(poise::CreateReply::default().embed(embed)).await?;
+    } else {
+        let page_count = pages.len();
+        let pages: Vec<String> = pages
+            .into_iter()
+            .enumerate()
+            .map(|(i, page)| {
+                // Long reminder messages can push a page past the embed limit
+                fit_description(&format!(
+                    "**📋 Your Active Reminders ({} total, page {}/{})**\n\n{}",
+                    user_reminders.len(),
+                    i + 1,
+                    page_count,
+                    page
+                ))
+            })
+            .collect();
+        let page_refs: Vec<&str> = pages.iter().map(String::as_str).collect();
+        poise::builtins::paginate(ctx, &page_refs).await?;
+    }
+
+    log::info!(
+        "Listed {} reminders for user {}",
+        user_reminders.len(),
+        ctx.author().name
+    );
+    Ok(())
+}
+
+/// A user's active reminders, optionally narrowed to those whose message
+/// contains `filter` (case-insensitive) and that are due within `within`.
+fn filter_reminders(
+    reminders: Vec<Reminder>,
+    user_id: u64,
+    filter: Option<&str>,
+    within: Option<Duration>,
+    now: DateTime<Utc>,
+) -> Vec<Reminder> {
+    let filter = filter.map(str::to_lowercase);
+    reminders
+        .into_iter()
+        .filter(|r| r.user_id == user_id && r.remind_at > now && !r.acknowledged)
+        .filter(|r| {
+            filter
+                .as_ref()
+                .is_none_or(|text| r.message.to_lowercase().contains(text))
+        })
+        .filter(|r| within.is_none_or(|within| r.remind_at - now <= within))
+        .collect()
+}
+
+fn sort_reminders(reminders: &mut [Reminder], sort: ReminderSort) {
+    match sort {
+        ReminderSort::Soonest => reminders.sort_by_key(|r| (r.remind_at, r.id)),
+        ReminderSort::Newest => {
+            reminders.sort_by(|a, b| b.created_at.cmp(&a.created_at).then(b.id.cmp(&a.id)))
+        }
+    }
+}
+
+fn format_reminder_entry(reminder: &Reminder) -> String {
+    let mut details = match reminder.relative_to {
+        Some(base) => format!(
+            "🔗 {} after ID {} (planned <t:{}:R>)",
+            format_duration(Duration::seconds(reminder.relative_offset_secs)),
+            base,
+            reminder.remind_at.timestamp()
+        ),
+        None => format!("⏰ <t:{}:R>", reminder.remind_at.timestamp()),
+    };
+    if let Some(minutes) = reminder.nag_interval_minutes {
+        details.push_str(&format!(
+            " • 🔁 every {}",
+            format_duration(Duration::minutes(minutes as i64))
+        ));
+    }
+    if let Some(minutes) = reminder.followup_after_minutes {
+        details.push_str(&format!(
+            " • 👋 nudge after {}",
+            format_duration(Duration::minutes(minutes as i64))
+        ));
+    }
+    if reminder.until_free.is_some() {
+        details.push_str(" • 🟢 sooner when you're back online");
+    }
+    if reminder.is_dm {
+        details.push_str(" • 📩 DM");
+    }
+    if reminder.webhook.is_some() {
+        details.push_str(" • 🪝 via webhook");
+    }
+    if let Some(creator) = reminder.created_by {
+        details.push_str(&format!(" • 👤 set by <@{}>", creator));
+    }
+    if reminder.snooze_count > 0 {
+        details.push_str(&format!(" • 💤 snoozed {}×", reminder.snooze_count));
+    }
+    if let Some(label) = priority_label(reminder.priority) {
+        details.push_str(&format!(" • {}", label));
+    }
+    format!(
+        "**ID {}:** {}\n{}\n\n",
+        reminder.id, reminder.message, details
+    )
+}
+
+/// Remove a specific reminder by ID
+#[poise::command(prefix_command, slash_command, rename = "remove")]
+pub async fn remind_remove(
+    ctx: Context<'_>,
+    #[description = "Reminder ID to remove"] id: u64,
+) -> Result<(), Error> {
+    log::info!(
+        "Remind remove command called by {} for ID: {}",
+        ctx.author().name,
+        id
+    );
+
+    let lock = REMINDERS_LOCK.lock().await;
+    let mut data = load_reminders();
+    let user_id = ctx.author().id.get();
+
+    // Find the reminder
+    let reminder_index = data
+        .reminders
+        .iter()
+        .position(|r| r.id == id && r.user_id == user_id);
+
+    match reminder_index {
+        Some(index) => {
+            let removed_reminder = data.reminders.remove(index);
+            let detached = detach_orphans(&mut data.reminders);
+
+            if let Err(e) = save_reminders(&data) {
+                ctx.say(format!("❌ Failed to save changes: {e}")).await?;
+                return Ok(());
+            }
+            drop(lock);
+
+            let mut description = format!("**Removed:** {}", removed_reminder.message);
+            if detached > 0 {
+                description.push_str(&format!(
+                    "\n🔗 {} reminder(s) chained to it keep their planned time.",
+                    detached
+                ));
+            }
+            let embed = CreateEmbed::new()
+                .title("🗑️ Reminder Removed")
+                .description(description)
+                .color(Color::DARK_RED)
+                .timestamp(Utc::now());
+
+            ctx.send(poise::CreateReply::default().embed(embed)).await?;
+
+            log::info!(
+                "Reminder {} removed successfully by user {}",
+                id,
+                ctx.author().name
+            );
+        }
+        None => {
+            ctx.say(
+                "❌ Reminder not found! Make sure you own this reminder and the ID is correct.",
+            )
+            .await?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Longest reminder message `remind edit` accepts, in characters.
+const MAX_REMINDER_MESSAGE_CHARS: usize = 1000;
+
+/// Tidy up a reminder message: trim it, drop control characters other than
+/// line breaks and defuse `@everyone`/`@here` so the fired reminder can't
+/// ping a whole server.
+pub(crate) fn clean_reminder_message(message: &str) -> Result<String, String> {
+    let cleaned: String = message
+        .trim()
+        .chars()
+        .filter(|c| !c.is_control() || *c == '\n')
+        .collect::<String>()
+        .replace("@everyone", "@\u{200B}everyone")
+        .replace("@here", "@\u{200B}here");
+    if cleaned.is_empty() {
+        return Err("❌ Please provide a reminder message!".to_string());
+    }
+    let length = cleaned.chars().count();
+    if length > MAX_REMINDER_MESSAGE_CHARS {
+        return Err(format!(
+            "❌ That message is {} characters long; reminders can be at most {}.",
+            length, MAX_REMINDER_MESSAGE_CHARS
+        ));
+    }
+    Ok(cleaned)
+}
+
+/// The old values of whatever `remind edit` changed; unchanged fields are
+/// `None`.
+#[derive(Debug, PartialEq)]
+struct ReminderEdit {
+    old_message: Option<String>,
+    old_remind_at: Option<DateTime<Utc>>,
+    /// The reminder it was chained to, if a new time unchained it.
+    unchained_from: Option<u64>,
+}
+
+/// Change the time and/or message of `user_id`'s reminder `id` in place, so
+/// its ID, reply and creation time stay. Reminders chained to it move with
+/// it; a nag carries on nagging from the new time.
+fn edit_reminder(
+    data: &mut RemindersData,
+    id: u64,
+    user_id: u64,
+    remind_at: Option<DateTime<Utc>>,
+    message: Option<String>,
+) -> Result<ReminderEdit, &'static str> {
+    let Some(reminder) = data
+        .reminders
+        .iter_mut()
+        .find(|r| r.id == id && r.user_id == user_id)
+    else {
+        return Err(
+            if data
+                .fired
+                .iter()
+                .any(|r| r.id == id && r.user_id == user_id)
+            {
+                "❌ That reminder already went off, so it can't be edited. Use `-remind set` for a new one."
+            } else {
+                "❌ Reminder not found! Make sure you own this reminder and the ID is correct."
+            },
+        );
+    };
+
+    let mut edit = ReminderEdit {
+        old_message: None,
+        old_remind_at: None,
+        unchained_from: None,
+    };
+    if let Some(message) = message.filter(|m| *m != reminder.message) {
+        edit.old_message = Some(std::mem::replace(&mut reminder.message, message));
+    }
+    if let Some(remind_at) = remind_at {
+        // An explicit time replaces waiting for another reminder
+        edit.unchained_from = reminder.relative_to.take();
+        if remind_at != reminder.remind_at {
+            edit.old_remind_at = Some(std::mem::replace(&mut reminder.remind_at, remind_at));
+        }
+        reschedule_dependents(&mut data.reminders, id, remind_at);
+    }
+    Ok(edit)
+}
+
+/// The confirmation for `remind edit`: each changed field as before → after.
+fn edit_summary(edit: &ReminderEdit, reminder: &Reminder) -> String {
+    let at = reminder.remind_at.timestamp();
+    let mut lines = Vec::new();
+    match &edit.old_message {
+        Some(old) => lines.push(format!(
+            "**Message:** ~~{}~~\n→ {}",
+            old.replace("~~", ""),
+            reminder.message
+        )),
+        None => lines.push(format!("**Message:** {}", reminder.message)),
+    }
+    match edit.old_remind_at {
+        Some(old) => lines.push(format!(
+            "**Remind at:** ~~<t:{}:F>~~\n→ <t:{}:F> (<t:{}:R>)",
+            old.timestamp(),
+            at,
+            at
+        )),
+        None => lines.push(format!("**Remind at:** <t:{}:F> (<t:{}:R>)", at, at)),
+    }
+    if let Some(base) = edit.unchained_from {
+        lines.push(format!("🔗 No longer waits for reminder {}.", base));
+    }
+    if edit.old_message.is_none() && edit.old_remind_at.is_none() && edit.unchained_from.is_none() {
+        lines.push("Nothing changed.".to_string());
+    }
+    lines.join("\n")
+}
+
+/// Change the time and/or message of one of your reminders
+///
+/// To change only the message with the prefix command, give `-` as the
+/// time: `-remind edit 3 - buy oat milk`.
+#[poise::command(prefix_command, slash_command, rename = "edit")]
+pub async fn remind_edit(
+    ctx: Context<'_>,
+    #[description = "Reminder ID to edit"] id: u64,
+    #[description = "New time from now (e.g., 5m, 1h, tonight); leave out or `-` to keep it"]
+    time: Option<Arg<String>>,
+    #[description = "New reminder message; leave out to keep it"]
+    #[rest]
+    message: Option<String>,
+) -> Result<(), Error> {
+    let time = time.map(|Arg(time)| time);
+    log::info!(
+        "Remind edit command called by {} for ID {} with time: {:?} and message: {:?}",
+        ctx.author().name,
+        id,
+        time,
+        message
+    );
+
+    let now = Utc::now();
+    let time = time.filter(|t| !t.trim().is_empty() && t.trim() != "-");
+    let (remind_at, message) = match time {
+        Some(time) => match resolve_remind_time(&time, message, now) {
+            Ok((at, message)) => (Some(at), message),
+            Err(error) => {
+                ctx.say(format!(
+                    "{}\nTo change only the message, give `-` as the time: \
+                     `-remind edit {} - <message>`",
+                    error, id
+                ))
+                .await?;
+                return Ok(());
+            }
+        },
+        None => (None, message),
+    };
+    // A time phrase may have used up the whole message
+    let message = match message.filter(|m| !m.trim().is_empty()) {
+        Some(message) => match clean_reminder_message(&message) {
+            Ok(message) => Some(message),
+            Err(error) => {
+                ctx.say(error).await?;
+                return Ok(());
+            }
+        },
+        None => None,
+    };
+    if remind_at.is_none() && message.is_none() {
+        ctx.say("❌ Give a new time, a new message or both: `-remind edit <id> [time] [message]`")
+            .await?;
+        return Ok(());
+    }
+
+    if let Some(at) = remind_at {
+        if !check_reminder_time(ctx, now, at).await? {
+            return Ok(());
+        }
+    }
+
+    // Load after any confirmation, so nothing that fired meanwhile is lost
+    let lock = REMINDERS_LOCK.lock().await;
+    let mut data = load_reminders();
+    let user_id = ctx.author().id.get();
+    let edit = match edit_reminder(&mut data, id, user_id, remind_at, message) {
+        Ok(edit) => edit,
+        Err(error) => {
+            ctx.say(error).await?;
+            return Ok(());
+        }
+    };
+    if let Err(e) = save_reminders(&data) {
+        ctx.say(format!("❌ Failed to save changes: {e}")).await?;
+        return Ok(());
+    }
+    drop(lock);
+    let Some(reminder) = data.reminders.iter().find(|r| r.id == id) else {
+        return Ok(());
+    };
+
+    let embed = CreateEmbed::new()
+        .title("✏️ Reminder Edited")
+        .description(edit_summary(&edit, reminder))
+        .color(Color::DARK_GREEN)
+        .footer(CreateEmbedFooter::new(format!("Reminder ID: {}", id)))
+        .timestamp(now);
+    ctx.send(poise::CreateReply::default().embed(embed)).await?;
+
+    log::info!("Reminder {} edited by {}", id, ctx.author().name);
+    Ok(())
+}
+
+/// Clear all your reminders
+#[poise::command(prefix_command, slash_command, rename = "clear")]
+pub async fn remind_clear(ctx: Context<'_>) -> Result<(), Error> {
+    log::info!("Remind clear command called by {}", ctx.author().name);
+
+    let lock = REMINDERS_LOCK.lock().await;
+    let mut data = load_reminders();
+    let user_id = ctx.author().id.get();
+
+    let initial_count = data.reminders.len();
+    data.reminders.retain(|r| r.user_id != user_id);
+    let removed_count = initial_count - data.reminders.len();
+
+    if removed_count == 0 {
+        ctx.say("📭 You have no reminders to clear!").await?;
+        return Ok(());
+    }
+
+    if let Err(e) = save_reminders(&data) {
+        ctx.say(format!("❌ Failed to save changes: {e}")).await?;
+        return Ok(());
+    }
+    drop(lock);
+
+    let embed = CreateEmbed::new()
+        .title("🧹 Reminders Cleared")
+        .description(format!("Removed {removed_count} reminder(s)"))
+        .color(Color::ORANGE)
+        .timestamp(Utc::now());
+
+    ctx.send(poise::CreateReply::default().embed(embed)).await?;
+
+    log::info!(
+        "Cleared {} reminders for user {}",
+        removed_count,
+        ctx.author().name
+    );
+    Ok(())
+}
+
+/// Hold your reminders during quiet hours; ones due then go off when they end
+#[poise::command(
+    prefix_command,
+    slash_command,
+    rename = "quiethours",
+    subcommands("remind_quiethours_set", "remind_quiethours_off")
+)]
+pub async fn remind_quiethours(ctx: Context<'_>) -> Result<(), Error> {
+    let data = load_reminders();
+    match data.quiet_hours.get(&ctx.author().id.get()) {
+        Some(quiet) => {
+            ctx.say(format!(
+                "🌙 Your quiet hours are {}. Reminders due then go off when they end, except high priority ones.",
+                quiet.describe()
+            ))
+            .await?;
+        }
+        None => {
+            ctx.say("🔔 You have no quiet hours. Set them with `-remind quiethours set 22:00 07:00 [timezone]`.")
+                .await?;
+        }
+    }
+    Ok(())
+}
+
+/// Set your quiet hours, e.g. 22:00 to 07:00
+#[poise::command(prefix_command, slash_command, rename = "set")]
+pub async fn remind_quiethours_set(
+    ctx: Context<'_>,
+    #[description = "When quiet hours start, e.g. 22:00"] start: Arg<String>,
+    #[description = "When they end, e.g. 07:00"] end: Arg<String>,
+    #[description = "IANA timezone, e.g. Europe/Copenhagen (default: the bot's)"] timezone: Option<
+        Arg<String>,
+    >,
+) -> Result<(), Error> {
+    let Arg(start) = start;
+    let Arg(end) = end;
+    let timezone = timezone.map(|Arg(timezone)| timezone);
+    log::info!(
+        "Remind quiethours set command called by {}",
+        ctx.author().name
+    );
+
+    let (Some((start_hour, start_minute)), Some((end_hour, end_minute))) =
+        (parse_clock_time(&start), parse_clock_time(&end))
+    else {
+        ctx.say("❌ Give the start and end as times of day like `22:00` and `07:00`.")
+            .await?;
+        return Ok(());
+    };
+    if (start_hour, start_minute) == (end_hour, end_minute) {
+        ctx.say("❌ Quiet hours need to start and end at different times!")
+            .await?;
+        return Ok(());
+    }
+    let timezone = match timezone {
+        Some(timezone) => match timezone.trim().parse::<Tz>() {
+            Ok(tz) => Some(tz.name().to_string()),
+            Err(_) => {
+                ctx.say("❌ Unknown timezone. Use a name like `Europe/Copenhagen`, `America/New_York` or `UTC`.")
+                    .await?;
+                return Ok(());
+            }
+        },
+        None => None,
+    };
+    let quiet = QuietHours {
+        start_hour,
+        start_minute,
+        end_hour,
+        end_minute,
+        timezone,
+    };
+
+    let lock = REMINDERS_LOCK.lock().await;
+    let mut data = load_reminders();
+    data.quiet_hours
+        .insert(ctx.author().id.get(), quiet.clone());
+    if let Err(e) = save_reminders(&data) {
+        ctx.say(format!("❌ Failed to save changes: {e}")).await?;
+        return Ok(());
+    }
+    drop(lock);
+
+    ctx.say(format!(
+        "🌙 Quiet hours set to {}. Reminders due then go off when they end, except high priority ones.",
+        quiet.describe()
+    ))
+    .await?;
+    Ok(())
+}
+
+/// Turn your quiet hours off
+#[poise::command(prefix_command, slash_command, rename = "off")]
+pub async fn remind_quiethours_off(ctx: Context<'_>) -> Result<(), Error> {
+    log::info!(
+        "Remind quiethours off command called by {}",
+        ctx.author().name
+    );
+
+    let lock = REMINDERS_LOCK.lock().await;
+    let mut data = load_reminders();
+    if data.quiet_hours.remove(&ctx.author().id.get()).is_none() {
+        ctx.say("❌ You don't have quiet hours set.").await?;
+        return Ok(());
+    }
+    if let Err(e) = save_reminders(&data) {
+        ctx.say(format!("❌ Failed to save changes: {e}")).await?;
+        return Ok(());
+    }
+    drop(lock);
+    ctx.say("🔔 Quiet hours are off, reminders go off right on time again.")
+        .await?;
+    Ok(())
+}
+
+/// Cancel the reminders due before a time and/or containing a keyword
+#[poise::command(prefix_command, slash_command, rename = "cancel")]
+pub async fn remind_cancel(
+    ctx: Context<'_>,
+    #[description = "Cancel reminders due before this (e.g., 24h, 7d, tonight)"] before: Option<
+        Arg<String>,
+    >,
+    #[description = "Cancel reminders whose message contains this text"]
+    #[rest]
+    matching: Option<String>,
+) -> Result<(), Error> {
+    let before = before.map(|Arg(before)| before);
+    log::info!(
+        "Remind cancel command called by {} with before: {:?} and matching: {:?}",
+        ctx.author().name,
+        before,
+        matching
+    );
+
+    let now = Utc::now();
+    // The prefix form reads `-remind cancel [time] [keyword]`, so a first
+    // word that isn't a time is the start of the keyword
+    let is_prefix = matches!(ctx, poise::Context::Prefix(_));
+    let (before, matching) = match before {
+        Some(time) => match resolve_remind_time(&time, matching.clone(), now) {
+            Ok((at, rest)) => (Some(at), rest),
+            Err(_) if is_prefix => (
+                None,
+                Some(format!("{} {}", time, matching.unwrap_or_default())),
+            ),
+            Err(error) => {
+                ctx.say(error).await?;
+                return Ok(());
+            }
+        },
+        None => (None, matching),
+    };
+    let matching = matching
+        .map(|text| text.trim().to_string())
+        .filter(|text| !text.is_empty());
+    if before.is_none() && matching.is_none() {
+        ctx.say("❌ Say which reminders to cancel: `-remind cancel <time> [keyword]` or `-remind cancel <keyword>`. Use `-remind clear` to cancel all of them.")
+            .await?;
+        return Ok(());
+    }
+
+    let lock = REMINDERS_LOCK.lock().await;
+    let mut data = load_reminders();
+    let removed = cancel_reminders(
+        &mut data.reminders,
+        ctx.author().id.get(),
+        before,
+        matching.as_deref(),
+    );
+    if removed == 0 {
+        ctx.say("📭 None of your reminders match that!").await?;
+        return Ok(());
+    }
+    let detached = detach_orphans(&mut data.reminders);
+    if let Err(e) = save_reminders(&data) {
+        ctx.say(format!("❌ Failed to save changes: {e}")).await?;
+        return Ok(());
+    }
+    drop(lock);
+
+    let mut criteria = Vec::new();
+    if let Some(at) = before {
+        criteria.push(format!("due before <t:{}:f>", at.timestamp()));
+    }
+    if let Some(text) = &matching {
+        criteria.push(format!("containing \"{}\"", text));
+    }
+    let mut description = format!("Removed {} reminder(s) {}", removed, criteria.join(" and "));
+    if detached > 0 {
+        description.push_str(&format!(
+            "\n🔗 {} reminder(s) chained to them keep their planned time.",
+            detached
+        ));
+    }
+    let embed = CreateEmbed::new()
+        .title("🧹 Reminders Cancelled")
+        .description(fit_description(&description))
+        .color(Color::ORANGE)
+        .timestamp(now);
+    ctx.send(poise::CreateReply::default().embed(embed)).await?;
+
+    log::info!(
+        "Cancelled {} reminders for user {}",
+        removed,
+        ctx.author().name
+    );
+    Ok(())
+}
+
+/// Remove a user's reminders that are due before `before` and contain
+/// `matching` (case-insensitive); a criterion left out matches everything.
+/// Returns how many were removed.
+fn cancel_reminders(
+    reminders: &mut Vec<Reminder>,
+    user_id: u64,
+    before: Option<DateTime<Utc>>,
+    matching: Option<&str>,
+) -> usize {
+    let matching = matching.map(str::to_lowercase);
+    let initial_count = reminders.len();
+    reminders.retain(|r| {
+        let cancel = r.user_id == user_id
+            && before.is_none_or(|before| r.remind_at < before)
+            && matching
+                .as_ref()
+                .is_none_or(|text| r.message.to_lowercase().contains(text));
+        !cancel
+    });
+    initial_count - reminders.len()
+}
+
+/// Exports bigger than this are cut short so they still upload; it's
+/// Discord's attachment limit for servers without boosts.
+const EXPORT_MAX_BYTES: usize = 8 * 1024 * 1024;
+
+/// Quote a CSV field if it has anything that would break the row.
+fn csv_field(field: &str) -> String {
+    if field.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.to_string()
+    }
+}
+
+/// Reminders as CSV, stopping before the output would pass `max_bytes`.
+/// Returns the CSV and how many reminders made it in.
+fn reminders_csv(reminders: &[Reminder], max_bytes: usize) -> (String, usize) {
+    let mut csv = String::from("id,user,channel,message,remind_at,created_at\n");
+    let mut rows = 0;
+    for reminder in reminders {
+        let row = format!(
+            "{},{},{},{},{},{}\n",
+            reminder.id,
+            reminder.user_id,
+            reminder.channel_id,
+            csv_field(&reminder.message),
+            reminder.remind_at.to_rfc3339(),
+            reminder.created_at.to_rfc3339()
+        );
+        if csv.len() + row.len() > max_bytes {
+            break;
+        }
+        csv.push_str(&row);
+        rows += 1;
+    }
+    (csv, rows)
+}
+
+/// Export every stored reminder as CSV (bot owners only)
+#[poise::command(prefix_command, slash_command, rename = "export")]
+pub async fn remind_export(ctx: Context<'_>) -> Result<(), Error> {
+    log::info!("Remind export command called by {}", ctx.author().name);
+
+    if !is_protected_user(&ctx.author().name) {
+        ctx.say("❌ You don't have permission to use this command!")
+            .await?;
+        return Ok(());
+    }
+
+    let data = load_reminders();
+    if data.reminders.is_empty() {
+        ctx.say("📭 There are no reminders to export.").await?;
+        return Ok(());
+    }
+
+    let (csv, rows) = reminders_csv(&data.reminders, EXPORT_MAX_BYTES);
+    let total = data.reminders.len();
+    let summary = if rows < total {
+        format!(
+            "📄 Exported {} of {} reminders; the rest didn't fit in one upload.",
+            rows, total
+        )
+    } else {
+        format!("📄 Exported {} reminder(s).", total)
+    };
+
+    ctx.send(
+        poise::CreateReply::default()
+            .content(summary)
+            .attachment(serenity::CreateAttachment::bytes(
+                csv.into_bytes(),
+                "reminders.csv",
+            ))
+            .ephemeral(true),
+    )
+    .await?;
+    Ok(())
+}
+
+/// Calendar files bigger than this aren't downloaded.
+const MAX_ICS_BYTES: u32 = 1024 * 1024;
+
+/// Most reminders one calendar import can create.
+const MAX_IMPORTED_REMINDERS: usize = 100;
+
+/// What importing a calendar would do.
+#[derive(Debug, Default, PartialEq)]
+struct IcsImport {
+    /// (message, due) for each reminder to create, soonest first.
+    reminders: Vec<(String, DateTime<Utc>)>,
+    past: usize,
+    too_far: usize,
+    /// Future events left out past [`MAX_IMPORTED_REMINDERS`].
+    over_limit: usize,
+    /// Imported repeating events, of which only the next start is used.
+    recurring: usize,
+}
+
+/// Sort calendar events into reminders to create and ones to skip.
+fn plan_ics_import(events: &[IcsEvent], now: DateTime<Utc>, horizon: Duration) -> IcsImport {
+    let mut import = IcsImport::default();
+    // Repeating events that started in the past still have a next time
+    let mut future: Vec<(&IcsEvent, DateTime<Utc>)> = Vec::new();
+    for event in events {
+        match event.next_start(now) {
+            None => import.past += 1,
+            Some(start) if start - now > horizon => import.too_far += 1,
+            Some(start) => future.push((event, start)),
+        }
+    }
+    future.sort_by_key(|(_, start)| *start);
+    import.over_limit = future.len().saturating_sub(MAX_IMPORTED_REMINDERS);
+    for (event, start) in future.into_iter().take(MAX_IMPORTED_REMINDERS) {
+        let title = if event.summary.is_empty() {
+            "Untitled event"
+        } else {
+            event.summary.as_str()
+        };
+        import.reminders.push((format!("📅 {}", title), start));
+        import.recurring += usize::from(event.recurring);
+    }
+    import
+}
+
+fn ics_import_summary(import: &IcsImport, unreadable: usize, file_name: &str) -> String {
+    let mut summary = format!(
+        "📅 Imported **{}** reminder{} from `{}`.",
+        import.reminders.len(),
+        if import.reminders.len() == 1 { "" } else { "s" },
+        file_name
+    );
+    let skipped: Vec<String> = [
+        (import.past, "already past"),
+        (import.too_far, "too far ahead"),
+        (import.over_limit, "over the limit of 100 per import"),
+        (unreadable, "without a start time I could read"),
+    ]
+    .into_iter()
+    .filter(|(count, _)| *count > 0)
+    .map(|(count, reason)| format!("{} {}", count, reason))
+    .collect();
+    if !skipped.is_empty() {
+        summary.push_str(&format!("\nSkipped: {}.", skipped.join(", ")));
+    }
+    if import.recurring > 0 {
+        summary.push_str(&format!(
+            "\n🔁 {} repeating event{} only got a reminder for the next time.",
+            import.recurring,
+            if import.recurring == 1 { "" } else { "s" }
+        ));
+    }
+    summary
+}
+
+/// Create reminders for the upcoming events in a calendar (.ics) file
+#[poise::command(prefix_command, slash_command, rename = "import")]
+pub async fn remind_import_ics(
+    ctx: Context<'_>,
+    #[description = "Calendar export (.ics), or reply to a message with one"] file: Option<
+        serenity::Attachment,
+    >,
+) -> Result<(), Error> {
+    log::info!("Remind import command called by {}", ctx.author().name);
+
+    // Fall back to a file on the replied-to message for prefix commands
+    let attachment = file.or_else(|| match ctx {
+        poise::Context::Prefix(prefix_ctx) => prefix_ctx
+            .msg
+            .attachments
+            .first()
+            .or_else(|| {
+                prefix_ctx
+                    .msg
+                    .referenced_message
+                    .as_ref()
+                    .and_then(|msg| msg.attachments.first())
+            })
+            .cloned(),
+        _ => None,
+    });
+    let Some(attachment) = attachment else {
+        ctx.say("❌ Attach a calendar file (.ics) or reply to a message with one!")
+            .await?;
+        return Ok(());
+    };
+
+    let is_calendar = attachment.filename.to_lowercase().ends_with(".ics")
+        || attachment
+            .content_type
+            .as_deref()
+            .is_some_and(|t| t.starts_with("text/calendar"));
+    if !is_calendar {
+        ctx.say("❌ That isn't a calendar file! Export an `.ics` file from your calendar app.")
+            .await?;
+        return Ok(());
+    }
+    if attachment.size > MAX_ICS_BYTES {
+        ctx.say("❌ That calendar is too large! The limit is 1 MB.")
+            .await?;
+        return Ok(());
+    }
+
+    ctx.defer().await?;
+    let bytes = match attachment.download().await {
+        Ok(bytes) => bytes,
+        Err(e) => {
+            ctx.say(format!("❌ Failed to download the calendar: {}", e))
+                .await?;
+            return Ok(());
+        }
+    };
+    let calendar = match String::from_utf8(bytes)
+        .map_err(|_| "it isn't text".to_string())
+        .and_then(|text| parse_calendar(&text, &REMINDER_TIMEZONE))
+    {
+        Ok(calendar) => calendar,
+        Err(reason) => {
+            ctx.say(format!(
+                "❌ I couldn't read `{}` as a calendar: {}.",
+                attachment.filename, reason
+            ))
+            .await?;
+            return Ok(());
+        }
+    };
+
+    let now = Utc::now();
+    let import = plan_ics_import(&calendar.events, now, max_schedule_horizon());
+    if !import.reminders.is_empty() {
+        let _lock = REMINDERS_LOCK.lock().await;
+        let mut data = load_reminders();
+        for (message, remind_at) in &import.reminders {
+            let id = data.next_id;
+            data.next_id += 1;
+            data.reminders.push(Reminder {
+                id,
+                user_id: ctx.author().id.get(),
+                channel_id: ctx.channel_id().get(),
+                message: message.clone(),
+                remind_at: *remind_at,
+                created_at: now,
+                reply_to_message_id: None,
+                nag_interval_minutes: None,
+                nag_count: 0,
+                acknowledged: false,
+                is_dm: ctx.guild_id().is_none(),
+                created_by: None,
+                snooze_count: 0,
+                guild_id: ctx.guild_id().map(|id| id.get()),
+                source_message_id: command_message_id(ctx),
+                relative_to: None,
+                relative_offset_secs: 0,
+                followup_after_minutes: None,
+                note_id: None,
+                priority: ReminderPriority::Normal,
+                cancel_message_id: None,
+                until_free: None,
+                webhook: None,
+                sending: None,
+            });
+        }
+        if let Err(e) = save_reminders(&data) {
+            log::error!("Failed to save imported reminders: {}", e);
+            ctx.say("❌ Failed to save the reminders, nothing was imported.")
+                .await?;
+            return Ok(());
+        }
+    }
+
+    ctx.say(ics_import_summary(
+        &import,
+        calendar.unreadable,
+        &attachment.filename,
+    ))
+    .await?;
+    Ok(())
+}
+
+/// Set the reminder for a note, in the channel the note was added in.
+/// Returns the reminder's ID.
+pub(crate) async fn add_note_reminder(
+    ctx: Context<'_>,
+    note_id: u64,
+    text: &str,
+    remind_at: DateTime<Utc>,
+) -> Result<u64, Error> {
+    let _lock = REMINDERS_LOCK.lock().await;
+    let mut data = load_reminders();
+    let id = data.next_id;
+    data.reminders.push(Reminder {
+        id,
+        user_id: ctx.author().id.get(),
+        channel_id: ctx.channel_id().get(),
+        message: format!("📝 {}", text),
+        remind_at,
+        created_at: Utc::now(),
+        reply_to_message_id: None,
+        nag_interval_minutes: None,
+        nag_count: 0,
+        acknowledged: false,
+        is_dm: ctx.guild_id().is_none(),
+        created_by: None,
+        snooze_count: 0,
+        guild_id: ctx.guild_id().map(|id| id.get()),
+        source_message_id: command_message_id(ctx),
+        relative_to: None,
+        relative_offset_secs: 0,
+        followup_after_minutes: None,
+        note_id: Some(note_id),
+        priority: ReminderPriority::Normal,
+        cancel_message_id: None,
+        until_free: None,
+        webhook: None,
+        sending: None,
+    });
+    data.next_id += 1;
+    save_reminders(&data)?;
+    Ok(id)
+}
+
+/// Cancel the reminders of a note that was finished or deleted. Returns how
+/// many were still pending.
+pub(crate) async fn cancel_note_reminders(user_id: u64, note_id: u64) -> Result<usize, Error> {
+    let _lock = REMINDERS_LOCK.lock().await;
+    let mut data = load_reminders();
+    let (pending, any) = remove_note_reminders(&mut data, user_id, note_id);
+    if any {
+        save_reminders(&data)?;
+    }
+    Ok(pending)
+}
+
+/// Drop a note's reminders: pending ones, and fired ones so they can't be
+/// snoozed back. Returns how many were pending and whether anything changed.
+fn remove_note_reminders(data: &mut RemindersData, user_id: u64, note_id: u64) -> (usize, bool) {
+    let is_note = |r: &Reminder| r.user_id == user_id && r.note_id == Some(note_id);
+    let fired_ids: HashSet<u64> = data
+        .fired
+        .iter()
+        .filter(|r| is_note(r))
+        .map(|r| r.id)
+        .collect();
+
+    let before = data.reminders.len();
+    data.reminders.retain(|r| !is_note(r));
+    let pending = before - data.reminders.len();
+    data.fired.retain(|r| !fired_ids.contains(&r.id));
+    data.awaiting_followup
+        .retain(|f| !fired_ids.contains(&f.reminder_id));
+    detach_orphans(&mut data.reminders);
+    (pending, pending > 0 || !fired_ids.is_empty())
+}
+
+/// Run the reminder checker now instead of waiting for its next tick.
+pub fn wake_reminder_checker() {
+    REMINDER_WAKEUP.notify_one();
+}
+
+/// Start the reminder checker background task
+pub fn start_reminder_checker(http: Arc<serenity::Http>, maintenance: Arc<Maintenance>) {
+    tokio::spawn(async move {
+        // Under the lock, like every other load: loading can set an
+        // unreadable file aside
+        {
+            let _lock = REMINDERS_LOCK.lock().await;
+            let waiting = load_reminders()
+                .reminders
+                .into_iter()
+                .filter(|r| r.until_free.is_some())
+                .map(|r| r.user_id);
+            WAITING_FOR_PRESENCE.write().extend(waiting);
+        }
+
+        let mut interval = interval(TokioDuration::from_secs(60)); // Check every minute
+
+        loop {
+            tokio::select! {
+                _ = interval.tick() => {}
+                _ = REMINDER_WAKEUP.notified() => {}
+            }
+
+            // Keep ticking during maintenance so we pick up right where we left off
+            if maintenance.is_enabled() {
+                continue;
+            }
+            // Sent reminders couldn't be marked as sent, so they'd repeat every tick
+            if storage::is_read_only(&storage::data_path(REMINDERS_FILE)) {
+                continue;
+            }
+
+            if let Err(e) = check_and_send_reminders(&http).await {
+                log::error!("Error checking reminders: {e}");
+            }
+        }
+    });
+}
+
+async fn check_and_send_reminders(http: &serenity::Http) -> Result<(), Error> {
+    let now = Utc::now();
+    let noted = run_check(
+        &RemindersFile,
+        now,
+        |reminder| async move { deliver_reminder(http, &reminder, now).await },
+        |due| async move { send_followups(http, &due).await },
+    )
+    .await?;
+
+    for (user_id, note_id) in noted {
+        if let Err(e) = mark_reminded(user_id, note_id, now) {
+            log::warn!("Failed to mark note {} as reminded: {}", note_id, e);
+        }
+    }
+
+    Ok(())
+}
+
+/// Where a reminder check loads and saves reminders: the reminders file, or
+/// a plain copy in tests.
+trait ReminderStore: Sync {
+    /// Load the reminders and let `change` edit them, saving them if it
+    /// returns `true` alongside its result. Runs under the reminders lock.
+    fn update<R: Send>(
+        &self,
+        change: impl FnOnce(&mut RemindersData) -> (R, bool) + Send,
+    ) -> impl Future<Output = Result<R, Error>> + Send;
+}
+
+/// The reminders file, under [`REMINDERS_LOCK`].
+struct RemindersFile;
+
+impl ReminderStore for RemindersFile {
+    async fn update<R: Send>(
+        &self,
+        change: impl FnOnce(&mut RemindersData) -> (R, bool) + Send,
+    ) -> Result<R, Error> {
+        let _lock = REMINDERS_LOCK.lock().await;
+        let mut data = load_reminders();
+        let (result, changed) = change(&mut data);
+        if changed {
+            save_reminders(&data)?;
+        }
+        Ok(result)
+    }
+}
+
+/// One run of the reminder checker: claim what's due, send it with `deliver`
+/// and the due follow-ups with `nudge`, then record how sending went.
+/// Claimed reminders are saved as in flight before the store is let go, so
+/// commands aren't held up by the sending and an overlapping check doesn't
+/// send them too. Returns the notes whose reminders went out.
+async fn run_check<D, N>(
+    store: &impl ReminderStore,
+    now: DateTime<Utc>,
+    deliver: impl Fn(Reminder) -> D,
+    nudge: impl FnOnce(Vec<PendingFollowup>) -> N,
+) -> Result<Vec<(u64, u64)>, Error>
+where
+    D: Future<Output = Delivery>,
+    N: Future<Output = ()>,
+{
+    let (claimed, due_followups) = store
+        .update(|data| {
+            let mut changed = false;
+
+            // Acknowledged nag reminders are done
+            let before = data.reminders.len();
+            data.reminders.retain(|r| !r.acknowledged);
+            changed |= data.reminders.len() != before;
+            changed |= detach_orphans(&mut data.reminders) > 0;
+
+            // Fired reminders can't be snoozed any more after a while
+            let before = data.fired.len();
+            data.fired
+                .retain(|r| now - r.remind_at < Duration::hours(SNOOZE_WINDOW_HOURS));
+            changed |= data.fired.len() != before;
+
+            changed |= hold_for_quiet_hours(data, now);
+
+            let claimed = claim_due(&mut data.reminders, now);
+            let due_followups = take_due_followups(&mut data.awaiting_followup, now);
+            changed |= !claimed.is_empty() || !due_followups.is_empty();
+            ((claimed, due_followups), changed)
+        })
+        .await?;
+
+    let mut deliveries = Vec::with_capacity(claimed.len());
+    for reminder in &claimed {
+        deliveries.push(deliver(reminder.clone()).await);
+    }
+    nudge(due_followups).await;
+
+    if claimed.is_empty() {
+        return Ok(Vec::new());
+    }
+    // Commands may have changed the reminders while we were sending
+    store
+        .update(|data| (record_deliveries(data, &claimed, &deliveries, now), true))
+        .await
+}
+
+/// Hold due reminders until their recipient's quiet hours end; high
+/// priority ones go off anyway. Returns whether any were held.
+fn hold_for_quiet_hours(data: &mut RemindersData, now: DateTime<Utc>) -> bool {
+    let mut held = false;
+    for reminder in data.reminders.iter_mut().filter(|r| {
+        r.remind_at <= now
+            && r.relative_to.is_none()
+            && r.sending.is_none()
+            && r.priority != ReminderPriority::High
+    }) {
+        let quiet_end = data
+            .quiet_hours
+            .get(&reminder.user_id)
+            .and_then(|quiet| quiet.end_after(now));
+        if let Some(end) = quiet_end {
+            log::info!("Holding reminder {} until quiet hours end", reminder.id);
+            reminder.remind_at = end;
+            held = true;
+        }
+    }
+    held
+}
+
+/// Claim the due reminders for sending and return copies of them to send.
+/// Ones another check is still sending are skipped; a claim older than
+/// [`CLAIM_TIMEOUT_MINUTES`] is taken over.
+fn claim_due(reminders: &mut [Reminder], now: DateTime<Utc>) -> Vec<Reminder> {
+    let mut claimed = Vec::new();
+    let mut in_flight = 0;
+    for reminder in reminders
+        .iter_mut()
+        .filter(|r| r.remind_at <= now && r.relative_to.is_none())
+    {
+        if let Some(claimed_at) = reminder.sending {
+            if now - claimed_at < Duration::minutes(CLAIM_TIMEOUT_MINUTES) {
+                in_flight += 1;
+                continue;
+            }
+            log::warn!(
+                "Reminder {} was claimed {} ago and never sent, sending it now",
+                reminder.id,
+                format_duration(now - claimed_at)
+            );
+        }
+        reminder.sending = Some(now);
+        claimed.push(reminder.clone());
+    }
+    if in_flight > 0 {
+        log::warn!(
+            "A previous reminder check is still sending {} reminder(s), leaving them to it",
+            in_flight
+        );
+    }
+    claimed
+}
+
+/// How sending a claimed reminder went.
+#[derive(Debug, Default)]
+struct Delivery {
+    /// The message the reminder went out as, or `None` if sending failed.
+    message_id: Option<u64>,
+    /// Its webhook was deleted or had its token reset.
+    webhook_gone: bool,
+}
+
+/// Send a claimed reminder to its channel, through its webhook if it has
+/// one, and DM a copy of high priority ones set in a server.
+async fn deliver_reminder(
+    http: &serenity::Http,
+    reminder: &Reminder,
+    now: DateTime<Utc>,
+) -> Delivery {
+    let channel_id = serenity::ChannelId::new(reminder.channel_id);
+    let user_mention = format!("<@{}>", reminder.user_id);
+
+    let footer = match reminder.nag_interval_minutes {
+        Some(_) => format!(
+            "Set {} ago • Nag {}/{} • Reminder ID: {}",
+            format_duration(now - reminder.created_at),
+            reminder.nag_count + 1,
+            MAX_NAGS,
+            reminder.id
+        ),
+        None if reminder.snooze_count > 0 => format!(
+            "Set {} ago • Snoozed {}×",
+            format_duration(now - reminder.created_at),
+            reminder.snooze_count
+        ),
+        None => format!("Set {} ago", format_duration(now - reminder.created_at)),
+    };
+
+    let mut description = match reminder.created_by {
+        Some(creator) => format!("{}\n\n*Set for you by <@{}>*", reminder.message, creator),
+        None => reminder.message.clone(),
+    };
+    if let Some(note_id) = reminder.note_id {
+        description.push_str(&format!(
+            "\n\n*From note #{} • `/note done {}` once it's finished*",
+            note_id, note_id
+        ));
+    }
+    let style = notification_style(reminder.priority);
+    let mut embed = CreateEmbed::new()
+        .title("⏰ Reminder!")
+        .description(fit_description(&description))
+        .color(style.color)
+        .footer(CreateEmbedFooter::new(footer))
+        .timestamp(now);
+    if let Some(link) = source_jump_link(reminder) {
+        embed = embed.field("Set here", format!("[Jump to message]({})", link), false);
+    }
+
+    // High priority reminders set in a server also land in the DMs, once
+    // the reminder itself went out so a retry doesn't DM twice
+    let dm_embed = (style.dm && !reminder.is_dm).then(|| embed.clone());
+
+    // Webhooks can't have the bot's buttons or reply to a message
+    let webhook_embed = reminder.webhook.is_some().then(|| embed.clone());
+    let mut message_builder = serenity::CreateMessage::new().embed(embed);
+    if style.mention {
+        message_builder = message_builder.content(&user_mention);
+    }
+
+    // Nag reminders get a button to make them stop
+    if reminder.nag_interval_minutes.is_some() {
+        let button = serenity::CreateButton::new(format!("{}{}", ACK_BUTTON_PREFIX, reminder.id))
+            .label("Acknowledge")
+            .emoji('✅')
+            .style(serenity::ButtonStyle::Success);
+        message_builder =
+            message_builder.components(vec![serenity::CreateActionRow::Buttons(vec![button])]);
+    } else {
+        let buttons = SNOOZE_OPTIONS
+            .iter()
+            .map(|(label, minutes)| {
+                serenity::CreateButton::new(format!(
+                    "{}{}:{}",
+                    SNOOZE_BUTTON_PREFIX, reminder.id, minutes
+                ))
+                .label(format!("Snooze {}", label))
+                .emoji('💤')
+                .style(serenity::ButtonStyle::Secondary)
+            })
+            .collect();
+        message_builder =
+            message_builder.components(vec![serenity::CreateActionRow::Buttons(buttons)]);
+    }
+
+    // Add reply reference if this reminder was set as a reply
+    if let Some(reply_msg_id) = reminder.reply_to_message_id {
+        message_builder = message_builder.reference_message((
+            serenity::ChannelId::new(reminder.channel_id),
+            serenity::MessageId::new(reply_msg_id),
+        ));
+    }
+
+    let mut delivery = Delivery::default();
+    let result = match (&reminder.webhook, webhook_embed) {
+        (Some(webhook), Some(embed)) => {
+            let content = style.mention.then_some(user_mention.as_str());
+            match send_via_webhook(http, webhook, content, embed).await {
+                Err(e) if is_webhook_gone(&e) => {
+                    log::warn!(
+                        "Webhook for reminder {} is gone, sending it as the bot: {}",
+                        reminder.id,
+                        e
+                    );
+                    delivery.webhook_gone = true;
+                    channel_id.send_message(http, message_builder).await
+                }
+                sent => sent,
+            }
+        }
+        _ => channel_id.send_message(http, message_builder).await,
+    };
+    match result {
+        Ok(sent) => {
+            log::info!("Sent reminder {} to user {}", reminder.id, reminder.user_id);
+            delivery.message_id = Some(sent.id.get());
+        }
+        Err(e) => {
+            log::error!("Failed to send reminder {}: {}", reminder.id, e);
+            return delivery;
         }
     }
 
-    Ok(())
+    if let Some(embed) = dm_embed {
+        let copy = serenity::CreateMessage::new()
+            .content(format!(
+                "🚨 High priority reminder in <#{}>",
+                reminder.channel_id
+            ))
+            .embed(embed);
+        if let Err(e) = send_dm(
+            http,
+            serenity::UserId::new(reminder.user_id),
+            "reminder",
+            reminder.guild_id.map(serenity::GuildId::new),
+            copy,
+        )
+        .await
+        {
+            log::warn!("Failed to DM high priority reminder {}: {}", reminder.id, e);
+        }
+    }
+    delivery
 }
 
-/// Clear all your reminders
-#[poise::command(prefix_command, slash_command, rename = "clear")]
-pub async fn remind_clear(ctx: Context<'_>) -> Result<(), Error> {
-    log::info!("Remind clear command called by {}", ctx.author().name);
-
-    let mut data = load_reminders();
-    let user_id = ctx.author().id.get();
+/// Record how sending the `claimed` reminders went and release their
+/// claims: ones that went out are rearmed or retired and start the reminders
+/// chained to them, failed ones are retried on the next check. Reminders
+/// removed or claimed by another check since are left alone, and so is the
+/// time of one edited meanwhile. Returns the notes whose reminder went out.
+fn record_deliveries(
+    data: &mut RemindersData,
+    claimed: &[Reminder],
+    deliveries: &[Delivery],
+    now: DateTime<Utc>,
+) -> Vec<(u64, u64)> {
+    let mut finished_reminders = Vec::new();
+    let mut fired_ids = Vec::new();
+    let mut noted = Vec::new();
 
-    let initial_count = data.reminders.len();
-    data.reminders.retain(|r| r.user_id != user_id);
-    let removed_count = initial_count - data.reminders.len();
+    for (sent, delivery) in claimed.iter().zip(deliveries) {
+        let Some(index) = data
+            .reminders
+            .iter()
+            .position(|r| r.id == sent.id && r.sending == sent.sending)
+        else {
+            log::warn!(
+                "Reminder {} was removed or reclaimed while being sent",
+                sent.id
+            );
+            continue;
+        };
+        let reminder = &mut data.reminders[index];
+        reminder.sending = None;
+        if delivery.webhook_gone {
+            reminder.webhook = None;
+        }
+        let Some(message_id) = delivery.message_id else {
+            continue;
+        };
 
-    if removed_count == 0 {
-        ctx.say("📭 You have no reminders to clear!").await?;
-        return Ok(());
+        if let Some(minutes) = reminder.followup_after_minutes {
+            data.awaiting_followup.push(PendingFollowup {
+                reminder_id: reminder.id,
+                user_id: reminder.user_id,
+                channel_id: reminder.channel_id,
+                message_id,
+                guild_id: reminder.guild_id,
+                message: reminder.message.clone(),
+                due_at: now + Duration::minutes(minutes as i64),
+            });
+        }
+        fired_ids.push(reminder.id);
+        if let Some(note_id) = reminder.note_id {
+            noted.push((reminder.user_id, note_id));
+        }
+        if reminder.remind_at == sent.remind_at && !rearm_after_sending(reminder, now) {
+            finished_reminders.push(index);
+        }
     }
 
-    if let Err(e) = save_reminders(&data) {
-        ctx.say(format!("❌ Failed to save changes: {e}")).await?;
-        return Ok(());
+    for id in fired_ids {
+        start_dependents(&mut data.reminders, id, now);
     }
 
-    let embed = CreateEmbed::new()
-        .title("🧹 Reminders Cleared")
-        .description(format!("Removed {removed_count} reminder(s)"))
-        .color(Color::ORANGE)
-        .timestamp(Utc::now());
-
-    ctx.send(poise::CreateReply::default().embed(embed)).await?;
+    // Remove finished reminders (in reverse order to maintain indices).
+    // One-offs stay around for a while so they can be snoozed.
+    finished_reminders.sort_unstable();
+    for &index in finished_reminders.iter().rev() {
+        let reminder = data.reminders.remove(index);
+        if reminder.nag_interval_minutes.is_none() {
+            data.fired.push(reminder);
+        }
+    }
 
-    log::info!(
-        "Cleared {} reminders for user {}",
-        removed_count,
-        ctx.author().name
-    );
-    Ok(())
+    noted
 }
 
-/// Start the reminder checker background task
-pub fn start_reminder_checker(http: Arc<serenity::Http>) {
-    tokio::spawn(async move {
-        let mut interval = interval(TokioDuration::from_secs(60)); // Check every minute
+/// Post a fired reminder through its webhook, as the name and avatar it was
+/// set with or else the webhook's own.
+async fn send_via_webhook(
+    http: &serenity::Http,
+    webhook: &ReminderWebhook,
+    content: Option<&str>,
+    embed: CreateEmbed,
+) -> serenity::Result<serenity::Message> {
+    let hook = serenity::Webhook::from_id_with_token(
+        http,
+        serenity::WebhookId::new(webhook.id),
+        &webhook.token,
+    )
+    .await?;
+    let mut execute = serenity::ExecuteWebhook::new().embed(embed);
+    if let Some(content) = content {
+        execute = execute.content(content);
+    }
+    if let Some(name) = &webhook.name {
+        execute = execute.username(name);
+    }
+    if let Some(avatar_url) = &webhook.avatar_url {
+        execute = execute.avatar_url(avatar_url);
+    }
+    hook.execute(http, true, execute)
+        .await?
+        .ok_or(serenity::Error::Other(
+            "the webhook didn't return its message",
+        ))
+}
 
-        loop {
-            interval.tick().await;
+/// Whether a webhook send failed because the webhook was deleted or its
+/// token reset, so retrying it is pointless.
+fn is_webhook_gone(error: &serenity::Error) -> bool {
+    is_not_found(error)
+        || matches!(
+            error,
+            serenity::Error::Http(serenity::HttpError::UnsuccessfulRequest(response))
+                if response.error.code == INVALID_WEBHOOK_TOKEN
+        )
+}
 
-            if let Err(e) = check_and_send_reminders(&http).await {
-                log::error!("Error checking reminders: {e}");
+/// Nudge users by DM about fired follow-up reminders whose window is up and
+/// that got no reaction or reply from them. Each reminder gets at most one
+/// nudge.
+async fn send_followups(http: &serenity::Http, due: &[PendingFollowup]) {
+    for pending in due {
+        match followup_answered(http, pending).await {
+            Ok(true) => {}
+            Ok(false) => {
+                let link = format!(
+                    "https://discord.com/channels/{}/{}/{}",
+                    pending
+                        .guild_id
+                        .map_or("@me".to_string(), |id| id.to_string()),
+                    pending.channel_id,
+                    pending.message_id
+                );
+                let nudge = serenity::CreateMessage::new().content(format!(
+                    "👋 Just checking: did you see your reminder **{}**? [Jump to it]({})",
+                    pending.message, link
+                ));
+                match send_dm(
+                    http,
+                    serenity::UserId::new(pending.user_id),
+                    "reminder follow-up",
+                    pending.guild_id.map(serenity::GuildId::new),
+                    nudge,
+                )
+                .await
+                {
+                    Ok(_) => log::info!(
+                        "Sent follow-up for reminder {} to user {}",
+                        pending.reminder_id,
+                        pending.user_id
+                    ),
+                    Err(e) => log::warn!(
+                        "Failed to DM follow-up for reminder {}: {}",
+                        pending.reminder_id,
+                        e
+                    ),
+                }
             }
+            // Deleted reminder messages don't need a nudge
+            Err(e) if is_not_found(&e) => {}
+            Err(e) => log::warn!(
+                "Couldn't check reminder {} for a follow-up, skipping it: {}",
+                pending.reminder_id,
+                e
+            ),
         }
-    });
+    }
 }
 
-async fn check_and_send_reminders(http: &serenity::Http) -> Result<(), Error> {
-    let mut data = load_reminders();
-    let now = Utc::now();
-    let mut sent_reminders = Vec::new();
+/// Split off the follow-ups whose window is up.
+fn take_due_followups(
+    pending: &mut Vec<PendingFollowup>,
+    now: DateTime<Utc>,
+) -> Vec<PendingFollowup> {
+    let (due, waiting) = std::mem::take(pending)
+        .into_iter()
+        .partition(|followup| followup.due_at <= now);
+    *pending = waiting;
+    due
+}
 
-    for (i, reminder) in data.reminders.iter().enumerate() {
-        if reminder.remind_at <= now {
-            // Send the reminder
-            let channel_id = serenity::ChannelId::new(reminder.channel_id);
-            let user_mention = format!("<@{}>", reminder.user_id);
+/// Whether the user reacted to the reminder message or replied to it.
+async fn followup_answered(
+    http: &serenity::Http,
+    pending: &PendingFollowup,
+) -> Result<bool, serenity::Error> {
+    let channel_id = serenity::ChannelId::new(pending.channel_id);
+    let message_id = serenity::MessageId::new(pending.message_id);
+    let user_id = serenity::UserId::new(pending.user_id);
 
-            let embed = CreateEmbed::new()
-                .title("⏰ Reminder!")
-                .description(&reminder.message)
-                .color(Color::GOLD)
-                .footer(CreateEmbedFooter::new(format!(
-                    "Set {} ago",
-                    format_duration(now - reminder.created_at)
-                )))
-                .timestamp(now);
-
-            let mut message_builder = serenity::CreateMessage::new()
-                .content(&user_mention)
-                .embed(embed);
-
-            // Add reply reference if this reminder was set as a reply
-            if let Some(reply_msg_id) = reminder.reply_to_message_id {
-                message_builder = message_builder.reference_message((
-                    serenity::ChannelId::new(reminder.channel_id),
-                    serenity::MessageId::new(reply_msg_id),
-                ));
-            }
+    let message = channel_id.message(http, message_id).await?;
+    for reaction in &message.reactions {
+        let users = channel_id
+            .reaction_users(
+                http,
+                message_id,
+                reaction.reaction_type.clone(),
+                Some(100),
+                None::<serenity::UserId>,
+            )
+            .await?;
+        if users.iter().any(|user| user.id == user_id) {
+            return Ok(true);
+        }
+    }
 
-            match channel_id.send_message(http, message_builder).await {
-                Ok(_) => {
-                    log::info!("Sent reminder {} to user {}", reminder.id, reminder.user_id);
-                    sent_reminders.push(i);
-                }
-                Err(e) => {
-                    log::error!("Failed to send reminder {}: {}", reminder.id, e);
-                }
-            }
+    let later = channel_id
+        .messages(
+            http,
+            serenity::GetMessages::new().after(message_id).limit(100),
+        )
+        .await?;
+    Ok(later.iter().any(|reply| {
+        reply.author.id == user_id
+            && reply
+                .message_reference
+                .as_ref()
+                .and_then(|reference| reference.message_id)
+                == Some(message_id)
+    }))
+}
+
+/// Move the reminders chained to `base_id` to their offset after `base_at`,
+/// and the ones chained to those in turn.
+fn reschedule_dependents(reminders: &mut [Reminder], base_id: u64, base_at: DateTime<Utc>) {
+    let mut pending = vec![(base_id, base_at)];
+    while let Some((base_id, base_at)) = pending.pop() {
+        for reminder in reminders
+            .iter_mut()
+            .filter(|r| r.relative_to == Some(base_id))
+        {
+            reminder.remind_at = base_at + Duration::seconds(reminder.relative_offset_secs);
+            pending.push((reminder.id, reminder.remind_at));
         }
     }
+}
 
-    // Remove sent reminders (in reverse order to maintain indices)
-    for &index in sent_reminders.iter().rev() {
-        data.reminders.remove(index);
+/// Reminder `base_id` just fired: the reminders chained to it become
+/// normal reminders, due their offset from now.
+fn start_dependents(reminders: &mut [Reminder], base_id: u64, now: DateTime<Utc>) {
+    reschedule_dependents(reminders, base_id, now);
+    for reminder in reminders
+        .iter_mut()
+        .filter(|r| r.relative_to == Some(base_id))
+    {
+        reminder.relative_to = None;
     }
+}
 
-    if !sent_reminders.is_empty() {
-        save_reminders(&data)?;
+/// Chained reminders whose base is gone keep their planned time as a normal
+/// reminder. Returns how many were detached.
+fn detach_orphans(reminders: &mut [Reminder]) -> usize {
+    let ids: HashSet<u64> = reminders.iter().map(|r| r.id).collect();
+    let mut detached = 0;
+    for reminder in reminders.iter_mut() {
+        if reminder
+            .relative_to
+            .is_some_and(|base| !ids.contains(&base))
+        {
+            reminder.relative_to = None;
+            detached += 1;
+        }
     }
+    detached
+}
 
-    Ok(())
+/// Schedule the next nag for a reminder that was just sent. Returns `false`
+/// once the reminder is done and should be removed.
+fn rearm_after_sending(reminder: &mut Reminder, now: DateTime<Utc>) -> bool {
+    let Some(minutes) = reminder.nag_interval_minutes else {
+        return false;
+    };
+
+    reminder.nag_count += 1;
+    if reminder.acknowledged || reminder.nag_count >= MAX_NAGS {
+        return false;
+    }
+
+    reminder.remind_at = now + Duration::minutes(minutes as i64);
+    true
 }
 
-fn format_duration(duration: Duration) -> String {
+pub fn format_duration(duration: Duration) -> String {
     let total_seconds = duration.num_seconds();
 
     if total_seconds < 60 {
@@ -479,6 +4025,57 @@ fn format_duration(duration: Duration) -> String {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::utils::random::SeededRng;
+
+    #[test]
+    fn test_time_phrases() {
+        let tz = &REMINDER_TIMEZONE;
+        // 12:17 in Copenhagen (CEST, UTC+2)
+        let now = Utc.with_ymd_and_hms(2026, 6, 16, 10, 17, 30).unwrap();
+
+        assert_eq!(
+            top_of_the_hour(now, tz),
+            Utc.with_ymd_and_hms(2026, 6, 16, 11, 0, 0).single()
+        );
+        assert_eq!(
+            in_the_morning(now, tz),
+            Utc.with_ymd_and_hms(2026, 6, 17, 6, 0, 0).single()
+        );
+        assert_eq!(
+            tonight(now, tz),
+            Utc.with_ymd_and_hms(2026, 6, 16, 18, 0, 0).single()
+        );
+
+        // After 20:00 local, "tonight" is tomorrow evening
+        let late = Utc.with_ymd_and_hms(2026, 6, 16, 19, 30, 0).unwrap();
+        assert_eq!(
+            tonight(late, tz),
+            Utc.with_ymd_and_hms(2026, 6, 17, 18, 0, 0).single()
+        );
+        // Winter time is UTC+1
+        let winter = Utc.with_ymd_and_hms(2026, 1, 10, 3, 0, 0).unwrap();
+        assert_eq!(
+            in_the_morning(winter, tz),
+            Utc.with_ymd_and_hms(2026, 1, 10, 7, 0, 0).single()
+        );
+    }
+
+    #[test]
+    fn test_parse_time_phrase() {
+        let tz = &REMINDER_TIMEZONE;
+        let now = Utc.with_ymd_and_hms(2026, 6, 16, 10, 17, 30).unwrap();
+
+        let (at, rest) = parse_time_phrase("Top of the hour stand up", now, tz).unwrap();
+        assert_eq!(at, top_of_the_hour(now, tz).unwrap());
+        assert_eq!(rest, "stand up");
+
+        let (at, rest) = parse_time_phrase("tonight ", now, tz).unwrap();
+        assert_eq!(at, tonight(now, tz).unwrap());
+        assert_eq!(rest, "");
+
+        assert!(parse_time_phrase("tonightly meeting", now, tz).is_none());
+        assert!(parse_time_phrase("in the evening", now, tz).is_none());
+    }
 
     #[test]
     fn test_parse_time_duration() {
@@ -488,6 +4085,958 @@ mod tests {
         assert_eq!(parse_time_duration("1w"), Some(Duration::weeks(1)));
         assert_eq!(parse_time_duration("30s"), Some(Duration::seconds(30)));
         assert_eq!(parse_time_duration("invalid"), None);
+        assert_eq!(parse_time_duration("99999999999999w"), None);
+    }
+
+    fn test_reminder(nag_interval_minutes: Option<u32>) -> Reminder {
+        let now = Utc::now();
+        Reminder {
+            id: 1,
+            user_id: 1,
+            channel_id: 1,
+            message: "Take out the trash".to_string(),
+            remind_at: now,
+            created_at: now,
+            reply_to_message_id: None,
+            nag_interval_minutes,
+            nag_count: 0,
+            acknowledged: false,
+            is_dm: false,
+            created_by: None,
+            snooze_count: 0,
+            guild_id: None,
+            source_message_id: None,
+            relative_to: None,
+            relative_offset_secs: 0,
+            followup_after_minutes: None,
+            note_id: None,
+            priority: ReminderPriority::Normal,
+            cancel_message_id: None,
+            until_free: None,
+            webhook: None,
+            sending: None,
+        }
+    }
+
+    #[test]
+    fn test_chained_reminders() {
+        let now = Utc::now();
+        let chained = |id, base, minutes: i64| {
+            let mut reminder = test_reminder(None);
+            reminder.id = id;
+            reminder.relative_to = Some(base);
+            reminder.relative_offset_secs = minutes * 60;
+            reminder.remind_at = now + Duration::days(1);
+            reminder
+        };
+        // 2 follows 1 by 30 minutes, 3 follows 2 by an hour
+        let mut reminders = vec![test_reminder(None), chained(2, 1, 30), chained(3, 2, 60)];
+
+        // When 1 fires, 2 is due 30 minutes from now and 3 is planned after it
+        start_dependents(&mut reminders, 1, now);
+        assert_eq!(reminders[1].relative_to, None);
+        assert_eq!(reminders[1].remind_at, now + Duration::minutes(30));
+        assert_eq!(reminders[2].relative_to, Some(2));
+        assert_eq!(reminders[2].remind_at, now + Duration::minutes(90));
+
+        // Removing 2 leaves 3 as a normal reminder at its planned time
+        assert_eq!(detach_orphans(&mut reminders), 0);
+        reminders.remove(1);
+        assert_eq!(detach_orphans(&mut reminders), 1);
+        assert_eq!(reminders[1].relative_to, None);
+        assert_eq!(reminders[1].remind_at, now + Duration::minutes(90));
+    }
+
+    #[test]
+    fn test_reminders_csv() {
+        let mut quoted = test_reminder(None);
+        quoted.id = 2;
+        quoted.message = "Buy milk, eggs\nand \"good\" bread".to_string();
+        let reminders = [test_reminder(None), quoted];
+
+        let (csv, rows) = reminders_csv(&reminders, usize::MAX);
+        assert_eq!(rows, 2);
+        let mut lines = csv.lines();
+        assert_eq!(
+            lines.next(),
+            Some("id,user,channel,message,remind_at,created_at")
+        );
+        assert!(lines
+            .next()
+            .unwrap()
+            .starts_with("1,1,1,Take out the trash,"));
+        assert!(csv.contains("2,1,1,\"Buy milk, eggs\nand \"\"good\"\" bread\","));
+
+        // Only whole rows that fit are kept
+        let (csv, rows) = reminders_csv(&reminders, 200);
+        assert_eq!(rows, 1);
+        assert!(csv.len() <= 200);
+    }
+
+    #[test]
+    fn test_source_jump_link() {
+        let mut reminder = test_reminder(None);
+        reminder.channel_id = 20;
+        assert_eq!(source_jump_link(&reminder), None);
+
+        reminder.source_message_id = Some(30);
+        reminder.guild_id = Some(10);
+        assert_eq!(
+            source_jump_link(&reminder).as_deref(),
+            Some("https://discord.com/channels/10/20/30")
+        );
+
+        reminder.guild_id = None;
+        reminder.is_dm = true;
+        assert_eq!(
+            source_jump_link(&reminder).as_deref(),
+            Some("https://discord.com/channels/@me/20/30")
+        );
+
+        // Set in a server before guild IDs were stored
+        reminder.is_dm = false;
+        assert_eq!(source_jump_link(&reminder), None);
+    }
+
+    #[test]
+    fn test_filter_reminders() {
+        let now = Utc::now();
+        let mut soon = test_reminder(None);
+        soon.id = 1;
+        soon.remind_at = now + Duration::hours(1);
+        let mut later = test_reminder(None);
+        later.id = 2;
+        later.message = "Dentist appointment".to_string();
+        later.remind_at = now + Duration::days(3);
+        let mut other_user = soon.clone();
+        other_user.user_id = 2;
+        let mut past = soon.clone();
+        past.remind_at = now - Duration::hours(1);
+
+        let all = vec![soon, later, other_user, past];
+
+        let ids = |reminders: Vec<Reminder>| reminders.iter().map(|r| r.id).collect::<Vec<_>>();
+        assert_eq!(
+            ids(filter_reminders(all.clone(), 1, None, None, now)),
+            vec![1, 2]
+        );
+        assert_eq!(
+            ids(filter_reminders(all.clone(), 1, Some("DENTIST"), None, now)),
+            vec![2]
+        );
+        assert_eq!(
+            ids(filter_reminders(
+                all,
+                1,
+                None,
+                Some(Duration::hours(24)),
+                now
+            )),
+            vec![1]
+        );
+    }
+
+    #[test]
+    fn test_sort_reminders() {
+        let now = Utc::now();
+        let mut first = test_reminder(None);
+        first.id = 1;
+        first.created_at = now - Duration::days(2);
+        first.remind_at = now + Duration::days(5);
+        let mut second = test_reminder(None);
+        second.id = 2;
+        second.created_at = now - Duration::days(1);
+        second.remind_at = now + Duration::days(1);
+
+        let mut reminders = vec![first.clone(), second.clone()];
+        sort_reminders(&mut reminders, ReminderSort::Soonest);
+        assert_eq!(reminders[0].id, 2);
+
+        let mut reminders = vec![second, first];
+        sort_reminders(&mut reminders, ReminderSort::Newest);
+        assert_eq!(reminders[0].id, 2);
+    }
+
+    #[test]
+    fn test_rearm_after_sending() {
+        let now = Utc::now();
+
+        let mut reminder = test_reminder(None);
+        assert!(!rearm_after_sending(&mut reminder, now));
+
+        let mut reminder = test_reminder(Some(10));
+        assert!(rearm_after_sending(&mut reminder, now));
+        assert_eq!(reminder.remind_at, now + Duration::minutes(10));
+        assert_eq!(reminder.nag_count, 1);
+
+        reminder.acknowledged = true;
+        assert!(!rearm_after_sending(&mut reminder, now));
+    }
+
+    #[test]
+    fn test_nag_safety_cap() {
+        let now = Utc::now();
+        let mut reminder = test_reminder(Some(5));
+        reminder.nag_count = MAX_NAGS - 1;
+        assert!(!rearm_after_sending(&mut reminder, now));
+    }
+
+    #[test]
+    fn test_old_reminders_load_without_nag_fields() {
+        let json = r#"{"reminders":[{"id":1,"user_id":1,"channel_id":1,"message":"hi","remind_at":"2024-01-01T00:00:00Z","created_at":"2024-01-01T00:00:00Z","reply_to_message_id":null}],"next_id":2}"#;
+        let data: RemindersData = serde_json::from_str(json).unwrap();
+        assert_eq!(data.reminders[0].nag_interval_minutes, None);
+        assert!(!data.reminders[0].acknowledged);
+        assert_eq!(data.reminders[0].created_by, None);
+        assert_eq!(data.reminders[0].snooze_count, 0);
+        assert_eq!(data.reminders[0].webhook, None);
+        assert!(data.fired.is_empty());
+    }
+
+    #[test]
+    fn test_resolve_remind_time() {
+        let now = Utc.with_ymd_and_hms(2026, 6, 16, 10, 17, 30).unwrap();
+
+        let (at, message) = resolve_remind_time("5m", Some("stand up".to_string()), now).unwrap();
+        assert_eq!(at, now + Duration::minutes(5));
+        assert_eq!(message.as_deref(), Some("stand up"));
+
+        let (at, message) =
+            resolve_remind_time("top", Some("of the hour stand up".to_string()), now).unwrap();
+        assert_eq!(at, top_of_the_hour(now, &REMINDER_TIMEZONE).unwrap());
+        assert_eq!(message.as_deref(), Some("stand up"));
+
+        assert_eq!(
+            resolve_remind_time("soonish", None, now),
+            Err(invalid_time_message("soonish"))
+        );
+    }
+
+    #[test]
+    fn test_random_remind_time() {
+        let now = Utc::now();
+        let mut rng = SeededRng::new(7);
+        for _ in 0..100 {
+            let at = random_remind_time("Random", now, &mut rng).unwrap();
+            assert!(at >= now + Duration::minutes(1));
+            assert!(at <= now + Duration::days(1));
+        }
+        assert!(random_remind_time("5m", now, &mut rng).is_none());
+        // The strict parser doesn't know about it
+        assert!(resolve_remind_time("random", None, now).is_err());
+    }
+
+    #[test]
+    fn test_snooze_fired_reminder() {
+        let now = Utc::now();
+        let mut data = RemindersData::default();
+        let mut fired = test_reminder(None);
+        fired.id = 5;
+        fired.user_id = 7;
+        data.fired.push(fired);
+        data.next_id = 6;
+
+        // Only the recipient can snooze
+        assert!(snooze_fired_reminder(&mut data, 5, 8, 10, now).is_none());
+
+        let snoozed = snooze_fired_reminder(&mut data, 5, 7, 10, now).unwrap();
+        assert_eq!(snoozed.id, 6);
+        assert_eq!(snoozed.snooze_count, 1);
+        assert_eq!(snoozed.remind_at, now + Duration::minutes(10));
+        assert_eq!(data.next_id, 7);
+        assert!(data.fired.is_empty());
+        assert_eq!(data.reminders.len(), 1);
+
+        // Already snoozed, so it's gone from the fired list
+        assert!(snooze_fired_reminder(&mut data, 5, 7, 10, now).is_none());
+
+        // Persisted with the count; old files without it load as 0
+        let json = serde_json::to_string(&data).unwrap();
+        let loaded: RemindersData = serde_json::from_str(&json).unwrap();
+        assert_eq!(loaded.reminders[0].snooze_count, 1);
+        assert!(format_reminder_entry(&loaded.reminders[0]).contains("💤 snoozed 1×"));
+    }
+
+    #[test]
+    fn test_followups() {
+        let now = Utc::now();
+        let pending = |reminder_id, minutes| PendingFollowup {
+            reminder_id,
+            user_id: 7,
+            channel_id: 1,
+            message_id: 100 + reminder_id,
+            guild_id: None,
+            message: "Stretch".to_string(),
+            due_at: now + Duration::minutes(minutes),
+        };
+        let mut waiting = vec![pending(1, -1), pending(2, 10), pending(3, 0)];
+        let due = take_due_followups(&mut waiting, now);
+        assert_eq!(due, vec![pending(1, -1), pending(3, 0)]);
+        assert_eq!(waiting, vec![pending(2, 10)]);
+        // Taken follow-ups are gone, so nobody is nudged twice
+        assert!(take_due_followups(&mut waiting, now).is_empty());
+
+        // Snoozing the fired reminder answers it
+        let mut data = RemindersData::default();
+        let mut fired = test_reminder(None);
+        fired.id = 2;
+        fired.user_id = 7;
+        fired.followup_after_minutes = Some(30);
+        data.fired.push(fired);
+        data.awaiting_followup = waiting;
+        data.next_id = 3;
+        let snoozed = snooze_fired_reminder(&mut data, 2, 7, 10, now).unwrap();
+        assert!(data.awaiting_followup.is_empty());
+        assert_eq!(snoozed.followup_after_minutes, Some(30));
+        assert!(format_reminder_entry(&snoozed).contains("👋 nudge after 30m"));
+
+        // Older files without follow-up state still load
+        let loaded: RemindersData =
+            serde_json::from_str(r#"{"reminders":[],"next_id":4}"#).unwrap();
+        assert!(loaded.awaiting_followup.is_empty());
+    }
+
+    #[test]
+    fn test_note_reminders() {
+        let now = Utc::now();
+        let note_reminder = |id, user_id, note_id| {
+            let mut reminder = test_reminder(None);
+            reminder.id = id;
+            reminder.user_id = user_id;
+            reminder.note_id = note_id;
+            reminder
+        };
+        let mut data = RemindersData {
+            reminders: vec![
+                note_reminder(1, 7, Some(3)),
+                note_reminder(2, 7, None),
+                // Note IDs are per user, so another user's note 3 stays
+                note_reminder(3, 8, Some(3)),
+            ],
+            ..Default::default()
+        };
+        let mut chained = note_reminder(4, 7, None);
+        chained.relative_to = Some(1);
+        data.reminders.push(chained);
+        data.fired.push(note_reminder(5, 7, Some(3)));
+        data.next_id = 6;
+
+        assert_eq!(remove_note_reminders(&mut data, 7, 3), (1, true));
+        let ids: Vec<u64> = data.reminders.iter().map(|r| r.id).collect();
+        assert_eq!(ids, vec![2, 3, 4]);
+        // A reminder chained to it keeps its planned time
+        assert_eq!(data.reminders[2].relative_to, None);
+        // The fired one can't be snoozed back
+        assert!(snooze_fired_reminder(&mut data, 5, 7, 10, now).is_none());
+        assert_eq!(remove_note_reminders(&mut data, 7, 3), (0, false));
+
+        // A snoozed note reminder keeps pointing at the note
+        data.fired.push(note_reminder(6, 7, Some(9)));
+        let snoozed = snooze_fired_reminder(&mut data, 6, 7, 10, now).unwrap();
+        assert_eq!(snoozed.note_id, Some(9));
+        assert_eq!(remove_note_reminders(&mut data, 7, 9), (1, true));
+    }
+
+    #[test]
+    fn test_notification_style() {
+        let low = notification_style(ReminderPriority::Low);
+        assert!(!low.mention && !low.dm);
+        assert_eq!(
+            notification_style(ReminderPriority::Normal),
+            NotificationStyle {
+                color: Color::GOLD,
+                mention: true,
+                dm: false,
+            }
+        );
+        let high = notification_style(ReminderPriority::High);
+        assert_eq!(high.color, Color::RED);
+        assert!(high.mention && high.dm);
+
+        // Older reminders are normal priority
+        let mut reminder = test_reminder(None);
+        let json = serde_json::to_string(&reminder).unwrap();
+        let json = json.replace(r#","priority":"normal""#, "");
+        let loaded: Reminder = serde_json::from_str(&json).unwrap();
+        assert_eq!(loaded.priority, ReminderPriority::Normal);
+        assert!(!format_reminder_entry(&loaded).contains("priority"));
+        reminder.priority = ReminderPriority::High;
+        assert!(format_reminder_entry(&reminder).contains("🚨 high priority"));
+    }
+
+    #[test]
+    fn test_plan_ics_import() {
+        let now = Utc::now();
+        let event = |summary: &str, hours: i64, recurring| IcsEvent {
+            summary: summary.to_string(),
+            start: now + Duration::hours(hours),
+            recurring,
+            rule: None,
+        };
+        let events = vec![
+            event("Later", 48, true),
+            event("Gone", -1, false),
+            event("", 2, false),
+            event("Far off", 24 * 365 * 5, false),
+        ];
+        let import = plan_ics_import(&events, now, Duration::days(730));
+        assert_eq!(
+            import.reminders,
+            vec![
+                ("📅 Untitled event".to_string(), now + Duration::hours(2)),
+                ("📅 Later".to_string(), now + Duration::hours(48)),
+            ]
+        );
+        assert_eq!((import.past, import.too_far, import.recurring), (1, 1, 1));
+        assert_eq!(
+            ics_import_summary(&import, 2, "cal.ics"),
+            "📅 Imported **2** reminders from `cal.ics`.\n\
+             Skipped: 1 already past, 1 too far ahead, 2 without a start time I could read.\n\
+             🔁 1 repeating event only got a reminder for the next time."
+        );
+
+        // A weekly event that started long ago gets its next time
+        let calendar = parse_calendar(
+            "BEGIN:VCALENDAR\nBEGIN:VEVENT\nSUMMARY:Standup\n\
+             DTSTART:20200106T080000Z\nRRULE:FREQ=WEEKLY\nEND:VEVENT\nEND:VCALENDAR\n",
+            &REMINDER_TIMEZONE,
+        )
+        .unwrap();
+        let import = plan_ics_import(&calendar.events, now, Duration::days(730));
+        assert_eq!(import.past, 0);
+        assert_eq!(import.recurring, 1);
+        let (_, start) = &import.reminders[0];
+        assert!(*start > now && *start - now <= Duration::weeks(1));
+        assert_eq!(chrono::Datelike::weekday(start), chrono::Weekday::Mon);
+
+        let many: Vec<IcsEvent> = (1..=MAX_IMPORTED_REMINDERS as i64 + 5)
+            .map(|hours| event("Standup", hours, false))
+            .collect();
+        let import = plan_ics_import(&many, now, Duration::days(730));
+        assert_eq!(import.reminders.len(), MAX_IMPORTED_REMINDERS);
+        assert_eq!(import.over_limit, 5);
+    }
+
+    #[test]
+    fn test_cancel_reminders() {
+        let now = Utc::now();
+        let reminder = |id, user_id, hours, message: &str| {
+            let mut reminder = test_reminder(None);
+            reminder.id = id;
+            reminder.user_id = user_id;
+            reminder.remind_at = now + Duration::hours(hours);
+            reminder.message = message.to_string();
+            reminder
+        };
+        let all = vec![
+            reminder(1, 7, 1, "Dentist at 3"),
+            reminder(2, 7, 48, "Pay rent"),
+            reminder(3, 7, 72, "Call the dentist back"),
+            reminder(4, 8, 1, "dentist"),
+        ];
+        let ids = |reminders: &[Reminder]| reminders.iter().map(|r| r.id).collect::<Vec<_>>();
+
+        let mut reminders = all.clone();
+        let day = Some(now + Duration::hours(24));
+        assert_eq!(cancel_reminders(&mut reminders, 7, day, None), 1);
+        assert_eq!(ids(&reminders), vec![2, 3, 4]);
+
+        let mut reminders = all.clone();
+        assert_eq!(
+            cancel_reminders(&mut reminders, 7, None, Some("DENTIST")),
+            2
+        );
+        assert_eq!(ids(&reminders), vec![2, 4]);
+
+        // Both criteria have to match
+        let mut reminders = all;
+        assert_eq!(cancel_reminders(&mut reminders, 7, day, Some("rent")), 0);
+        assert_eq!(reminders.len(), 4);
+    }
+
+    #[test]
+    fn test_clean_reminder_message() {
+        assert_eq!(
+            clean_reminder_message("  call\u{7}\nmom  "),
+            Ok("call\nmom".to_string())
+        );
+        assert_eq!(
+            clean_reminder_message("@everyone standup"),
+            Ok("@\u{200B}everyone standup".to_string())
+        );
+        assert!(clean_reminder_message("   ").is_err());
+        assert!(clean_reminder_message(&"a".repeat(MAX_REMINDER_MESSAGE_CHARS)).is_ok());
+        assert!(clean_reminder_message(&"a".repeat(MAX_REMINDER_MESSAGE_CHARS + 1)).is_err());
+    }
+
+    #[test]
+    fn test_claim_due() {
+        let now = Utc.with_ymd_and_hms(2026, 3, 1, 9, 0, 0).unwrap();
+        let reminder = |id, remind_at, sending| {
+            let mut reminder = test_reminder(None);
+            reminder.id = id;
+            reminder.remind_at = remind_at;
+            reminder.sending = sending;
+            reminder
+        };
+        let mut reminders = vec![
+            reminder(1, now, None),
+            reminder(2, now + Duration::minutes(5), None),
+            // Another check is sending it
+            reminder(3, now, Some(now - Duration::minutes(1))),
+            // Its check never finished
+            reminder(
+                4,
+                now - Duration::hours(1),
+                Some(now - Duration::minutes(CLAIM_TIMEOUT_MINUTES)),
+            ),
+        ];
+
+        let claimed = claim_due(&mut reminders, now);
+        assert_eq!(claimed.iter().map(|r| r.id).collect::<Vec<_>>(), vec![1, 4]);
+        assert!(claimed.iter().all(|r| r.sending == Some(now)));
+        assert_eq!(reminders[0].sending, Some(now));
+        assert_eq!(reminders[1].sending, None);
+        assert_eq!(reminders[2].sending, Some(now - Duration::minutes(1)));
+
+        // An overlapping check finds nothing left to send
+        assert!(claim_due(&mut reminders, now + Duration::seconds(1)).is_empty());
+    }
+
+    impl ReminderStore for tokio::sync::Mutex<RemindersData> {
+        async fn update<R: Send>(
+            &self,
+            change: impl FnOnce(&mut RemindersData) -> (R, bool) + Send,
+        ) -> Result<R, Error> {
+            let mut stored = self.lock().await;
+            let mut data = stored.clone();
+            let (result, changed) = change(&mut data);
+            if changed {
+                *stored = data;
+            }
+            Ok(result)
+        }
+    }
+
+    #[tokio::test]
+    async fn test_overlapping_checks_send_once() {
+        let now = Utc::now();
+        let mut data = RemindersData::default();
+        for id in 1..=3 {
+            let mut reminder = test_reminder((id == 3).then_some(10));
+            reminder.id = id;
+            reminder.remind_at = now - Duration::minutes(1);
+            data.reminders.push(reminder);
+        }
+        let store = tokio::sync::Mutex::new(data);
+
+        // Discord takes its time, so the second check runs while the first
+        // one is still sending
+        let sent = std::sync::Mutex::new(Vec::new());
+        let log = &sent;
+        let deliver = move |reminder: Reminder| async move {
+            tokio::time::sleep(std::time::Duration::from_millis(50)).await;
+            log.lock().unwrap().push(reminder.id);
+            Delivery {
+                message_id: Some(100 + reminder.id),
+                webhook_gone: false,
+            }
+        };
+        let (first, second) = tokio::join!(run_check(&store, now, deliver, |_| async {}), async {
+            tokio::time::sleep(std::time::Duration::from_millis(10)).await;
+            run_check(&store, now + Duration::seconds(1), deliver, |_| async {}).await
+        });
+        first.unwrap();
+        second.unwrap();
+
+        let mut sent = sent.into_inner().unwrap();
+        sent.sort_unstable();
+        assert_eq!(sent, vec![1, 2, 3]);
+        let data = store.into_inner();
+        let mut fired = data.fired.iter().map(|r| r.id).collect::<Vec<_>>();
+        fired.sort_unstable();
+        assert_eq!(fired, vec![1, 2]);
+        // The nag is released and waits for its next round
+        assert_eq!(data.reminders.len(), 1);
+        assert_eq!(data.reminders[0].sending, None);
+        assert!(data.reminders[0].remind_at > now);
+    }
+
+    #[test]
+    fn test_record_deliveries() {
+        let now = Utc.with_ymd_and_hms(2026, 3, 1, 9, 0, 0).unwrap();
+        let mut data = RemindersData::default();
+        for id in 1..=5 {
+            let mut reminder = test_reminder((id == 2).then_some(10));
+            reminder.id = id;
+            reminder.remind_at = now;
+            data.reminders.push(reminder);
+        }
+        data.reminders[0].note_id = Some(7);
+        data.reminders[0].followup_after_minutes = Some(30);
+        let claimed = claim_due(&mut data.reminders, now);
+        // Meanwhile 4 was cancelled and 5 was taken over by another check
+        data.reminders.retain(|r| r.id != 4);
+        data.reminders[3].sending = Some(now + Duration::minutes(20));
+
+        let sent = |id: u64| Delivery {
+            message_id: Some(100 + id),
+            webhook_gone: false,
+        };
+        let deliveries = vec![sent(1), sent(2), Delivery::default(), sent(4), sent(5)];
+        let noted = record_deliveries(&mut data, &claimed, &deliveries, now);
+        assert_eq!(noted, vec![(1, 7)]);
+
+        // The one-off went out and can be snoozed now
+        assert_eq!(data.fired.iter().map(|r| r.id).collect::<Vec<_>>(), vec![1]);
+        assert_eq!(data.awaiting_followup.len(), 1);
+        assert_eq!(data.awaiting_followup[0].message_id, 101);
+        // The nag comes back, the failed one is retried on the next check
+        let ids = data.reminders.iter().map(|r| r.id).collect::<Vec<_>>();
+        assert_eq!(ids, vec![2, 3, 5]);
+        assert_eq!(data.reminders[0].remind_at, now + Duration::minutes(10));
+        assert_eq!(data.reminders[0].sending, None);
+        assert_eq!(data.reminders[1].remind_at, now);
+        assert_eq!(data.reminders[1].sending, None);
+        assert_eq!(data.reminders[2].sending, Some(now + Duration::minutes(20)));
+    }
+
+    #[test]
+    fn test_parse_webhook_identity() {
+        assert_eq!(
+            parse_webhook_identity("Standup in 5"),
+            Ok((None, None, "Standup in 5".to_string()))
+        );
+        assert_eq!(
+            parse_webhook_identity("--name Clockbot Standup in 5"),
+            Ok((
+                Some("Clockbot".to_string()),
+                None,
+                "Standup in 5".to_string()
+            ))
+        );
+        assert_eq!(
+            parse_webhook_identity(
+                r#"--avatar https://example.com/owl.png --name "Night Owl" Lights out "now""#
+            ),
+            Ok((
+                Some("Night Owl".to_string()),
+                Some("https://example.com/owl.png".to_string()),
+                r#"Lights out "now""#.to_string()
+            ))
+        );
+        // Options only count at the start
+        assert_eq!(
+            parse_webhook_identity("Ask about --name tags"),
+            Ok((None, None, "Ask about --name tags".to_string()))
+        );
+        assert_eq!(
+            parse_webhook_identity("--name Clockbot"),
+            Ok((Some("Clockbot".to_string()), None, String::new()))
+        );
+
+        assert!(parse_webhook_identity(r#"--name "Night Owl Lights out"#)
+            .unwrap_err()
+            .contains("closing quote"));
+        assert!(parse_webhook_identity(&format!(
+            "--name {} hi",
+            "a".repeat(MAX_WEBHOOK_NAME_CHARS + 1)
+        ))
+        .unwrap_err()
+        .contains("1 to 80 characters"));
+        assert!(parse_webhook_identity(r#"--name "" hi"#)
+            .unwrap_err()
+            .contains("1 to 80 characters"));
+        assert!(
+            parse_webhook_identity("--avatar http://example.com/owl.png hi")
+                .unwrap_err()
+                .contains("https://")
+        );
+    }
+
+    #[test]
+    fn test_edit_reminder() {
+        let now = Utc::now();
+        let mut base = test_reminder(Some(30));
+        base.user_id = 7;
+        base.reply_to_message_id = Some(55);
+        base.relative_to = Some(9);
+        let mut chained = test_reminder(None);
+        chained.id = 2;
+        chained.relative_to = Some(1);
+        chained.relative_offset_secs = 600;
+        let mut fired = test_reminder(None);
+        fired.id = 3;
+        fired.user_id = 7;
+        let mut data = RemindersData {
+            reminders: vec![base.clone(), chained],
+            fired: vec![fired],
+            ..Default::default()
+        };
+
+        // Someone else's reminder, one that already fired, and a missing one
+        assert!(edit_reminder(&mut data, 1, 8, None, Some("x".to_string())).is_err());
+        assert!(edit_reminder(&mut data, 3, 7, None, Some("x".to_string()))
+            .unwrap_err()
+            .contains("already went off"));
+        assert!(edit_reminder(&mut data, 4, 7, None, Some("x".to_string())).is_err());
+
+        // Only the message
+        let edit = edit_reminder(&mut data, 1, 7, None, Some("Walk the dog".to_string())).unwrap();
+        assert_eq!(edit.old_message.as_deref(), Some("Take out the trash"));
+        assert_eq!(edit.old_remind_at, None);
+        assert_eq!(data.reminders[0].message, "Walk the dog");
+        assert_eq!(data.reminders[0].relative_to, Some(9));
+
+        // A new time keeps the ID and reply, unchains it and moves its dependents
+        let at = now + Duration::hours(2);
+        let edit = edit_reminder(&mut data, 1, 7, Some(at), None).unwrap();
+        assert_eq!(edit.old_message, None);
+        assert_eq!(edit.old_remind_at, Some(base.remind_at));
+        assert_eq!(edit.unchained_from, Some(9));
+        let edited = &data.reminders[0];
+        assert_eq!(
+            (edited.id, edited.reply_to_message_id, edited.created_at),
+            (1, Some(55), base.created_at)
+        );
+        assert_eq!(edited.remind_at, at);
+        assert_eq!(edited.nag_interval_minutes, Some(30));
+        assert_eq!(data.reminders[1].remind_at, at + Duration::minutes(10));
+
+        let summary = edit_summary(&edit, &data.reminders[0]);
+        assert!(summary.contains(&format!("~~<t:{}:F>~~", base.remind_at.timestamp())));
+        assert!(summary.contains("No longer waits for reminder 9"));
+    }
+
+    #[test]
+    fn test_cancel_by_reaction() {
+        let reminder = |id, cancel_message_id| {
+            let mut reminder = test_reminder(None);
+            reminder.id = id;
+            reminder.user_id = 7;
+            reminder.cancel_message_id = cancel_message_id;
+            reminder
+        };
+        let mut chained = reminder(3, None);
+        chained.relative_to = Some(1);
+        let mut set_for_someone = reminder(4, Some(400));
+        set_for_someone.created_by = Some(9);
+        let mut data = RemindersData {
+            reminders: vec![reminder(1, Some(100)), chained, set_for_someone],
+            fired: vec![reminder(2, Some(200))],
+            ..Default::default()
+        };
+
+        // Only the reminder's owner can cancel it
+        assert_eq!(
+            cancel_by_reaction(&mut data, 100, 8),
+            ReactionCancel::NotACopy
+        );
+        assert_eq!(
+            cancel_by_reaction(&mut data, 100, 7),
+            ReactionCancel::Cancelled(1, test_reminder(None).message, 1)
+        );
+        assert_eq!(data.reminders[0].relative_to, None);
+        assert_eq!(
+            cancel_by_reaction(&mut data, 100, 7),
+            ReactionCancel::NotACopy
+        );
+
+        assert_eq!(
+            cancel_by_reaction(&mut data, 200, 7),
+            ReactionCancel::AlreadyFired(2)
+        );
+        // Whoever set it for someone else can cancel it too
+        assert!(matches!(
+            cancel_by_reaction(&mut data, 400, 9),
+            ReactionCancel::Cancelled(4, _, 0)
+        ));
+        assert_eq!(data.reminders.len(), 1);
+    }
+
+    #[test]
+    fn test_apply_presence() {
+        let now = Utc::now();
+        let deadline = now + Duration::hours(4);
+        let when_free = |id, user_id, away_seen| {
+            let mut reminder = test_reminder(None);
+            reminder.id = id;
+            reminder.user_id = user_id;
+            reminder.remind_at = deadline;
+            reminder.until_free = Some(UntilFree { away_seen });
+            reminder
+        };
+        let mut data = RemindersData {
+            reminders: vec![when_free(1, 7, false), when_free(2, 8, true)],
+            ..Default::default()
+        };
+        let mut plain = test_reminder(None);
+        plain.id = 3;
+        plain.user_id = 7;
+        plain.remind_at = deadline;
+        data.reminders.push(plain);
+
+        // Set while online: still being online doesn't count as coming back
+        assert_eq!(apply_presence(&mut data, 7, true, now), (false, false));
+        // Going idle arms it, once
+        assert_eq!(apply_presence(&mut data, 7, false, now), (true, false));
+        assert_eq!(apply_presence(&mut data, 7, false, now), (false, false));
+        // Back online: due now, and only the when-free reminder
+        assert_eq!(apply_presence(&mut data, 7, true, now), (true, true));
+        assert_eq!(data.reminders[0].remind_at, now);
+        assert_eq!(data.reminders[0].until_free, None);
+        assert_eq!(data.reminders[2].remind_at, deadline);
+        // Other users' reminders are untouched
+        assert_eq!(data.reminders[1].remind_at, deadline);
+
+        // Past its deadline it's the checker's to send, not the presence's
+        assert_eq!(
+            apply_presence(&mut data, 8, true, deadline + Duration::minutes(1)),
+            (false, false)
+        );
+
+        let entry = format_reminder_entry(&data.reminders[1]);
+        assert!(entry.contains("back online"), "{entry}");
+
+        // Snoozing one that went off makes it an ordinary timed reminder
+        data.fired.push(data.reminders.remove(1));
+        data.next_id = 4;
+        let snoozed = snooze_fired_reminder(&mut data, 2, 8, 10, now).unwrap();
+        assert_eq!(snoozed.until_free, None);
+    }
+
+    #[test]
+    fn test_snooze_nudge() {
+        assert!(snooze_nudge(1).is_none());
+        assert!(snooze_nudge(SNOOZE_NUDGE_THRESHOLD - 1).is_none());
+        assert!(snooze_nudge(SNOOZE_NUDGE_THRESHOLD)
+            .unwrap()
+            .contains("maybe just do it"));
+    }
+
+    #[test]
+    fn test_format_reminder_entry_shows_creator() {
+        let mut reminder = test_reminder(None);
+        assert!(!format_reminder_entry(&reminder).contains("set by"));
+
+        reminder.created_by = Some(42);
+        assert!(format_reminder_entry(&reminder).contains("👤 set by <@42>"));
+    }
+
+    #[test]
+    fn test_parse_clock_time() {
+        assert_eq!(parse_clock_time("22:00"), Some((22, 0)));
+        assert_eq!(parse_clock_time(" 7:30 "), Some((7, 30)));
+        assert_eq!(parse_clock_time("7"), Some((7, 0)));
+        assert_eq!(parse_clock_time("24:00"), None);
+        assert_eq!(parse_clock_time("7:5"), None);
+        assert_eq!(parse_clock_time("late"), None);
+    }
+
+    #[test]
+    fn test_quiet_hours_end() {
+        let quiet = QuietHours {
+            start_hour: 22,
+            start_minute: 0,
+            end_hour: 7,
+            end_minute: 0,
+            timezone: Some("Europe/Copenhagen".to_string()),
+        };
+        let tz = quiet.timezone();
+        let at = |day, hour, minute| {
+            tz.with_ymd_and_hms(2026, 10, day, hour, minute, 0)
+                .unwrap()
+                .with_timezone(&Utc)
+        };
+
+        // Before midnight the window ends the next morning, after it the same morning
+        assert_eq!(quiet.end_after(at(16, 23, 30)), Some(at(17, 7, 0)));
+        assert_eq!(quiet.end_after(at(16, 22, 0)), Some(at(17, 7, 0)));
+        assert_eq!(quiet.end_after(at(17, 0, 0)), Some(at(17, 7, 0)));
+        assert_eq!(quiet.end_after(at(17, 6, 59)), Some(at(17, 7, 0)));
+        assert_eq!(quiet.end_after(at(17, 7, 0)), None);
+        assert_eq!(quiet.end_after(at(16, 21, 59)), None);
+        assert_eq!(quiet.end_after(at(16, 12, 0)), None);
+
+        // The night the clocks go back (25 October 2026) is an hour longer
+        let end = quiet.end_after(at(24, 23, 0)).unwrap();
+        assert_eq!(end, at(25, 7, 0));
+        assert_eq!(end - at(24, 23, 0), Duration::hours(9));
+
+        // A window within one day
+        let lunch = QuietHours {
+            start_hour: 12,
+            start_minute: 30,
+            end_hour: 13,
+            end_minute: 15,
+            timezone: None,
+        };
+        let tz = REMINDER_TIMEZONE;
+        let at = |hour, minute| {
+            tz.with_ymd_and_hms(2026, 10, 16, hour, minute, 0)
+                .unwrap()
+                .with_timezone(&Utc)
+        };
+        assert_eq!(lunch.end_after(at(12, 45)), Some(at(13, 15)));
+        assert_eq!(lunch.end_after(at(12, 29)), None);
+        assert_eq!(lunch.end_after(at(13, 15)), None);
+        assert_eq!(lunch.describe(), "12:30-13:15 (Europe/Copenhagen)");
+    }
+
+    #[test]
+    fn test_move_reminders_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let old = dir.path().join("old.json");
+        let new = dir.path().join("data").join("rustbot_reminders.json");
+        assert!(!move_reminders_file(&old, &new).unwrap());
+
+        std::fs::write(&old, "{}").unwrap();
+        assert!(move_reminders_file(&old, &new).unwrap());
+        assert!(!old.exists());
+        assert_eq!(std::fs::read_to_string(&new).unwrap(), "{}");
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            let mode = std::fs::metadata(&new).unwrap().permissions().mode();
+            assert_eq!(mode & 0o777, 0o600);
+        }
+
+        // Reminders already in the data directory win
+        std::fs::write(&old, "stale").unwrap();
+        assert!(!move_reminders_file(&old, &new).unwrap());
+        assert_eq!(std::fs::read_to_string(&new).unwrap(), "{}");
+        assert!(old.exists());
+    }
+
+    #[test]
+    fn test_take_priority_marker() {
+        assert_eq!(
+            take_priority_marker("!high call mom"),
+            Ok((ReminderPriority::High, "call mom"))
+        );
+        assert_eq!(
+            take_priority_marker("--priority low water plants"),
+            Ok((ReminderPriority::Low, "water plants"))
+        );
+        assert_eq!(
+            take_priority_marker("--priority=HIGH pay rent"),
+            Ok((ReminderPriority::High, "pay rent"))
+        );
+        assert_eq!(
+            take_priority_marker("!low"),
+            Ok((ReminderPriority::Low, ""))
+        );
+        // Without a marker the first word stays part of the message
+        assert_eq!(
+            take_priority_marker("high five the team"),
+            Ok((ReminderPriority::Normal, "high five the team"))
+        );
+        assert_eq!(
+            take_priority_marker("!important thing"),
+            Ok((ReminderPriority::Normal, "!important thing"))
+        );
+        assert!(take_priority_marker("--priority urgent call").is_err());
+        assert!(take_priority_marker("--priority").is_err());
     }
 
     #[test]
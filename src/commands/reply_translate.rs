@@ -0,0 +1,271 @@
+//! Translating a message by replying to it with `-tr`, into each user's own
+//! language. Uses the same API and languages as flag translation.
+
+use crate::commands::flagtranslate::{find_language, MAX_TRANSLATE_CHARS};
+use crate::storage;
+use crate::translate::translate_text;
+use crate::utils::limits::fit_description;
+use crate::utils::messages::parse_message_ref;
+use crate::utils::permissions::{check_author_can_read, is_not_found};
+use crate::{Context, Error};
+use parking_lot::RwLock;
+use poise::serenity_prelude as serenity;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+const TRANSLATE_PREFS_FILE: &str = "translate_prefs.json";
+
+/// Used until a user picks a language.
+const DEFAULT_LANGUAGE: &str = "en";
+
+#[derive(Serialize, Deserialize, Default)]
+struct TranslatePrefs {
+    /// Language code each user wants translations in.
+    languages: HashMap<u64, String>,
+}
+
+lazy_static::lazy_static! {
+    static ref TRANSLATE_PREFS: RwLock<TranslatePrefs> =
+        RwLock::new(storage::load(TRANSLATE_PREFS_FILE));
+}
+
+/// Re-read the users' languages from disk.
+pub fn reload_translate_prefs() -> Result<(), Error> {
+    *TRANSLATE_PREFS.write() = storage::reload(TRANSLATE_PREFS_FILE)?;
+    Ok(())
+}
+
+/// Command check for commands that change the stored languages.
+async fn translate_prefs_writable(ctx: Context<'_>) -> Result<bool, Error> {
+    crate::utils::check_store_writable(ctx, &storage::data_path(TRANSLATE_PREFS_FILE)).await
+}
+
+/// The language a user gets translations in, as code and name.
+fn user_language(user_id: u64) -> (&'static str, &'static str) {
+    TRANSLATE_PREFS
+        .read()
+        .languages
+        .get(&user_id)
+        .and_then(|code| find_language(code))
+        .or_else(|| find_language(DEFAULT_LANGUAGE))
+        .unwrap_or((DEFAULT_LANGUAGE, "English"))
+}
+
+/// Translate a message, given as a link or ID (or the one a prefix command
+/// replies to), into `language` or the author's own language.
+async fn translate_message(
+    ctx: Context<'_>,
+    message: Option<String>,
+    language: Option<String>,
+) -> Result<(), Error> {
+    let target = match language.as_deref().map(str::trim).filter(|l| !l.is_empty()) {
+        Some(input) => match find_language(input) {
+            Some(language) => language,
+            None => {
+                ctx.say(format!(
+                    "❌ I don't know the language `{}`. Use a code like `fr` or a name like `French`.",
+                    input
+                ))
+                .await?;
+                return Ok(());
+            }
+        },
+        None => user_language(ctx.author().id.get()),
+    };
+
+    let original = match (message, ctx) {
+        (Some(text), _) => {
+            let Some((channel_id, message_id)) = parse_message_ref(&text, ctx.channel_id()) else {
+                ctx.say("❌ That isn't a message link or ID.").await?;
+                return Ok(());
+            };
+            if let Err(refusal) = check_author_can_read(ctx, channel_id).await {
+                ctx.say(refusal).await?;
+                return Ok(());
+            }
+            match channel_id.message(ctx.http(), message_id).await {
+                Ok(message) => message,
+                Err(e) if is_not_found(&e) => {
+                    ctx.say("❌ That message doesn't exist (anymore).").await?;
+                    return Ok(());
+                }
+                Err(e) => return Err(e.into()),
+            }
+        }
+        (None, poise::Context::Prefix(prefix_ctx)) => match &prefix_ctx.msg.referenced_message {
+            Some(message) => message.as_ref().clone(),
+            None => {
+                ctx.say("❌ Reply to a message with `-tr` to translate it!")
+                    .await?;
+                return Ok(());
+            }
+        },
+        (None, poise::Context::Application(_)) => {
+            ctx.say("❌ Give the link or ID of the message to translate.")
+                .await?;
+            return Ok(());
+        }
+    };
+
+    let text = original.content.trim();
+    if text.is_empty() {
+        ctx.say("❌ That message has no text to translate.").await?;
+        return Ok(());
+    }
+    if text.chars().count() > MAX_TRANSLATE_CHARS {
+        ctx.say(format!(
+            "❌ That message is too long to translate (max {} characters).",
+            MAX_TRANSLATE_CHARS
+        ))
+        .await?;
+        return Ok(());
+    }
+
+    ctx.defer().await?;
+    let (code, name) = target;
+    let translation = match translate_text(text, code).await {
+        Ok(translation) => translation,
+        Err(e) => {
+            log::warn!("Failed to translate message {}: {}", original.id, e);
+            ctx.say("❌ The translation service didn't answer. Try again in a bit.")
+                .await?;
+            return Ok(());
+        }
+    };
+    if translation.source_language.as_deref() == Some(code) {
+        ctx.say(format!("🌐 That message is already in {}.", name))
+            .await?;
+        return Ok(());
+    }
+
+    let footer = match &translation.source_language {
+        Some(source) => format!("From {} • Requested by {}", source, ctx.author().name),
+        None => format!("Requested by {}", ctx.author().name),
+    };
+    let embed = serenity::CreateEmbed::new()
+        .title(format!("🌐 {}", name))
+        .description(fit_description(&translation.text))
+        .color(0x5865F2)
+        .footer(serenity::CreateEmbedFooter::new(footer));
+
+    // Answer the original message, so the translation sits under it
+    if original.channel_id == ctx.channel_id() {
+        original
+            .channel_id
+            .send_message(
+                ctx.http(),
+                serenity::CreateMessage::new()
+                    .embed(embed)
+                    .reference_message(&original)
+                    .allowed_mentions(serenity::CreateAllowedMentions::new()),
+            )
+            .await?;
+        if let poise::Context::Application(_) = ctx {
+            ctx.send(
+                poise::CreateReply::default()
+                    .content("🌐 Translated.")
+                    .ephemeral(true),
+            )
+            .await?;
+        }
+    } else {
+        let link = original.id.link(original.channel_id, original.guild_id);
+        ctx.send(
+            poise::CreateReply::default()
+                .content(format!("🌐 Translation of {}", link))
+                .embed(embed),
+        )
+        .await?;
+    }
+
+    log::info!(
+        "Translated message {} to {} for {}",
+        original.id,
+        code,
+        ctx.author().name
+    );
+    Ok(())
+}
+
+/// Translate the message you reply to into your language
+///
+/// As a prefix command `-tr [language]` is short for `-translate auto`.
+#[poise::command(
+    prefix_command,
+    slash_command,
+    aliases("tr"),
+    subcommands("translate_auto", "translate_language")
+)]
+pub async fn translate(
+    ctx: Context<'_>,
+    #[description = "Language to translate into (default: yours)"] language: Option<String>,
+) -> Result<(), Error> {
+    log::info!("Translate command called by {}", ctx.author().name);
+    translate_message(ctx, None, language).await
+}
+
+/// Detect a message's language and translate it into yours
+#[poise::command(prefix_command, slash_command, rename = "auto")]
+pub async fn translate_auto(
+    ctx: Context<'_>,
+    #[description = "Link or ID of the message (or reply to it)"] message: Option<String>,
+    #[description = "Language to translate into (default: yours)"] language: Option<String>,
+) -> Result<(), Error> {
+    log::info!("Translate auto command called by {}", ctx.author().name);
+    translate_message(ctx, message, language).await
+}
+
+/// Show or set the language your translations are in
+#[poise::command(
+    prefix_command,
+    slash_command,
+    rename = "language",
+    check = "translate_prefs_writable"
+)]
+pub async fn translate_language(
+    ctx: Context<'_>,
+    #[description = "Language code like fr or name like French"] language: Option<String>,
+) -> Result<(), Error> {
+    log::info!(
+        "Translate language command called by {} with {:?}",
+        ctx.author().name,
+        language
+    );
+
+    let user_id = ctx.author().id.get();
+    let Some(input) = language.as_deref().map(str::trim).filter(|l| !l.is_empty()) else {
+        let (code, name) = user_language(user_id);
+        ctx.say(format!(
+            "🌐 Your translations are in **{}** (`{}`). Change it with `-translate language <language>`.",
+            name, code
+        ))
+        .await?;
+        return Ok(());
+    };
+    let Some((code, name)) = find_language(input) else {
+        ctx.say(format!(
+            "❌ I don't know the language `{}`. Use a code like `fr` or a name like `French`.",
+            input
+        ))
+        .await?;
+        return Ok(());
+    };
+
+    let saved = {
+        let mut prefs = TRANSLATE_PREFS.write();
+        prefs.languages.insert(user_id, code.to_string());
+        storage::save(TRANSLATE_PREFS_FILE, &*prefs)
+    };
+    if let Err(e) = saved {
+        log::error!("Failed to save translate preferences: {}", e);
+        ctx.say(format!("❌ Failed to save your language: {e}"))
+            .await?;
+        return Ok(());
+    }
+    ctx.say(format!(
+        "✅ Messages you translate with `-tr` now come out in **{}**.",
+        name
+    ))
+    .await?;
+    Ok(())
+}
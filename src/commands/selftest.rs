@@ -0,0 +1,164 @@
+use crate::commands::park::encryption_key_status;
+use crate::storage::DATA_DIR;
+use crate::utils::is_protected_user;
+use crate::utils::meme::{asset_problems, assets_dir};
+use crate::{Context, Error};
+use poise::serenity_prelude as serenity;
+use std::fs;
+use std::io::Write;
+use std::path::Path;
+use std::time::Instant;
+
+/// The outcome of one subsystem check.
+struct Check {
+    name: &'static str,
+    result: Result<String, String>,
+}
+
+/// Check every subsystem and report which ones work (bot owners only)
+///
+/// Meant for checking a fresh deploy: writes and reads a file in the data
+/// directory, pings the Discord API, looks for the meme GIFs and reads the
+/// parking key. The checks run at the same time.
+#[poise::command(prefix_command, slash_command)]
+pub async fn selftest(ctx: Context<'_>) -> Result<(), Error> {
+    log::info!("Selftest command called by {}", ctx.author().name);
+
+    if !is_protected_user(&ctx.author().name) {
+        ctx.say("❌ You don't have permission to use this command!")
+            .await?;
+        return Ok(());
+    }
+
+    ctx.defer().await?;
+    let (persistence, discord, assets, parking) = tokio::join!(
+        blocking("Persistence", || check_roundtrip(Path::new(DATA_DIR))),
+        check_discord(ctx),
+        blocking("Assets", check_assets),
+        blocking("Parking key", encryption_key_status),
+    );
+    let checks = [persistence, discord, assets, parking];
+
+    let (color, summary) = summarize(&checks);
+    let mut embed = serenity::CreateEmbed::new()
+        .title("🩺 Self-test")
+        .description(summary)
+        .color(color)
+        .timestamp(serenity::Timestamp::now());
+    for check in &checks {
+        let (icon, detail) = match &check.result {
+            Ok(detail) => ("✅", detail),
+            Err(detail) => ("❌", detail),
+        };
+        embed = embed.field(format!("{} {}", icon, check.name), detail, false);
+    }
+    ctx.send(poise::CreateReply::default().embed(embed)).await?;
+    Ok(())
+}
+
+/// Run a check that touches the disk on the blocking pool.
+async fn blocking(
+    name: &'static str,
+    check: impl FnOnce() -> Result<String, String> + Send + 'static,
+) -> Check {
+    let result = tokio::task::spawn_blocking(check)
+        .await
+        .unwrap_or_else(|e| Err(format!("the check crashed: {}", e)));
+    Check { name, result }
+}
+
+async fn check_discord(ctx: Context<'_>) -> Check {
+    let start = Instant::now();
+    let result = match ctx.http().get_current_user().await {
+        Ok(_) => Ok(format!("API answered in {}ms", start.elapsed().as_millis())),
+        Err(e) => Err(format!("API request failed: {}", e)),
+    };
+    Check {
+        name: "Discord API",
+        result,
+    }
+}
+
+/// Write a file to `dir` and read it back, like the stores do.
+fn check_roundtrip(dir: &Path) -> Result<String, String> {
+    let written = format!("rustbot selftest {}", std::process::id());
+    let roundtrip = || -> std::io::Result<String> {
+        fs::create_dir_all(dir)?;
+        let mut file = tempfile::NamedTempFile::new_in(dir)?;
+        file.write_all(written.as_bytes())?;
+        file.flush()?;
+        fs::read_to_string(file.path())
+    };
+    match roundtrip() {
+        Ok(read) if read == written => {
+            Ok(format!("wrote and read back a file in {}", dir.display()))
+        }
+        Ok(_) => Err(format!(
+            "a file written to {} read back differently",
+            dir.display()
+        )),
+        Err(e) => Err(format!("can't write to {}: {}", dir.display(), e)),
+    }
+}
+
+fn check_assets() -> Result<String, String> {
+    let problems = asset_problems();
+    if problems.is_empty() {
+        Ok(format!(
+            "bonk and hit GIFs found in {}",
+            assets_dir().display()
+        ))
+    } else {
+        Err(problems.join("\n"))
+    }
+}
+
+/// Embed color and headline for a set of checks.
+fn summarize(checks: &[Check]) -> (u32, String) {
+    let passed = checks.iter().filter(|check| check.result.is_ok()).count();
+    if passed == checks.len() {
+        (0x00ff00, format!("All {} checks passed.", checks.len()))
+    } else {
+        (
+            0xff0000,
+            format!(
+                "{} of {} checks failed.",
+                checks.len() - passed,
+                checks.len()
+            ),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_check_roundtrip() {
+        let dir = tempfile::tempdir().unwrap();
+        assert!(check_roundtrip(&dir.path().join("data")).is_ok());
+        // No files left behind
+        assert_eq!(fs::read_dir(dir.path().join("data")).unwrap().count(), 0);
+
+        let blocker = dir.path().join("file");
+        fs::write(&blocker, "").unwrap();
+        let error = check_roundtrip(&blocker.join("data")).unwrap_err();
+        assert!(error.starts_with("can't write to"), "{error}");
+    }
+
+    #[test]
+    fn test_summarize() {
+        let check = |result| Check { name: "x", result };
+        let checks = [check(Ok("fine".into())), check(Ok("fine".into()))];
+        assert_eq!(
+            summarize(&checks),
+            (0x00ff00, "All 2 checks passed.".into())
+        );
+        let checks = [check(Ok("fine".into())), check(Err("broken".into()))];
+        assert_eq!(
+            summarize(&checks),
+            (0xff0000, "1 of 2 checks failed.".into())
+        );
+    }
+}
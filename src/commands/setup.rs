@@ -0,0 +1,453 @@
+//! `-setup`: a checklist of what a server can configure, for admins who've
+//! just added the bot. It shows which features are set up, whether the bot
+//! can post where they post, and offers a menu to set up the rest.
+//!
+//! Each item reads its state through the module that owns the feature, and
+//! the test at the bottom makes sure every per-server store in
+//! [`crate::commands::reload_config`] shows up here, so a new feature can't
+//! be left off the list by accident.
+
+use crate::commands::flagtranslate::FlagTranslateMode;
+use crate::commands::{
+    automod, autopublish, autoreact, birthday, boost, bumper, digest, invites, reactmap, streak,
+};
+use crate::guild_config::{get_guild_config, guild_config_writable, update_guild_config};
+use crate::utils::args::usage_line;
+use crate::utils::permissions::check_channel_perms;
+use crate::{Context, Data, Error};
+use poise::serenity_prelude as serenity;
+use std::time::Duration;
+
+/// How long the menu keeps working after the checklist is posted.
+const MENU_TIMEOUT: Duration = Duration::from_secs(300);
+
+/// What the bot needs in a channel it posts embeds in.
+const POSTING: &[serenity::Permissions] = &[
+    serenity::Permissions::VIEW_CHANNEL,
+    serenity::Permissions::SEND_MESSAGES,
+    serenity::Permissions::EMBED_LINKS,
+];
+
+/// Where a checklist item stands in a guild.
+#[derive(Debug, PartialEq)]
+pub enum ItemStatus {
+    Unset,
+    Set,
+    /// Set up, and the feature posts in this channel.
+    PostsIn(u64),
+}
+
+impl ItemStatus {
+    fn from_channel(channel: Option<u64>) -> Self {
+        channel.map_or(ItemStatus::Unset, ItemStatus::PostsIn)
+    }
+
+    fn from_flag(set: bool) -> Self {
+        if set {
+            ItemStatus::Set
+        } else {
+            ItemStatus::Unset
+        }
+    }
+}
+
+/// A one-click setup from the menu, done in the channel `-setup` was run
+/// in. These only change server settings; anything that needs more than a
+/// channel points to its command instead.
+pub struct QuickSetup {
+    pub label: &'static str,
+    /// Applies the setting for (guild, channel) and says what changed.
+    pub apply: fn(u64, u64) -> String,
+}
+
+pub struct SetupItem {
+    pub name: &'static str,
+    /// The store the setting lives in, as named in `reload-config`.
+    pub store: &'static str,
+    /// The command that sets it up, with its subcommand.
+    pub command: &'static str,
+    pub status: fn(u64) -> ItemStatus,
+    pub quick: Option<QuickSetup>,
+}
+
+/// Everything on the checklist, in the order it's shown.
+pub const SETUP_ITEMS: &[SetupItem] = &[
+    SetupItem {
+        name: "Status channel",
+        store: "Server settings",
+        command: "statuschannel set",
+        status: |guild_id| {
+            ItemStatus::from_channel(get_guild_config(guild_id).and_then(|c| c.status_channel))
+        },
+        quick: Some(QuickSetup {
+            label: "Announce restarts in this channel",
+            apply: |guild_id, channel_id| {
+                update_guild_config(guild_id, |config| config.status_channel = Some(channel_id));
+                format!(
+                    "✅ I'll say when I'm back online after a restart in <#{}>.",
+                    channel_id
+                )
+            },
+        }),
+    },
+    SetupItem {
+        name: "Flag translation",
+        store: "Server settings",
+        command: "flagtranslate",
+        status: |guild_id| {
+            ItemStatus::from_flag(
+                get_guild_config(guild_id)
+                    .is_some_and(|c| c.flag_translate != FlagTranslateMode::Off),
+            )
+        },
+        quick: Some(QuickSetup {
+            label: "Reply to flag reactions with a translation",
+            apply: |guild_id, _| {
+                update_guild_config(guild_id, |config| {
+                    config.flag_translate = FlagTranslateMode::Reply
+                });
+                "🌐 Flag translation is now **on**. React with a country flag like 🇫🇷 and I'll reply with a translation.".to_string()
+            },
+        }),
+    },
+    SetupItem {
+        name: "Bot admin role",
+        store: "Server settings",
+        command: "botadmin set",
+        status: |guild_id| {
+            ItemStatus::from_flag(
+                get_guild_config(guild_id).is_some_and(|c| c.bot_admin_role.is_some()),
+            )
+        },
+        quick: None,
+    },
+    SetupItem {
+        name: "Daily digest",
+        store: "Digest",
+        command: "digest set",
+        status: |guild_id| ItemStatus::from_channel(digest::digest_channel(guild_id)),
+        quick: None,
+    },
+    SetupItem {
+        name: "Birthday announcements",
+        store: "Birthdays",
+        command: "birthday channel",
+        status: |guild_id| ItemStatus::from_channel(birthday::announce_channel(guild_id)),
+        quick: None,
+    },
+    SetupItem {
+        name: "Boost thank-yous",
+        store: "Boosts",
+        command: "boost channel",
+        status: |guild_id| ItemStatus::from_channel(boost::boost_channel(guild_id)),
+        quick: None,
+    },
+    SetupItem {
+        name: "Bumper",
+        store: "Bumper",
+        command: "bumper set",
+        status: |guild_id| ItemStatus::from_channel(bumper::bumper_channel(guild_id)),
+        quick: None,
+    },
+    SetupItem {
+        name: "Invite log",
+        store: "Invites",
+        command: "invites channel",
+        status: |guild_id| ItemStatus::from_channel(invites::invite_log_channel(guild_id)),
+        quick: None,
+    },
+    SetupItem {
+        name: "Automod",
+        store: "Automod",
+        command: "automod add",
+        status: |guild_id| ItemStatus::from_flag(automod::has_blocked_words(guild_id)),
+        quick: None,
+    },
+    SetupItem {
+        name: "Auto-react",
+        store: "Auto-react",
+        command: "autoreact add",
+        status: |guild_id| ItemStatus::from_flag(autoreact::has_rules(guild_id)),
+        quick: None,
+    },
+    SetupItem {
+        name: "React emojis",
+        store: "React emojis",
+        command: "reactmap set",
+        status: |guild_id| ItemStatus::from_flag(!reactmap::guild_overrides(guild_id).is_empty()),
+        quick: None,
+    },
+    SetupItem {
+        name: "Auto-publish",
+        store: "Auto-publish",
+        command: "autopublish on",
+        status: |guild_id| ItemStatus::from_flag(autopublish::has_channels(guild_id)),
+        quick: None,
+    },
+    SetupItem {
+        name: "Streak rewards",
+        store: "Streaks",
+        command: "streak reward",
+        status: |guild_id| ItemStatus::from_flag(streak::has_rewards(guild_id)),
+        quick: None,
+    },
+];
+
+/// Stores that hold no server settings, so they have nothing to check:
+/// they're per user, or filled in by members themselves.
+#[cfg(test)]
+const NOT_ON_CHECKLIST: &[&str] = &[
+    "Parking",
+    "Translation languages",
+    "Fishing",
+    "Color roles",
+    "Poll templates",
+];
+
+/// The command at `path` ("digest set"), looked up by name through the
+/// subcommands.
+fn find_command<'a>(
+    commands: &'a [poise::Command<Data, Error>],
+    path: &str,
+) -> Option<&'a poise::Command<Data, Error>> {
+    let mut names = path.split(' ');
+    let first = names.next()?;
+    let mut command = commands.iter().find(|c| c.name == first)?;
+    for name in names {
+        command = command.subcommands.iter().find(|c| c.name == name)?;
+    }
+    Some(command)
+}
+
+/// One checklist line per item.
+fn checklist_lines(statuses: &[(&SetupItem, ItemStatus)]) -> String {
+    statuses
+        .iter()
+        .map(|(item, status)| match status {
+            ItemStatus::Unset => format!("⬜ **{}** • `-{}`", item.name, item.command),
+            ItemStatus::Set => format!("✅ **{}**", item.name),
+            ItemStatus::PostsIn(channel_id) => {
+                format!("✅ **{}** in <#{}>", item.name, channel_id)
+            }
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// Whether the bot can post in each channel features post in, plus the
+/// channel `-setup` was run in.
+async fn permission_lines(ctx: Context<'_>, statuses: &[(&SetupItem, ItemStatus)]) -> String {
+    let mut channels = vec![ctx.channel_id().get()];
+    for (_, status) in statuses {
+        if let ItemStatus::PostsIn(channel_id) = status {
+            if !channels.contains(channel_id) {
+                channels.push(*channel_id);
+            }
+        }
+    }
+
+    let mut lines = Vec::new();
+    for channel_id in channels {
+        let channel_id = serenity::ChannelId::new(channel_id);
+        match check_channel_perms(ctx, channel_id, POSTING).await {
+            Ok(()) => lines.push(format!("✅ <#{}>", channel_id)),
+            Err(missing) => lines.push(format!(
+                "⚠️ <#{}> needs **{}**",
+                channel_id,
+                missing.missing.get_permission_names().join("**, **")
+            )),
+        }
+    }
+    lines.join("\n")
+}
+
+/// The checklist embed and, while anything is left, the menu to set it up.
+async fn checklist_reply(ctx: Context<'_>, guild_id: u64, menu_id: &str) -> poise::CreateReply {
+    let statuses: Vec<(&SetupItem, ItemStatus)> = SETUP_ITEMS
+        .iter()
+        .map(|item| (item, (item.status)(guild_id)))
+        .collect();
+    let done = statuses
+        .iter()
+        .filter(|(_, status)| *status != ItemStatus::Unset)
+        .count();
+
+    let embed = serenity::CreateEmbed::new()
+        .title("🛠️ Server setup")
+        .description(format!("{} of {} set up.", done, statuses.len()))
+        .color(0x5865F2)
+        .field("Features", checklist_lines(&statuses), false)
+        .field(
+            "Where I post",
+            permission_lines(ctx, &statuses).await,
+            false,
+        )
+        .footer(serenity::CreateEmbedFooter::new(
+            "Pick something from the menu to set it up.",
+        ));
+
+    let options: Vec<serenity::CreateSelectMenuOption> = statuses
+        .iter()
+        .enumerate()
+        .filter(|(_, (_, status))| *status == ItemStatus::Unset)
+        .map(|(i, (item, _))| {
+            let description = match &item.quick {
+                Some(quick) => quick.label.to_string(),
+                None => format!("-{}", item.command),
+            };
+            serenity::CreateSelectMenuOption::new(item.name, i.to_string()).description(description)
+        })
+        .collect();
+
+    let reply = poise::CreateReply::default().embed(embed);
+    if options.is_empty() {
+        return reply.components(vec![]);
+    }
+    let menu = serenity::CreateSelectMenu::new(
+        menu_id,
+        serenity::CreateSelectMenuKind::String { options },
+    )
+    .placeholder("Set something up");
+    reply.components(vec![serenity::CreateActionRow::SelectMenu(menu)])
+}
+
+/// Check what this server has set up, and set up the rest
+#[poise::command(
+    prefix_command,
+    slash_command,
+    guild_only,
+    required_permissions = "MANAGE_GUILD"
+)]
+pub async fn setup(ctx: Context<'_>) -> Result<(), Error> {
+    log::info!("Setup command called by {}", ctx.author().name);
+
+    let Some(guild_id) = ctx.guild_id() else {
+        return Ok(());
+    };
+    let guild_id = guild_id.get();
+    let menu_id = format!("{}_setup", ctx.id());
+
+    let reply = ctx
+        .send(checklist_reply(ctx, guild_id, &menu_id).await)
+        .await?;
+
+    loop {
+        let id = menu_id.clone();
+        let pick = serenity::ComponentInteractionCollector::new(ctx)
+            .author_id(ctx.author().id)
+            .channel_id(ctx.channel_id())
+            .timeout(MENU_TIMEOUT)
+            .filter(move |pick| pick.data.custom_id == id)
+            .await;
+        let Some(pick) = pick else {
+            break;
+        };
+        pick.create_response(ctx, serenity::CreateInteractionResponse::Acknowledge)
+            .await?;
+
+        let serenity::ComponentInteractionDataKind::StringSelect { values } = &pick.data.kind
+        else {
+            continue;
+        };
+        let Some(item) = values
+            .first()
+            .and_then(|value| value.parse::<usize>().ok())
+            .and_then(|i| SETUP_ITEMS.get(i))
+        else {
+            continue;
+        };
+
+        let message = match &item.quick {
+            Some(quick) => {
+                if !guild_config_writable(ctx).await? {
+                    continue;
+                }
+                log::info!(
+                    "Setup: {} set up {} in guild {}",
+                    ctx.author().name,
+                    item.name,
+                    guild_id
+                );
+                (quick.apply)(guild_id, ctx.channel_id().get())
+            }
+            None => match find_command(&ctx.framework().options().commands, item.command) {
+                Some(command) => format!(
+                    "To set up **{}**, run `{}` • more in `-help {}`",
+                    item.name,
+                    usage_line(command),
+                    command.qualified_name
+                ),
+                None => format!("To set up **{}**, run `-{}`.", item.name, item.command),
+            },
+        };
+        ctx.send(
+            poise::CreateReply::default()
+                .content(message)
+                .ephemeral(true),
+        )
+        .await?;
+        reply
+            .edit(ctx, checklist_reply(ctx, guild_id, &menu_id).await)
+            .await?;
+    }
+
+    reply
+        .edit(ctx, poise::CreateReply::default().components(vec![]))
+        .await?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::commands::reload_config::RELOADERS;
+
+    #[test]
+    fn test_every_settings_store_is_on_the_checklist() {
+        for (store, _) in RELOADERS {
+            let listed = SETUP_ITEMS.iter().any(|item| item.store == *store);
+            let skipped = NOT_ON_CHECKLIST.contains(store);
+            assert!(
+                listed != skipped,
+                "{store} should be on the setup checklist or in NOT_ON_CHECKLIST, not both or neither"
+            );
+        }
+        for item in SETUP_ITEMS {
+            assert!(
+                RELOADERS.iter().any(|(store, _)| *store == item.store),
+                "{} names an unknown store {}",
+                item.name,
+                item.store
+            );
+        }
+    }
+
+    #[test]
+    fn test_checklist_commands_are_registered() {
+        let commands = crate::build_framework_options().commands;
+        for item in SETUP_ITEMS {
+            assert!(
+                find_command(&commands, item.command).is_some(),
+                "{} points to -{}, which isn't a command",
+                item.name,
+                item.command
+            );
+        }
+        assert!(find_command(&commands, "digest nope").is_none());
+    }
+
+    #[test]
+    fn test_checklist_lines() {
+        let statuses = vec![
+            (&SETUP_ITEMS[0], ItemStatus::PostsIn(42)),
+            (&SETUP_ITEMS[1], ItemStatus::Set),
+            (&SETUP_ITEMS[2], ItemStatus::Unset),
+        ];
+        assert_eq!(
+            checklist_lines(&statuses),
+            "✅ **Status channel** in <#42>\n\
+             ✅ **Flag translation**\n\
+             ⬜ **Bot admin role** • `-botadmin set`"
+        );
+    }
+}
@@ -0,0 +1,223 @@
+use crate::commands::stats_render::{draw_text, paste_avatar_circle, text_width};
+use crate::utils::avatar::{download_avatar, still_avatar_url};
+use crate::{Context, Error};
+use image::{Rgba, RgbaImage};
+use parking_lot::Mutex;
+use poise::serenity_prelude as serenity;
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+use std::time::{Duration, Instant};
+
+/// Cards for the same two users (in the same order) are reused this long.
+const CARD_CACHE_TTL: Duration = Duration::from_secs(60 * 60);
+
+// Card layout
+const CARD_WIDTH: u32 = 600;
+const CARD_HEIGHT: u32 = 260;
+const AVATAR_SIZE: u32 = 180;
+const HEART_SIZE: u32 = 120;
+
+const BACKGROUND: Rgba<u8> = Rgba([30, 31, 34, 255]);
+const HEART_FILLED: Rgba<u8> = Rgba([237, 66, 69, 255]);
+const HEART_EMPTY: Rgba<u8> = Rgba([56, 58, 64, 255]);
+const TEXT: Rgba<u8> = Rgba([237, 238, 240, 255]);
+
+/// Rendered PNGs by (first, second) user ID, with when they were drawn.
+type CardCache = HashMap<(u64, u64), (Instant, Vec<u8>)>;
+
+lazy_static::lazy_static! {
+    static ref CARD_CACHE: Mutex<CardCache> = Mutex::new(HashMap::new());
+}
+
+/// Check how compatible two users are
+#[poise::command(prefix_command, slash_command)]
+pub async fn ship(
+    ctx: Context<'_>,
+    #[description = "First user"] first: serenity::User,
+    #[description = "Second user"] second: serenity::User,
+) -> Result<(), Error> {
+    log::info!("Ship command called by {}", ctx.author().name);
+
+    let score = ship_score(first.id.get(), second.id.get());
+    let name = ship_name(&first.name, &second.name);
+    let text = format!(
+        "💘 **{}** + **{}** = **{}**\nCompatibility: **{}%** {}",
+        first.name,
+        second.name,
+        name,
+        score,
+        verdict(score)
+    );
+
+    ctx.defer().await?;
+    match ship_card(&first, &second, score).await {
+        Ok(card) => {
+            let attachment = serenity::CreateAttachment::bytes(card, "ship.png");
+            ctx.send(
+                poise::CreateReply::default()
+                    .content(text)
+                    .attachment(attachment),
+            )
+            .await?;
+        }
+        Err(e) => {
+            log::warn!("Failed to make a ship card: {}", e);
+            ctx.say(format!("{}\n-# (no card this time: {})", text, e))
+                .await?;
+        }
+    }
+    Ok(())
+}
+
+/// The card for two users, from the cache or freshly drawn.
+async fn ship_card(
+    first: &serenity::User,
+    second: &serenity::User,
+    score: u8,
+) -> Result<Vec<u8>, Error> {
+    let key = (first.id.get(), second.id.get());
+    let now = Instant::now();
+    {
+        let mut cache = CARD_CACHE.lock();
+        cache.retain(|_, (created, _)| now.duration_since(*created) < CARD_CACHE_TTL);
+        if let Some((_, card)) = cache.get(&key) {
+            return Ok(card.clone());
+        }
+    }
+
+    let left = download_avatar(&still_avatar_url(first)).await?;
+    let right = download_avatar(&still_avatar_url(second)).await?;
+    let card = tokio::task::spawn_blocking(move || {
+        render_card(&left.to_rgba8(), &right.to_rgba8(), score)
+    })
+    .await??;
+
+    CARD_CACHE.lock().insert(key, (now, card.clone()));
+    Ok(card)
+}
+
+/// Compatibility from 0 to 100. Depends only on the two user IDs, not on
+/// their order, so a pair always gets the same score.
+pub fn ship_score(a: u64, b: u64) -> u8 {
+    let (low, high) = if a <= b { (a, b) } else { (b, a) };
+    let digest = Sha256::new()
+        .chain_update(low.to_le_bytes())
+        .chain_update(high.to_le_bytes())
+        .finalize();
+    let mut bytes = [0u8; 8];
+    bytes.copy_from_slice(&digest[..8]);
+    (u64::from_le_bytes(bytes) % 101) as u8
+}
+
+/// The first half of one name and the second half of the other, counted in
+/// characters so non-ASCII names aren't cut mid-character. Odd lengths give
+/// the middle character to the first half.
+pub fn ship_name(first: &str, second: &str) -> String {
+    let first: Vec<char> = first.chars().collect();
+    let second: Vec<char> = second.chars().collect();
+    first[..first.len().div_ceil(2)]
+        .iter()
+        .chain(&second[second.len() / 2..])
+        .collect()
+}
+
+fn verdict(score: u8) -> &'static str {
+    match score {
+        0..=19 => "💔 Not meant to be.",
+        20..=49 => "🤝 Better as friends.",
+        50..=79 => "💕 There's something there!",
+        80..=99 => "💞 A match made in heaven!",
+        _ => "💍 Soulmates!",
+    }
+}
+
+/// Whether a point (in units where the heart spans about -1.2..1.2) is
+/// inside the heart curve (x² + y² - 1)³ - x²y³ ≤ 0.
+fn in_heart(x: f32, y: f32) -> bool {
+    let a = x * x + y * y - 1.0;
+    a * a * a - x * x * y * y * y <= 0.0
+}
+
+/// Both avatars side by side with a heart between them, filled from the
+/// bottom up to the score, and the score under it. Encoded as PNG.
+fn render_card(left: &RgbaImage, right: &RgbaImage, score: u8) -> Result<Vec<u8>, Error> {
+    let mut canvas = RgbaImage::from_pixel(CARD_WIDTH, CARD_HEIGHT, BACKGROUND);
+    let resize = |avatar: &RgbaImage| {
+        image::imageops::resize(
+            avatar,
+            AVATAR_SIZE,
+            AVATAR_SIZE,
+            image::imageops::FilterType::Lanczos3,
+        )
+    };
+    let avatar_y = ((CARD_HEIGHT - AVATAR_SIZE) / 2) as i32;
+    paste_avatar_circle(&mut canvas, &resize(left), 30, avatar_y, AVATAR_SIZE);
+    let right_x = (CARD_WIDTH - AVATAR_SIZE - 30) as i32;
+    paste_avatar_circle(&mut canvas, &resize(right), right_x, avatar_y, AVATAR_SIZE);
+
+    let heart_x = (CARD_WIDTH - HEART_SIZE) / 2;
+    let heart_y = (CARD_HEIGHT - HEART_SIZE) / 2 - 15;
+    // Rows below this one (counted from the top of the heart) are filled
+    let fill_from = HEART_SIZE as f32 * (1.0 - score as f32 / 100.0);
+    for py in 0..HEART_SIZE {
+        for px in 0..HEART_SIZE {
+            let x = (px as f32 + 0.5) / HEART_SIZE as f32 * 2.6 - 1.3;
+            let y = 1.3 - (py as f32 + 0.5) / HEART_SIZE as f32 * 2.6;
+            if !in_heart(x, y) {
+                continue;
+            }
+            let color = if py as f32 >= fill_from {
+                HEART_FILLED
+            } else {
+                HEART_EMPTY
+            };
+            canvas.put_pixel(heart_x + px, heart_y + py, color);
+        }
+    }
+
+    let label = format!("{}%", score);
+    let label_x = (CARD_WIDTH as i32 - text_width(32.0, true, &label)) / 2;
+    let label_y = (heart_y + HEART_SIZE + 8) as i32;
+    draw_text(&mut canvas, TEXT, label_x, label_y, 32.0, true, &label);
+
+    let mut bytes = Vec::new();
+    canvas.write_to(
+        &mut std::io::Cursor::new(&mut bytes),
+        image::ImageOutputFormat::Png,
+    )?;
+    Ok(bytes)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_ship_score_is_stable_and_symmetric() {
+        let score = ship_score(398107630524039170, 1234);
+        assert_eq!(score, ship_score(1234, 398107630524039170));
+        assert_eq!(score, ship_score(398107630524039170, 1234));
+        assert!((0..1000u64).all(|id| ship_score(id, 42) <= 100));
+        // Not everyone gets the same score
+        assert!((0..20u64).any(|id| ship_score(id, 42) != ship_score(id + 1, 42)));
+    }
+
+    #[test]
+    fn test_ship_name() {
+        assert_eq!(ship_name("alice", "bob"), "aliob");
+        assert_eq!(ship_name("bob", "alice"), "boice");
+        assert_eq!(ship_name("a", "b"), "ab");
+        assert_eq!(ship_name("ab", "c"), "ac");
+        assert_eq!(ship_name("Zoë", "Jürgen"), "Zogen");
+        assert_eq!(ship_name("ねこちゃん", "🐶dog🐶"), "ねこちog🐶");
+    }
+
+    #[test]
+    fn test_render_card() {
+        let avatar = RgbaImage::from_pixel(64, 64, Rgba([255, 255, 255, 255]));
+        let card = render_card(&avatar, &avatar, 50).unwrap();
+        let decoded = image::load_from_memory(&card).unwrap();
+        assert_eq!(decoded.width(), CARD_WIDTH);
+        assert_eq!(decoded.height(), CARD_HEIGHT);
+    }
+}
@@ -1,13 +1,47 @@
+use crate::guild_config::get_guild_config;
+use crate::utils::permissions::is_not_found;
+use crate::utils::permissions::{history_error_message, is_missing_permission};
 use crate::{Context, Error};
 use poise::serenity_prelude as serenity;
 use serenity::Mentionable;
+use std::sync::Arc;
 use tokio::time::{sleep, Duration};
 
+/// Pings before giving up in a thread, about 8 minutes.
+const MAX_PINGS: u32 = 50;
+/// Pings before giving up when pinging in the channel itself, where
+/// everyone else has to read along.
+const FALLBACK_MAX_PINGS: u32 = 10;
+
+/// Discord's JSON error code for "Cannot execute action on this channel type".
+const INVALID_CHANNEL_TYPE: isize = 50024;
+
+/// Where the pings go.
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum PingTarget {
+    /// A thread made for it, deleted once they respond.
+    Thread(serenity::ChannelId),
+    /// The channel the command was used in, when no thread could be made.
+    Channel,
+}
+
+/// Why there's no spamping thread.
+#[derive(Debug, PartialEq)]
+enum NoThread {
+    /// Threads can't be made in this kind of channel.
+    Unsupported,
+    /// The bot isn't allowed to make threads here.
+    MissingPermission,
+    Other(String),
+}
+
 /// Spam ping a user in a new thread until they respond
 #[poise::command(prefix_command, slash_command)]
 pub async fn spamping(
     ctx: Context<'_>,
     #[description = "User to spam ping"] user: serenity::User,
+    #[description = "Ping in this channel (at most 10 times) if a thread can't be made"]
+    fallback: Option<bool>,
 ) -> Result<(), Error> {
     log::info!(
         "Spamping command called by {} for user {}",
@@ -16,156 +50,390 @@ pub async fn spamping(
     );
 
     // Check if we're in a guild (server) and not in DMs
-    let Some(_guild_id) = ctx.guild_id() else {
+    let Some(guild_id) = ctx.guild_id() else {
         ctx.say("❌ This command can only be used in servers, not in DMs!")
             .await?;
         return Ok(());
     };
 
+    if let Some(refusal) = refuse_target(ctx, guild_id, &user).await {
+        ctx.say(refusal).await?;
+        return Ok(());
+    }
+
     // Get the channel
     let channel = ctx
         .channel_id()
         .to_channel(&ctx.serenity_context().http)
         .await?;
 
-    if let serenity::Channel::Guild(guild_channel) = channel {
-        // Create a new thread
-        let thread_name = format!("Spamping {} until they respond", user.name);
+    let serenity::Channel::Guild(guild_channel) = channel else {
+        ctx.say("❌ This command can only be used in server channels!")
+            .await?;
+        return Ok(());
+    };
 
-        let thread = match guild_channel
+    // Create a new thread
+    let thread_name = format!("Spamping {} until they respond", user.name);
+    let thread = match thread_kind(guild_channel.kind) {
+        Some(kind) => guild_channel
             .create_thread(
                 &ctx.serenity_context().http,
                 serenity::CreateThread::new(&thread_name)
                     .auto_archive_duration(serenity::AutoArchiveDuration::OneHour)
-                    .kind(serenity::ChannelType::PublicThread),
+                    .kind(kind),
             )
             .await
-        {
-            Ok(thread) => thread,
-            Err(e) => {
-                ctx.say(format!("❌ {e}")).await?;
+            .map_err(|e| no_thread_reason(&e)),
+        None => Err(NoThread::Unsupported),
+    };
+
+    let fallback = fallback.unwrap_or(false);
+    let target = match thread {
+        Ok(thread) => PingTarget::Thread(thread.id),
+        Err(reason) => {
+            log::info!("No spamping thread in {}: {:?}", guild_channel.id, reason);
+            ctx.say(no_thread_message(&reason, guild_channel.id, fallback))
+                .await?;
+            if !fallback {
                 return Ok(());
             }
-        };
-
-        // Send initial message
-        let initial_msg = format!(
-            "**SPAM PING ACTIVATED**\n\n{}, you are being pinged every 10 seconds until you respond!\nType anything in this thread to stop the spam!",
-            user.mention()
-        );
-
-        if let Err(e) = thread.say(&ctx.serenity_context().http, initial_msg).await {
-            ctx.say(format!("❌ {e}")).await?;
-            return Ok(());
+            PingTarget::Channel
         }
+    };
+    let channel_id = ctx.channel_id();
+    let ping_channel = match target {
+        PingTarget::Thread(thread_id) => thread_id,
+        PingTarget::Channel => channel_id,
+    };
+    let max_pings = match target {
+        PingTarget::Thread(_) => MAX_PINGS,
+        PingTarget::Channel => FALLBACK_MAX_PINGS,
+    };
 
-        // Clone necessary data for the spawned task
-        let http = ctx.serenity_context().http.clone();
-        let channel_id = ctx.channel_id();
+    // Send initial message
+    let initial_msg = format!(
+        "**SPAM PING ACTIVATED**\n\n{}, you are being pinged every 10 seconds until you respond!\nType anything in this {} to stop the spam!",
+        user.mention(),
+        if target == PingTarget::Channel { "channel" } else { "thread" }
+    );
 
-        // Store command message info for deletion later
-        let command_msg_id = match ctx {
-            poise::Context::Prefix(prefix_ctx) => Some(prefix_ctx.msg.id),
-            _ => None,
-        };
-        let thread_id = thread.id;
-        let user_id = user.id;
-        let user_mention = user.mention().to_string();
+    if let Err(e) = ping_channel
+        .say(&ctx.serenity_context().http, initial_msg)
+        .await
+    {
+        ctx.say(format!("❌ {e}")).await?;
+        return Ok(());
+    }
 
-        // Spawn the spam ping task
-        tokio::spawn(async move {
-            let mut ping_count = 1;
+    // Store command message info for deletion later
+    let command_msg_id = match ctx {
+        poise::Context::Prefix(prefix_ctx) => Some(prefix_ctx.msg.id),
+        _ => None,
+    };
 
-            loop {
-                sleep(Duration::from_secs(10)).await;
+    // Spawn the spam ping task
+    tokio::spawn(spam_until_response(
+        ctx.serenity_context().http.clone(),
+        guild_id,
+        channel_id,
+        target,
+        user,
+        command_msg_id,
+        max_pings,
+    ));
 
-                // Check if there are new messages from the target user in the thread
-                if let Ok(messages) = thread_id
-                    .messages(&http, serenity::GetMessages::new().limit(50))
-                    .await
-                {
-                    // Check if the user has sent any messages in the last minute
-                    let user_responded = messages.iter().any(|msg| {
-                        msg.author.id == user_id
-                            && msg.timestamp.timestamp() > (chrono::Utc::now().timestamp() - 60)
-                    });
-
-                    if user_responded {
-                        let _ = thread_id
-                            .say(
-                                &http,
-                                format!("{user_mention} responded! Spam ping stopped after {ping_count} pings. Thread will be deleted in 5 seconds..."),
-                            )
-                            .await;
-
-                        // Wait 5 seconds to let people see the final message
-                        sleep(Duration::from_secs(5)).await;
-
-                        // Delete the thread
-                        let _ = thread_id.delete(&http).await;
-
-                        // Clean up leftover messages in the original channel
-                        if let Some(cmd_msg_id) = command_msg_id {
-                            let _ = channel_id.delete_message(&http, cmd_msg_id).await;
-                        }
+    Ok(())
+}
 
-                        // Find and delete the thread creation message
-                        if let Ok(messages) = channel_id
-                            .messages(&http, serenity::GetMessages::new().limit(10))
-                            .await
-                        {
-                            for message in messages {
-                                if message.kind == serenity::MessageType::ThreadCreated
-                                    && message.content.contains(&format!("Spamping {}", user.name))
-                                {
-                                    let _ = message.delete(&http).await;
-                                    break;
-                                }
-                            }
-                        }
-                        break;
-                    }
-                }
+/// Ping `user` every 10 seconds in `target` until they answer there, leave
+/// the server, the thread goes away, or `max_pings` is reached.
+async fn spam_until_response(
+    http: Arc<serenity::Http>,
+    guild_id: serenity::GuildId,
+    channel_id: serenity::ChannelId,
+    target: PingTarget,
+    user: serenity::User,
+    command_msg_id: Option<serenity::MessageId>,
+    max_pings: u32,
+) {
+    let ping_channel = match target {
+        PingTarget::Thread(thread_id) => thread_id,
+        PingTarget::Channel => channel_id,
+    };
+    let user_id = user.id;
+    let user_mention = user.mention().to_string();
+    // Anything they said before the pings started doesn't count as answering
+    let started = chrono::Utc::now().timestamp();
+    let mut ping_count = 1;
 
-                // Send the ping
-                ping_count += 1;
-                let ping_message = match ping_count {
-                    1..=5 => format!("Ping #{ping_count}: {user_mention} - Please respond!"),
-                    6..=10 => format!(
-                        "Ping #{ping_count}: {user_mention} - HELLO?! Are you there?"
-                    ),
-                    11..=15 => format!(
-                        "Ping #{ping_count}: {user_mention} - EMERGENCY PING! RESPOND NOW!"
-                    ),
-                    16..=20 => format!(
-                        "Ping #{ping_count}: {user_mention} - Are you still alive?! RESPOND!"
-                    ),
-                    _ => format!(
-                        "Ping #{ping_count}: {user_mention} - This is getting ridiculous... please respond!"
-                    ),
-                };
+    loop {
+        sleep(Duration::from_secs(10)).await;
 
-                if let Err(e) = thread_id.say(&http, ping_message).await {
-                    let _ = thread_id.say(&http, format!("❌ {e}")).await;
+        // A moderator deleting or archiving the thread ends it
+        if let PingTarget::Thread(thread_id) = target {
+            match thread_id.to_channel(&http).await {
+                Err(e) if is_not_found(&e) => {
+                    let _ = channel_id
+                        .say(
+                            &http,
+                            format!("🧵 Spamping {} stopped: the thread was deleted.", user.name),
+                        )
+                        .await;
                     break;
                 }
+                Ok(serenity::Channel::Guild(thread))
+                    if thread
+                        .thread_metadata
+                        .is_some_and(|metadata| metadata.archived || metadata.locked) =>
+                {
+                    let _ = channel_id
+                        .say(
+                            &http,
+                            format!(
+                                "🧵 Spamping {} stopped: the thread was archived.",
+                                user.name
+                            ),
+                        )
+                        .await;
+                    break;
+                }
+                _ => {}
+            }
+        }
 
-                // Stop after 50 pings (about 8 minutes) to prevent infinite spam
-                if ping_count >= 50 {
-                    let _ = thread_id
+        // Nobody is left to answer if they left the server
+        if let Err(e) = guild_id.member(&http, user_id).await {
+            if is_not_found(&e) {
+                let _ = ping_channel
+                    .say(
+                        &http,
+                        format!("👋 {} left the server, so the spam ping stopped after {ping_count} pings.", user.name),
+                    )
+                    .await;
+                break;
+            }
+        }
+
+        // Check if there are new messages from the target user
+        let recent = ping_channel
+            .messages(&http, serenity::GetMessages::new().limit(50))
+            .await;
+        if let Err(e) = &recent {
+            // Without history access a reply can never be seen, so
+            // stop instead of pinging until the cap
+            if is_missing_permission(e) {
+                let _ = ping_channel
+                    .say(&http, history_error_message(e, ping_channel))
+                    .await;
+                break;
+            }
+        }
+        if let Ok(messages) = recent {
+            let user_responded = messages
+                .iter()
+                .any(|msg| msg.author.id == user_id && msg.timestamp.timestamp() >= started);
+
+            if user_responded {
+                let PingTarget::Thread(thread_id) = target else {
+                    let _ = ping_channel
                         .say(
                             &http,
-                            format!("Spam ping stopped after 50 attempts. {user_mention} might be AFK or ignoring us..."),
+                            format!("{user_mention} responded! Spam ping stopped after {ping_count} pings."),
                         )
                         .await;
                     break;
+                };
+
+                let _ = thread_id
+                    .say(
+                        &http,
+                        format!("{user_mention} responded! Spam ping stopped after {ping_count} pings. Thread will be deleted in 5 seconds..."),
+                    )
+                    .await;
+
+                // Wait 5 seconds to let people see the final message
+                sleep(Duration::from_secs(5)).await;
+
+                // Delete the thread
+                let _ = thread_id.delete(&http).await;
+
+                // Clean up leftover messages in the original channel
+                if let Some(cmd_msg_id) = command_msg_id {
+                    let _ = channel_id.delete_message(&http, cmd_msg_id).await;
+                }
+
+                // Find and delete the thread creation message
+                if let Ok(messages) = channel_id
+                    .messages(&http, serenity::GetMessages::new().limit(10))
+                    .await
+                {
+                    for message in messages {
+                        if message.kind == serenity::MessageType::ThreadCreated
+                            && message.content.contains(&format!("Spamping {}", user.name))
+                        {
+                            let _ = message.delete(&http).await;
+                            break;
+                        }
+                    }
                 }
+                break;
             }
-        });
+        }
+
+        // Send the ping
+        ping_count += 1;
+        let ping_message = match ping_count {
+            1..=5 => format!("Ping #{ping_count}: {user_mention} - Please respond!"),
+            6..=10 => format!("Ping #{ping_count}: {user_mention} - HELLO?! Are you there?"),
+            11..=15 => format!("Ping #{ping_count}: {user_mention} - EMERGENCY PING! RESPOND NOW!"),
+            16..=20 => {
+                format!("Ping #{ping_count}: {user_mention} - Are you still alive?! RESPOND!")
+            }
+            _ => format!(
+                "Ping #{ping_count}: {user_mention} - This is getting ridiculous... please respond!"
+            ),
+        };
+
+        if let Err(e) = ping_channel.say(&http, ping_message).await {
+            let _ = ping_channel.say(&http, format!("❌ {e}")).await;
+            break;
+        }
+
+        // Stop at the cap to prevent infinite spam
+        if ping_count >= max_pings {
+            let _ = ping_channel
+                .say(
+                    &http,
+                    format!("Spam ping stopped after {max_pings} attempts. {user_mention} might be AFK or ignoring us..."),
+                )
+                .await;
+            break;
+        }
+    }
+}
+
+/// The kind of thread to make in a channel of `kind`, if it can have any.
+fn thread_kind(kind: serenity::ChannelType) -> Option<serenity::ChannelType> {
+    match kind {
+        serenity::ChannelType::Text => Some(serenity::ChannelType::PublicThread),
+        serenity::ChannelType::News => Some(serenity::ChannelType::NewsThread),
+        _ => None,
+    }
+}
+
+fn no_thread_reason(error: &serenity::Error) -> NoThread {
+    if is_missing_permission(error) {
+        return NoThread::MissingPermission;
+    }
+    match error {
+        serenity::Error::Http(serenity::HttpError::UnsuccessfulRequest(response))
+            if response.error.code == INVALID_CHANNEL_TYPE =>
+        {
+            NoThread::Unsupported
+        }
+        _ => NoThread::Other(error.to_string()),
+    }
+}
+
+/// What to say when no thread could be made, and what happens instead.
+fn no_thread_message(reason: &NoThread, channel_id: serenity::ChannelId, fallback: bool) -> String {
+    let problem = match reason {
+        NoThread::Unsupported => format!(
+            "❌ <#{}> can't have threads; only text and announcement channels can.",
+            channel_id
+        ),
+        NoThread::MissingPermission => format!(
+            "❌ I need the **Create Public Threads** and **Send Messages in Threads** permissions in <#{}> to start a spamping thread.",
+            channel_id
+        ),
+        NoThread::Other(error) => format!("❌ Couldn't create the spamping thread: {}", error),
+    };
+    if fallback {
+        format!(
+            "{}\nPinging here instead, at most {} times.",
+            problem, FALLBACK_MAX_PINGS
+        )
     } else {
-        ctx.say("❌ This command can only be used in server channels!")
-            .await?;
+        format!(
+            "{}\nUse `-spamping <user> true` to ping in this channel instead (at most {} pings).",
+            problem, FALLBACK_MAX_PINGS
+        )
     }
+}
 
-    Ok(())
+/// Why `user` can't be spampinged here, if they can't: the bot itself, other
+/// bots, people who aren't in the server, bot admins and moderators.
+async fn refuse_target(
+    ctx: Context<'_>,
+    guild_id: serenity::GuildId,
+    user: &serenity::User,
+) -> Option<String> {
+    if user.id == ctx.framework().bot_id {
+        return Some("🤖 Nice try, but I'm not going to spam ping myself!".to_string());
+    }
+    if user.bot {
+        return Some(format!(
+            "🤖 {} is a bot and can't reply to make the pings stop!",
+            user.name
+        ));
+    }
+
+    let Ok(member) = guild_id.member(ctx, user.id).await else {
+        return Some(format!(
+            "❌ {} isn't a member of this server, so they'd never see the pings!",
+            user.name
+        ));
+    };
+
+    let admin_role = get_guild_config(guild_id.get()).and_then(|config| config.bot_admin_role);
+    if admin_role.is_some_and(|role| member.roles.contains(&serenity::RoleId::new(role))) {
+        return Some(format!(
+            "🛡️ {} has the bot-admin role, so they're safe from spam pings.",
+            user.name
+        ));
+    }
+
+    let channel = ctx.guild_channel().await?;
+    let is_moderator = ctx.guild().is_some_and(|guild| {
+        guild
+            .user_permissions_in(&channel, &member)
+            .manage_messages()
+    });
+    if is_moderator {
+        return Some(format!(
+            "🛡️ {} is a moderator here (Manage Messages), so they're safe from spam pings.",
+            user.name
+        ));
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_no_thread_message() {
+        assert_eq!(
+            thread_kind(serenity::ChannelType::News),
+            Some(serenity::ChannelType::NewsThread)
+        );
+        assert_eq!(thread_kind(serenity::ChannelType::Voice), None);
+        assert_eq!(thread_kind(serenity::ChannelType::PublicThread), None);
+
+        let missing = serenity::Error::Model(serenity::ModelError::InvalidPermissions {
+            required: serenity::Permissions::CREATE_PUBLIC_THREADS,
+            present: serenity::Permissions::SEND_MESSAGES,
+        });
+        assert_eq!(no_thread_reason(&missing), NoThread::MissingPermission);
+
+        let channel = serenity::ChannelId::new(42);
+        let message = no_thread_message(&NoThread::MissingPermission, channel, false);
+        assert!(message.contains("**Create Public Threads**"));
+        assert!(message.ends_with("instead (at most 10 pings)."));
+        let message = no_thread_message(&NoThread::Unsupported, channel, true);
+        assert!(message.starts_with("❌ <#42> can't have threads"));
+        assert!(message.ends_with("Pinging here instead, at most 10 times."));
+    }
 }
@@ -1,14 +1,34 @@
 use crate::commands::stats_render::{self, BarEntry, Infographic, Slice, AVATAR_D, SLICE_EMOJI};
+use crate::utils::avatar::{download_avatar, still_avatar_url};
+use crate::utils::limits::EmbedBudget;
+use crate::utils::messages::collect_messages;
+use crate::utils::output::{output_target, ProgressReporter};
+use crate::utils::permissions::{
+    check_channel_perms, history_error_message, is_not_found, READ_HISTORY,
+};
 use crate::{Context, Error};
 use chrono::{DateTime, NaiveDate, Timelike, Utc};
 use chrono_tz::Europe::Copenhagen;
 use image::RgbaImage;
 use poise::serenity_prelude as serenity;
+use std::cmp::Reverse;
 use std::collections::HashMap;
-use tokio::time::{sleep, Duration};
 
 const MAX_BARS: usize = 10; // rows in the "most active users" bar chart
 const MAX_PIE: usize = 8; // named slices before folding into "Others"
+const MAX_MESSAGES: u64 = 10000; // cap on messages fetched per command
+const MAX_ROLE_ROWS: usize = 15; // roles listed in the role breakdown
+
+/// Whose activity `stats` ranks.
+#[derive(poise::ChoiceParameter, Clone, Copy, Debug, PartialEq, Default)]
+pub enum StatsGroup {
+    #[default]
+    #[name = "users"]
+    Users,
+    /// Each author counts towards their highest role.
+    #[name = "roles"]
+    Roles,
+}
 
 /// Shows detailed statistics about message activity in a channel
 ///
@@ -21,6 +41,8 @@ const MAX_PIE: usize = 8; // named slices before folding into "Others"
 /// - `-stats` or `/stats` - Analyze last 1000 messages in current channel
 /// - `-stats 2000` - Analyze last 2000 messages in current channel
 /// - `-stats 500 #general` - Analyze last 500 messages in #general channel
+/// - `-stats 1000 roles` - Rank roles instead of users, counting each author
+///   towards their highest role
 ///
 /// # Rate Limiting
 /// This command implements proper rate limiting (1 second between API requests) to avoid
@@ -37,10 +59,18 @@ pub async fn stats(
     #[description = "Channel to analyze (default: current channel)"] channel: Option<
         serenity::GuildChannel,
     >,
+    #[description = "Rank users (default) or the authors' top roles"] group: Option<StatsGroup>,
 ) -> Result<(), Error> {
     log::info!("Stats command called by {}", ctx.author().name);
 
-    let message_count = count.unwrap_or(1000).min(10000); // Cap at 10k for safety
+    let group = group.unwrap_or_default();
+    if group == StatsGroup::Roles && ctx.guild_id().is_none() {
+        ctx.say("❌ The role breakdown only works in a server.")
+            .await?;
+        return Ok(());
+    }
+
+    let message_count = count.unwrap_or(1000).min(MAX_MESSAGES); // Cap at 10k for safety
     let (target_channel, channel_name) = match &channel {
         Some(ch) => (ch.id, ch.name.clone()),
         None => {
@@ -53,82 +83,62 @@ pub async fn stats(
         }
     };
 
-    // Send initial message
-    let reply = ctx
-        .say(format!(
-            "Analyzing last {message_count} messages in <#{target_channel}>..."
-        ))
-        .await?;
-
-    // Collect messages with rate limiting
-    let mut all_messages = Vec::new();
-    let mut last_message_id = None;
-    let mut collected = 0u64;
-
-    while collected < message_count {
-        let batch_size = (message_count - collected).min(100); // Discord API limit is 100 per request
+    if let Err(missing) = check_channel_perms(ctx, target_channel, READ_HISTORY).await {
+        ctx.say(missing.to_string()).await?;
+        return Ok(());
+    }
 
-        let mut builder = serenity::GetMessages::new().limit(batch_size as u8);
-        if let Some(before_id) = last_message_id {
-            builder = builder.before(before_id);
-        }
+    // Send initial message, in a thread if the guild wants long output there
+    let output = output_target(ctx, &format!("#{channel_name} stats")).await?;
+    let reporter = ProgressReporter::new(
+        ctx,
+        output
+            .say(
+                ctx,
+                format!("Analyzing last {message_count} messages in <#{target_channel}>..."),
+            )
+            .await?,
+    );
 
-        let messages = match target_channel.messages(&ctx.http(), builder).await {
-            Ok(msgs) => msgs,
+    // Collect messages with rate limiting
+    let reporter = &reporter;
+    let progress = |collected: u64| async move {
+        reporter
+            .set(format!("Analyzing messages... {collected}/{message_count}"))
+            .await?;
+        Ok::<(), Error>(())
+    };
+    let all_messages =
+        match collect_messages(ctx.http(), target_channel, message_count, progress).await {
+            Ok(messages) => messages,
             Err(e) => {
-                reply
-                    .edit(
-                        ctx,
-                        poise::CreateReply::default()
-                            .content(format!("Error fetching messages: {e}")),
-                    )
+                reporter
+                    .set(history_error_message(e.as_ref(), target_channel))
                     .await?;
                 return Ok(());
             }
         };
 
-        if messages.is_empty() {
-            break; // No more messages to fetch
-        }
-
-        last_message_id = Some(messages.last().unwrap().id);
-        collected += messages.len() as u64;
-        all_messages.extend(messages);
-
-        // Update progress every 500 messages
-        if collected.is_multiple_of(500) || collected >= message_count {
-            reply
-                .edit(
-                    ctx,
-                    poise::CreateReply::default()
-                        .content(format!("Analyzing messages... {collected}/{message_count}")),
-                )
-                .await?;
-        }
-
-        // Rate limiting - wait 1 second between requests to avoid hitting rate limits
-        sleep(Duration::from_millis(1000)).await;
-    }
-
     if all_messages.is_empty() {
-        reply
-            .edit(
-                ctx,
-                poise::CreateReply::default().content("No messages found in this channel."),
-            )
-            .await?;
+        reporter.set("No messages found in this channel.").await?;
         return Ok(());
     }
 
     // Analyze the messages
     let stats = analyze_messages(&all_messages);
 
-    reply
-        .edit(
-            ctx,
-            poise::CreateReply::default().content("Rendering charts..."),
-        )
-        .await?;
+    if let (StatsGroup::Roles, Some(guild_id)) = (group, ctx.guild_id()) {
+        reporter.set("Looking up the authors' roles...").await?;
+        let buckets = resolve_role_buckets(ctx, guild_id, stats.users.values()).await;
+        let roles = role_breakdown(&stats, &buckets);
+        let embed = create_role_embed(&roles, &channel_name, all_messages.len());
+        reporter
+            .update(poise::CreateReply::default().content("").embed(embed))
+            .await?;
+        return Ok(());
+    }
+
+    reporter.set("Rendering charts...").await?;
 
     // Fetch avatars for the top users so they can appear on the bar chart.
     let top_users = stats.top_users(MAX_BARS);
@@ -169,17 +179,182 @@ pub async fn stats(
         builder = builder.attachment(serenity::CreateAttachment::bytes(png, "stats.png"));
     }
 
-    reply.edit(ctx, builder.embed(embed)).await?;
+    reporter.update(builder.embed(embed)).await?;
+
+    Ok(())
+}
+
+/// Compares message activity between two channels side by side
+///
+/// Analyzes the last N messages in each channel and shows message, word and
+/// user counts, averages, the top user and the peak hour for both.
+///
+/// # Usage
+/// - `-statscompare #general #memes` - Compare the last 1000 messages of each
+/// - `/statscompare first:#general second:#memes count:3000`
+///
+/// # Limitations
+/// - At most 5,000 messages per channel, so both together stay within the
+///   10,000 message cap of `stats`
+/// - You need to be able to read the history of both channels
+#[poise::command(prefix_command, slash_command, guild_only)]
+pub async fn statscompare(
+    ctx: Context<'_>,
+    #[description = "First channel"] first: serenity::GuildChannel,
+    #[description = "Second channel"] second: serenity::GuildChannel,
+    #[description = "Messages to analyze per channel (default: 1000, max: 5000)"] count: Option<
+        u64,
+    >,
+) -> Result<(), Error> {
+    log::info!("Statscompare command called by {}", ctx.author().name);
+
+    if first.id == second.id {
+        ctx.say("❌ Pick two different channels to compare!")
+            .await?;
+        return Ok(());
+    }
+
+    let Some(member) = ctx.author_member().await else {
+        ctx.say("❌ Couldn't look up your server permissions.")
+            .await?;
+        return Ok(());
+    };
+    for channel in [&first, &second] {
+        if !can_read_history(ctx, &member, channel) {
+            ctx.say(format!(
+                "❌ You don't have permission to read the history of <#{}>.",
+                channel.id
+            ))
+            .await?;
+            return Ok(());
+        }
+        if let Err(missing) = check_channel_perms(ctx, channel.id, READ_HISTORY).await {
+            ctx.say(missing.to_string()).await?;
+            return Ok(());
+        }
+    }
+
+    let message_count = count.unwrap_or(1000).min(MAX_MESSAGES / 2);
+
+    let output = output_target(ctx, &format!("#{} vs #{}", first.name, second.name)).await?;
+    let reporter = ProgressReporter::new(
+        ctx,
+        output
+            .say(
+                ctx,
+                format!(
+                    "Comparing last {message_count} messages in <#{}> and <#{}>...",
+                    first.id, second.id
+                ),
+            )
+            .await?,
+    );
+
+    let reporter = &reporter;
+    let mut analyzed = Vec::with_capacity(2);
+    for channel in [&first, &second] {
+        let progress = |collected: u64| async move {
+            reporter
+                .set(format!(
+                    "Analyzing #{}... {collected}/{message_count}",
+                    channel.name
+                ))
+                .await?;
+            Ok::<(), Error>(())
+        };
+        let messages = match collect_messages(ctx.http(), channel.id, message_count, progress).await
+        {
+            Ok(messages) => messages,
+            Err(e) => {
+                reporter
+                    .set(history_error_message(e.as_ref(), channel.id))
+                    .await?;
+                return Ok(());
+            }
+        };
+        analyzed.push((analyze_messages(&messages), messages.len()));
+    }
+
+    let (first_stats, first_count) = &analyzed[0];
+    let (second_stats, second_count) = &analyzed[1];
+
+    let mut budget = EmbedBudget::new();
+    let mut embed = serenity::CreateEmbed::new().color(0x5865F2);
+    embed = budget.title(embed, &format!("#{} vs #{}", first.name, second.name));
+    embed = budget.description(
+        embed,
+        &format!(
+            "Last **{}** messages per channel",
+            commafy(message_count as u32)
+        ),
+    );
+    embed = budget.field(
+        embed,
+        &format!("#{}", first.name),
+        &comparison_column(first_stats, *first_count),
+        true,
+    );
+    embed = budget.field(
+        embed,
+        &format!("#{}", second.name),
+        &comparison_column(second_stats, *second_count),
+        true,
+    );
+
+    reporter
+        .update(poise::CreateReply::default().content("").embed(embed))
+        .await?;
 
     Ok(())
 }
 
+/// Whether `member` can see `channel` and read its message history.
+fn can_read_history(
+    ctx: Context<'_>,
+    member: &serenity::Member,
+    channel: &serenity::GuildChannel,
+) -> bool {
+    ctx.guild().is_some_and(|guild| {
+        let permissions = guild.user_permissions_in(channel, member);
+        permissions.view_channel() && permissions.read_message_history()
+    })
+}
+
+/// One channel's column in the comparison embed.
+fn comparison_column(stats: &MessageStats, analyzed: usize) -> String {
+    if analyzed == 0 {
+        return "No messages found.".to_string();
+    }
+
+    let top_user = match stats.ranked().first() {
+        Some(u) => format!("{} ({})", u.display, commafy(u.messages)),
+        None => "—".to_string(),
+    };
+    let (peak_hour, _) = stats.peak_hour();
+    let span = match (stats.first_ts, stats.last_ts) {
+        (Some(a), Some(b)) => format!("{} days", (b - a).num_days() + 1),
+        _ => "—".to_string(),
+    };
+
+    format!(
+        "💬 **{}** messages\n👥 **{}** users\n📝 **{}** words\n📏 {:.1} words/msg\n👑 {}\n🕐 Peak {:02}:00\n📅 Spans {}",
+        commafy(stats.total_messages),
+        commafy(stats.users.len() as u32),
+        commafy(stats.total_words),
+        stats.avg_words(),
+        top_user,
+        peak_hour,
+        span
+    )
+}
+
 // ---------------------------------------------------------------------------
 // Analysis
 // ---------------------------------------------------------------------------
 
 #[derive(Default, Clone)]
 struct UserAgg {
+    user_id: u64,
     display: String,
     avatar_url: String,
     messages: u32,
@@ -232,6 +407,31 @@ const STOPWORDS: &[&str] = &[
     "because", "their", "these", "those", "http", "https", "www", "com",
 ];
 
+/// Words shorter than this don't make the top words.
+const MIN_TOPIC_WORD_CHARS: usize = 4;
+
+/// The words of a message that count towards its channel's top words:
+/// lowercased with punctuation stripped, leaving out links, mentions, custom
+/// emoji, numbers, short words and [`STOPWORDS`].
+fn topic_words(content: &str) -> impl Iterator<Item = String> + '_ {
+    content
+        .split_whitespace()
+        .filter(|raw| !raw.contains("://") && !raw.starts_with("www."))
+        // <@id>, <@!id>, <@&id>, <#id>, <:name:id> and <a:name:id>
+        .filter(|raw| !(raw.contains('<') && raw.contains('>')))
+        .map(|raw| {
+            raw.to_lowercase()
+                .chars()
+                .filter(|c| c.is_alphanumeric())
+                .collect::<String>()
+        })
+        .filter(|word| {
+            word.chars().count() >= MIN_TOPIC_WORD_CHARS
+                && !word.chars().all(|c| c.is_numeric())
+                && !STOPWORDS.contains(&word.as_str())
+        })
+}
+
 fn analyze_messages(messages: &[serenity::Message]) -> MessageStats {
     let mut users: HashMap<String, UserAgg> = HashMap::new();
     let mut hourly = [0u32; 24];
@@ -267,16 +467,13 @@ fn analyze_messages(messages: &[serenity::Message]) -> MessageStats {
 
         let entry = users.entry(username.clone()).or_default();
         if entry.messages == 0 {
+            entry.user_id = message.author.id.get();
             entry.display = username.split('#').next().unwrap_or(&username).to_string();
-            entry.avatar_url = message
-                .author
-                .avatar_url()
-                .unwrap_or_else(|| message.author.default_avatar_url());
+            entry.avatar_url = still_avatar_url(&message.author);
         }
         entry.messages += 1;
 
-        let words: Vec<&str> = content.split_whitespace().collect();
-        let word_count = words.len() as u32;
+        let word_count = content.split_whitespace().count() as u32;
         entry.words += word_count;
         total_words += word_count;
 
@@ -313,18 +510,8 @@ fn analyze_messages(messages: &[serenity::Message]) -> MessageStats {
         }
 
         // Word frequency (for "top words").
-        for raw in &words {
-            if raw.starts_with("http") {
-                continue;
-            }
-            let w: String = raw
-                .to_lowercase()
-                .chars()
-                .filter(|c| c.is_alphanumeric())
-                .collect();
-            if w.chars().count() >= 4 && !STOPWORDS.contains(&w.as_str()) {
-                *word_freq.entry(w).or_insert(0) += 1;
-            }
+        for word in topic_words(content) {
+            *word_freq.entry(word).or_insert(0) += 1;
         }
     }
 
@@ -458,17 +645,15 @@ fn commafy(n: u32) -> String {
 // ---------------------------------------------------------------------------
 
 async fn fetch_avatars(users: &[UserAgg]) -> Vec<Option<RgbaImage>> {
-    let client = reqwest::Client::new();
     let mut out = Vec::with_capacity(users.len());
     for u in users {
-        out.push(fetch_one_avatar(&client, &u.avatar_url).await);
+        out.push(fetch_one_avatar(&u.avatar_url).await);
     }
     out
 }
 
-async fn fetch_one_avatar(client: &reqwest::Client, url: &str) -> Option<RgbaImage> {
-    let bytes = client.get(url).send().await.ok()?.bytes().await.ok()?;
-    let img = image::load_from_memory(&bytes).ok()?;
+async fn fetch_one_avatar(url: &str) -> Option<RgbaImage> {
+    let img = download_avatar(url).await.ok()?;
     Some(image::imageops::resize(
         &img.to_rgba8(),
         AVATAR_D,
@@ -481,15 +666,172 @@ async fn fetch_one_avatar(client: &reqwest::Client, url: &str) -> Option<RgbaIma
 // Embed
 // ---------------------------------------------------------------------------
 
+/// Where an author's activity counts in the role breakdown.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+enum RoleBucket {
+    Role(serenity::RoleId),
+    /// Still a member, but without any roles.
+    NoRole,
+    /// Left the server since posting.
+    FormerMember,
+    /// Couldn't be looked up for another reason.
+    Unknown,
+}
+
+impl RoleBucket {
+    fn label(&self) -> String {
+        match self {
+            RoleBucket::Role(id) => format!("<@&{}>", id),
+            RoleBucket::NoRole => "No role".to_string(),
+            RoleBucket::FormerMember => "Former members".to_string(),
+            RoleBucket::Unknown => "Couldn't look up".to_string(),
+        }
+    }
+}
+
+#[derive(Debug, PartialEq)]
+struct RoleActivity {
+    bucket: RoleBucket,
+    messages: u32,
+    words: u32,
+    authors: u32,
+}
+
+/// A member's highest role: the one with the highest position, ties going to
+/// the older (lower ID) role like Discord does.
+fn top_role(
+    member_roles: &[serenity::RoleId],
+    positions: &HashMap<serenity::RoleId, u16>,
+) -> Option<serenity::RoleId> {
+    member_roles
+        .iter()
+        .filter_map(|id| Some((*id, *positions.get(id)?)))
+        .max_by_key(|(id, position)| (*position, Reverse(*id)))
+        .map(|(id, _)| id)
+}
+
+/// Look up each author once and find the role their activity counts
+/// towards. Members come from the cache when they're in it.
+async fn resolve_role_buckets<'a>(
+    ctx: Context<'_>,
+    guild_id: serenity::GuildId,
+    users: impl Iterator<Item = &'a UserAgg>,
+) -> HashMap<u64, RoleBucket> {
+    let positions: HashMap<serenity::RoleId, u16> = match ctx.guild() {
+        Some(guild) => guild
+            .roles
+            .iter()
+            .map(|(id, role)| (*id, role.position))
+            .collect(),
+        None => HashMap::new(),
+    };
+
+    let mut buckets = HashMap::new();
+    for user in users {
+        if buckets.contains_key(&user.user_id) {
+            continue;
+        }
+        let bucket = match guild_id
+            .member(ctx, serenity::UserId::new(user.user_id))
+            .await
+        {
+            Ok(member) => {
+                top_role(&member.roles, &positions).map_or(RoleBucket::NoRole, RoleBucket::Role)
+            }
+            Err(e) if is_not_found(&e) => RoleBucket::FormerMember,
+            Err(e) => {
+                log::warn!("Couldn't look up member {}: {}", user.user_id, e);
+                RoleBucket::Unknown
+            }
+        };
+        buckets.insert(user.user_id, bucket);
+    }
+    buckets
+}
+
+/// Message and word counts per role bucket, most messages first.
+fn role_breakdown(stats: &MessageStats, buckets: &HashMap<u64, RoleBucket>) -> Vec<RoleActivity> {
+    let mut totals: HashMap<RoleBucket, RoleActivity> = HashMap::new();
+    for user in stats.users.values() {
+        let bucket = buckets
+            .get(&user.user_id)
+            .copied()
+            .unwrap_or(RoleBucket::Unknown);
+        let entry = totals.entry(bucket).or_insert(RoleActivity {
+            bucket,
+            messages: 0,
+            words: 0,
+            authors: 0,
+        });
+        entry.messages += user.messages;
+        entry.words += user.words;
+        entry.authors += 1;
+    }
+    let mut roles: Vec<RoleActivity> = totals.into_values().collect();
+    roles.sort_by(|a, b| {
+        b.messages
+            .cmp(&a.messages)
+            .then_with(|| b.words.cmp(&a.words))
+            .then_with(|| a.bucket.label().cmp(&b.bucket.label()))
+    });
+    roles
+}
+
+fn create_role_embed(
+    roles: &[RoleActivity],
+    channel_name: &str,
+    analyzed_count: usize,
+) -> serenity::CreateEmbed {
+    let total: u32 = roles.iter().map(|r| r.messages).sum::<u32>().max(1);
+    let mut lines: Vec<String> = roles
+        .iter()
+        .take(MAX_ROLE_ROWS)
+        .enumerate()
+        .map(|(i, role)| {
+            format!(
+                "**{}.** {} — {} msgs ({:.0}%) · {} words · {} author(s)",
+                i + 1,
+                role.bucket.label(),
+                commafy(role.messages),
+                role.messages as f32 / total as f32 * 100.0,
+                commafy(role.words),
+                role.authors
+            )
+        })
+        .collect();
+    if roles.len() > MAX_ROLE_ROWS {
+        lines.push(format!("…and {} more", roles.len() - MAX_ROLE_ROWS));
+    }
+
+    let mut budget = EmbedBudget::new();
+    let embed = serenity::CreateEmbed::new().color(0x5865F2);
+    let embed = budget.title(embed, &format!("#{channel_name} — activity by role"));
+    let embed = budget.description(
+        embed,
+        &format!(
+            "Analysis of **{}** messages, each author counted towards their highest role\n\n{}",
+            commafy(analyzed_count as u32),
+            lines.join("\n")
+        ),
+    );
+    budget.footer(
+        embed,
+        "Bot messages excluded • Roles as they are now, not when the messages were sent",
+    )
+}
+
 fn create_stats_embed(
     stats: &MessageStats,
     channel_name: &str,
     analyzed_count: usize,
     slices: &[PieSlice],
 ) -> serenity::CreateEmbed {
-    let mut embed = serenity::CreateEmbed::new()
-        .title(format!("#{channel_name} — activity report"))
-        .description(format!(
+    let mut budget = EmbedBudget::new();
+    let mut embed = serenity::CreateEmbed::new().color(0x5865F2);
+    embed = budget.title(embed, &format!("#{channel_name} — activity report"));
+    embed = budget.description(
+        embed,
+        &format!(
             "Analysis of **{}** messages\n• {} words · {} characters\n• {:.1} words/msg · {:.1} chars/msg avg",
             commafy(analyzed_count as u32),
             commafy(stats.total_words),
@@ -500,8 +842,8 @@ fn create_stats_embed(
             } else {
                 0.0
             },
-        ))
-        .color(0x5865F2);
+        ),
+    );
 
     // Message-share legend (mirrors the pie chart colours).
     let total_msgs: u32 = slices.iter().map(|s| s.count).sum::<u32>().max(1);
@@ -519,14 +861,20 @@ fn create_stats_embed(
         .collect::<Vec<_>>()
         .join("\n");
     if !legend.is_empty() {
-        embed = embed.field("Message share", legend, true);
+        embed = budget.field(embed, "Message share", &legend, true);
     }
 
     // Word / character leaders.
-    embed = embed.field("Most words", top_list(stats, |u| u.words, "words", 5), true);
-    embed = embed.field(
+    embed = budget.field(
+        embed,
+        "Most words",
+        &top_list(stats, |u| u.words, "words", 5),
+        true,
+    );
+    embed = budget.field(
+        embed,
         "Most characters",
-        top_list(stats, |u| u.chars, "chars", 5),
+        &top_list(stats, |u| u.chars, "chars", 5),
         true,
     );
 
@@ -546,7 +894,7 @@ fn create_stats_embed(
         .collect::<Vec<_>>()
         .join("\n");
     if !yap_text.is_empty() {
-        embed = embed.field("Yappiest (5+ msgs)", yap_text, true);
+        embed = budget.field(embed, "Yappiest (5+ msgs)", &yap_text, true);
     }
 
     // Top words.
@@ -557,7 +905,7 @@ fn create_stats_embed(
             .map(|(w, c)| format!("`{w}` ×{c}"))
             .collect::<Vec<_>>()
             .join("  ");
-        embed = embed.field("Top words", words_text, false);
+        embed = budget.field(embed, "Top words", &words_text, false);
     }
 
     // Awards.
@@ -596,7 +944,7 @@ fn create_stats_embed(
         ));
     }
     if !awards.is_empty() {
-        embed = embed.field("Awards", awards.join("\n"), false);
+        embed = budget.field(embed, "Awards", &awards.join("\n"), false);
     }
 
     // Highlights.
@@ -625,11 +973,12 @@ fn create_stats_embed(
             preview.trim()
         ));
     }
-    embed = embed.field("Highlights", highlights.join("\n"), false);
+    embed = budget.field(embed, "Highlights", &highlights.join("\n"), false);
 
-    embed.footer(serenity::CreateEmbedFooter::new(
+    budget.footer(
+        embed,
         "Bot messages excluded • Times in Europe/Copenhagen • Rate limited for API safety",
-    ))
+    )
 }
 
 /// Build a numbered "top N" list from a per-user metric.
@@ -698,6 +1047,69 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_top_role() {
+        let role = serenity::RoleId::new;
+        let positions = HashMap::from([(role(1), 0), (role(2), 5), (role(3), 5), (role(4), 2)]);
+        assert_eq!(top_role(&[role(1), role(4)], &positions), Some(role(4)));
+        // Same position: the older role wins
+        assert_eq!(
+            top_role(&[role(3), role(2), role(1)], &positions),
+            Some(role(2))
+        );
+        // Roles that aren't in the guild any more are ignored
+        assert_eq!(top_role(&[role(9)], &positions), None);
+        assert_eq!(top_role(&[], &positions), None);
+    }
+
+    #[test]
+    fn test_role_breakdown() {
+        let author = |id: u64, messages: u32| UserAgg {
+            user_id: id,
+            words: messages * 3,
+            ..agg(&format!("user{id}"), messages)
+        };
+        let stats = stats_with(vec![
+            author(1, 10),
+            author(2, 5),
+            author(3, 20),
+            author(4, 1),
+        ]);
+        let mods = RoleBucket::Role(serenity::RoleId::new(100));
+        let buckets = HashMap::from([
+            (1, mods),
+            (2, mods),
+            (3, RoleBucket::FormerMember),
+            (4, RoleBucket::NoRole),
+        ]);
+
+        let roles = role_breakdown(&stats, &buckets);
+        assert_eq!(
+            roles,
+            vec![
+                RoleActivity {
+                    bucket: RoleBucket::FormerMember,
+                    messages: 20,
+                    words: 60,
+                    authors: 1,
+                },
+                RoleActivity {
+                    bucket: mods,
+                    messages: 15,
+                    words: 45,
+                    authors: 2,
+                },
+                RoleActivity {
+                    bucket: RoleBucket::NoRole,
+                    messages: 1,
+                    words: 3,
+                    authors: 1,
+                },
+            ]
+        );
+        assert_eq!(mods.label(), "<@&100>");
+    }
+
     #[test]
     fn test_commafy() {
         assert_eq!(commafy(5), "5");
@@ -753,6 +1165,35 @@ mod tests {
         assert_eq!(tw[1], ("code".to_string(), 3));
     }
 
+    #[test]
+    fn test_topic_words() {
+        let words: Vec<String> = topic_words(
+            "Rust, RUST! rust's borrow-checker is great <@123456> <:ferris:98765> \
+             see https://rust-lang.org (2024) and that's just what THEY said",
+        )
+        .collect();
+        assert_eq!(
+            words,
+            ["rust", "rust", "rusts", "borrowchecker", "great", "said"]
+        );
+        assert_eq!(topic_words("<#42> <@&7> 12345 www.example.com").count(), 0);
+    }
+
+    #[test]
+    fn test_comparison_column() {
+        let mut s = stats_with(vec![agg("alice", 7), agg("bob", 3)]);
+        s.total_words = 40;
+        s.hourly[21] = 6;
+        let column = comparison_column(&s, 10);
+        assert!(column.contains("**10** messages"));
+        assert!(column.contains("**2** users"));
+        assert!(column.contains("4.0 words/msg"));
+        assert!(column.contains("alice (7)"));
+        assert!(column.contains("Peak 21:00"));
+
+        assert_eq!(comparison_column(&s, 0), "No messages found.");
+    }
+
     #[test]
     fn test_analyze_empty() {
         let stats = analyze_messages(&[]);
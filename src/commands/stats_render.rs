@@ -28,11 +28,11 @@ const ROW_H: i32 = 56;
 pub const AVATAR_D: u32 = 40;
 
 // Colours (Discord-ish dark theme).
-const BG: Rgba<u8> = Rgba([30, 31, 34, 255]);
+pub(crate) const BG: Rgba<u8> = Rgba([30, 31, 34, 255]);
 const PANEL: Rgba<u8> = Rgba([43, 45, 49, 255]);
-const TEXT: Rgba<u8> = Rgba([237, 238, 240, 255]);
-const MUTED: Rgba<u8> = Rgba([148, 155, 164, 255]);
-const TRACK: Rgba<u8> = Rgba([56, 58, 64, 255]);
+pub(crate) const TEXT: Rgba<u8> = Rgba([237, 238, 240, 255]);
+pub(crate) const MUTED: Rgba<u8> = Rgba([148, 155, 164, 255]);
+pub(crate) const TRACK: Rgba<u8> = Rgba([56, 58, 64, 255]);
 
 /// Slice/bar palette. Index-aligned with [`SLICE_EMOJI`] so the on-image colours
 /// match the coloured-square emojis used in the embed legend.
@@ -81,7 +81,7 @@ pub struct Infographic<'a> {
 
 // --- small drawing helpers -------------------------------------------------
 
-fn draw_text(
+pub(crate) fn draw_text(
     canvas: &mut RgbaImage,
     color: Rgba<u8>,
     x: i32,
@@ -94,7 +94,7 @@ fn draw_text(
     drawing::draw_text_mut(canvas, color, x, y, Scale::uniform(size), font, s);
 }
 
-fn text_width(size: f32, bold: bool, s: &str) -> i32 {
+pub(crate) fn text_width(size: f32, bold: bool, s: &str) -> i32 {
     let font: &Font = if bold { &FONT_BOLD } else { &FONT };
     drawing::text_size(Scale::uniform(size), font, s).0
 }
@@ -115,7 +115,7 @@ fn truncate(size: f32, bold: bool, s: &str, max_w: i32) -> String {
     "…".to_string()
 }
 
-fn fill_rect(canvas: &mut RgbaImage, color: Rgba<u8>, x: i32, y: i32, w: i32, h: i32) {
+pub(crate) fn fill_rect(canvas: &mut RgbaImage, color: Rgba<u8>, x: i32, y: i32, w: i32, h: i32) {
     if w <= 0 || h <= 0 {
         return;
     }
@@ -123,7 +123,13 @@ fn fill_rect(canvas: &mut RgbaImage, color: Rgba<u8>, x: i32, y: i32, w: i32, h:
 }
 
 /// Paste a pre-sized square avatar as a circle with a subtle ring.
-fn paste_avatar_circle(canvas: &mut RgbaImage, avatar: &RgbaImage, cx: i32, cy: i32, d: u32) {
+pub(crate) fn paste_avatar_circle(
+    canvas: &mut RgbaImage,
+    avatar: &RgbaImage,
+    cx: i32,
+    cy: i32,
+    d: u32,
+) {
     let r = d as f32 / 2.0;
     let (cw, ch) = canvas.dimensions();
     for ay in 0..d {
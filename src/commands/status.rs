@@ -1,3 +1,5 @@
+use crate::message_content::content_available;
+use crate::utils::limits::EmbedBudget;
 use crate::{Context, Error};
 use poise::serenity_prelude as serenity;
 use std::time::{SystemTime, UNIX_EPOCH};
@@ -33,6 +35,7 @@ pub async fn status(ctx: Context<'_>) -> Result<(), Error> {
     let api_start = std::time::Instant::now();
     let _ping_test = ctx.http().get_current_user().await;
     let api_latency = api_start.elapsed().as_millis();
+    crate::commands::ping::record_latency(api_latency as u64);
 
     // Get basic information
     let bot_user_id = ctx.framework().bot_id;
@@ -102,15 +105,17 @@ pub async fn status(ctx: Context<'_>) -> Result<(), Error> {
         .as_secs();
 
     // Create comprehensive status embed
+    let mut budget = EmbedBudget::new();
     let mut embed = serenity::CreateEmbed::new()
-        .title("Bot Status & Diagnostics")
         .color(0x00ff00) // Green for healthy status
         .timestamp(serenity::Timestamp::now());
+    embed = budget.title(embed, "Bot Status & Diagnostics");
 
     // Basic bot information
-    embed = embed.field(
+    embed = budget.field(
+        embed,
         "Bot Information",
-        format!(
+        &format!(
             "**Name:** {}\n**ID:** {}\n**Status:** ✅ Online & Responsive\n**API Latency:** {}ms",
             current_user_name, bot_user_id, api_latency
         ),
@@ -118,9 +123,10 @@ pub async fn status(ctx: Context<'_>) -> Result<(), Error> {
     );
 
     // System information
-    embed = embed.field(
+    embed = budget.field(
+        embed,
         "System Information",
-        format!(
+        &format!(
             "**Process ID:** {}\n**Timestamp:** <t:{}:F>\n**Response Time:** {}ms",
             process_id,
             current_time,
@@ -152,7 +158,7 @@ pub async fn status(ctx: Context<'_>) -> Result<(), Error> {
         )
     };
 
-    embed = embed.field("Discord Statistics", stats_text, true);
+    embed = budget.field(embed, "Discord Statistics", &stats_text, true);
 
     // Current guild information
     if let (Some(name), Some(members), Some(roles)) =
@@ -162,14 +168,23 @@ pub async fn status(ctx: Context<'_>) -> Result<(), Error> {
             "**Guild:** {}\n**Members:** {}\n**Bot Roles:** {}",
             name, members, roles
         );
-        embed = embed.field("Current Guild", guild_text, true);
+        embed = budget.field(embed, "Current Guild", &guild_text, true);
     }
 
-    // Bot capabilities and features
+    // Bot capabilities and features. Without message content only
+    // `@Bot command` works as a prefix.
+    let (prefix_commands, message_content) = if content_available() {
+        ("✅ Prefix Commands (-command)", "✅ Message Content Access")
+    } else {
+        (
+            "⚠️ Prefix Commands (only as @mention)",
+            "❌ Message Content Access (off in the Developer Portal)",
+        )
+    };
     let capabilities = [
-        "✅ Prefix Commands (-command)",
+        prefix_commands,
         "✅ Slash Commands (/command)",
-        "✅ Message Content Access",
+        message_content,
         "✅ Guild Member Intents",
         "✅ Error Handling & Logging",
         "✅ Auto-restart on Updates",
@@ -178,12 +193,12 @@ pub async fn status(ctx: Context<'_>) -> Result<(), Error> {
         "✅ Utility Commands",
     ];
 
-    embed = embed.field("Bot Capabilities", capabilities.join("\n"), false);
+    embed = budget.field(embed, "Bot Capabilities", &capabilities.join("\n"), false);
 
     // Gateway intents information
     let intents_info = "**Configured Intents:**\n• Guild Messages ✅\n• Direct Messages ✅\n• Message Content ✅\n• Guild Members ✅\n\n*If user counts show 0, the bot may need time to cache member data or guilds may have member count disabled.*";
 
-    embed = embed.field("Gateway Intents", intents_info, false);
+    embed = budget.field(embed, "Gateway Intents", intents_info, false);
 
     // Version and build information
     let version_info = format!(
@@ -191,7 +206,7 @@ pub async fn status(ctx: Context<'_>) -> Result<(), Error> {
         env!("CARGO_PKG_VERSION")
     );
 
-    embed = embed.field("Version Information", version_info, true);
+    embed = budget.field(embed, "Version Information", &version_info, true);
 
     // Health check summary
     let health_checks = [
@@ -203,13 +218,14 @@ pub async fn status(ctx: Context<'_>) -> Result<(), Error> {
         "✅ Error Recovery",
     ];
 
-    embed = embed.field("Health Checks", health_checks.join("\n"), true);
+    embed = budget.field(embed, "Health Checks", &health_checks.join("\n"), true);
 
     // Available commands count
     let command_count = ctx.framework().options().commands.len();
-    embed = embed.field(
+    embed = budget.field(
+        embed,
         "Commands Available",
-        format!(
+        &format!(
             "**Total Commands:** {}\n**Type:** `-help` for list",
             command_count
         ),
@@ -217,10 +233,13 @@ pub async fn status(ctx: Context<'_>) -> Result<(), Error> {
     );
 
     // Footer with additional info
-    embed = embed.footer(serenity::CreateEmbedFooter::new(format!(
-        "Requested by {} • Bot is healthy and operational",
-        ctx.author().name
-    )));
+    embed = budget.footer(
+        embed,
+        &format!(
+            "Requested by {} • Bot is healthy and operational",
+            ctx.author().name
+        ),
+    );
 
     // Update the reply with the diagnostic information
     reply
@@ -0,0 +1,101 @@
+use crate::guild_config::{get_guild_config, guild_config_writable, update_guild_config};
+use crate::{Context, Error};
+use poise::serenity_prelude as serenity;
+
+/// Choose where the bot announces it's back online after a restart
+#[poise::command(
+    prefix_command,
+    slash_command,
+    guild_only,
+    required_permissions = "MANAGE_GUILD",
+    subcommands("statuschannel_set", "statuschannel_clear", "statuschannel_show")
+)]
+pub async fn statuschannel(_ctx: Context<'_>) -> Result<(), Error> {
+    Ok(())
+}
+
+/// Post restart announcements in a channel
+#[poise::command(
+    prefix_command,
+    slash_command,
+    guild_only,
+    rename = "set",
+    required_permissions = "MANAGE_GUILD",
+    check = "guild_config_writable"
+)]
+pub async fn statuschannel_set(
+    ctx: Context<'_>,
+    #[description = "Channel for restart announcements"] channel: serenity::GuildChannel,
+) -> Result<(), Error> {
+    log::info!(
+        "Statuschannel set command called by {} for channel {}",
+        ctx.author().name,
+        channel.id
+    );
+
+    let Some(guild_id) = ctx.guild_id() else {
+        return Ok(());
+    };
+    if channel.guild_id != guild_id {
+        ctx.say("❌ That channel isn't in this server!").await?;
+        return Ok(());
+    }
+
+    update_guild_config(guild_id.get(), |config| {
+        config.status_channel = Some(channel.id.get())
+    });
+
+    ctx.say(format!(
+        "✅ I'll say when I'm back online after a restart (update, kys, crash or a normal restart) in <#{}>.",
+        channel.id
+    ))
+    .await?;
+    Ok(())
+}
+
+/// Stop posting restart announcements
+#[poise::command(
+    prefix_command,
+    slash_command,
+    guild_only,
+    rename = "clear",
+    required_permissions = "MANAGE_GUILD",
+    check = "guild_config_writable"
+)]
+pub async fn statuschannel_clear(ctx: Context<'_>) -> Result<(), Error> {
+    log::info!(
+        "Statuschannel clear command called by {}",
+        ctx.author().name
+    );
+
+    let Some(guild_id) = ctx.guild_id() else {
+        return Ok(());
+    };
+    update_guild_config(guild_id.get(), |config| config.status_channel = None);
+
+    ctx.say("✅ Restart announcements are off for this server.")
+        .await?;
+    Ok(())
+}
+
+/// Show the current status channel
+#[poise::command(
+    prefix_command,
+    slash_command,
+    guild_only,
+    rename = "show",
+    required_permissions = "MANAGE_GUILD"
+)]
+pub async fn statuschannel_show(ctx: Context<'_>) -> Result<(), Error> {
+    let Some(guild_id) = ctx.guild_id() else {
+        return Ok(());
+    };
+
+    let message = match get_guild_config(guild_id.get()).and_then(|c| c.status_channel) {
+        Some(channel_id) => format!("📣 Restart announcements go to <#{}>.", channel_id),
+        None => "📣 No status channel is set. Use `-statuschannel set #channel` to pick one."
+            .to_string(),
+    };
+    ctx.say(message).await?;
+    Ok(())
+}
@@ -0,0 +1,891 @@
+use crate::commands::maintenance::Maintenance;
+use crate::config_check::{ConfigProblem, LiveIds};
+use crate::storage;
+use crate::utils::permissions::check_assignable_role;
+use crate::{Context, Error};
+use chrono::{DateTime, Duration, NaiveDate, Utc};
+use chrono_tz::Tz;
+use parking_lot::RwLock;
+use poise::serenity_prelude as serenity;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::sync::Arc;
+use tokio::time::{interval, Duration as TokioDuration};
+
+const STREAKS_FILE: &str = "streaks.json";
+
+/// Days on which anyone in a guild was seen are kept this long, to tell
+/// quiet days apart from days the bot was offline. A gap reaching further
+/// back than this always breaks a streak.
+const ACTIVITY_HISTORY_DAYS: i64 = 60;
+
+const MAX_REWARDS_PER_GUILD: usize = 10;
+
+const MAX_REWARD_DAYS: u32 = 1000;
+
+/// Save after this many streak changes, even if the check interval hasn't passed.
+const FLUSH_EVERY_EVENTS: usize = 100;
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq)]
+struct UserStreak {
+    /// Last local day the user posted.
+    last_active: NaiveDate,
+    /// Consecutive active days up to `last_active`; 0 once it has broken.
+    current: u32,
+    best: u32,
+}
+
+#[derive(Serialize, Deserialize, Default, Debug, Clone)]
+#[serde(default)]
+struct GuildStreaks {
+    /// IANA timezone days roll over in, e.g. `Europe/Copenhagen`. UTC if unset.
+    timezone: Option<String>,
+    /// Streak length in days -> role held while the streak is at least that long.
+    rewards: BTreeMap<u32, u64>,
+    /// Local days on which anyone in the guild posted, for the last
+    /// [`ACTIVITY_HISTORY_DAYS`].
+    active_days: BTreeSet<NaiveDate>,
+    users: HashMap<u64, UserStreak>,
+}
+
+impl GuildStreaks {
+    fn timezone(&self) -> Tz {
+        self.timezone
+            .as_deref()
+            .and_then(|tz| tz.parse().ok())
+            .unwrap_or(Tz::UTC)
+    }
+
+    fn today(&self, now: DateTime<Utc>) -> NaiveDate {
+        now.with_timezone(&self.timezone()).date_naive()
+    }
+
+    /// Whether nobody in the guild posted on any day strictly between `from`
+    /// and `to`. A day without a single recorded message means the bot was
+    /// offline, so it shouldn't cost anyone their streak.
+    fn gap_is_pardoned(&self, from: NaiveDate, to: NaiveDate, today: NaiveDate) -> bool {
+        let first_missed = from + Duration::days(1);
+        if first_missed >= to {
+            return true;
+        }
+        if first_missed <= today - Duration::days(ACTIVITY_HISTORY_DAYS) {
+            return false;
+        }
+        self.active_days.range(first_missed..to).next().is_none()
+    }
+
+    /// The user's streak as of `today`: the stored one while it's still
+    /// alive, 0 once they've missed a day the rest of the guild was active.
+    fn live_streak(&self, user_id: u64, today: NaiveDate) -> u32 {
+        match self.users.get(&user_id) {
+            Some(streak) if self.gap_is_pardoned(streak.last_active, today, today) => {
+                streak.current
+            }
+            _ => 0,
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Default, Clone)]
+struct StreakData {
+    guilds: HashMap<u64, GuildStreaks>,
+}
+
+struct StreakStore {
+    data: StreakData,
+    unsaved_events: usize,
+}
+
+lazy_static::lazy_static! {
+    static ref STREAKS: RwLock<StreakStore> = RwLock::new(StreakStore {
+        data: storage::load(STREAKS_FILE),
+        unsaved_events: 0,
+    });
+}
+
+/// Re-read the streaks from disk. Refused while there's activity that
+/// hasn't been saved yet: it would be lost, or saving it would overwrite
+/// the file being reloaded.
+pub fn reload_streaks() -> Result<(), Error> {
+    let mut store = STREAKS.write();
+    if store.unsaved_events > 0 {
+        return Err(format!(
+            "{} update(s) aren't saved yet, try again after the next save (every 5 minutes)",
+            store.unsaved_events
+        )
+        .into());
+    }
+    store.data = storage::reload(STREAKS_FILE)?;
+    Ok(())
+}
+
+fn save_streaks(store: &mut StreakStore) {
+    match storage::save(STREAKS_FILE, &store.data) {
+        Ok(()) => store.unsaved_events = 0,
+        Err(e) => log::error!("Failed to save streaks: {}", e),
+    }
+}
+
+/// Drop an orphaned guild's streaks and rewards once the retention
+/// window is over.
+pub fn purge_guild(guild_id: u64) {
+    let mut store = STREAKS.write();
+    if store.data.guilds.remove(&guild_id).is_some() {
+        save_streaks(&mut store);
+    }
+}
+
+/// Drop settings that can't work: a timezone that isn't one (days would
+/// roll over in UTC without saying so), and rewards for deleted roles or
+/// streak lengths `-streak reward` wouldn't take.
+fn validate_streaks(data: &mut StreakData, live: &LiveIds) -> Vec<ConfigProblem> {
+    let mut problems = Vec::new();
+    for (&guild_id, guild) in &mut data.guilds {
+        if let Some(timezone) = guild
+            .timezone
+            .take_if(|timezone| timezone.parse::<Tz>().is_err())
+        {
+            problems.push(ConfigProblem::invalid(
+                guild_id,
+                "timezone",
+                format!("{} isn't a timezone", timezone),
+                timezone,
+            ));
+        }
+        guild.rewards.retain(|&days, &mut role_id| {
+            let problem = if days == 0 || days > MAX_REWARD_DAYS {
+                ConfigProblem::invalid(
+                    guild_id,
+                    "rewards",
+                    format!("a reward for {} days, must be 1-{}", days, MAX_REWARD_DAYS),
+                    (days, role_id),
+                )
+            } else if live.role_gone(guild_id, role_id) {
+                ConfigProblem::deleted_role(guild_id, "rewards", role_id)
+            } else {
+                return true;
+            };
+            problems.push(problem);
+            false
+        });
+    }
+    problems
+}
+
+/// Check the streak settings, see [`crate::config_check`].
+pub fn check_streaks(live: &LiveIds, quarantine: bool) -> Vec<ConfigProblem> {
+    let mut store = STREAKS.write();
+    let mut data = store.data.clone();
+    let problems = validate_streaks(&mut data, live);
+    if quarantine && !problems.is_empty() {
+        store.data = data;
+        save_streaks(&mut store);
+    }
+    problems
+}
+
+/// A streak moving from `before` (what the user's roles reflect) to `after`.
+#[derive(Debug, PartialEq)]
+struct StreakChange {
+    before: u32,
+    after: u32,
+}
+
+/// Count the user as active on `today`. Returns `None` if they already were.
+fn record_activity(
+    guild: &mut GuildStreaks,
+    user_id: u64,
+    today: NaiveDate,
+) -> Option<StreakChange> {
+    guild.active_days.insert(today);
+
+    let stored = guild.users.get(&user_id).copied();
+    if stored.is_some_and(|streak| streak.last_active >= today) {
+        return None;
+    }
+    let after = guild.live_streak(user_id, today) + 1;
+    let best = stored.map_or(0, |streak| streak.best).max(after);
+    guild.users.insert(
+        user_id,
+        UserStreak {
+            last_active: today,
+            current: after,
+            best,
+        },
+    );
+    Some(StreakChange {
+        before: stored.map_or(0, |streak| streak.current),
+        after,
+    })
+}
+
+/// Zero out streaks that have broken and return each user with the streak
+/// they lost.
+fn expire_streaks(guild: &mut GuildStreaks, today: NaiveDate) -> Vec<(u64, u32)> {
+    let broken: Vec<(u64, u32)> = guild
+        .users
+        .iter()
+        .filter(|(_, streak)| streak.current > 0)
+        .filter(|(user_id, _)| guild.live_streak(**user_id, today) == 0)
+        .map(|(user_id, streak)| (*user_id, streak.current))
+        .collect();
+    for (user_id, _) in &broken {
+        if let Some(streak) = guild.users.get_mut(user_id) {
+            streak.current = 0;
+        }
+    }
+    broken
+}
+
+/// Forget guild activity older than the history window.
+fn prune_activity(guild: &mut GuildStreaks, today: NaiveDate) {
+    let cutoff = today - Duration::days(ACTIVITY_HISTORY_DAYS);
+    guild.active_days.retain(|day| *day > cutoff);
+}
+
+/// Reward roles to add and remove when a streak goes from `before` to `after`.
+fn role_changes(rewards: &BTreeMap<u32, u64>, before: u32, after: u32) -> (Vec<u64>, Vec<u64>) {
+    let between = |low: u32, high: u32| -> Vec<u64> {
+        if low >= high {
+            return Vec::new();
+        }
+        rewards
+            .range(low + 1..=high)
+            .map(|(_, role)| *role)
+            .collect()
+    };
+    (between(before, after), between(after, before))
+}
+
+async fn apply_role_changes(
+    http: &serenity::Http,
+    guild_id: u64,
+    user_id: u64,
+    add: &[u64],
+    remove: &[u64],
+) {
+    let guild_id = serenity::GuildId::new(guild_id);
+    let user_id = serenity::UserId::new(user_id);
+    for role in add {
+        let role = serenity::RoleId::new(*role);
+        if let Err(e) = http
+            .add_member_role(guild_id, user_id, role, Some("Streak milestone reached"))
+            .await
+        {
+            log::warn!("Failed to add streak role {} to {}: {}", role, user_id, e);
+        }
+    }
+    for role in remove {
+        let role = serenity::RoleId::new(*role);
+        if let Err(e) = http
+            .remove_member_role(guild_id, user_id, role, Some("Streak ended"))
+            .await
+        {
+            log::warn!(
+                "Failed to remove streak role {} from {}: {}",
+                role,
+                user_id,
+                e
+            );
+        }
+    }
+}
+
+/// Count a message towards its author's daily streak, granting any reward
+/// role they just earned. Called for every new message.
+pub async fn record_message(ctx: &serenity::Context, message: &serenity::Message) {
+    if message.author.bot {
+        return;
+    }
+    let Some(guild_id) = message.guild_id else {
+        return;
+    };
+
+    let (add, remove) = {
+        let mut store = STREAKS.write();
+        let guild = store.data.guilds.entry(guild_id.get()).or_default();
+        let today = guild.today(Utc::now());
+        let Some(change) = record_activity(guild, message.author.id.get(), today) else {
+            return;
+        };
+        let changes = role_changes(&guild.rewards, change.before, change.after);
+
+        store.unsaved_events += 1;
+        if store.unsaved_events >= FLUSH_EVERY_EVENTS {
+            save_streaks(&mut store);
+        }
+        changes
+    };
+
+    apply_role_changes(
+        &ctx.http,
+        guild_id.get(),
+        message.author.id.get(),
+        &add,
+        &remove,
+    )
+    .await;
+}
+
+/// Save any streak changes that haven't been written to disk yet.
+pub fn flush_streaks() {
+    let mut store = STREAKS.write();
+    if store.unsaved_events > 0 {
+        save_streaks(&mut store);
+    }
+}
+
+/// Start the background task that ends broken streaks, takes their reward
+/// roles away and saves streaks
+pub fn start_streak_checker(http: Arc<serenity::Http>, maintenance: Arc<Maintenance>) {
+    tokio::spawn(async move {
+        let mut interval = interval(TokioDuration::from_secs(300)); // Check every 5 minutes
+
+        loop {
+            interval.tick().await;
+
+            // Skip the run during maintenance and pick up on the next tick
+            if maintenance.is_enabled() {
+                continue;
+            }
+
+            // (guild, user, roles to remove)
+            let mut removals: Vec<(u64, u64, Vec<u64>)> = Vec::new();
+            {
+                let mut store = STREAKS.write();
+                let now = Utc::now();
+                let mut expired = 0;
+                for (guild_id, guild) in store.data.guilds.iter_mut() {
+                    let today = guild.today(now);
+                    prune_activity(guild, today);
+                    for (user_id, lost) in expire_streaks(guild, today) {
+                        expired += 1;
+                        let (_, remove) = role_changes(&guild.rewards, lost, 0);
+                        if !remove.is_empty() {
+                            removals.push((*guild_id, user_id, remove));
+                        }
+                    }
+                }
+                store.unsaved_events += expired;
+                if store.unsaved_events > 0 {
+                    save_streaks(&mut store);
+                }
+            }
+
+            for (guild_id, user_id, remove) in removals {
+                apply_role_changes(&http, guild_id, user_id, &[], &remove).await;
+            }
+        }
+    });
+}
+
+/// The timezone a guild's days roll over in, set with `-streak timezone`.
+/// UTC if it was never set.
+pub fn guild_timezone(guild_id: u64) -> Tz {
+    STREAKS
+        .read()
+        .data
+        .guilds
+        .get(&guild_id)
+        .map_or(Tz::UTC, GuildStreaks::timezone)
+}
+
+/// Whether a guild has any streak reward roles.
+pub fn has_rewards(guild_id: u64) -> bool {
+    STREAKS
+        .read()
+        .data
+        .guilds
+        .get(&guild_id)
+        .is_some_and(|guild| !guild.rewards.is_empty())
+}
+
+/// Users in a guild whose live streak is at least `days`.
+fn users_with_streak(guild_id: u64, days: u32) -> Vec<u64> {
+    let store = STREAKS.read();
+    let Some(guild) = store.data.guilds.get(&guild_id) else {
+        return Vec::new();
+    };
+    let today = guild.today(Utc::now());
+    guild
+        .users
+        .keys()
+        .filter(|user_id| guild.live_streak(**user_id, today) >= days)
+        .copied()
+        .collect()
+}
+
+/// Show a daily activity streak (yours by default)
+#[poise::command(
+    prefix_command,
+    slash_command,
+    guild_only,
+    subcommands(
+        "streak_show",
+        "streak_reward",
+        "streak_unreward",
+        "streak_rewards",
+        "streak_timezone"
+    )
+)]
+pub async fn streak(
+    ctx: Context<'_>,
+    #[description = "Whose streak to show (default: you)"] user: Option<serenity::User>,
+) -> Result<(), Error> {
+    show_streak(ctx, user).await
+}
+
+/// Show a daily activity streak (yours by default)
+#[poise::command(prefix_command, slash_command, guild_only, rename = "show")]
+pub async fn streak_show(
+    ctx: Context<'_>,
+    #[description = "Whose streak to show (default: you)"] user: Option<serenity::User>,
+) -> Result<(), Error> {
+    show_streak(ctx, user).await
+}
+
+async fn show_streak(ctx: Context<'_>, user: Option<serenity::User>) -> Result<(), Error> {
+    let user = user.unwrap_or_else(|| ctx.author().clone());
+    log::info!(
+        "Streak command called by {} for {}",
+        ctx.author().name,
+        user.name
+    );
+
+    let Some(guild_id) = ctx.guild_id() else {
+        return Ok(());
+    };
+
+    let (stored, current, next_reward) = {
+        let store = STREAKS.read();
+        match store.data.guilds.get(&guild_id.get()) {
+            Some(guild) => {
+                let current = guild.live_streak(user.id.get(), guild.today(Utc::now()));
+                let next_reward = guild
+                    .rewards
+                    .range(current + 1..)
+                    .next()
+                    .map(|(days, role)| (*days, *role));
+                (
+                    guild.users.get(&user.id.get()).copied(),
+                    current,
+                    next_reward,
+                )
+            }
+            None => (None, 0, None),
+        }
+    };
+
+    let Some(stored) = stored else {
+        ctx.say(format!(
+            "🔥 {} hasn't posted here since streaks started being tracked.",
+            user.name
+        ))
+        .await?;
+        return Ok(());
+    };
+
+    let mut embed = serenity::CreateEmbed::new()
+        .title(format!("🔥 {}'s Streak", user.name))
+        .color(0xff8c00)
+        .field("Current", format!("{} day(s)", current), true)
+        .field("Best", format!("{} day(s)", stored.best), true)
+        .field(
+            "Last Active",
+            stored.last_active.format("%Y-%m-%d").to_string(),
+            true,
+        );
+    if let Some((days, role)) = next_reward {
+        embed = embed.field(
+            "Next Reward",
+            format!("<@&{}> in {} more day(s)", role, days - current),
+            false,
+        );
+    }
+    embed = embed.footer(serenity::CreateEmbedFooter::new(
+        "Post at least once a day to keep the streak going",
+    ));
+
+    ctx.send(poise::CreateReply::default().embed(embed)).await?;
+    Ok(())
+}
+
+/// Give members a role once their streak reaches a number of days
+#[poise::command(
+    prefix_command,
+    slash_command,
+    guild_only,
+    rename = "reward",
+    required_permissions = "MANAGE_GUILD",
+    required_bot_permissions = "MANAGE_ROLES"
+)]
+pub async fn streak_reward(
+    ctx: Context<'_>,
+    #[description = "Streak length in days"] days: u32,
+    #[description = "Role to give"] role: serenity::Role,
+) -> Result<(), Error> {
+    log::info!(
+        "Streak reward command called by {} for {} days: {}",
+        ctx.author().name,
+        days,
+        role.name
+    );
+
+    let Some(guild_id) = ctx.guild_id() else {
+        return Ok(());
+    };
+    if days == 0 || days > MAX_REWARD_DAYS {
+        ctx.say(format!(
+            "❌ Days must be between 1 and {}.",
+            MAX_REWARD_DAYS
+        ))
+        .await?;
+        return Ok(());
+    }
+    if role.guild_id != guild_id || role.id.get() == guild_id.get() || role.managed {
+        ctx.say("❌ That role can't be given out as a streak reward.")
+            .await?;
+        return Ok(());
+    }
+    if let Err(refusal) = check_assignable_role(ctx, &role).await {
+        ctx.say(refusal).await?;
+        return Ok(());
+    }
+
+    // `None` if the guild is out of reward slots
+    let outcome = {
+        let mut store = STREAKS.write();
+        let guild = store.data.guilds.entry(guild_id.get()).or_default();
+        if !guild.rewards.contains_key(&days) && guild.rewards.len() >= MAX_REWARDS_PER_GUILD {
+            None
+        } else {
+            let previous_role = guild.rewards.insert(days, role.id.get());
+            save_streaks(&mut store);
+            Some(previous_role)
+        }
+    };
+    let Some(previous_role) = outcome else {
+        ctx.say(format!(
+            "❌ This server already has {} streak rewards. Remove one with `-streak unreward <days>` first.",
+            MAX_REWARDS_PER_GUILD
+        ))
+        .await?;
+        return Ok(());
+    };
+
+    // Bring members who already qualify up to date
+    let http = ctx.http();
+    for user_id in users_with_streak(guild_id.get(), days) {
+        let remove: Vec<u64> = previous_role.into_iter().collect();
+        apply_role_changes(http, guild_id.get(), user_id, &[role.id.get()], &remove).await;
+    }
+
+    ctx.say(format!(
+        "✅ Members with a streak of {} day(s) or more now get **{}**. They lose it when their streak ends.",
+        days, role.name
+    ))
+    .await?;
+    Ok(())
+}
+
+/// Stop giving out a streak reward role
+#[poise::command(
+    prefix_command,
+    slash_command,
+    guild_only,
+    rename = "unreward",
+    required_permissions = "MANAGE_GUILD",
+    required_bot_permissions = "MANAGE_ROLES"
+)]
+pub async fn streak_unreward(
+    ctx: Context<'_>,
+    #[description = "Streak length of the reward to remove"] days: u32,
+) -> Result<(), Error> {
+    log::info!(
+        "Streak unreward command called by {} for {} days",
+        ctx.author().name,
+        days
+    );
+
+    let Some(guild_id) = ctx.guild_id() else {
+        return Ok(());
+    };
+    let removed = {
+        let mut store = STREAKS.write();
+        let removed = store
+            .data
+            .guilds
+            .get_mut(&guild_id.get())
+            .and_then(|guild| guild.rewards.remove(&days));
+        if removed.is_some() {
+            save_streaks(&mut store);
+        }
+        removed
+    };
+
+    let Some(role) = removed else {
+        ctx.say(format!("❌ There's no reward for a {}-day streak.", days))
+            .await?;
+        return Ok(());
+    };
+
+    // Take it back from everyone who was given it
+    let http = ctx.http();
+    for user_id in users_with_streak(guild_id.get(), days) {
+        apply_role_changes(http, guild_id.get(), user_id, &[], &[role]).await;
+    }
+
+    ctx.say(format!("✅ Removed the {}-day streak reward.", days))
+        .await?;
+    Ok(())
+}
+
+/// List streak reward roles and the timezone days roll over in
+#[poise::command(prefix_command, slash_command, guild_only, rename = "rewards")]
+pub async fn streak_rewards(ctx: Context<'_>) -> Result<(), Error> {
+    let Some(guild_id) = ctx.guild_id() else {
+        return Ok(());
+    };
+
+    let (rewards, timezone) = {
+        let store = STREAKS.read();
+        match store.data.guilds.get(&guild_id.get()) {
+            Some(guild) => (
+                guild
+                    .rewards
+                    .iter()
+                    .map(|(days, role)| format!("**{} day(s)** — <@&{}>", days, role))
+                    .collect::<Vec<_>>(),
+                guild.timezone(),
+            ),
+            None => (Vec::new(), Tz::UTC),
+        }
+    };
+
+    let rewards = if rewards.is_empty() {
+        "No reward roles yet. Add one with `-streak reward <days> <role>`.".to_string()
+    } else {
+        rewards.join("\n")
+    };
+    let embed = serenity::CreateEmbed::new()
+        .title("🔥 Streak Rewards")
+        .color(0xff8c00)
+        .description(rewards)
+        .footer(serenity::CreateEmbedFooter::new(format!(
+            "Days roll over at midnight {}",
+            timezone.name()
+        )));
+    ctx.send(poise::CreateReply::default().embed(embed)).await?;
+    Ok(())
+}
+
+/// Set the timezone streak days roll over in
+#[poise::command(
+    prefix_command,
+    slash_command,
+    guild_only,
+    rename = "timezone",
+    required_permissions = "MANAGE_GUILD"
+)]
+pub async fn streak_timezone(
+    ctx: Context<'_>,
+    #[description = "IANA timezone, e.g. Europe/Copenhagen or America/New_York"] timezone: String,
+) -> Result<(), Error> {
+    log::info!(
+        "Streak timezone command called by {} with: {}",
+        ctx.author().name,
+        timezone
+    );
+
+    let Ok(tz) = timezone.trim().parse::<Tz>() else {
+        ctx.say("❌ Unknown timezone. Use a name like `Europe/Copenhagen`, `America/New_York` or `UTC`.")
+            .await?;
+        return Ok(());
+    };
+    let Some(guild_id) = ctx.guild_id() else {
+        return Ok(());
+    };
+
+    {
+        let mut store = STREAKS.write();
+        store
+            .data
+            .guilds
+            .entry(guild_id.get())
+            .or_default()
+            .timezone = Some(tz.name().to_string());
+        save_streaks(&mut store);
+    }
+
+    ctx.say(format!(
+        "✅ Streak days now roll over at midnight {}.",
+        tz.name()
+    ))
+    .await?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn day(n: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(2026, 3, 1).unwrap() + Duration::days(n as i64 - 1)
+    }
+
+    #[test]
+    fn test_validate_streaks() {
+        let mut data: StreakData =
+            serde_json::from_str(include_str!("../../tests/fixtures/config/streaks.json")).unwrap();
+        let live = LiveIds::default().with_guild(1, &[], &[20, 21]);
+
+        let mut problems = validate_streaks(&mut data, &live);
+        problems.sort_by_key(|problem| problem.problem.clone());
+        let found: Vec<(&str, &str)> = problems
+            .iter()
+            .map(|problem| (problem.field.as_str(), problem.problem.as_str()))
+            .collect();
+        assert_eq!(
+            found,
+            [
+                ("timezone", "Mars/Olympus_Mons isn't a timezone"),
+                ("rewards", "a reward for 0 days, must be 1-1000"),
+                ("rewards", "role 22 was deleted"),
+            ]
+        );
+        let guild = &data.guilds[&1];
+        assert_eq!(guild.timezone, None);
+        assert_eq!(guild.rewards, BTreeMap::from([(7, 20), (30, 21)]));
+        assert_eq!(guild.users.len(), 1);
+        assert!(validate_streaks(&mut data, &live).is_empty());
+    }
+
+    /// Someone other than the users under test posts, so the day isn't pardoned.
+    fn someone_posts(guild: &mut GuildStreaks, on: NaiveDate) {
+        record_activity(guild, 999, on);
+    }
+
+    #[test]
+    fn test_consecutive_days_build_a_streak() {
+        let mut guild = GuildStreaks::default();
+        assert_eq!(
+            record_activity(&mut guild, 1, day(1)),
+            Some(StreakChange {
+                before: 0,
+                after: 1
+            })
+        );
+        // Only the first message of the day counts
+        assert_eq!(record_activity(&mut guild, 1, day(1)), None);
+        assert_eq!(
+            record_activity(&mut guild, 1, day(2)),
+            Some(StreakChange {
+                before: 1,
+                after: 2
+            })
+        );
+        record_activity(&mut guild, 1, day(3));
+        assert_eq!(guild.users[&1].current, 3);
+        assert_eq!(guild.users[&1].best, 3);
+    }
+
+    #[test]
+    fn test_missed_day_breaks_streak() {
+        let mut guild = GuildStreaks::default();
+        record_activity(&mut guild, 1, day(1));
+        record_activity(&mut guild, 1, day(2));
+        someone_posts(&mut guild, day(3));
+
+        // Still alive on day 3 until the day is over
+        assert_eq!(guild.live_streak(1, day(3)), 2);
+        assert_eq!(guild.live_streak(1, day(4)), 0);
+
+        assert_eq!(
+            record_activity(&mut guild, 1, day(4)),
+            Some(StreakChange {
+                before: 2,
+                after: 1
+            })
+        );
+        assert_eq!(guild.users[&1].best, 2);
+    }
+
+    #[test]
+    fn test_offline_day_is_pardoned() {
+        let mut guild = GuildStreaks::default();
+        record_activity(&mut guild, 1, day(1));
+        record_activity(&mut guild, 1, day(2));
+        // Nobody at all posted on days 3 and 4: the bot was down
+
+        assert_eq!(guild.live_streak(1, day(5)), 2);
+        assert_eq!(
+            record_activity(&mut guild, 1, day(5)),
+            Some(StreakChange {
+                before: 2,
+                after: 3
+            })
+        );
+    }
+
+    #[test]
+    fn test_gap_beyond_history_is_not_pardoned() {
+        let mut guild = GuildStreaks::default();
+        record_activity(&mut guild, 1, day(1));
+        let much_later = day(1) + Duration::days(ACTIVITY_HISTORY_DAYS + 5);
+        assert_eq!(guild.live_streak(1, much_later), 0);
+    }
+
+    #[test]
+    fn test_expire_streaks() {
+        let mut guild = GuildStreaks::default();
+        record_activity(&mut guild, 1, day(1));
+        record_activity(&mut guild, 2, day(1));
+        record_activity(&mut guild, 2, day(2));
+
+        assert!(expire_streaks(&mut guild, day(2)).is_empty());
+        assert_eq!(expire_streaks(&mut guild, day(3)), vec![(1, 1)]);
+        assert_eq!(guild.users[&1].current, 0);
+        // Already expired streaks aren't reported again
+        assert!(expire_streaks(&mut guild, day(3)).is_empty());
+
+        assert_eq!(
+            record_activity(&mut guild, 1, day(3)),
+            Some(StreakChange {
+                before: 0,
+                after: 1
+            })
+        );
+    }
+
+    #[test]
+    fn test_role_changes() {
+        let rewards: BTreeMap<u32, u64> = [(3, 30), (7, 70), (30, 300)].into();
+        assert_eq!(role_changes(&rewards, 2, 3), (vec![30], vec![]));
+        assert_eq!(role_changes(&rewards, 3, 4), (vec![], vec![]));
+        assert_eq!(role_changes(&rewards, 0, 10), (vec![30, 70], vec![]));
+        assert_eq!(role_changes(&rewards, 8, 1), (vec![], vec![30, 70]));
+    }
+
+    #[test]
+    fn test_timezone_decides_the_day() {
+        let mut guild = GuildStreaks::default();
+        let now = DateTime::parse_from_rfc3339("2026-03-01T23:30:00Z")
+            .unwrap()
+            .with_timezone(&Utc);
+        assert_eq!(guild.today(now), day(1));
+        guild.timezone = Some("Europe/Copenhagen".to_string());
+        assert_eq!(guild.today(now), day(2));
+    }
+
+    #[test]
+    fn test_prune_activity() {
+        let mut guild = GuildStreaks::default();
+        someone_posts(&mut guild, day(1));
+        someone_posts(&mut guild, day(2));
+        prune_activity(&mut guild, day(1) + Duration::days(ACTIVITY_HISTORY_DAYS));
+        assert_eq!(
+            guild.active_days.iter().copied().collect::<Vec<_>>(),
+            vec![day(2)]
+        );
+    }
+}
@@ -1,15 +1,33 @@
+use crate::commands::general::{
+    is_game_idle, ActiveGame, GameKind, GamePlace, GameSession, GameSessions, GameSummary,
+    GAME_IDLE_TIMEOUT,
+};
 use crate::{Context, Error};
+use chrono::{DateTime, Utc};
 use poise::serenity_prelude::{self as serenity, Mentionable};
 use std::collections::HashMap;
 use std::fmt;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::Instant;
 use tokio::sync::RwLock;
 
-// Global storage for active games
 lazy_static::lazy_static! {
-    static ref ACTIVE_GAMES: RwLock<HashMap<u64, TicTacToeGame>> = RwLock::new(HashMap::new());
+    /// Source of game IDs for button custom IDs, so buttons on a finished
+    /// game's message can't affect a newer game between the same players.
+    /// Seeded from the start time so IDs don't repeat after a restart.
+    static ref NEXT_GAME_ID: AtomicU64 = AtomicU64::new(first_game_id(Utc::now()));
 }
 
-#[derive(Clone, Copy, PartialEq)]
+/// First game ID for a bot started at `now`: its Unix time in milliseconds,
+/// which is past any ID handed out before the restart.
+fn first_game_id(now: DateTime<Utc>) -> u64 {
+    now.timestamp_millis().max(1) as u64
+}
+
+/// Custom ID prefix for board buttons: `ttt:<game id>:<position>`.
+pub const BUTTON_PREFIX: &str = "ttt:";
+
+#[derive(Clone, Copy, PartialEq, Debug)]
 enum Player {
     X,
     O,
@@ -39,8 +57,25 @@ impl fmt::Display for Cell {
     }
 }
 
+/// How a turn left the game.
+#[derive(Debug, PartialEq)]
+enum Outcome {
+    Ongoing,
+    Won(Player),
+    Tie,
+}
+
+/// Result of a player's move, including the AI's reply in AI games.
+#[derive(Debug, PartialEq)]
+struct TurnResult {
+    outcome: Outcome,
+    ai_move: Option<usize>,
+}
+
+/// A Tic-Tac-Toe game, stored under both players in [`GameSessions`].
 #[derive(Clone)]
-struct TicTacToeGame {
+pub(crate) struct TicTacToeGame {
+    game_id: u64,
     board: [[Cell; 3]; 3],
     current_player: Player,
     player_x_id: u64,
@@ -48,6 +83,9 @@ struct TicTacToeGame {
     is_ai_game: bool,
     message_id: Option<u64>, // For editing the game message
     channel_id: u64,
+    guild_id: Option<u64>,
+    started: DateTime<Utc>,
+    last_activity: Instant,
 }
 
 impl TicTacToeGame {
@@ -56,6 +94,7 @@ impl TicTacToeGame {
         &self,
         ctx: &Context<'_>,
         content: String,
+        components: Vec<serenity::CreateActionRow>,
     ) -> Result<u64, Error> {
         // Delete the previous message if it exists
         if let Some(msg_id) = self.message_id {
@@ -69,12 +108,19 @@ impl TicTacToeGame {
         }
 
         // Send the new message and return its ID
-        let reply = ctx.say(content).await?;
+        let reply = ctx
+            .send(
+                poise::CreateReply::default()
+                    .content(content)
+                    .components(components),
+            )
+            .await?;
         Ok(reply.message().await?.id.get())
     }
 
     fn new_two_player(player_x_id: u64, player_o_id: u64, channel_id: u64) -> Self {
         TicTacToeGame {
+            game_id: NEXT_GAME_ID.fetch_add(1, Ordering::Relaxed),
             board: [[Cell::Empty; 3]; 3],
             current_player: Player::X,
             player_x_id,
@@ -82,11 +128,15 @@ impl TicTacToeGame {
             is_ai_game: false,
             message_id: None,
             channel_id,
+            guild_id: None,
+            started: Utc::now(),
+            last_activity: Instant::now(),
         }
     }
 
     fn new_vs_ai(player_x_id: u64, channel_id: u64) -> Self {
         TicTacToeGame {
+            game_id: NEXT_GAME_ID.fetch_add(1, Ordering::Relaxed),
             board: [[Cell::Empty; 3]; 3],
             current_player: Player::X,
             player_x_id,
@@ -94,6 +144,9 @@ impl TicTacToeGame {
             is_ai_game: true,
             message_id: None,
             channel_id,
+            guild_id: None,
+            started: Utc::now(),
+            last_activity: Instant::now(),
         }
     }
 
@@ -134,6 +187,7 @@ impl TicTacToeGame {
         }
 
         self.board[row][col] = Cell::Occupied(self.current_player);
+        self.last_activity = Instant::now();
         Ok(())
     }
 
@@ -264,10 +318,299 @@ impl TicTacToeGame {
             Player::O => self.player_o_id,
         }
     }
+
+    fn has_player(&self, user_id: u64) -> bool {
+        self.player_x_id == user_id || self.player_o_id == Some(user_id)
+    }
+
+    /// Play the current player's move and, in AI games, the AI's reply.
+    fn play_turn(&mut self, position: usize) -> Result<TurnResult, String> {
+        self.make_move(position)?;
+
+        if let Some(winner) = self.check_winner() {
+            return Ok(TurnResult {
+                outcome: Outcome::Won(winner),
+                ai_move: None,
+            });
+        } else if self.is_board_full() {
+            return Ok(TurnResult {
+                outcome: Outcome::Tie,
+                ai_move: None,
+            });
+        }
+
+        self.switch_player();
+
+        if !(self.is_ai_game && self.current_player == Player::O) {
+            return Ok(TurnResult {
+                outcome: Outcome::Ongoing,
+                ai_move: None,
+            });
+        }
+
+        let ai_move = self.get_ai_move();
+        if let Some(pos) = ai_move {
+            self.make_move(pos)?;
+        }
+
+        let outcome = if let Some(winner) = self.check_winner() {
+            Outcome::Won(winner)
+        } else if self.is_board_full() {
+            Outcome::Tie
+        } else {
+            self.switch_player();
+            Outcome::Ongoing
+        };
+
+        Ok(TurnResult { outcome, ai_move })
+    }
+
+    /// Message text describing the board after a turn.
+    fn turn_message(&self, result: &TurnResult) -> String {
+        let ai_line = result
+            .ai_move
+            .map(|pos| format!("AI played position **{}**\n", pos))
+            .unwrap_or_default();
+
+        match result.outcome {
+            Outcome::Won(winner) => {
+                let winner_text = match (winner, self.player_o_id) {
+                    (Player::O, None) => "🤖 **AI wins!**".to_string(),
+                    (Player::O, Some(id)) => format!("🎉 **<@{}> wins!**", id),
+                    (Player::X, _) => format!("🎉 **<@{}> wins!**", self.player_x_id),
+                };
+                format!(
+                    "{}\n{}{}\n\nGame over! Use `/tictactoe` to start a new game.",
+                    winner_text,
+                    ai_line,
+                    self.display_board()
+                )
+            }
+            Outcome::Tie => format!(
+                "🤝 **It's a tie!**\n{}{}\n\nGame over! Use `/tictactoe` to start a new game.",
+                ai_line,
+                self.display_board()
+            ),
+            Outcome::Ongoing if self.is_ai_game => format!(
+                "🤖 {}{}\n\nYour turn! <@{}> Press a button or use `/move <position>`",
+                ai_line,
+                self.display_board(),
+                self.player_x_id
+            ),
+            Outcome::Ongoing => format!(
+                "{}\n\nCurrent turn: {} <@{}>",
+                self.display_board(),
+                self.current_player,
+                self.get_current_player_id().unwrap_or_default()
+            ),
+        }
+    }
+
+    /// The 3x3 grid of buttons for the game message. Taken cells are
+    /// disabled, and every cell is once the game is over.
+    fn board_buttons(&self, game_over: bool) -> Vec<serenity::CreateActionRow> {
+        self.board
+            .iter()
+            .enumerate()
+            .map(|(row_idx, row)| {
+                let buttons = row
+                    .iter()
+                    .enumerate()
+                    .map(|(col_idx, cell)| {
+                        let position = row_idx * 3 + col_idx + 1;
+                        let button = serenity::CreateButton::new(format!(
+                            "{}{}:{}",
+                            BUTTON_PREFIX, self.game_id, position
+                        ));
+                        match cell {
+                            Cell::Empty => button
+                                .label(position.to_string())
+                                .style(serenity::ButtonStyle::Secondary)
+                                .disabled(game_over),
+                            Cell::Occupied(player) => button
+                                .label(player.to_string())
+                                .style(match player {
+                                    Player::X => serenity::ButtonStyle::Danger,
+                                    Player::O => serenity::ButtonStyle::Primary,
+                                })
+                                .disabled(true),
+                        }
+                    })
+                    .collect();
+                serenity::CreateActionRow::Buttons(buttons)
+            })
+            .collect()
+    }
+}
+
+/// Store a game under both players' IDs.
+fn store_game(games: &mut HashMap<u64, TicTacToeGame>, game: TicTacToeGame) {
+    if let Some(opponent_id) = game.player_o_id {
+        games.insert(opponent_id, game.clone());
+    }
+    games.insert(game.player_x_id, game);
+}
+
+/// Remove a game for both players.
+fn remove_game(games: &mut HashMap<u64, TicTacToeGame>, game: &TicTacToeGame) {
+    games.remove(&game.player_x_id);
+    if let Some(opponent_id) = game.player_o_id {
+        games.remove(&opponent_id);
+    }
+}
+
+/// Drop games nobody has moved in for a while, letting the channel know when a
+/// two-player game expires. Returns how many games were removed.
+pub(crate) async fn expire_idle_games(
+    games: &RwLock<HashMap<u64, TicTacToeGame>>,
+    http: &serenity::Http,
+) -> usize {
+    let expired = {
+        let mut games = games.write().await;
+        remove_idle_games(&mut games, Instant::now())
+    };
+
+    for game in &expired {
+        if let Some(player_o_id) = game.player_o_id {
+            let message = format!(
+                "⌛ The Tic-Tac-Toe game between <@{}> and <@{}> expired after {} minutes without a move.",
+                game.player_x_id,
+                player_o_id,
+                GAME_IDLE_TIMEOUT.as_secs() / 60
+            );
+            if let Err(e) = serenity::ChannelId::new(game.channel_id)
+                .say(http, message)
+                .await
+            {
+                log::warn!("Failed to send game expiry notice: {}", e);
+            }
+        }
+    }
+
+    expired.len()
+}
+
+/// Two-player games are stored under both players' IDs; both entries are
+/// removed but each game is only returned once (keyed by player X).
+fn remove_idle_games(games: &mut HashMap<u64, TicTacToeGame>, now: Instant) -> Vec<TicTacToeGame> {
+    let idle_ids: Vec<u64> = games
+        .iter()
+        .filter(|(_, game)| is_game_idle(game.last_activity, now))
+        .map(|(id, _)| *id)
+        .collect();
+
+    let mut expired = Vec::new();
+    for id in idle_ids {
+        if let Some(game) = games.remove(&id) {
+            if game.player_x_id == id {
+                expired.push(game);
+            }
+        }
+    }
+    expired
+}
+
+/// Read-only overview of active Tic-Tac-Toe games
+///
+/// Two-player games are stored under both players' IDs, so they are keyed by
+/// player X here to count each game once.
+pub(crate) async fn active_games_summary(
+    games: &RwLock<HashMap<u64, TicTacToeGame>>,
+) -> GameSummary {
+    let games = games.read().await;
+    let unique: HashMap<u64, &TicTacToeGame> = games
+        .values()
+        .map(|game| (game.player_x_id, game))
+        .collect();
+
+    let mut details: Vec<String> = unique
+        .values()
+        .map(|game| {
+            let opponent = match game.player_o_id {
+                Some(id) => format!("<@{}>", id),
+                None => "AI".to_string(),
+            };
+            let moves = game
+                .board
+                .iter()
+                .flatten()
+                .filter(|cell| **cell != Cell::Empty)
+                .count();
+            format!(
+                "<@{}> vs {} in <#{}> — {} moves",
+                game.player_x_id, opponent, game.channel_id, moves
+            )
+        })
+        .collect();
+    details.sort();
+
+    GameSummary {
+        name: "Tic-Tac-Toe",
+        active: unique.len(),
+        details,
+    }
+}
+
+impl GameSession for TicTacToeGame {
+    const KIND: GameKind = GameKind::TicTacToe;
+
+    fn other_player(&self, user_id: u64) -> Option<u64> {
+        let player_o_id = self.player_o_id?;
+        Some(if player_o_id == user_id {
+            self.player_x_id
+        } else {
+            player_o_id
+        })
+    }
+
+    fn describe(&self, user_id: u64) -> ActiveGame {
+        let opponent = match self.other_player(user_id) {
+            Some(other) => format!("<@{}>", other),
+            None => "AI".to_string(),
+        };
+        let moves = self
+            .board
+            .iter()
+            .flatten()
+            .filter(|cell| **cell != Cell::Empty)
+            .count();
+        ActiveGame {
+            kind: Self::KIND,
+            opponent: Some(opponent),
+            started: self.started,
+            place: Some(GamePlace {
+                guild_id: self.guild_id,
+                channel_id: self.channel_id,
+                message_id: self.message_id,
+            }),
+            progress: format!("{} moves", moves),
+        }
+    }
+
+    fn channel_id(&self) -> Option<u64> {
+        Some(self.channel_id)
+    }
+
+    fn end(&self) -> String {
+        "🏳️ **Tic-Tac-Toe game ended!** Thanks for playing! 👋".to_string()
+    }
+
+    /// Disables the board buttons on the game message.
+    async fn close(&self, http: &serenity::Http) {
+        if let Some(msg_id) = self.message_id {
+            let _ = serenity::ChannelId::new(self.channel_id)
+                .edit_message(
+                    http,
+                    serenity::MessageId::new(msg_id),
+                    serenity::EditMessage::new().components(self.board_buttons(true)),
+                )
+                .await;
+        }
+    }
 }
 
 /// Start a Tic-Tac-Toe game! Play against another player or the AI
-#[poise::command(prefix_command, slash_command)]
+#[poise::command(prefix_command, slash_command, aliases("ttt"))]
 pub async fn tictactoe(
     ctx: Context<'_>,
     #[description = "Player to challenge (leave empty to play vs AI)"] opponent: Option<
@@ -278,14 +621,14 @@ pub async fn tictactoe(
 
     // Check if user already has an active game
     {
-        let games = ACTIVE_GAMES.read().await;
+        let games = ctx.data().games.tictactoe.read().await;
         if games.contains_key(&user_id) {
-            ctx.say("❌ You already have an active Tic-Tac-Toe game! Use `/move <position>` to play or `/endttt` to quit.").await?;
+            ctx.say("❌ You already have a Tic-Tac-Toe game going! Use `/move_ttt <position>` to play or `/endgame tictactoe` to quit.").await?;
             return Ok(());
         }
     }
 
-    let game = match opponent {
+    let mut game = match opponent {
         Some(opponent_user) => {
             if opponent_user.id == ctx.author().id {
                 ctx.say("❌ You can't play against yourself! Try `/tictactoe` without mentioning anyone to play vs AI.").await?;
@@ -299,7 +642,7 @@ pub async fn tictactoe(
 
             // Check if opponent already has a game
             {
-                let games = ACTIVE_GAMES.read().await;
+                let games = ctx.data().games.tictactoe.read().await;
                 if games.contains_key(&opponent_user.id.get()) {
                     ctx.say(format!(
                         "❌ {} already has an active game!",
@@ -314,6 +657,7 @@ pub async fn tictactoe(
         }
         None => TicTacToeGame::new_vs_ai(user_id, ctx.channel_id().get()),
     };
+    game.guild_id = ctx.guild_id().map(|guild_id| guild_id.get());
 
     let game_type = if game.is_ai_game {
         "🤖 **vs AI**"
@@ -332,7 +676,7 @@ pub async fn tictactoe(
         **Player X:** {}\n\
         **Player O:** {}\n\n\
         {} goes first!\n\
-        Use `/move <position>` where position is 1-9:\n\
+        Press a button or use `/move <position>` where position is 1-9:\n\
         {}\n\
         Current turn: {} {}",
         game_type,
@@ -345,17 +689,20 @@ pub async fn tictactoe(
     );
 
     // Send initial message and store its ID
-    let reply = ctx.say(response).await?;
+    let reply = ctx
+        .send(
+            poise::CreateReply::default()
+                .content(response)
+                .components(game.board_buttons(false)),
+        )
+        .await?;
     let mut game_with_msg_id = game.clone();
     game_with_msg_id.message_id = Some(reply.message().await?.id.get());
 
     // Store game for both players
     {
-        let mut games = ACTIVE_GAMES.write().await;
-        games.insert(user_id, game_with_msg_id.clone());
-        if let Some(opponent_id) = game_with_msg_id.player_o_id {
-            games.insert(opponent_id, game_with_msg_id);
-        }
+        let mut games = ctx.data().games.tictactoe.write().await;
+        store_game(&mut games, game_with_msg_id);
     }
     Ok(())
 }
@@ -368,18 +715,11 @@ pub async fn move_ttt(
 ) -> Result<(), Error> {
     let user_id = ctx.author().id.get();
 
-    // Get current game state and extract needed info
-    let (mut updated_game, player_x_id, player_o_id) = {
-        let games = ACTIVE_GAMES.read().await;
+    // Get current game state
+    let mut updated_game = {
+        let games = ctx.data().games.tictactoe.read().await;
         match games.get(&user_id) {
-            Some(game) => {
-                let game_clone = game.clone();
-                (
-                    game_clone.clone(),
-                    game_clone.player_x_id,
-                    game_clone.player_o_id,
-                )
-            }
+            Some(game) => game.clone(),
             None => {
                 ctx.say(
                     "❌ You don't have an active Tic-Tac-Toe game! Start one with `/tictactoe`",
@@ -409,144 +749,113 @@ pub async fn move_ttt(
     }
 
     // Try to make the move
-    match updated_game.make_move(position as usize) {
-        Ok(_) => {
-            // Check for win or tie
-            if let Some(winner) = updated_game.check_winner() {
-                let winner_id = if winner == Player::X {
-                    updated_game.player_x_id
-                } else {
-                    updated_game.player_o_id.unwrap_or(0) // 0 for AI
-                };
-
-                let winner_text = if winner_id == 0 {
-                    "🤖 **AI wins!**".to_string()
-                } else {
-                    format!("🎉 **<@{}> wins!**", winner_id)
-                };
-
-                let response = format!(
-                    "{}\n{}\n\nGame over! Use `/tictactoe` to start a new game.",
-                    winner_text,
-                    updated_game.display_board()
-                );
-
-                // Remove game for both players
-                {
-                    let mut games = ACTIVE_GAMES.write().await;
-                    games.remove(&player_x_id);
-                    if let Some(opponent_id) = player_o_id {
-                        games.remove(&opponent_id);
-                    }
-                }
-
-                updated_game.delete_and_send_message(&ctx, response).await?;
-                return Ok(());
-            } else if updated_game.is_board_full() {
-                let response = format!(
-                    "🤝 **It's a tie!**\n{}\n\nGame over! Use `/tictactoe` to start a new game.",
-                    updated_game.display_board()
-                );
-
-                // Remove game for both players
-                {
-                    let mut games = ACTIVE_GAMES.write().await;
-                    games.remove(&player_x_id);
-                    if let Some(opponent_id) = player_o_id {
-                        games.remove(&opponent_id);
-                    }
-                }
+    match updated_game.play_turn(position as usize) {
+        Ok(result) => {
+            let game_over = result.outcome != Outcome::Ongoing;
+            let response = updated_game.turn_message(&result);
+            let new_msg_id = updated_game
+                .delete_and_send_message(&ctx, response, updated_game.board_buttons(game_over))
+                .await?;
+            updated_game.message_id = Some(new_msg_id);
 
-                updated_game.delete_and_send_message(&ctx, response).await?;
-                return Ok(());
+            // Update (or remove) both players' game states
+            let mut games = ctx.data().games.tictactoe.write().await;
+            if game_over {
+                remove_game(&mut games, &updated_game);
+            } else {
+                store_game(&mut games, updated_game);
             }
+        }
+        Err(msg) => {
+            ctx.say(format!("❌ {}", msg)).await?;
+        }
+    }
 
-            updated_game.switch_player();
-
-            // Handle AI move if it's an AI game and now AI's turn
-            if updated_game.is_ai_game && updated_game.current_player == Player::O {
-                if let Some(ai_move) = updated_game.get_ai_move() {
-                    updated_game.make_move(ai_move).unwrap();
-
-                    // Check for AI win or tie after AI move
-                    if let Some(_winner) = updated_game.check_winner() {
-                        let response = format!(
-                            "🤖 **AI wins!**\nAI played position {}\n{}\n\nGame over! Use `/tictactoe` to start a new game.",
-                            ai_move,
-                            updated_game.display_board()
-                        );
-
-                        // Remove game
-                        {
-                            let mut games = ACTIVE_GAMES.write().await;
-                            games.remove(&player_x_id);
-                        }
-
-                        updated_game.delete_and_send_message(&ctx, response).await?;
-                        return Ok(());
-                    } else if updated_game.is_board_full() {
-                        let response = format!(
-                            "🤝 **It's a tie!**\nAI played position {}\n{}\n\nGame over! Use `/tictactoe` to start a new game.",
-                            ai_move,
-                            updated_game.display_board()
-                        );
-
-                        // Remove game
-                        {
-                            let mut games = ACTIVE_GAMES.write().await;
-                            games.remove(&player_x_id);
-                        }
-
-                        updated_game.delete_and_send_message(&ctx, response).await?;
-                        return Ok(());
-                    }
+    Ok(())
+}
 
-                    updated_game.switch_player();
+/// Handle a press of one of the board buttons on a game message.
+pub async fn handle_button(
+    ctx: &serenity::Context,
+    interaction: &serenity::ComponentInteraction,
+    sessions: &GameSessions,
+) -> Result<(), Error> {
+    let Some((game_id, position)) = parse_button_id(&interaction.data.custom_id) else {
+        return Ok(());
+    };
+    let user_id = interaction.user.id.get();
+
+    // Apply the turn under the lock so simultaneous presses are applied in
+    // order, but release it before talking to Discord
+    let (game, result) = {
+        let mut games = sessions.tictactoe.write().await;
+        let game = games.values().find(|game| game.game_id == game_id).cloned();
+
+        let error = match game {
+            None => Some("❌ This game is over or has expired.".to_string()),
+            Some(ref game) if !game.has_player(user_id) => {
+                Some("❌ You're not playing in this game!".to_string())
+            }
+            Some(ref game) if game.get_current_player_id() != Some(user_id) => {
+                Some("❌ It's not your turn!".to_string())
+            }
+            Some(_) => None,
+        };
+        if let Some(error) = error {
+            drop(games);
+            return respond_ephemeral(ctx, interaction, error).await;
+        }
 
-                    // Show board after AI move
-                    let response = format!(
-                        "🤖 AI played position **{}**\n{}\n\nYour turn! {} Use `/move <position>`",
-                        ai_move,
-                        updated_game.display_board(),
-                        ctx.author().mention()
-                    );
+        let mut game = game.unwrap();
+        let result = match game.play_turn(position) {
+            Ok(result) => result,
+            Err(msg) => {
+                drop(games);
+                return respond_ephemeral(ctx, interaction, format!("❌ {}", msg)).await;
+            }
+        };
 
-                    let new_msg_id = updated_game.delete_and_send_message(&ctx, response).await?;
-                    updated_game.message_id = Some(new_msg_id);
-                }
-            } else {
-                // Show current board state for human vs human
-                let current_player_mention = if updated_game.current_player == Player::X {
-                    format!("<@{}>", updated_game.player_x_id)
-                } else {
-                    format!("<@{}>", updated_game.player_o_id.unwrap())
-                };
+        game.message_id = Some(interaction.message.id.get());
+        if result.outcome != Outcome::Ongoing {
+            remove_game(&mut games, &game);
+        } else {
+            store_game(&mut games, game.clone());
+        }
+        (game, result)
+    };
 
-                let response = format!(
-                    "{}\n\nCurrent turn: {} {}",
-                    updated_game.display_board(),
-                    updated_game.current_player,
-                    current_player_mention
-                );
+    let game_over = result.outcome != Outcome::Ongoing;
+    let response = serenity::CreateInteractionResponse::UpdateMessage(
+        serenity::CreateInteractionResponseMessage::new()
+            .content(game.turn_message(&result))
+            .components(game.board_buttons(game_over)),
+    );
+    interaction.create_response(&ctx.http, response).await?;
 
-                let new_msg_id = updated_game.delete_and_send_message(&ctx, response).await?;
-                updated_game.message_id = Some(new_msg_id);
-            }
+    Ok(())
+}
 
-            // Always update both players' game states after any move
-            {
-                let mut games = ACTIVE_GAMES.write().await;
-                games.insert(player_x_id, updated_game.clone());
-                if let Some(opponent_id) = player_o_id {
-                    games.insert(opponent_id, updated_game);
-                }
-            }
-        }
-        Err(msg) => {
-            ctx.say(format!("❌ {}", msg)).await?;
-        }
-    }
+/// Parse a `ttt:<game id>:<position>` button custom ID.
+fn parse_button_id(custom_id: &str) -> Option<(u64, usize)> {
+    let (game_id, position) = custom_id.strip_prefix(BUTTON_PREFIX)?.split_once(':')?;
+    Some((game_id.parse().ok()?, position.parse().ok()?))
+}
 
+async fn respond_ephemeral(
+    ctx: &serenity::Context,
+    interaction: &serenity::ComponentInteraction,
+    content: String,
+) -> Result<(), Error> {
+    interaction
+        .create_response(
+            &ctx.http,
+            serenity::CreateInteractionResponse::Message(
+                serenity::CreateInteractionResponseMessage::new()
+                    .content(content)
+                    .ephemeral(true),
+            ),
+        )
+        .await?;
     Ok(())
 }
 
@@ -555,7 +864,7 @@ pub async fn move_ttt(
 pub async fn board(ctx: Context<'_>) -> Result<(), Error> {
     let user_id = ctx.author().id.get();
 
-    let games = ACTIVE_GAMES.read().await;
+    let games = ctx.data().games.tictactoe.read().await;
 
     if let Some(game) = games.get(&user_id) {
         let current_player_mention = if let Some(current_id) = game.get_current_player_id() {
@@ -605,21 +914,111 @@ pub async fn board(ctx: Context<'_>) -> Result<(), Error> {
 #[poise::command(prefix_command, slash_command)]
 pub async fn endttt(ctx: Context<'_>) -> Result<(), Error> {
     let user_id = ctx.author().id.get();
+    match ctx
+        .data()
+        .games
+        .end_game(ctx.http(), GameKind::TicTacToe, user_id)
+        .await
+    {
+        Some(response) => ctx.say(response).await?,
+        None => {
+            ctx.say("❌ You don't have an active Tic-Tac-Toe game!")
+                .await?
+        }
+    };
+    Ok(())
+}
 
-    let mut games = ACTIVE_GAMES.write().await;
+#[cfg(test)]
+mod tests {
+    use super::*;
 
-    if let Some(game) = games.remove(&user_id) {
-        // Also remove the game for the opponent if it's a two-player game
-        if let Some(opponent_id) = game.player_o_id {
-            games.remove(&opponent_id);
+    #[test]
+    fn test_remove_idle_games_counts_two_player_game_once() {
+        let mut games = HashMap::new();
+        let game = TicTacToeGame::new_two_player(1, 2, 100);
+        games.insert(1, game.clone());
+        games.insert(2, game);
+        games.insert(3, TicTacToeGame::new_vs_ai(3, 100));
+
+        assert!(remove_idle_games(&mut games, Instant::now()).is_empty());
+
+        let expired = remove_idle_games(&mut games, Instant::now() + GAME_IDLE_TIMEOUT);
+        assert_eq!(expired.len(), 2);
+        assert!(games.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_end_two_player_game_for_both() {
+        let sessions = GameSessions::default();
+        let game = TicTacToeGame::new_two_player(1, 2, 100);
+        store_game(&mut *sessions.tictactoe.write().await, game);
+        assert_eq!(sessions.games_in(2, 100).await, vec![GameKind::TicTacToe]);
+        assert_eq!(
+            sessions.player_games(2).await[0].opponent.as_deref(),
+            Some("<@1>")
+        );
+
+        // No board message yet, so ending it doesn't talk to Discord
+        let http = serenity::Http::new("");
+        let ended = sessions.end_game(&http, GameKind::TicTacToe, 2).await;
+        assert!(ended.is_some());
+        assert!(sessions.player_games(1).await.is_empty());
+        assert!(sessions
+            .end_game(&http, GameKind::TicTacToe, 1)
+            .await
+            .is_none());
+    }
+
+    #[test]
+    fn test_first_game_id_follows_start_time() {
+        let earlier = Utc::now();
+        let later = earlier + chrono::Duration::seconds(1);
+        assert!(first_game_id(later) > first_game_id(earlier));
+        assert!(first_game_id(DateTime::<Utc>::UNIX_EPOCH) >= 1);
+    }
+
+    #[test]
+    fn test_parse_button_id() {
+        assert_eq!(parse_button_id("ttt:42:5"), Some((42, 5)));
+        assert_eq!(parse_button_id("ttt:42"), None);
+        assert_eq!(parse_button_id("remind_ack:1"), None);
+    }
+
+    #[test]
+    fn test_play_turn_two_player_win() {
+        let mut game = TicTacToeGame::new_two_player(1, 2, 100);
+        for pos in [1, 4, 2, 5] {
+            let result = game.play_turn(pos).unwrap();
+            assert_eq!(result.outcome, Outcome::Ongoing);
         }
 
-        ctx.say("🏳️ **Tic-Tac-Toe game ended!** Thanks for playing! 👋")
-            .await?;
-    } else {
-        ctx.say("❌ You don't have an active Tic-Tac-Toe game!")
-            .await?;
+        let result = game.play_turn(3).unwrap();
+        assert_eq!(result.outcome, Outcome::Won(Player::X));
+        assert!(game.turn_message(&result).contains("<@1> wins!"));
     }
 
-    Ok(())
+    #[test]
+    fn test_play_turn_ai_replies() {
+        let mut game = TicTacToeGame::new_vs_ai(1, 100);
+        let result = game.play_turn(1).unwrap();
+
+        assert_eq!(result.ai_move, Some(5));
+        assert_eq!(result.outcome, Outcome::Ongoing);
+        assert!(game.current_player == Player::X);
+        assert!(game.play_turn(5).is_err());
+    }
+
+    #[test]
+    fn test_board_buttons_disable_taken_cells() {
+        let mut game = TicTacToeGame::new_vs_ai(1, 100);
+        game.play_turn(1).unwrap();
+
+        let rows = game.board_buttons(false);
+        assert_eq!(rows.len(), 3);
+        let json = serde_json::to_value(&rows).unwrap();
+        assert_eq!(json[0]["components"][0]["disabled"], true);
+        assert_eq!(json[0]["components"][1]["disabled"], false);
+        assert_eq!(json[1]["components"][1]["disabled"], true);
+    }
 }
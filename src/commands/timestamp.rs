@@ -0,0 +1,311 @@
+use crate::commands::remind::{parse_time_duration, parse_time_phrase, REMINDER_TIMEZONE};
+use crate::{Context, Error};
+use chrono::{DateTime, Duration, NaiveDate, NaiveDateTime, NaiveTime, TimeZone, Utc};
+use chrono_tz::Tz;
+
+/// Discord's timestamp styles, as (suffix, name).
+const STYLES: &[(char, &str)] = &[
+    ('t', "Short time"),
+    ('T', "Long time"),
+    ('d', "Short date"),
+    ('D', "Long date"),
+    ('f', "Short date/time"),
+    ('F', "Long date/time"),
+    ('R', "Relative"),
+];
+
+const DEFAULT_STYLE: char = 'F';
+
+/// Absolute dates and times accepted besides durations and phrases.
+const DATE_TIME_FORMATS: &[&str] = &[
+    "%Y-%m-%d %H:%M",
+    "%Y-%m-%d %H:%M:%S",
+    "%Y-%m-%dT%H:%M",
+    "%Y-%m-%dT%H:%M:%S",
+    "%d/%m/%Y %H:%M",
+];
+const DATE_FORMATS: &[&str] = &["%Y-%m-%d", "%d/%m/%Y"];
+const TIME_FORMATS: &[&str] = &["%H:%M", "%H:%M:%S"];
+
+const UNKNOWN_TIME_MESSAGE: &str = "❌ I couldn't understand that time! Try a duration like \
+     `2h`, a phrase like `tonight`, `2024-06-01 14:30`, `2024-06-01` or `14:30`.";
+
+/// Why a time couldn't be turned into a timestamp.
+#[derive(Debug, PartialEq)]
+enum WhenError {
+    /// Nothing in it looked like a time.
+    Unknown,
+    /// The start is a time, but then there's text that isn't.
+    Leftover(DateTime<Utc>, String),
+    /// A local time skipped by a daylight saving change.
+    Skipped(NaiveDateTime),
+    TooFar,
+}
+
+/// Turn a time into a Discord timestamp you can paste into messages
+///
+/// Accepts durations (`2h`), phrases (`tonight`) and absolute times
+/// (`2024-06-01 14:30`, `14:30`) in the bot's timezone.
+#[poise::command(prefix_command, slash_command)]
+pub async fn timestamp(
+    ctx: Context<'_>,
+    #[description = "When: 2h, tonight, 2024-06-01 14:30, 14:30, ..."] when: String,
+    #[description = "Style: t, T, d, D, f, F (default) or R"] format: Option<String>,
+    #[description = "Show every style"] all: Option<bool>,
+) -> Result<(), Error> {
+    log::info!(
+        "Timestamp command called by {} with '{}'",
+        ctx.author().name,
+        when
+    );
+
+    // Prefix arguments split at spaces, so `-timestamp 2024-06-01 14:30`
+    // hands the time over in two pieces
+    let is_prefix = matches!(ctx, poise::Context::Prefix(_));
+    let (when, style) = match format {
+        Some(format) => match parse_style(&format) {
+            Some(style) => (when, style),
+            None if is_prefix => (format!("{} {}", when, format), DEFAULT_STYLE),
+            None => {
+                ctx.say(invalid_style_message(&format)).await?;
+                return Ok(());
+            }
+        },
+        None => (when, DEFAULT_STYLE),
+    };
+
+    let tz = REMINDER_TIMEZONE;
+    let at = match parse_when(&when, Utc::now(), &tz) {
+        Ok(at) => at,
+        Err(error) => {
+            ctx.say(when_error_message(&error, &tz)).await?;
+            return Ok(());
+        }
+    };
+
+    let reply = if all.unwrap_or(false) {
+        all_styles(at, &tz)
+    } else {
+        single_style(at, style, &tz)
+    };
+    ctx.say(reply).await?;
+    Ok(())
+}
+
+/// A style given as its suffix (case matters: `t` and `T` differ) or name.
+fn parse_style(input: &str) -> Option<char> {
+    let input = input.trim();
+    STYLES
+        .iter()
+        .find(|(suffix, name)| {
+            input.len() == 1 && input.starts_with(*suffix) || name.eq_ignore_ascii_case(input)
+        })
+        .map(|(suffix, _)| *suffix)
+}
+
+fn invalid_style_message(input: &str) -> String {
+    let styles: Vec<String> = STYLES
+        .iter()
+        .map(|(suffix, name)| format!("`{}` ({})", suffix, name))
+        .collect();
+    format!(
+        "❌ Unknown style `{}`! Use one of {}.",
+        input,
+        styles.join(", ")
+    )
+}
+
+/// Parse a duration, a natural phrase or an absolute time in `tz`.
+fn parse_when(input: &str, now: DateTime<Utc>, tz: &Tz) -> Result<DateTime<Utc>, WhenError> {
+    let input = input.trim();
+    if input.is_empty() {
+        return Err(WhenError::Unknown);
+    }
+
+    // Durations and phrases, as for reminders
+    if let Some(duration) = parse_time_duration(input) {
+        return now.checked_add_signed(duration).ok_or(WhenError::TooFar);
+    }
+    if let Some((at, rest)) = parse_time_phrase(input, now, tz) {
+        return if rest.is_empty() {
+            Ok(at)
+        } else {
+            Err(WhenError::Leftover(at, rest.to_string()))
+        };
+    }
+
+    // The longest run of leading words that's an absolute time
+    let words: Vec<&str> = input.split_whitespace().collect();
+    for taken in (1..=words.len().min(2)).rev() {
+        let Some(local) = parse_absolute(&words[..taken].join(" "), now, tz) else {
+            continue;
+        };
+        let at = tz
+            .from_local_datetime(&local)
+            .earliest()
+            .ok_or(WhenError::Skipped(local))?
+            .with_timezone(&Utc);
+        let rest = words[taken..].join(" ");
+        return if rest.is_empty() {
+            Ok(at)
+        } else {
+            Err(WhenError::Leftover(at, rest))
+        };
+    }
+    Err(WhenError::Unknown)
+}
+
+/// A date and time, a date (at midnight) or a time of day (its next
+/// occurrence), as local time in `tz`.
+fn parse_absolute(input: &str, now: DateTime<Utc>, tz: &Tz) -> Option<NaiveDateTime> {
+    if let Some(at) = DATE_TIME_FORMATS
+        .iter()
+        .find_map(|format| NaiveDateTime::parse_from_str(input, format).ok())
+    {
+        return Some(at);
+    }
+    if let Some(date) = DATE_FORMATS
+        .iter()
+        .find_map(|format| NaiveDate::parse_from_str(input, format).ok())
+    {
+        return Some(date.and_time(NaiveTime::MIN));
+    }
+    let time = TIME_FORMATS
+        .iter()
+        .find_map(|format| NaiveTime::parse_from_str(input, format).ok())?;
+    let local_now = now.with_timezone(tz).naive_local();
+    let today = local_now.date().and_time(time);
+    if today > local_now {
+        Some(today)
+    } else {
+        Some(today + Duration::days(1))
+    }
+}
+
+/// How an instant reads in `tz`, to show what a time was understood as.
+fn interpreted(at: DateTime<Utc>, tz: &Tz) -> String {
+    format!(
+        "interpreted as {} {}",
+        at.with_timezone(tz).format("%Y-%m-%d %H:%M"),
+        tz.name()
+    )
+}
+
+fn when_error_message(error: &WhenError, tz: &Tz) -> String {
+    match error {
+        WhenError::Unknown => UNKNOWN_TIME_MESSAGE.to_string(),
+        WhenError::Leftover(at, rest) => format!(
+            "❌ I don't know what `{}` means. The part before it was {}; leave it out if that's right.",
+            rest,
+            interpreted(*at, tz)
+        ),
+        WhenError::Skipped(local) => format!(
+            "❌ {} doesn't exist in {}: the clocks skip it for daylight saving time.",
+            local.format("%Y-%m-%d %H:%M"),
+            tz.name()
+        ),
+        WhenError::TooFar => "❌ That's too far in the future!".to_string(),
+    }
+}
+
+fn markup(at: DateTime<Utc>, style: char) -> String {
+    format!("<t:{}:{}>", at.timestamp(), style)
+}
+
+/// The preview and copyable markup for one style.
+fn single_style(at: DateTime<Utc>, style: char, tz: &Tz) -> String {
+    let markup = markup(at, style);
+    format!(
+        "🕒 {} ({})\n```\n{}\n```",
+        markup,
+        interpreted(at, tz),
+        markup
+    )
+}
+
+/// Every style side by side, each with its markup.
+fn all_styles(at: DateTime<Utc>, tz: &Tz) -> String {
+    let mut reply = format!("🕒 Every style, {}:", interpreted(at, tz));
+    for (suffix, name) in STYLES {
+        let markup = markup(at, *suffix);
+        reply.push_str(&format!("\n**{}** `{}` → {}", name, markup, markup));
+    }
+    reply
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn utc(s: &str) -> DateTime<Utc> {
+        DateTime::parse_from_rfc3339(s).unwrap().with_timezone(&Utc)
+    }
+
+    #[test]
+    fn test_parse_style() {
+        assert_eq!(parse_style("t"), Some('t'));
+        assert_eq!(parse_style("T"), Some('T'));
+        assert_eq!(parse_style("relative"), Some('R'));
+        assert_eq!(parse_style("Long Date"), Some('D'));
+        assert_eq!(parse_style("x"), None);
+        assert_eq!(parse_style("14:30"), None);
+    }
+
+    #[test]
+    fn test_parse_when() {
+        let tz = REMINDER_TIMEZONE;
+        // 14:00 in Copenhagen (summer time)
+        let now = utc("2024-06-01T12:00:00Z");
+        assert_eq!(parse_when("2h", now, &tz), Ok(utc("2024-06-01T14:00:00Z")));
+        assert_eq!(
+            parse_when("tonight", now, &tz),
+            Ok(utc("2024-06-01T18:00:00Z"))
+        );
+        assert_eq!(
+            parse_when("2024-06-01 14:30", now, &tz),
+            Ok(utc("2024-06-01T12:30:00Z"))
+        );
+        assert_eq!(
+            parse_when("24/12/2024", now, &tz),
+            Ok(utc("2024-12-23T23:00:00Z"))
+        );
+        // Times of day are the next time the clock shows them
+        assert_eq!(
+            parse_when("15:00", now, &tz),
+            Ok(utc("2024-06-01T13:00:00Z"))
+        );
+        assert_eq!(
+            parse_when("13:00", now, &tz),
+            Ok(utc("2024-06-02T11:00:00Z"))
+        );
+
+        assert_eq!(parse_when("whenever", now, &tz), Err(WhenError::Unknown));
+        assert_eq!(
+            parse_when("2024-06-01 14:30 ish", now, &tz),
+            Err(WhenError::Leftover(
+                utc("2024-06-01T12:30:00Z"),
+                "ish".to_string()
+            ))
+        );
+        assert!(matches!(
+            parse_when("2024-03-31 02:30", now, &tz),
+            Err(WhenError::Skipped(_))
+        ));
+    }
+
+    #[test]
+    fn test_replies() {
+        let tz = REMINDER_TIMEZONE;
+        let at = utc("2024-06-01T12:30:00Z");
+        assert_eq!(
+            single_style(at, 'R', &tz),
+            "🕒 <t:1717245000:R> (interpreted as 2024-06-01 14:30 Europe/Copenhagen)\n```\n<t:1717245000:R>\n```"
+        );
+        let all = all_styles(at, &tz);
+        assert_eq!(all.lines().count(), STYLES.len() + 1);
+        assert!(all.contains("**Short time** `<t:1717245000:t>` → <t:1717245000:t>"));
+        assert!(
+            when_error_message(&WhenError::Leftover(at, "ish".to_string()), &tz).contains("`ish`")
+        );
+    }
+}
@@ -1,18 +1,37 @@
+use crate::utils::output::ProgressReporter;
 use crate::utils::{get_git_branch, is_protected_user};
 use crate::{Context, Error};
 
-use serde::{Deserialize, Serialize};
+use crate::lifecycle::{write_shutdown_marker, RestartReason};
 use std::env;
-use std::fs;
-use std::process::{Command, Stdio};
+use std::process::{Command, Output, Stdio};
+use std::sync::atomic::{AtomicBool, Ordering};
 
-#[derive(Serialize, Deserialize)]
-struct UpdateInfo {
-    channel_id: u64,
-    user_name: String,
+/// Set while an update is pulling and building, so `-update`, the update
+/// button and the automatic update never run on top of each other.
+static UPDATE_RUNNING: AtomicBool = AtomicBool::new(false);
+
+/// Held for the length of an update; lets the next one start when dropped.
+pub struct UpdateGuard(());
+
+impl Drop for UpdateGuard {
+    fn drop(&mut self) {
+        UPDATE_RUNNING.store(false, Ordering::Release);
+    }
+}
+
+/// Claim the update, or `None` if one is already running.
+pub fn start_update() -> Option<UpdateGuard> {
+    UPDATE_RUNNING
+        .compare_exchange(false, true, Ordering::Acquire, Ordering::Relaxed)
+        .ok()
+        .map(|_| UpdateGuard(()))
 }
 
-fn find_rustbot_directory() -> Option<String> {
+/// What to say when [`start_update`] found an update already running.
+pub const UPDATE_ALREADY_RUNNING: &str = "⏳ An update is already running.";
+
+pub fn find_rustbot_directory() -> Option<String> {
     // First, try to detect if we're running from /app/RustBot (Docker environment)
     if std::path::Path::new("/app/RustBot/.git").exists() {
         return Some("/app/RustBot".to_string());
@@ -43,6 +62,149 @@ fn find_rustbot_directory() -> Option<String> {
     None
 }
 
+/// Run a program in `dir` on the blocking pool, capturing its output.
+async fn run_in(dir: &str, program: &'static str, args: Vec<String>) -> std::io::Result<Output> {
+    let dir = dir.to_string();
+    tokio::task::spawn_blocking(move || {
+        Command::new(program)
+            .args(&args)
+            .current_dir(dir)
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .output()
+    })
+    .await
+    .map_err(std::io::Error::other)?
+}
+
+/// Stderr in a code block, cut to fit a Discord message.
+fn stderr_block(output: &Output) -> String {
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    format!(
+        "```\n{}\n```",
+        stderr.chars().take(1900).collect::<String>()
+    )
+}
+
+/// Subjects of the commits on `origin/<branch>` that aren't checked out yet,
+/// oldest first, after fetching.
+pub async fn fetch_pending_commits(dir: &str, branch: &str) -> Result<Vec<String>, String> {
+    let fetch = run_in(
+        dir,
+        "git",
+        vec!["fetch".into(), "origin".into(), branch.into()],
+    )
+    .await
+    .map_err(|e| format!("Failed to run git fetch: {e}"))?;
+    if !fetch.status.success() {
+        return Err(format!("Git fetch failed:\n{}", stderr_block(&fetch)));
+    }
+
+    let log = run_in(
+        dir,
+        "git",
+        vec![
+            "log".into(),
+            "--reverse".into(),
+            "--format=%s".into(),
+            format!("HEAD..origin/{branch}"),
+        ],
+    )
+    .await
+    .map_err(|e| format!("Failed to run git log: {e}"))?;
+    if !log.status.success() {
+        return Err(format!("Git log failed:\n{}", stderr_block(&log)));
+    }
+    Ok(String::from_utf8_lossy(&log.stdout)
+        .lines()
+        .map(str::to_string)
+        .collect())
+}
+
+/// Reset to `origin/<branch>` and pull. The error is ready to show.
+pub async fn pull_latest(dir: &str, branch: &str) -> Result<(), String> {
+    // Reset any local changes first (handles deleted files)
+    match run_in(
+        dir,
+        "git",
+        vec!["reset".into(), "--hard".into(), format!("origin/{branch}")],
+    )
+    .await
+    {
+        Ok(reset_output) => {
+            if !reset_output.status.success() {
+                let stderr = String::from_utf8_lossy(&reset_output.stderr);
+                log::warn!("Git reset had issues but continuing: {stderr}");
+            }
+        }
+        Err(e) => {
+            log::warn!("Failed to run git reset, continuing anyway: {e}");
+        }
+    }
+
+    // Pull the latest changes
+    match run_in(
+        dir,
+        "git",
+        vec!["pull".into(), "origin".into(), branch.into()],
+    )
+    .await
+    {
+        Ok(output) if output.status.success() => {
+            let stdout = String::from_utf8_lossy(&output.stdout);
+            log::info!("Git pull successful: {stdout}");
+            Ok(())
+        }
+        Ok(output) => {
+            log::error!(
+                "Git pull failed: {}",
+                String::from_utf8_lossy(&output.stderr)
+            );
+            Err(format!("❌ Git pull failed:\n{}", stderr_block(&output)))
+        }
+        Err(e) => {
+            log::error!("Failed to run git pull: {e}");
+            Err(format!("❌ Failed to run git pull: {e}"))
+        }
+    }
+}
+
+/// Build the release binary. The error is ready to show.
+pub async fn build_release(dir: &str) -> Result<(), String> {
+    match run_in(dir, "cargo", vec!["build".into(), "--release".into()]).await {
+        Ok(output) if output.status.success() => {
+            log::info!("Build successful");
+            Ok(())
+        }
+        Ok(output) => {
+            log::error!("Build failed: {}", String::from_utf8_lossy(&output.stderr));
+            Err(format!("❌ Build failed:\n{}", stderr_block(&output)))
+        }
+        Err(e) => {
+            log::error!("Failed to run cargo build: {e}");
+            Err(format!("❌ Failed to run cargo build: {e}"))
+        }
+    }
+}
+
+/// Exit so the service manager starts the freshly built binary. The next
+/// startup reports this as an update by `user_name`.
+pub async fn restart_after_update(user_name: Option<String>) -> ! {
+    if let Err(e) = write_shutdown_marker(RestartReason::Update, user_name) {
+        log::error!("Failed to write shutdown marker: {e}");
+    }
+
+    // Wait a moment before exiting
+    tokio::time::sleep(tokio::time::Duration::from_secs(3)).await;
+
+    // Save buffered command usage and seen users so they aren't lost on restart
+    crate::commands::usage::flush_command_usage();
+    crate::onboarding::flush_seen_users();
+
+    // Exit with a specific code that indicates a restart is needed
+    std::process::exit(42);
+}
+
 /// Update the bot by pulling latest changes from GitHub and restarting
 #[poise::command(slash_command, prefix_command)]
 pub async fn update(ctx: Context<'_>) -> Result<(), Error> {
@@ -53,6 +215,11 @@ pub async fn update(ctx: Context<'_>) -> Result<(), Error> {
         return Ok(());
     }
 
+    let Some(_update) = start_update() else {
+        ctx.say(UPDATE_ALREADY_RUNNING).await?;
+        return Ok(());
+    };
+
     ctx.say("🔄 Starting update process...").await?;
 
     // Find the correct RustBot directory
@@ -65,138 +232,24 @@ pub async fn update(ctx: Context<'_>) -> Result<(), Error> {
         }
     };
 
-    // Create a follow-up message that we can edit
-    let reply = ctx.say("📥 Pulling latest changes from GitHub...").await?;
+    // Building can take longer than a slash command reply can be edited for
+    let progress = ProgressReporter::say(ctx, "📥 Pulling latest changes from GitHub...").await?;
 
-    // Reset any local changes first (handles deleted files)
-    let branch = get_git_branch();
-    let git_reset = Command::new("git")
-        .args(["reset", "--hard", &format!("origin/{branch}")])
-        .current_dir(&rustbot_dir)
-        .stdout(Stdio::piped())
-        .stderr(Stdio::piped())
-        .output();
-
-    match git_reset {
-        Ok(reset_output) => {
-            if !reset_output.status.success() {
-                let stderr = String::from_utf8_lossy(&reset_output.stderr);
-                log::warn!("Git reset had issues but continuing: {stderr}");
-            }
-        }
-        Err(e) => {
-            log::warn!("Failed to run git reset, continuing anyway: {e}");
-        }
+    if let Err(message) = pull_latest(&rustbot_dir, &get_git_branch()).await {
+        progress.set(message).await?;
+        return Ok(());
     }
+    progress
+        .set("✅ Successfully pulled latest changes!\n🔨 Compiling new version...")
+        .await?;
 
-    // Pull the latest changes
-    let git_pull = Command::new("git")
-        .args(["pull", "origin", &branch])
-        .current_dir(&rustbot_dir)
-        .stdout(Stdio::piped())
-        .stderr(Stdio::piped())
-        .output();
-
-    match git_pull {
-        Ok(output) => {
-            if output.status.success() {
-                let stdout = String::from_utf8_lossy(&output.stdout);
-                log::info!("Git pull successful: {stdout}");
-
-                reply
-                    .edit(
-                        ctx,
-                        poise::CreateReply::default().content(
-                            "✅ Successfully pulled latest changes!\n🔨 Compiling new version...",
-                        ),
-                    )
-                    .await?;
-
-                // Build the new version
-                let cargo_build = Command::new("cargo")
-                    .args(["build", "--release"])
-                    .current_dir(&rustbot_dir)
-                    .stdout(Stdio::piped())
-                    .stderr(Stdio::piped())
-                    .output();
-
-                match cargo_build {
-                    Ok(build_output) => {
-                        if build_output.status.success() {
-                            log::info!("Build successful");
-                            reply
-                                .edit(
-                                    ctx,
-                                    poise::CreateReply::default()
-                                        .content("✅ Compilation successful!\n🔄 Restarting bot in 3 seconds..."),
-                                )
-                                .await?;
-
-                            // Store update info for startup message
-                            let update_info = UpdateInfo {
-                                channel_id: ctx.channel_id().get(),
-                                user_name: ctx.author().name.clone(),
-                            };
-
-                            if let Ok(update_json) = serde_json::to_string(&update_info) {
-                                let _ = fs::write("/tmp/rustbot_update_info.json", update_json);
-                            }
-
-                            // Wait a moment before exiting
-                            tokio::time::sleep(tokio::time::Duration::from_secs(3)).await;
-
-                            // Exit with a specific code that indicates a restart is needed
-                            std::process::exit(42);
-                        } else {
-                            let stderr = String::from_utf8_lossy(&build_output.stderr);
-                            log::error!("Build failed: {stderr}");
-                            reply
-                                .edit(
-                                    ctx,
-                                    poise::CreateReply::default().content(format!(
-                                        "❌ Build failed:\n```\n{}\n```",
-                                        stderr.chars().take(1900).collect::<String>()
-                                    )),
-                                )
-                                .await?;
-                        }
-                    }
-                    Err(e) => {
-                        log::error!("Failed to run cargo build: {e}");
-                        reply
-                            .edit(
-                                ctx,
-                                poise::CreateReply::default()
-                                    .content(format!("❌ Failed to run cargo build: {e}")),
-                            )
-                            .await?;
-                    }
-                }
-            } else {
-                let stderr = String::from_utf8_lossy(&output.stderr);
-                log::error!("Git pull failed: {stderr}");
-                reply
-                    .edit(
-                        ctx,
-                        poise::CreateReply::default().content(format!(
-                            "❌ Git pull failed:\n```\n{}\n```",
-                            stderr.chars().take(1900).collect::<String>()
-                        )),
-                    )
-                    .await?;
-            }
-        }
-        Err(e) => {
-            log::error!("Failed to run git pull: {e}");
-            reply
-                .edit(
-                    ctx,
-                    poise::CreateReply::default()
-                        .content(format!("❌ Failed to run git pull: {e}")),
-                )
-                .await?;
-        }
+    if let Err(message) = build_release(&rustbot_dir).await {
+        progress.set(message).await?;
+        return Ok(());
     }
+    progress
+        .set("✅ Compilation successful!\n🔄 Restarting bot in 3 seconds...")
+        .await?;
 
-    Ok(())
+    restart_after_update(Some(ctx.author().name.clone())).await
 }
@@ -0,0 +1,373 @@
+use crate::storage;
+use crate::{Context, Error};
+use chrono::{Duration, NaiveDate, Utc};
+use parking_lot::RwLock;
+use poise::serenity_prelude as serenity;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap, HashSet};
+use tokio::time::{interval, Duration as TokioDuration};
+
+const USAGE_FILE: &str = "command_usage.json";
+
+/// Daily buckets older than this are pruned.
+const RETENTION_DAYS: i64 = 90;
+
+/// `/usage` compares against the previous period, which has to fit in retention.
+const MAX_REPORT_DAYS: u32 = (RETENTION_DAYS / 2) as u32;
+
+/// Save after this many unsaved commands, even if the flush interval hasn't passed.
+const FLUSH_EVERY_EVENTS: usize = 100;
+
+const TOP_COMMANDS_LIMIT: usize = 10;
+
+#[derive(Serialize, Deserialize, Default, Clone, Debug, PartialEq)]
+struct DayStats {
+    count: u64,
+    users: HashSet<u64>,
+}
+
+#[derive(Serialize, Deserialize, Default)]
+struct UsageData {
+    /// guild ID -> UTC day -> command name -> stats
+    guilds: HashMap<u64, BTreeMap<NaiveDate, HashMap<String, DayStats>>>,
+}
+
+struct UsageStore {
+    data: UsageData,
+    unsaved_events: usize,
+}
+
+lazy_static::lazy_static! {
+    static ref USAGE: RwLock<UsageStore> = RwLock::new(UsageStore {
+        data: storage::load(USAGE_FILE),
+        unsaved_events: 0,
+    });
+}
+
+#[derive(Debug, PartialEq)]
+struct CommandUsage {
+    name: String,
+    count: u64,
+    previous_count: u64,
+    unique_users: usize,
+}
+
+#[derive(Debug, PartialEq)]
+struct UsageReport {
+    commands: Vec<CommandUsage>,
+    total: u64,
+    previous_total: u64,
+}
+
+fn record(data: &mut UsageData, guild_id: u64, command: &str, user_id: u64, day: NaiveDate) {
+    let stats = data
+        .guilds
+        .entry(guild_id)
+        .or_default()
+        .entry(day)
+        .or_default()
+        .entry(command.to_string())
+        .or_default();
+    stats.count += 1;
+    stats.users.insert(user_id);
+}
+
+/// Drop buckets that fell out of the retention window. Returns how many were removed.
+fn prune(data: &mut UsageData, today: NaiveDate) -> usize {
+    let cutoff = today - Duration::days(RETENTION_DAYS);
+    let mut removed = 0;
+    for days in data.guilds.values_mut() {
+        let before = days.len();
+        days.retain(|day, _| *day > cutoff);
+        removed += before - days.len();
+    }
+    data.guilds.retain(|_, days| !days.is_empty());
+    removed
+}
+
+/// Usage over the last `days` days (including today) compared with the `days` before that.
+fn summarize(data: &UsageData, guild_id: u64, today: NaiveDate, days: u32) -> UsageReport {
+    let period_start = today - Duration::days(days as i64 - 1);
+    let previous_start = period_start - Duration::days(days as i64);
+
+    let mut current: HashMap<&str, (u64, HashSet<u64>)> = HashMap::new();
+    let mut previous: HashMap<&str, u64> = HashMap::new();
+
+    if let Some(guild_days) = data.guilds.get(&guild_id) {
+        for (day, commands) in guild_days.range(previous_start..=today) {
+            for (name, stats) in commands {
+                if *day >= period_start {
+                    let entry = current.entry(name).or_default();
+                    entry.0 += stats.count;
+                    entry.1.extend(&stats.users);
+                } else {
+                    *previous.entry(name).or_default() += stats.count;
+                }
+            }
+        }
+    }
+
+    let mut commands: Vec<CommandUsage> = current
+        .into_iter()
+        .map(|(name, (count, users))| CommandUsage {
+            name: name.to_string(),
+            count,
+            previous_count: previous.get(name).copied().unwrap_or(0),
+            unique_users: users.len(),
+        })
+        .collect();
+    commands.sort_by(|a, b| b.count.cmp(&a.count).then_with(|| a.name.cmp(&b.name)));
+
+    UsageReport {
+        total: commands.iter().map(|c| c.count).sum(),
+        previous_total: previous.values().sum(),
+        commands,
+    }
+}
+
+fn format_trend(current: u64, previous: u64) -> String {
+    if previous == 0 {
+        return if current == 0 { "—" } else { "🆕 new" }.to_string();
+    }
+
+    let change = (current as f64 - previous as f64) / previous as f64 * 100.0;
+    if change > 0.0 {
+        format!("▲ {:.0}%", change)
+    } else if change < 0.0 {
+        format!("▼ {:.0}%", -change)
+    } else {
+        "＝ 0%".to_string()
+    }
+}
+
+fn save_usage(store: &mut UsageStore) {
+    prune(&mut store.data, Utc::now().date_naive());
+    match storage::save(USAGE_FILE, &store.data) {
+        Ok(()) => store.unsaved_events = 0,
+        Err(e) => log::error!("Failed to save command usage: {}", e),
+    }
+}
+
+/// Drop an orphaned guild's command usage.
+pub fn purge_guild(guild_id: u64) {
+    let mut store = USAGE.write();
+    if store.data.guilds.remove(&guild_id).is_some() {
+        save_usage(&mut store);
+    }
+}
+
+/// Count a command invocation. Called from the framework's `pre_command` hook.
+///
+/// Writes go to memory and are saved in batches, either every
+/// [`FLUSH_EVERY_EVENTS`] commands or by the background flush task.
+pub fn record_command_usage(guild_id: u64, command: &str, user_id: u64) {
+    let mut store = USAGE.write();
+    record(
+        &mut store.data,
+        guild_id,
+        command,
+        user_id,
+        Utc::now().date_naive(),
+    );
+    store.unsaved_events += 1;
+    if store.unsaved_events >= FLUSH_EVERY_EVENTS {
+        save_usage(&mut store);
+    }
+}
+
+/// Save any usage that hasn't been written to disk yet.
+pub fn flush_command_usage() {
+    let mut store = USAGE.write();
+    if store.unsaved_events > 0 {
+        save_usage(&mut store);
+    }
+}
+
+/// The guild's most used commands over the last `days` days, most used first.
+pub fn top_commands(guild_id: u64, days: u32, limit: usize) -> Vec<String> {
+    summarize(&USAGE.read().data, guild_id, Utc::now().date_naive(), days)
+        .commands
+        .into_iter()
+        .take(limit)
+        .map(|command| command.name)
+        .collect()
+}
+
+/// Start the background task that periodically saves command usage
+pub fn start_usage_flusher() {
+    tokio::spawn(async move {
+        let mut interval = interval(TokioDuration::from_secs(60)); // Flush every minute
+
+        loop {
+            interval.tick().await;
+            flush_command_usage();
+        }
+    });
+}
+
+/// Show which commands this server uses most
+#[poise::command(
+    prefix_command,
+    slash_command,
+    guild_only,
+    required_permissions = "MANAGE_GUILD"
+)]
+pub async fn usage(
+    ctx: Context<'_>,
+    #[description = "Number of days to look back (default: 7)"] days: Option<u32>,
+) -> Result<(), Error> {
+    log::info!("Usage command called by {}", ctx.author().name);
+
+    let days = days.unwrap_or(7);
+    if days == 0 || days > MAX_REPORT_DAYS {
+        ctx.say(format!(
+            "❌ Days must be between 1 and {}.",
+            MAX_REPORT_DAYS
+        ))
+        .await?;
+        return Ok(());
+    }
+
+    let Some(guild_id) = ctx.guild_id() else {
+        return Ok(());
+    };
+
+    let report = summarize(
+        &USAGE.read().data,
+        guild_id.get(),
+        Utc::now().date_naive(),
+        days,
+    );
+
+    if report.commands.is_empty() {
+        ctx.say(format!(
+            "📊 No commands have been used in this server in the last {} day(s).",
+            days
+        ))
+        .await?;
+        return Ok(());
+    }
+
+    let top = report
+        .commands
+        .iter()
+        .take(TOP_COMMANDS_LIMIT)
+        .enumerate()
+        .map(|(i, command)| {
+            format!(
+                "**{}.** `{}` — {} use(s), {} user(s) ({})",
+                i + 1,
+                command.name,
+                command.count,
+                command.unique_users,
+                format_trend(command.count, command.previous_count)
+            )
+        })
+        .collect::<Vec<_>>()
+        .join("\n");
+
+    let embed = serenity::CreateEmbed::new()
+        .title(format!("📊 Command Usage — Last {} Day(s)", days))
+        .color(0x5865f2)
+        .description(format!(
+            "**Total:** {} command(s) ({} vs the previous {} day(s))",
+            report.total,
+            format_trend(report.total, report.previous_total),
+            days
+        ))
+        .field("Top Commands", top, false)
+        .footer(serenity::CreateEmbedFooter::new(format!(
+            "{} different command(s) used • Requested by {}",
+            report.commands.len(),
+            ctx.author().name
+        )));
+
+    ctx.send(poise::CreateReply::default().embed(embed)).await?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn date(s: &str) -> NaiveDate {
+        NaiveDate::parse_from_str(s, "%Y-%m-%d").unwrap()
+    }
+
+    #[test]
+    fn test_daily_bucket_rollover() {
+        let mut data = UsageData::default();
+        record(&mut data, 1, "ping", 10, date("2024-05-01"));
+        record(&mut data, 1, "ping", 11, date("2024-05-01"));
+        record(&mut data, 1, "ping", 10, date("2024-05-02"));
+
+        let days = data.guilds.get(&1).unwrap();
+        assert_eq!(days.len(), 2);
+        assert_eq!(days[&date("2024-05-01")]["ping"].count, 2);
+        assert_eq!(days[&date("2024-05-02")]["ping"].count, 1);
+
+        let report = summarize(&data, 1, date("2024-05-02"), 1);
+        assert_eq!(report.total, 1);
+        assert_eq!(report.previous_total, 2);
+        assert_eq!(report.commands[0].unique_users, 1);
+    }
+
+    #[test]
+    fn test_retention_pruning() {
+        let mut data = UsageData::default();
+        let today = date("2024-06-30");
+        record(
+            &mut data,
+            1,
+            "ping",
+            10,
+            today - Duration::days(RETENTION_DAYS),
+        );
+        record(
+            &mut data,
+            1,
+            "ping",
+            10,
+            today - Duration::days(RETENTION_DAYS - 1),
+        );
+        record(
+            &mut data,
+            2,
+            "hit",
+            10,
+            today - Duration::days(RETENTION_DAYS + 5),
+        );
+
+        assert_eq!(prune(&mut data, today), 2);
+        assert_eq!(data.guilds.get(&1).unwrap().len(), 1);
+        assert!(!data.guilds.contains_key(&2));
+    }
+
+    #[test]
+    fn test_summarize_counts_unique_users_and_sorts() {
+        let mut data = UsageData::default();
+        let today = date("2024-05-10");
+        for user in [1, 2, 2] {
+            record(&mut data, 1, "hangman", user, today);
+        }
+        record(&mut data, 1, "hangman", 3, today - Duration::days(1));
+        record(&mut data, 1, "ping", 1, today);
+        record(&mut data, 2, "ping", 1, today);
+
+        let report = summarize(&data, 1, today, 7);
+        assert_eq!(report.total, 5);
+        assert_eq!(report.commands[0].name, "hangman");
+        assert_eq!(report.commands[0].count, 4);
+        assert_eq!(report.commands[0].unique_users, 3);
+        assert_eq!(report.commands[1].name, "ping");
+    }
+
+    #[test]
+    fn test_format_trend() {
+        assert_eq!(format_trend(0, 0), "—");
+        assert_eq!(format_trend(5, 0), "🆕 new");
+        assert_eq!(format_trend(15, 10), "▲ 50%");
+        assert_eq!(format_trend(5, 10), "▼ 50%");
+        assert_eq!(format_trend(10, 10), "＝ 0%");
+    }
+}
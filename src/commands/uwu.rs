@@ -1,145 +1,68 @@
+use crate::utils::args::take_flag;
+use crate::utils::transforms::{run_transform, uwuify};
 use crate::{Context, Error};
 
-/// Transform text into uwu language
-fn uwuify(text: &str) -> String {
-    let mut result = text.to_string();
-
-    // Replace patterns - handle case insensitive replacements
-    result = result.replace("The", "Teh");
-    result = result.replace("the", "teh");
-    result = result.replace("th", "d");
-    result = result.replace("r", "w");
-    result = result.replace("l", "w");
-    result = result.replace("na", "nya");
-    result = result.replace("ne", "nye");
-    result = result.replace("ni", "nyi");
-    result = result.replace("no", "nyo");
-    result = result.replace("nu", "nyu");
-    result = result.replace("ove", "uv");
-
-    // Add uwu expressions
-    let uwu_expressions = [" uwu ", " owo ", " >w< ", " ^w^ ", " (>ω<) "];
-    let sentences: Vec<&str> = result.split(['.', '!', '?']).collect();
-    let mut uwu_sentences = Vec::new();
-
-    for (i, sentence) in sentences.iter().enumerate() {
-        if !sentence.trim().is_empty() {
-            let mut sentence_str = sentence.trim().to_string();
-
-            // Add uwu expression to longer sentences
-            if sentence.len() > 20 && i % 2 == 0 {
-                let expr_idx = i % uwu_expressions.len();
-                let words: Vec<&str> = sentence_str.split_whitespace().collect();
-                if words.len() > 3 {
-                    let mid_point = words.len() / 2;
-                    let first_half = words[..mid_point].join(" ");
-                    let second_half = words[mid_point..].join(" ");
-                    sentence_str =
-                        format!("{}{}{}", first_half, uwu_expressions[expr_idx], second_half);
-                }
-            }
-
-            uwu_sentences.push(sentence_str);
-        }
-    }
-
-    let mut final_result = uwu_sentences.join(". ");
-
-    // Clean up and add ending expression
-    if !final_result.is_empty() {
-        final_result = final_result.trim().to_string();
-        if !final_result.ends_with("uwu")
-            && !final_result.ends_with("owo")
-            && !final_result.ends_with(">w<")
-        {
-            final_result.push_str(" uwu");
-        }
-    }
-
-    final_result
+/// Take a leading `--level <1-3>` (or `--level=<1-3>`) off the text. A named
+/// flag, so text that starts with a number (`-uwu 3 cats`) stays text.
+pub fn take_level_flag(text: &str) -> Result<(u8, &str), String> {
+    let Some((level, rest)) = take_flag(text, "level") else {
+        return Ok((1, text.trim_start()));
+    };
+    level
+        .parse()
+        .ok()
+        .filter(|level| (1..=3).contains(level))
+        .map(|level| (level, rest))
+        .ok_or_else(|| "❌ `--level` takes 1, 2 or 3.".to_string())
 }
 
 /// Transform text into uwu language, or reply to a message to uwuify it
 #[poise::command(prefix_command, slash_command)]
 pub async fn uwu(
     ctx: Context<'_>,
-    #[description = "Text to uwuify (leave empty to uwuify replied message)"]
+    #[description = "Text to uwuify, starting with --level 2 or 3 for more chaos (empty: replied message)"]
     #[rest]
     text: Option<String>,
 ) -> Result<(), Error> {
     log::info!("UwU command called by {}", ctx.author().name);
 
-    let (text_to_uwuify, is_reply) = if let Some(text) = text {
-        // User provided text directly
-        (text, false)
-    } else {
-        // Check if this is a prefix command with a replied message
-        match ctx {
-            poise::Context::Prefix(prefix_ctx) => {
-                if let Some(replied_message) = prefix_ctx.msg.referenced_message.as_ref() {
-                    // Delete the invoker's message when replying to another message
-                    if let Err(e) = prefix_ctx.msg.delete(&ctx.http()).await {
-                        log::warn!("Failed to delete invoker's message: {}", e);
-                    }
-                    (replied_message.content.clone(), true)
-                } else {
-                    ctx.say(
-                        "❌ Please provide text to uwuify or reply to a message with this command!",
-                    )
-                    .await?;
-                    return Ok(());
-                }
-            }
-            _ => {
-                ctx.say("❌ Please provide text to uwuify! (Message replies only work with prefix commands)")
-                    .await?;
-                return Ok(());
-            }
+    let (level, text) = match text.as_deref().map(take_level_flag) {
+        Some(Ok((level, text))) => (level, (!text.is_empty()).then(|| text.to_string())),
+        Some(Err(message)) => {
+            ctx.say(message).await?;
+            return Ok(());
         }
+        None => (1, None),
     };
-
-    if text_to_uwuify.trim().is_empty() {
-        ctx.say("❌ Cannot uwuify empty text!").await?;
-        return Ok(());
-    }
-
-    let uwuified = uwuify(&text_to_uwuify);
-
-    // Create response with user mention if replying to someone else's message
-    let response = if is_reply {
-        match ctx {
-            poise::Context::Prefix(prefix_ctx) => {
-                if let Some(replied_message) = prefix_ctx.msg.referenced_message.as_ref() {
-                    if replied_message.author.id != ctx.author().id {
-                        format!("*{} says:*\n{}", replied_message.author.name, uwuified)
-                    } else {
-                        uwuified
-                    }
-                } else {
-                    uwuified
-                }
-            }
-            _ => uwuified,
-        }
-    } else {
-        uwuified
-    };
-
-    if let Err(e) = ctx.say(response).await {
-        ctx.say(format!("❌ {e}")).await?;
-    }
-
-    Ok(())
+    run_transform(ctx, text, "uwuify", |text| {
+        uwuify(text, level, &mut rand::thread_rng())
+    })
+    .await
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::utils::random::SeededRng;
+
+    #[test]
+    fn test_take_level_flag() {
+        assert_eq!(take_level_flag("3 cats"), Ok((1, "3 cats")));
+        assert_eq!(take_level_flag("--level 3 cats"), Ok((3, "cats")));
+        assert_eq!(take_level_flag("--LEVEL=2 cats"), Ok((2, "cats")));
+        assert_eq!(take_level_flag("--level 2"), Ok((2, "")));
+        assert_eq!(
+            take_level_flag("hello --level 3"),
+            Ok((1, "hello --level 3"))
+        );
+        assert!(take_level_flag("--level 4 cats").is_err());
+        assert!(take_level_flag("--level cats").is_err());
+    }
 
     #[test]
     fn test_uwuify_basic() {
         let input = "Hello world! This is a test.";
-        let result = uwuify(input);
+        let result = uwuify(input, 1, &mut SeededRng::new(1));
 
         // Check that basic transformations work
         assert!(result.contains("w")); // r/l -> w
@@ -149,7 +72,7 @@ mod tests {
     #[test]
     fn test_uwuify_specific_patterns() {
         let input = "The cat loves running";
-        let result = uwuify(input);
+        let result = uwuify(input, 1, &mut SeededRng::new(1));
 
         // Check specific transformations
         assert!(result.contains("Teh")); // The -> Teh
@@ -159,14 +82,14 @@ mod tests {
 
     #[test]
     fn test_uwuify_empty_string() {
-        let result = uwuify("");
+        let result = uwuify("", 1, &mut SeededRng::new(1));
         assert_eq!(result, "");
     }
 
     #[test]
     fn test_uwuify_preserves_capitalization() {
         let input = "Hello World";
-        let result = uwuify(input);
+        let result = uwuify(input, 1, &mut SeededRng::new(1));
 
         // Should preserve some capitalization structure
         assert!(!result.is_empty());
@@ -1,6 +1,12 @@
 use crate::{Context, Error};
 use poise::serenity_prelude as serenity;
 use rand::seq::SliceRandom;
+use rand::Rng;
+
+/// Pick the member who gets to be "your mom".
+fn pick_member<'a, T, R: Rng + ?Sized>(members: &'a [T], rng: &mut R) -> Option<&'a T> {
+    members.choose(rng)
+}
 
 /// Displays the profile picture of a random server member
 #[poise::command(prefix_command, slash_command)]
@@ -42,10 +48,7 @@ pub async fn yourmom(ctx: Context<'_>) -> Result<(), Error> {
     }
 
     // Select a random member
-    let random_member = {
-        let mut rng = rand::thread_rng();
-        real_members.choose(&mut rng)
-    };
+    let random_member = pick_member(&real_members, &mut rand::thread_rng());
 
     let random_member = match random_member {
         Some(member) => member,
@@ -89,6 +92,18 @@ pub async fn yourmom(ctx: Context<'_>) -> Result<(), Error> {
 
 #[cfg(test)]
 mod tests {
+    use super::*;
+    use crate::utils::random::SeededRng;
+
+    #[test]
+    fn test_pick_member_with_seed() {
+        let members = ["alice", "bob", "carol", "dave"];
+        let pick = |seed| pick_member(&members, &mut SeededRng::new(seed)).copied();
+        assert_eq!(pick(3), pick(3));
+        assert!(pick(3).is_some());
+        assert_eq!(pick_member::<&str, _>(&[], &mut SeededRng::new(3)), None);
+    }
+
     #[test]
     fn test_yourmom_command_exists() {
         // This is a basic test to ensure the command function exists
@@ -0,0 +1,488 @@
+//! Validation of the stored settings. The bot starts with whatever is on
+//! disk, so a hand-edited file or a channel deleted while the bot was offline
+//! would otherwise only show up as a feature quietly misbehaving.
+//!
+//! Every store with settings worth checking has a validator in
+//! [`VALIDATORS`]. The checks run once the cache is ready after startup and
+//! on `-configcheck`. Whatever fails is moved to [`QUARANTINE_FILE`] and
+//! treated as unset, and the owner gets the report. Files that couldn't be
+//! parsed at all were already moved aside by [`storage`], and entries a
+//! store couldn't read on load were quarantined by it through
+//! [`quarantine_unreadable`]; both are reported alongside.
+
+use crate::commands::{
+    automod, autopublish, autoreact, birthday, boost, bumper, color, digest, invites,
+    poll_templates, streak,
+};
+use crate::guild_config;
+use crate::storage;
+use crate::utils::permissions::is_not_found;
+use crate::utils::send_dm_to_deekahy;
+use chrono::{DateTime, Utc};
+use parking_lot::{Mutex, RwLock};
+use poise::serenity_prelude as serenity;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::{BTreeSet, HashMap, HashSet};
+use std::fmt;
+use std::sync::atomic::{AtomicBool, Ordering};
+
+/// Where quarantined settings are kept, so nothing is lost for good.
+const QUARANTINE_FILE: &str = "quarantine.json";
+
+/// Oldest entries are dropped past this many.
+const MAX_QUARANTINED: usize = 500;
+
+/// Reports longer than this are cut short; the log has all of it.
+const MAX_REPORT_CHARS: usize = 1800;
+
+/// Checks one store: `quarantine` says whether to unset what's wrong and
+/// save, or only report it.
+pub type Validator = fn(&LiveIds, bool) -> Vec<ConfigProblem>;
+
+/// The stores that are checked, named as in
+/// [`crate::commands::reload_config::RELOADERS`].
+pub(crate) const VALIDATORS: &[(&str, Validator)] = &[
+    ("Server settings", guild_config::check_guild_configs),
+    ("Color roles", color::check_color_roles),
+    ("Digest", digest::check_digests),
+    ("Automod", automod::check_automod),
+    ("Auto-react", autoreact::check_rules),
+    ("Auto-publish", autopublish::check_autopublish),
+    ("Streaks", streak::check_streaks),
+    ("Birthdays", birthday::check_birthdays),
+    ("Boosts", boost::check_boosts),
+    ("Bumper", bumper::check_bumpers),
+    ("Invites", invites::check_invites),
+    ("Poll templates", poll_templates::check_poll_templates),
+];
+
+/// Entries quarantined on load by [`quarantine_unreadable`], waiting for
+/// the next report.
+static UNREADABLE_ENTRIES: Mutex<Vec<ConfigProblem>> = Mutex::new(Vec::new());
+
+/// One thing wrong with a stored setting.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ConfigProblem {
+    /// The store's name in [`VALIDATORS`], or the file name of one that
+    /// couldn't be parsed. Filled in by the runner.
+    pub store: String,
+    pub guild_id: Option<u64>,
+    pub field: String,
+    pub problem: String,
+    /// The setting that was set aside.
+    pub value: Value,
+    /// Set for a channel that isn't in the cache, which is confirmed with
+    /// Discord before it counts as deleted.
+    channel_id: Option<u64>,
+}
+
+impl ConfigProblem {
+    /// A setting with a value that can't work.
+    pub fn invalid(
+        guild_id: u64,
+        field: impl Into<String>,
+        problem: impl Into<String>,
+        value: impl Serialize,
+    ) -> Self {
+        Self {
+            store: String::new(),
+            guild_id: Some(guild_id),
+            field: field.into(),
+            problem: problem.into(),
+            value: serde_json::to_value(value).unwrap_or(Value::Null),
+            channel_id: None,
+        }
+    }
+
+    /// A setting pointing at a channel that was deleted.
+    pub fn deleted_channel(guild_id: u64, field: impl Into<String>, channel_id: u64) -> Self {
+        Self {
+            channel_id: Some(channel_id),
+            ..Self::invalid(
+                guild_id,
+                field,
+                format!("channel {} was deleted", channel_id),
+                channel_id,
+            )
+        }
+    }
+
+    /// A setting pointing at a role that was deleted.
+    pub fn deleted_role(guild_id: u64, field: impl Into<String>, role_id: u64) -> Self {
+        Self::invalid(
+            guild_id,
+            field,
+            format!("role {} was deleted", role_id),
+            role_id,
+        )
+    }
+
+    /// A guild's entry that couldn't be read at all, keyed by `key` in the
+    /// file.
+    pub fn unreadable_entry(key: &str, error: impl fmt::Display, value: Value) -> Self {
+        Self {
+            store: String::new(),
+            guild_id: key.parse().ok(),
+            field: "(whole entry)".to_string(),
+            problem: format!("couldn't be read ({})", error),
+            value,
+            channel_id: None,
+        }
+    }
+
+    fn unreadable(store: storage::UnreadableStore) -> Self {
+        Self {
+            store: store.file_name,
+            guild_id: None,
+            field: "(whole file)".to_string(),
+            problem: format!(
+                "couldn't be read ({}), moved to {}",
+                store.error, store.moved_to
+            ),
+            value: Value::Null,
+            channel_id: None,
+        }
+    }
+}
+
+impl fmt::Display for ConfigProblem {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "**{}**", self.store)?;
+        if let Some(guild_id) = self.guild_id {
+            write!(f, " · server {}", guild_id)?;
+        }
+        write!(f, " · `{}`: {}", self.field, self.problem)
+    }
+}
+
+#[derive(Default)]
+struct GuildIds {
+    channels: HashSet<u64>,
+    roles: HashSet<u64>,
+}
+
+/// The channels and roles of the guilds in the cache, to tell which stored
+/// IDs were deleted. Guilds that aren't cached are never judged.
+#[derive(Default)]
+pub struct LiveIds {
+    guilds: HashMap<u64, GuildIds>,
+}
+
+impl LiveIds {
+    fn from_cache(cache: &serenity::Cache) -> Self {
+        let guilds = cache
+            .guilds()
+            .into_iter()
+            .filter_map(|guild_id| {
+                let guild = cache.guild(guild_id)?;
+                let channels = guild
+                    .channels
+                    .keys()
+                    .copied()
+                    .chain(guild.threads.iter().map(|thread| thread.id))
+                    .map(|id| id.get())
+                    .collect();
+                let roles = guild.roles.keys().map(|id| id.get()).collect();
+                Some((guild_id.get(), GuildIds { channels, roles }))
+            })
+            .collect();
+        Self { guilds }
+    }
+
+    /// Whether a cached guild no longer has the channel.
+    pub fn channel_gone(&self, guild_id: u64, channel_id: u64) -> bool {
+        self.guilds
+            .get(&guild_id)
+            .is_some_and(|guild| !guild.channels.contains(&channel_id))
+    }
+
+    /// Whether a cached guild no longer has the role.
+    pub fn role_gone(&self, guild_id: u64, role_id: u64) -> bool {
+        self.guilds
+            .get(&guild_id)
+            .is_some_and(|guild| !guild.roles.contains(&role_id))
+    }
+
+    fn keep_channel(&mut self, guild_id: u64, channel_id: u64) {
+        if let Some(guild) = self.guilds.get_mut(&guild_id) {
+            guild.channels.insert(channel_id);
+        }
+    }
+
+    #[cfg(test)]
+    pub fn with_guild(mut self, guild_id: u64, channels: &[u64], roles: &[u64]) -> Self {
+        self.guilds.insert(
+            guild_id,
+            GuildIds {
+                channels: channels.iter().copied().collect(),
+                roles: roles.iter().copied().collect(),
+            },
+        );
+        self
+    }
+}
+
+/// Run `validate` on a copy of a store's data. If anything's wrong and
+/// `quarantine` is set, the fixed copy replaces the data and is saved.
+pub fn check_store<T: Clone>(
+    store: &RwLock<T>,
+    quarantine: bool,
+    validate: impl FnOnce(&mut T) -> Vec<ConfigProblem>,
+    save: impl FnOnce(&T),
+) -> Vec<ConfigProblem> {
+    let mut data = store.write();
+    let mut checked = data.clone();
+    let problems = validate(&mut checked);
+    if quarantine && !problems.is_empty() {
+        save(&checked);
+        *data = checked;
+    }
+    problems
+}
+
+fn run_validators(
+    validators: &[(&str, Validator)],
+    live: &LiveIds,
+    quarantine: bool,
+) -> Vec<ConfigProblem> {
+    validators
+        .iter()
+        .flat_map(|(name, validate)| {
+            let mut problems = validate(live, quarantine);
+            problems.sort_by(|a, b| (a.guild_id, &a.field).cmp(&(b.guild_id, &b.field)));
+            for problem in &mut problems {
+                problem.store = name.to_string();
+            }
+            problems
+        })
+        .collect()
+}
+
+#[derive(Serialize, Deserialize)]
+struct QuarantinedSetting {
+    at: DateTime<Utc>,
+    store: String,
+    guild_id: Option<u64>,
+    field: String,
+    problem: String,
+    value: Value,
+}
+
+/// Add the settings that were set aside to [`QUARANTINE_FILE`].
+fn quarantine(problems: &[ConfigProblem]) {
+    if problems.is_empty() {
+        return;
+    }
+    let mut entries: Vec<QuarantinedSetting> = storage::load(QUARANTINE_FILE);
+    let now = Utc::now();
+    entries.extend(problems.iter().map(|problem| QuarantinedSetting {
+        at: now,
+        store: problem.store.clone(),
+        guild_id: problem.guild_id,
+        field: problem.field.clone(),
+        problem: problem.problem.clone(),
+        value: problem.value.clone(),
+    }));
+    let excess = entries.len().saturating_sub(MAX_QUARANTINED);
+    entries.drain(..excess);
+    if let Err(e) = storage::save(QUARANTINE_FILE, &entries) {
+        log::error!("Failed to save quarantined settings: {}", e);
+    }
+}
+
+/// Quarantine the entries a store couldn't read while loading, before
+/// anything saves over them, and keep them for the next report.
+pub fn quarantine_unreadable(store: &str, mut problems: Vec<ConfigProblem>) {
+    for problem in &mut problems {
+        problem.store = store.to_string();
+    }
+    quarantine(&problems);
+    UNREADABLE_ENTRIES.lock().extend(problems);
+}
+
+/// Check every store, quarantining what's wrong. Returns what was found.
+pub async fn run_checks(ctx: &serenity::Context) -> Vec<ConfigProblem> {
+    let mut live = LiveIds::from_cache(&ctx.cache);
+
+    // A channel missing from the cache can still be an archived thread, so
+    // only a channel Discord doesn't know either counts as deleted
+    let suspects: BTreeSet<(u64, u64)> = run_validators(VALIDATORS, &live, false)
+        .into_iter()
+        .filter_map(|problem| Some((problem.guild_id?, problem.channel_id?)))
+        .collect();
+    for (guild_id, channel_id) in suspects {
+        match ctx
+            .http
+            .get_channel(serenity::ChannelId::new(channel_id))
+            .await
+        {
+            Err(e) if is_not_found(&e) => {}
+            Err(e) => {
+                log::warn!("Couldn't check channel {}: {}", channel_id, e);
+                live.keep_channel(guild_id, channel_id);
+            }
+            Ok(_) => live.keep_channel(guild_id, channel_id),
+        }
+    }
+
+    let found = run_validators(VALIDATORS, &live, true);
+    quarantine(&found);
+    let mut problems: Vec<ConfigProblem> = storage::take_unreadable()
+        .into_iter()
+        .map(ConfigProblem::unreadable)
+        .collect();
+    problems.append(&mut UNREADABLE_ENTRIES.lock());
+    problems.extend(found);
+    for problem in &problems {
+        log::warn!("Config check: {}", problem);
+    }
+    problems
+}
+
+/// The report for the owner.
+pub fn report(problems: &[ConfigProblem]) -> String {
+    if problems.is_empty() {
+        return "✅ All stored settings look fine.".to_string();
+    }
+    let mut report = format!(
+        "⚠️ Found {} problem(s) in the stored settings. The bad entries were set aside in \
+         `{}` and are treated as unset:",
+        problems.len(),
+        QUARANTINE_FILE
+    );
+    for (shown, problem) in problems.iter().enumerate() {
+        let line = format!("\n• {}", problem);
+        if report.chars().count() + line.chars().count() > MAX_REPORT_CHARS {
+            report.push_str(&format!(
+                "\n…and {} more, see the log.",
+                problems.len() - shown
+            ));
+            break;
+        }
+        report.push_str(&line);
+    }
+    report
+}
+
+static STARTUP_CHECK_DONE: AtomicBool = AtomicBool::new(false);
+
+/// Run the checks once the cache is first ready, and DM the owner if
+/// anything was wrong. Later cache-ready events (after a reconnect) are
+/// ignored.
+pub async fn check_on_startup(ctx: &serenity::Context) {
+    if STARTUP_CHECK_DONE.swap(true, Ordering::Relaxed) {
+        return;
+    }
+    let problems = run_checks(ctx).await;
+    if problems.is_empty() {
+        log::info!("Config check found no problems");
+        return;
+    }
+    if let Err(e) = send_dm_to_deekahy(&ctx.http, &report(&problems)).await {
+        log::warn!("Failed to report config problems: {}", e);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::commands::reload_config::RELOADERS;
+
+    #[test]
+    fn test_validators_name_known_stores() {
+        for (name, _) in VALIDATORS {
+            assert!(
+                RELOADERS.iter().any(|(store, _)| store == name),
+                "validator for unknown store {name}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_live_ids() {
+        let live = LiveIds::default().with_guild(1, &[10], &[20]);
+        assert!(!live.channel_gone(1, 10));
+        assert!(live.channel_gone(1, 11));
+        assert!(!live.role_gone(1, 20));
+        assert!(live.role_gone(1, 21));
+        // Guilds that aren't cached aren't judged
+        assert!(!live.channel_gone(2, 11));
+        assert!(!live.role_gone(2, 21));
+
+        let mut live = live;
+        live.keep_channel(1, 11);
+        assert!(!live.channel_gone(1, 11));
+    }
+
+    #[test]
+    fn test_run_validators_names_and_orders_problems() {
+        let problems = run_validators(
+            &[
+                ("First", |_, _| {
+                    vec![
+                        ConfigProblem::deleted_channel(2, "channel", 5),
+                        ConfigProblem::invalid(1, "hour", "must be 0-23", 25),
+                    ]
+                }),
+                ("Second", |_, _| {
+                    vec![ConfigProblem::deleted_role(1, "role", 6)]
+                }),
+            ],
+            &LiveIds::default(),
+            false,
+        );
+        let lines: Vec<String> = problems.iter().map(ToString::to_string).collect();
+        assert_eq!(
+            lines,
+            [
+                "**First** · server 1 · `hour`: must be 0-23",
+                "**First** · server 2 · `channel`: channel 5 was deleted",
+                "**Second** · server 1 · `role`: role 6 was deleted",
+            ]
+        );
+        assert_eq!(problems[1].channel_id, Some(5));
+        assert_eq!(problems[2].value, serde_json::json!(6));
+    }
+
+    #[test]
+    fn test_check_store_only_changes_when_quarantining() {
+        let store = RwLock::new(vec![1, -2, 3]);
+        let validate = |data: &mut Vec<i32>| {
+            let problems = data
+                .iter()
+                .filter(|n| **n < 0)
+                .map(|n| ConfigProblem::invalid(1, "n", "negative", n))
+                .collect();
+            data.retain(|n| *n >= 0);
+            problems
+        };
+        let mut saved = None;
+
+        assert_eq!(check_store(&store, false, validate, |_| {}).len(), 1);
+        assert_eq!(*store.read(), [1, -2, 3]);
+
+        assert_eq!(
+            check_store(&store, true, validate, |data| saved = Some(data.clone())).len(),
+            1
+        );
+        assert_eq!(*store.read(), [1, 3]);
+        assert_eq!(saved, Some(vec![1, 3]));
+    }
+
+    #[test]
+    fn test_report() {
+        assert_eq!(report(&[]), "✅ All stored settings look fine.");
+
+        let mut problem = ConfigProblem::deleted_channel(7, "channel", 5);
+        problem.store = "Boosts".to_string();
+        let text = report(&[problem.clone()]);
+        assert!(text.starts_with("⚠️ Found 1 problem(s)"));
+        assert!(text.ends_with("\n• **Boosts** · server 7 · `channel`: channel 5 was deleted"));
+
+        let many = vec![problem; 100];
+        let text = report(&many);
+        assert!(text.chars().count() <= MAX_REPORT_CHARS + 40);
+        assert!(text.ends_with("more, see the log."), "{text}");
+    }
+}
@@ -0,0 +1,596 @@
+//! Every DM the bot sends on its own (reminders, parking, ...) goes through
+//! here, so a closed inbox doesn't fail silently: each attempt is written to
+//! a bounded log per server that `-dmlog` shows, and attempts that failed
+//! for a reason that might pass (Discord hiccups, rate limits, network
+//! trouble) are retried a few times with growing gaps. Callers send each DM
+//! once and leave retrying to this queue. The retry queue is kept in memory,
+//! so DMs still waiting for a retry are dropped on restart.
+
+use crate::storage;
+use chrono::{DateTime, Duration, Utc};
+use parking_lot::{Mutex, RwLock};
+use poise::serenity_prelude as serenity;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, VecDeque};
+use std::future::Future;
+use std::sync::Arc;
+use tokio::time::{interval, Duration as TokioDuration};
+
+pub const DM_LOG_FILE: &str = "dm_log.json";
+
+/// Attempts kept per server unless it picked another number with
+/// `-dmlog keep`; the oldest are dropped past it.
+pub const DEFAULT_LOG_ENTRIES: usize = 100;
+
+/// Most attempts a server can keep.
+pub const MAX_LOG_ENTRIES: usize = 500;
+
+/// Where DMs that aren't about any server (e.g. parking) are logged.
+const NO_GUILD: u64 = 0;
+
+/// Wait before each retry; a DM is given up on after the last one.
+const RETRY_BACKOFF_SECS: [i64; 3] = [60, 5 * 60, 15 * 60];
+
+/// How often the retry queue is checked.
+const RETRY_CHECK_SECS: u64 = 30;
+
+/// How one attempt at a DM went.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(tag = "result", rename_all = "lowercase")]
+pub enum DmOutcome {
+    Sent,
+    Failed { error: String, will_retry: bool },
+}
+
+/// One attempt at a DM, as kept in the log.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct DmRecord {
+    pub at: DateTime<Utc>,
+    pub user_id: u64,
+    /// The feature that sent it, e.g. `reminder` or `parking`.
+    pub source: String,
+    /// The server the DM is about, if any.
+    pub guild_id: Option<u64>,
+    /// 1 for the first try, 2 for the first retry, ...
+    pub attempt: u32,
+    pub outcome: DmOutcome,
+}
+
+/// The attempts about one server.
+#[derive(Serialize, Deserialize, Default, Clone)]
+struct GuildDmLog {
+    /// How many attempts to keep, if not [`DEFAULT_LOG_ENTRIES`]. 0 turns
+    /// the log off for the server.
+    #[serde(default)]
+    limit: Option<usize>,
+    /// Oldest first.
+    #[serde(default)]
+    entries: VecDeque<DmRecord>,
+}
+
+impl GuildDmLog {
+    fn limit(&self) -> usize {
+        self.limit
+            .unwrap_or(DEFAULT_LOG_ENTRIES)
+            .min(MAX_LOG_ENTRIES)
+    }
+
+    fn trim(&mut self) {
+        while self.entries.len() > self.limit() {
+            self.entries.pop_front();
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Default, Clone)]
+struct DmLog {
+    /// By server ID, with DMs about no server under [`NO_GUILD`].
+    #[serde(default)]
+    guilds: HashMap<u64, GuildDmLog>,
+    /// The single bot-wide log from before it was split per server, sorted
+    /// into `guilds` on load.
+    #[serde(default, skip_serializing)]
+    entries: VecDeque<DmRecord>,
+}
+
+impl DmLog {
+    fn load() -> Self {
+        let mut log: Self = storage::load(DM_LOG_FILE);
+        log.sort_old_entries();
+        log
+    }
+
+    /// Move the entries of a log from before the split into their servers.
+    fn sort_old_entries(&mut self) {
+        for record in std::mem::take(&mut self.entries) {
+            self.push(record);
+        }
+    }
+
+    fn push(&mut self, record: DmRecord) {
+        let guild = self
+            .guilds
+            .entry(record.guild_id.unwrap_or(NO_GUILD))
+            .or_default();
+        if guild.limit() == 0 {
+            return;
+        }
+        guild.entries.push_back(record);
+        guild.trim();
+    }
+}
+
+/// Why a DM didn't go through, and whether trying again might help.
+#[derive(Debug, Clone, PartialEq)]
+pub struct DmFailure {
+    pub error: String,
+    pub transient: bool,
+}
+
+impl From<&serenity::Error> for DmFailure {
+    fn from(error: &serenity::Error) -> Self {
+        Self {
+            error: error.to_string(),
+            transient: is_transient(error),
+        }
+    }
+}
+
+/// Server errors, rate limits and failed connections may pass; anything
+/// else (closed DMs, unknown users) will fail the same way again.
+fn is_transient(error: &serenity::Error) -> bool {
+    match error {
+        serenity::Error::Http(serenity::HttpError::UnsuccessfulRequest(response)) => {
+            response.status_code.is_server_error()
+                || response.status_code == serenity::StatusCode::TOO_MANY_REQUESTS
+        }
+        serenity::Error::Http(serenity::HttpError::Request(_)) => true,
+        _ => false,
+    }
+}
+
+/// A DM waiting for its next try.
+#[derive(Clone, Debug)]
+struct PendingDm<M> {
+    user_id: u64,
+    source: String,
+    guild_id: Option<u64>,
+    message: M,
+    /// Tries made so far.
+    attempts: u32,
+    retry_at: DateTime<Utc>,
+}
+
+/// DMs to try again, each no more than [`RETRY_BACKOFF_SECS`]`.len()` times.
+#[derive(Debug)]
+struct RetryQueue<M> {
+    pending: Vec<PendingDm<M>>,
+}
+
+impl<M> Default for RetryQueue<M> {
+    fn default() -> Self {
+        Self {
+            pending: Vec::new(),
+        }
+    }
+}
+
+impl<M> RetryQueue<M> {
+    /// Queue a DM whose latest try failed, after the backoff for the number
+    /// of tries it has had. Returns false when it's out of retries.
+    fn schedule(&mut self, mut dm: PendingDm<M>, now: DateTime<Utc>) -> bool {
+        let Some(backoff) = RETRY_BACKOFF_SECS.get(dm.attempts as usize - 1) else {
+            return false;
+        };
+        dm.retry_at = now + Duration::seconds(*backoff);
+        self.pending.push(dm);
+        true
+    }
+
+    /// Take out the DMs due for a retry.
+    fn take_due(&mut self, now: DateTime<Utc>) -> Vec<PendingDm<M>> {
+        let (due, waiting) = std::mem::take(&mut self.pending)
+            .into_iter()
+            .partition(|dm| dm.retry_at <= now);
+        self.pending = waiting;
+        due
+    }
+}
+
+lazy_static::lazy_static! {
+    static ref DM_LOG: RwLock<DmLog> = RwLock::new(DmLog::load());
+    /// Held while saving the log, so snapshots reach the file in order
+    /// without keeping readers of the log waiting on the disk.
+    static ref DM_LOG_SAVE: Mutex<()> = Mutex::new(());
+    static ref RETRY_QUEUE: Mutex<RetryQueue<serenity::CreateMessage>> =
+        Mutex::new(RetryQueue::default());
+}
+
+/// Change the log with `update` and save it.
+fn update_log<T>(update: impl FnOnce(&mut DmLog) -> T) -> Result<T, crate::Error> {
+    let _saving = DM_LOG_SAVE.lock();
+    let (result, snapshot) = {
+        let mut log = DM_LOG.write();
+        let result = update(&mut log);
+        (result, log.clone())
+    };
+    storage::save(DM_LOG_FILE, &snapshot)?;
+    Ok(result)
+}
+
+fn record(record: DmRecord) {
+    if let Err(e) = update_log(|log| log.push(record)) {
+        log::error!("Failed to save DM log: {}", e);
+    }
+}
+
+/// Keep the latest `limit` attempts for a server, 0 to stop logging its DMs.
+pub fn set_log_limit(guild_id: u64, limit: usize) -> Result<(), crate::Error> {
+    update_log(|log| {
+        let guild = log.guilds.entry(guild_id).or_default();
+        guild.limit = Some(limit.min(MAX_LOG_ENTRIES));
+        guild.trim();
+    })
+}
+
+/// How many attempts are kept for a server.
+pub fn log_limit(guild_id: u64) -> usize {
+    DM_LOG
+        .read()
+        .guilds
+        .get(&guild_id)
+        .map_or(DEFAULT_LOG_ENTRIES, GuildDmLog::limit)
+}
+
+/// The record of an attempt, and the DM to queue if it's worth retrying.
+fn settle<M>(
+    mut dm: PendingDm<M>,
+    result: Result<(), DmFailure>,
+    queue: &mut RetryQueue<M>,
+    now: DateTime<Utc>,
+) -> DmRecord {
+    dm.attempts += 1;
+    let (user_id, source, guild_id, attempt) =
+        (dm.user_id, dm.source.clone(), dm.guild_id, dm.attempts);
+    let outcome = match result {
+        Ok(()) => DmOutcome::Sent,
+        Err(failure) => {
+            let will_retry = failure.transient && queue.schedule(dm, now);
+            DmOutcome::Failed {
+                error: failure.error,
+                will_retry,
+            }
+        }
+    };
+    DmRecord {
+        at: now,
+        user_id,
+        source,
+        guild_id,
+        attempt,
+        outcome,
+    }
+}
+
+/// Send `message` to a user, logging the attempt as coming from `source`
+/// and queueing a retry if it failed for a reason that may pass.
+pub async fn send_dm(
+    http: &serenity::Http,
+    user_id: serenity::UserId,
+    source: &str,
+    guild_id: Option<serenity::GuildId>,
+    message: serenity::CreateMessage,
+) -> Result<serenity::Message, serenity::Error> {
+    let result = user_id.direct_message(http, message.clone()).await;
+    let dm = PendingDm {
+        user_id: user_id.get(),
+        source: source.to_string(),
+        guild_id: guild_id.map(|id| id.get()),
+        message,
+        attempts: 0,
+        retry_at: Utc::now(),
+    };
+    let entry = {
+        let outcome = result.as_ref().map(|_| ()).map_err(DmFailure::from);
+        settle(dm, outcome, &mut RETRY_QUEUE.lock(), Utc::now())
+    };
+    if let DmOutcome::Failed { error, will_retry } = &entry.outcome {
+        log::warn!(
+            "Failed to DM user {} ({}): {}{}",
+            user_id,
+            source,
+            error,
+            if *will_retry { ", will retry" } else { "" }
+        );
+    }
+    record(entry);
+    result
+}
+
+/// [`send_dm`] for a plain text message.
+pub async fn send_dm_text(
+    http: &serenity::Http,
+    user_id: serenity::UserId,
+    source: &str,
+    text: &str,
+) -> Result<serenity::Message, serenity::Error> {
+    send_dm(
+        http,
+        user_id,
+        source,
+        None,
+        serenity::CreateMessage::new().content(text),
+    )
+    .await
+}
+
+/// Retry every due DM with `send`, requeueing the ones that fail again.
+/// Returns a record per attempt.
+async fn retry_due<M, S, Fut>(
+    queue: &Mutex<RetryQueue<M>>,
+    now: DateTime<Utc>,
+    mut send: S,
+) -> Vec<DmRecord>
+where
+    S: FnMut(&PendingDm<M>) -> Fut,
+    Fut: Future<Output = Result<(), DmFailure>>,
+{
+    let due = queue.lock().take_due(now);
+    let mut records = Vec::with_capacity(due.len());
+    for dm in due {
+        let result = send(&dm).await;
+        records.push(settle(dm, result, &mut queue.lock(), now));
+    }
+    records
+}
+
+/// Start the background task that retries failed DMs.
+pub fn start_dm_retry_task(http: Arc<serenity::Http>) {
+    tokio::spawn(async move {
+        let mut interval = interval(TokioDuration::from_secs(RETRY_CHECK_SECS));
+
+        loop {
+            interval.tick().await;
+
+            let records = retry_due(&RETRY_QUEUE, Utc::now(), |dm| {
+                let http = http.clone();
+                let user_id = serenity::UserId::new(dm.user_id);
+                let message = dm.message.clone();
+                async move {
+                    user_id
+                        .direct_message(&http, message)
+                        .await
+                        .map(|_| ())
+                        .map_err(|e| DmFailure::from(&e))
+                }
+            })
+            .await;
+            for entry in records {
+                match &entry.outcome {
+                    DmOutcome::Sent => log::info!(
+                        "Retried DM to user {} ({}) went through",
+                        entry.user_id,
+                        entry.source
+                    ),
+                    DmOutcome::Failed { error, .. } => log::warn!(
+                        "Retried DM to user {} ({}) failed again: {}",
+                        entry.user_id,
+                        entry.source,
+                        error
+                    ),
+                }
+                record(entry);
+            }
+        }
+    });
+}
+
+/// The latest `limit` attempts, newest first, about one server or about
+/// any, and optionally for one user.
+pub fn recent_attempts(guild_id: Option<u64>, user_id: Option<u64>, limit: usize) -> Vec<DmRecord> {
+    let log = DM_LOG.read();
+    let mut attempts: Vec<&DmRecord> = log
+        .guilds
+        .iter()
+        .filter(|(id, _)| guild_id.is_none_or(|guild_id| **id == guild_id))
+        .flat_map(|(_, guild)| &guild.entries)
+        .filter(|entry| user_id.is_none_or(|id| entry.user_id == id))
+        .collect();
+    attempts.sort_by_key(|entry| std::cmp::Reverse(entry.at));
+    attempts.into_iter().take(limit).cloned().collect()
+}
+
+/// How many DMs are waiting for a retry.
+pub fn pending_retries() -> usize {
+    RETRY_QUEUE.lock().pending.len()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::cell::RefCell;
+
+    fn pending(user_id: u64, message: &'static str) -> PendingDm<&'static str> {
+        PendingDm {
+            user_id,
+            source: "reminder".to_string(),
+            guild_id: None,
+            message,
+            attempts: 0,
+            retry_at: Utc::now(),
+        }
+    }
+
+    fn failure(transient: bool) -> DmFailure {
+        DmFailure {
+            error: "nope".to_string(),
+            transient,
+        }
+    }
+
+    #[test]
+    fn test_settle_queues_only_transient_failures() {
+        let now = Utc::now();
+        let mut queue = RetryQueue::default();
+
+        let sent = settle(pending(1, "hi"), Ok(()), &mut queue, now);
+        assert_eq!(sent.outcome, DmOutcome::Sent);
+        assert_eq!(sent.attempt, 1);
+
+        // DMs closed: no point trying again
+        let closed = settle(pending(2, "hi"), Err(failure(false)), &mut queue, now);
+        assert_eq!(
+            closed.outcome,
+            DmOutcome::Failed {
+                error: "nope".to_string(),
+                will_retry: false
+            }
+        );
+        assert!(queue.pending.is_empty());
+
+        let flaky = settle(pending(3, "hi"), Err(failure(true)), &mut queue, now);
+        assert!(matches!(
+            flaky.outcome,
+            DmOutcome::Failed {
+                will_retry: true,
+                ..
+            }
+        ));
+        assert_eq!(queue.pending.len(), 1);
+        assert_eq!(
+            queue.pending[0].retry_at,
+            now + Duration::seconds(RETRY_BACKOFF_SECS[0])
+        );
+    }
+
+    #[tokio::test]
+    async fn test_retry_backoff_and_give_up() {
+        let start = Utc::now();
+        let queue = Mutex::new(RetryQueue::default());
+        let first = settle(
+            pending(7, "hi"),
+            Err(failure(true)),
+            &mut queue.lock(),
+            start,
+        );
+        assert_eq!(first.attempt, 1);
+
+        let sends = RefCell::new(Vec::new());
+        let always_failing = |dm: &PendingDm<&'static str>| {
+            sends.borrow_mut().push((dm.user_id, dm.message));
+            async { Err(failure(true)) }
+        };
+
+        // Nothing is due before the first backoff is up
+        let early = start + Duration::seconds(RETRY_BACKOFF_SECS[0] - 1);
+        assert!(retry_due(&queue, early, always_failing).await.is_empty());
+
+        // Each retry waits longer than the one before
+        let mut now = start;
+        for (retry, backoff) in RETRY_BACKOFF_SECS.iter().enumerate() {
+            now += Duration::seconds(*backoff);
+            let records = retry_due(&queue, now, always_failing).await;
+            assert_eq!(records.len(), 1);
+            assert_eq!(records[0].attempt, retry as u32 + 2);
+            let last_retry = retry == RETRY_BACKOFF_SECS.len() - 1;
+            assert_eq!(
+                records[0].outcome,
+                DmOutcome::Failed {
+                    error: "nope".to_string(),
+                    will_retry: !last_retry
+                }
+            );
+            if let Some(next) = RETRY_BACKOFF_SECS.get(retry + 1) {
+                assert_eq!(
+                    queue.lock().pending[0].retry_at,
+                    now + Duration::seconds(*next)
+                );
+            }
+        }
+
+        // Given up after three retries
+        assert!(queue.lock().pending.is_empty());
+        assert_eq!(sends.borrow().len(), RETRY_BACKOFF_SECS.len());
+        assert!(sends.borrow().iter().all(|sent| *sent == (7, "hi")));
+    }
+
+    #[tokio::test]
+    async fn test_retry_success_leaves_the_queue() {
+        let now = Utc::now();
+        let queue = Mutex::new(RetryQueue::default());
+        settle(pending(1, "a"), Err(failure(true)), &mut queue.lock(), now);
+        settle(pending(2, "b"), Err(failure(true)), &mut queue.lock(), now);
+
+        // The first goes through, the second fails for good this time
+        let later = now + Duration::seconds(RETRY_BACKOFF_SECS[0]);
+        let records = retry_due(&queue, later, |dm| {
+            let result = if dm.user_id == 1 {
+                Ok(())
+            } else {
+                Err(failure(false))
+            };
+            async move { result }
+        })
+        .await;
+        assert_eq!(records[0].outcome, DmOutcome::Sent);
+        assert_eq!(records[0].attempt, 2);
+        assert!(matches!(
+            records[1].outcome,
+            DmOutcome::Failed {
+                will_retry: false,
+                ..
+            }
+        ));
+        assert!(queue.lock().pending.is_empty());
+    }
+
+    fn sent(user_id: u64, guild_id: Option<u64>) -> DmRecord {
+        DmRecord {
+            at: Utc::now(),
+            user_id,
+            source: "parking".to_string(),
+            guild_id,
+            attempt: 1,
+            outcome: DmOutcome::Sent,
+        }
+    }
+
+    #[test]
+    fn test_log_is_bounded_per_guild() {
+        let mut log = DmLog::default();
+        for user_id in 0..DEFAULT_LOG_ENTRIES as u64 + 3 {
+            log.push(sent(user_id, None));
+        }
+        log.push(sent(1, Some(7)));
+        let direct = &log.guilds[&NO_GUILD].entries;
+        assert_eq!(direct.len(), DEFAULT_LOG_ENTRIES);
+        assert_eq!(direct.front().map(|e| e.user_id), Some(3));
+        // Another server's DMs don't push these out
+        assert_eq!(log.guilds[&7].entries.len(), 1);
+
+        // A server can keep fewer, or none
+        let guild = log.guilds.entry(7).or_default();
+        guild.limit = Some(0);
+        guild.trim();
+        log.push(sent(2, Some(7)));
+        assert!(log.guilds[&7].entries.is_empty());
+        log.guilds.get_mut(&7).unwrap().limit = Some(MAX_LOG_ENTRIES * 2);
+        assert_eq!(log.guilds[&7].limit(), MAX_LOG_ENTRIES);
+    }
+
+    #[test]
+    fn test_old_log_is_split_per_guild() {
+        let old = serde_json::json!({
+            "entries": [
+                serde_json::to_value(sent(1, Some(7))).unwrap(),
+                serde_json::to_value(sent(2, None)).unwrap(),
+            ]
+        });
+        let mut log: DmLog = serde_json::from_value(old).unwrap();
+        log.sort_old_entries();
+        assert_eq!(log.guilds[&7].entries[0].user_id, 1);
+        assert_eq!(log.guilds[&NO_GUILD].entries[0].user_id, 2);
+        let saved = serde_json::to_value(&log).unwrap();
+        assert!(saved.get("entries").is_none());
+    }
+}
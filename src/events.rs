@@ -0,0 +1,217 @@
+//! Handlers for gateway events that aren't tied to a command.
+
+use crate::commands::{
+    automod, autopublish, autoreact, boost, botreplies, flagtranslate, invites, remind, streak,
+    tictactoe,
+};
+use crate::config_check;
+use crate::guild_config::{self, SeedOutcome};
+use crate::update_check;
+use crate::utils::send_dm_to_deekahy;
+use crate::{Data, Error};
+use poise::serenity_prelude as serenity;
+use serenity::audit_log::{Action, MemberAction};
+
+/// Framework-wide event handler, registered in `lib.rs`.
+pub async fn event_handler(
+    ctx: &serenity::Context,
+    event: &serenity::FullEvent,
+    framework: poise::FrameworkContext<'_, Data, Error>,
+    data: &Data,
+) -> Result<(), Error> {
+    match event {
+        serenity::FullEvent::CacheReady { .. } => {
+            // Deleted channels can only be told once every guild is cached
+            let ctx = ctx.clone();
+            tokio::spawn(async move { config_check::check_on_startup(&ctx).await });
+        }
+        serenity::FullEvent::GuildCreate { guild, is_new } => {
+            // GuildCreate also fires for every guild on startup/resume; only
+            // `is_new == Some(true)` means the bot was actually just added.
+            let outcome = guild_config::ensure_guild_config(guild.id.get());
+            invites::cache_guild_invites(&ctx.http, guild.id).await;
+            if *is_new == Some(true) {
+                on_guild_join(ctx, guild, outcome).await;
+            }
+        }
+        // `unavailable` means a Discord outage, not that we were removed.
+        serenity::FullEvent::GuildDelete { incomplete, full } if !incomplete.unavailable => {
+            on_guild_leave(ctx, incomplete.id, full.as_ref()).await;
+        }
+        serenity::FullEvent::Message { new_message } => {
+            let prefix = guild_config::command_prefix(new_message.guild_id.map(|id| id.get()));
+            if automod::moderate_message(ctx, new_message, &prefix).await {
+                return Ok(());
+            }
+            autopublish::publish_message(ctx, new_message).await;
+            streak::record_message(ctx, new_message).await;
+            autoreact::apply_auto_reactions(ctx, new_message, &prefix).await;
+            let commands = &framework.options().commands;
+            let is_command = |word: &str| {
+                commands.iter().any(|command| {
+                    command.name == word || command.aliases.iter().any(|a| a == word)
+                })
+            };
+            botreplies::handle_mention(ctx, new_message, &prefix, is_command).await;
+        }
+        serenity::FullEvent::ReactionAdd { add_reaction } => {
+            if let Err(e) = flagtranslate::handle_flag_reaction(ctx, add_reaction).await {
+                log::warn!("Failed to handle flag reaction: {}", e);
+            }
+            if let Err(e) = remind::handle_cancel_reaction(ctx, add_reaction).await {
+                log::warn!("Failed to handle reminder cancel reaction: {}", e);
+            }
+        }
+        serenity::FullEvent::GuildMemberUpdate {
+            old_if_available,
+            event,
+            ..
+        } => {
+            boost::handle_member_update(ctx, old_if_available.as_ref(), event).await;
+        }
+        serenity::FullEvent::GuildMemberAddition { new_member } => {
+            invites::handle_member_join(ctx, new_member).await;
+        }
+        serenity::FullEvent::InviteCreate { data } => {
+            invites::handle_invite_create(data).await;
+        }
+        serenity::FullEvent::InviteDelete { data } => {
+            invites::handle_invite_delete(data).await;
+        }
+        serenity::FullEvent::ChannelDelete { channel, .. } => {
+            boost::forget_channel(channel.guild_id.get(), channel.id.get());
+        }
+        serenity::FullEvent::PresenceUpdate { new_data } => {
+            if let Err(e) = remind::handle_presence_update(new_data).await {
+                log::warn!("Failed to handle presence update: {}", e);
+            }
+        }
+        serenity::FullEvent::InteractionCreate { interaction } => {
+            if let Some(component) = interaction.as_message_component() {
+                on_component(ctx, component, data).await?;
+            }
+        }
+        _ => {}
+    }
+
+    Ok(())
+}
+
+/// Route button presses to the feature that owns them, by custom ID prefix.
+async fn on_component(
+    ctx: &serenity::Context,
+    component: &serenity::ComponentInteraction,
+    data: &Data,
+) -> Result<(), Error> {
+    if component
+        .data
+        .custom_id
+        .starts_with(remind::ACK_BUTTON_PREFIX)
+    {
+        remind::handle_ack_button(ctx, component).await?;
+    } else if component
+        .data
+        .custom_id
+        .starts_with(remind::SNOOZE_BUTTON_PREFIX)
+    {
+        remind::handle_snooze_button(ctx, component).await?;
+    } else if component
+        .data
+        .custom_id
+        .starts_with(tictactoe::BUTTON_PREFIX)
+    {
+        tictactoe::handle_button(ctx, component, &data.games).await?;
+    } else if component
+        .data
+        .custom_id
+        .starts_with(update_check::UPDATE_BUTTON_PREFIX)
+    {
+        update_check::handle_update_button(ctx, component).await?;
+    }
+    Ok(())
+}
+
+async fn on_guild_join(ctx: &serenity::Context, guild: &serenity::Guild, outcome: SeedOutcome) {
+    log::info!(
+        "Joined guild {} ({}) with {} members",
+        guild.name,
+        guild.id,
+        guild.member_count
+    );
+
+    let added_by = match find_inviter(ctx, guild.id).await {
+        Some(user) => format!("{} (<@{}>)", user.name, user.id),
+        None => "unknown (no audit log access)".to_string(),
+    };
+    let restored_note = if outcome == SeedOutcome::Restored {
+        "\n♻️ Previous settings restored"
+    } else {
+        ""
+    };
+
+    let message = format!(
+        "📥 **Joined a new server!**\n🏠 **Server:** {} (`{}`)\n👥 **Members:** {}\n🙋 **Added by:** {}{}",
+        guild.name, guild.id, guild.member_count, added_by, restored_note
+    );
+    if let Err(e) = send_dm_to_deekahy(&ctx.http, &message).await {
+        log::warn!("Failed to send guild join DM to deekahy: {}", e);
+    }
+
+    if let Some(channel_id) = guild.system_channel_id {
+        let intro = format!(
+            "👋 Hi, I'm {}! Thanks for having me.\nUse `{}help` or `/help` to see everything I can do.",
+            ctx.cache.current_user().name,
+            guild_config::command_prefix(Some(guild.id.get()))
+        );
+        if let Err(e) = channel_id.say(&ctx.http, intro).await {
+            log::warn!("Failed to send introduction in guild {}: {}", guild.id, e);
+        }
+    }
+}
+
+async fn on_guild_leave(
+    ctx: &serenity::Context,
+    guild_id: serenity::GuildId,
+    guild: Option<&serenity::Guild>,
+) {
+    let name = guild
+        .map(|g| g.name.clone())
+        .unwrap_or_else(|| "Unknown".to_string());
+    log::info!("Removed from guild {} ({})", name, guild_id);
+
+    guild_config::mark_guild_orphaned(guild_id.get());
+
+    let message = format!(
+        "📤 **Removed from a server**\n🏠 **Server:** {} (`{}`)\n🗄️ Its data will be kept for {} days in case I'm re-added.",
+        name,
+        guild_id,
+        guild_config::ORPHAN_RETENTION_DAYS
+    );
+    if let Err(e) = send_dm_to_deekahy(&ctx.http, &message).await {
+        log::warn!("Failed to send guild leave DM to deekahy: {}", e);
+    }
+}
+
+/// Who added the bot, from the guild's audit log (needs View Audit Log).
+async fn find_inviter(
+    ctx: &serenity::Context,
+    guild_id: serenity::GuildId,
+) -> Option<serenity::User> {
+    let bot_id = ctx.cache.current_user().id;
+    let logs = guild_id
+        .audit_logs(
+            &ctx.http,
+            Some(Action::Member(MemberAction::BotAdd)),
+            None,
+            None,
+            Some(10),
+        )
+        .await
+        .ok()?;
+
+    let entry = logs
+        .entries
+        .iter()
+        .find(|entry| entry.target_id.map(|id| id.get()) == Some(bot_id.get()))?;
+    logs.users.get(&entry.user_id).cloned()
+}
@@ -0,0 +1,507 @@
+//! Per-guild configuration, seeded when the bot joins a server.
+//!
+//! When the bot is removed from a guild its record is only marked as orphaned;
+//! a background task purges orphaned records after [`ORPHAN_RETENTION_DAYS`],
+//! along with what the other stores keep for the guild, so a quick
+//! kick-and-reinvite keeps the old settings.
+
+use crate::commands::maintenance::Maintenance;
+use crate::commands::{self, flagtranslate::FlagTranslateMode};
+use crate::config_check::{self, check_store, ConfigProblem, LiveIds};
+use crate::onboarding;
+use crate::storage;
+use crate::utils::check_store_writable;
+use chrono::{DateTime, Duration, Utc};
+use parking_lot::{Mutex, RwLock};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::HashMap;
+use std::sync::Arc;
+use tokio::time::{interval, Duration as TokioDuration};
+
+const GUILD_CONFIG_FILE: &str = "guild_configs.json";
+
+/// How long an orphaned guild's data is kept before it is purged.
+pub const ORPHAN_RETENTION_DAYS: i64 = 30;
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(default)]
+pub struct GuildConfig {
+    pub prefix: String,
+    pub disabled_commands: Vec<String>,
+    pub sfw: bool,
+    pub joined_at: Option<DateTime<Utc>>,
+    /// Set when the bot is removed from the guild; cleared if it comes back.
+    pub orphaned_at: Option<DateTime<Utc>>,
+    /// How flag-emoji reactions are translated, if at all.
+    pub flag_translate: FlagTranslateMode,
+    /// Where the bot announces that it's back online after a restart.
+    pub status_channel: Option<u64>,
+    /// Whether long command output goes to a thread, see
+    /// [`crate::utils::output::output_target`].
+    pub threaded_replies: bool,
+    /// Members with this role count as the server's bot admins.
+    pub bot_admin_role: Option<u64>,
+    /// Whether a bare @-mention of the bot gets a reply, see
+    /// [`crate::commands::botreplies::handle_mention`].
+    pub mention_reply_enabled: bool,
+    /// That reply, if not the default.
+    pub mention_reply: Option<String>,
+}
+
+impl Default for GuildConfig {
+    fn default() -> Self {
+        Self {
+            prefix: crate::DEFAULT_PREFIX.to_string(),
+            disabled_commands: Vec::new(),
+            sfw: false,
+            joined_at: None,
+            orphaned_at: None,
+            flag_translate: FlagTranslateMode::Off,
+            status_channel: None,
+            threaded_replies: false,
+            bot_admin_role: None,
+            mention_reply_enabled: true,
+            mention_reply: None,
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Default, Clone)]
+struct GuildConfigData {
+    guilds: HashMap<u64, GuildConfig>,
+}
+
+lazy_static::lazy_static! {
+    static ref GUILD_CONFIGS: RwLock<GuildConfigData> = RwLock::new(GuildConfigData::default());
+    /// Held while saving, since saves under a shared read lock would
+    /// otherwise write the same temporary file at once.
+    static ref GUILD_CONFIGS_SAVE: Mutex<()> = Mutex::new(());
+}
+
+/// What [`ensure_guild_config`] had to do for a guild.
+#[derive(Debug, PartialEq)]
+pub enum SeedOutcome {
+    Created,
+    Restored,
+    Existing,
+}
+
+/// Load stored guild configs into memory. Called once at startup.
+///
+/// Each guild's entry is read on its own, so one bad entry is quarantined
+/// instead of the whole file being set aside and every server reset.
+pub fn load_guild_configs() {
+    let data = match storage::load_json(&storage::data_path(GUILD_CONFIG_FILE)) {
+        Some(value) => {
+            let (data, unreadable) = parse_guild_entries(value);
+            if !unreadable.is_empty() {
+                log::warn!(
+                    "Quarantined {} unreadable guild config(s)",
+                    unreadable.len()
+                );
+                config_check::quarantine_unreadable("Server settings", unreadable);
+            }
+            data
+        }
+        // Missing, or not JSON at all, which `load` sets aside
+        None => storage::load(GUILD_CONFIG_FILE),
+    };
+    log::info!("Loaded {} guild config(s)", data.guilds.len());
+    *GUILD_CONFIGS.write() = data;
+}
+
+/// The guild configs in a stored file's data, and the entries that
+/// couldn't be read.
+fn parse_guild_entries(value: Value) -> (GuildConfigData, Vec<ConfigProblem>) {
+    let mut data = GuildConfigData::default();
+    let mut unreadable = Vec::new();
+    let entries = match value {
+        Value::Object(mut map) => match map.remove("guilds") {
+            Some(Value::Object(entries)) => entries,
+            Some(other) => {
+                unreadable.push(ConfigProblem::unreadable_entry(
+                    "guilds",
+                    "not a map of servers",
+                    other,
+                ));
+                return (data, unreadable);
+            }
+            None => return (data, unreadable),
+        },
+        other => {
+            unreadable.push(ConfigProblem::unreadable_entry(
+                "(file)",
+                "not an object",
+                other,
+            ));
+            return (data, unreadable);
+        }
+    };
+    for (key, entry) in entries {
+        let parsed = key
+            .parse::<u64>()
+            .map_err(|e| e.to_string())
+            .and_then(|guild_id| {
+                serde_json::from_value::<GuildConfig>(entry.clone())
+                    .map(|config| (guild_id, config))
+                    .map_err(|e| e.to_string())
+            });
+        match parsed {
+            Ok((guild_id, config)) => {
+                data.guilds.insert(guild_id, config);
+            }
+            Err(e) => unreadable.push(ConfigProblem::unreadable_entry(&key, e, entry)),
+        }
+    }
+    (data, unreadable)
+}
+
+/// Re-read the guild configs from disk, e.g. after the file was edited by
+/// hand. On a parse error the configs in memory are kept.
+pub fn reload_guild_configs() -> Result<(), crate::Error> {
+    *GUILD_CONFIGS.write() = storage::reload(GUILD_CONFIG_FILE)?;
+    Ok(())
+}
+
+fn save_guild_configs() {
+    write_guild_configs(&GUILD_CONFIGS.read());
+}
+
+fn write_guild_configs(data: &GuildConfigData) {
+    let _saving = GUILD_CONFIGS_SAVE.lock();
+    if let Err(e) = storage::save(GUILD_CONFIG_FILE, data) {
+        log::error!("Failed to save guild configs: {}", e);
+    }
+}
+
+/// Unset what can't work: a blank prefix (which would make every message
+/// look like a command) and a deleted status channel or bot admin role.
+fn validate_guild_configs(data: &mut GuildConfigData, live: &LiveIds) -> Vec<ConfigProblem> {
+    let mut problems = Vec::new();
+    for (&guild_id, config) in &mut data.guilds {
+        if config.prefix.trim().is_empty() {
+            problems.push(ConfigProblem::invalid(
+                guild_id,
+                "prefix",
+                "the prefix is blank",
+                &config.prefix,
+            ));
+            config.prefix = GuildConfig::default().prefix;
+        }
+        if let Some(channel_id) = config
+            .status_channel
+            .filter(|&channel_id| live.channel_gone(guild_id, channel_id))
+        {
+            problems.push(ConfigProblem::deleted_channel(
+                guild_id,
+                "status_channel",
+                channel_id,
+            ));
+            config.status_channel = None;
+        }
+        if let Some(role_id) = config
+            .bot_admin_role
+            .filter(|&role_id| live.role_gone(guild_id, role_id))
+        {
+            problems.push(ConfigProblem::deleted_role(
+                guild_id,
+                "bot_admin_role",
+                role_id,
+            ));
+            config.bot_admin_role = None;
+        }
+    }
+    problems
+}
+
+/// Check the guild configs, see [`crate::config_check`].
+pub fn check_guild_configs(live: &LiveIds, quarantine: bool) -> Vec<ConfigProblem> {
+    check_store(
+        &GUILD_CONFIGS,
+        quarantine,
+        |data| validate_guild_configs(data, live),
+        write_guild_configs,
+    )
+}
+
+/// Command check for commands that change guild configs.
+pub async fn guild_config_writable(ctx: crate::Context<'_>) -> Result<bool, crate::Error> {
+    check_store_writable(ctx, &storage::data_path(GUILD_CONFIG_FILE)).await
+}
+
+/// The stored config for a guild, if any.
+pub fn get_guild_config(guild_id: u64) -> Option<GuildConfig> {
+    GUILD_CONFIGS.read().guilds.get(&guild_id).cloned()
+}
+
+/// The prefix commands take in a guild, or in DMs for `None`.
+pub fn command_prefix(guild_id: Option<u64>) -> String {
+    guild_id
+        .and_then(|guild_id| {
+            GUILD_CONFIGS
+                .read()
+                .guilds
+                .get(&guild_id)
+                .map(|config| config.prefix.clone())
+        })
+        .unwrap_or_else(|| crate::DEFAULT_PREFIX.to_string())
+}
+
+/// Status channels of every guild the bot is still in.
+pub fn status_channels() -> Vec<u64> {
+    GUILD_CONFIGS
+        .read()
+        .guilds
+        .values()
+        .filter(|config| config.orphaned_at.is_none())
+        .filter_map(|config| config.status_channel)
+        .collect()
+}
+
+/// Make sure a guild has a config record, seeding the defaults if it has none
+/// and un-orphaning it if the bot was re-added within the retention window.
+pub fn ensure_guild_config(guild_id: u64) -> SeedOutcome {
+    let outcome = seed_guild(&mut GUILD_CONFIGS.write(), guild_id, Utc::now());
+    if outcome != SeedOutcome::Existing {
+        save_guild_configs();
+    }
+    outcome
+}
+
+/// Change a guild's config, seeding it first if it has none, and save it.
+pub fn update_guild_config(guild_id: u64, update: impl FnOnce(&mut GuildConfig)) {
+    {
+        let mut data = GUILD_CONFIGS.write();
+        seed_guild(&mut data, guild_id, Utc::now());
+        if let Some(config) = data.guilds.get_mut(&guild_id) {
+            update(config);
+        }
+    }
+    save_guild_configs();
+}
+
+/// Mark a guild's data as orphaned after the bot was removed from it. A
+/// guild without a config record gets one, so the other stores' data for it
+/// is still purged.
+pub fn mark_guild_orphaned(guild_id: u64) {
+    mark_orphaned(&mut GUILD_CONFIGS.write(), guild_id, Utc::now());
+    save_guild_configs();
+}
+
+fn mark_orphaned(data: &mut GuildConfigData, guild_id: u64, now: DateTime<Utc>) {
+    data.guilds.entry(guild_id).or_default().orphaned_at = Some(now);
+}
+
+fn seed_guild(data: &mut GuildConfigData, guild_id: u64, now: DateTime<Utc>) -> SeedOutcome {
+    match data.guilds.get_mut(&guild_id) {
+        Some(config) if config.orphaned_at.is_some() => {
+            config.orphaned_at = None;
+            SeedOutcome::Restored
+        }
+        Some(_) => SeedOutcome::Existing,
+        None => {
+            data.guilds.insert(
+                guild_id,
+                GuildConfig {
+                    joined_at: Some(now),
+                    ..Default::default()
+                },
+            );
+            SeedOutcome::Created
+        }
+    }
+}
+
+/// Remove guilds that have been orphaned for longer than the retention
+/// window, returning their ids.
+fn purge_orphaned(data: &mut GuildConfigData, now: DateTime<Utc>) -> Vec<u64> {
+    let cutoff = now - Duration::days(ORPHAN_RETENTION_DAYS);
+    let mut purged = Vec::new();
+    data.guilds.retain(|&guild_id, config| {
+        let keep = config.orphaned_at.is_none_or(|orphaned| orphaned > cutoff);
+        if !keep {
+            purged.push(guild_id);
+        }
+        keep
+    });
+    purged
+}
+
+/// The other stores' per-guild data, dropped along with a purged guild's
+/// config record.
+const GUILD_DATA_PURGES: &[fn(u64)] = &[
+    commands::automod::purge_guild,
+    commands::autoreact::purge_guild,
+    commands::birthday::purge_guild,
+    commands::boost::purge_guild,
+    commands::bumper::purge_guild,
+    commands::digest::purge_guild,
+    commands::invites::purge_guild,
+    commands::poll_templates::purge_guild,
+    commands::reactmap::purge_guild,
+    commands::streak::purge_guild,
+    commands::usage::purge_guild,
+    onboarding::purge_guild,
+];
+
+/// Start the background task that purges orphaned guild data
+pub fn start_guild_cleanup_task(maintenance: Arc<Maintenance>) {
+    tokio::spawn(async move {
+        let mut interval = interval(TokioDuration::from_secs(3600)); // Check every hour
+
+        loop {
+            interval.tick().await;
+
+            // Don't purge anything during maintenance
+            if maintenance.is_enabled() {
+                continue;
+            }
+
+            let purged = purge_orphaned(&mut GUILD_CONFIGS.write(), Utc::now());
+            if purged.is_empty() {
+                continue;
+            }
+            save_guild_configs();
+            for &guild_id in &purged {
+                for purge in GUILD_DATA_PURGES {
+                    purge(guild_id);
+                }
+            }
+            log::info!("Purged data for {} orphaned guild(s)", purged.len());
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_seed_creates_defaults() {
+        let mut data = GuildConfigData::default();
+        let now = Utc::now();
+
+        assert_eq!(seed_guild(&mut data, 1, now), SeedOutcome::Created);
+        let config = data.guilds.get(&1).unwrap();
+        assert_eq!(config.prefix, "-");
+        assert!(config.disabled_commands.is_empty());
+        assert!(!config.sfw);
+        assert!(!config.threaded_replies);
+        assert_eq!(config.joined_at, Some(now));
+
+        assert_eq!(seed_guild(&mut data, 1, now), SeedOutcome::Existing);
+    }
+
+    #[test]
+    fn test_seed_restores_orphaned_guild() {
+        let mut data = GuildConfigData::default();
+        let now = Utc::now();
+        seed_guild(&mut data, 1, now);
+        data.guilds.get_mut(&1).unwrap().orphaned_at = Some(now);
+
+        assert_eq!(seed_guild(&mut data, 1, now), SeedOutcome::Restored);
+        assert!(data.guilds.get(&1).unwrap().orphaned_at.is_none());
+    }
+
+    #[test]
+    fn test_mark_orphaned_without_a_record() {
+        let mut data = GuildConfigData::default();
+        let now = Utc::now();
+        mark_orphaned(&mut data, 1, now - Duration::days(31));
+
+        assert_eq!(
+            data.guilds.get(&1).unwrap().orphaned_at,
+            Some(now - Duration::days(31))
+        );
+        assert_eq!(purge_orphaned(&mut data, now), vec![1]);
+    }
+
+    #[test]
+    fn test_purge_only_expired_orphans() {
+        let mut data = GuildConfigData::default();
+        let now = Utc::now();
+        for id in 1..=3 {
+            seed_guild(&mut data, id, now);
+        }
+        data.guilds.get_mut(&1).unwrap().orphaned_at = Some(now - Duration::days(31));
+        data.guilds.get_mut(&2).unwrap().orphaned_at = Some(now - Duration::days(5));
+
+        assert_eq!(purge_orphaned(&mut data, now), vec![1]);
+        assert!(!data.guilds.contains_key(&1));
+        assert!(data.guilds.contains_key(&2));
+        assert!(data.guilds.contains_key(&3));
+    }
+
+    #[test]
+    fn test_validate_guild_configs() {
+        let mut data: GuildConfigData =
+            serde_json::from_str(include_str!("../tests/fixtures/config/guild_configs.json"))
+                .unwrap();
+        let live = LiveIds::default()
+            .with_guild(1, &[10], &[20])
+            .with_guild(2, &[10], &[20]);
+
+        let mut problems = validate_guild_configs(&mut data, &live);
+        problems.sort_by_key(|problem| problem.field.clone());
+        let fields: Vec<(Option<u64>, &str)> = problems
+            .iter()
+            .map(|problem| (problem.guild_id, problem.field.as_str()))
+            .collect();
+        assert_eq!(
+            fields,
+            [
+                (Some(2), "bot_admin_role"),
+                (Some(2), "prefix"),
+                (Some(2), "status_channel"),
+            ]
+        );
+        let broken = &data.guilds[&2];
+        assert_eq!(broken.prefix, "-");
+        assert_eq!(broken.status_channel, None);
+        assert_eq!(broken.bot_admin_role, None);
+        // Fine, and a guild that isn't cached isn't judged
+        assert_eq!(data.guilds[&1].status_channel, Some(10));
+        assert_eq!(data.guilds[&3].status_channel, Some(99));
+        assert!(validate_guild_configs(&mut data, &live).is_empty());
+    }
+
+    #[test]
+    fn test_parse_guild_entries_keeps_the_readable_ones() {
+        let (data, unreadable) = parse_guild_entries(serde_json::json!({
+            "guilds": {
+                "1": {"prefix": "!"},
+                "2": {"prefix": 5},
+                "three": {},
+                "4": {"sfw": true}
+            }
+        }));
+        assert_eq!(data.guilds.len(), 2);
+        assert_eq!(data.guilds[&1].prefix, "!");
+        assert!(data.guilds[&4].sfw);
+
+        let mut bad: Vec<(Option<u64>, Value)> = unreadable
+            .into_iter()
+            .map(|problem| (problem.guild_id, problem.value))
+            .collect();
+        bad.sort_by_key(|(guild_id, _)| *guild_id);
+        assert_eq!(
+            bad,
+            vec![
+                (None, serde_json::json!({})),
+                (Some(2), serde_json::json!({"prefix": 5})),
+            ]
+        );
+
+        let (data, unreadable) = parse_guild_entries(serde_json::json!({"guilds": []}));
+        assert!(data.guilds.is_empty());
+        assert_eq!(unreadable.len(), 1);
+    }
+
+    #[test]
+    fn test_old_records_fill_missing_fields() {
+        let config: GuildConfig = serde_json::from_str(r#"{"sfw": true}"#).unwrap();
+        assert!(config.sfw);
+        assert_eq!(config.prefix, "-");
+    }
+}
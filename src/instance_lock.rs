@@ -0,0 +1,120 @@
+//! Makes sure only one bot process per data directory runs the schedulers.
+//!
+//! Two instances started against the same [`DATA_DIR`](crate::storage::DATA_DIR)
+//! would otherwise both fire every reminder and both send every scheduled
+//! parking request. At startup the bot takes an exclusive advisory lock
+//! (`flock`) on [`LOCK_FILE`] and keeps it until it exits; the OS drops it
+//! when the process dies, so a crash never leaves a stale lock behind. An
+//! instance that doesn't get the lock still answers commands, it just
+//! doesn't start the background schedulers.
+
+use crate::storage;
+use chrono::Utc;
+use fs2::FileExt;
+use std::fs::{self, File, OpenOptions};
+use std::io::{Read, Seek, Write};
+use std::path::Path;
+use std::sync::OnceLock;
+
+const LOCK_FILE: &str = "instance.lock";
+
+/// The lock file, held open (and so locked) for the life of the process.
+static HELD_LOCK: OnceLock<File> = OnceLock::new();
+
+/// Whether this instance may run the background schedulers.
+#[derive(Debug)]
+pub enum InstanceLock {
+    /// This instance holds the lock for as long as it runs.
+    Acquired,
+    /// Another live process holds it; what that process wrote about itself.
+    HeldElsewhere(String),
+    /// The lock file couldn't be opened at all, e.g. there's no data
+    /// directory on a development machine.
+    Unavailable(String),
+}
+
+/// Take the instance lock in the data directory.
+pub fn acquire() -> InstanceLock {
+    if HELD_LOCK.get().is_some() {
+        return InstanceLock::Acquired;
+    }
+    match lock_at(&storage::data_path(LOCK_FILE)) {
+        Ok(Ok(file)) => {
+            let _ = HELD_LOCK.set(file);
+            InstanceLock::Acquired
+        }
+        Ok(Err(holder)) => InstanceLock::HeldElsewhere(holder),
+        Err(e) => InstanceLock::Unavailable(e.to_string()),
+    }
+}
+
+/// Lock the file at `path`, recording who holds it. `Ok(Err(holder))` if
+/// another process got there first.
+fn lock_at(path: &Path) -> std::io::Result<Result<File, String>> {
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir)?;
+    }
+    // No truncate: the current holder's details must survive a failed attempt
+    let mut file = OpenOptions::new()
+        .read(true)
+        .write(true)
+        .create(true)
+        .truncate(false)
+        .open(path)?;
+
+    if let Err(e) = file.try_lock_exclusive() {
+        if e.kind() != fs2::lock_contended_error().kind() {
+            return Err(e);
+        }
+        let mut holder = String::new();
+        let _ = file.read_to_string(&mut holder);
+        let holder = holder.trim();
+        return Ok(Err(if holder.is_empty() {
+            "an unknown process".to_string()
+        } else {
+            holder.to_string()
+        }));
+    }
+
+    file.set_len(0)?;
+    file.rewind()?;
+    writeln!(file, "{}", holder_description())?;
+    file.flush()?;
+    Ok(Ok(file))
+}
+
+/// Who holds the lock, for the warning another instance gets.
+fn holder_description() -> String {
+    let host = std::env::var("HOSTNAME")
+        .ok()
+        .or_else(|| fs::read_to_string("/etc/hostname").ok())
+        .map(|host| host.trim().to_string())
+        .filter(|host| !host.is_empty())
+        .unwrap_or_else(|| "an unknown host".to_string());
+    format!(
+        "process {} on {}, started {}",
+        std::process::id(),
+        host,
+        Utc::now().format("%Y-%m-%d %H:%M UTC")
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_lock_is_exclusive() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("data").join(LOCK_FILE);
+
+        let held = lock_at(&path).unwrap().expect("first lock succeeds");
+        // flock locks belong to the open file, so a second open conflicts
+        // even within one process
+        let holder = lock_at(&path).unwrap().unwrap_err();
+        assert!(holder.starts_with(&format!("process {} on ", std::process::id())));
+
+        drop(held);
+        assert!(lock_at(&path).unwrap().is_ok());
+    }
+}
@@ -0,0 +1,509 @@
+//! RustBot's commands and framework setup. `main.rs` only reads the
+//! environment and runs the client, so the framework can be built and
+//! inspected from integration tests.
+
+use poise::serenity_prelude as serenity;
+use serenity::GatewayIntents;
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::Instant;
+
+mod backups;
+pub mod commands;
+mod config_check;
+mod dm_delivery;
+mod events;
+mod guild_config;
+mod instance_lock;
+pub mod lifecycle;
+mod message_content;
+mod onboarding;
+pub mod repl;
+mod s3;
+mod storage;
+mod translate;
+mod update_check;
+pub mod utils;
+
+use utils::send_dm_to_deekahy;
+
+use commands::{
+    ascii, assets, attachmentstats, automod, autopublish, autoreact, avatarguess, backup, birthday,
+    board, bonk, boost, botadmin, botreplies, botstats, bumper, clap, cleanup, coinflip, color,
+    combo, configcheck, dice, digest, dmlog, emojistats, endgame, endhangman, endttt, fish,
+    flagtranslate, games, gamestatus, guess, hangman, hangmanhint, hangmanstatus, hello, help,
+    hint, hit, inventory, invite, invites, kys, leave, letter, maintenance, mock, move_ttt, note,
+    numberguess, owoify, park, pfp, ping, pinghistory, play, poll, pollclose, pollstraw,
+    polltemplate, queue, react, reactmap, reload_config, remind, selftest, setup, ship, skip,
+    sounds, spamping, start_autopublish_queue, start_birthday_scheduler, start_bumper,
+    start_color_role_cleanup, start_digest_scheduler, start_latency_probe, start_parking_scheduler,
+    start_poll_closer, start_reaction_watcher, start_reminder_checker, start_streak_checker,
+    start_usage_flusher, stats, statscompare, status, statuschannel, stop, streak, tictactoe,
+    timestamp, translate, update, usage, uwu, yourmom,
+};
+
+pub type Error = Box<dyn std::error::Error + Send + Sync>;
+pub type Context<'a> = poise::Context<'a, Data, Error>;
+
+/// The command prefix in DMs and in servers that haven't set their own.
+pub const DEFAULT_PREFIX: &str = "-";
+
+// User data, which is stored and accessible in all command invocations.
+pub struct Data {
+    /// Games in progress, by player.
+    pub games: Arc<commands::general::GameSessions>,
+    /// Whether the bot is in maintenance mode; the schedulers share it.
+    pub maintenance: Arc<commands::maintenance::Maintenance>,
+    /// The meme GIFs found in the asset directories.
+    pub assets: utils::meme::AssetScan,
+}
+
+async fn on_error(error: poise::FrameworkError<'_, Data, Error>) {
+    // This is our global error handler
+    match error {
+        poise::FrameworkError::Setup { error, .. } => panic!("Failed to start bot: {error:?}"),
+        poise::FrameworkError::Command { error, ctx, .. } => {
+            println!("Error in command `{}`: {:?}", ctx.command().name, error,);
+        }
+        poise::FrameworkError::ArgumentParse {
+            error, input, ctx, ..
+        } => {
+            let args = match ctx {
+                poise::Context::Prefix(prefix_ctx) => prefix_ctx.args,
+                poise::Context::Application(_) => "",
+            };
+            let message = utils::args::argument_error_message(
+                ctx.command(),
+                args,
+                input.as_deref(),
+                error.as_ref(),
+            );
+            if let Err(e) = ctx.say(message).await {
+                log::warn!("Failed to report an argument error: {}", e);
+            }
+        }
+        error => {
+            if let Err(e) = poise::builtins::on_error(error).await {
+                println!("Error while handling error: {e}")
+            }
+        }
+    }
+}
+
+/// Whether `PRESENCE_INTENT` asks for the privileged presence intent, which
+/// only `remind whenfree` uses. Off by default, since Discord refuses the
+/// connection if it isn't enabled in the Developer Portal.
+pub fn presence_intent_enabled() -> bool {
+    std::env::var("PRESENCE_INTENT").is_ok_and(|value| {
+        matches!(
+            value.trim().to_lowercase().as_str(),
+            "1" | "true" | "yes" | "on"
+        )
+    })
+}
+
+/// The gateway intents the bot needs.
+pub fn gateway_intents() -> GatewayIntents {
+    let intents = GatewayIntents::GUILD_MESSAGES
+        | GatewayIntents::DIRECT_MESSAGES
+        | GatewayIntents::MESSAGE_CONTENT
+        | GatewayIntents::GUILD_MEMBERS
+        // GUILDS delivers GUILD_CREATE, which carries the initial voice-state list
+        // (who is already sitting in which channel). Without it the cache's
+        // voice_states stay empty and -play can never find the caller's channel.
+        | GatewayIntents::GUILDS
+        // GUILD_VOICE_STATES keeps that list live via VOICE_STATE_UPDATE.
+        | GatewayIntents::GUILD_VOICE_STATES
+        // Flag-emoji reactions trigger translations.
+        | GatewayIntents::GUILD_MESSAGE_REACTIONS
+        // ❌ on a reminder's DM copy cancels it.
+        | GatewayIntents::DIRECT_MESSAGE_REACTIONS
+        // Invite create/delete keep the use counts `-invites` diffs on a join.
+        | GatewayIntents::GUILD_INVITES;
+    // `remind whenfree` goes off when its recipient comes back online.
+    // Privileged: "Presence Intent" must be enabled in the Developer Portal,
+    // or the bot can't connect, so it's opt-in.
+    if presence_intent_enabled() {
+        intents | GatewayIntents::GUILD_PRESENCES
+    } else {
+        intents
+    }
+}
+
+/// The intents to connect with: [`gateway_intents`] minus Message Content
+/// if the application isn't allowed it, so the connection isn't refused.
+pub async fn startup_intents(http: &serenity::Http) -> GatewayIntents {
+    message_content::check_message_content(http, gateway_intents()).await
+}
+
+/// Every command plus the prefix, hooks and event handler.
+pub fn build_framework_options() -> poise::FrameworkOptions<Data, Error> {
+    let commands = vec![
+        ping(),
+        pinghistory(),
+        hello(),
+        help(),
+        invite(),
+        invites(),
+        spamping(),
+        uwu(),
+        mock(),
+        clap(),
+        owoify(),
+        ship(),
+        coinflip(),
+        ascii(),
+        autoreact(),
+        backup(),
+        assets(),
+        attachmentstats(),
+        automod(),
+        autopublish(),
+        color(),
+        flagtranslate(),
+        translate(),
+        dice(),
+        digest(),
+        birthday(),
+        boost(),
+        bumper(),
+        pfp(),
+        yourmom(),
+        stats(),
+        statscompare(),
+        emojistats(),
+        status(),
+        selftest(),
+        setup(),
+        statuschannel(),
+        botreplies(),
+        botadmin(),
+        streak(),
+        update(),
+        usage(),
+        botstats(),
+        kys(),
+        maintenance(),
+        reload_config(),
+        configcheck(),
+        dmlog(),
+        poll(),
+        pollstraw(),
+        polltemplate(),
+        pollclose(),
+        cleanup(),
+        remind(),
+        note(),
+        timestamp(),
+        react(),
+        reactmap(),
+        hit(),
+        bonk(),
+        combo(),
+        park(),
+        // Voice / music commands
+        play(),
+        skip(),
+        stop(),
+        queue(),
+        leave(),
+        sounds(),
+        // Game commands
+        numberguess(),
+        guess(),
+        hint(),
+        gamestatus(),
+        games(),
+        endgame(),
+        tictactoe(),
+        move_ttt(),
+        board(),
+        endttt(),
+        hangman(),
+        letter(),
+        hangmanstatus(),
+        hangmanhint(),
+        endhangman(),
+        avatarguess(),
+        fish(),
+        inventory(),
+    ];
+    assert_unique_command_names(&commands, "");
+
+    poise::FrameworkOptions {
+        commands,
+        prefix_options: poise::PrefixFrameworkOptions {
+            // Each server's own prefix, see `GuildConfig::prefix`
+            dynamic_prefix: Some(|ctx| {
+                Box::pin(async move {
+                    Ok(Some(guild_config::command_prefix(
+                        ctx.guild_id.map(|id| id.get()),
+                    )))
+                })
+            }),
+            ..Default::default()
+        },
+        on_error: |error| Box::pin(on_error(error)),
+        command_check: Some(|ctx| Box::pin(commands::maintenance::maintenance_check(ctx))),
+        event_handler: |ctx, event, framework, data| {
+            Box::pin(events::event_handler(ctx, event, framework, data))
+        },
+        pre_command: |ctx| {
+            Box::pin(async move {
+                log::info!("Executing command {}...", ctx.command().qualified_name);
+                ctx.set_invocation_data(commands::botstats::CommandStart(Instant::now()))
+                    .await;
+                if let Some(guild_id) = ctx.guild_id() {
+                    commands::usage::record_command_usage(
+                        guild_id.get(),
+                        &ctx.command().qualified_name,
+                        ctx.author().id.get(),
+                    );
+                }
+            })
+        },
+        post_command: |ctx| {
+            Box::pin(async move {
+                log::info!("Executed command {}!", ctx.command().qualified_name);
+                // Copied out so the invocation data isn't locked while recording
+                let started = ctx
+                    .invocation_data::<commands::botstats::CommandStart>()
+                    .await
+                    .map(|start| start.0);
+                if let Some(started) = started {
+                    commands::botstats::record_command_latency(
+                        &ctx.command().qualified_name,
+                        started.elapsed(),
+                    );
+                }
+                if let Err(e) = onboarding::maybe_send_hint(ctx).await {
+                    log::warn!("Failed to send onboarding hint: {}", e);
+                }
+            })
+        },
+        ..Default::default()
+    }
+}
+
+/// Fail fast if two commands, or a command and an alias, share a name on the
+/// same level. The prefix framework would otherwise quietly run whichever
+/// comes first.
+fn assert_unique_command_names(commands: &[poise::Command<Data, Error>], parent: &str) {
+    let mut seen: HashMap<String, &str> = HashMap::new();
+    for command in commands {
+        for name in std::iter::once(&command.name).chain(&command.aliases) {
+            if let Some(other) = seen.insert(name.to_lowercase(), &command.name) {
+                panic!(
+                    "`{parent}{name}` is claimed by both `{parent}{other}` and `{parent}{}`; \
+                     rename one of them or drop the alias",
+                    command.name
+                );
+            }
+        }
+        assert_unique_command_names(&command.subcommands, &format!("{parent}{} ", command.name));
+    }
+}
+
+/// The framework with its startup routine: restart bookkeeping, command
+/// registration and the background tasks.
+pub fn build_framework() -> poise::Framework<Data, Error> {
+    poise::Framework::builder()
+        .options(build_framework_options())
+        .setup(move |ctx, _ready, framework| {
+            Box::pin(async move {
+                log::info!("Logged in as {}", _ready.user.name);
+                println!("🤖 {} is online and ready!", _ready.user.name);
+
+                // Why did the last run end? A `-kys` restart that came back
+                // too early goes straight back to sleep.
+                let shutdown = lifecycle::read_shutdown_marker();
+                if let Some(remaining) =
+                    lifecycle::kys_cooldown_remaining(shutdown.as_ref(), chrono::Utc::now())
+                {
+                    log::warn!(
+                        "Kys cooldown not complete. {} seconds remaining. Exiting again.",
+                        remaining.num_seconds()
+                    );
+                    std::process::exit(43);
+                }
+                lifecycle::clear_shutdown_marker();
+
+                // Load per-guild configs before any guild events are handled
+                guild_config::load_guild_configs();
+                // Reminders used to live in /tmp, readable by anyone
+                commands::remind::move_old_reminders_file();
+
+                // Data written by a newer build (e.g. after a rollback) is kept
+                // read-only so saving can't drop what this build doesn't know
+                let read_only = storage::check_schema_versions();
+                if !read_only.is_empty() {
+                    let message = format!(
+                        "⚠️ These stores were written by a newer version of the bot and are \
+                         read-only until it's updated: {}",
+                        read_only
+                            .iter()
+                            .map(|name| format!("`{}`", name))
+                            .collect::<Vec<_>>()
+                            .join(", ")
+                    );
+                    if let Err(e) = send_dm_to_deekahy(&ctx.http, &message).await {
+                        log::warn!("Failed to report read-only stores: {}", e);
+                    }
+                }
+
+                lifecycle::announce_startup(&ctx.http, &_ready.user.name, shutdown.as_ref()).await;
+                if shutdown
+                    .as_ref()
+                    .is_some_and(|m| m.reason == lifecycle::RestartReason::Kys)
+                {
+                    if let Err(e) = send_dm_to_deekahy(
+                        &ctx.http,
+                        "✅ Bot is back online after the 1-hour kys cooldown!",
+                    )
+                    .await
+                    {
+                        log::warn!("Failed to send startup DM to deekahy: {}", e);
+                    }
+                }
+
+                // Log all registered commands for debugging
+                let commands = &framework.options().commands;
+                log::info!("Registering {} commands:", commands.len());
+                for command in commands {
+                    log::info!(
+                        "  - {} (prefix: {}, slash: {})",
+                        command.name,
+                        command.prefix_action.is_some(),
+                        command.slash_action.is_some()
+                    );
+                }
+
+                // Keep showing the maintenance status if we restarted during maintenance
+                let maintenance = Arc::new(commands::maintenance::Maintenance::load());
+                commands::maintenance::apply_maintenance_presence(ctx, &maintenance);
+
+                let assets = utils::meme::AssetScan::load().await?;
+
+                poise::builtins::register_globally(ctx, commands).await?;
+                log::info!("All commands registered successfully");
+
+                // Warn if prefix commands can't see message text
+                message_content::report_missing_content(&ctx.http).await;
+
+                // Start abandoned game cleanup background task
+                let games = Arc::new(commands::general::GameSessions::default());
+                commands::general::start_game_cleanup_task(ctx.http.clone(), games.clone());
+                log::info!("Game cleanup task started");
+
+                // The schedulers below act on stored data, so only one
+                // instance per data directory may run them
+                let run_schedulers = match instance_lock::acquire() {
+                    instance_lock::InstanceLock::Acquired => true,
+                    instance_lock::InstanceLock::HeldElsewhere(holder) => {
+                        log::error!(
+                            "Another instance ({}) is using {}, not starting the schedulers",
+                            holder,
+                            storage::DATA_DIR
+                        );
+                        let message = format!(
+                            "⚠️ Another bot instance ({}) is already using `{}`. This one answers \
+                             commands but won't run reminders, parking or other schedulers until \
+                             the other one stops and this one restarts.",
+                            holder,
+                            storage::DATA_DIR
+                        );
+                        if let Err(e) = send_dm_to_deekahy(&ctx.http, &message).await {
+                            log::warn!("Failed to report the second instance: {}", e);
+                        }
+                        false
+                    }
+                    instance_lock::InstanceLock::Unavailable(e) => {
+                        log::warn!(
+                            "Couldn't take the instance lock ({}), starting the schedulers anyway",
+                            e
+                        );
+                        true
+                    }
+                };
+
+                if run_schedulers {
+                    // Start command usage flush background task
+                    start_usage_flusher();
+                    log::info!("Command usage flusher started");
+
+                    // Start onboarding seen-users flush background task
+                    onboarding::start_seen_users_flusher();
+                    log::info!("Seen users flusher started");
+
+                    // Start queued announcement publishing background task
+                    start_autopublish_queue(ctx.http.clone());
+                    log::info!("Autopublish queue started");
+
+                    // Start reminder checker background task
+                    start_reminder_checker(ctx.http.clone(), maintenance.clone());
+                    log::info!("Reminder checker started");
+
+                    // Start reaction milestone watcher background task
+                    start_reaction_watcher(ctx.http.clone(), maintenance.clone());
+                    log::info!("Reaction watcher started");
+
+                    // Start poll auto-close background task
+                    start_poll_closer(ctx.http.clone(), maintenance.clone());
+                    log::info!("Poll closer started");
+
+                    // Start parking scheduler background task
+                    start_parking_scheduler(ctx.http.clone(), maintenance.clone());
+                    log::info!("Parking scheduler started");
+
+                    // Start streak checker background task
+                    start_streak_checker(ctx.http.clone(), maintenance.clone());
+                    log::info!("Streak checker started");
+
+                    // Start unused color role cleanup background task
+                    start_color_role_cleanup(ctx.http.clone(), maintenance.clone());
+                    log::info!("Color role cleanup task started");
+
+                    // Start daily digest background task
+                    start_digest_scheduler(ctx.http.clone(), maintenance.clone());
+                    log::info!("Digest scheduler started");
+
+                    // Start daily birthday announcements
+                    start_birthday_scheduler(ctx.http.clone(), maintenance.clone());
+                    log::info!("Birthday scheduler started");
+
+                    // Start idle channel bumper background task
+                    start_bumper(ctx.http.clone(), maintenance.clone());
+                    log::info!("Bumper started");
+
+                    // Start API latency probe background task
+                    start_latency_probe(ctx.http.clone());
+                    log::info!("Latency probe started");
+
+                    // Start failed DM retry background task
+                    dm_delivery::start_dm_retry_task(ctx.http.clone());
+                    log::info!("DM retry task started");
+
+                    // Start nightly backup background task
+                    if backups::start_backup_scheduler(ctx.http.clone()) {
+                        log::info!("Backup scheduler started");
+                    }
+
+                    // Start the update checker background task
+                    if update_check::start_update_checker(ctx.http.clone(), maintenance.clone()) {
+                        log::info!("Update checker started");
+                    }
+
+                    // Start orphaned guild data cleanup background task
+                    guild_config::start_guild_cleanup_task(maintenance.clone());
+                    log::info!("Guild data cleanup task started");
+                }
+
+                Ok(Data {
+                    games,
+                    maintenance,
+                    assets,
+                })
+            })
+        })
+        .build()
+}
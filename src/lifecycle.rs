@@ -0,0 +1,271 @@
+//! Restart bookkeeping and the startup announcement in each guild's status
+//! channel.
+//!
+//! Every intentional shutdown (`-update`, `-kys`, `-backup restore`, or a stop signal) writes a
+//! shutdown marker saying why. On startup the marker is read and cleared; if
+//! there is none, the previous run must have crashed.
+
+use crate::guild_config;
+use crate::storage;
+use crate::utils::cooldown;
+use chrono::{DateTime, Duration, Utc};
+use poise::serenity_prelude as serenity;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::sync::Arc;
+
+const SHUTDOWN_MARKER_FILE: &str = "shutdown_marker.json";
+
+/// Written by `-update` and `-kys` before this was replaced by the shutdown
+/// marker. Still read for one release so a restart across the upgrade is
+/// reported correctly.
+const LEGACY_UPDATE_INFO_FILE: &str = "/tmp/rustbot_update_info.json";
+const LEGACY_KYS_INFO_FILE: &str = "/tmp/rustbot_kys_info.json";
+
+/// How long `-kys` keeps the bot down.
+pub const KYS_COOLDOWN_MINUTES: i64 = 60;
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum RestartReason {
+    Update,
+    Kys,
+    /// Restarted by `-backup restore` to load the restored data.
+    Restore,
+    /// Stopped by a signal, e.g. a service restart.
+    Normal,
+    /// No shutdown marker was left behind.
+    Crash,
+}
+
+impl RestartReason {
+    fn label(self) -> &'static str {
+        match self {
+            RestartReason::Update => "update",
+            RestartReason::Kys => "kys",
+            RestartReason::Restore => "restore",
+            RestartReason::Normal => "normal",
+            RestartReason::Crash => "crash",
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct ShutdownMarker {
+    pub reason: RestartReason,
+    /// Who triggered the shutdown, for `-update` and `-kys`.
+    pub user_name: Option<String>,
+    pub timestamp: DateTime<Utc>,
+}
+
+/// Both legacy files had these fields (plus a now unused `channel_id`).
+#[derive(Deserialize)]
+struct LegacyRestartInfo {
+    user_name: String,
+    timestamp: Option<u64>,
+}
+
+/// Record why the bot is about to shut down.
+pub fn write_shutdown_marker(
+    reason: RestartReason,
+    user_name: Option<String>,
+) -> Result<(), crate::Error> {
+    let marker = ShutdownMarker {
+        reason,
+        user_name,
+        timestamp: Utc::now(),
+    };
+    storage::save(SHUTDOWN_MARKER_FILE, &Some(marker))
+}
+
+/// Why the previous run ended, from the shutdown marker or the legacy files.
+/// `None` means it crashed.
+pub fn read_shutdown_marker() -> Option<ShutdownMarker> {
+    if let Some(marker) = storage::load::<Option<ShutdownMarker>>(SHUTDOWN_MARKER_FILE) {
+        return Some(marker);
+    }
+
+    let legacy = |path: &str| {
+        let info: LegacyRestartInfo = serde_json::from_str(&fs::read_to_string(path).ok()?).ok()?;
+        let timestamp = info
+            .timestamp
+            .and_then(|secs| DateTime::from_timestamp(secs as i64, 0))
+            .unwrap_or_else(Utc::now);
+        Some((info.user_name, timestamp))
+    };
+    if let Some((user_name, timestamp)) = legacy(LEGACY_KYS_INFO_FILE) {
+        return Some(ShutdownMarker {
+            reason: RestartReason::Kys,
+            user_name: Some(user_name),
+            timestamp,
+        });
+    }
+    legacy(LEGACY_UPDATE_INFO_FILE).map(|(user_name, timestamp)| ShutdownMarker {
+        reason: RestartReason::Update,
+        user_name: Some(user_name),
+        timestamp,
+    })
+}
+
+/// Forget the previous shutdown once startup has dealt with it, so that the
+/// next unexpected exit is seen as a crash.
+pub fn clear_shutdown_marker() {
+    for path in [
+        storage::data_path(SHUTDOWN_MARKER_FILE),
+        LEGACY_UPDATE_INFO_FILE.into(),
+        LEGACY_KYS_INFO_FILE.into(),
+    ] {
+        if let Err(e) = fs::remove_file(&path) {
+            if e.kind() != std::io::ErrorKind::NotFound {
+                log::warn!("Failed to remove {}: {}", path.display(), e);
+            }
+        }
+    }
+}
+
+/// How much of the `-kys` cooldown is left, if the bot was shut down by
+/// `-kys` and woke up too early.
+pub fn kys_cooldown_remaining(
+    marker: Option<&ShutdownMarker>,
+    now: DateTime<Utc>,
+) -> Option<Duration> {
+    let marker = marker.filter(|m| m.reason == RestartReason::Kys)?;
+    cooldown::remaining(
+        Some(marker.timestamp),
+        Duration::minutes(KYS_COOLDOWN_MINUTES),
+        now,
+    )
+}
+
+/// The status channel announcement for this startup.
+fn startup_message(bot_name: &str, marker: Option<&ShutdownMarker>, now: DateTime<Utc>) -> String {
+    let reason = marker.map_or(RestartReason::Crash, |m| m.reason);
+    let mut message = format!(
+        "🟢 **{}** is online • version {} • restart reason: **{}**",
+        bot_name,
+        env!("CARGO_PKG_VERSION"),
+        reason.label()
+    );
+    if let Some(marker) = marker {
+        if let Some(user_name) = &marker.user_name {
+            message.push_str(&format!(" (by {})", user_name));
+        }
+        message.push_str(&format!(
+            " • down for {}",
+            crate::commands::remind::format_duration(now - marker.timestamp)
+        ));
+    }
+    message
+}
+
+/// Post the startup message to every configured status channel.
+pub async fn announce_startup(
+    http: &serenity::Http,
+    bot_name: &str,
+    marker: Option<&ShutdownMarker>,
+) {
+    let message = startup_message(bot_name, marker, Utc::now());
+    for channel_id in guild_config::status_channels() {
+        if let Err(e) = serenity::ChannelId::new(channel_id)
+            .say(http, &message)
+            .await
+        {
+            log::warn!(
+                "Failed to send startup message to status channel {}: {}",
+                channel_id,
+                e
+            );
+        }
+    }
+}
+
+/// On Ctrl+C or SIGTERM, write a `Normal` shutdown marker, flush buffered
+/// data and stop the shards.
+pub fn spawn_shutdown_handler(shard_manager: Arc<serenity::ShardManager>) {
+    tokio::spawn(async move {
+        wait_for_shutdown_signal().await;
+        log::info!("Shutdown signal received, stopping...");
+        if let Err(e) = write_shutdown_marker(RestartReason::Normal, None) {
+            log::error!("Failed to write shutdown marker: {}", e);
+        }
+        crate::commands::usage::flush_command_usage();
+        crate::onboarding::flush_seen_users();
+        crate::commands::streak::flush_streaks();
+        shard_manager.shutdown_all().await;
+    });
+}
+
+/// Wait for Ctrl+C or, on Unix, SIGTERM (what systemd sends on stop).
+async fn wait_for_shutdown_signal() {
+    #[cfg(unix)]
+    {
+        use tokio::signal::unix::{signal, SignalKind};
+        match signal(SignalKind::terminate()) {
+            Ok(mut terminate) => {
+                tokio::select! {
+                    _ = tokio::signal::ctrl_c() => {}
+                    _ = terminate.recv() => {}
+                }
+                return;
+            }
+            Err(e) => log::warn!("Failed to listen for SIGTERM: {}", e),
+        }
+    }
+    if let Err(e) = tokio::signal::ctrl_c().await {
+        log::warn!("Failed to listen for Ctrl+C: {}", e);
+        std::future::pending::<()>().await;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn marker(reason: RestartReason, minutes_ago: i64, now: DateTime<Utc>) -> ShutdownMarker {
+        ShutdownMarker {
+            reason,
+            user_name: Some("deekahy".to_string()),
+            timestamp: now - Duration::minutes(minutes_ago),
+        }
+    }
+
+    #[test]
+    fn test_startup_message() {
+        let now = Utc::now();
+        let update = marker(RestartReason::Update, 2, now);
+        let message = startup_message("RustBot", Some(&update), now);
+        assert!(message.contains("restart reason: **update** (by deekahy)"));
+        assert!(message.contains("down for 2m"));
+        assert!(message.contains(env!("CARGO_PKG_VERSION")));
+
+        assert!(startup_message("RustBot", None, now).contains("restart reason: **crash**"));
+    }
+
+    #[test]
+    fn test_kys_cooldown_remaining() {
+        let now = Utc::now();
+        let early = marker(RestartReason::Kys, 20, now);
+        assert_eq!(
+            kys_cooldown_remaining(Some(&early), now),
+            Some(Duration::minutes(40))
+        );
+        let done = marker(RestartReason::Kys, 61, now);
+        assert_eq!(kys_cooldown_remaining(Some(&done), now), None);
+        let update = marker(RestartReason::Update, 1, now);
+        assert_eq!(kys_cooldown_remaining(Some(&update), now), None);
+        assert_eq!(kys_cooldown_remaining(None, now), None);
+    }
+
+    #[test]
+    fn test_legacy_info_parses() {
+        let kys: LegacyRestartInfo = serde_json::from_str(
+            r#"{"channel_id": 1, "user_name": "deekahy", "timestamp": 1700000000}"#,
+        )
+        .unwrap();
+        assert_eq!(kys.timestamp, Some(1700000000));
+        let update: LegacyRestartInfo =
+            serde_json::from_str(r#"{"channel_id": 1, "user_name": "deekahy"}"#).unwrap();
+        assert_eq!(update.user_name, "deekahy");
+        assert_eq!(update.timestamp, None);
+    }
+}
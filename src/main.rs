@@ -1,58 +1,10 @@
 use std::env;
-use std::fs;
+use std::io;
 
 use poise::serenity_prelude as serenity;
-use serde::{Deserialize, Serialize};
-use serenity::{ChannelId, Client, GatewayIntents};
+use serenity::Client;
 use songbird::serenity::SerenityInit;
 
-mod commands;
-mod utils;
-
-use utils::send_dm_to_deekahy;
-
-use commands::{
-    board, bonk, cleanup, coinflip, dice, endgame, endhangman, endttt, gamestatus, guess, hangman,
-    hangmanhint, hangmanstatus, hello, help, hint, hit, invite, kys, leave, letter, mock, move_ttt,
-    numberguess, park, pfp, ping, play, poll, queue, react, remind, skip, spamping,
-    start_parking_scheduler, start_reminder_checker, stats, status, stop, tictactoe, update, uwu,
-    yourmom,
-};
-
-type Error = Box<dyn std::error::Error + Send + Sync>;
-type Context<'a> = poise::Context<'a, Data, Error>;
-
-#[derive(Serialize, Deserialize)]
-struct UpdateInfo {
-    channel_id: u64,
-    user_name: String,
-}
-
-#[derive(Serialize, Deserialize)]
-struct KysInfo {
-    channel_id: u64,
-    user_name: String,
-    timestamp: u64,
-}
-
-// User data, which is stored and accessible in all command invocations.
-pub struct Data {}
-
-async fn on_error(error: poise::FrameworkError<'_, Data, Error>) {
-    // This is our global error handler
-    match error {
-        poise::FrameworkError::Setup { error, .. } => panic!("Failed to start bot: {error:?}"),
-        poise::FrameworkError::Command { error, ctx, .. } => {
-            println!("Error in command `{}`: {:?}", ctx.command().name, error,);
-        }
-        error => {
-            if let Err(e) = poise::builtins::on_error(error).await {
-                println!("Error while handling error: {e}")
-            }
-        }
-    }
-}
-
 #[tokio::main]
 async fn main() {
     // Initialize logger
@@ -61,234 +13,34 @@ async fn main() {
     // Load environment variables from .env file
     dotenvy::dotenv().ok();
 
+    // `--repl` runs commands from stdin without connecting to Discord
+    if env::args().skip(1).any(|arg| arg == "--repl") {
+        if let Err(e) = rustbot::repl::run(io::stdin().lock(), io::stdout()).await {
+            log::error!("REPL error: {e}");
+        }
+        return;
+    }
+
+    rustbot::commands::play::scan_sounds();
+
     // Get the bot token from environment variables
     let token = env::var("DISCORD_TOKEN")
         .expect("Expected a Discord bot token in the environment variable DISCORD_TOKEN");
 
-    // Set gateway intents
-    let intents = GatewayIntents::GUILD_MESSAGES
-        | GatewayIntents::DIRECT_MESSAGES
-        | GatewayIntents::MESSAGE_CONTENT
-        | GatewayIntents::GUILD_MEMBERS
-        // GUILDS delivers GUILD_CREATE, which carries the initial voice-state list
-        // (who is already sitting in which channel). Without it the cache's
-        // voice_states stay empty and -play can never find the caller's channel.
-        | GatewayIntents::GUILDS
-        // GUILD_VOICE_STATES keeps that list live via VOICE_STATE_UPDATE.
-        | GatewayIntents::GUILD_VOICE_STATES;
-
-    let framework = poise::Framework::builder()
-        .options(poise::FrameworkOptions {
-            commands: vec![
-                ping(),
-                hello(),
-                help(),
-                invite(),
-                spamping(),
-                uwu(),
-                mock(),
-                coinflip(),
-                dice(),
-                pfp(),
-                yourmom(),
-                stats(),
-                status(),
-                update(),
-                kys(),
-                poll(),
-                cleanup(),
-                remind(),
-                react(),
-                hit(),
-                bonk(),
-                park(),
-                // Voice / music commands
-                play(),
-                skip(),
-                stop(),
-                queue(),
-                leave(),
-                // Game commands
-                numberguess(),
-                guess(),
-                hint(),
-                gamestatus(),
-                endgame(),
-                tictactoe(),
-                move_ttt(),
-                board(),
-                endttt(),
-                hangman(),
-                letter(),
-                hangmanstatus(),
-                hangmanhint(),
-                endhangman(),
-            ],
-            prefix_options: poise::PrefixFrameworkOptions {
-                prefix: Some("-".into()),
-                ..Default::default()
-            },
-            on_error: |error| Box::pin(on_error(error)),
-            pre_command: |ctx| {
-                Box::pin(async move {
-                    log::info!("Executing command {}...", ctx.command().qualified_name);
-                })
-            },
-            post_command: |ctx| {
-                Box::pin(async move {
-                    log::info!("Executed command {}!", ctx.command().qualified_name);
-                })
-            },
-            ..Default::default()
-        })
-        .setup(move |ctx, _ready, framework| {
-            Box::pin(async move {
-                log::info!("Logged in as {}", _ready.user.name);
-                println!("🤖 {} is online and ready!", _ready.user.name);
-
-                // Check if this is a restart after an update
-                if let Ok(update_info_str) = fs::read_to_string("/tmp/rustbot_update_info.json") {
-                    if let Ok(update_info) = serde_json::from_str::<UpdateInfo>(&update_info_str) {
-                        let channel_id = ChannelId::new(update_info.channel_id);
-                        match channel_id
-                            .say(
-                                &ctx.http,
-                                format!(
-                                    "✅ Update complete! {} is back online and ready! 🤖",
-                                    _ready.user.name
-                                ),
-                            )
-                            .await
-                        {
-                            Ok(_) => {
-                                log::info!(
-                                    "Successfully sent post-update startup message to channel {}",
-                                    update_info.channel_id
-                                );
-                            }
-                            Err(e) => {
-                                log::error!(
-                                    "Failed to send startup message to channel {}: {}",
-                                    update_info.channel_id,
-                                    e
-                                );
-                            }
-                        }
-
-                        // Clean up the update info file
-                        if let Err(e) = fs::remove_file("/tmp/rustbot_update_info.json") {
-                            log::warn!("Failed to remove update info file: {e}");
-                        }
-                    }
-                }
-
-                // Check if this is a restart after a kys command (1-hour cooldown)
-                if let Ok(kys_info_str) = fs::read_to_string("/tmp/rustbot_kys_info.json") {
-                    if let Ok(kys_info) = serde_json::from_str::<KysInfo>(&kys_info_str) {
-                        // Verify that at least 1 hour has passed since the kys command
-                        let current_time = std::time::SystemTime::now()
-                            .duration_since(std::time::UNIX_EPOCH)
-                            .unwrap()
-                            .as_secs();
-
-                        let time_elapsed = current_time - kys_info.timestamp;
-                        let one_hour = 3600; // seconds
-
-                        if time_elapsed >= one_hour {
-                            let channel_id = ChannelId::new(kys_info.channel_id);
-                            match channel_id
-                                .say(
-                                    &ctx.http,
-                                    format!(
-                                        "Good morning! {} has awakened from their 1-hour slumber and is back online! ⏰ (Waited {} minutes)",
-                                        _ready.user.name,
-                                        time_elapsed / 60
-                                    ),
-                                )
-                                .await
-                            {
-                                Ok(_) => {
-                                    log::info!(
-                                        "Successfully sent post-kys startup message to channel {} after {} seconds",
-                                        kys_info.channel_id,
-                                        time_elapsed
-                                    );
-
-                                    // Also send DM to deekahy about startup
-                                    if let Err(e) = send_dm_to_deekahy(
-                                        &ctx.http,
-                                        &format!(
-                                            "✅ Bot is back online after 1-hour kys cooldown! Waited {} minutes.",
-                                            time_elapsed / 60
-                                        ),
-                                    )
-                                    .await
-                                    {
-                                        log::warn!("Failed to send startup DM to deekahy: {}", e);
-                                    }
-                                }
-                                Err(e) => {
-                                    log::error!(
-                                        "Failed to send kys startup message to channel {}: {}",
-                                        kys_info.channel_id,
-                                        e
-                                    );
-                                }
-                            }
-
-                            // Clean up the kys info file
-                            if let Err(e) = fs::remove_file("/tmp/rustbot_kys_info.json") {
-                                log::warn!("Failed to remove kys info file: {e}");
-                            }
-                        } else {
-                            // Not enough time has passed, exit again to continue waiting
-                            let remaining_time = one_hour - time_elapsed;
-                            log::warn!(
-                                "Kys cooldown not complete. {} seconds remaining. Exiting again.",
-                                remaining_time
-                            );
-                            std::process::exit(43);
-                        }
-                    } else {
-                        log::warn!("Failed to parse kys info JSON, cleaning up file");
-                        let _ = fs::remove_file("/tmp/rustbot_kys_info.json");
-                    }
-                }
-
-                // Log all registered commands for debugging
-                let commands = &framework.options().commands;
-                log::info!("Registering {} commands:", commands.len());
-                for command in commands {
-                    log::info!(
-                        "  - {} (prefix: {}, slash: {})",
-                        command.name,
-                        command.prefix_action.is_some(),
-                        command.slash_action.is_some()
-                    );
-                }
-
-                poise::builtins::register_globally(ctx, commands).await?;
-                log::info!("All commands registered successfully");
-
-                // Start reminder checker background task
-                start_reminder_checker(ctx.http.clone());
-                log::info!("Reminder checker started");
-
-                // Start parking scheduler background task
-                start_parking_scheduler(ctx.http.clone());
-                log::info!("Parking scheduler started");
-
-                Ok(Data {})
-            })
-        })
-        .build();
+    // Asking for an intent the application doesn't have gets the connection
+    // refused, so check the privileged ones first
+    let intents = rustbot::startup_intents(&serenity::Http::new(&token)).await;
 
     let mut client = Client::builder(&token, intents)
-        .framework(framework)
+        .framework(rustbot::build_framework())
         .register_songbird()
         .await
         .expect("Error creating client");
 
+    // Leave a shutdown marker on Ctrl+C / SIGTERM so the next startup
+    // doesn't report a crash
+    rustbot::lifecycle::spawn_shutdown_handler(client.shard_manager.clone());
+
     // Start the bot
     log::info!("Starting bot...");
     if let Err(why) = client.start().await {
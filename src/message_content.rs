@@ -0,0 +1,116 @@
+//! Whether the bot can read message text. With the Message Content intent
+//! turned off in the Developer Portal, Discord still delivers messages but
+//! with empty text, so `-` commands silently stop working. Messages that
+//! mention the bot (and DMs) keep their text, so `@Bot help` still works
+//! through poise's `mention_as_prefix`, and slash commands aren't affected.
+//!
+//! The intent is checked through the application's flags before the client
+//! connects, because asking for a privileged intent the application doesn't
+//! have makes Discord close the connection (4014). If it's off, the bot
+//! connects without it, the owner is told and a bare mention of the bot
+//! explains the situation instead of the usual mention reply.
+
+use crate::utils::send_dm_to_deekahy;
+use poise::serenity_prelude as serenity;
+use serenity::{ApplicationFlags, GatewayIntents};
+use std::sync::atomic::{AtomicBool, Ordering};
+
+/// Assumed until the startup check says otherwise.
+static CONTENT_AVAILABLE: AtomicBool = AtomicBool::new(true);
+
+/// Whether prefix commands can work, as far as the startup check knows.
+pub fn content_available() -> bool {
+    CONTENT_AVAILABLE.load(Ordering::Relaxed)
+}
+
+/// Whether the application flags allow reading message text. The limited
+/// flag is what bots in under 100 servers get. `None` if Discord didn't
+/// send the flags.
+fn content_allowed(flags: Option<ApplicationFlags>) -> Option<bool> {
+    flags.map(|flags| {
+        flags.intersects(
+            ApplicationFlags::GATEWAY_MESSAGE_CONTENT
+                | ApplicationFlags::GATEWAY_MESSAGE_CONTENT_LIMITED,
+        )
+    })
+}
+
+/// What a bare mention gets while the intent is off.
+pub fn fallback_notice(bot_name: &str) -> String {
+    format!(
+        "⚠️ I can't read messages right now, so `-` commands don't work. \
+         Use slash commands like `/help`, or mention me instead of the dash: `@{} help`.",
+        bot_name
+    )
+}
+
+/// Drop the Message Content intent from `intents` if the application
+/// isn't allowed it, and remember whether message text can be read. Run
+/// before the client is built; if the flags can't be fetched the intent is
+/// kept.
+pub async fn check_message_content(
+    http: &serenity::Http,
+    intents: GatewayIntents,
+) -> GatewayIntents {
+    let flags = match http.get_current_application_info().await {
+        Ok(info) => info.flags,
+        Err(e) => {
+            log::warn!("Couldn't check the Message Content intent: {}", e);
+            return intents;
+        }
+    };
+    if content_allowed(flags) != Some(false) {
+        CONTENT_AVAILABLE.store(true, Ordering::Relaxed);
+        return intents;
+    }
+
+    CONTENT_AVAILABLE.store(false, Ordering::Relaxed);
+    log::error!("==============================================================");
+    log::error!("The Message Content intent is OFF in the Developer Portal.");
+    log::error!("Prefix commands won't work; slash commands and @mentions will.");
+    log::error!("==============================================================");
+    intents - GatewayIntents::MESSAGE_CONTENT
+}
+
+/// Tell the owner once the bot is up if message text can't be read; the
+/// bot keeps running on slash commands and mentions.
+pub async fn report_missing_content(http: &serenity::Http) {
+    if content_available() {
+        return;
+    }
+    let message = "⚠️ The Message Content intent is turned off in the Developer Portal, so `-` \
+                   commands don't work. Slash commands and `@mention` commands still do. Turn it \
+                   back on under Bot → Privileged Gateway Intents and restart the bot.";
+    if let Err(e) = send_dm_to_deekahy(http, message).await {
+        log::warn!("Failed to report the missing Message Content intent: {}", e);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_content_allowed() {
+        assert_eq!(
+            content_allowed(Some(ApplicationFlags::GATEWAY_MESSAGE_CONTENT)),
+            Some(true)
+        );
+        assert_eq!(
+            content_allowed(Some(
+                ApplicationFlags::GATEWAY_MESSAGE_CONTENT_LIMITED
+                    | ApplicationFlags::GATEWAY_GUILD_MEMBERS_LIMITED
+            )),
+            Some(true)
+        );
+        assert_eq!(
+            content_allowed(Some(ApplicationFlags::GATEWAY_PRESENCE)),
+            Some(false)
+        );
+        assert_eq!(
+            content_allowed(Some(ApplicationFlags::empty())),
+            Some(false)
+        );
+        assert_eq!(content_allowed(None), None);
+    }
+}
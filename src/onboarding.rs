@@ -0,0 +1,214 @@
+//! One-time hint for people using the bot in a guild for the first time.
+//!
+//! Every user who successfully runs a command is added to a per-guild seen set.
+//! The first time someone is added they get a short pointer to `help` and the
+//! guild's most used commands. Seen sets are saved in batches by a background
+//! task, like command usage.
+
+use crate::commands::usage;
+use crate::guild_config;
+use crate::storage;
+use crate::utils::is_protected_user;
+use crate::{Context, Error};
+use parking_lot::RwLock;
+use poise::serenity_prelude as serenity;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet, VecDeque};
+use tokio::time::{interval, Duration};
+
+const SEEN_USERS_FILE: &str = "seen_users.json";
+
+/// Per-guild cap on remembered users; the longest-known users are forgotten first.
+const MAX_SEEN_USERS_PER_GUILD: usize = 50_000;
+
+/// Window used to pick the "popular here" commands.
+const POPULAR_COMMANDS_DAYS: u32 = 30;
+
+/// Users in the order they were first seen, with a set for fast lookups.
+#[derive(Serialize, Deserialize, Default)]
+struct SeenUsers {
+    order: VecDeque<u64>,
+    #[serde(skip)]
+    lookup: HashSet<u64>,
+}
+
+impl SeenUsers {
+    /// Remember a user, evicting the oldest past `cap`. Returns `true` if the
+    /// user hadn't been seen before.
+    fn insert(&mut self, user_id: u64, cap: usize) -> bool {
+        if !self.lookup.insert(user_id) {
+            return false;
+        }
+        self.order.push_back(user_id);
+        while self.order.len() > cap {
+            if let Some(oldest) = self.order.pop_front() {
+                self.lookup.remove(&oldest);
+            }
+        }
+        true
+    }
+}
+
+#[derive(Serialize, Deserialize, Default)]
+struct SeenUsersData {
+    guilds: HashMap<u64, SeenUsers>,
+}
+
+struct SeenUsersStore {
+    data: SeenUsersData,
+    dirty: bool,
+}
+
+lazy_static::lazy_static! {
+    static ref SEEN_USERS: RwLock<SeenUsersStore> = RwLock::new(SeenUsersStore {
+        data: load_seen_users(),
+        dirty: false,
+    });
+}
+
+fn load_seen_users() -> SeenUsersData {
+    let mut data: SeenUsersData = storage::load(SEEN_USERS_FILE);
+    for seen in data.guilds.values_mut() {
+        seen.lookup = seen.order.iter().copied().collect();
+    }
+    data
+}
+
+/// Save the seen sets if anyone new was added since the last save.
+pub fn flush_seen_users() {
+    let mut store = SEEN_USERS.write();
+    if store.dirty {
+        match storage::save(SEEN_USERS_FILE, &store.data) {
+            Ok(()) => store.dirty = false,
+            Err(e) => log::error!("Failed to save seen users: {}", e),
+        }
+    }
+}
+
+/// Drop an orphaned guild's seen set, so a re-add greets everyone again.
+pub fn purge_guild(guild_id: u64) {
+    let mut store = SEEN_USERS.write();
+    if store.data.guilds.remove(&guild_id).is_some() {
+        store.dirty = true;
+    }
+}
+
+/// Start the background task that periodically saves the seen sets
+pub fn start_seen_users_flusher() {
+    tokio::spawn(async move {
+        let mut interval = interval(Duration::from_secs(60)); // Flush every minute
+
+        loop {
+            interval.tick().await;
+            flush_seen_users();
+        }
+    });
+}
+
+fn hint_message(guild_name: &str, prefix: &str, popular: &[String]) -> String {
+    let mut message = format!(
+        "👋 Looks like this is your first time using me in **{}**!\n\
+         Use `{}help` or `/help` to see everything I can do.",
+        guild_name, prefix
+    );
+    if !popular.is_empty() {
+        let commands = popular
+            .iter()
+            .map(|name| format!("`{}{}`", prefix, name))
+            .collect::<Vec<_>>()
+            .join(", ");
+        message.push_str(&format!("\n🔥 Popular here: {}", commands));
+    }
+    message
+}
+
+/// Show the onboarding hint if this is the author's first command in the
+/// guild. Called from the framework's `post_command` hook, so the command's
+/// own response has already been sent.
+pub async fn maybe_send_hint(ctx: Context<'_>) -> Result<(), Error> {
+    let Some(guild_id) = ctx.guild_id() else {
+        return Ok(());
+    };
+    if is_protected_user(&ctx.author().name) {
+        return Ok(());
+    }
+
+    let first_time = {
+        let mut store = SEEN_USERS.write();
+        let first_time = store
+            .data
+            .guilds
+            .entry(guild_id.get())
+            .or_default()
+            .insert(ctx.author().id.get(), MAX_SEEN_USERS_PER_GUILD);
+        store.dirty |= first_time;
+        first_time
+    };
+    if !first_time {
+        return Ok(());
+    }
+
+    let guild_name = ctx
+        .guild()
+        .map(|guild| guild.name.clone())
+        .unwrap_or_else(|| "this server".to_string());
+    let popular = usage::top_commands(guild_id.get(), POPULAR_COMMANDS_DAYS, 3);
+    let prefix = guild_config::command_prefix(Some(guild_id.get()));
+    let hint = hint_message(&guild_name, &prefix, &popular);
+
+    match ctx {
+        poise::Context::Application(_) => {
+            ctx.send(poise::CreateReply::default().content(hint).ephemeral(true))
+                .await?;
+        }
+        poise::Context::Prefix(_) => {
+            ctx.author()
+                .dm(ctx.http(), serenity::CreateMessage::new().content(hint))
+                .await?;
+        }
+    }
+
+    log::info!(
+        "Sent onboarding hint to {} in guild {}",
+        ctx.author().name,
+        guild_id
+    );
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_insert_only_first_time() {
+        let mut seen = SeenUsers::default();
+        assert!(seen.insert(1, 10));
+        assert!(!seen.insert(1, 10));
+        assert!(seen.insert(2, 10));
+    }
+
+    #[test]
+    fn test_insert_evicts_oldest_past_cap() {
+        let mut seen = SeenUsers::default();
+        for user in 1..=3 {
+            seen.insert(user, 2);
+        }
+        assert_eq!(seen.order, VecDeque::from([2, 3]));
+        assert!(!seen.lookup.contains(&1));
+        // An evicted user counts as new again
+        assert!(seen.insert(1, 2));
+    }
+
+    #[test]
+    fn test_hint_message() {
+        let hint = hint_message("Test Server", "-", &["play".to_string(), "hit".to_string()]);
+        assert!(hint.contains("**Test Server**"));
+        assert!(hint.contains("`-play`, `-hit`"));
+        assert!(!hint_message("Test Server", "-", &[]).contains("Popular"));
+
+        let hint = hint_message("Test Server", "!", &["play".to_string()]);
+        assert!(hint.contains("`!help`"));
+        assert!(hint.contains("`!play`"));
+    }
+}
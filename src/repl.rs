@@ -0,0 +1,223 @@
+//! Offline mode (`rustbot --repl`): type prefix commands on stdin and see
+//! what the bot would answer, without a Discord token. Only commands whose
+//! logic doesn't need Discord can run; the rest say so.
+
+use crate::commands::coinflip::{flip, flip_message};
+use crate::commands::dice::{roll_dice, roll_message, DiceRoll};
+use crate::commands::general::GameSessions;
+use crate::commands::hangman;
+use crate::commands::poll::{parse_poll, poll_options_text, take_duration_flag};
+use crate::commands::remind::{format_duration, plan_reminder, priority_label};
+use crate::commands::uwu::take_level_flag;
+use crate::utils::args::pop_arg;
+use crate::utils::reply::{Printer, Replier};
+use crate::utils::schedule::{check_schedule_time, max_schedule_horizon, ScheduleCheck};
+use crate::utils::transforms::{clapify, fit_message, mockify, owoify, uwuify};
+use crate::{build_framework_options, Data, Error, DEFAULT_PREFIX};
+use chrono::Utc;
+use std::io::{BufRead, Write};
+
+/// Who's typing. Hangman games are kept per user, so the REPL plays as one.
+const REPL_USER_ID: u64 = 0;
+const REPL_USER_NAME: &str = "you";
+
+/// Commands that work offline, by qualified name.
+const OFFLINE_COMMANDS: &[&str] = &[
+    "clap",
+    "coinflip",
+    "dice",
+    "endhangman",
+    "hangman",
+    "hangmanhint",
+    "hangmanstatus",
+    "letter",
+    "mock",
+    "owoify",
+    "poll",
+    "remind",
+    "remind set",
+    "uwu",
+];
+
+/// Read commands from `input` until it runs out (or `quit`), writing the
+/// replies to `output`.
+pub async fn run<R: BufRead, W: Write + Send>(input: R, output: W) -> Result<W, Error> {
+    let options = build_framework_options();
+    let prefix = DEFAULT_PREFIX;
+    let mut out = Printer::new(output);
+    let games = GameSessions::default();
+
+    out.say(format!(
+        "RustBot offline mode. Available: {}. Type `quit` to leave.",
+        OFFLINE_COMMANDS
+            .iter()
+            .map(|name| format!("{}{}", prefix, name))
+            .collect::<Vec<_>>()
+            .join(", ")
+    ))
+    .await?;
+
+    for line in input.lines() {
+        let line = line?;
+        let line = line.trim();
+        if line.is_empty() {
+            continue;
+        }
+        if line == "quit" || line == "exit" {
+            break;
+        }
+        let Some(invocation) = line.strip_prefix(prefix) else {
+            out.say(format!(
+                "❓ Commands start with `{}`, e.g. `{}dice 20`",
+                prefix, prefix
+            ))
+            .await?;
+            continue;
+        };
+        execute(&options.commands, &games, prefix, invocation, &mut out).await?;
+    }
+
+    Ok(out.into_inner())
+}
+
+/// Look the command up the way the prefix framework would and run it.
+async fn execute(
+    commands: &[poise::Command<Data, Error>],
+    games: &GameSessions,
+    prefix: &str,
+    invocation: &str,
+    out: &mut impl Replier,
+) -> Result<(), Error> {
+    let mut parents = Vec::new();
+    let Some((command, _, args)) = poise::find_command(commands, invocation, true, &mut parents)
+    else {
+        let name = invocation.split_whitespace().next().unwrap_or_default();
+        return out
+            .say(format!("❓ Unknown command `{}{}`", prefix, name))
+            .await;
+    };
+    let args = args.trim();
+    let first_arg = args.split_whitespace().next();
+    // `qualified_name` is only filled in once a framework is built, so put
+    // it together from the parents instead
+    let name = parents
+        .iter()
+        .chain([&command])
+        .map(|command| command.name.as_str())
+        .collect::<Vec<_>>()
+        .join(" ");
+
+    match name.as_str() {
+        "coinflip" => {
+            let heads = flip(0.5, &mut rand::thread_rng());
+            out.say(flip_message(REPL_USER_NAME, heads)).await
+        }
+        "dice" => {
+            let sides = match first_arg.map(str::parse::<u32>) {
+                None => 6,
+                Some(Ok(sides)) => sides,
+                Some(Err(_)) => return out.say("❌ The number of sides must be a number!").await,
+            };
+            let roll = roll_dice(sides, &mut rand::thread_rng());
+            match roll {
+                DiceRoll::Invalid(message) | DiceRoll::Marvin(message) => out.say(message).await,
+                DiceRoll::Rolled(result) => {
+                    out.say(roll_message(REPL_USER_NAME, sides, result)).await
+                }
+            }
+        }
+        "uwu" | "mock" | "clap" | "owoify" if args.is_empty() => {
+            out.say("❌ Please provide some text!").await
+        }
+        "uwu" => {
+            let (level, text) = match take_level_flag(args) {
+                Ok(parsed) => parsed,
+                Err(message) => return out.say(message).await,
+            };
+            if text.is_empty() {
+                return out.say("❌ Please provide some text!").await;
+            }
+            out.say(fit_message(&uwuify(text, level, &mut rand::thread_rng())))
+                .await
+        }
+        "mock" => out.say(fit_message(&mockify(args))).await,
+        "clap" => out.say(fit_message(&clapify(args))).await,
+        "owoify" => {
+            out.say(fit_message(&owoify(args, &mut rand::thread_rng())))
+                .await
+        }
+        "poll" => match take_duration_flag(args).and_then(|(_, rest)| parse_poll(rest)) {
+            Ok((question, options)) => {
+                out.say(format!(
+                    "📊 Poll\n**{}**\n\n{}",
+                    question,
+                    poll_options_text(&options)
+                ))
+                .await
+            }
+            Err(error) => out.say(error).await,
+        },
+        "remind" | "remind set" => {
+            // The time is split off like the command's `Arg` does
+            let (time, message) = match pop_arg(args) {
+                Ok(Some((time, rest))) => (time, rest.trim()),
+                Ok(None) => return out.say("❌ Usage: `remind set <time> <message>`").await,
+                Err(error) => return out.say(error.to_string()).await,
+            };
+            let message = (!message.is_empty()).then(|| message.to_string());
+            let now = Utc::now();
+            let Some(plan) = plan_reminder(out, &time, None, message, true, false, now).await?
+            else {
+                return Ok(());
+            };
+            let warning = match check_schedule_time(
+                "reminder",
+                now,
+                plan.remind_at,
+                max_schedule_horizon(),
+            ) {
+                Ok(ScheduleCheck::Fine) => None,
+                Ok(ScheduleCheck::Confirm(warning)) => Some(warning),
+                Err(error) => return out.say(error).await,
+            };
+            if let Some(warning) = warning {
+                out.say(format!("⚠️ {} (would ask to confirm)", warning))
+                    .await?;
+            }
+            let priority = match priority_label(plan.priority) {
+                Some(label) => format!(" ({})", label),
+                None => String::new(),
+            };
+            out.say(format!(
+                "⏰ Would remind you at {} ({} from now){}: {}",
+                plan.remind_at.format("%Y-%m-%d %H:%M UTC"),
+                format_duration(plan.remind_at - now),
+                priority,
+                plan.message
+            ))
+            .await
+        }
+        "hangman" => {
+            hangman::start_game(
+                out,
+                games,
+                REPL_USER_ID,
+                REPL_USER_NAME,
+                None,
+                first_arg.map(str::to_string),
+            )
+            .await
+        }
+        "letter" => hangman::guess(out, games, REPL_USER_ID, first_arg.unwrap_or_default()).await,
+        "hangmanstatus" => hangman::show_status(out, games, REPL_USER_ID).await,
+        "hangmanhint" => hangman::show_hint(out, games, REPL_USER_ID).await,
+        "endhangman" => hangman::end_game(out, games, REPL_USER_ID).await,
+        name => {
+            out.say(format!(
+                "🔌 `{}{}` needs Discord and isn't available offline.",
+                prefix, name
+            ))
+            .await
+        }
+    }
+}
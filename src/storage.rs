@@ -0,0 +1,719 @@
+//! Shared JSON file storage for state that has to survive a restart.
+//!
+//! Each store is a single pretty-printed JSON file inside [`DATA_DIR`]. Loading
+//! never fails: a missing or unparseable file yields the type's default so a
+//! corrupt file can't keep the bot from starting. An unparseable file is moved
+//! aside first, so the next save doesn't overwrite it, and reported by the
+//! config check ([`crate::config_check`]). Saves go through a temporary
+//! file and a rename so a crash mid-write never leaves a truncated file behind,
+//! one save of a store at a time.
+//!
+//! Files are saved as `{"schema_version": N, "data": ...}` so a build can tell
+//! when a file was written by a newer one (e.g. after a rollback). Such a
+//! store is still loaded, but becomes read-only for the rest of the run:
+//! saving it would silently drop whatever the newer build added. Files from
+//! before the envelope count as version 0.
+//!
+//! Builds from before the envelope can't read it: they see every store as
+//! empty and their first save resets it. Rolling back that far means
+//! restoring a backup taken before the upgrade (`-backup restore`), or
+//! replacing each file with its `data` by hand, before starting the old build.
+//!
+//! For backups, every persisted file can be bundled into a `.tar.gz`
+//! ([`bundle_files`]) and written back from one ([`restore_bundle`]).
+
+use crate::Error;
+use chrono::Utc;
+use flate2::read::GzDecoder;
+use flate2::write::GzEncoder;
+use flate2::Compression;
+use lazy_static::lazy_static;
+use parking_lot::Mutex;
+use serde::{de::DeserializeOwned, Serialize};
+use serde_json::Value;
+use std::collections::{BTreeSet, HashMap};
+use std::fmt;
+use std::fs;
+use std::io::{Read, Write};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+/// Directory every store lives in (shared with the parking data).
+pub const DATA_DIR: &str = "/var/lib/rustbot";
+
+/// Full path of a store file inside [`DATA_DIR`].
+pub fn data_path(file_name: &str) -> PathBuf {
+    Path::new(DATA_DIR).join(file_name)
+}
+
+/// The schema version this build reads and writes, per store file name.
+/// Stores not listed are at version 1. Bump a store's version in the same
+/// change as any new field or other format change older builds would lose
+/// data reading, so they leave the file alone instead of saving over it.
+/// A new store file needs no entry; older builds never open it.
+const SCHEMA_VERSIONS: &[(&str, u32)] = &[
+    // 2: chained reminders (relative_to, relative_offset_secs)
+    // 3: follow-ups (followup_after_minutes, awaiting_followup)
+    // 4: note reminders (note_id)
+    // 5: priorities (priority)
+    // 6: DM cancel copies (cancel_message_id)
+    // 7: quiet hours (quiet_hours)
+    // 8: when-free reminders (until_free)
+    // 9: webhook reminders (webhook)
+    // 10: reminders claimed for sending (sending)
+    ("rustbot_reminders.json", 10),
+    // 2: scheduled request claims (scheduled_attempts)
+    ("parking_data.json", 2),
+    // 2: mention replies (mention_reply_enabled, mention_reply)
+    ("guild_configs.json", 2),
+    // 2: per-server logs (guilds)
+    ("dm_log.json", 2),
+];
+
+const SCHEMA_VERSION_KEY: &str = "schema_version";
+const DATA_KEY: &str = "data";
+
+fn schema_version(path: &Path) -> u32 {
+    let name = path.file_name().unwrap_or_default().to_string_lossy();
+    SCHEMA_VERSIONS
+        .iter()
+        .find(|(file_name, _)| *file_name == name)
+        .map_or(1, |(_, version)| *version)
+}
+
+lazy_static! {
+    /// Paths of the stores written by a newer build, which must not be saved.
+    static ref READ_ONLY_STORES: Mutex<BTreeSet<PathBuf>> = Mutex::new(BTreeSet::new());
+    /// Stores that couldn't be parsed since the config check last asked.
+    static ref UNREADABLE_STORES: Mutex<Vec<UnreadableStore>> = Mutex::new(Vec::new());
+    /// One lock per store file, held while it's written and renamed into
+    /// place, so two saves of a store never share its temporary file.
+    static ref SAVE_LOCKS: Mutex<HashMap<PathBuf, Arc<Mutex<()>>>> = Mutex::new(HashMap::new());
+}
+
+/// The lock that saves of the store at `path` take turns on.
+fn save_lock(path: &Path) -> Arc<Mutex<()>> {
+    SAVE_LOCKS
+        .lock()
+        .entry(path.to_path_buf())
+        .or_default()
+        .clone()
+}
+
+/// A store file that couldn't be parsed and was moved aside.
+#[derive(Debug, Clone, PartialEq)]
+pub struct UnreadableStore {
+    pub file_name: String,
+    /// Where the file is now, next to where it was.
+    pub moved_to: String,
+    pub error: String,
+}
+
+/// The stores that were moved aside since the last call.
+pub fn take_unreadable() -> Vec<UnreadableStore> {
+    std::mem::take(&mut *UNREADABLE_STORES.lock())
+}
+
+/// Returned instead of saving a store written by a newer build.
+#[derive(Debug)]
+pub struct ReadOnlyStore;
+
+impl fmt::Display for ReadOnlyStore {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "this feature is read-only until the bot is updated")
+    }
+}
+
+impl std::error::Error for ReadOnlyStore {}
+
+/// Whether the store at `path` was written by a newer build and so can't
+/// be saved until the bot is updated.
+pub fn is_read_only(path: &Path) -> bool {
+    READ_ONLY_STORES.lock().contains(path)
+}
+
+/// Load a store, falling back to `T::default()` if it is missing or invalid.
+pub fn load<T: DeserializeOwned + Default>(file_name: &str) -> T {
+    load_from(&data_path(file_name))
+}
+
+/// Persist a store, creating [`DATA_DIR`] if needed.
+pub fn save<T: Serialize>(file_name: &str, data: &T) -> Result<(), Error> {
+    save_to(&data_path(file_name), data)
+}
+
+/// [`load`] for a store that migrates old formats by hand: data that isn't
+/// a `T` goes to `migrate`, and only if that fails too is the file set aside.
+pub fn load_migrating<T: DeserializeOwned + Default>(
+    file_name: &str,
+    migrate: impl FnOnce(&Value) -> Option<T>,
+) -> T {
+    let path = data_path(file_name);
+    load_or_migrate(&path, schema_version(&path), migrate)
+}
+
+/// [`load`] for a store that lives outside [`DATA_DIR`].
+pub fn load_from<T: DeserializeOwned + Default>(path: &Path) -> T {
+    load_versioned(path, schema_version(path))
+}
+
+/// [`save`] for a store that lives outside [`DATA_DIR`].
+pub fn save_to<T: Serialize>(path: &Path, data: &T) -> Result<(), Error> {
+    save_versioned(path, data, schema_version(path))
+}
+
+/// Re-read a store that's already in memory, e.g. after the file was edited
+/// by hand. Unlike [`load`], a file that can't be read or parsed is an error
+/// so the caller can keep what it has; a missing file is still the default.
+pub fn reload<T: DeserializeOwned + Default>(file_name: &str) -> Result<T, Error> {
+    reload_from(&data_path(file_name))
+}
+
+/// [`reload`] for a store that lives outside [`DATA_DIR`].
+pub fn reload_from<T: DeserializeOwned + Default>(path: &Path) -> Result<T, Error> {
+    let content = match fs::read_to_string(path) {
+        Ok(content) => content,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(T::default()),
+        Err(e) => return Err(e.into()),
+    };
+    let payload = unwrap_envelope(path, schema_version(path), serde_json::from_str(&content)?);
+    Ok(serde_json::from_value(payload)?)
+}
+
+/// The stored data of the file at `path`, unwrapped from its envelope, for
+/// stores that migrate old formats by hand. `None` if the file is missing
+/// or isn't JSON.
+pub fn load_json(path: &Path) -> Option<Value> {
+    read_payload(path, schema_version(path))
+}
+
+fn load_versioned<T: DeserializeOwned + Default>(path: &Path, known: u32) -> T {
+    load_or_migrate(path, known, |_| None)
+}
+
+fn load_or_migrate<T: DeserializeOwned + Default>(
+    path: &Path,
+    known: u32,
+    migrate: impl FnOnce(&Value) -> Option<T>,
+) -> T {
+    let Ok(content) = fs::read_to_string(path) else {
+        return T::default();
+    };
+    let parsed = serde_json::from_str(&content).and_then(|value| {
+        let payload = unwrap_envelope(path, known, value);
+        T::deserialize(&payload).or_else(|e| migrate(&payload).ok_or(e))
+    });
+    match parsed {
+        Ok(data) => data,
+        // A newer build's file is kept where it is for when it's back
+        Err(e) if is_read_only(path) => {
+            log::warn!("Failed to parse {}: {}, starting fresh", path.display(), e);
+            T::default()
+        }
+        Err(e) => {
+            set_aside(path, &e.to_string());
+            T::default()
+        }
+    }
+}
+
+/// Move an unparseable store next to where it was, so saving the fresh one
+/// doesn't overwrite it, and note it for [`take_unreadable`].
+fn set_aside(path: &Path, error: &str) {
+    let moved_to = path.with_extension(format!(
+        "json.invalid-{}",
+        Utc::now().format("%Y%m%d-%H%M%S")
+    ));
+    if let Err(e) = fs::rename(path, &moved_to) {
+        log::warn!(
+            "Failed to parse {}: {}, starting fresh (couldn't move it aside: {})",
+            path.display(),
+            error,
+            e
+        );
+        return;
+    }
+    log::warn!(
+        "Failed to parse {}: {}, moved it to {} and starting fresh",
+        path.display(),
+        error,
+        moved_to.display()
+    );
+    let file_name = |path: &Path| {
+        path.file_name()
+            .unwrap_or_default()
+            .to_string_lossy()
+            .into_owned()
+    };
+    UNREADABLE_STORES.lock().push(UnreadableStore {
+        file_name: file_name(path),
+        moved_to: file_name(&moved_to),
+        error: error.to_string(),
+    });
+}
+
+fn read_payload(path: &Path, known: u32) -> Option<Value> {
+    let content = fs::read_to_string(path).ok()?;
+    let value = match serde_json::from_str(&content) {
+        Ok(value) => value,
+        Err(e) => {
+            log::warn!("Failed to parse {}: {}, starting fresh", path.display(), e);
+            return None;
+        }
+    };
+
+    Some(unwrap_envelope(path, known, value))
+}
+
+/// The data in a stored file's JSON, marking the store read-only if a newer
+/// build wrote it.
+fn unwrap_envelope(path: &Path, known: u32, value: Value) -> Value {
+    let (version, payload) = split_envelope(value);
+    if version > known {
+        log::warn!(
+            "{} has schema version {} but this build only knows {}, keeping it read-only",
+            path.display(),
+            version,
+            known
+        );
+        READ_ONLY_STORES.lock().insert(path.to_path_buf());
+    }
+    payload
+}
+
+/// The schema version and data of a stored file; files without the
+/// envelope are version 0.
+fn split_envelope(value: Value) -> (u32, Value) {
+    if let Value::Object(mut map) = value {
+        let version = map.get(SCHEMA_VERSION_KEY).and_then(Value::as_u64);
+        if let (Some(version), true) = (version, map.len() == 2) {
+            if let Some(data) = map.remove(DATA_KEY) {
+                return (u32::try_from(version).unwrap_or(u32::MAX), data);
+            }
+        }
+        return (0, Value::Object(map));
+    }
+    (0, value)
+}
+
+#[derive(Serialize)]
+struct Envelope<'a, T> {
+    schema_version: u32,
+    data: &'a T,
+}
+
+fn save_versioned<T: Serialize>(path: &Path, data: &T, version: u32) -> Result<(), Error> {
+    if is_read_only(path) {
+        return Err(ReadOnlyStore.into());
+    }
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir)?;
+    }
+
+    let json = serde_json::to_string_pretty(&Envelope {
+        schema_version: version,
+        data,
+    })?;
+    let lock = save_lock(path);
+    let _saving = lock.lock();
+    let tmp_path = path.with_extension("json.tmp");
+    fs::write(&tmp_path, json)?;
+    restrict_to_owner(&tmp_path)?;
+    fs::rename(&tmp_path, path)?;
+    Ok(())
+}
+
+/// Make a file readable by the bot's user only, since stores can hold
+/// secrets such as webhook tokens.
+pub fn restrict_to_owner(path: &Path) -> std::io::Result<()> {
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        fs::set_permissions(path, fs::Permissions::from_mode(0o600))?;
+    }
+    #[cfg(not(unix))]
+    let _ = path;
+    Ok(())
+}
+
+/// Check the schema version of every persisted JSON file, so stores written
+/// by a newer build are read-only from the start rather than from their
+/// first load. Returns the names of the read-only stores.
+pub fn check_schema_versions() -> Vec<String> {
+    fs::read_dir(DATA_DIR)
+        .into_iter()
+        .flatten()
+        .flatten()
+        .map(|entry| entry.path())
+        .filter(|path| path.extension().is_some_and(|ext| ext == "json"))
+        .filter(|path| {
+            read_payload(path, schema_version(path));
+            is_read_only(path)
+        })
+        .map(|path| {
+            path.file_name()
+                .unwrap_or_default()
+                .to_string_lossy()
+                .into_owned()
+        })
+        .collect()
+}
+
+/// Files in [`DATA_DIR`] that are left out of backups: the parking
+/// encryption key (a backup shouldn't carry the key to its own data) and the
+/// shutdown marker and instance lock, which only describe the current run.
+const NOT_BACKED_UP: &[&str] = &["parking_key", "shutdown_marker.json", "instance.lock"];
+
+/// Bundle every persisted file into a `.tar.gz`.
+pub fn bundle_files() -> Result<Vec<u8>, Error> {
+    bundle_from(Path::new(DATA_DIR))
+}
+
+/// Write the files in a bundle from [`bundle_files`] back to where they
+/// belong. Returns the names of the restored files.
+pub fn restore_bundle(bundle: &[u8]) -> Result<Vec<String>, Error> {
+    restore_into(Path::new(DATA_DIR), bundle)
+}
+
+/// Check that a bundle can be read and list the files in it.
+pub fn bundle_contents(bundle: &[u8]) -> Result<Vec<String>, Error> {
+    Ok(read_bundle(bundle)?
+        .into_iter()
+        .map(|(name, _)| name)
+        .collect())
+}
+
+fn bundle_from(dir: &Path) -> Result<Vec<u8>, Error> {
+    let mut files = Vec::new();
+    if dir.exists() {
+        for entry in fs::read_dir(dir)? {
+            let entry = entry?;
+            let name = entry.file_name().to_string_lossy().into_owned();
+            // Subdirectories hold snapshots of earlier bundles
+            if !entry.file_type()?.is_file()
+                || name.ends_with(".tmp")
+                || NOT_BACKED_UP.contains(&name.as_str())
+            {
+                continue;
+            }
+            files.push((name, fs::read(entry.path())?));
+        }
+    }
+    files.sort();
+
+    let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+    encoder.write_all(&write_tar(&files)?)?;
+    Ok(encoder.finish()?)
+}
+
+fn restore_into(dir: &Path, bundle: &[u8]) -> Result<Vec<String>, Error> {
+    let files = read_bundle(bundle)?;
+    fs::create_dir_all(dir)?;
+    let mut restored = Vec::new();
+    for (name, content) in files {
+        let path = dir.join(&name);
+        let tmp_path = path.with_extension("restore.tmp");
+        fs::write(&tmp_path, content)?;
+        restrict_to_owner(&tmp_path)?;
+        fs::rename(&tmp_path, &path)?;
+        restored.push(name);
+    }
+    Ok(restored)
+}
+
+fn read_bundle(bundle: &[u8]) -> Result<Vec<(String, Vec<u8>)>, Error> {
+    let mut tar = Vec::new();
+    GzDecoder::new(bundle).read_to_end(&mut tar)?;
+    let files = read_tar(&tar)?;
+    for (name, _) in &files {
+        // Only plain names: a bundle must not write outside the data directory
+        if name.is_empty() || name.contains(['/', '\\']) || name.starts_with('.') {
+            return Err(format!("unexpected file name in backup: {:?}", name).into());
+        }
+    }
+    Ok(files)
+}
+
+const TAR_BLOCK: usize = 512;
+
+/// A minimal ustar archive of regular files, which is all a bundle needs.
+fn write_tar(files: &[(String, Vec<u8>)]) -> Result<Vec<u8>, Error> {
+    let mut tar = Vec::new();
+    for (name, content) in files {
+        if name.len() > 99 {
+            return Err(format!("file name too long for a backup: {}", name).into());
+        }
+        let mut header = [0u8; TAR_BLOCK];
+        header[..name.len()].copy_from_slice(name.as_bytes());
+        header[100..108].copy_from_slice(b"0000644\0");
+        header[108..116].copy_from_slice(b"0000000\0");
+        header[116..124].copy_from_slice(b"0000000\0");
+        header[124..136].copy_from_slice(format!("{:011o}\0", content.len()).as_bytes());
+        header[136..148].copy_from_slice(b"00000000000\0");
+        header[156] = b'0';
+        header[257..263].copy_from_slice(b"ustar\0");
+        header[263..265].copy_from_slice(b"00");
+        // The checksum is computed with its own field filled with spaces
+        header[148..156].fill(b' ');
+        let checksum: u32 = header.iter().map(|&b| b as u32).sum();
+        header[148..156].copy_from_slice(format!("{:06o}\0 ", checksum).as_bytes());
+
+        tar.extend_from_slice(&header);
+        tar.extend_from_slice(content);
+        tar.resize(tar.len().next_multiple_of(TAR_BLOCK), 0);
+    }
+    // Two empty blocks mark the end of the archive
+    tar.resize(tar.len() + 2 * TAR_BLOCK, 0);
+    Ok(tar)
+}
+
+/// Read the regular files out of a ustar archive, skipping anything else.
+fn read_tar(tar: &[u8]) -> Result<Vec<(String, Vec<u8>)>, Error> {
+    let mut files = Vec::new();
+    let mut offset = 0;
+    while offset + TAR_BLOCK <= tar.len() {
+        let header = &tar[offset..offset + TAR_BLOCK];
+        if header.iter().all(|&b| b == 0) {
+            break;
+        }
+        let field = |range: std::ops::Range<usize>| {
+            let bytes = &header[range];
+            let end = bytes.iter().position(|&b| b == 0).unwrap_or(bytes.len());
+            String::from_utf8_lossy(&bytes[..end]).trim().to_string()
+        };
+        let name = field(0..100);
+        let size = usize::from_str_radix(&field(124..136), 8)
+            .map_err(|_| format!("corrupt size for {:?} in backup", name))?;
+        let start = offset + TAR_BLOCK;
+        let content = tar
+            .get(start..start + size)
+            .ok_or_else(|| format!("backup is cut off in {:?}", name))?;
+        if matches!(header[156], b'0' | 0) {
+            files.push((name, content.to_vec()));
+        }
+        offset = start + size.next_multiple_of(TAR_BLOCK);
+    }
+    Ok(files)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_roundtrip() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("store.json");
+
+        let mut data: HashMap<u64, String> = HashMap::new();
+        data.insert(1, "one".to_string());
+        save_to(&path, &data).unwrap();
+
+        let loaded: HashMap<u64, String> = load_from(&path);
+        assert_eq!(loaded, data);
+        assert!(!path.with_extension("json.tmp").exists());
+    }
+
+    #[test]
+    fn test_concurrent_saves_of_one_store() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("store.json");
+
+        std::thread::scope(|scope| {
+            for n in 0..8u64 {
+                let path = &path;
+                scope.spawn(move || {
+                    for _ in 0..20 {
+                        let data: HashMap<u64, u64> = (0..100).map(|k| (k, n)).collect();
+                        save_to(path, &data).unwrap();
+                    }
+                });
+            }
+        });
+
+        let loaded: HashMap<u64, u64> = load_from(&path);
+        assert_eq!(loaded.len(), 100);
+        assert!(!path.with_extension("json.tmp").exists());
+    }
+
+    #[test]
+    fn test_missing_or_corrupt_file_yields_default() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("store.json");
+
+        let loaded: HashMap<u64, String> = load_from(&path);
+        assert!(loaded.is_empty());
+
+        fs::write(&path, "not json").unwrap();
+        let loaded: HashMap<u64, String> = load_from(&path);
+        assert!(loaded.is_empty());
+    }
+
+    #[test]
+    fn test_unparseable_store_is_set_aside() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("wrong_types.json");
+        let fixture = include_str!("../tests/fixtures/config/wrong_types.json");
+        fs::write(&path, fixture).unwrap();
+
+        let loaded: HashMap<u64, u64> = load_from(&path);
+        assert!(loaded.is_empty());
+        assert!(!path.exists());
+        let unreadable: Vec<UnreadableStore> = take_unreadable()
+            .into_iter()
+            .filter(|store| store.file_name == "wrong_types.json")
+            .collect();
+        assert_eq!(unreadable.len(), 1, "{unreadable:?}");
+        assert!(unreadable[0]
+            .moved_to
+            .starts_with("wrong_types.json.invalid-"));
+        let moved = dir.path().join(&unreadable[0].moved_to);
+        assert_eq!(fs::read_to_string(moved).unwrap(), fixture);
+
+        // A newer build's file stays put even if this one can't read it
+        let newer = dir.path().join("newer.json");
+        fs::write(&newer, r#"{"schema_version": 99, "data": {"1": "one"}}"#).unwrap();
+        assert!(load_versioned::<HashMap<u64, u64>>(&newer, 1).is_empty());
+        assert!(newer.exists());
+    }
+
+    #[test]
+    fn test_migrating_load() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("old_format.json");
+        let migrate = |value: &Value| {
+            let names = value.get("names")?.as_array()?;
+            Some(
+                names
+                    .iter()
+                    .filter_map(Value::as_str)
+                    .map(String::from)
+                    .collect(),
+            )
+        };
+
+        fs::write(&path, r#"{"names": ["one", "two"]}"#).unwrap();
+        let loaded: Vec<String> = load_or_migrate(&path, 1, migrate);
+        assert_eq!(loaded, ["one", "two"]);
+        assert!(path.exists());
+
+        // Neither format, so it's moved aside before anything is saved
+        fs::write(&path, r#"{"nothing": "useful"}"#).unwrap();
+        let loaded: Vec<String> = load_or_migrate(&path, 1, migrate);
+        assert!(loaded.is_empty());
+        assert!(!path.exists());
+        assert!(take_unreadable()
+            .iter()
+            .any(|store| store.file_name == "old_format.json"));
+    }
+
+    #[test]
+    fn test_reload_reports_corrupt_files() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("store.json");
+
+        let loaded: HashMap<u64, String> = reload_from(&path).unwrap();
+        assert!(loaded.is_empty());
+
+        let data = HashMap::from([(1u64, "one".to_string())]);
+        save_to(&path, &data).unwrap();
+        assert_eq!(reload_from::<HashMap<u64, String>>(&path).unwrap(), data);
+
+        fs::write(&path, "{\"schema_version\": 1, \"data\": {\"1\": 2}}").unwrap();
+        assert!(reload_from::<HashMap<u64, String>>(&path).is_err());
+        fs::write(&path, "{ half written").unwrap();
+        let error = reload_from::<HashMap<u64, String>>(&path).unwrap_err();
+        assert!(error.to_string().contains("line 1"), "{error}");
+    }
+
+    #[test]
+    fn test_schema_versions() {
+        let dir = tempfile::tempdir().unwrap();
+        let data = HashMap::from([(1u64, "one".to_string())]);
+
+        // Equal versions: a plain roundtrip in the envelope
+        let same = dir.path().join("same.json");
+        save_versioned(&same, &data, 2).unwrap();
+        let written: Value = serde_json::from_str(&fs::read_to_string(&same).unwrap()).unwrap();
+        assert_eq!(written[SCHEMA_VERSION_KEY], 2);
+        assert_eq!(load_versioned::<HashMap<u64, String>>(&same, 2), data);
+        assert!(!is_read_only(&same));
+
+        // A newer build reads an older file, including one from before the
+        // envelope, and saves it at its own version
+        let legacy = dir.path().join("legacy.json");
+        fs::write(&legacy, r#"{"1": "one"}"#).unwrap();
+        assert_eq!(load_versioned::<HashMap<u64, String>>(&legacy, 1), data);
+        let older = dir.path().join("older.json");
+        save_versioned(&older, &data, 1).unwrap();
+        assert_eq!(load_versioned::<HashMap<u64, String>>(&older, 2), data);
+        assert!(!is_read_only(&older));
+        save_versioned(&older, &data, 2).unwrap();
+
+        // An older build reads a newer file: the data still loads, but the
+        // file is left alone
+        let newer = dir.path().join("newer.json");
+        save_versioned(&newer, &data, 3).unwrap();
+        let before = fs::read_to_string(&newer).unwrap();
+        assert_eq!(load_versioned::<HashMap<u64, String>>(&newer, 2), data);
+        assert!(is_read_only(&newer));
+        let error = save_versioned(&newer, &HashMap::<u64, String>::new(), 2).unwrap_err();
+        assert!(error.is::<ReadOnlyStore>());
+        assert_eq!(fs::read_to_string(&newer).unwrap(), before);
+    }
+
+    #[test]
+    fn test_schema_versions_are_listed_once() {
+        let names: BTreeSet<&str> = SCHEMA_VERSIONS.iter().map(|(name, _)| *name).collect();
+        assert_eq!(names.len(), SCHEMA_VERSIONS.len());
+        assert!(SCHEMA_VERSIONS.iter().all(|(_, version)| *version > 1));
+    }
+
+    #[test]
+    fn test_bundle_roundtrip() {
+        let data = tempfile::tempdir().unwrap();
+        let reminders = data.path().join("rustbot_reminders.json");
+
+        fs::write(data.path().join("streaks.json"), "{\"a\": 1}").unwrap();
+        fs::write(data.path().join("parking_key"), "secret").unwrap();
+        fs::write(data.path().join("usage.json.tmp"), "half written").unwrap();
+        fs::create_dir(data.path().join("snapshots")).unwrap();
+        fs::write(&reminders, vec![b'x'; 1500]).unwrap();
+
+        let bundle = bundle_from(data.path()).unwrap();
+        assert_eq!(
+            bundle_contents(&bundle).unwrap(),
+            ["rustbot_reminders.json", "streaks.json"]
+        );
+
+        fs::write(data.path().join("streaks.json"), "{}").unwrap();
+        fs::remove_file(&reminders).unwrap();
+        let restored = restore_into(data.path(), &bundle).unwrap();
+        assert_eq!(restored.len(), 2);
+        assert_eq!(
+            fs::read_to_string(data.path().join("streaks.json")).unwrap(),
+            "{\"a\": 1}"
+        );
+        assert_eq!(fs::read(&reminders).unwrap(), vec![b'x'; 1500]);
+        assert_eq!(
+            fs::read_to_string(data.path().join("parking_key")).unwrap(),
+            "secret"
+        );
+    }
+
+    #[test]
+    fn test_bundle_rejects_bad_input() {
+        assert!(bundle_contents(b"not a gzip file").is_err());
+
+        let tar = write_tar(&[("../escape.json".to_string(), b"{}".to_vec())]).unwrap();
+        let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+        encoder.write_all(&tar).unwrap();
+        assert!(bundle_contents(&encoder.finish().unwrap()).is_err());
+
+        // Cut off in the middle of a file
+        let tar = write_tar(&[("big.json".to_string(), vec![b'x'; 2000])]).unwrap();
+        let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+        encoder.write_all(&tar[..1024]).unwrap();
+        assert!(bundle_contents(&encoder.finish().unwrap()).is_err());
+    }
+}
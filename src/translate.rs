@@ -0,0 +1,93 @@
+//! Text translation through a LibreTranslate-compatible API.
+//!
+//! The server is set with `TRANSLATE_API_URL` (defaults to the public
+//! libretranslate.com instance) and, if it needs one, `TRANSLATE_API_KEY`.
+
+use crate::Error;
+use serde::{Deserialize, Serialize};
+use std::env;
+use std::time::Duration;
+
+const DEFAULT_TRANSLATE_API_URL: &str = "https://libretranslate.com";
+
+#[derive(Serialize)]
+struct TranslateRequest<'a> {
+    q: &'a str,
+    source: &'a str,
+    target: &'a str,
+    format: &'a str,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    api_key: Option<String>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct TranslateResponse {
+    translated_text: String,
+    detected_language: Option<DetectedLanguage>,
+}
+
+#[derive(Deserialize)]
+struct DetectedLanguage {
+    language: String,
+}
+
+/// A translated text and the language it was detected as, if known.
+#[derive(Clone, Debug)]
+pub struct Translation {
+    pub text: String,
+    pub source_language: Option<String>,
+}
+
+/// Translate `text` into the language with code `target` (e.g. `fr`),
+/// detecting the source language.
+pub async fn translate_text(text: &str, target: &str) -> Result<Translation, Error> {
+    let base_url =
+        env::var("TRANSLATE_API_URL").unwrap_or_else(|_| DEFAULT_TRANSLATE_API_URL.to_string());
+    let request = TranslateRequest {
+        q: text,
+        source: "auto",
+        target,
+        format: "text",
+        api_key: env::var("TRANSLATE_API_KEY").ok(),
+    };
+
+    let response = reqwest::Client::new()
+        .post(format!("{}/translate", base_url.trim_end_matches('/')))
+        .timeout(Duration::from_secs(15))
+        .json(&request)
+        .send()
+        .await?;
+
+    if !response.status().is_success() {
+        let status = response.status();
+        let error_text = response.text().await.unwrap_or_default();
+        return Err(format!("Translation failed: {} - {}", status, error_text).into());
+    }
+
+    let body: TranslateResponse = response.json().await?;
+    Ok(Translation {
+        text: body.translated_text,
+        source_language: body.detected_language.map(|d| d.language),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_response() {
+        let body: TranslateResponse = serde_json::from_str(
+            r#"{"detectedLanguage": {"confidence": 90, "language": "en"}, "translatedText": "Bonjour"}"#,
+        )
+        .unwrap();
+        assert_eq!(body.translated_text, "Bonjour");
+        assert_eq!(body.detected_language.unwrap().language, "en");
+
+        // Servers only report the detected language for `source: auto`
+        let body: TranslateResponse =
+            serde_json::from_str(r#"{"translatedText": "Hallo"}"#).unwrap();
+        assert!(body.detected_language.is_none());
+    }
+}
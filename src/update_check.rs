@@ -0,0 +1,346 @@
+//! Checks for new commits on the deploy branch, so updates don't depend on
+//! remembering `-update`.
+//!
+//! Every [`CHECK_INTERVAL`] the checker runs `git fetch` in the bot's checkout
+//! and compares `HEAD` with `origin/<branch>`. New commits are announced to
+//! the owner by DM and in every status channel, with a button that runs the
+//! same pull, build and restart as `-update` once confirmed. With
+//! `AUTO_UPDATE=true` pending commits are also installed automatically during
+//! the daily update window (`AUTO_UPDATE_HOUR`, 04:00 Copenhagen time by
+//! default).
+
+use crate::commands::maintenance::Maintenance;
+use crate::commands::update::{
+    build_release, fetch_pending_commits, find_rustbot_directory, pull_latest,
+    restart_after_update, start_update, UPDATE_ALREADY_RUNNING,
+};
+use crate::guild_config;
+use crate::utils::{get_git_branch, is_protected_user, send_dm_to_deekahy, DEEKAHY_ID};
+use crate::Error;
+use chrono::{DateTime, NaiveDate, Timelike, Utc};
+use chrono_tz::Europe::Copenhagen;
+use chrono_tz::Tz;
+use poise::serenity_prelude as serenity;
+use std::env;
+use std::sync::Arc;
+use std::time::Instant;
+use tokio::time::{interval, Duration as TokioDuration};
+
+/// How often to look for new commits.
+const CHECK_INTERVAL: TokioDuration = TokioDuration::from_secs(6 * 60 * 60);
+
+/// Local hour automatic updates run in unless `AUTO_UPDATE_HOUR` says otherwise.
+const DEFAULT_AUTO_UPDATE_HOUR: u32 = 4;
+
+/// How many commit subjects an announcement lists.
+const MAX_LISTED_COMMITS: usize = 10;
+
+/// Custom ID prefix for the update buttons.
+pub const UPDATE_BUTTON_PREFIX: &str = "update:";
+const APPLY_BUTTON_ID: &str = "update:apply";
+const CONFIRM_BUTTON_ID: &str = "update:confirm";
+const CANCEL_BUTTON_ID: &str = "update:cancel";
+
+/// Whether `AUTO_UPDATE` turns on automatic updates.
+fn auto_update_enabled() -> bool {
+    env::var("AUTO_UPDATE").is_ok_and(|value| {
+        matches!(
+            value.trim().to_lowercase().as_str(),
+            "1" | "true" | "yes" | "on"
+        )
+    })
+}
+
+/// The hour automatic updates run in, from `AUTO_UPDATE_HOUR`.
+fn auto_update_hour() -> u32 {
+    env::var("AUTO_UPDATE_HOUR")
+        .ok()
+        .and_then(|hour| hour.trim().parse().ok())
+        .filter(|hour| *hour < 24)
+        .unwrap_or(DEFAULT_AUTO_UPDATE_HOUR)
+}
+
+/// Whether to try an automatic update now. Only inside the window hour, and
+/// once per day; unlike backups a missed window waits for the next one.
+fn auto_update_due(now: DateTime<Tz>, hour: u32, last_attempt: Option<NaiveDate>) -> bool {
+    now.hour() == hour && last_attempt != Some(now.date_naive())
+}
+
+/// Start the update checker background task, if there's a git checkout to
+/// update. Returns whether it started.
+pub fn start_update_checker(http: Arc<serenity::Http>, maintenance: Arc<Maintenance>) -> bool {
+    if find_rustbot_directory().is_none() {
+        log::info!("No git checkout found, update checks are off");
+        return false;
+    }
+
+    tokio::spawn(async move {
+        let mut interval = interval(TokioDuration::from_secs(60)); // Check every minute
+        let mut last_check: Option<Instant> = None;
+        let mut last_auto_attempt: Option<NaiveDate> = None;
+        // Only announce the same pending commits once
+        let mut announced: Vec<String> = Vec::new();
+
+        loop {
+            interval.tick().await;
+
+            let now = Utc::now().with_timezone(&Copenhagen);
+            if auto_update_enabled() && auto_update_due(now, auto_update_hour(), last_auto_attempt)
+            {
+                last_auto_attempt = Some(now.date_naive());
+                if maintenance.is_enabled() {
+                    log::info!("Skipping the automatic update during maintenance");
+                } else {
+                    auto_update(&http).await;
+                }
+                continue;
+            }
+
+            if last_check.is_some_and(|at| at.elapsed() < CHECK_INTERVAL) {
+                continue;
+            }
+            last_check = Some(Instant::now());
+            check_for_updates(&http, &mut announced).await;
+        }
+    });
+    true
+}
+
+/// Fetch and announce new commits, unless they were announced already.
+async fn check_for_updates(http: &serenity::Http, announced: &mut Vec<String>) {
+    let Some(dir) = find_rustbot_directory() else {
+        return;
+    };
+    let branch = get_git_branch();
+    let commits = match fetch_pending_commits(&dir, &branch).await {
+        Ok(commits) => commits,
+        Err(e) => {
+            log::warn!("Update check failed: {}", e);
+            return;
+        }
+    };
+    if commits.is_empty() || commits == *announced {
+        return;
+    }
+    log::info!("{} new commits on origin/{}", commits.len(), branch);
+
+    let auto_hour = auto_update_enabled().then(auto_update_hour);
+    announce(http, &update_summary(&commits, &branch, auto_hour)).await;
+    *announced = commits;
+}
+
+/// The announcement for pending commits: how many, their subjects (up to
+/// [`MAX_LISTED_COMMITS`]) and when they'll be installed automatically.
+fn update_summary(commits: &[String], branch: &str, auto_hour: Option<u32>) -> String {
+    let mut summary = format!(
+        "📦 **{} new commit{}** on `{}`:",
+        commits.len(),
+        if commits.len() == 1 { "" } else { "s" },
+        branch
+    );
+    for subject in commits.iter().take(MAX_LISTED_COMMITS) {
+        summary.push_str(&format!("\n• {}", subject));
+    }
+    if commits.len() > MAX_LISTED_COMMITS {
+        summary.push_str(&format!(
+            "\n… and {} more",
+            commits.len() - MAX_LISTED_COMMITS
+        ));
+    }
+    if let Some(hour) = auto_hour {
+        summary.push_str(&format!(
+            "\nThey'll be installed automatically at {:02}:00 Copenhagen time.",
+            hour
+        ));
+    }
+    summary
+}
+
+fn apply_button() -> serenity::CreateActionRow {
+    serenity::CreateActionRow::Buttons(vec![serenity::CreateButton::new(APPLY_BUTTON_ID)
+        .label("Update now")
+        .emoji('🔄')
+        .style(serenity::ButtonStyle::Primary)])
+}
+
+/// DM the owner and post to every status channel, with an update button.
+async fn announce(http: &serenity::Http, summary: &str) {
+    let message = || {
+        // Commit subjects are whatever was pushed, so they mustn't ping
+        serenity::CreateMessage::new()
+            .content(summary)
+            .allowed_mentions(serenity::CreateAllowedMentions::new())
+            .components(vec![apply_button()])
+    };
+    if let Err(e) = serenity::UserId::new(DEEKAHY_ID)
+        .direct_message(http, message())
+        .await
+    {
+        log::warn!("Failed to DM the owner about pending updates: {}", e);
+    }
+    for channel_id in guild_config::status_channels() {
+        if let Err(e) = serenity::ChannelId::new(channel_id)
+            .send_message(http, message())
+            .await
+        {
+            log::warn!(
+                "Failed to announce pending updates in status channel {}: {}",
+                channel_id,
+                e
+            );
+        }
+    }
+}
+
+/// Install pending commits during the update window, telling the owner.
+async fn auto_update(http: &serenity::Http) {
+    let Some(dir) = find_rustbot_directory() else {
+        return;
+    };
+    let branch = get_git_branch();
+    let commits = match fetch_pending_commits(&dir, &branch).await {
+        Ok(commits) if commits.is_empty() => return,
+        Ok(commits) => commits,
+        Err(e) => {
+            log::warn!("Automatic update check failed: {}", e);
+            return;
+        }
+    };
+
+    log::info!("Automatically updating with {} new commits", commits.len());
+    let message = format!(
+        "🔄 Installing {} new commit{} automatically...",
+        commits.len(),
+        if commits.len() == 1 { "" } else { "s" }
+    );
+    if let Err(e) = send_dm_to_deekahy(http, &message).await {
+        log::warn!("Failed to tell the owner about the automatic update: {}", e);
+    }
+    if let Err(error) = install_update(Some("auto-update".to_string())).await {
+        let message = format!("⚠️ Automatic update failed:\n{}", error);
+        if let Err(e) = send_dm_to_deekahy(http, &message).await {
+            log::warn!("Failed to report the failed automatic update: {}", e);
+        }
+    }
+}
+
+/// The `-update` flow: pull, build and restart. Only returns if a step
+/// failed or another update is already running, with the message to show.
+async fn install_update(user_name: Option<String>) -> Result<(), String> {
+    let _update = start_update().ok_or(UPDATE_ALREADY_RUNNING)?;
+    let dir =
+        find_rustbot_directory().ok_or("❌ Could not find RustBot directory with .git folder!")?;
+    pull_latest(&dir, &get_git_branch()).await?;
+    build_release(&dir).await?;
+    restart_after_update(user_name).await
+}
+
+/// Handle the "Update now" button and its confirmation.
+pub async fn handle_update_button(
+    ctx: &serenity::Context,
+    interaction: &serenity::ComponentInteraction,
+) -> Result<(), Error> {
+    if !is_protected_user(&interaction.user.name) {
+        let response = serenity::CreateInteractionResponse::Message(
+            serenity::CreateInteractionResponseMessage::new()
+                .content("❌ You don't have permission to use this command!")
+                .ephemeral(true),
+        );
+        interaction.create_response(&ctx.http, response).await?;
+        return Ok(());
+    }
+
+    match interaction.data.custom_id.as_str() {
+        APPLY_BUTTON_ID => {
+            let buttons = serenity::CreateActionRow::Buttons(vec![
+                serenity::CreateButton::new(CONFIRM_BUTTON_ID)
+                    .label("Update and restart")
+                    .style(serenity::ButtonStyle::Success),
+                serenity::CreateButton::new(CANCEL_BUTTON_ID)
+                    .label("Cancel")
+                    .style(serenity::ButtonStyle::Danger),
+            ]);
+            let response = serenity::CreateInteractionResponse::Message(
+                serenity::CreateInteractionResponseMessage::new()
+                    .content("Pull, compile and restart the bot now?")
+                    .components(vec![buttons])
+                    .ephemeral(true),
+            );
+            interaction.create_response(&ctx.http, response).await?;
+        }
+        CANCEL_BUTTON_ID => {
+            let response = serenity::CreateInteractionResponse::UpdateMessage(
+                serenity::CreateInteractionResponseMessage::new()
+                    .content("❌ Update cancelled.")
+                    .components(vec![]),
+            );
+            interaction.create_response(&ctx.http, response).await?;
+        }
+        CONFIRM_BUTTON_ID => {
+            log::info!("Update confirmed by {} via button", interaction.user.name);
+            let response = serenity::CreateInteractionResponse::UpdateMessage(
+                serenity::CreateInteractionResponseMessage::new()
+                    .content("🔄 Pulling and compiling, the bot restarts once it's done...")
+                    .components(vec![]),
+            );
+            interaction.create_response(&ctx.http, response).await?;
+
+            if let Err(message) = install_update(Some(interaction.user.name.clone())).await {
+                interaction
+                    .edit_response(
+                        &ctx.http,
+                        serenity::EditInteractionResponse::new().content(message),
+                    )
+                    .await?;
+            }
+        }
+        _ => {}
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    #[test]
+    fn test_only_one_update_at_a_time() {
+        let first = start_update().expect("no update is running");
+        assert!(start_update().is_none());
+        drop(first);
+        assert!(start_update().is_some());
+    }
+
+    #[test]
+    fn test_update_summary() {
+        let commits: Vec<String> = (1..=12).map(|i| format!("Commit {}", i)).collect();
+        let summary = update_summary(&commits, "main", None);
+        assert!(summary.starts_with("📦 **12 new commits** on `main`:"));
+        assert!(summary.contains("• Commit 10"));
+        assert!(!summary.contains("• Commit 11"));
+        assert!(summary.ends_with("… and 2 more"));
+
+        let summary = update_summary(&commits[..1], "dev", Some(4));
+        assert!(summary.starts_with("📦 **1 new commit** on `dev`:\n• Commit 1"));
+        assert!(summary.ends_with("installed automatically at 04:00 Copenhagen time."));
+    }
+
+    #[test]
+    fn test_auto_update_due() {
+        let at = |hour| {
+            Copenhagen
+                .with_ymd_and_hms(2026, 10, 16, hour, 30, 0)
+                .unwrap()
+        };
+        let today = at(4).date_naive();
+        let yesterday = today.pred_opt().unwrap();
+
+        assert!(auto_update_due(at(4), 4, None));
+        assert!(auto_update_due(at(4), 4, Some(yesterday)));
+        assert!(!auto_update_due(at(4), 4, Some(today)));
+        // Missed windows wait for the next day
+        assert!(!auto_update_due(at(5), 4, Some(yesterday)));
+        assert!(!auto_update_due(at(3), 4, None));
+    }
+}
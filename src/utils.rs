@@ -1,5 +1,22 @@
+pub mod args;
+pub mod avatar;
+pub mod confirm;
+pub mod cooldown;
+pub mod ics;
+pub mod limits;
+pub mod meme;
+pub mod messages;
+pub mod output;
+pub mod permissions;
+pub mod random;
+pub mod reply;
+pub mod schedule;
+pub mod transforms;
+
+use crate::storage;
 use poise::serenity_prelude as serenity;
 use std::env;
+use std::path::Path;
 
 /// Check if a user is authorized to use protected commands
 pub fn is_protected_user(username: &str) -> bool {
@@ -10,17 +27,38 @@ pub fn is_protected_user(username: &str) -> bool {
         .any(|user| user.trim().eq_ignore_ascii_case(username))
 }
 
+/// Command check for commands that change the store at `path`: while it's
+/// read-only because a newer build wrote it, say so instead of running.
+pub async fn check_store_writable(
+    ctx: crate::Context<'_>,
+    path: &Path,
+) -> Result<bool, crate::Error> {
+    if !storage::is_read_only(path) {
+        return Ok(true);
+    }
+    ctx.send(
+        poise::CreateReply::default()
+            .content("🔒 This feature is read-only until the bot is updated.")
+            .ephemeral(true),
+    )
+    .await?;
+    Ok(false)
+}
+
 /// Get the git branch to use for updates
 pub fn get_git_branch() -> String {
     env::var("GIT_BRANCH").unwrap_or_else(|_| "main".to_string()) // Default to main branch
 }
 
+/// The bot owner's Discord user ID, for startup and failure DMs.
+pub const DEEKAHY_ID: u64 = 398107630524039170;
+
 /// Send a DM to deekahy using their user ID
 pub async fn send_dm_to_deekahy(
     http: &serenity::Http,
     message: &str,
 ) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
-    let deekahy_id = serenity::UserId::new(398107630524039170);
+    let deekahy_id = serenity::UserId::new(DEEKAHY_ID);
 
     log::info!("Attempting to send DM to deekahy (ID: {})", deekahy_id);
 
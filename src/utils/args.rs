@@ -0,0 +1,443 @@
+//! Splitting prefix-command input into arguments, and explaining to the
+//! user what went wrong when poise can't parse a command's arguments.
+//!
+//! Arguments are separated by whitespace. One that starts with a quote
+//! (`"`, `'` or `“`) runs to the matching closing quote, so it can hold
+//! spaces and the other kinds of quote. A `'` only quotes when a later `'`
+//! ends a word, so apostrophes (`'90s`, `it's`) stay text. A backslash makes
+//! the next character literal, in or out of quotes; a backslash at the very
+//! end is kept as it is.
+//!
+//! Prefix commands take [`Arg`] parameters to have poise split them this way.
+
+use crate::utils::limits::truncate;
+use crate::{Data, Error};
+use poise::serenity_prelude as serenity;
+use std::fmt;
+use std::str::FromStr;
+
+/// How much of the offending input an error message repeats.
+const MAX_ECHOED_CHARS: usize = 50;
+
+#[derive(Debug, PartialEq)]
+pub enum ArgError {
+    /// A quoted argument was never closed; holds the opening quote.
+    UnclosedQuote(char),
+}
+
+impl fmt::Display for ArgError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ArgError::UnclosedQuote(quote) => write!(
+                f,
+                "❌ A `{}` quote is never closed. Put a `\\` before quotes that are part of the text.",
+                quote
+            ),
+        }
+    }
+}
+
+impl std::error::Error for ArgError {}
+
+/// The quote that closes one opened with `c`, if `c` opens a quote.
+fn closing_quote(c: char) -> Option<char> {
+    match c {
+        '"' => Some('"'),
+        '\'' => Some('\''),
+        '“' => Some('”'),
+        _ => None,
+    }
+}
+
+/// Whether `text` (what follows a closing quote) ends the argument there.
+fn ends_arg(text: &str) -> bool {
+    text.chars().next().is_none_or(char::is_whitespace)
+}
+
+/// Whether a `close` quote that ends an argument comes up in `text`.
+fn closes_later(text: &str, close: char) -> bool {
+    let mut chars = text.chars();
+    while let Some(c) = chars.next() {
+        if c == '\\' {
+            chars.next();
+        } else if c == close && ends_arg(chars.as_str()) {
+            return true;
+        }
+    }
+    false
+}
+
+/// The opening and closing quote if the argument at the start of `text` is
+/// a quoted one.
+fn opening_quote(text: &str) -> Option<(char, char)> {
+    let mut chars = text.chars();
+    let open = chars.next()?;
+    let close = closing_quote(open)?;
+    // An apostrophe only quotes when it's closed, so `'90s` stays a word
+    (open != '\'' || closes_later(chars.as_str(), close)).then_some((open, close))
+}
+
+/// Whether `input` starts with a quoted argument.
+pub fn starts_quoted(input: &str) -> bool {
+    opening_quote(input.trim_start()).is_some()
+}
+
+/// Take the first argument off `input`. Returns it and the rest of the
+/// input, or `None` if there are no arguments left.
+pub fn pop_arg(input: &str) -> Result<Option<(String, &str)>, ArgError> {
+    let input = input.trim_start();
+    if input.is_empty() {
+        return Ok(None);
+    }
+    let mut arg = String::new();
+    // The opening and closing quote while inside a quoted argument
+    let mut quote = opening_quote(input);
+    let mut chars = input.chars();
+    if quote.is_some() {
+        chars.next();
+    }
+
+    loop {
+        let rest = chars.as_str();
+        let Some(c) = chars.next() else { break };
+        if c == '\\' {
+            arg.push(chars.next().unwrap_or('\\'));
+            continue;
+        }
+        match quote {
+            // A `'` inside a word is an apostrophe, not the closing quote
+            Some((_, close)) if c == close && (c != '\'' || ends_arg(chars.as_str())) => {
+                quote = None
+            }
+            Some(_) => arg.push(c),
+            None if c.is_whitespace() => return Ok(Some((arg, rest))),
+            None => arg.push(c),
+        }
+    }
+
+    if let Some((open, _)) = quote {
+        return Err(ArgError::UnclosedQuote(open));
+    }
+    Ok(Some((arg, chars.as_str())))
+}
+
+/// Split `input` into arguments. `""` is an empty argument; quotes in the
+/// middle of a word (`don't`) are just characters.
+pub fn split_args(input: &str) -> Result<Vec<String>, ArgError> {
+    let mut args = Vec::new();
+    let mut rest = input;
+    while let Some((arg, next)) = pop_arg(rest)? {
+        args.push(arg);
+        rest = next;
+    }
+    Ok(args)
+}
+
+/// A prefix-command parameter that's split off like [`split_args`] does,
+/// instead of with poise's own rules, then parsed as a `T`. Slash commands
+/// get it like a plain `T`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Arg<T>(pub T);
+
+#[async_trait::async_trait]
+impl<'a, T> poise::PopArgument<'a> for Arg<T>
+where
+    T: FromStr + Send,
+    T::Err: std::error::Error + Send + Sync + 'static,
+{
+    async fn pop_from(
+        args: &'a str,
+        attachment_index: usize,
+        _ctx: &serenity::Context,
+        _msg: &serenity::Message,
+    ) -> Result<(&'a str, usize, Self), (Box<dyn std::error::Error + Send + Sync>, Option<String>)>
+    {
+        let (arg, rest) = match pop_arg(args) {
+            Ok(Some(popped)) => popped,
+            Ok(None) => return Err((poise::TooFewArguments::default().into(), None)),
+            Err(e) => return Err((e.into(), None)),
+        };
+        match arg.parse() {
+            Ok(value) => Ok((rest.trim_start(), attachment_index, Arg(value))),
+            Err(e) => Err((Box::new(e), Some(arg))),
+        }
+    }
+}
+
+/// Slash commands see an `Arg<T>` as the `T` it holds.
+macro_rules! slash_arg {
+    ($($type:ty),*) => {$(
+        #[async_trait::async_trait]
+        impl poise::SlashArgument for Arg<$type> {
+            async fn extract(
+                ctx: &serenity::Context,
+                interaction: &serenity::CommandInteraction,
+                value: &serenity::ResolvedValue<'_>,
+            ) -> Result<Self, poise::SlashArgError> {
+                poise::extract_slash_argument!($type, ctx, interaction, value)
+                    .await
+                    .map(Arg)
+            }
+
+            fn create(builder: serenity::CreateCommandOption) -> serenity::CreateCommandOption {
+                poise::create_slash_argument!($type, builder)
+            }
+        }
+    )*};
+}
+
+slash_arg!(String, u32);
+
+/// Take a leading `--name <value>` or `--name=<value>` off `text`, for
+/// options that sit in front of free text where a positional argument would
+/// swallow its first word. Returns the value (empty if it's missing) and the
+/// rest of the text; `None` if the text doesn't start with the flag.
+pub fn take_flag<'a>(text: &'a str, name: &str) -> Option<(&'a str, &'a str)> {
+    let text = text.trim_start();
+    let (first, rest) = text.split_once(char::is_whitespace).unwrap_or((text, ""));
+    let flag = first.strip_prefix("--")?;
+    if flag.eq_ignore_ascii_case(name) {
+        let rest = rest.trim_start();
+        let (value, rest) = rest.split_once(char::is_whitespace).unwrap_or((rest, ""));
+        return Some((value, rest.trim_start()));
+    }
+    let (flag, value) = flag.split_once('=')?;
+    flag.eq_ignore_ascii_case(name)
+        .then_some((value, rest.trim_start()))
+}
+
+/// A command's prefix usage line built from its parameters, e.g.
+/// `-remind set <time> [priority] [message]`.
+pub fn usage_line(command: &poise::Command<Data, Error>) -> String {
+    let mut usage = format!("-{}", command.qualified_name);
+    for parameter in &command.parameters {
+        if parameter.required {
+            usage.push_str(&format!(" <{}>", parameter.name));
+        } else {
+            usage.push_str(&format!(" [{}]", parameter.name));
+        }
+    }
+    usage
+}
+
+/// What a parse error means, phrased for the user.
+fn describe_parse_error(error: &(dyn std::error::Error + Send + Sync + 'static)) -> String {
+    if error.is::<std::num::ParseIntError>() {
+        "expected a whole number".to_string()
+    } else if error.is::<std::num::ParseFloatError>() {
+        "expected a number".to_string()
+    } else if error.is::<poise::TooFewArguments>() {
+        "it's missing an argument".to_string()
+    } else if error.is::<poise::TooManyArguments>() {
+        "that's more arguments than it takes".to_string()
+    } else if error.is::<poise::InvalidChoice>() {
+        "that isn't one of the choices".to_string()
+    } else if error.is::<poise::InvalidBool>() {
+        "expected yes or no".to_string()
+    } else if error.is::<serenity::UserParseError>() || error.is::<serenity::MemberParseError>() {
+        "expected a user mention, name or ID".to_string()
+    } else if error.is::<serenity::ChannelParseError>() {
+        "expected a channel mention or ID".to_string()
+    } else if let Some(ArgError::UnclosedQuote(quote)) = error.downcast_ref::<ArgError>() {
+        format!(
+            "a `{}` quote is never closed (put a `\\` before quotes that are part of the text)",
+            quote
+        )
+    } else {
+        error.to_string()
+    }
+}
+
+/// The parameter `input` was given for, when `args` (the raw input) tells:
+/// `input` must be exactly one of its arguments (or, for a `#[rest]`
+/// parameter, the rest of the input from one), and only required parameters
+/// may come before it, since optional ones may have been skipped.
+fn failed_parameter<'a>(
+    command: &'a poise::Command<Data, Error>,
+    args: &str,
+    input: &str,
+) -> Option<&'a poise::CommandParameter<Data, Error>> {
+    let input = input.trim();
+    let mut positions = Vec::new();
+    let mut rest = args.trim();
+    let mut index = 0;
+    while !rest.is_empty() {
+        let popped = pop_arg(rest).ok().flatten();
+        if rest == input || popped.as_ref().is_some_and(|(arg, _)| arg == input) {
+            positions.push(index);
+        }
+        let Some((_, next)) = popped else { break };
+        rest = next.trim_start();
+        index += 1;
+    }
+    let [position] = positions[..] else {
+        return None;
+    };
+    let parameters = &command.parameters;
+    parameters[..position.min(parameters.len())]
+        .iter()
+        .all(|parameter| parameter.required)
+        .then(|| parameters.get(position))
+        .flatten()
+}
+
+/// The reply to a command whose arguments couldn't be parsed: which
+/// argument (when it can be told from `args`, the raw input), why, and the
+/// command's usage.
+pub fn argument_error_message(
+    command: &poise::Command<Data, Error>,
+    args: &str,
+    input: Option<&str>,
+    error: &(dyn std::error::Error + Send + Sync + 'static),
+) -> String {
+    let why = describe_parse_error(error);
+    let problem = match input {
+        Some(input) => {
+            let got = truncate(&input.replace('`', "'"), MAX_ECHOED_CHARS);
+            match failed_parameter(command, args, input) {
+                Some(parameter) => format!(
+                    "Couldn't read **{}**: {}, got `{}`.",
+                    parameter.name, why, got
+                ),
+                None => format!("Couldn't read `{}`: {}.", got, why),
+            }
+        }
+        None => format!("Couldn't read that: {}.", why),
+    };
+    format!(
+        "❌ {}\nUsage: `{}` • more in `-help {}`",
+        problem,
+        usage_line(command),
+        command.qualified_name
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_split_args() {
+        let cases: &[(&str, &[&str])] = &[
+            ("", &[]),
+            ("   ", &[]),
+            ("one two  three", &["one", "two", "three"]),
+            (r#""two words" after"#, &["two words", "after"]),
+            // The other kind of quote is just text inside a quoted argument
+            (r#""say 'hi' now" x"#, &["say 'hi' now", "x"]),
+            (r#"'a "b" c'"#, &[r#"a "b" c"#]),
+            (
+                r#""escaped \"inner\" quotes""#,
+                &[r#"escaped "inner" quotes"#],
+            ),
+            // Quotes inside a word don't start an argument
+            ("don't stop", &["don't", "stop"]),
+            // Empty arguments
+            (r#""" x ''"#, &["", "x", ""]),
+            // Escapes outside quotes, and a trailing backslash is kept
+            (r"a\ b c\", &["a b", r"c\"]),
+            (r"\", &[r"\"]),
+            // Unicode text and typographic quotes from phone keyboards
+            (
+                "“pizza place” 🍕 smørrebrød",
+                &["pizza place", "🍕", "smørrebrød"],
+            ),
+            ("tab\tand\nnewline", &["tab", "and", "newline"]),
+            // A `'` that's never closed is an apostrophe
+            ("'90s music", &["'90s", "music"]),
+            ("'open", &["'open"]),
+            ("'90s or '80s", &["'90s", "or", "'80s"]),
+            // and so is one inside a quoted word
+            ("'it's here' x", &["it's here", "x"]),
+            (r#""it's" 'x'"#, &["it's", "x"]),
+        ];
+        for (input, expected) in cases {
+            let expected: Vec<String> = expected.iter().map(|arg| arg.to_string()).collect();
+            assert_eq!(split_args(input), Ok(expected), "{input:?}");
+        }
+    }
+
+    #[test]
+    fn test_split_args_errors() {
+        let cases: &[(&str, char)] = &[
+            (r#""never closed"#, '"'),
+            (r#""escaped close\""#, '"'),
+            ("“smart", '“'),
+        ];
+        for (input, quote) in cases {
+            assert_eq!(
+                split_args(input),
+                Err(ArgError::UnclosedQuote(*quote)),
+                "{input:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_pop_arg() {
+        assert_eq!(
+            pop_arg("  \"tomorrow 9am\" take out the trash"),
+            Ok(Some(("tomorrow 9am".to_string(), " take out the trash")))
+        );
+        assert_eq!(pop_arg("5m"), Ok(Some(("5m".to_string(), ""))));
+        assert_eq!(pop_arg("   "), Ok(None));
+        assert_eq!(pop_arg("“open"), Err(ArgError::UnclosedQuote('“')));
+        assert!(starts_quoted(" 'Lunch' pizza"));
+        assert!(!starts_quoted("'90s or '80s? yes no"));
+        assert!(!starts_quoted("Lunch? pizza"));
+    }
+
+    #[test]
+    fn test_take_flag() {
+        assert_eq!(take_flag("--level 3 cats", "level"), Some(("3", "cats")));
+        assert_eq!(take_flag("  --LEVEL=2 cats", "level"), Some(("2", "cats")));
+        assert_eq!(take_flag("--level", "level"), Some(("", "")));
+        assert_eq!(take_flag("--levels 3 cats", "level"), None);
+        assert_eq!(take_flag("3 cats --level 2", "level"), None);
+        assert_eq!(take_flag("—level 2", "level"), None);
+    }
+
+    #[test]
+    fn test_argument_error_message() {
+        let dice = crate::commands::dice();
+        assert_eq!(usage_line(&dice), "-dice [sides]");
+
+        let error: Error = "ten".parse::<u32>().unwrap_err().into();
+        assert_eq!(
+            argument_error_message(&dice, "ten", Some("ten"), error.as_ref()),
+            "❌ Couldn't read **sides**: expected a whole number, got `ten`.\n\
+             Usage: `-dice [sides]` • more in `-help dice`"
+        );
+
+        // The same text twice could be either argument
+        let after = crate::commands::remind()
+            .subcommands
+            .into_iter()
+            .find(|command| command.name == "after")
+            .unwrap();
+        let error: Error = "x".parse::<u64>().unwrap_err().into();
+        let message = argument_error_message(&after, "x 5m hi", Some("x"), error.as_ref());
+        assert!(message.contains("Couldn't read **id**"), "{message}");
+        let message = argument_error_message(&after, "x x hi", Some("x"), error.as_ref());
+        assert!(message.contains("Couldn't read `x`"), "{message}");
+        // After an optional parameter, which may have been skipped
+        let list = crate::commands::remind()
+            .subcommands
+            .into_iter()
+            .find(|command| command.name == "list")
+            .unwrap();
+        let message = argument_error_message(&list, "pizza bogus", Some("bogus"), error.as_ref());
+        assert!(message.contains("Couldn't read `bogus`"), "{message}");
+
+        let error: Error = ArgError::UnclosedQuote('"').into();
+        let message = argument_error_message(&dice, "\"6", None, error.as_ref());
+        assert!(message.contains("quote is never closed"), "{message}");
+
+        let error: Error = poise::TooManyArguments::default().into();
+        let message = argument_error_message(&dice, "6 7", None, error.as_ref());
+        assert!(
+            message.contains("more arguments than it takes"),
+            "{message}"
+        );
+    }
+}
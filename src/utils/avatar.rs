@@ -0,0 +1,230 @@
+use image::io::{Limits, Reader};
+use image::DynamicImage;
+use poise::serenity_prelude as serenity;
+use std::fmt;
+use std::io::Cursor;
+use std::time::Duration;
+
+/// Downloads are aborted once the body grows past this.
+pub const MAX_AVATAR_BYTES: usize = 10 * 1024 * 1024;
+
+/// Images wider or taller than this are rejected before decoding.
+pub const MAX_AVATAR_DIMENSION: u32 = 4096;
+
+const DOWNLOAD_TIMEOUT: Duration = Duration::from_secs(15);
+
+/// Why an avatar couldn't be fetched. The `Display` text is meant for users,
+/// so commands can show it after their own wording.
+#[derive(Debug)]
+pub enum AvatarError {
+    Request(reqwest::Error),
+    Status(reqwest::StatusCode),
+    NotAnImage(Option<String>),
+    TooLarge,
+    TooManyPixels,
+    Decode(image::ImageError),
+}
+
+impl fmt::Display for AvatarError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Request(e) if e.is_timeout() => write!(f, "the download timed out"),
+            Self::Request(_) => write!(f, "couldn't reach Discord's image server"),
+            Self::Status(status) => write!(f, "Discord's image server answered {}", status),
+            Self::NotAnImage(Some(content_type)) => {
+                write!(f, "that isn't an image (got {})", content_type)
+            }
+            Self::NotAnImage(None) => write!(f, "that isn't an image"),
+            Self::TooLarge => write!(
+                f,
+                "the image is over {} MB",
+                MAX_AVATAR_BYTES / (1024 * 1024)
+            ),
+            Self::TooManyPixels => write!(
+                f,
+                "the image is bigger than {0}x{0} pixels",
+                MAX_AVATAR_DIMENSION
+            ),
+            Self::Decode(_) => write!(f, "the image couldn't be read"),
+        }
+    }
+}
+
+impl std::error::Error for AvatarError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::Request(e) => Some(e),
+            Self::Decode(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+/// A still PNG of the user's avatar, or their default avatar if they have
+/// none. Animated (`a_`) avatars come back as their first frame, which is
+/// all the image commands need.
+pub fn still_avatar_url(user: &serenity::User) -> String {
+    match &user.avatar {
+        Some(hash) => format!(
+            "https://cdn.discordapp.com/avatars/{}/{}.png?size=1024",
+            user.id, hash
+        ),
+        None => user.default_avatar_url(),
+    }
+}
+
+/// Download and decode an avatar, refusing anything that isn't an image, is
+/// over [`MAX_AVATAR_BYTES`] or is larger than [`MAX_AVATAR_DIMENSION`].
+pub async fn download_avatar(url: &str) -> Result<DynamicImage, AvatarError> {
+    let bytes = fetch_limited(url, MAX_AVATAR_BYTES).await?;
+    decode_limited(&bytes, MAX_AVATAR_DIMENSION)
+}
+
+/// Fetch an image body, streaming it so an oversized response is dropped
+/// as soon as it passes `max_bytes` rather than after it's all in memory.
+async fn fetch_limited(url: &str, max_bytes: usize) -> Result<Vec<u8>, AvatarError> {
+    let mut response = reqwest::Client::new()
+        .get(url)
+        .timeout(DOWNLOAD_TIMEOUT)
+        .send()
+        .await
+        .map_err(AvatarError::Request)?;
+
+    if !response.status().is_success() {
+        return Err(AvatarError::Status(response.status()));
+    }
+
+    let content_type = response
+        .headers()
+        .get(reqwest::header::CONTENT_TYPE)
+        .and_then(|value| value.to_str().ok())
+        .map(str::to_string);
+    if !content_type
+        .as_deref()
+        .is_some_and(|t| t.starts_with("image/"))
+    {
+        return Err(AvatarError::NotAnImage(content_type));
+    }
+
+    if response
+        .content_length()
+        .is_some_and(|length| length > max_bytes as u64)
+    {
+        return Err(AvatarError::TooLarge);
+    }
+
+    let mut body = Vec::new();
+    while let Some(chunk) = response.chunk().await.map_err(AvatarError::Request)? {
+        if body.len() + chunk.len() > max_bytes {
+            return Err(AvatarError::TooLarge);
+        }
+        body.extend_from_slice(&chunk);
+    }
+    Ok(body)
+}
+
+/// Decode an image, checking its dimensions from the header first.
+fn decode_limited(bytes: &[u8], max_dimension: u32) -> Result<DynamicImage, AvatarError> {
+    let mut limits = Limits::default();
+    limits.max_image_width = Some(max_dimension);
+    limits.max_image_height = Some(max_dimension);
+
+    let mut reader = Reader::new(Cursor::new(bytes))
+        .with_guessed_format()
+        .map_err(|e| AvatarError::Decode(e.into()))?;
+    reader.limits(limits);
+    reader.decode().map_err(|e| match e {
+        image::ImageError::Limits(_) => AvatarError::TooManyPixels,
+        e => AvatarError::Decode(e),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use image::{ImageOutputFormat, RgbaImage};
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+    use tokio::net::TcpListener;
+
+    /// Serve one HTTP response on a local port and return its URL. The body
+    /// is sent chunked, without a Content-Length, to exercise streaming.
+    async fn serve_once(content_type: &'static str, body: Vec<u8>) -> String {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let address = listener.local_addr().unwrap();
+        tokio::spawn(async move {
+            let (mut socket, _) = listener.accept().await.unwrap();
+            let mut request = [0u8; 1024];
+            let _ = socket.read(&mut request).await;
+
+            let head = format!(
+                "HTTP/1.1 200 OK\r\nContent-Type: {}\r\nTransfer-Encoding: chunked\r\nConnection: close\r\n\r\n",
+                content_type
+            );
+            let _ = socket.write_all(head.as_bytes()).await;
+            for chunk in body.chunks(1024) {
+                let frame = format!("{:x}\r\n", chunk.len());
+                let _ = socket.write_all(frame.as_bytes()).await;
+                let _ = socket.write_all(chunk).await;
+                let _ = socket.write_all(b"\r\n").await;
+            }
+            let _ = socket.write_all(b"0\r\n\r\n").await;
+        });
+        format!("http://{}/avatar.png", address)
+    }
+
+    fn png(width: u32, height: u32) -> Vec<u8> {
+        let mut bytes = Vec::new();
+        DynamicImage::ImageRgba8(RgbaImage::new(width, height))
+            .write_to(&mut Cursor::new(&mut bytes), ImageOutputFormat::Png)
+            .unwrap();
+        bytes
+    }
+
+    #[tokio::test]
+    async fn test_fetch_stops_past_size_limit() {
+        let url = serve_once("image/png", vec![0; 8 * 1024]).await;
+        assert!(matches!(
+            fetch_limited(&url, 4 * 1024).await,
+            Err(AvatarError::TooLarge)
+        ));
+
+        let url = serve_once("image/png", vec![7; 3000]).await;
+        assert_eq!(fetch_limited(&url, 4 * 1024).await.unwrap(), vec![7; 3000]);
+    }
+
+    #[tokio::test]
+    async fn test_fetch_rejects_non_images() {
+        let url = serve_once("text/html", b"<html></html>".to_vec()).await;
+        assert!(matches!(
+            fetch_limited(&url, 1024).await,
+            Err(AvatarError::NotAnImage(Some(t))) if t == "text/html"
+        ));
+    }
+
+    #[test]
+    fn test_decode_limits_dimensions() {
+        assert_eq!(decode_limited(&png(16, 8), 16).unwrap().width(), 16);
+        assert!(matches!(
+            decode_limited(&png(17, 8), 16),
+            Err(AvatarError::TooManyPixels)
+        ));
+        assert!(matches!(
+            decode_limited(b"definitely not an image", 16),
+            Err(AvatarError::Decode(_))
+        ));
+    }
+
+    #[test]
+    fn test_still_avatar_url() {
+        let mut user = serenity::User::default();
+        user.id = serenity::UserId::new(1234);
+        user.avatar = Some("a_1269e74af4df7417b13759eae50c83dc".parse().unwrap());
+        assert_eq!(
+            still_avatar_url(&user),
+            "https://cdn.discordapp.com/avatars/1234/a_1269e74af4df7417b13759eae50c83dc.png?size=1024"
+        );
+
+        user.avatar = None;
+        assert!(still_avatar_url(&user).ends_with(".png"));
+    }
+}
@@ -0,0 +1,83 @@
+use crate::{Context, Error};
+use poise::serenity_prelude as serenity;
+use std::time::Duration;
+
+/// Ask the author a yes/no question with a confirm and a cancel button.
+/// Returns whether they confirmed before `timeout` ran out; either way the
+/// buttons are replaced with the outcome. `cancelled` says what didn't
+/// happen, e.g. "reminder not set."
+pub async fn confirm_with_buttons(
+    ctx: Context<'_>,
+    prompt: String,
+    confirm_label: &str,
+    cancelled: &str,
+    timeout: Duration,
+) -> Result<bool, Error> {
+    let confirm_id = format!("{}_confirm", ctx.id());
+    let cancel_id = format!("{}_cancel", ctx.id());
+
+    let buttons = serenity::CreateActionRow::Buttons(vec![
+        serenity::CreateButton::new(&confirm_id)
+            .label(confirm_label)
+            .style(serenity::ButtonStyle::Success),
+        serenity::CreateButton::new(&cancel_id)
+            .label("Cancel")
+            .style(serenity::ButtonStyle::Danger),
+    ]);
+
+    let reply = ctx
+        .send(
+            poise::CreateReply::default()
+                .content(prompt)
+                .components(vec![buttons])
+                .ephemeral(true),
+        )
+        .await?;
+
+    let ids = [confirm_id.clone(), cancel_id];
+    let press = serenity::ComponentInteractionCollector::new(ctx)
+        .author_id(ctx.author().id)
+        .channel_id(ctx.channel_id())
+        .timeout(timeout)
+        .filter(move |press| ids.contains(&press.data.custom_id))
+        .await;
+
+    let Some(press) = press else {
+        reply
+            .edit(
+                ctx,
+                poise::CreateReply::default()
+                    .content(format!("⌛ No answer, {}", cancelled))
+                    .components(vec![]),
+            )
+            .await?;
+        return Ok(false);
+    };
+
+    let confirmed = press.data.custom_id == confirm_id;
+    let content = if confirmed {
+        "✅ Confirmed.".to_string()
+    } else {
+        format!("❌ {}", capitalize(cancelled))
+    };
+    press
+        .create_response(
+            ctx,
+            serenity::CreateInteractionResponse::UpdateMessage(
+                serenity::CreateInteractionResponseMessage::new()
+                    .content(content)
+                    .components(vec![]),
+            ),
+        )
+        .await?;
+
+    Ok(confirmed)
+}
+
+fn capitalize(text: &str) -> String {
+    let mut chars = text.chars();
+    match chars.next() {
+        Some(first) => first.to_uppercase().chain(chars).collect(),
+        None => String::new(),
+    }
+}
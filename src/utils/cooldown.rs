@@ -0,0 +1,39 @@
+//! Cooldowns for things that may only be done every so often, like `-kys`
+//! or casting a fishing line.
+
+use chrono::{DateTime, Duration, Utc};
+
+/// How long is left before something last done at `last` may be done again,
+/// or `None` if it's allowed now (including if it was never done).
+pub fn remaining(
+    last: Option<DateTime<Utc>>,
+    cooldown: Duration,
+    now: DateTime<Utc>,
+) -> Option<Duration> {
+    let left = cooldown - (now - last?);
+    (left > Duration::zero()).then_some(left)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_remaining() {
+        let now = Utc::now();
+        let cooldown = Duration::minutes(30);
+        assert_eq!(remaining(None, cooldown, now), None);
+        assert_eq!(
+            remaining(Some(now - Duration::minutes(10)), cooldown, now),
+            Some(Duration::minutes(20))
+        );
+        assert_eq!(
+            remaining(Some(now - Duration::minutes(30)), cooldown, now),
+            None
+        );
+        assert_eq!(
+            remaining(Some(now - Duration::days(1)), cooldown, now),
+            None
+        );
+    }
+}
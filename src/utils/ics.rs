@@ -0,0 +1,566 @@
+//! A small iCalendar (RFC 5545) reader: just enough to pull the title and
+//! start time out of each `VEVENT` in a `.ics` export.
+//!
+//! Simple recurrence rules (a `FREQ` with `INTERVAL`, `COUNT`, `UNTIL` and
+//! weekly `BYDAY`) are followed to find a repeating event's next time;
+//! `EXDATE`s and other `BY...` parts aren't, and such events only yield
+//! their first occurrence. Events whose start can't be read are counted
+//! instead of failing the whole file.
+
+use chrono::{
+    DateTime, Datelike, Duration, NaiveDate, NaiveDateTime, NaiveTime, TimeZone, Utc, Weekday,
+};
+use chrono_tz::Tz;
+
+/// Local time all-day events (a date without a time) are placed at.
+const ALL_DAY_HOUR: u32 = 9;
+
+/// How many periods (days, weeks, ...) of a rule are stepped through looking
+/// for the next occurrence; a daily event from over a century ago gives up.
+const MAX_RULE_PERIODS: u32 = 50_000;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct IcsEvent {
+    /// The event's `SUMMARY`, empty if it had none.
+    pub summary: String,
+    /// Its first occurrence.
+    pub start: DateTime<Utc>,
+    /// Whether it has an `RRULE`.
+    pub recurring: bool,
+    /// That rule, if it's one this reader can follow.
+    pub rule: Option<Recurrence>,
+}
+
+impl IcsEvent {
+    /// When the event next starts after `after`: its start for a one-off
+    /// event (or a rule that can't be followed), the next occurrence of a
+    /// repeating one. `None` if that's in the past.
+    pub fn next_start(&self, after: DateTime<Utc>) -> Option<DateTime<Utc>> {
+        match &self.rule {
+            Some(rule) => rule.next_after(after),
+            None => (self.start > after).then_some(self.start),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Frequency {
+    Daily,
+    Weekly,
+    Monthly,
+    Yearly,
+}
+
+/// A followable `RRULE`, anchored at the event's first start.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Recurrence {
+    frequency: Frequency,
+    interval: u32,
+    count: Option<u32>,
+    until: Option<DateTime<Utc>>,
+    /// Weekdays of a weekly rule, Monday first; empty for the start's own.
+    by_day: Vec<Weekday>,
+    /// The first start's wall-clock time and zone, which later occurrences
+    /// keep across daylight saving changes.
+    local_start: NaiveDateTime,
+    tz: Tz,
+}
+
+impl Recurrence {
+    /// Read an `RRULE` value. `None` for rules this reader can't follow.
+    fn parse(value: &str, local_start: NaiveDateTime, tz: Tz) -> Option<Self> {
+        let mut rule = Recurrence {
+            frequency: Frequency::Daily,
+            interval: 1,
+            count: None,
+            until: None,
+            by_day: Vec::new(),
+            local_start,
+            tz,
+        };
+        let mut frequency = None;
+        for part in value.split(';').filter(|part| !part.is_empty()) {
+            let (key, value) = part.split_once('=')?;
+            match key.trim().to_ascii_uppercase().as_str() {
+                "FREQ" => {
+                    frequency = Some(match value.trim().to_ascii_uppercase().as_str() {
+                        "DAILY" => Frequency::Daily,
+                        "WEEKLY" => Frequency::Weekly,
+                        "MONTHLY" => Frequency::Monthly,
+                        "YEARLY" => Frequency::Yearly,
+                        _ => return None,
+                    })
+                }
+                "INTERVAL" => rule.interval = value.trim().parse().ok().filter(|n| *n > 0)?,
+                "COUNT" => rule.count = Some(value.trim().parse().ok()?),
+                "UNTIL" => rule.until = Some(parse_date_time(&[], value.trim(), &tz)?),
+                "BYDAY" => {
+                    for day in value.split(',') {
+                        rule.by_day.push(parse_weekday(day.trim())?);
+                    }
+                }
+                "WKST" => {}
+                _ => return None,
+            }
+        }
+        rule.frequency = frequency?;
+        if !rule.by_day.is_empty() && rule.frequency != Frequency::Weekly {
+            return None;
+        }
+        rule.by_day.sort_by_key(|day| day.num_days_from_monday());
+        rule.by_day.dedup();
+        Some(rule)
+    }
+
+    /// The dates the rule gives in its `period`th period, in order.
+    fn dates_in_period(&self, period: u32) -> Vec<NaiveDate> {
+        let first = self.local_start.date();
+        let step = i64::from(period) * i64::from(self.interval);
+        match self.frequency {
+            Frequency::Daily => vec![first + Duration::days(step)],
+            Frequency::Weekly if self.by_day.is_empty() => vec![first + Duration::weeks(step)],
+            Frequency::Weekly => {
+                let monday = first - Duration::days(first.weekday().num_days_from_monday().into())
+                    + Duration::weeks(step);
+                self.by_day
+                    .iter()
+                    .map(|day| monday + Duration::days(day.num_days_from_monday().into()))
+                    .filter(|date| *date >= first)
+                    .collect()
+            }
+            // Months and years without the start's day (the 31st, February
+            // 29th) are skipped, as the RFC says
+            Frequency::Monthly => {
+                let month0 = i64::from(first.month0()) + step;
+                i32::try_from(i64::from(first.year()) + month0.div_euclid(12))
+                    .ok()
+                    .and_then(|year| {
+                        NaiveDate::from_ymd_opt(year, month0.rem_euclid(12) as u32 + 1, first.day())
+                    })
+                    .into_iter()
+                    .collect()
+            }
+            Frequency::Yearly => i32::try_from(i64::from(first.year()) + step)
+                .ok()
+                .and_then(|year| NaiveDate::from_ymd_opt(year, first.month(), first.day()))
+                .into_iter()
+                .collect(),
+        }
+    }
+
+    /// The first occurrence after `after`, if the rule has one.
+    fn next_after(&self, after: DateTime<Utc>) -> Option<DateTime<Utc>> {
+        let time = self.local_start.time();
+        let mut seen = 0;
+        for period in 0..MAX_RULE_PERIODS {
+            for date in self.dates_in_period(period) {
+                seen += 1;
+                if self.count.is_some_and(|count| seen > count) {
+                    return None;
+                }
+                let Some(at) = local_to_utc(&self.tz, date.and_time(time)) else {
+                    continue;
+                };
+                if self.until.is_some_and(|until| at > until) {
+                    return None;
+                }
+                if at > after {
+                    return Some(at);
+                }
+            }
+        }
+        None
+    }
+}
+
+/// A plain `BYDAY` weekday like `MO`; ones with a position (`1MO`) aren't
+/// followed.
+fn parse_weekday(day: &str) -> Option<Weekday> {
+    Some(match day.to_ascii_uppercase().as_str() {
+        "MO" => Weekday::Mon,
+        "TU" => Weekday::Tue,
+        "WE" => Weekday::Wed,
+        "TH" => Weekday::Thu,
+        "FR" => Weekday::Fri,
+        "SA" => Weekday::Sat,
+        "SU" => Weekday::Sun,
+        _ => return None,
+    })
+}
+
+#[derive(Debug, Default, PartialEq)]
+pub struct IcsCalendar {
+    pub events: Vec<IcsEvent>,
+    /// Events without a start time that could be read.
+    pub unreadable: usize,
+}
+
+/// Parse a calendar. Times without a timezone ("floating" times) and
+/// all-day dates are read in `default_tz`. Errors if the text isn't a
+/// calendar at all or its `BEGIN`/`END` blocks don't match up.
+pub fn parse_calendar(text: &str, default_tz: &Tz) -> Result<IcsCalendar, String> {
+    let mut calendar = IcsCalendar::default();
+    let mut blocks: Vec<String> = Vec::new();
+    let mut saw_calendar = false;
+    // The event being read, as (summary, local start and zone, rule)
+    let mut event: Option<(String, Option<LocalTime>, Option<String>)> = None;
+
+    for line in unfold(text) {
+        let Some((name, params, value)) = split_line(&line) else {
+            continue;
+        };
+        match name.as_str() {
+            "BEGIN" => {
+                let block = value.to_ascii_uppercase();
+                if block == "VCALENDAR" {
+                    saw_calendar = true;
+                } else if block == "VEVENT" {
+                    if event.is_some() {
+                        return Err("an event starts inside another event".to_string());
+                    }
+                    event = Some((String::new(), None, None));
+                }
+                blocks.push(block);
+            }
+            "END" => {
+                let block = value.to_ascii_uppercase();
+                if blocks.pop().as_deref() != Some(block.as_str()) {
+                    return Err(format!("`END:{}` doesn't match an open block", value));
+                }
+                if block == "VEVENT" {
+                    let read = event.take().and_then(|(summary, start, rule)| {
+                        let (local, tz) = start?;
+                        Some(IcsEvent {
+                            summary,
+                            start: local_to_utc(&tz, local)?,
+                            recurring: rule.is_some(),
+                            rule: rule.and_then(|rule| Recurrence::parse(&rule, local, tz)),
+                        })
+                    });
+                    match read {
+                        Some(event) => calendar.events.push(event),
+                        None => calendar.unreadable += 1,
+                    }
+                }
+            }
+            // Only the event's own properties count, not those of alarms
+            // and other blocks nested in it
+            _ if blocks.last().map(String::as_str) != Some("VEVENT") => {}
+            "SUMMARY" => {
+                if let Some(event) = event.as_mut() {
+                    event.0 = unescape(value);
+                }
+            }
+            "DTSTART" => {
+                if let Some(event) = event.as_mut() {
+                    event.1 = parse_local_date_time(&params, value, default_tz);
+                }
+            }
+            "RRULE" => {
+                if let Some(event) = event.as_mut() {
+                    event.2 = Some(value.to_string());
+                }
+            }
+            _ => {}
+        }
+    }
+
+    if !saw_calendar {
+        return Err("it has no `BEGIN:VCALENDAR`".to_string());
+    }
+    if let Some(block) = blocks.last() {
+        return Err(format!("`BEGIN:{}` is never closed", block));
+    }
+    Ok(calendar)
+}
+
+/// Join folded lines: a line break followed by a space or tab continues the
+/// previous line.
+fn unfold(text: &str) -> Vec<String> {
+    let mut lines: Vec<String> = Vec::new();
+    for line in text.lines() {
+        match line.strip_prefix([' ', '\t']) {
+            Some(rest) if !lines.is_empty() => {
+                if let Some(last) = lines.last_mut() {
+                    last.push_str(rest);
+                }
+            }
+            _ => lines.push(line.to_string()),
+        }
+    }
+    lines
+}
+
+/// A wall-clock time and the zone it's in.
+type LocalTime = (NaiveDateTime, Tz);
+
+/// A content line's parameters as (name, value) pairs.
+type Params = Vec<(String, String)>;
+
+/// Split a content line into its uppercased name, its parameters (name
+/// uppercased, quotes removed) and its value.
+fn split_line(line: &str) -> Option<(String, Params, &str)> {
+    // The value starts at the first colon outside a quoted parameter value
+    let mut in_quotes = false;
+    let colon = line.char_indices().find_map(|(i, c)| match c {
+        '"' => {
+            in_quotes = !in_quotes;
+            None
+        }
+        ':' if !in_quotes => Some(i),
+        _ => None,
+    })?;
+    let (head, value) = (&line[..colon], &line[colon + 1..]);
+
+    let mut parts = head.split(';');
+    let name = parts.next()?.trim().to_ascii_uppercase();
+    let params = parts
+        .filter_map(|param| {
+            let (key, value) = param.split_once('=')?;
+            Some((
+                key.trim().to_ascii_uppercase(),
+                value.trim().trim_matches('"').to_string(),
+            ))
+        })
+        .collect();
+    Some((name, params, value.trim()))
+}
+
+/// Undo text escaping: `\n`, `\,`, `\;` and `\\`.
+fn unescape(value: &str) -> String {
+    let mut text = String::with_capacity(value.len());
+    let mut chars = value.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            text.push(c);
+            continue;
+        }
+        match chars.next() {
+            Some('n' | 'N') => text.push('\n'),
+            Some(other) => text.push(other),
+            None => {}
+        }
+    }
+    text.trim().to_string()
+}
+
+/// A `DTSTART` value: UTC (`...Z`), local to its `TZID`, floating (read in
+/// `default_tz`) or a date for all-day events.
+fn parse_date_time(
+    params: &[(String, String)],
+    value: &str,
+    default_tz: &Tz,
+) -> Option<DateTime<Utc>> {
+    let (local, tz) = parse_local_date_time(params, value, default_tz)?;
+    local_to_utc(&tz, local)
+}
+
+/// [`parse_date_time`] before it's placed on the timeline: the wall-clock
+/// time and the zone it's in (UTC for `...Z` times).
+fn parse_local_date_time(
+    params: &[(String, String)],
+    value: &str,
+    default_tz: &Tz,
+) -> Option<LocalTime> {
+    let param = |key: &str| {
+        params
+            .iter()
+            .find(|(name, _)| name == key)
+            .map(|(_, value)| value.as_str())
+    };
+    let tz = match param("TZID") {
+        // Some exporters put a path in front, e.g. `/citadel.org/.../Europe/Paris`
+        Some(tzid) => tzid.parse::<Tz>().ok().or_else(|| known_tz_suffix(tzid))?,
+        None => *default_tz,
+    };
+
+    if param("VALUE").is_some_and(|kind| kind.eq_ignore_ascii_case("DATE")) || value.len() == 8 {
+        let date = NaiveDate::parse_from_str(value, "%Y%m%d").ok()?;
+        return Some((
+            date.and_time(NaiveTime::from_hms_opt(ALL_DAY_HOUR, 0, 0)?),
+            tz,
+        ));
+    }
+
+    if let Some(utc) = value.strip_suffix(['Z', 'z']) {
+        let at = NaiveDateTime::parse_from_str(utc, "%Y%m%dT%H%M%S").ok()?;
+        return Some((at, Tz::UTC));
+    }
+    let local = NaiveDateTime::parse_from_str(value, "%Y%m%dT%H%M%S").ok()?;
+    Some((local, tz))
+}
+
+/// A wall-clock time in `tz` as UTC. A time skipped by daylight saving is
+/// read an hour later, like clocks do.
+fn local_to_utc(tz: &Tz, local: NaiveDateTime) -> Option<DateTime<Utc>> {
+    tz.from_local_datetime(&local)
+        .earliest()
+        .or_else(|| {
+            tz.from_local_datetime(&(local + Duration::hours(1)))
+                .earliest()
+        })
+        .map(|at| at.with_timezone(&Utc))
+}
+
+/// The IANA zone at the end of a prefixed TZID like `/example.com/Europe/Oslo`.
+fn known_tz_suffix(tzid: &str) -> Option<Tz> {
+    let parts: Vec<&str> = tzid.split('/').collect();
+    (1..parts.len()).find_map(|i| parts[i..].join("/").parse().ok())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const COPENHAGEN: Tz = chrono_tz::Europe::Copenhagen;
+
+    fn utc(s: &str) -> DateTime<Utc> {
+        DateTime::parse_from_rfc3339(s).unwrap().with_timezone(&Utc)
+    }
+
+    #[test]
+    fn test_parse_calendar() {
+        let ics = "BEGIN:VCALENDAR\r\n\
+                   VERSION:2.0\r\n\
+                   BEGIN:VEVENT\r\n\
+                   SUMMARY:Dentist\\, room 3\r\n\
+                   DTSTART:20260601T120000Z\r\n\
+                   BEGIN:VALARM\r\n\
+                   SUMMARY:Alarm text\r\n\
+                   END:VALARM\r\n\
+                   END:VEVENT\r\n\
+                   BEGIN:VEVENT\r\n\
+                   SUMMARY:Team standup that has a very long na\r\n \
+                   me\r\n\
+                   DTSTART;TZID=\"Europe/Copenhagen\":20260601T093000\r\n\
+                   RRULE:FREQ=DAILY\r\n\
+                   END:VEVENT\r\n\
+                   BEGIN:VEVENT\r\n\
+                   SUMMARY:Birthday\r\n\
+                   DTSTART;VALUE=DATE:20261224\r\n\
+                   END:VEVENT\r\n\
+                   BEGIN:VEVENT\r\n\
+                   SUMMARY:Broken\r\n\
+                   DTSTART:tomorrow\r\n\
+                   END:VEVENT\r\n\
+                   END:VCALENDAR\r\n";
+        let calendar = parse_calendar(ics, &COPENHAGEN).unwrap();
+        assert_eq!(calendar.unreadable, 1);
+        assert_eq!(
+            calendar.events,
+            vec![
+                IcsEvent {
+                    summary: "Dentist, room 3".to_string(),
+                    start: utc("2026-06-01T12:00:00Z"),
+                    recurring: false,
+                    rule: None,
+                },
+                IcsEvent {
+                    summary: "Team standup that has a very long name".to_string(),
+                    start: utc("2026-06-01T07:30:00Z"),
+                    recurring: true,
+                    rule: Recurrence::parse(
+                        "FREQ=DAILY",
+                        NaiveDate::from_ymd_opt(2026, 6, 1)
+                            .unwrap()
+                            .and_hms_opt(9, 30, 0)
+                            .unwrap(),
+                        COPENHAGEN
+                    ),
+                },
+                IcsEvent {
+                    summary: "Birthday".to_string(),
+                    start: utc("2026-12-24T08:00:00Z"),
+                    recurring: false,
+                    rule: None,
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_next_start_follows_rules() {
+        let event = |start: &str, rule: &str| {
+            let local = NaiveDateTime::parse_from_str(start, "%Y%m%dT%H%M%S").unwrap();
+            IcsEvent {
+                summary: String::new(),
+                start: local_to_utc(&COPENHAGEN, local).unwrap(),
+                recurring: true,
+                rule: Recurrence::parse(rule, local, COPENHAGEN),
+            }
+        };
+        let after = utc("2026-10-16T12:00:00Z");
+
+        // Keeps the wall-clock time across the change to summer time
+        let daily = event("20260301T093000", "FREQ=DAILY");
+        assert_eq!(daily.next_start(after), Some(utc("2026-10-17T07:30:00Z")));
+        assert_eq!(
+            event("20260302T093000", "FREQ=DAILY;INTERVAL=2").next_start(after),
+            Some(utc("2026-10-18T07:30:00Z"))
+        );
+        // 2026-10-16 is a Friday
+        assert_eq!(
+            event("20260105T170000", "FREQ=WEEKLY;BYDAY=TU,FR").next_start(after),
+            Some(utc("2026-10-16T15:00:00Z"))
+        );
+        assert_eq!(
+            event("20260131T100000", "FREQ=MONTHLY").next_start(after),
+            Some(utc("2026-10-31T09:00:00Z"))
+        );
+        assert_eq!(
+            event("20200229T100000", "FREQ=YEARLY").next_start(after),
+            Some(utc("2028-02-29T09:00:00Z"))
+        );
+        // Rules that ran out
+        assert_eq!(
+            event("20260301T093000", "FREQ=DAILY;COUNT=10").next_start(after),
+            None
+        );
+        assert_eq!(
+            event("20260301T093000", "FREQ=WEEKLY;UNTIL=20260601T000000Z").next_start(after),
+            None
+        );
+        // Rules that can't be followed only have their first start
+        let unfollowable = event("20260301T093000", "FREQ=MONTHLY;BYDAY=1MO");
+        assert!(unfollowable.rule.is_none());
+        assert_eq!(unfollowable.next_start(after), None);
+        assert!(event("20260301T093000", "FREQ=HOURLY").rule.is_none());
+    }
+
+    #[test]
+    fn test_malformed_calendars() {
+        assert!(parse_calendar("hello world", &COPENHAGEN).is_err());
+        assert!(parse_calendar("BEGIN:VCALENDAR\nBEGIN:VEVENT\n", &COPENHAGEN).is_err());
+        assert!(parse_calendar("BEGIN:VCALENDAR\nEND:VEVENT\nEND:VCALENDAR", &COPENHAGEN).is_err());
+        assert_eq!(
+            parse_calendar("BEGIN:VCALENDAR\nEND:VCALENDAR\n", &COPENHAGEN),
+            Ok(IcsCalendar::default())
+        );
+    }
+
+    #[test]
+    fn test_parse_date_time() {
+        let tzid = |tz: &str| vec![("TZID".to_string(), tz.to_string())];
+        // Floating times are read in the default timezone
+        assert_eq!(
+            parse_date_time(&[], "20260115T100000", &COPENHAGEN),
+            Some(utc("2026-01-15T09:00:00Z"))
+        );
+        assert_eq!(
+            parse_date_time(&tzid("America/New_York"), "20260115T100000", &COPENHAGEN),
+            Some(utc("2026-01-15T15:00:00Z"))
+        );
+        assert_eq!(
+            parse_date_time(
+                &tzid("/citadel.org/20070103_1/Europe/Oslo"),
+                "20260115T100000",
+                &COPENHAGEN
+            ),
+            Some(utc("2026-01-15T09:00:00Z"))
+        );
+        assert_eq!(
+            parse_date_time(&tzid("Mars/Olympus"), "20260115T100000", &COPENHAGEN),
+            None
+        );
+    }
+}
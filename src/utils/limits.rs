@@ -0,0 +1,289 @@
+//! Discord's length limits for messages and embeds, and helpers that cut
+//! text down to them. Going over any of these fails the whole request with
+//! an HTTP 400, so anything built from user-controlled text (names, reminder
+//! messages, long lists) should go through here.
+//!
+//! Cuts never split a character or Discord markup like `<:emoji:123>` or
+//! `<@123>`, and end in an ellipsis so it's clear something was left out.
+
+use crate::utils::transforms::is_discord_markup;
+use poise::serenity_prelude as serenity;
+
+pub const MESSAGE_CONTENT: usize = 2000;
+pub const EMBED_TITLE: usize = 256;
+pub const EMBED_DESCRIPTION: usize = 4096;
+pub const EMBED_FIELD_NAME: usize = 256;
+pub const EMBED_FIELD_VALUE: usize = 1024;
+pub const EMBED_FOOTER: usize = 2048;
+/// All titles, descriptions, field names and values and the footer together.
+pub const EMBED_TOTAL: usize = 6000;
+pub const EMBED_FIELDS: usize = 25;
+
+const ELLIPSIS: &str = "…";
+
+/// `text` cut to at most `max_chars` characters, ending in an ellipsis if
+/// anything was dropped.
+pub fn truncate(text: &str, max_chars: usize) -> String {
+    if text.chars().count() <= max_chars {
+        return text.to_string();
+    }
+    let keep = max_chars.saturating_sub(ELLIPSIS.chars().count());
+    let mut end = text.char_indices().nth(keep).map_or(text.len(), |(i, _)| i);
+    // Drop markup the cut would break in half rather than leave `<:emo…`
+    if let Some(start) = text[..end].rfind('<') {
+        if !text[start..end].contains('>') && is_discord_markup(&text[start..]) {
+            end = start;
+        }
+    }
+    let mut cut = text[..end].trim_end().to_string();
+    if max_chars > 0 {
+        cut.push_str(ELLIPSIS);
+    }
+    cut
+}
+
+pub fn fit_title(text: &str) -> String {
+    truncate(text, EMBED_TITLE)
+}
+
+pub fn fit_description(text: &str) -> String {
+    truncate(text, EMBED_DESCRIPTION)
+}
+
+pub fn fit_field_value(text: &str) -> String {
+    truncate(text, EMBED_FIELD_VALUE)
+}
+
+pub fn fit_content(text: &str) -> String {
+    truncate(text, MESSAGE_CONTENT)
+}
+
+/// As many of `lines` as fit in `max_chars` joined by newlines, with
+/// "…and N more" in place of the rest.
+pub fn fit_list<S: AsRef<str>>(lines: &[S], max_chars: usize) -> String {
+    let all = lines
+        .iter()
+        .map(|line| line.as_ref().chars().count())
+        .sum::<usize>()
+        + lines.len().saturating_sub(1);
+    if all <= max_chars {
+        return lines
+            .iter()
+            .map(AsRef::as_ref)
+            .collect::<Vec<_>>()
+            .join("\n");
+    }
+    let more = |n: usize| format!("…and {} more", n);
+    let mut out = String::new();
+    let mut used = 0;
+    for (i, line) in lines.iter().enumerate() {
+        let line = line.as_ref();
+        let separator = usize::from(i > 0);
+        let remaining = lines.len() - i - 1;
+        // Leave room to say how many are left if this isn't the last line
+        let reserve = match remaining {
+            0 => 0,
+            n => more(n).chars().count() + 1,
+        };
+        let length = line.chars().count();
+        if used + separator + length + reserve > max_chars {
+            let summary = more(lines.len() - i);
+            if i > 0 {
+                out.push('\n');
+            }
+            out.push_str(&summary);
+            return truncate(&out, max_chars);
+        }
+        if i > 0 {
+            out.push('\n');
+        }
+        out.push_str(line);
+        used += separator + length;
+    }
+    out
+}
+
+/// A field split at line breaks into as many fields as it takes to stay
+/// under the field value limit; the extra ones are named "<name> (cont.)".
+pub fn split_field(name: &str, value: &str) -> Vec<(String, String)> {
+    let mut chunks: Vec<String> = Vec::new();
+    let mut current = String::new();
+    for line in value.lines() {
+        let line = truncate(line, EMBED_FIELD_VALUE);
+        let joined = current.chars().count() + line.chars().count() + 1;
+        if !current.is_empty() && joined > EMBED_FIELD_VALUE {
+            chunks.push(std::mem::take(&mut current));
+        }
+        if !current.is_empty() {
+            current.push('\n');
+        }
+        current.push_str(&line);
+    }
+    chunks.push(current);
+
+    chunks
+        .into_iter()
+        .enumerate()
+        .map(|(i, chunk)| {
+            let name = if i == 0 {
+                name.to_string()
+            } else {
+                format!("{} (cont.)", name)
+            };
+            (truncate(&name, EMBED_FIELD_NAME), chunk)
+        })
+        .collect()
+}
+
+/// Keeps an embed under [`EMBED_TOTAL`] while it's built: every piece of
+/// text is cut to its own limit and to what's left of the total, and fields
+/// that don't fit any more (or past [`EMBED_FIELDS`]) are left out.
+pub struct EmbedBudget {
+    remaining: usize,
+    fields: usize,
+}
+
+impl Default for EmbedBudget {
+    fn default() -> Self {
+        Self {
+            remaining: EMBED_TOTAL,
+            fields: 0,
+        }
+    }
+}
+
+impl EmbedBudget {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// `text` cut to `limit` and to the remaining total, which it uses up.
+    pub fn fit(&mut self, text: &str, limit: usize) -> String {
+        let fitted = truncate(text, limit.min(self.remaining));
+        self.remaining -= fitted.chars().count();
+        fitted
+    }
+
+    pub fn title(&mut self, embed: serenity::CreateEmbed, text: &str) -> serenity::CreateEmbed {
+        embed.title(self.fit(text, EMBED_TITLE))
+    }
+
+    pub fn description(
+        &mut self,
+        embed: serenity::CreateEmbed,
+        text: &str,
+    ) -> serenity::CreateEmbed {
+        embed.description(self.fit(text, EMBED_DESCRIPTION))
+    }
+
+    /// Add a field if there's room for its name and at least some of its value.
+    pub fn field(
+        &mut self,
+        embed: serenity::CreateEmbed,
+        name: &str,
+        value: &str,
+        inline: bool,
+    ) -> serenity::CreateEmbed {
+        let name_len = name.chars().count().min(EMBED_FIELD_NAME);
+        // Keep a little room so the value isn't reduced to just an ellipsis
+        if self.fields >= EMBED_FIELDS || name_len + 16 > self.remaining {
+            return embed;
+        }
+        self.fields += 1;
+        let name = self.fit(name, EMBED_FIELD_NAME);
+        let value = self.fit(value, EMBED_FIELD_VALUE);
+        embed.field(name, value, inline)
+    }
+
+    pub fn footer(&mut self, embed: serenity::CreateEmbed, text: &str) -> serenity::CreateEmbed {
+        embed.footer(serenity::CreateEmbedFooter::new(
+            self.fit(text, EMBED_FOOTER),
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_truncate() {
+        assert_eq!(truncate("hello", 5), "hello");
+        assert_eq!(truncate("hello!", 5), "hell…");
+        assert_eq!(truncate("", 0), "");
+        assert_eq!(truncate("hello", 0), "");
+        // Counts characters, not bytes, and never cuts inside one
+        assert_eq!(truncate("æøåæøå", 6), "æøåæøå");
+        assert_eq!(truncate("æøåæøå", 4), "æøå…");
+        assert_eq!(truncate("🦀🦀🦀", 2), "🦀…");
+        // Custom emoji and mentions are kept whole or dropped
+        assert_eq!(truncate("hi <:ferris:123456> there", 10), "hi…");
+        assert_eq!(
+            truncate("hi <:ferris:123456> there", 20),
+            "hi <:ferris:123456>…"
+        );
+        assert_eq!(truncate("ping <@123456789> now", 10), "ping…");
+        // A lone `<` is just text
+        assert_eq!(truncate("1 < 2 and more", 8), "1 < 2 a…");
+
+        let long = "x".repeat(EMBED_FIELD_VALUE + 1);
+        assert_eq!(fit_field_value(&long).chars().count(), EMBED_FIELD_VALUE);
+        assert_eq!(
+            fit_field_value(&long[..EMBED_FIELD_VALUE]).chars().count(),
+            EMBED_FIELD_VALUE
+        );
+        assert!(!fit_field_value(&long[..EMBED_FIELD_VALUE]).ends_with(ELLIPSIS));
+    }
+
+    #[test]
+    fn test_fit_list() {
+        let lines: Vec<String> = (1..=5).map(|i| format!("line {}", i)).collect();
+        assert_eq!(fit_list(&lines, 100), lines.join("\n"));
+        // Exactly fitting needs no summary
+        let exact = lines.join("\n").chars().count();
+        assert_eq!(fit_list(&lines, exact), lines.join("\n"));
+        let fitted = fit_list(&lines, 30);
+        assert_eq!(fitted, "line 1\nline 2\n…and 3 more");
+        assert!(fitted.chars().count() <= 30);
+        assert_eq!(fit_list(&lines, 14), "…and 5 more");
+        assert!(fit_list::<&str>(&[], 10).is_empty());
+    }
+
+    #[test]
+    fn test_split_field() {
+        let short = split_field("Tools", "a\nb");
+        assert_eq!(short, vec![("Tools".to_string(), "a\nb".to_string())]);
+
+        let line = "y".repeat(300);
+        let value = [line.as_str(); 5].join("\n");
+        let fields = split_field("Tools", &value);
+        assert_eq!(fields.len(), 2);
+        assert_eq!(fields[0].1.lines().count(), 3);
+        assert_eq!(fields[1].0, "Tools (cont.)");
+        assert!(fields
+            .iter()
+            .all(|(_, value)| value.chars().count() <= EMBED_FIELD_VALUE));
+    }
+
+    #[test]
+    fn test_embed_budget() {
+        let mut budget = EmbedBudget::new();
+        let big = "z".repeat(EMBED_FIELD_VALUE);
+        let mut embed = serenity::CreateEmbed::new();
+        for _ in 0..10 {
+            embed = budget.field(embed, "Field", &big, false);
+        }
+        // 5 whole fields, then a cut one, then no more room
+        assert_eq!(budget.fields, 6);
+        assert_eq!(budget.remaining, 0);
+        let _ = embed;
+
+        let mut budget = EmbedBudget::new();
+        let mut embed = serenity::CreateEmbed::new();
+        for _ in 0..30 {
+            embed = budget.field(embed, "n", "v", true);
+        }
+        let _ = embed;
+        assert_eq!(budget.fields, EMBED_FIELDS);
+    }
+}
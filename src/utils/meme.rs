@@ -0,0 +1,770 @@
+use crate::utils::avatar::AvatarError;
+use crate::{Context, Error};
+use image::{AnimationDecoder, DynamicImage};
+use parking_lot::RwLock;
+use poise::serenity_prelude as serenity;
+use rand::seq::SliceRandom;
+use rand::Rng;
+use std::env;
+use std::fmt;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Where the meme GIFs live unless `RUSTBOT_ASSETS_DIR` says otherwise,
+/// relative to the working directory.
+const DEFAULT_ASSETS_DIR: &str = "assets";
+
+/// Subdirectories of the assets directory that hold base GIFs.
+pub const MEME_ASSET_DIRS: &[&str] = &["bonk", "hit"];
+
+/// Slowest and fastest a meme GIF can be played with the `speed` option.
+pub const MIN_SPEED: f32 = 0.25;
+pub const MAX_SPEED: f32 = 4.0;
+
+/// Shortest frame delay written when changing the speed. GIFs count delays
+/// in hundredths of a second, and many players show 0 or 10 ms frames at
+/// 100 ms, which would make a sped-up GIF slower instead.
+const MIN_FRAME_DELAY_MS: u32 = 20;
+
+lazy_static::lazy_static! {
+    static ref ASSETS_DIR: PathBuf = env::var("RUSTBOT_ASSETS_DIR")
+        .ok()
+        .filter(|dir| !dir.trim().is_empty())
+        .map(PathBuf::from)
+        .unwrap_or_else(|| PathBuf::from(DEFAULT_ASSETS_DIR));
+}
+
+/// The directory with the meme GIFs (`bonk/`, `hit/`), read from
+/// `RUSTBOT_ASSETS_DIR` the first time it's needed.
+pub fn assets_dir() -> &'static Path {
+    &ASSETS_DIR
+}
+
+/// Problems with the meme asset directories, for `-selftest`. Scans the
+/// disk afresh without touching what the meme commands use.
+pub fn asset_problems() -> Vec<String> {
+    let dir = assets_dir();
+    scan_problems(dir, &scan_assets_in(dir))
+}
+
+fn scan_problems(dir: &Path, scan: &[AssetCategory]) -> Vec<String> {
+    let mut problems = Vec::new();
+    for category in scan {
+        let path = dir.join(category.name);
+        for rejected in &category.rejected {
+            problems.push(format!(
+                "{} is skipped: {}",
+                path.join(&rejected.file_name).display(),
+                rejected.reason
+            ));
+        }
+        if let Some(e) = &category.error {
+            problems.push(format!(
+                "Asset directory {} is missing ({}); those commands will fall back to text",
+                path.display(),
+                e
+            ));
+        } else if category.valid.is_empty() {
+            let what = if category.rejected.is_empty() {
+                "no GIFs"
+            } else {
+                "no usable GIFs"
+            };
+            problems.push(format!(
+                "Asset directory {} has {}; those commands will fall back to text",
+                path.display(),
+                what
+            ));
+        }
+    }
+    problems
+}
+
+fn is_gif(path: &Path) -> bool {
+    path.extension()
+        .is_some_and(|ext| ext.eq_ignore_ascii_case("gif"))
+}
+
+/// Base GIFs with more frames than this are skipped by the scan, to keep
+/// rendering time down.
+const MAX_FRAMES: usize = 100;
+
+/// Smallest and largest a base GIF can be on either side, in pixels.
+const MIN_GIF_SIDE: u32 = 32;
+const MAX_GIF_SIDE: u32 = 1024;
+
+/// Where the avatar goes on a base GIF, as fractions of the GIF's size.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Overlay {
+    pub x_percent: f32,
+    pub y_percent: f32,
+    pub scale_percent: f32,
+}
+
+/// A base GIF that passed the scan, with where its avatar goes.
+#[derive(Clone, Debug, PartialEq)]
+pub struct GifAsset {
+    pub file_name: String,
+    pub path: PathBuf,
+    pub overlay: Overlay,
+}
+
+/// A file in an asset directory the scan left out, and why.
+#[derive(Clone, Debug, PartialEq)]
+pub struct RejectedAsset {
+    pub file_name: String,
+    pub reason: String,
+}
+
+/// What the scan found in one asset directory (`bonk`, `hit`).
+#[derive(Clone, Debug, PartialEq)]
+pub struct AssetCategory {
+    pub name: &'static str,
+    /// Sorted by file name, so a seeded RNG always picks the same GIF.
+    pub valid: Vec<GifAsset>,
+    pub rejected: Vec<RejectedAsset>,
+    /// Why the directory couldn't be read at all.
+    pub error: Option<String>,
+}
+
+/// The last scan of the asset directories, kept in [`crate::Data`] for
+/// the meme commands to pick GIFs from.
+pub struct AssetScan {
+    scan: RwLock<Vec<AssetCategory>>,
+}
+
+impl AssetScan {
+    /// Scan the asset directories, log where they are and warn about ones
+    /// that are missing or have no usable GIFs. Called once at startup.
+    pub async fn load() -> Result<Self, Error> {
+        let dir = assets_dir();
+        log::info!("Loading meme GIFs from {}", dir.display());
+        let scan = scan_logged().await?;
+        for category in &scan {
+            log::info!(
+                "{} GIFs: {} usable, {} skipped",
+                category.name,
+                category.valid.len(),
+                category.rejected.len()
+            );
+        }
+        for problem in scan_problems(dir, &scan) {
+            log::warn!("{}", problem);
+        }
+        Ok(Self {
+            scan: RwLock::new(scan),
+        })
+    }
+
+    /// The scan of every asset directory.
+    pub fn categories(&self) -> Vec<AssetCategory> {
+        self.scan.read().clone()
+    }
+
+    /// Scan the asset directories again and replace the scan, logging each
+    /// file that's skipped. Returns the new scan.
+    pub async fn rescan(&self) -> Result<Vec<AssetCategory>, Error> {
+        // Scan before taking the lock, so picking a GIF never waits on the disk
+        let scan = scan_logged().await?;
+        *self.scan.write() = scan.clone();
+        Ok(scan)
+    }
+
+    /// Pick a random base GIF of `kind` (`bonk`, `hit`) from the scanned
+    /// ones, with where its avatar goes.
+    pub fn select_random_gif<R: Rng + ?Sized>(
+        &self,
+        kind: &str,
+        rng: &mut R,
+    ) -> Result<(String, Overlay), Error> {
+        let scan = self.scan.read();
+        let assets = scan
+            .iter()
+            .find(|category| category.name == kind)
+            .map(|category| category.valid.as_slice())
+            .unwrap_or_default();
+        let picked = assets.choose(rng).ok_or_else(|| {
+            format!(
+                "No usable {} GIFs found in {}",
+                kind,
+                assets_dir().join(kind).display()
+            )
+        })?;
+        Ok((
+            picked.path.to_string_lossy().to_string(),
+            picked.overlay.clone(),
+        ))
+    }
+}
+
+/// Scan the asset directories on a blocking thread, logging each file
+/// that's skipped.
+async fn scan_logged() -> Result<Vec<AssetCategory>, Error> {
+    let scan = tokio::task::spawn_blocking(|| scan_assets_in(assets_dir())).await?;
+    for category in &scan {
+        for rejected in &category.rejected {
+            log::warn!(
+                "Skipping {} GIF {}: {}",
+                category.name,
+                rejected.file_name,
+                rejected.reason
+            );
+        }
+    }
+    Ok(scan)
+}
+
+fn scan_assets_in(dir: &Path) -> Vec<AssetCategory> {
+    MEME_ASSET_DIRS
+        .iter()
+        .map(|name| scan_category(dir, name))
+        .collect()
+}
+
+fn scan_category(dir: &Path, name: &'static str) -> AssetCategory {
+    let mut category = AssetCategory {
+        name,
+        valid: Vec::new(),
+        rejected: Vec::new(),
+        error: None,
+    };
+    let entries = match fs::read_dir(dir.join(name)) {
+        Ok(entries) => entries,
+        Err(e) => {
+            category.error = Some(e.to_string());
+            return category;
+        }
+    };
+
+    let mut paths: Vec<PathBuf> = entries
+        .flatten()
+        .map(|entry| entry.path())
+        .filter(|path| is_gif(path))
+        .collect();
+    paths.sort();
+    for path in paths {
+        let file_name = path
+            .file_name()
+            .map(|n| n.to_string_lossy().to_string())
+            .unwrap_or_default();
+        let checked = check_gif_name(name, &file_name)
+            .and_then(|overlay| read_gif_shape(&path).map(|shape| (overlay, shape)))
+            .and_then(|(overlay, (width, height, frames))| {
+                check_gif_shape(width, height, frames).map(|()| overlay)
+            });
+        match checked {
+            Ok(overlay) => category.valid.push(GifAsset {
+                file_name,
+                path,
+                overlay,
+            }),
+            Err(reason) => category.rejected.push(RejectedAsset { file_name, reason }),
+        }
+    }
+    category
+}
+
+/// Check a base GIF's file name for the `kind` directory and read where
+/// its avatar goes. `Err` says what's wrong with it.
+fn check_gif_name(kind: &str, file_name: &str) -> Result<Overlay, String> {
+    let overlay = parse_gif_filename(kind, file_name)
+        .map_err(|_| format!("the name isn't like `{}_<n>_x<x>_y<y>_s<scale>.gif`", kind))?;
+    let on_gif = 0.0..1.0;
+    if !on_gif.contains(&overlay.x_percent) || !on_gif.contains(&overlay.y_percent) {
+        return Err(format!(
+            "the avatar at x{} y{} is off the GIF (both must be at least 0 and under 1)",
+            overlay.x_percent, overlay.y_percent
+        ));
+    }
+    if !(overlay.scale_percent > 0.0 && overlay.scale_percent <= 1.0) {
+        return Err(format!(
+            "the avatar scale s{} must be above 0 and at most 1",
+            overlay.scale_percent
+        ));
+    }
+    Ok(overlay)
+}
+
+/// A GIF's width, height and frame count, counting no further than one
+/// past [`MAX_FRAMES`].
+fn read_gif_shape(path: &Path) -> Result<(u32, u32, usize), String> {
+    let read = || -> Result<(u32, u32, usize), gif::DecodingError> {
+        let mut decoder = gif::DecodeOptions::new().read_info(fs::File::open(path)?)?;
+        let (width, height) = (decoder.width() as u32, decoder.height() as u32);
+        let mut frames = 0;
+        while frames <= MAX_FRAMES && decoder.next_frame_info()?.is_some() {
+            frames += 1;
+        }
+        Ok((width, height, frames))
+    };
+    read().map_err(|e| format!("it can't be read as a GIF ({})", e))
+}
+
+/// Check a base GIF's size and length. `Err` says what's wrong with it.
+fn check_gif_shape(width: u32, height: u32, frames: usize) -> Result<(), String> {
+    let sides = MIN_GIF_SIDE..=MAX_GIF_SIDE;
+    if !sides.contains(&width) || !sides.contains(&height) {
+        return Err(format!(
+            "it's {}x{}, sides must be {} to {} pixels",
+            width, height, MIN_GIF_SIDE, MAX_GIF_SIDE
+        ));
+    }
+    if frames == 0 {
+        return Err("it has no frames".to_string());
+    }
+    if frames > MAX_FRAMES {
+        return Err(format!("it has more than {} frames", MAX_FRAMES));
+    }
+    Ok(())
+}
+
+/// Read the avatar's place from a file name like `bonk_1_x0.2_y0.3_s0.25.gif`.
+pub fn parse_gif_filename(kind: &str, filename: &str) -> Result<Overlay, Error> {
+    let basename = filename
+        .split('/')
+        .next_back()
+        .unwrap_or(filename)
+        .trim_end_matches(".gif");
+
+    let parts: Vec<&str> = basename.split('_').collect();
+    if parts.len() < 5 || parts[0] != kind {
+        return Err(format!("Invalid {} filename format: {}", kind, filename).into());
+    }
+
+    let mut overlay = Overlay {
+        x_percent: 0.1,
+        y_percent: 0.4,
+        scale_percent: 0.3,
+    };
+    for part in &parts[2..] {
+        if let Some(x_val) = part.strip_prefix('x') {
+            overlay.x_percent = x_val.parse::<f32>()?;
+        } else if let Some(y_val) = part.strip_prefix('y') {
+            overlay.y_percent = y_val.parse::<f32>()?;
+        } else if let Some(s_val) = part.strip_prefix('s') {
+            overlay.scale_percent = s_val.parse::<f32>()?;
+        }
+    }
+    Ok(overlay)
+}
+
+/// The playback speed for a requested one: normal speed when none was
+/// asked for, otherwise clamped to [`MIN_SPEED`]..=[`MAX_SPEED`].
+pub fn clamp_speed(speed: Option<f32>) -> f32 {
+    match speed {
+        Some(speed) if speed.is_finite() => speed.clamp(MIN_SPEED, MAX_SPEED),
+        _ => 1.0,
+    }
+}
+
+/// A frame delay of `numer / denom` ms played at `speed`, in whole
+/// hundredths of a second like GIFs store it, and never shorter than
+/// [`MIN_FRAME_DELAY_MS`].
+fn scaled_delay_ms(numer: u32, denom: u32, speed: f32) -> u32 {
+    let ms = numer as f32 / denom.max(1) as f32 / speed;
+    let centiseconds = (ms / 10.0).round() as u32;
+    (centiseconds * 10).max(MIN_FRAME_DELAY_MS)
+}
+
+/// Put the avatar on every frame of the GIF at `gif_path`, alpha blended,
+/// and encode the result. At a `speed` of 1 frames keep their delays;
+/// otherwise each delay is divided by it.
+pub fn overlay_avatar(
+    avatar_img: &DynamicImage,
+    gif_path: &str,
+    overlay: &Overlay,
+    speed: f32,
+) -> Result<Vec<u8>, Error> {
+    let gif_file = fs::File::open(gif_path)?;
+    let decoder = image::codecs::gif::GifDecoder::new(gif_file)?;
+    let frames = decoder.into_frames().collect::<Result<Vec<_>, _>>()?;
+    let Some(first_frame) = frames.first() else {
+        return Err("GIF has no frames".into());
+    };
+    let (screen_width, screen_height) = first_frame.buffer().dimensions();
+
+    let pfp_size = (screen_height as f32 * overlay.scale_percent) as u32;
+    let resized_avatar = avatar_img
+        .resize_exact(pfp_size, pfp_size, image::imageops::FilterType::Lanczos3)
+        .to_rgba8();
+    let overlay_x = (screen_width as f32 * overlay.x_percent) as u32;
+    let overlay_y = (screen_height as f32 * overlay.y_percent) as u32;
+
+    let mut output = Vec::new();
+    {
+        let mut encoder = image::codecs::gif::GifEncoder::new(&mut output);
+        encoder.set_repeat(image::codecs::gif::Repeat::Infinite)?;
+
+        for frame in frames.iter().take(MAX_FRAMES) {
+            let mut frame_buffer = frame.buffer().clone();
+
+            for y in 0..pfp_size {
+                for x in 0..pfp_size {
+                    let dst_x = overlay_x + x;
+                    let dst_y = overlay_y + y;
+                    if dst_x >= screen_width || dst_y >= screen_height {
+                        continue;
+                    }
+
+                    let avatar_pixel = resized_avatar.get_pixel(x, y);
+                    let alpha = avatar_pixel[3] as f32 / 255.0;
+                    if alpha > 0.0 {
+                        let frame_pixel = frame_buffer.get_pixel_mut(dst_x, dst_y);
+                        let inv_alpha = 1.0 - alpha;
+                        for channel in 0..4 {
+                            frame_pixel[channel] = (avatar_pixel[channel] as f32 * alpha
+                                + frame_pixel[channel] as f32 * inv_alpha)
+                                .min(255.0)
+                                as u8;
+                        }
+                    }
+                }
+            }
+
+            let (numer, denom) = frame.delay().numer_denom_ms();
+            let frame_delay = if speed == 1.0 {
+                image::Delay::from_numer_denom_ms(numer, denom)
+            } else {
+                image::Delay::from_numer_denom_ms(scaled_delay_ms(numer, denom, speed), 1)
+            };
+            encoder.encode_frame(image::Frame::from_parts(frame_buffer, 0, 0, frame_delay))?;
+        }
+    }
+    Ok(output)
+}
+
+/// Why a meme GIF (bonk, hit) couldn't be made.
+#[derive(Debug)]
+pub enum MemeFailure {
+    /// No base GIF could be picked: the assets directory is missing or has
+    /// no GIFs that passed the scan.
+    NoAssets(Error),
+    /// The target's avatar couldn't be fetched.
+    Avatar {
+        base_gif: String,
+        error: AvatarError,
+    },
+    /// Putting the avatar on the picked GIF failed.
+    Render { base_gif: String, error: Error },
+}
+
+impl fmt::Display for MemeFailure {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::NoAssets(e) => write!(f, "no usable base GIF: {}", e),
+            Self::Avatar { base_gif, error } => {
+                write!(f, "avatar download failed ({}): {:?}", base_gif, error)
+            }
+            Self::Render { base_gif, error } => {
+                write!(f, "rendering {} failed: {}", base_gif, error)
+            }
+        }
+    }
+}
+
+/// What to post instead of the personalised GIF.
+#[derive(Debug, PartialEq)]
+enum Fallback {
+    /// The picked GIF as-is, without the avatar on it.
+    BaseGif(Vec<u8>),
+    /// Just the text.
+    Text,
+}
+
+/// How a meme command words its fallbacks.
+pub struct MemeFallback {
+    /// Attachment name for the base GIF, e.g. `bonk.gif`.
+    pub file_name: &'static str,
+    /// Message posted with the base GIF.
+    pub gif_caption: String,
+    /// Message posted when there is no GIF to show at all.
+    pub text: String,
+}
+
+/// Pick the best fallback for a failure: the base GIF when only the
+/// personalising failed, text when the GIF itself is missing or broken.
+fn choose_fallback(failure: &MemeFailure) -> Fallback {
+    let base_gif = match failure {
+        MemeFailure::NoAssets(_) => return Fallback::Text,
+        MemeFailure::Avatar { base_gif, .. } => base_gif,
+        MemeFailure::Render { base_gif, error } => {
+            // A GIF we couldn't decode is no better in a Discord client
+            let undecodable = error
+                .downcast_ref::<image::ImageError>()
+                .is_some_and(|e| matches!(e, image::ImageError::Decoding(_)));
+            if undecodable {
+                return Fallback::Text;
+            }
+            base_gif
+        }
+    };
+    match fs::read(base_gif) {
+        Ok(bytes) if bytes.starts_with(b"GIF8") => Fallback::BaseGif(bytes),
+        _ => Fallback::Text,
+    }
+}
+
+/// Replace the command's progress message with a fallback so the meme
+/// still lands. The detailed error only goes to the logs.
+pub async fn send_meme_fallback(
+    ctx: Context<'_>,
+    progress: &poise::ReplyHandle<'_>,
+    failure: MemeFailure,
+    fallback: MemeFallback,
+) -> Result<(), Error> {
+    let reply = match choose_fallback(&failure) {
+        Fallback::BaseGif(bytes) => {
+            log::warn!(
+                "{} failed, sending the base GIF instead: {}",
+                fallback.file_name,
+                failure
+            );
+            poise::CreateReply::default()
+                .content(fallback.gif_caption)
+                .attachment(serenity::CreateAttachment::bytes(bytes, fallback.file_name))
+        }
+        Fallback::Text => {
+            log::warn!(
+                "{} failed, sending text instead: {}",
+                fallback.file_name,
+                failure
+            );
+            poise::CreateReply::default().content(fallback.text)
+        }
+    };
+    progress.edit(ctx, reply).await?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn render_failure(base_gif: &str, error: Error) -> MemeFailure {
+        MemeFailure::Render {
+            base_gif: base_gif.to_string(),
+            error,
+        }
+    }
+
+    #[test]
+    fn test_overlay_avatar() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("bonk_1_x0.5_y0.5_s0.5.gif");
+        write_gif(&path, 20, 3);
+        let path = path.to_string_lossy();
+        let overlay = parse_gif_filename("bonk", &path).unwrap();
+        let white = DynamicImage::ImageRgba8(image::RgbaImage::from_pixel(
+            4,
+            4,
+            image::Rgba([255, 255, 255, 255]),
+        ));
+
+        let gif = overlay_avatar(&white, &path, &overlay, 1.0).unwrap();
+        let decoder = image::codecs::gif::GifDecoder::new(std::io::Cursor::new(gif)).unwrap();
+        let frames = decoder.into_frames().collect_frames().unwrap();
+        assert_eq!(frames.len(), 3);
+        // The avatar covers the bottom-right quarter only
+        let frame = frames[0].buffer();
+        assert_eq!(frame.get_pixel(15, 15)[0], 255);
+        assert_eq!(frame.get_pixel(2, 2)[0], 0);
+
+        assert!(parse_gif_filename("hit", &path).is_err());
+    }
+
+    #[test]
+    fn test_speed_scales_frame_delays() {
+        // 100 ms frames: half speed doubles them, double speed halves them
+        assert_eq!(scaled_delay_ms(100, 1, 0.5), 200);
+        assert_eq!(scaled_delay_ms(100, 1, 2.0), 50);
+        // Rounded to hundredths of a second, as GIFs store them
+        assert_eq!(scaled_delay_ms(70, 1, 1.5), 50);
+        assert_eq!(scaled_delay_ms(100, 3, 1.0), 30);
+        // Never so short that players fall back to a slow default
+        assert_eq!(scaled_delay_ms(30, 1, 4.0), MIN_FRAME_DELAY_MS);
+        assert_eq!(scaled_delay_ms(0, 1, 2.0), MIN_FRAME_DELAY_MS);
+        assert_eq!(scaled_delay_ms(100, 0, 1.0), 100);
+
+        assert_eq!(clamp_speed(None), 1.0);
+        assert_eq!(clamp_speed(Some(1.5)), 1.5);
+        assert_eq!(clamp_speed(Some(0.0)), MIN_SPEED);
+        assert_eq!(clamp_speed(Some(-2.0)), MIN_SPEED);
+        assert_eq!(clamp_speed(Some(100.0)), MAX_SPEED);
+        assert_eq!(clamp_speed(Some(f32::NAN)), 1.0);
+    }
+
+    fn write_gif(path: &Path, side: u32, frames: usize) {
+        let file = fs::File::create(path).unwrap();
+        let mut encoder = image::codecs::gif::GifEncoder::new(file);
+        for _ in 0..frames {
+            let black = image::RgbaImage::from_pixel(side, side, image::Rgba([0, 0, 0, 255]));
+            encoder.encode_frame(image::Frame::new(black)).unwrap();
+        }
+    }
+
+    #[test]
+    fn test_asset_dir_problems() {
+        let dir = Path::new(DEFAULT_ASSETS_DIR);
+        assert!(scan_problems(dir, &scan_assets_in(dir)).is_empty());
+        let dir = Path::new("no/such/assets");
+        let problems = scan_problems(dir, &scan_assets_in(dir));
+        assert_eq!(problems.len(), MEME_ASSET_DIRS.len());
+        assert!(problems[0].contains("is missing"), "{problems:?}");
+
+        let dir = tempfile::tempdir().unwrap();
+        fs::create_dir(dir.path().join("bonk")).unwrap();
+        fs::write(dir.path().join("bonk/notes.txt"), "").unwrap();
+        fs::create_dir(dir.path().join("hit")).unwrap();
+        fs::write(dir.path().join("hit/hit_1.GIF"), "").unwrap();
+        let problems = scan_problems(dir.path(), &scan_assets_in(dir.path()));
+        assert_eq!(problems.len(), 3);
+        assert!(problems[0].contains("bonk has no GIFs"), "{problems:?}");
+        assert!(problems[1].contains("hit_1.GIF is skipped"), "{problems:?}");
+        assert!(
+            problems[2].contains("hit has no usable GIFs"),
+            "{problems:?}"
+        );
+    }
+
+    #[test]
+    fn test_check_gif_name() {
+        assert_eq!(
+            check_gif_name("bonk", "bonk_1_x0.2_y0.3_s0.25.gif"),
+            Ok(Overlay {
+                x_percent: 0.2,
+                y_percent: 0.3,
+                scale_percent: 0.25,
+            })
+        );
+        // Missing tokens keep their defaults
+        assert_eq!(
+            check_gif_name("hit", "hit_2_x0.5_y0.5_extra.gif").map(|o| o.scale_percent),
+            Ok(0.3)
+        );
+
+        let reason = |kind, name| check_gif_name(kind, name).unwrap_err();
+        assert!(reason("bonk", "bonk_1.gif").contains("isn't like `bonk_<n>_"));
+        assert!(reason("bonk", "hit_1_x0.2_y0.3_s0.25.gif").contains("isn't like"));
+        assert!(reason("bonk", "bonk_1_xabc_y0.3_s0.25.gif").contains("isn't like"));
+        assert!(reason("bonk", "bonk_1_x1.5_y0.3_s0.25.gif").contains("off the GIF"));
+        assert!(reason("bonk", "bonk_1_x0.2_y-0.1_s0.25.gif").contains("off the GIF"));
+        assert!(reason("bonk", "bonk_1_x0.2_y0.3_s0.gif").contains("scale s0"));
+        assert!(reason("bonk", "bonk_1_x0.2_y0.3_s2.gif").contains("scale s2"));
+        assert!(reason("bonk", "bonk_1_x0.2_y0.3_sNaN.gif").contains("scale sNaN"));
+    }
+
+    #[test]
+    fn test_check_gif_shape() {
+        assert_eq!(check_gif_shape(200, 150, 12), Ok(()));
+        assert_eq!(
+            check_gif_shape(MIN_GIF_SIDE, MAX_GIF_SIDE, MAX_FRAMES),
+            Ok(())
+        );
+        assert!(check_gif_shape(16, 200, 12).unwrap_err().contains("16x200"));
+        assert!(check_gif_shape(200, 4000, 12)
+            .unwrap_err()
+            .contains("200x4000"));
+        assert_eq!(
+            check_gif_shape(200, 200, 0),
+            Err("it has no frames".to_string())
+        );
+        assert!(check_gif_shape(200, 200, MAX_FRAMES + 1)
+            .unwrap_err()
+            .contains("more than 100 frames"));
+    }
+
+    #[test]
+    fn test_scan_skips_invalid_gifs() {
+        let dir = tempfile::tempdir().unwrap();
+        let bonk = dir.path().join("bonk");
+        fs::create_dir(&bonk).unwrap();
+        write_gif(&bonk.join("bonk_2_x0.5_y0.5_s0.5.gif"), 40, 2);
+        write_gif(&bonk.join("bonk_1_x0.1_y0.1_s0.2.gif"), 40, 1);
+        write_gif(&bonk.join("bonk_3_x0.5_y0.5_s0.5.gif"), 8, 1);
+        write_gif(&bonk.join("bonk_4_x0.5_y0.5_s0.5.gif"), 40, MAX_FRAMES + 5);
+        write_gif(&bonk.join("bonk_typo.gif"), 40, 1);
+        fs::write(bonk.join("bonk_5_x0.5_y0.5_s0.5.gif"), b"not a gif").unwrap();
+        fs::write(bonk.join("README.md"), "").unwrap();
+
+        let category = scan_category(dir.path(), "bonk");
+        assert_eq!(category.error, None);
+        let valid: Vec<&str> = category
+            .valid
+            .iter()
+            .map(|asset| asset.file_name.as_str())
+            .collect();
+        assert_eq!(
+            valid,
+            ["bonk_1_x0.1_y0.1_s0.2.gif", "bonk_2_x0.5_y0.5_s0.5.gif"]
+        );
+        assert_eq!(category.valid[0].overlay.scale_percent, 0.2);
+
+        let rejected: Vec<&str> = category
+            .rejected
+            .iter()
+            .map(|asset| asset.file_name.as_str())
+            .collect();
+        assert_eq!(
+            rejected,
+            [
+                "bonk_3_x0.5_y0.5_s0.5.gif",
+                "bonk_4_x0.5_y0.5_s0.5.gif",
+                "bonk_5_x0.5_y0.5_s0.5.gif",
+                "bonk_typo.gif",
+            ]
+        );
+        assert!(category.rejected[0].reason.contains("8x8"));
+        assert!(category.rejected[1].reason.contains("more than"));
+        assert!(category.rejected[2].reason.contains("can't be read"));
+
+        assert!(scan_category(dir.path(), "hit").error.is_some());
+    }
+
+    #[test]
+    fn test_missing_assets_fall_back_to_text() {
+        let failure = MemeFailure::NoAssets("No bonk GIFs found".into());
+        assert_eq!(choose_fallback(&failure), Fallback::Text);
+
+        let failure = render_failure("/nonexistent/bonk_1_x0.1_y0.4_s0.3.gif", "oops".into());
+        assert_eq!(choose_fallback(&failure), Fallback::Text);
+    }
+
+    #[test]
+    fn test_render_failure_falls_back_to_base_gif() {
+        let dir = tempfile::tempdir().unwrap();
+        let gif = dir.path().join("bonk_1_x0.1_y0.4_s0.3.gif");
+        fs::write(&gif, b"GIF89a rest of the file").unwrap();
+        let gif = gif.to_string_lossy();
+
+        let failure = render_failure(&gif, "disk full".into());
+        assert_eq!(
+            choose_fallback(&failure),
+            Fallback::BaseGif(b"GIF89a rest of the file".to_vec())
+        );
+
+        let failure = MemeFailure::Avatar {
+            base_gif: gif.to_string(),
+            error: AvatarError::TooLarge,
+        };
+        assert!(matches!(choose_fallback(&failure), Fallback::BaseGif(_)));
+    }
+
+    #[test]
+    fn test_broken_base_gif_falls_back_to_text() {
+        let dir = tempfile::tempdir().unwrap();
+        let not_a_gif = dir.path().join("hit_1_x0.1_y0.4_s0.3.gif");
+        fs::write(&not_a_gif, b"not a gif").unwrap();
+        let failure = render_failure(&not_a_gif.to_string_lossy(), "disk full".into());
+        assert_eq!(choose_fallback(&failure), Fallback::Text);
+
+        // Looks like a GIF, but the decoder choked on it
+        let truncated = dir.path().join("hit_2_x0.1_y0.4_s0.3.gif");
+        fs::write(&truncated, b"GIF89a").unwrap();
+        let decode_error = image::ImageError::Decoding(image::error::DecodingError::new(
+            image::ImageFormat::Gif.into(),
+            "unexpected end of file",
+        ));
+        let failure = render_failure(&truncated.to_string_lossy(), Box::new(decode_error));
+        assert_eq!(choose_fallback(&failure), Fallback::Text);
+    }
+}
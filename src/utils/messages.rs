@@ -0,0 +1,198 @@
+use crate::Error;
+use poise::serenity_prelude as serenity;
+use std::future::Future;
+use tokio::time::{sleep, Duration};
+
+/// Discord returns at most 100 messages per request.
+const BATCH_SIZE: u64 = 100;
+
+/// Pause between requests so long fetches stay well clear of rate limits.
+const BATCH_DELAY: Duration = Duration::from_millis(1000);
+
+/// How often (in messages) `progress` is called.
+const PROGRESS_EVERY: u64 = 500;
+
+/// Fetch up to `count` of the newest messages in a channel, newest first.
+///
+/// Messages are requested 100 at a time, paging backwards with `before`, with
+/// a one second pause between requests (skipped once there is nothing left to
+/// fetch). `progress` is called with the running total every 500 messages and
+/// once all are collected.
+pub async fn collect_messages<F, Fut>(
+    http: &serenity::Http,
+    channel_id: serenity::ChannelId,
+    count: u64,
+    progress: F,
+) -> Result<Vec<serenity::Message>, Error>
+where
+    F: FnMut(u64) -> Fut,
+    Fut: Future<Output = Result<(), Error>>,
+{
+    let fetch = |limit: u8, before: Option<serenity::MessageId>| async move {
+        let mut builder = serenity::GetMessages::new().limit(limit);
+        if let Some(before_id) = before {
+            builder = builder.before(before_id);
+        }
+        channel_id.messages(http, builder).await
+    };
+    collect_with(fetch, count, BATCH_DELAY, progress).await
+}
+
+/// The paging loop behind [`collect_messages`], with the request abstracted
+/// away so it can be tested without Discord.
+async fn collect_with<Fetch, FetchFut, F, Fut>(
+    mut fetch: Fetch,
+    count: u64,
+    delay: Duration,
+    mut progress: F,
+) -> Result<Vec<serenity::Message>, Error>
+where
+    Fetch: FnMut(u8, Option<serenity::MessageId>) -> FetchFut,
+    FetchFut: Future<Output = Result<Vec<serenity::Message>, serenity::Error>>,
+    F: FnMut(u64) -> Fut,
+    Fut: Future<Output = Result<(), Error>>,
+{
+    let mut all_messages = Vec::new();
+    let mut last_message_id = None;
+    let mut collected = 0u64;
+
+    while collected < count {
+        let batch_size = (count - collected).min(BATCH_SIZE);
+
+        let messages = fetch(batch_size as u8, last_message_id).await?;
+
+        if messages.is_empty() {
+            break; // No more messages to fetch
+        }
+
+        // A short page means we reached the start of the channel
+        let exhausted = (messages.len() as u64) < batch_size;
+
+        last_message_id = messages.last().map(|m| m.id);
+        collected += messages.len() as u64;
+        all_messages.extend(messages);
+
+        if collected.is_multiple_of(PROGRESS_EVERY) || collected >= count {
+            progress(collected).await?;
+        }
+
+        if exhausted || collected >= count {
+            break;
+        }
+        sleep(delay).await;
+    }
+
+    Ok(all_messages)
+}
+
+/// A message link (`https://discord.com/channels/<guild>/<channel>/<message>`)
+/// or a bare message ID in `current_channel`.
+pub fn parse_message_ref(
+    text: &str,
+    current_channel: serenity::ChannelId,
+) -> Option<(serenity::ChannelId, serenity::MessageId)> {
+    let text = text.trim();
+    if let Some(rest) = text.split("/channels/").nth(1) {
+        let mut parts = rest.split('/').skip(1);
+        let channel = parts.next()?.parse().ok().filter(|id| *id != 0)?;
+        let message = parts
+            .next()?
+            .trim_end_matches(|c: char| !c.is_ascii_digit())
+            .parse()
+            .ok()
+            .filter(|id| *id != 0)?;
+        return Some((
+            serenity::ChannelId::new(channel),
+            serenity::MessageId::new(message),
+        ));
+    }
+    let message = text.parse().ok().filter(|id| *id != 0)?;
+    Some((current_channel, serenity::MessageId::new(message)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Mutex;
+
+    /// A fake channel holding messages with ids `total..=1`, newest first.
+    fn fake_channel(
+        total: u64,
+    ) -> impl FnMut(
+        u8,
+        Option<serenity::MessageId>,
+    ) -> std::future::Ready<Result<Vec<serenity::Message>, serenity::Error>> {
+        move |limit, before| {
+            let start = before.map_or(total, |id| id.get() - 1);
+            let page = (1..=start)
+                .rev()
+                .take(limit as usize)
+                .map(|id| {
+                    let mut message = serenity::Message::default();
+                    message.id = serenity::MessageId::new(id);
+                    message
+                })
+                .collect();
+            std::future::ready(Ok(page))
+        }
+    }
+
+    #[tokio::test]
+    async fn test_collects_requested_count_in_order() {
+        let reported = Mutex::new(Vec::new());
+        let progress = |collected| {
+            reported.lock().unwrap().push(collected);
+            async { Ok(()) }
+        };
+
+        let messages = collect_with(fake_channel(2000), 1050, Duration::ZERO, progress)
+            .await
+            .unwrap();
+
+        assert_eq!(messages.len(), 1050);
+        assert_eq!(messages[0].id.get(), 2000);
+        assert_eq!(messages[1049].id.get(), 951);
+        assert_eq!(*reported.lock().unwrap(), vec![500, 1000, 1050]);
+    }
+
+    #[tokio::test]
+    async fn test_stops_at_start_of_channel() {
+        let reported = Mutex::new(Vec::new());
+        let progress = |collected| {
+            reported.lock().unwrap().push(collected);
+            async { Ok(()) }
+        };
+
+        let messages = collect_with(fake_channel(230), 1000, Duration::ZERO, progress)
+            .await
+            .unwrap();
+
+        assert_eq!(messages.len(), 230);
+        assert_eq!(messages.last().unwrap().id.get(), 1);
+        // Never reached a multiple of 500 or the requested count
+        assert!(reported.lock().unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_parse_message_ref() {
+        let here = serenity::ChannelId::new(5);
+        assert_eq!(
+            parse_message_ref("https://discord.com/channels/1/2/3", here),
+            Some((serenity::ChannelId::new(2), serenity::MessageId::new(3)))
+        );
+        assert_eq!(
+            parse_message_ref("<https://ptb.discord.com/channels/@me/2/3>", here),
+            Some((serenity::ChannelId::new(2), serenity::MessageId::new(3)))
+        );
+        assert_eq!(
+            parse_message_ref("42", here),
+            Some((here, serenity::MessageId::new(42)))
+        );
+        assert_eq!(
+            parse_message_ref("https://discord.com/channels/1/2", here),
+            None
+        );
+        assert_eq!(parse_message_ref("soon", here), None);
+        assert_eq!(parse_message_ref("0", here), None);
+    }
+}
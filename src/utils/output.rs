@@ -0,0 +1,365 @@
+use crate::guild_config::get_guild_config;
+use crate::utils::permissions::is_missing_permission;
+use crate::{Context, Error};
+use chrono::{DateTime, Duration, Utc};
+use parking_lot::Mutex;
+use poise::serenity_prelude as serenity;
+
+/// Interaction tokens run out 15 minutes after the command was used, and
+/// edits to the reply fail from then on. Progress moves to a message of
+/// the bot's own a little before that.
+const TOKEN_SWITCH_AFTER_MINUTES: i64 = 14;
+
+/// Discord's JSON error code for an expired or unknown interaction token.
+const INVALID_WEBHOOK_TOKEN: isize = 50027;
+
+/// Where a command with long output (progress updates, charts, boards) sends
+/// it. Guilds that turned on `-botreplies threaded` get a thread per
+/// invocation so busy channels only see a one-line pointer.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum OutputTarget {
+    /// Reply normally in the invoking channel.
+    Here,
+    Thread(serenity::ChannelId),
+}
+
+/// A message on an [`OutputTarget`] that can be edited as work progresses.
+pub enum OutputMessage<'a> {
+    Reply(poise::ReplyHandle<'a>),
+    Thread(serenity::ChannelId, serenity::MessageId),
+}
+
+/// Pick the output target for this invocation, creating its thread if the
+/// guild wants threaded replies. The thread starts from the invoking message,
+/// or from the pointer reply for slash commands. Falls back to [`OutputTarget::Here`]
+/// when the thread can't be created, e.g. without Create Public Threads or
+/// when already inside a thread.
+pub async fn output_target(ctx: Context<'_>, title: &str) -> Result<OutputTarget, Error> {
+    let Some(guild_id) = ctx.guild_id() else {
+        return Ok(OutputTarget::Here);
+    };
+    if !get_guild_config(guild_id.get()).is_some_and(|config| config.threaded_replies) {
+        return Ok(OutputTarget::Here);
+    }
+
+    let http = ctx.serenity_context().http.clone();
+    let builder = serenity::CreateThread::new(thread_name(title, &ctx.author().name))
+        .auto_archive_duration(serenity::AutoArchiveDuration::OneDay);
+
+    let thread = match ctx {
+        poise::Context::Prefix(prefix_ctx) => {
+            match ctx
+                .channel_id()
+                .create_thread_from_message(&http, prefix_ctx.msg.id, builder)
+                .await
+            {
+                Ok(thread) => {
+                    ctx.say(format!("🧵 Continued in <#{}>", thread.id)).await?;
+                    thread
+                }
+                Err(e) => return Ok(fall_back(e)),
+            }
+        }
+        poise::Context::Application(_) => {
+            let pointer = ctx.say("🧵 Opening a thread...").await?;
+            let message = pointer.message().await?;
+            match ctx
+                .channel_id()
+                .create_thread_from_message(&http, message.id, builder)
+                .await
+            {
+                Ok(thread) => {
+                    pointer
+                        .edit(
+                            ctx,
+                            poise::CreateReply::default()
+                                .content(format!("🧵 Continued in <#{}>", thread.id)),
+                        )
+                        .await?;
+                    thread
+                }
+                Err(e) => {
+                    // The pointer would only be noise now; later replies
+                    // become follow-ups instead
+                    pointer.delete(ctx).await?;
+                    return Ok(fall_back(e));
+                }
+            }
+        }
+    };
+    Ok(OutputTarget::Thread(thread.id))
+}
+
+fn fall_back(error: serenity::Error) -> OutputTarget {
+    if is_missing_permission(&error) {
+        log::info!("No permission to create an output thread, replying in the channel");
+    } else {
+        log::warn!("Failed to create an output thread: {}", error);
+    }
+    OutputTarget::Here
+}
+
+/// Thread names are capped at 100 characters.
+fn thread_name(title: &str, author: &str) -> String {
+    format!("{} for {}", title, author)
+        .chars()
+        .take(100)
+        .collect()
+}
+
+impl OutputTarget {
+    /// Post a message to the target.
+    pub async fn send<'a>(
+        &self,
+        ctx: Context<'a>,
+        reply: poise::CreateReply,
+    ) -> Result<OutputMessage<'a>, Error> {
+        match self {
+            OutputTarget::Here => Ok(OutputMessage::Reply(ctx.send(reply).await?)),
+            OutputTarget::Thread(thread_id) => {
+                let message = thread_id
+                    .send_message(ctx.http(), thread_message(reply))
+                    .await?;
+                Ok(OutputMessage::Thread(*thread_id, message.id))
+            }
+        }
+    }
+
+    pub async fn say<'a>(
+        &self,
+        ctx: Context<'a>,
+        text: impl Into<String>,
+    ) -> Result<OutputMessage<'a>, Error> {
+        self.send(ctx, poise::CreateReply::default().content(text))
+            .await
+    }
+}
+
+/// A reply as a plain message. poise only converts replies to messages that
+/// answer the invocation, and a thread message can't reference it.
+fn thread_message(reply: poise::CreateReply) -> serenity::CreateMessage {
+    let mut message = serenity::CreateMessage::new()
+        .embeds(reply.embeds)
+        .files(reply.attachments);
+    if let Some(content) = reply.content {
+        message = message.content(content);
+    }
+    if let Some(components) = reply.components {
+        message = message.components(components);
+    }
+    if let Some(allowed_mentions) = reply.allowed_mentions {
+        message = message.allowed_mentions(allowed_mentions);
+    }
+    message
+}
+
+impl OutputMessage<'_> {
+    pub async fn edit(&self, ctx: Context<'_>, reply: poise::CreateReply) -> Result<(), Error> {
+        match self {
+            OutputMessage::Reply(handle) => handle.edit(ctx, reply).await?,
+            OutputMessage::Thread(thread_id, message_id) => {
+                edit_own_message(ctx, *thread_id, *message_id, reply).await?;
+            }
+        }
+        Ok(())
+    }
+}
+
+async fn edit_own_message(
+    ctx: Context<'_>,
+    channel_id: serenity::ChannelId,
+    message_id: serenity::MessageId,
+    reply: poise::CreateReply,
+) -> Result<(), Error> {
+    channel_id
+        .edit_message(
+            ctx.http(),
+            message_id,
+            reply.to_prefix_edit(serenity::EditMessage::new()),
+        )
+        .await?;
+    Ok(())
+}
+
+/// Where the next progress edit goes.
+#[derive(Debug, PartialEq)]
+enum ProgressRoute {
+    /// Edit the message the reporter started with.
+    Original,
+    /// The interaction token is about to run out: post a message of the
+    /// bot's own and keep editing that one.
+    Switch,
+    /// Already switched.
+    Own,
+}
+
+/// `token_issued` is when the interaction behind the original message was
+/// created, or `None` if editing it doesn't need a token.
+fn progress_route(
+    token_issued: Option<DateTime<Utc>>,
+    switched: bool,
+    now: DateTime<Utc>,
+) -> ProgressRoute {
+    if switched {
+        ProgressRoute::Own
+    } else if token_issued
+        .is_some_and(|issued| now - issued >= Duration::minutes(TOKEN_SWITCH_AFTER_MINUTES))
+    {
+        ProgressRoute::Switch
+    } else {
+        ProgressRoute::Original
+    }
+}
+
+/// Whether an edit failed because the interaction token ran out.
+fn is_expired_token(error: &Error) -> bool {
+    matches!(
+        error.downcast_ref::<serenity::Error>(),
+        Some(serenity::Error::Http(serenity::HttpError::UnsuccessfulRequest(response)))
+            if response.error.code == INVALID_WEBHOOK_TOKEN
+                || response.status_code == serenity::StatusCode::UNAUTHORIZED
+    )
+}
+
+/// A status message for work that may take a while, like collecting
+/// thousands of messages or building an update. Prefix commands and thread
+/// output just edit their message. Slash command replies can only be edited
+/// for 15 minutes, so after 14 (or once an edit fails because the token
+/// ran out) the reporter posts a message of its own in the channel and
+/// edits that from then on.
+pub struct ProgressReporter<'a> {
+    ctx: Context<'a>,
+    message: OutputMessage<'a>,
+    token_issued: Option<DateTime<Utc>>,
+    /// The bot's own message, once it switched to one.
+    own: Mutex<Option<(serenity::ChannelId, serenity::MessageId)>>,
+}
+
+impl<'a> ProgressReporter<'a> {
+    pub fn new(ctx: Context<'a>, message: OutputMessage<'a>) -> Self {
+        let token_issued = match (&message, ctx) {
+            (OutputMessage::Reply(_), poise::Context::Application(app_ctx)) => {
+                DateTime::from_timestamp(app_ctx.interaction.id.created_at().unix_timestamp(), 0)
+            }
+            _ => None,
+        };
+        Self {
+            ctx,
+            message,
+            token_issued,
+            own: Mutex::new(None),
+        }
+    }
+
+    /// Send `text` as a reply in the invoking channel and report progress
+    /// by editing it.
+    pub async fn say(ctx: Context<'a>, text: impl Into<String>) -> Result<Self, Error> {
+        let message = OutputTarget::Here.say(ctx, text).await?;
+        Ok(Self::new(ctx, message))
+    }
+
+    /// The ID of the message the reporter started with.
+    pub async fn message_id(&self) -> Result<serenity::MessageId, Error> {
+        match &self.message {
+            OutputMessage::Reply(handle) => Ok(handle.message().await?.id),
+            OutputMessage::Thread(_, message_id) => Ok(*message_id),
+        }
+    }
+
+    pub async fn update(&self, reply: poise::CreateReply) -> Result<(), Error> {
+        let own = *self.own.lock();
+        match progress_route(self.token_issued, own.is_some(), Utc::now()) {
+            ProgressRoute::Own => {
+                let (channel_id, message_id) = own.expect("switched to a message of its own");
+                edit_own_message(self.ctx, channel_id, message_id, reply).await
+            }
+            ProgressRoute::Switch => self.switch(reply, true).await,
+            ProgressRoute::Original => match self.message.edit(self.ctx, reply.clone()).await {
+                Err(e) if is_expired_token(&e) => self.switch(reply, false).await,
+                result => result,
+            },
+        }
+    }
+
+    pub async fn set(&self, text: impl Into<String>) -> Result<(), Error> {
+        self.update(poise::CreateReply::default().content(text))
+            .await
+    }
+
+    /// Continue in a new message of the bot's own. While the token still
+    /// works, the original message points at the new one.
+    async fn switch(&self, reply: poise::CreateReply, token_works: bool) -> Result<(), Error> {
+        let message = self
+            .ctx
+            .channel_id()
+            .send_message(self.ctx.http(), thread_message(reply))
+            .await?;
+        *self.own.lock() = Some((message.channel_id, message.id));
+        log::info!(
+            "Progress for {} moved to message {}",
+            self.ctx.command().qualified_name,
+            message.id
+        );
+
+        if token_works {
+            let pointer = poise::CreateReply::default()
+                .content(format!(
+                    "⏳ This took a while, updates continue in {}",
+                    message.link()
+                ))
+                .components(Vec::new());
+            if let Err(e) = self.message.edit(self.ctx, pointer).await {
+                log::warn!("Failed to point at the new progress message: {}", e);
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_progress_route() {
+        let issued = Utc::now();
+        let after = |minutes| issued + Duration::minutes(minutes);
+
+        assert_eq!(
+            progress_route(Some(issued), false, after(0)),
+            ProgressRoute::Original
+        );
+        assert_eq!(
+            progress_route(Some(issued), false, issued + Duration::seconds(14 * 60 - 1)),
+            ProgressRoute::Original
+        );
+        assert_eq!(
+            progress_route(Some(issued), false, after(14)),
+            ProgressRoute::Switch
+        );
+        assert_eq!(
+            progress_route(Some(issued), false, after(60)),
+            ProgressRoute::Switch
+        );
+        // Once switched it stays on its own message, whatever the clock says
+        assert_eq!(
+            progress_route(Some(issued), true, after(1)),
+            ProgressRoute::Own
+        );
+        assert_eq!(
+            progress_route(Some(issued), true, after(20)),
+            ProgressRoute::Own
+        );
+        // Prefix commands and thread output have no token to run out
+        assert_eq!(
+            progress_route(None, false, after(120)),
+            ProgressRoute::Original
+        );
+    }
+
+    #[test]
+    fn test_thread_name_fits_discord_limit() {
+        assert_eq!(thread_name("Stats", "alice"), "Stats for alice");
+        assert_eq!(thread_name(&"x".repeat(120), "alice").chars().count(), 100);
+    }
+}
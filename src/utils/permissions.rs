@@ -0,0 +1,548 @@
+use crate::Context;
+use poise::serenity_prelude as serenity;
+use serenity::{
+    ChannelId, GuildId, PermissionOverwrite, PermissionOverwriteType, Permissions, RoleId, UserId,
+};
+use std::collections::HashMap;
+use std::fmt;
+
+/// Discord's JSON error codes for "Missing Access" and "Missing Permissions".
+const MISSING_ACCESS: isize = 50001;
+const MISSING_PERMISSIONS: isize = 50013;
+
+/// Whether a serenity error means the bot lacks a permission, either because
+/// Discord refused the request or because serenity's own cache check did.
+pub fn is_missing_permission(error: &serenity::Error) -> bool {
+    match error {
+        serenity::Error::Http(serenity::HttpError::UnsuccessfulRequest(response)) => {
+            refused_for_permissions(response.status_code, response.error.code)
+        }
+        serenity::Error::Model(serenity::ModelError::InvalidPermissions { .. }) => true,
+        _ => false,
+    }
+}
+
+/// Whether Discord says the thing asked about doesn't exist (anymore), e.g. a
+/// deleted message or a member who left.
+pub fn is_not_found(error: &serenity::Error) -> bool {
+    matches!(
+        error,
+        serenity::Error::Http(serenity::HttpError::UnsuccessfulRequest(response))
+            if response.status_code == serenity::StatusCode::NOT_FOUND
+    )
+}
+
+fn refused_for_permissions(status: serenity::StatusCode, code: isize) -> bool {
+    status == serenity::StatusCode::FORBIDDEN
+        || matches!(code, MISSING_ACCESS | MISSING_PERMISSIONS)
+}
+
+/// What to tell the user when reading a channel's history failed. Missing
+/// permissions get an actionable message instead of the raw serenity error.
+pub fn history_error_message(
+    error: &(dyn std::error::Error + 'static),
+    channel_id: serenity::ChannelId,
+) -> String {
+    match error.downcast_ref::<serenity::Error>() {
+        Some(error) if is_missing_permission(error) => format!(
+            "❌ I need the **Read Message History** permission in <#{}> to do that.",
+            channel_id
+        ),
+        _ => format!(
+            "❌ Error fetching messages from <#{}>: {}",
+            channel_id, error
+        ),
+    }
+}
+
+/// What the bot needs in a channel to read its past messages.
+pub const READ_HISTORY: &[Permissions] =
+    &[Permissions::VIEW_CHANNEL, Permissions::READ_MESSAGE_HISTORY];
+
+/// What the bot needs in a voice channel to play audio in it.
+pub const VOICE: &[Permissions] = &[
+    Permissions::VIEW_CHANNEL,
+    Permissions::CONNECT,
+    Permissions::SPEAK,
+];
+
+/// Permissions the bot lacks in a channel, found before starting work that
+/// would fail halfway without them.
+#[derive(Debug, PartialEq)]
+pub struct MissingPerms {
+    pub channel_id: ChannelId,
+    pub missing: Permissions,
+}
+
+impl fmt::Display for MissingPerms {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let names: Vec<String> = self
+            .missing
+            .get_permission_names()
+            .into_iter()
+            .map(|name| format!("**{}**", name))
+            .collect();
+        let (list, noun) = match names.split_last() {
+            Some((last, [])) => (last.clone(), "permission"),
+            Some((last, rest)) => (format!("{} and {}", rest.join(", "), last), "permissions"),
+            None => (String::new(), "permissions"),
+        };
+        write!(
+            f,
+            "❌ I need the {} {} in <#{}> to do that.",
+            list, noun, self.channel_id
+        )
+    }
+}
+
+/// Check that the bot has every one of `required` in `channel_id`, taking
+/// the channel's overwrites into account (a thread's come from its parent).
+/// Outside servers, or when the channel isn't cached, this passes and the
+/// operation reports its own errors.
+pub async fn check_channel_perms(
+    ctx: Context<'_>,
+    channel_id: ChannelId,
+    required: &[Permissions],
+) -> Result<(), MissingPerms> {
+    let Some(guild_id) = ctx.guild_id() else {
+        return Ok(());
+    };
+    let bot_id = ctx.cache().current_user().id;
+    let member = match guild_id.member(ctx, bot_id).await {
+        Ok(member) => member,
+        Err(e) => {
+            log::warn!(
+                "Couldn't look up my own member in guild {}: {}",
+                guild_id,
+                e
+            );
+            return Ok(());
+        }
+    };
+
+    let granted = {
+        let Some(guild) = ctx.guild() else {
+            return Ok(());
+        };
+        let channel = guild.channels.get(&channel_id).or_else(|| {
+            let parent = guild
+                .threads
+                .iter()
+                .find(|t| t.id == channel_id)?
+                .parent_id?;
+            guild.channels.get(&parent)
+        });
+        let Some(channel) = channel else {
+            return Ok(());
+        };
+        let role_permissions: HashMap<RoleId, Permissions> = guild
+            .roles
+            .iter()
+            .map(|(id, role)| (*id, role.permissions))
+            .collect();
+        resolve_channel_permissions(
+            guild_id,
+            bot_id,
+            guild.owner_id == bot_id,
+            &role_permissions,
+            &member.roles,
+            &channel.permission_overwrites,
+        )
+    };
+
+    let required = required
+        .iter()
+        .fold(Permissions::empty(), |all, permission| all | *permission);
+    let missing = required - granted;
+    if missing.is_empty() {
+        Ok(())
+    } else {
+        Err(MissingPerms {
+            channel_id,
+            missing,
+        })
+    }
+}
+
+/// Check that the author may read the messages in `channel_id`, before the
+/// bot fetches one for them from a link: the channel has to be in the server
+/// the command runs in (or be the DM it runs in), and the author needs View
+/// Channel and Read Message History there. Anything that can't be looked up
+/// counts as a no. The error is the message to show.
+pub async fn check_author_can_read(ctx: Context<'_>, channel_id: ChannelId) -> Result<(), String> {
+    if channel_id == ctx.channel_id() {
+        return Ok(());
+    }
+    let not_here = "❌ I can only fetch messages from this server.".to_string();
+    let Some(guild_id) = ctx.guild_id() else {
+        return Err(not_here);
+    };
+    let channel = match channel_id.to_channel(ctx).await {
+        Ok(serenity::Channel::Guild(channel)) if channel.guild_id == guild_id => channel,
+        Ok(_) => return Err(not_here),
+        Err(e) => {
+            log::warn!("Couldn't look up channel {}: {}", channel_id, e);
+            return Err("❌ I can't find that channel.".to_string());
+        }
+    };
+    let no_access = format!(
+        "❌ You need to be able to read the messages in <#{}> for that.",
+        channel_id
+    );
+    let Some(member) = ctx.author_member().await else {
+        return Err(no_access);
+    };
+
+    let granted = {
+        let Some(guild) = ctx.guild() else {
+            return Err(no_access);
+        };
+        // A thread's overwrites are its parent's
+        let overwrites = if channel.thread_metadata.is_some() {
+            match channel
+                .parent_id
+                .and_then(|parent| guild.channels.get(&parent))
+            {
+                Some(parent) => parent.permission_overwrites.clone(),
+                None => return Err(no_access),
+            }
+        } else {
+            channel.permission_overwrites.clone()
+        };
+        let role_permissions: HashMap<RoleId, Permissions> = guild
+            .roles
+            .iter()
+            .map(|(id, role)| (*id, role.permissions))
+            .collect();
+        resolve_channel_permissions(
+            guild_id,
+            member.user.id,
+            guild.owner_id == member.user.id,
+            &role_permissions,
+            &member.roles,
+            &overwrites,
+        )
+    };
+    if granted.contains(Permissions::VIEW_CHANNEL | Permissions::READ_MESSAGE_HISTORY) {
+        Ok(())
+    } else {
+        Err(no_access)
+    }
+}
+
+/// Permissions a role the bot hands out by itself must not carry, so
+/// setting one up can't be used to promote yourself.
+const DANGEROUS_ROLE_PERMISSIONS: Permissions = Permissions::ADMINISTRATOR
+    .union(Permissions::MANAGE_GUILD)
+    .union(Permissions::MANAGE_ROLES)
+    .union(Permissions::MANAGE_CHANNELS)
+    .union(Permissions::MANAGE_WEBHOOKS)
+    .union(Permissions::MANAGE_MESSAGES)
+    .union(Permissions::MANAGE_NICKNAMES)
+    .union(Permissions::MANAGE_THREADS)
+    .union(Permissions::MANAGE_EVENTS)
+    .union(Permissions::MANAGE_GUILD_EXPRESSIONS)
+    .union(Permissions::KICK_MEMBERS)
+    .union(Permissions::BAN_MEMBERS)
+    .union(Permissions::MODERATE_MEMBERS)
+    .union(Permissions::MENTION_EVERYONE)
+    .union(Permissions::VIEW_AUDIT_LOG);
+
+/// What matters about the member setting up a role for the bot to hand out.
+struct RoleSetter {
+    is_owner: bool,
+    permissions: Permissions,
+    /// Position of their highest role.
+    top_position: u16,
+}
+
+/// Why `setter` may not have the bot hand out a role at `position` with
+/// `permissions`, if they may not.
+fn role_refusal(setter: &RoleSetter, position: u16, permissions: Permissions) -> Option<String> {
+    let dangerous = permissions & DANGEROUS_ROLE_PERMISSIONS;
+    if !dangerous.is_empty() {
+        let names = dangerous.get_permission_names().join(", ");
+        return Some(format!(
+            "❌ That role has moderator permissions ({}). Pick one without them.",
+            names
+        ));
+    }
+    if setter.is_owner || setter.permissions.administrator() {
+        return None;
+    }
+    if !setter.permissions.manage_roles() {
+        return Some("❌ You need the **Manage Roles** permission to pick that role.".to_string());
+    }
+    if position >= setter.top_position {
+        return Some("❌ That role isn't below your highest role.".to_string());
+    }
+    None
+}
+
+/// Check that the author may have the bot hand out `role` automatically,
+/// like a birthday or streak role: they need Manage Roles, the role has to
+/// be below their highest role, and it can't carry moderator permissions.
+/// The error is the message to show.
+pub async fn check_assignable_role(ctx: Context<'_>, role: &serenity::Role) -> Result<(), String> {
+    let unknown = "❌ I couldn't check your roles, try again in a bit.".to_string();
+    let (Some(guild_id), Some(member)) = (ctx.guild_id(), ctx.author_member().await) else {
+        return Err(unknown);
+    };
+    let setter = {
+        let Some(guild) = ctx.guild() else {
+            return Err(unknown);
+        };
+        let everyone = RoleId::new(guild_id.get());
+        let roles: Vec<&serenity::Role> = std::iter::once(&everyone)
+            .chain(&member.roles)
+            .filter_map(|id| guild.roles.get(id))
+            .collect();
+        RoleSetter {
+            is_owner: guild.owner_id == member.user.id,
+            permissions: roles
+                .iter()
+                .fold(Permissions::empty(), |all, role| all | role.permissions),
+            top_position: roles.iter().map(|role| role.position).max().unwrap_or(0),
+        }
+    };
+    match role_refusal(&setter, role.position, role.permissions) {
+        Some(refusal) => Err(refusal),
+        None => Ok(()),
+    }
+}
+
+/// A member's permissions in a channel, in Discord's order: their roles
+/// (`@everyone` has the guild's ID), then the channel's `@everyone`
+/// overwrite, their roles' overwrites together, and their own overwrite.
+/// The owner and administrators have everything; without View Channel
+/// nothing else counts.
+fn resolve_channel_permissions(
+    guild_id: GuildId,
+    user_id: UserId,
+    is_owner: bool,
+    role_permissions: &HashMap<RoleId, Permissions>,
+    member_roles: &[RoleId],
+    overwrites: &[PermissionOverwrite],
+) -> Permissions {
+    let everyone = RoleId::new(guild_id.get());
+    let mut permissions = std::iter::once(&everyone)
+        .chain(member_roles)
+        .filter_map(|role| role_permissions.get(role))
+        .fold(Permissions::empty(), |all, role| all | *role);
+    if is_owner || permissions.administrator() {
+        return Permissions::all();
+    }
+
+    let mut apply = |allow: Permissions, deny: Permissions| {
+        permissions = (permissions - deny) | allow;
+    };
+    for overwrite in overwrites {
+        if overwrite.kind == PermissionOverwriteType::Role(everyone) {
+            apply(overwrite.allow, overwrite.deny);
+        }
+    }
+    let (allow, deny) = overwrites
+        .iter()
+        .filter(|overwrite| {
+            matches!(overwrite.kind, PermissionOverwriteType::Role(role)
+                if role != everyone && member_roles.contains(&role))
+        })
+        .fold(
+            (Permissions::empty(), Permissions::empty()),
+            |(allow, deny), overwrite| (allow | overwrite.allow, deny | overwrite.deny),
+        );
+    apply(allow, deny);
+    for overwrite in overwrites {
+        if overwrite.kind == PermissionOverwriteType::Member(user_id) {
+            apply(overwrite.allow, overwrite.deny);
+        }
+    }
+
+    if permissions.view_channel() {
+        permissions
+    } else {
+        Permissions::empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_detects_missing_permissions() {
+        assert!(refused_for_permissions(
+            serenity::StatusCode::FORBIDDEN,
+            MISSING_ACCESS
+        ));
+        assert!(refused_for_permissions(
+            serenity::StatusCode::BAD_REQUEST,
+            MISSING_PERMISSIONS
+        ));
+        assert!(!refused_for_permissions(
+            serenity::StatusCode::NOT_FOUND,
+            10003
+        ));
+
+        let channel = serenity::ChannelId::new(42);
+        let missing: crate::Error = Box::new(serenity::Error::Model(
+            serenity::ModelError::InvalidPermissions {
+                required: serenity::Permissions::READ_MESSAGE_HISTORY,
+                present: serenity::Permissions::VIEW_CHANNEL,
+            },
+        ));
+        assert!(history_error_message(missing.as_ref(), channel).contains("Read Message History"));
+        let other: crate::Error = "connection reset".into();
+        assert_eq!(
+            history_error_message(other.as_ref(), channel),
+            "❌ Error fetching messages from <#42>: connection reset"
+        );
+    }
+
+    #[test]
+    fn test_role_refusal() {
+        let setter = |permissions, top_position| RoleSetter {
+            is_owner: false,
+            permissions,
+            top_position,
+        };
+        let manager = setter(Permissions::MANAGE_ROLES, 5);
+        assert_eq!(role_refusal(&manager, 3, Permissions::SEND_MESSAGES), None);
+        assert!(role_refusal(&manager, 5, Permissions::empty())
+            .unwrap()
+            .contains("below your highest role"));
+        assert!(role_refusal(
+            &setter(Permissions::MANAGE_GUILD, 9),
+            3,
+            Permissions::empty()
+        )
+        .unwrap()
+        .contains("Manage Roles"));
+        let admin_role = role_refusal(&manager, 3, Permissions::ADMINISTRATOR).unwrap();
+        assert!(admin_role.contains("Administrator"), "{admin_role}");
+        assert!(role_refusal(&manager, 3, Permissions::BAN_MEMBERS).is_some());
+
+        // Owners and administrators can pick any safe role
+        let owner = RoleSetter {
+            is_owner: true,
+            permissions: Permissions::empty(),
+            top_position: 0,
+        };
+        assert_eq!(role_refusal(&owner, 8, Permissions::SEND_MESSAGES), None);
+        assert!(role_refusal(&owner, 8, Permissions::MANAGE_ROLES).is_some());
+        assert_eq!(
+            role_refusal(
+                &setter(Permissions::ADMINISTRATOR, 1),
+                8,
+                Permissions::empty()
+            ),
+            None
+        );
+    }
+
+    fn overwrite(
+        kind: PermissionOverwriteType,
+        allow: Permissions,
+        deny: Permissions,
+    ) -> PermissionOverwrite {
+        PermissionOverwrite { allow, deny, kind }
+    }
+
+    #[test]
+    fn test_resolve_channel_permissions() {
+        let guild = GuildId::new(1);
+        let everyone = RoleId::new(1);
+        let moderator = RoleId::new(2);
+        let muted = RoleId::new(3);
+        let bot = UserId::new(10);
+        let roles = HashMap::from([
+            (
+                everyone,
+                Permissions::VIEW_CHANNEL
+                    | Permissions::SEND_MESSAGES
+                    | Permissions::READ_MESSAGE_HISTORY,
+            ),
+            (moderator, Permissions::MANAGE_MESSAGES),
+            (muted, Permissions::empty()),
+        ]);
+        let resolve = |member_roles: &[RoleId], overwrites: &[PermissionOverwrite]| {
+            resolve_channel_permissions(guild, bot, false, &roles, member_roles, overwrites)
+        };
+
+        let plain = resolve(&[moderator], &[]);
+        assert!(plain.manage_messages() && plain.read_message_history());
+
+        // Role overwrites beat @everyone's, and allows beat denies among roles
+        let history_hidden = [
+            overwrite(
+                PermissionOverwriteType::Role(everyone),
+                Permissions::empty(),
+                Permissions::READ_MESSAGE_HISTORY,
+            ),
+            overwrite(
+                PermissionOverwriteType::Role(moderator),
+                Permissions::READ_MESSAGE_HISTORY,
+                Permissions::empty(),
+            ),
+            overwrite(
+                PermissionOverwriteType::Role(muted),
+                Permissions::empty(),
+                Permissions::READ_MESSAGE_HISTORY | Permissions::SEND_MESSAGES,
+            ),
+        ];
+        assert!(!resolve(&[], &history_hidden).read_message_history());
+        let both = resolve(&[moderator, muted], &history_hidden);
+        assert!(both.read_message_history());
+        assert!(!both.send_messages());
+
+        // The member's own overwrite comes last
+        let mut personal = history_hidden.to_vec();
+        personal.push(overwrite(
+            PermissionOverwriteType::Member(bot),
+            Permissions::SEND_MESSAGES,
+            Permissions::MANAGE_MESSAGES,
+        ));
+        let own = resolve(&[moderator, muted], &personal);
+        assert!(own.send_messages() && !own.manage_messages());
+
+        // Without View Channel nothing else counts
+        let hidden = [overwrite(
+            PermissionOverwriteType::Role(everyone),
+            Permissions::empty(),
+            Permissions::VIEW_CHANNEL,
+        )];
+        assert_eq!(resolve(&[moderator], &hidden), Permissions::empty());
+
+        // Administrators and the owner ignore overwrites
+        let mut admin_roles = roles.clone();
+        admin_roles.insert(moderator, Permissions::ADMINISTRATOR);
+        assert_eq!(
+            resolve_channel_permissions(guild, bot, false, &admin_roles, &[moderator], &hidden),
+            Permissions::all()
+        );
+        assert_eq!(
+            resolve_channel_permissions(guild, bot, true, &roles, &[], &hidden),
+            Permissions::all()
+        );
+    }
+
+    #[test]
+    fn test_missing_perms_message() {
+        let missing = |missing| MissingPerms {
+            channel_id: ChannelId::new(42),
+            missing,
+        };
+        assert_eq!(
+            missing(Permissions::MANAGE_MESSAGES).to_string(),
+            "❌ I need the **Manage Messages** permission in <#42> to do that."
+        );
+        let several = missing(
+            Permissions::VIEW_CHANNEL
+                | Permissions::READ_MESSAGE_HISTORY
+                | Permissions::MANAGE_MESSAGES,
+        )
+        .to_string();
+        assert!(several.contains("** and **"), "{several}");
+        assert!(several.contains("permissions in <#42>"), "{several}");
+    }
+}
@@ -0,0 +1,114 @@
+use chrono::NaiveDate;
+use rand::rngs::StdRng;
+use rand::{RngCore, SeedableRng};
+use sha2::{Digest, Sha256};
+
+/// A seed that is the same all day for a given feature (`scope`) and guild,
+/// and changes at the date boundary. Used for "daily" features like a daily
+/// word, so every player in a guild gets the same one.
+///
+/// The seed is derived with SHA-256 rather than std's hasher, whose output
+/// isn't guaranteed to stay the same between Rust releases.
+pub fn daily_seed(scope: &str, date: NaiveDate, guild_id: Option<u64>) -> u64 {
+    let mut hasher = Sha256::new();
+    hasher.update(scope.as_bytes());
+    hasher.update(b"\0");
+    hasher.update(date.format("%Y-%m-%d").to_string().as_bytes());
+    hasher.update(b"\0");
+    if let Some(guild_id) = guild_id {
+        hasher.update(guild_id.to_le_bytes());
+    }
+    let digest = hasher.finalize();
+
+    let mut bytes = [0u8; 8];
+    bytes.copy_from_slice(&digest[..8]);
+    u64::from_le_bytes(bytes)
+}
+
+/// A reproducible RNG. Anything that takes `&mut impl Rng` can be given one
+/// of these instead of `thread_rng()`, e.g. in tests or for daily features.
+pub struct SeededRng(StdRng);
+
+impl SeededRng {
+    pub fn new(seed: u64) -> Self {
+        Self(StdRng::seed_from_u64(seed))
+    }
+
+    /// An RNG seeded with [`daily_seed`].
+    pub fn daily(scope: &str, date: NaiveDate, guild_id: Option<u64>) -> Self {
+        Self::new(daily_seed(scope, date, guild_id))
+    }
+}
+
+impl RngCore for SeededRng {
+    fn next_u32(&mut self) -> u32 {
+        self.0.next_u32()
+    }
+
+    fn next_u64(&mut self) -> u64 {
+        self.0.next_u64()
+    }
+
+    fn fill_bytes(&mut self, dest: &mut [u8]) {
+        self.0.fill_bytes(dest)
+    }
+
+    fn try_fill_bytes(&mut self, dest: &mut [u8]) -> Result<(), rand::Error> {
+        self.0.try_fill_bytes(dest)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::Rng;
+
+    fn date(day: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(2026, 3, day).unwrap()
+    }
+
+    #[test]
+    fn test_daily_seed_is_stable() {
+        assert_eq!(
+            daily_seed("hangman", date(1), Some(42)),
+            daily_seed("hangman", date(1), Some(42))
+        );
+        // Pinned so an accidental change to the derivation is caught
+        assert_eq!(
+            daily_seed("hangman", date(1), Some(42)),
+            4092175267920421440
+        );
+    }
+
+    #[test]
+    fn test_daily_seed_differs_by_guild_and_scope() {
+        let base = daily_seed("hangman", date(1), Some(42));
+        assert_ne!(base, daily_seed("hangman", date(1), Some(43)));
+        assert_ne!(base, daily_seed("hangman", date(1), None));
+        assert_ne!(base, daily_seed("wordle", date(1), Some(42)));
+    }
+
+    #[test]
+    fn test_daily_seed_changes_at_date_boundary() {
+        let last_day_of_month = NaiveDate::from_ymd_opt(2026, 2, 28).unwrap();
+        assert_ne!(
+            daily_seed("hangman", last_day_of_month, Some(42)),
+            daily_seed("hangman", date(1), Some(42))
+        );
+        assert_ne!(
+            daily_seed("hangman", date(1), Some(42)),
+            daily_seed("hangman", date(2), Some(42))
+        );
+    }
+
+    #[test]
+    fn test_seeded_rng_is_reproducible() {
+        let rolls = |mut rng: SeededRng| (0..10).map(|_| rng.gen_range(1..=6)).collect::<Vec<u8>>();
+        assert_eq!(rolls(SeededRng::new(7)), rolls(SeededRng::new(7)));
+        assert_ne!(rolls(SeededRng::new(7)), rolls(SeededRng::new(8)));
+        assert_eq!(
+            rolls(SeededRng::daily("dice", date(1), None)),
+            rolls(SeededRng::daily("dice", date(1), None))
+        );
+    }
+}
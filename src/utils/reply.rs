@@ -0,0 +1,45 @@
+use crate::Error;
+use std::future::Future;
+use std::io::Write;
+
+/// Somewhere a command's text replies go. Commands reply through their
+/// Discord context; the offline REPL (`--repl`) prints to stdout. Logic
+/// written against this runs the same either way.
+pub trait Replier {
+    fn say(&mut self, text: impl Into<String>) -> impl Future<Output = Result<(), Error>> + Send;
+}
+
+// Generic over the error type rather than for `crate::Context` so it also
+// holds inside poise's command macro, which erases the error's lifetime.
+impl<U: Send + Sync + 'static, E> Replier for poise::Context<'_, U, E> {
+    fn say(&mut self, text: impl Into<String>) -> impl Future<Output = Result<(), Error>> + Send {
+        let ctx = *self;
+        let text = text.into();
+        async move {
+            poise::say_reply(ctx, text).await?;
+            Ok(())
+        }
+    }
+}
+
+/// Writes replies out as plain lines, one reply after another.
+pub struct Printer<W> {
+    out: W,
+}
+
+impl<W: Write> Printer<W> {
+    pub fn new(out: W) -> Self {
+        Self { out }
+    }
+
+    pub fn into_inner(self) -> W {
+        self.out
+    }
+}
+
+impl<W: Write + Send> Replier for Printer<W> {
+    fn say(&mut self, text: impl Into<String>) -> impl Future<Output = Result<(), Error>> + Send {
+        let result = writeln!(self.out, "{}", text.into()).and_then(|_| self.out.flush());
+        async move { Ok(result?) }
+    }
+}
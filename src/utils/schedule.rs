@@ -0,0 +1,170 @@
+use crate::utils::confirm::confirm_with_buttons;
+use crate::{Context, Error};
+use chrono::{DateTime, Duration, NaiveDate, TimeZone, Utc};
+use chrono_tz::Tz;
+use std::env;
+
+/// How far ahead anything can be scheduled unless `MAX_SCHEDULE_DAYS` says
+/// otherwise.
+const DEFAULT_MAX_SCHEDULE_DAYS: i64 = 730;
+
+/// Anything due further out than this asks for confirmation first.
+const CONFIRM_AFTER_DAYS: i64 = 90;
+
+/// How long the confirm/cancel buttons stay live.
+const CONFIRM_TIMEOUT_SECS: u64 = 30;
+
+/// The furthest ahead reminders and other scheduled things may be set.
+pub fn max_schedule_horizon() -> Duration {
+    let days = env::var("MAX_SCHEDULE_DAYS")
+        .ok()
+        .and_then(|days| days.trim().parse::<i64>().ok())
+        .filter(|days| *days > 0)
+        .unwrap_or(DEFAULT_MAX_SCHEDULE_DAYS);
+    Duration::days(days)
+}
+
+/// What to do with a requested time that's within the horizon.
+#[derive(Debug, PartialEq)]
+pub enum ScheduleCheck {
+    Fine,
+    /// Looks like a typo; ask before saving.
+    Confirm(String),
+}
+
+/// Check a time something is being scheduled for. `what` names the thing in
+/// the messages, e.g. "reminder". Times past `max_horizon` are an error;
+/// ones that are due right away or more than 90 days out need confirming.
+pub fn check_schedule_time(
+    what: &str,
+    now: DateTime<Utc>,
+    at: DateTime<Utc>,
+    max_horizon: Duration,
+) -> Result<ScheduleCheck, String> {
+    let until = at - now;
+    if until > max_horizon {
+        return Err(format!(
+            "❌ That {} would be due in {}. The limit is {} ahead, did you mistype the time?",
+            what,
+            describe_span(until),
+            describe_span(max_horizon)
+        ));
+    }
+    if at <= now {
+        Ok(ScheduleCheck::Confirm(format!(
+            "This {} would go off right away.",
+            what
+        )))
+    } else if until > Duration::days(CONFIRM_AFTER_DAYS) {
+        Ok(ScheduleCheck::Confirm(format!(
+            "This {} is due in {}, confirm?",
+            what,
+            describe_span(until)
+        )))
+    } else {
+        Ok(ScheduleCheck::Fine)
+    }
+}
+
+/// The local time `hour:minute` on `date` in `tz`, for things that run at
+/// a set time every day. On the spring DST change a time inside the skipped
+/// hour moves to just after it.
+pub fn local_time_on(tz: &Tz, date: NaiveDate, hour: u8, minute: u8) -> Option<DateTime<Tz>> {
+    let local = date.and_hms_opt(hour as u32, minute as u32, 0)?;
+    tz.from_local_datetime(&local).earliest().or_else(|| {
+        tz.from_local_datetime(&(local + Duration::hours(1)))
+            .earliest()
+    })
+}
+
+/// A rough, human-sized length of time like "7 months" or "3 years".
+fn describe_span(span: Duration) -> String {
+    let days = span.num_days();
+    let (amount, unit) = match days {
+        ..=0 => return "less than a day".to_string(),
+        1..=13 => (days, "day"),
+        14..=59 => (days / 7, "week"),
+        60..=729 => (days / 30, "month"),
+        _ => (days / 365, "year"),
+    };
+    if amount == 1 {
+        format!("1 {}", unit)
+    } else {
+        format!("{} {}s", amount, unit)
+    }
+}
+
+/// Ask the author to confirm a schedule time with buttons. Returns whether
+/// they confirmed before the prompt expired.
+pub async fn confirm_schedule_time(
+    ctx: Context<'_>,
+    warning: &str,
+    at: DateTime<Utc>,
+    cancelled: &str,
+) -> Result<bool, Error> {
+    confirm_with_buttons(
+        ctx,
+        format!(
+            "⚠️ {}\n**Due:** <t:{}:F> (<t:{}:R>)",
+            warning,
+            at.timestamp(),
+            at.timestamp()
+        ),
+        "Set it anyway",
+        cancelled,
+        std::time::Duration::from_secs(CONFIRM_TIMEOUT_SECS),
+    )
+    .await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_schedule_time_bounds() {
+        let now = Utc::now();
+        let horizon = Duration::days(DEFAULT_MAX_SCHEDULE_DAYS);
+        let check = |at| check_schedule_time("reminder", now, at, horizon);
+
+        assert_eq!(check(now + Duration::hours(2)), Ok(ScheduleCheck::Fine));
+        assert_eq!(check(now + Duration::days(90)), Ok(ScheduleCheck::Fine));
+        assert_eq!(
+            check(now + Duration::days(210)),
+            Ok(ScheduleCheck::Confirm(
+                "This reminder is due in 7 months, confirm?".to_string()
+            ))
+        );
+        assert!(matches!(check(now), Ok(ScheduleCheck::Confirm(_))));
+        assert!(matches!(
+            check(now + Duration::days(730)),
+            Ok(ScheduleCheck::Confirm(_))
+        ));
+
+        let error = check(now + Duration::weeks(500)).unwrap_err();
+        assert!(error.contains("9 years"), "{error}");
+        assert!(error.contains("2 years"), "{error}");
+    }
+
+    #[test]
+    fn test_local_time_on() {
+        let tz = chrono_tz::Europe::Copenhagen;
+        let date = |d| NaiveDate::from_ymd_opt(2026, 3, d).unwrap();
+        let at = local_time_on(&tz, date(28), 8, 30).unwrap();
+        assert_eq!(at.to_rfc3339(), "2026-03-28T08:30:00+01:00");
+        // 02:30 doesn't exist the night clocks go forward
+        let at = local_time_on(&tz, date(29), 2, 30).unwrap();
+        assert_eq!(at.to_rfc3339(), "2026-03-29T03:30:00+02:00");
+        assert!(local_time_on(&tz, date(28), 24, 0).is_none());
+    }
+
+    #[test]
+    fn test_describe_span() {
+        assert_eq!(describe_span(Duration::hours(5)), "less than a day");
+        assert_eq!(describe_span(Duration::days(1)), "1 day");
+        assert_eq!(describe_span(Duration::days(20)), "2 weeks");
+        assert_eq!(describe_span(Duration::days(45)), "6 weeks");
+        assert_eq!(describe_span(Duration::days(400)), "13 months");
+        assert_eq!(describe_span(Duration::days(365 * 3)), "3 years");
+    }
+}
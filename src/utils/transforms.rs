@@ -0,0 +1,429 @@
+//! Text transforms behind `-uwu`, `-mock`, `-clap` and `-owoify`.
+//!
+//! Every transform only touches plain text: URLs, mentions, custom emoji,
+//! timestamps and code spans are passed through untouched (see [`segments`]),
+//! so links keep working and pings still ping.
+
+use crate::{Context, Error};
+use rand::seq::SliceRandom;
+use rand::Rng;
+
+/// Discord's message length limit.
+pub const MAX_MESSAGE_CHARS: usize = 2000;
+
+const TRUNCATION_NOTICE: &str = "… (cut off at 2000 characters)";
+
+const UWU_FACES: &[&str] = &["uwu", "owo", ">w<", "^w^", "(>ω<)"];
+const OWO_FACES: &[&str] = &[
+    "owo",
+    "uwu",
+    ">w<",
+    "^w^",
+    "(>ω<)",
+    "(・`ω´・)",
+    "(ᵘʷᵘ)",
+    "x3",
+    ":3",
+    "(˘ω˘)",
+];
+
+/// A piece of a message: plain text that may be transformed, or something
+/// that has to stay exactly as written.
+#[derive(Debug, PartialEq)]
+pub enum Segment<'a> {
+    Text(&'a str),
+    Verbatim(&'a str),
+}
+
+/// Split a message into text and the parts transforms must leave alone: URLs,
+/// `<@mentions>`, `<#channels>`, `<@&roles>`, `<:custom:emoji>`, `<t:timestamps>`,
+/// and `inline` or fenced code.
+pub fn segments(text: &str) -> Vec<Segment<'_>> {
+    let mut segments = Vec::new();
+    let mut text_start = 0;
+    let mut i = 0;
+
+    while i < text.len() {
+        let rest = &text[i..];
+        let verbatim_len = if let Some(code) = rest.strip_prefix("```") {
+            code.find("```").map(|end| end + 6)
+        } else if let Some(code) = rest.strip_prefix('`') {
+            code.find('`').map(|end| end + 2)
+        } else if rest.starts_with("https://") || rest.starts_with("http://") {
+            Some(rest.find(char::is_whitespace).unwrap_or(rest.len()))
+        } else if rest.starts_with('<') && is_discord_markup(rest) {
+            rest.find('>').map(|end| end + 1)
+        } else {
+            None
+        };
+
+        match verbatim_len {
+            Some(len) => {
+                if text_start < i {
+                    segments.push(Segment::Text(&text[text_start..i]));
+                }
+                segments.push(Segment::Verbatim(&rest[..len]));
+                i += len;
+                text_start = i;
+            }
+            None => i += rest.chars().next().map_or(1, char::len_utf8),
+        }
+    }
+    if text_start < text.len() {
+        segments.push(Segment::Text(&text[text_start..]));
+    }
+    segments
+}
+
+/// Whether `rest` starts with Discord markup like `<@123>` or `<:name:123>`.
+pub(crate) fn is_discord_markup(rest: &str) -> bool {
+    let Some(end) = rest.find('>') else {
+        return false;
+    };
+    let inner = &rest[1..end];
+    !inner.contains(char::is_whitespace)
+        && ["@", "#", ":", "a:", "t:"]
+            .iter()
+            .any(|start| inner.starts_with(start))
+}
+
+/// Apply `transform` to the text segments of `text`, keeping the rest as is.
+pub fn map_text(text: &str, mut transform: impl FnMut(&str) -> String) -> String {
+    segments(text)
+        .into_iter()
+        .map(|segment| match segment {
+            Segment::Text(text) => transform(text),
+            Segment::Verbatim(verbatim) => verbatim.to_string(),
+        })
+        .collect()
+}
+
+/// Cut `text` down to Discord's limit, saying so if anything was dropped.
+pub fn fit_message(text: &str) -> String {
+    if text.chars().count() <= MAX_MESSAGE_CHARS {
+        return text.to_string();
+    }
+    let keep = MAX_MESSAGE_CHARS - TRUNCATION_NOTICE.chars().count();
+    let mut fitted: String = text.chars().take(keep).collect();
+    fitted.push_str(TRUNCATION_NOTICE);
+    fitted
+}
+
+/// Turn text into uwu speak. `level` 1 is the classic letter swaps plus a
+/// few faces; 2 adds "h-hewwo" stutters (20% of words starting with a
+/// consonant) and more faces; 3 goes full chaos with random capitals and
+/// doubled letters. Levels outside 1-3 are clamped.
+pub fn uwuify(text: &str, level: u8, rng: &mut impl Rng) -> String {
+    let level = level.clamp(1, 3);
+    let mut sentence = 0;
+    let transformed = map_text(text, |text| {
+        let mut text = uwu_substitutions(text);
+        if level >= 2 {
+            let stutter_chance = if level == 3 { 0.4 } else { 0.2 };
+            text = stutter(&text, stutter_chance, rng);
+        }
+        if level == 3 {
+            text = chaos_case(&text, rng);
+        }
+        add_faces(&text, level, &mut sentence, rng)
+    });
+
+    let trimmed = transformed.trim();
+    if trimmed.is_empty() || UWU_FACES.iter().any(|face| trimmed.ends_with(face)) {
+        return trimmed.to_string();
+    }
+    format!("{} uwu", trimmed)
+}
+
+fn uwu_substitutions(text: &str) -> String {
+    text.replace("The", "Teh")
+        .replace("the", "teh")
+        .replace("th", "d")
+        .replace(['r', 'l'], "w")
+        .replace("na", "nya")
+        .replace("ne", "nye")
+        .replace("ni", "nyi")
+        .replace("no", "nyo")
+        .replace("nu", "nyu")
+        .replace("ove", "uv")
+}
+
+/// Repeat the first letter of words starting with a consonant, "h-hewwo".
+fn stutter(text: &str, chance: f64, rng: &mut impl Rng) -> String {
+    let mut result = String::with_capacity(text.len());
+    let mut at_word_start = true;
+    for ch in text.chars() {
+        if at_word_start && is_consonant(ch) && rng.gen_bool(chance) {
+            result.push(ch);
+            result.push('-');
+        }
+        result.push(ch);
+        at_word_start = ch.is_whitespace();
+    }
+    result
+}
+
+fn is_consonant(ch: char) -> bool {
+    ch.is_ascii_alphabetic() && !"aeiouAEIOU".contains(ch)
+}
+
+/// Random capitals and the odd doubled letter.
+fn chaos_case(text: &str, rng: &mut impl Rng) -> String {
+    let mut result = String::with_capacity(text.len() * 2);
+    for ch in text.chars() {
+        let ch = if ch.is_alphabetic() && rng.gen_bool(0.3) {
+            ch.to_uppercase().next().unwrap_or(ch)
+        } else {
+            ch
+        };
+        result.push(ch);
+        if ch.is_alphabetic() && rng.gen_bool(0.1) {
+            result.push(ch);
+        }
+    }
+    result
+}
+
+/// Put faces after sentence ends. Level 1 adds one after every other
+/// sentence longer than 20 characters; higher levels add one after half of
+/// all sentences. `sentence` counts across segments.
+fn add_faces(text: &str, level: u8, sentence: &mut usize, rng: &mut impl Rng) -> String {
+    let mut result = String::with_capacity(text.len());
+    let mut sentence_len = 0;
+    let mut chars = text.chars().peekable();
+    while let Some(ch) = chars.next() {
+        result.push(ch);
+        sentence_len += 1;
+        let sentence_end =
+            matches!(ch, '.' | '!' | '?') && chars.peek().is_none_or(|next| next.is_whitespace());
+        if !sentence_end {
+            continue;
+        }
+        let face = if level == 1 {
+            (sentence_len > 20 && sentence.is_multiple_of(2))
+                .then(|| UWU_FACES[*sentence % UWU_FACES.len()])
+        } else {
+            rng.gen_bool(0.5)
+                .then(|| *UWU_FACES.choose(rng).unwrap_or(&"uwu"))
+        };
+        if let Some(face) = face {
+            result.push(' ');
+            result.push_str(face);
+        }
+        *sentence += 1;
+        sentence_len = 0;
+    }
+    result
+}
+
+/// SpOnGeBoB mocking case: letters alternate, starting lowercase.
+pub fn mockify(text: &str) -> String {
+    let mut is_uppercase = false;
+    map_text(text, |text| {
+        text.chars()
+            .map(|ch| {
+                if !ch.is_alphabetic() {
+                    return ch;
+                }
+                let ch = if is_uppercase {
+                    ch.to_uppercase().next().unwrap_or(ch)
+                } else {
+                    ch.to_lowercase().next().unwrap_or(ch)
+                };
+                is_uppercase = !is_uppercase;
+                ch
+            })
+            .collect()
+    })
+}
+
+/// Put 👏 between 👏 every 👏 word.
+pub fn clapify(text: &str) -> String {
+    segments(text)
+        .iter()
+        .flat_map(|segment| match segment {
+            Segment::Text(text) => text.split_whitespace().collect::<Vec<_>>(),
+            Segment::Verbatim(verbatim) => vec![*verbatim],
+        })
+        .collect::<Vec<_>>()
+        .join(" 👏 ")
+}
+
+/// Leave the words alone and just sprinkle emoticons: one after every
+/// sentence and, at random, after about a fifth of the other words.
+pub fn owoify(text: &str, rng: &mut impl Rng) -> String {
+    let with_faces = map_text(text, |text| {
+        let mut result = String::with_capacity(text.len());
+        for (i, word) in text.split_inclusive(char::is_whitespace).enumerate() {
+            result.push_str(word);
+            let bare = word.trim_end();
+            if bare.is_empty() || word.len() == bare.len() {
+                continue; // The segment's last word, which may run into the next one
+            }
+            let ends_sentence = bare.ends_with(['.', '!', '?']);
+            if ends_sentence || (i > 0 && rng.gen_bool(0.2)) {
+                result.push_str(OWO_FACES.choose(rng).unwrap_or(&"owo"));
+                result.push(' ');
+            }
+        }
+        result
+    });
+    format!(
+        "{} {}",
+        with_faces.trim_end(),
+        OWO_FACES.choose(rng).unwrap_or(&"owo")
+    )
+}
+
+/// Shared body of the text transform commands: use `text`, or the message
+/// being replied to (whose invocation is then deleted), transform it and
+/// post the result, cut to fit a message. `verb` names the transform in
+/// errors, e.g. "uwuify".
+pub async fn run_transform(
+    ctx: Context<'_>,
+    text: Option<String>,
+    verb: &str,
+    transform: impl FnOnce(&str) -> String,
+) -> Result<(), Error> {
+    let (text, quoted_author) = match text {
+        Some(text) => (text, None),
+        None => match ctx {
+            poise::Context::Prefix(prefix_ctx) => {
+                let Some(replied_message) = prefix_ctx.msg.referenced_message.as_ref() else {
+                    ctx.say(format!(
+                        "❌ Please provide text to {verb} or reply to a message with this command!"
+                    ))
+                    .await?;
+                    return Ok(());
+                };
+                // Delete the invoker's message when replying to another message
+                if let Err(e) = prefix_ctx.msg.delete(&ctx.http()).await {
+                    log::warn!("Failed to delete invoker's message: {}", e);
+                }
+                let author = (replied_message.author.id != ctx.author().id)
+                    .then(|| replied_message.author.name.clone());
+                (replied_message.content.clone(), author)
+            }
+            _ => {
+                ctx.say(format!(
+                    "❌ Please provide text to {verb}! (Message replies only work with prefix commands)"
+                ))
+                .await?;
+                return Ok(());
+            }
+        },
+    };
+
+    if text.trim().is_empty() {
+        ctx.say(format!("❌ Cannot {verb} empty text!")).await?;
+        return Ok(());
+    }
+
+    let transformed = transform(&text);
+    let response = match quoted_author {
+        Some(author) => format!("*{} says:*\n{}", author, transformed),
+        None => transformed,
+    };
+
+    if let Err(e) = ctx.say(fit_message(&response)).await {
+        ctx.say(format!("❌ {e}")).await?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::random::SeededRng;
+
+    #[test]
+    fn test_segments_skip_links_mentions_and_code() {
+        let text = "look <@123> at https://x.com/a.b?c=1 and `run this` or <:pog:456>!";
+        assert_eq!(
+            segments(text),
+            vec![
+                Segment::Text("look "),
+                Segment::Verbatim("<@123>"),
+                Segment::Text(" at "),
+                Segment::Verbatim("https://x.com/a.b?c=1"),
+                Segment::Text(" and "),
+                Segment::Verbatim("`run this`"),
+                Segment::Text(" or "),
+                Segment::Verbatim("<:pog:456>"),
+                Segment::Text("!"),
+            ]
+        );
+        // Unclosed code and plain angle brackets are just text
+        assert_eq!(segments("a < b `c"), vec![Segment::Text("a < b `c")]);
+        assert_eq!(
+            segments("```\nlet x = 1;\n``` done"),
+            vec![
+                Segment::Verbatim("```\nlet x = 1;\n```"),
+                Segment::Text(" done")
+            ]
+        );
+    }
+
+    #[test]
+    fn test_uwuify_level_one_is_deterministic() {
+        let mut rng = SeededRng::new(1);
+        assert_eq!(
+            uwuify("The cat loves running", 1, &mut rng),
+            "Teh cat wuvs wunnying uwu"
+        );
+        assert_eq!(
+            uwuify("hello <@42> https://example.com/really", 1, &mut rng),
+            "hewwo <@42> https://example.com/really uwu"
+        );
+        assert_eq!(uwuify("", 1, &mut rng), "");
+    }
+
+    #[test]
+    fn test_uwuify_higher_levels_with_seed() {
+        let text = "hello there my friend. this is great! really now.";
+        let run = |level, seed| uwuify(text, level, &mut SeededRng::new(seed));
+
+        assert_eq!(run(2, 7), run(2, 7));
+        assert_eq!(run(3, 7), run(3, 7));
+        // Over a few seeds, level 2 stutters and level 3 shouts somewhere
+        assert!((0..10).any(|seed| run(2, seed).contains("-")));
+        assert!((0..10).any(|seed| run(3, seed).chars().any(char::is_uppercase)));
+        // Level 2 never changes case
+        assert!((0..10).all(|seed| !run(2, seed).chars().any(char::is_uppercase)));
+
+        let linked = uwuify("check `rust code` please", 3, &mut SeededRng::new(3));
+        assert!(linked.contains("`rust code`"), "{linked}");
+    }
+
+    #[test]
+    fn test_mock_and_clap() {
+        assert_eq!(mockify("hello world"), "hElLo WoRlD");
+        assert_eq!(mockify("hi <@1> there"), "hI <@1> tHeRe");
+        assert_eq!(clapify("this is   fine"), "this 👏 is 👏 fine");
+        assert_eq!(
+            clapify("see https://a.b/c now"),
+            "see 👏 https://a.b/c 👏 now"
+        );
+    }
+
+    #[test]
+    fn test_owoify_only_adds_faces() {
+        let text = "I like this. Look at https://x.y/z.";
+        let result = owoify(text, &mut SeededRng::new(5));
+        assert_eq!(result, owoify(text, &mut SeededRng::new(5)));
+        assert!(result.contains("https://x.y/z."), "{result}");
+        let words: Vec<&str> = result
+            .split_whitespace()
+            .filter(|word| !OWO_FACES.contains(word))
+            .collect();
+        assert_eq!(words.join(" "), text);
+    }
+
+    #[test]
+    fn test_fit_message() {
+        assert_eq!(fit_message("short"), "short");
+        let fitted = fit_message(&"a".repeat(3000));
+        assert_eq!(fitted.chars().count(), MAX_MESSAGE_CHARS);
+        assert!(fitted.ends_with(TRUNCATION_NOTICE));
+    }
+}
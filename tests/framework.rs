@@ -0,0 +1,101 @@
+use std::collections::HashSet;
+
+use image::{DynamicImage, Rgba, RgbaImage};
+use rustbot::commands::general::pixelate;
+use rustbot::commands::remind::{format_duration, parse_time_duration};
+use rustbot::{build_framework_options, Data, Error, DEFAULT_PREFIX};
+
+type Command = poise::Command<Data, Error>;
+
+/// Every command with its subcommands, as (qualified name, command).
+fn all_commands(commands: &[Command]) -> Vec<(String, &Command)> {
+    let mut all = Vec::new();
+    for command in commands {
+        all.push((command.qualified_name.clone(), command));
+        all.extend(all_commands(&command.subcommands));
+    }
+    all
+}
+
+#[test]
+fn test_every_command_is_described_and_invocable() {
+    let options = build_framework_options();
+    assert!(!options.commands.is_empty());
+
+    for (name, command) in all_commands(&options.commands) {
+        assert!(
+            command
+                .description
+                .as_deref()
+                .is_some_and(|d| !d.trim().is_empty()),
+            "`{name}` has no description"
+        );
+        assert!(
+            command.prefix_action.is_some() || command.slash_action.is_some(),
+            "`{name}` is neither a prefix nor a slash command"
+        );
+    }
+}
+
+#[test]
+fn test_no_duplicate_names_or_aliases() {
+    fn check_level(commands: &[Command], parent: &str) {
+        let mut seen = HashSet::new();
+        for command in commands {
+            for name in std::iter::once(&command.name).chain(&command.aliases) {
+                assert!(
+                    seen.insert(name.to_lowercase()),
+                    "`{parent}{name}` is registered twice"
+                );
+            }
+            check_level(
+                &command.subcommands,
+                &format!("{}{} ", parent, command.name),
+            );
+        }
+    }
+    check_level(&build_framework_options().commands, "");
+}
+
+#[test]
+fn test_prefix_is_dash() {
+    let options = build_framework_options();
+    assert_eq!(DEFAULT_PREFIX, "-");
+    // Servers can pick their own, so there's no fixed prefix
+    assert!(options.prefix_options.prefix.is_none());
+    assert!(options.prefix_options.dynamic_prefix.is_some());
+}
+
+#[test]
+fn test_reminder_durations_round_trip() {
+    for (input, shown) in [
+        ("45s", "45s"),
+        ("5m", "5m"),
+        ("2 hours", "2h"),
+        ("3d", "3d"),
+    ] {
+        let duration = parse_time_duration(input).unwrap();
+        assert_eq!(format_duration(duration), shown, "for {input}");
+    }
+    assert!(parse_time_duration("soon").is_none());
+    assert!(parse_time_duration("99999999999w").is_none());
+}
+
+#[test]
+fn test_pixelate_makes_uniform_blocks() {
+    // Left half black, right half white
+    let source = RgbaImage::from_fn(64, 64, |x, _| {
+        if x < 32 {
+            Rgba([0, 0, 0, 255])
+        } else {
+            Rgba([255, 255, 255, 255])
+        }
+    });
+    let pixelated = pixelate(&DynamicImage::ImageRgba8(source), 2, 128);
+
+    assert_eq!(pixelated.dimensions(), (128, 128));
+    // Each of the 2x2 blocks is a single 64px colour
+    assert_eq!(pixelated.get_pixel(0, 0), pixelated.get_pixel(63, 127));
+    assert_eq!(pixelated.get_pixel(64, 0), pixelated.get_pixel(127, 127));
+    assert_ne!(pixelated.get_pixel(0, 0), pixelated.get_pixel(127, 0));
+}
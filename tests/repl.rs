@@ -0,0 +1,76 @@
+//! Drives the offline REPL the way a developer would, so the logic shared
+//! between the commands and `--repl` is exercised end to end.
+
+async fn run(input: &str) -> String {
+    let output = rustbot::repl::run(input.as_bytes(), Vec::new())
+        .await
+        .unwrap();
+    String::from_utf8(output).unwrap()
+}
+
+#[tokio::test]
+async fn test_offline_commands() {
+    let output = run("-dice 1\n\
+         -dice 5000\n\
+         -uwu hello there\n\
+         -mock hello <@42>\n\
+         -clap so much wow\n\
+         -poll Lunch? pizza tacos\n\
+         -poll no question\n\
+         -remind set 2h stretch\n\
+         -remind set 500w oops\n\
+         -remind set 200d later\n\
+         -r 10m tea\n\
+         -remind set 1h !high call mom\n")
+    .await;
+
+    // A one-sided die gets one of Marvin's sighs instead of a roll
+    let marvin = output.lines().nth(1).unwrap();
+    assert!(marvin.contains("one") || marvin.contains("One"), "{marvin}");
+    assert!(!marvin.contains("rolled"));
+    assert!(output.contains("way too many sides"));
+    assert!(output.contains("hewwo"));
+    assert!(output.contains("hElLo <@42>"));
+    assert!(output.contains("so 👏 much 👏 wow"));
+    assert!(output.contains("**Lunch**\n\n1️⃣ pizza\n2️⃣ tacos"));
+    assert!(output.contains("❌ Invalid format!"));
+    assert!(output.contains("(2h from now): stretch"));
+    assert!(output.contains("❌ That reminder would be due in 9 years"));
+    assert!(output.contains("⚠️ This reminder is due in 6 months, confirm?"));
+    assert!(output.contains("(200d from now): later"));
+    // The `-r` alias forwards to `remind set`
+    assert!(output.contains("(10m from now): tea"));
+    // Same priority markers as the command
+    assert!(output.contains("(1h from now) (🚨 high priority): call mom"));
+}
+
+#[tokio::test]
+async fn test_hangman_session() {
+    let output = run("-hangman go\n\
+         -letter x\n\
+         -hangmanstatus\n\
+         -letter g\n\
+         -letter o\n\
+         -hangmanstatus\n")
+    .await;
+
+    assert!(output.contains("Hangman Game Started!"));
+    assert!(output.contains("The letter 'X' is not in the word"));
+    assert!(output.contains("You guessed the word: **GO**"));
+    // The game is over, so there's nothing left to show
+    assert!(
+        output.ends_with("❌ You don't have an active Hangman game! Start one with `/hangman`\n")
+    );
+}
+
+#[tokio::test]
+async fn test_unavailable_and_unknown_commands() {
+    let output = run("-play some song\n-remind list\n-nope\nhello\nquit\n-dice 6\n").await;
+
+    assert!(output.contains("`-play` needs Discord"));
+    assert!(output.contains("`-remind list` needs Discord"));
+    assert!(output.contains("❓ Unknown command `-nope`"));
+    assert!(output.contains("❓ Commands start with `-`"));
+    // Nothing after `quit` runs
+    assert!(!output.contains("rolled"));
+}